@@ -0,0 +1,32 @@
+//! Benchmarks the move generation by running a perft on a few well known positions.
+//!
+//! Run with `cargo bench --bench movegen`.
+
+use std::time::Instant;
+use ladybug::board::Board;
+use ladybug::move_gen::perft::perft_driver;
+
+/// The positions to benchmark, together with the perft depth.
+/// Taken from https://www.chessprogramming.org/Perft_Results.
+const POSITIONS: [(&str, &str, u64); 3] = [
+    ("startpos", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4),
+    ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3),
+    ("position 3", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5),
+];
+
+fn main() {
+    let mut total_nodes = 0;
+    let start = Instant::now();
+
+    for (name, fen, depth) in POSITIONS {
+        let position = Board::from_fen(fen).unwrap().position;
+        let time = Instant::now();
+        let nodes = perft_driver(position, depth);
+        let elapsed = time.elapsed();
+        total_nodes += nodes;
+        println!("{name:<12} depth {depth}: {nodes:>9} nodes in {elapsed:>10.2?} ({:.0} nodes/s)", nodes as f64 / elapsed.as_secs_f64());
+    }
+
+    let elapsed = start.elapsed();
+    println!("total:               {total_nodes:>9} nodes in {elapsed:>10.2?} ({:.0} nodes/s)", total_nodes as f64 / elapsed.as_secs_f64());
+}
@@ -0,0 +1,263 @@
+//! The analysis module implements Ladybug's interactive analysis mode.
+//!
+//! In contrast to UCI, the analysis mode is meant to be used by humans on the console:
+//! moves are entered in SAN or coordinate notation, the board is shown after each move,
+//! and moves can be taken back and replayed.
+
+use crate::board::color::Color;
+use crate::board::position::MAX_GAME_PHASE;
+use crate::evaluation;
+use crate::evaluation::kpk;
+use crate::evaluation::scaling;
+use crate::evaluation::scaling::SCALE_NORMAL;
+use crate::game::Game;
+use crate::move_gen::ply::{Ply, PlyParseError};
+
+/// The depth to which the position is searched when the user asks for a hint.
+pub const HINT_DEPTH: u64 = 6;
+
+/// Represents a command of the analysis mode.
+#[derive(PartialEq, Debug)]
+pub enum AnalysisCommand {
+    Move(String),
+    Undo,
+    Redo,
+    Eval,
+    Hint,
+    Board,
+    Fen,
+    Help,
+    Exit,
+    Quit,
+}
+
+/// Tries to parse a string as a command of the analysis mode.
+/// Any single word that is not a command is treated as a move.
+pub fn parse_analysis_command(input: &str) -> Result<AnalysisCommand, String> {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+    if parts.len() != 1 {
+        return Err(String::from("Unknown command, type \"help\" to see a list of all commands"));
+    }
+
+    match parts[0] {
+        "undo" => Ok(AnalysisCommand::Undo),
+        "redo" => Ok(AnalysisCommand::Redo),
+        "eval" => Ok(AnalysisCommand::Eval),
+        "hint" => Ok(AnalysisCommand::Hint),
+        "board" | "d" => Ok(AnalysisCommand::Board),
+        "fen" => Ok(AnalysisCommand::Fen),
+        "help" => Ok(AnalysisCommand::Help),
+        "exit" => Ok(AnalysisCommand::Exit),
+        "quit" => Ok(AnalysisCommand::Quit),
+        other => Ok(AnalysisCommand::Move(other.to_string())),
+    }
+}
+
+/// The state of the analysis mode: the game that is analyzed, and the moves that can be replayed.
+pub struct Analysis {
+    /// The game that is analyzed.
+    game: Game,
+    /// The moves that have been taken back, with the most recently taken back move last.
+    redo_moves: Vec<Ply>,
+}
+
+impl Analysis {
+    /// Starts analyzing the given game.
+    pub fn new(game: Game) -> Analysis {
+        Analysis {
+            game,
+            redo_moves: Vec::new(),
+        }
+    }
+
+    /// Returns the game that is analyzed.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Ends the analysis and returns the analyzed game.
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    /// Plays the given move, which is either given in coordinate notation or in SAN, and returns it in SAN.
+    /// Playing a new move discards the moves that could be replayed.
+    pub fn make_move(&mut self, move_str: &str) -> Result<String, String> {
+        let ply = match self.game.parse_move(move_str) {
+            Ok(ply) => ply,
+            Err(error @ PlyParseError::MissingPromotionPiece(_)) => return Err(error.to_string()),
+            Err(_error) => self.game.parse_san(move_str)?,
+        };
+        let san = ply.to_san(self.game.board().position);
+        self.game.make_move(ply)?;
+        self.redo_moves.clear();
+        Ok(san)
+    }
+
+    /// Takes back the last move and returns it in SAN, or returns None if no move has been played yet.
+    pub fn undo(&mut self) -> Option<String> {
+        let ply = self.game.undo()?;
+        self.redo_moves.push(ply);
+        Some(ply.to_san(self.game.board().position))
+    }
+
+    /// Replays the last move that was taken back and returns it in SAN, or returns None if there is no such move.
+    pub fn redo(&mut self) -> Option<String> {
+        let ply = self.redo_moves.pop()?;
+        let san = ply.to_san(self.game.board().position);
+        // the move was legal when it was taken back, so it is still legal now
+        self.game.make_move(ply).ok()?;
+        Some(san)
+    }
+
+    /// Returns the lines that show the board, together with the outcome of the game if it is over.
+    pub fn render(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.game.board().position.to_string().lines().map(|line| line.to_string()).collect();
+        lines.push(format!("Fen: {}", self.game.board().to_fen()));
+        if let Some(outcome) = self.game.outcome() {
+            lines.push(format!("Game over: {:?} ({})", outcome, outcome.result().to_pgn()));
+        }
+        lines
+    }
+
+    /// Returns the lines that show the terms of the static evaluation for both colors.
+    /// All values are given from White's point of view.
+    pub fn eval_breakdown(&self) -> Vec<String> {
+        let position = self.game.board().position;
+        let white = evaluation::evaluation_terms(position, Color::White);
+        let black = evaluation::evaluation_terms(position, Color::Black);
+        let total = white.total() - black.total();
+        let mut lines = vec![
+            format!("{:<16}{:>8}{:>8}{:>8}", "Term", "White", "Black", "Total"),
+            format!("{:<16}{:>8}{:>8}{:>8}", "Material", white.material, black.material, white.material - black.material),
+            format!("{:<16}{:>8}{:>8}{:>8}", "Piece-square", white.pst, black.pst, white.pst - black.pst),
+            format!("{:<16}{:>8}{:>8}{:>8}", "Passed pawns", white.passed_pawns, black.passed_pawns, white.passed_pawns - black.passed_pawns),
+            format!("{:<16}{:>8}{:>8}{:>8}", "Coordination", white.coordination, black.coordination, white.coordination - black.coordination),
+            format!("{:<16}{:>8}{:>8}{:>8}", "Bishops", white.bishops, black.bishops, white.bishops - black.bishops),
+            format!("{:<16}{:>8}{:>8}{:>8}", "Threats", white.threats, black.threats, white.threats - black.threats),
+            format!("{:<16}{:>8}{:>8}{:>8}", "Tempo", white.tempo, black.tempo, white.tempo - black.tempo),
+            format!("{:<16}{:>8}{:>8}{:>8}", "Evaluation", white.total(), black.total(), total),
+            format!("{:<16}{:>16}", "Game phase", format!("{}/{MAX_GAME_PHASE}", position.game_phase())),
+        ];
+
+        // drawish endgames are scaled towards a draw, which is only shown if it applies
+        let strong_color = match total >= 0 {
+            true => Color::White,
+            false => Color::Black,
+        };
+        let scale_factor = scaling::scale_factor(position, strong_color);
+        if scale_factor != SCALE_NORMAL {
+            lines.push(format!("{:<16}{:>16}{:>8}", "Endgame scale", format!("{scale_factor}/{SCALE_NORMAL}"), total * scale_factor / SCALE_NORMAL));
+        }
+
+        // a king and pawn against a king is evaluated with the bitbase instead
+        if let Some((_strong_color, is_win)) = kpk::probe(position) {
+            let score = match position.color_to_move {
+                Color::White => evaluation::evaluate(position),
+                Color::Black => -evaluation::evaluate(position),
+            };
+            let result = match is_win {
+                true => "win",
+                false => "draw",
+            };
+            lines.push(format!("{:<16}{:>16}{:>8}", "KPK bitbase", result, score));
+        }
+        lines
+    }
+
+    /// Returns the lines that list all commands of the analysis mode.
+    pub fn help() -> Vec<String> {
+        vec![
+            String::from("<move>          : Play a move in SAN (Nf3) or coordinate notation (g1f3)"),
+            String::from("undo            : Take back the last move"),
+            String::from("redo            : Replay the last move that was taken back"),
+            String::from("eval            : Show the terms of the evaluation and the game phase"),
+            String::from("hint            : Search for the best move"),
+            String::from("board           : Show the board"),
+            String::from("fen             : Show the FEN of the position"),
+            String::from("help            : Show this list of commands"),
+            String::from("exit            : Leave the analysis mode"),
+            String::from("quit            : Quit Ladybug"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::{parse_analysis_command, Analysis, AnalysisCommand};
+    use crate::board::Board;
+    use crate::game::Game;
+
+    #[test]
+    fn test_parse_analysis_command() {
+        assert_eq!(Ok(AnalysisCommand::Undo), parse_analysis_command("undo\n"));
+        assert_eq!(Ok(AnalysisCommand::Redo), parse_analysis_command("  redo "));
+        assert_eq!(Ok(AnalysisCommand::Eval), parse_analysis_command("eval"));
+        assert_eq!(Ok(AnalysisCommand::Hint), parse_analysis_command("hint"));
+        assert_eq!(Ok(AnalysisCommand::Board), parse_analysis_command("board"));
+        assert_eq!(Ok(AnalysisCommand::Fen), parse_analysis_command("fen"));
+        assert_eq!(Ok(AnalysisCommand::Help), parse_analysis_command("help"));
+        assert_eq!(Ok(AnalysisCommand::Exit), parse_analysis_command("exit"));
+        assert_eq!(Ok(AnalysisCommand::Quit), parse_analysis_command("quit"));
+        assert_eq!(Ok(AnalysisCommand::Move(String::from("Nf3"))), parse_analysis_command("Nf3"));
+        assert!(parse_analysis_command("").is_err());
+        assert!(parse_analysis_command("play Nf3").is_err());
+    }
+
+    #[test]
+    fn test_analysis_moves() {
+        let mut analysis = Analysis::new(Game::new());
+        assert_eq!(Ok(String::from("e4")), analysis.make_move("e2e4"));
+        assert_eq!(Ok(String::from("e5")), analysis.make_move("e5"));
+        assert_eq!(Ok(String::from("Nf3")), analysis.make_move("Nf3"));
+        assert!(analysis.make_move("Nf3").is_err());
+        assert_eq!(3, analysis.game().moves().len());
+
+        // undo and redo in both directions
+        assert_eq!(Some(String::from("Nf3")), analysis.undo());
+        assert_eq!(Some(String::from("e5")), analysis.undo());
+        assert_eq!(Some(String::from("e5")), analysis.redo());
+        assert_eq!(Some(String::from("Nf3")), analysis.redo());
+        assert_eq!(None, analysis.redo());
+
+        // a new move discards the moves that could be replayed
+        assert_eq!(Some(String::from("Nf3")), analysis.undo());
+        assert_eq!(Ok(String::from("Nc3")), analysis.make_move("b1c3"));
+        assert_eq!(None, analysis.redo());
+
+        assert!(analysis.undo().is_some());
+        assert!(analysis.undo().is_some());
+        assert!(analysis.undo().is_some());
+        assert_eq!(None, analysis.undo());
+        assert_eq!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", analysis.game().board().to_fen());
+    }
+
+    #[test]
+    fn test_analysis_output() {
+        let mut analysis = Analysis::new(Game::new());
+        let board = analysis.render();
+        assert_eq!("8  r  n  b  q  k  b  n  r  ", board[0]);
+        assert_eq!("Fen: rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", board[board.len() - 1]);
+
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            analysis.make_move(san).unwrap();
+        }
+        assert_eq!("Game over: Checkmate(Black) (0-1)", analysis.render().last().unwrap());
+
+        let eval = analysis.eval_breakdown();
+        assert_eq!(10, eval.len());
+        assert_eq!("Material           24050   24050       0", eval[1]);
+        assert_eq!("Game phase                 24/24", eval[9]);
+
+        // a rook against a bishop is drawish
+        let eval = Analysis::new(Game::from_board(Board::from_fen("8/5k2/4b3/8/8/8/2K5/3R4 w - - 0 1").unwrap())).eval_breakdown();
+        assert_eq!(11, eval.len());
+        assert_eq!("Game phase                  3/24", eval[9]);
+        assert!(eval[10].starts_with("Endgame scale               8/64"));
+
+        // a king and pawn against a king is looked up in the bitbase
+        let eval = Analysis::new(Game::from_board(Board::from_fen("8/3k4/8/3K4/3P4/8/8/8 w - - 0 1").unwrap())).eval_breakdown();
+        assert_eq!(11, eval.len());
+        assert_eq!("KPK bitbase                 draw       0", eval[10]);
+    }
+}
@@ -0,0 +1,134 @@
+//! The async_io module is a tokio-based front end for the input and output of Ladybug, which is built with the "async" feature:
+//!
+//! ```text
+//! cargo run --release --features async
+//! ```
+//!
+//! The Ladybug and search threads stay the same, but the input lines are read and the output is written by tasks
+//! of a tokio runtime instead of the blocking input and output threads. `read_input` and `write_output` work with
+//! any asynchronous reader and writer, so that async hosts, e.g. web services or bots, can connect Ladybug to their own streams.
+
+use std::io;
+use std::sync::mpsc::{Receiver, SyncSender};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use crate::ladybug;
+use crate::ladybug::{Message, OUTPUT_CHANNEL_CAPACITY};
+
+/// Reads the input lines from the given reader, e.g. tokio's Stdin, and sends them to Ladybug.
+///
+/// The lines are cleaned up with `ladybug::clean_input_line`, just like by the blocking `ladybug::read_input`.
+/// If the input is closed or can't be read anymore, Ladybug is told to quit.
+pub async fn read_input(mut reader: impl AsyncBufRead + Unpin, sender: SyncSender<Message>) {
+    loop {
+        let mut bytes = Vec::new();
+        if reader.read_until(b'\n', &mut bytes).await.is_err() {
+            bytes.clear();
+        }
+        let Some(line) = ladybug::clean_input_line(&bytes) else {
+            continue;
+        };
+
+        let quit = line == "quit";
+        // the message channel is bounded, so sending may block until Ladybug catches up
+        let sender = sender.clone();
+        let send_result = tokio::task::spawn_blocking(move || sender.send(Message::ConsoleMessage(line))).await;
+        // if the Ladybug thread was terminated, stop reading as well
+        if !matches!(send_result, Ok(Ok(()))) || quit {
+            return;
+        }
+    }
+}
+
+/// Receives the output of Ladybug and writes it line by line to the given writer, e.g. tokio's Stdout.
+/// Returns once Ladybug sends "quit" or closes the connection, or the output can't be written anymore.
+pub async fn write_output(mut writer: impl AsyncWrite + Unpin, receiver: Receiver<String>) {
+    // Ladybug sends her output through a blocking channel, which is forwarded to the task by a blocking task
+    let (output_sender, mut output_receiver) = mpsc::channel::<String>(OUTPUT_CHANNEL_CAPACITY);
+    tokio::task::spawn_blocking(move || {
+        while let Ok(output) = receiver.recv() {
+            if output_sender.blocking_send(output).is_err() {
+                return;
+            }
+        }
+    });
+
+    while let Some(output) = output_receiver.recv().await {
+        if output == "quit" {
+            return;
+        }
+        let line = format!("{output}\n");
+        if writer.write_all(line.as_bytes()).await.is_err() || writer.flush().await.is_err() {
+            return;
+        }
+    }
+}
+
+/// The input and output of Ladybug on Stdin and Stdout, handled by the tasks of a tokio runtime.
+pub struct AsyncConsole {
+    /// The runtime the tasks run on.
+    runtime: Runtime,
+    /// The task that writes the output, which has to finish before the process exits.
+    output_task: JoinHandle<()>,
+}
+
+impl AsyncConsole {
+    /// Starts reading the input lines from Stdin and writing the output of Ladybug to Stdout.
+    pub fn start(sender: SyncSender<Message>, receiver: Receiver<String>) -> io::Result<AsyncConsole> {
+        // a single worker is enough for two tasks, and keeps the runtime running while the main thread runs Ladybug
+        let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(1).thread_name("console").build()?;
+        runtime.spawn(read_input(BufReader::new(tokio::io::stdin()), sender));
+        let output_task = runtime.spawn(write_output(tokio::io::stdout(), receiver));
+        Ok(AsyncConsole { runtime, output_task })
+    }
+
+    /// Waits until the remaining output is written, and shuts the runtime down.
+    pub fn join(self) {
+        let _ = self.runtime.block_on(self.output_task);
+        // reading Stdin can't be cancelled, so the runtime doesn't wait for the input task
+        self.runtime.shutdown_background();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, SyncSender};
+    use crate::async_io::{read_input, write_output};
+    use crate::ladybug::Message;
+
+    #[test]
+    fn test_read_input() {
+        let (sender, receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(16);
+        let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(1).build().unwrap();
+        let input: &[u8] = b"\xef\xbb\xbfuci\r\n\nisready\ngo depth 1";
+        runtime.block_on(read_input(input, sender));
+
+        let lines: Vec<String> = receiver.try_iter()
+            .map(|message| match message {
+                Message::ConsoleMessage(line) => line,
+                Message::SearchMessage(output) => output,
+            })
+            .collect();
+        // the closed input makes Ladybug quit
+        assert_eq!(vec!["uci", "isready", "go depth 1", "quit"], lines);
+    }
+
+    #[test]
+    fn test_write_output() {
+        let (sender, receiver): (SyncSender<String>, Receiver<String>) = mpsc::sync_channel(16);
+        for output in ["id name Ladybug", "uciok", "quit", "readyok"] {
+            sender.send(output.to_string()).unwrap();
+        }
+        // the runtime waits for the forwarding task, which only finishes once the channel is closed
+        drop(sender);
+        let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(1).build().unwrap();
+        let mut written: Vec<u8> = Vec::new();
+        runtime.block_on(write_output(&mut written, receiver));
+
+        // nothing is written after "quit"
+        assert_eq!("id name Ladybug\nuciok\n", String::from_utf8(written).unwrap());
+    }
+}
@@ -0,0 +1,240 @@
+//! The "ladybug-lichess" binary plays games on Lichess with a bot account, using the [Lichess Bot API](https://lichess.org/api#tag/Bot).
+//!
+//! The API token of the bot account is read from the LICHESS_TOKEN environment variable:
+//!
+//! ```text
+//! cargo run --release --features lichess --bin ladybug-lichess
+//! ```
+//!
+//! The bot accepts challenges of the variants Ladybug can play, and plays one game at a time.
+//! The HTTP requests are made with curl, so that Ladybug doesn't need a HTTP client, and the token is passed to curl
+//! through its standard input, so that it doesn't show up in the process list.
+
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+use std::thread;
+use ladybug::game::START_FEN;
+use ladybug::ladybug::{Ladybug, Message, MESSAGE_CHANNEL_CAPACITY, OUTPUT_CHANNEL_CAPACITY};
+use ladybug::lichess;
+use ladybug::json::Json;
+use ladybug::lichess::{Event, GameEvent};
+use ladybug::search::{Search, SearchCommand};
+
+/// The address of Lichess.
+const LICHESS_URL: &str = "https://lichess.org";
+
+/// Connects to Lichess and plays the games of the bot account until the event stream is closed.
+fn main() {
+    let Ok(token) = std::env::var("LICHESS_TOKEN") else {
+        println!("Set LICHESS_TOKEN to the API token of the bot account");
+        std::process::exit(1);
+    };
+
+    if let Err(message) = run(token.as_str()) {
+        println!("{message}");
+        std::process::exit(1);
+    }
+}
+
+/// Handles the events of the bot account: challenges are accepted or declined, and started games are played in their own thread.
+fn run(token: &str) -> Result<(), String> {
+    let account = Json::parse(request(token, "GET", "/api/account", None)?.as_str())?;
+    let bot_id = account.get("id").and_then(|id| id.as_str()).ok_or("Could not read the account of the token")?.to_string();
+    println!("Logged in as {bot_id}");
+
+    // only one game is played at a time, since the engine uses all of its time for it
+    let playing = Arc::new(AtomicBool::new(false));
+
+    for line in stream(token, "/api/stream/event")? {
+        match Event::parse(line.as_str()) {
+            Ok(Event::Challenge { id, variant }) => {
+                let reason = match (lichess::uci_variant(variant.as_str()), playing.load(Ordering::Relaxed)) {
+                    (None, _playing) => Some("variant"),
+                    (Some(_variant), true) => Some("later"),
+                    (Some(_variant), false) => None,
+                };
+                let result = match reason {
+                    None => request(token, "POST", format!("/api/challenge/{id}/accept").as_str(), None),
+                    Some(reason) => request(token, "POST", format!("/api/challenge/{id}/decline").as_str(), Some(format!("reason={reason}").as_str())),
+                };
+                if let Err(message) = result {
+                    println!("{message}");
+                }
+            }
+            Ok(Event::GameStart { id }) => {
+                if playing.swap(true, Ordering::Relaxed) {
+                    continue;
+                }
+                println!("Game {id} started");
+                let (token, bot_id, playing) = (token.to_string(), bot_id.clone(), playing.clone());
+                thread::spawn(move || {
+                    if let Err(message) = play_game(token.as_str(), bot_id.as_str(), id.as_str()) {
+                        println!("{message}");
+                    }
+                    playing.store(false, Ordering::Relaxed);
+                });
+            }
+            Ok(Event::Other) => {}
+            Err(message) => println!("Invalid event: {message}"),
+        }
+    }
+    Err(String::from("The event stream was closed"))
+}
+
+/// Plays the game with the given id until it is over.
+fn play_game(token: &str, bot_id: &str, game_id: &str) -> Result<(), String> {
+    let engine = Engine::start();
+    let mut initial_fen = START_FEN.to_string();
+    let mut bot_is_white = true;
+
+    for line in stream(token, format!("/api/bot/game/stream/{game_id}").as_str())? {
+        let state = match GameEvent::parse(line.as_str()) {
+            Ok(GameEvent::GameFull { white, black: _black, variant, initial_fen: fen, state }) => {
+                bot_is_white = white.eq_ignore_ascii_case(bot_id);
+                initial_fen = fen;
+                engine.send(format!("setoption name UCI_Variant value {}", lichess::uci_variant(variant.as_str()).unwrap_or("chess")));
+                state
+            }
+            Ok(GameEvent::GameState(state)) => state,
+            Ok(GameEvent::Other) => continue,
+            Err(message) => {
+                println!("Invalid game state: {message}");
+                continue;
+            }
+        };
+
+        if !state.is_running() {
+            println!("Game {game_id} ended: {}", state.status);
+            return Ok(());
+        }
+        let Some(commands) = lichess::engine_commands(initial_fen.as_str(), &state, bot_is_white) else {
+            continue;
+        };
+        if let Some(best_move) = engine.best_move(&commands) {
+            request(token, "POST", format!("/api/bot/game/{game_id}/move/{best_move}").as_str(), None)?;
+        }
+    }
+    Ok(())
+}
+
+/// Ladybug running in the threads of this process, just like in the main binary, but talking to the bot instead of the console.
+struct Engine {
+    /// Sends commands to Ladybug.
+    sender: SyncSender<Message>,
+    /// Receives the output of Ladybug.
+    receiver: Receiver<String>,
+}
+
+impl Engine {
+    /// Spawns the Ladybug and search threads.
+    fn start() -> Engine {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+        let (output_sender, output_receiver): (SyncSender<String>, Receiver<String>) = mpsc::sync_channel(OUTPUT_CHANNEL_CAPACITY);
+
+        let mut search = Search::new(search_command_receiver, message_sender.clone());
+        let stop_flag = search.stop_flag();
+        let ponder_flag = search.ponder_flag();
+        let _ = thread::Builder::new().name("search".to_string()).spawn(move || search.run());
+
+        let mut ladybug = Ladybug::new(search_command_sender, output_sender, message_receiver, stop_flag, ponder_flag);
+        let _ = thread::Builder::new().name("ladybug".to_string()).spawn(move || ladybug.run());
+
+        Engine { sender: message_sender, receiver: output_receiver }
+    }
+
+    /// Sends the given command to Ladybug.
+    fn send(&self, command: String) {
+        let _ = self.sender.send(Message::ConsoleMessage(command));
+    }
+
+    /// Sends the given commands to Ladybug and returns the best move she finds,
+    /// or None if there is no legal move or she stopped responding.
+    fn best_move(&self, commands: &[String]) -> Option<String> {
+        for command in commands {
+            self.send(command.clone());
+        }
+        loop {
+            let output = self.receiver.recv().ok()?;
+            if let Some(best_move) = output.strip_prefix("bestmove ") {
+                // the null move means that the game is over
+                return Some(best_move.to_string()).filter(|best_move| best_move != "0000");
+            }
+        }
+    }
+}
+
+impl Drop for Engine {
+    /// Shuts Ladybug down once the game is over.
+    fn drop(&mut self) {
+        self.send(String::from("quit"));
+    }
+}
+
+/// Starts curl with the given arguments and the authorization header of the given token.
+fn curl(token: &str, args: &[&str]) -> Result<Child, String> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--no-buffer", "--header", "@-"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|_| String::from("Could not start curl"))?;
+
+    // curl reads the header from its standard input, which is closed afterwards
+    let mut stdin = child.stdin.take().ok_or("Could not start curl")?;
+    writeln!(stdin, "Authorization: Bearer {token}").map_err(|_| String::from("Could not pass the token to curl"))?;
+    Ok(child)
+}
+
+/// Sends a request with the given method to the given path of the API, and returns the body of the response.
+/// The data, if any, is sent form-encoded.
+fn request(token: &str, method: &str, path: &str, data: Option<&str>) -> Result<String, String> {
+    let url = format!("{LICHESS_URL}{path}");
+    let mut args = vec!["--request", method, url.as_str()];
+    if let Some(data) = data {
+        args.extend(["--data", data]);
+    }
+    let output = curl(token, &args)?.wait_with_output().map_err(|_| format!("Request to {path} failed"))?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+        false => Err(format!("Request to {path} failed")),
+    }
+}
+
+/// The lines of a stream of the API, which are read from curl until the stream is closed.
+struct Stream {
+    /// The curl process that receives the stream.
+    child: Child,
+    /// The lines curl writes to its standard output.
+    lines: Lines<BufReader<ChildStdout>>,
+}
+
+impl Iterator for Stream {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.lines.next()?.ok()
+    }
+}
+
+impl Drop for Stream {
+    /// Stops curl, in case the stream is dropped before it is closed.
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Opens the stream of the given path of the API.
+fn stream(token: &str, path: &str) -> Result<Stream, String> {
+    let url = format!("{LICHESS_URL}{path}");
+    let mut child = curl(token, &[url.as_str()])?;
+    let stdout = child.stdout.take().ok_or(format!("Could not open the stream {path}"))?;
+    Ok(Stream { child, lines: BufReader::new(stdout).lines() })
+}
@@ -4,7 +4,9 @@
 
 use arrayvec::ArrayVec;
 use position::Position;
+use crate::board::fen::FenError;
 use crate::board::color::Color;
+use crate::board::history::{BoardHistory, HistoryError};
 use crate::board::piece::Piece;
 use crate::move_gen::ply::Ply;
 
@@ -17,10 +19,15 @@ pub mod castling_rights;
 pub mod piece;
 pub mod position;
 pub mod fen;
+pub mod dirty_pieces;
+pub mod variant;
+pub mod history;
 
 /// The board struct holds the current position of the board.
-/// It also keeps track of the full move counter, the halfmove clock (50 move rule),
-/// and a list of all positions that have been on the board before (threefold repetition).
+/// It also keeps track of the full move counter and the halfmove clock (50 move rule).
+///
+/// The board doesn't keep the moves that led to it, so that the search can copy it cheaply.
+/// Moves that need to be taken back are played with `push` instead of `make_move`, which records them in a `BoardHistory`.
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Board {
     /// The current position of the chess board.
@@ -47,18 +54,28 @@ impl Board {
     /// Constructs a new board from a FEN string.
     /// If the FEN could be parsed successfully, the result will contain the newly constructed board.
     /// Otherwise, it will contain an error.
-    pub fn from_fen(fen: &str) -> Result<Board, String> {
+    ///
+    /// Since many tools omit the halfmove clock and the fullmove counter, they default to 0 and 1 if they are missing.
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
         Self::parse_fen(fen)
     }
 
+    /// Constructs a new board from a FEN string, without checking whether the position can occur in a game.
+    /// This allows setting up positions without kings, which are used by some perft suites.
+    pub fn from_fen_unchecked(fen: &str) -> Result<Board, FenError> {
+        Self::parse_fen_unchecked(fen)
+    }
+
     /// Returns a new board that reflects the board state where the given move (ply) has been played.
+    /// The halfmove clock is reset by pawn moves (including promotions) and captures (including en passant),
+    /// and incremented otherwise. The fullmove counter is incremented after Black's move.
     pub fn make_move(&self, ply: Ply) -> Board {
         let mut board = *self;
         
         board.position = board.position.make_move(ply);
         
         // update the halfmove clock
-        if ply.piece != Piece::Pawn && ply.captured_piece.is_none() {
+        if ply.piece() != Piece::Pawn && !ply.is_capture() {
             // if the move is neither a pawn move nor a capture, increment the halfmove clock
             board.halfmove_clock += 1;
         } else {
@@ -73,6 +90,23 @@ impl Board {
         
         board
     }
+
+    /// Plays the given move on this board and records it in the given history, so that it can be taken back with `pop`.
+    /// Like `make_move`, this doesn't check whether the move is legal.
+    /// If the history is full, the move is not played and the result contains an error.
+    pub fn push(&mut self, ply: Ply, history: &mut BoardHistory) -> Result<(), HistoryError> {
+        history.push(ply, *self)?;
+        *self = self.make_move(ply);
+        Ok(())
+    }
+
+    /// Takes back the move that has been played last according to the given history and returns it,
+    /// or returns None if the history is empty.
+    pub fn pop(&mut self, history: &mut BoardHistory) -> Option<Ply> {
+        let (ply, board) = history.pop()?;
+        *self = board;
+        Some(ply)
+    }
     
     /// Checks whether the position is a draw by either threefold repetition or the 50 move rule, based on the given board history.
     pub fn is_draw(&self, board_history: &ArrayVec<u64, 1000>) -> bool {
@@ -111,19 +145,14 @@ mod tests {
     use crate::board::{Board, square};
     use crate::board::castling_rights::CastlingRights;
     use crate::board::color::Color::{Black, White};
+    use crate::board::history::{BoardHistory, HistoryError, MAX_HISTORY_LENGTH};
     use crate::board::piece::Piece;
     use crate::board::position::Position;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
     use crate::move_gen::ply::Ply;
     use crate::zobrist;
 
     #[test]
     fn default_returns_board_with_default_values() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-        
         let board = Board::default();
         assert_eq!(Position::default(), board.position);
         assert_eq!(0, board.halfmove_clock);
@@ -132,10 +161,6 @@ mod tests {
 
     #[test]
     fn from_fen_with_valid_fen_returns_board() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // -----------------------------------------------------------------------------------------
         // position 1
         // -----------------------------------------------------------------------------------------
@@ -148,7 +173,7 @@ mod tests {
         ];
         assert_eq!(bitboards, board.position.pieces);
         assert_eq!(White, board.position.color_to_move);
-        assert_eq!([CastlingRights::NoRights; 2], board.position.castling_rights);
+        assert_eq!(CastlingRights::NONE, board.position.castling_rights);
         assert_eq!(None, board.position.en_passant);
         assert_eq!(0, board.halfmove_clock);
         assert_eq!(34, board.fullmove_counter);
@@ -165,7 +190,7 @@ mod tests {
         ];
         assert_eq!(bitboards, board.position.pieces);
         assert_eq!(Black, board.position.color_to_move);
-        assert_eq!([CastlingRights::Both; 2], board.position.castling_rights);
+        assert_eq!(CastlingRights::ALL, board.position.castling_rights);
         assert_eq!(None, board.position.en_passant);
         assert_eq!(0, board.halfmove_clock);
         assert_eq!(13, board.fullmove_counter);
@@ -182,7 +207,7 @@ mod tests {
         ];
         assert_eq!(bitboards, board.position.pieces);
         assert_eq!(White, board.position.color_to_move);
-        assert_eq!([CastlingRights::NoRights; 2], board.position.castling_rights);
+        assert_eq!(CastlingRights::NONE, board.position.castling_rights);
         assert_eq!(None, board.position.en_passant);
         assert_eq!(4, board.halfmove_clock);
         assert_eq!(29, board.fullmove_counter);
@@ -190,108 +215,122 @@ mod tests {
     
     #[test]
     fn test_make_move() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-        
         // g1-f3
         let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
-        let board = board.make_move(Ply {
-            source: square::G1,
-            target: square::F3,
-            piece: Piece::Knight,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let board = board.make_move(Ply::new(square::G1, square::F3, Piece::Knight, None, false));
         assert_eq!(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1").unwrap(), board);
 
         // b8-c6
-        let board = board.make_move(Ply {
-            source: square::B8,
-            target: square::C6,
-            piece: Piece::Knight,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let board = board.make_move(Ply::new(square::B8, square::C6, Piece::Knight, None, false));
         assert_eq!(Board::from_fen("r1bqkbnr/pppppppp/2n5/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 2 2").unwrap(), board);
 
         // b1-c3
-        let board = board.make_move(Ply {
-            source: square::B1,
-            target: square::C3,
-            piece: Piece::Knight,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let board = board.make_move(Ply::new(square::B1, square::C3, Piece::Knight, None, false));
         assert_eq!(Board::from_fen("r1bqkbnr/pppppppp/2n5/8/8/2N2N2/PPPPPPPP/R1BQKB1R b KQkq - 3 2").unwrap(), board);
 
         // g8-f6
-        let board = board.make_move(Ply {
-            source: square::G8,
-            target: square::F6,
-            piece: Piece::Knight,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let board = board.make_move(Ply::new(square::G8, square::F6, Piece::Knight, None, false));
         assert_eq!(Board::from_fen("r1bqkb1r/pppppppp/2n2n2/8/8/2N2N2/PPPPPPPP/R1BQKB1R w KQkq - 4 3").unwrap(), board);
 
         // f3-e5
-        let board = board.make_move(Ply {
-            source: square::F3,
-            target: square::E5,
-            piece: Piece::Knight,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let board = board.make_move(Ply::new(square::F3, square::E5, Piece::Knight, None, false));
         assert_eq!(Board::from_fen("r1bqkb1r/pppppppp/2n2n2/4N3/8/2N5/PPPPPPPP/R1BQKB1R b KQkq - 5 3").unwrap(), board);
 
         // c6-e5
-        let board = board.make_move(Ply {
-            source: square::C6,
-            target: square::E5,
-            piece: Piece::Knight,
-            captured_piece: Some(Piece::Knight),
-            promotion_piece: None,
-        });
+        let board = board.make_move(Ply::new(square::C6, square::E5, Piece::Knight, None, true));
         assert_eq!(Board::from_fen("r1bqkb1r/pppppppp/5n2/4n3/8/2N5/PPPPPPPP/R1BQKB1R w KQkq - 0 4").unwrap(), board);
 
         // d2-d4
-        let board = board.make_move(Ply {
-            source: square::D2,
-            target: square::D4,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let board = board.make_move(Ply::new(square::D2, square::D4, Piece::Pawn, None, false));
         assert_eq!(Board::from_fen("r1bqkb1r/pppppppp/5n2/4n3/3P4/2N5/PPP1PPPP/R1BQKB1R b KQkq d3 0 4").unwrap(), board);
 
         // e5-c6
-        let board = board.make_move(Ply {
-            source: square::E5,
-            target: square::C6,
-            piece: Piece::Knight,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let board = board.make_move(Ply::new(square::E5, square::C6, Piece::Knight, None, false));
         assert_eq!(Board::from_fen("r1bqkb1r/pppppppp/2n2n2/8/3P4/2N5/PPP1PPPP/R1BQKB1R w KQkq - 1 5").unwrap(), board);
 
         // e5-c6
-        let board = board.make_move(Ply {
-            source: square::D4,
-            target: square::D5,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let board = board.make_move(Ply::new(square::D4, square::D5, Piece::Pawn, None, false));
         assert_eq!(Board::from_fen("r1bqkb1r/pppppppp/2n2n2/3P4/8/2N5/PPP1PPPP/R1BQKB1R b KQkq - 0 5").unwrap(), board);
     }
     
+    #[test]
+    fn test_make_move_updates_clocks() {
+        // castling increments the halfmove clock
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 7 20").unwrap();
+        let board = board.make_move(Ply::new(square::E1, square::G1, Piece::King, None, false));
+        assert_eq!("r3k2r/8/8/8/8/8/8/R4RK1 b kq - 8 20", board.to_fen());
+        let board = board.make_move(Ply::new(square::E8, square::C8, Piece::King, None, false));
+        assert_eq!("2kr3r/8/8/8/8/8/8/R4RK1 w - - 9 21", board.to_fen());
+
+        // en passant resets the halfmove clock
+        let board = Board::from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 12 40").unwrap();
+        let board = board.make_move(Ply::new(square::D5, square::E6, Piece::Pawn, None, true));
+        assert_eq!("4k3/8/4P3/8/8/8/8/4K3 b - - 0 40", board.to_fen());
+
+        // a promotion resets the halfmove clock
+        let board = Board::from_fen("4k3/8/8/8/8/8/p7/4K3 b - - 3 57").unwrap();
+        let board = board.make_move(Ply::new(square::A2, square::A1, Piece::Pawn, Some(Piece::Queen), false));
+        assert_eq!("4k3/8/8/8/8/8/8/q3K3 w - - 0 58", board.to_fen());
+    }
+
+    #[test]
+    fn test_push_and_pop() {
+        let start = Board::from_fen("r3k2r/8/8/3Pp3/8/8/8/R3K2R w KQkq e6 7 20").unwrap();
+        let mut board = start;
+        let mut history = BoardHistory::new();
+        let moves = [
+            Ply::new(square::D5, square::E6, Piece::Pawn, None, true),
+            Ply::new(square::E8, square::C8, Piece::King, None, false),
+            Ply::new(square::E1, square::G1, Piece::King, None, false),
+        ];
+        for ply in moves {
+            board.push(ply, &mut history).unwrap();
+        }
+        assert_eq!("2kr3r/8/4P3/8/8/8/8/R4RK1 b - - 2 21", board.to_fen());
+        assert_eq!(start.make_move(moves[0]).make_move(moves[1]).make_move(moves[2]), board);
+        assert_eq!(Some(moves[2]), history.last_move());
+        assert_eq!(3, history.moves().len());
+        assert_eq!(start.position.hash, history.hashes()[0]);
+
+        // the moves are taken back in reverse order, which restores the position, its hash and the history
+        let board_after_first_move = start.make_move(moves[0]);
+        assert_eq!(Some(moves[2]), board.pop(&mut history));
+        assert_eq!(Some(moves[1]), board.pop(&mut history));
+        assert_eq!(board_after_first_move, board);
+        assert_eq!(zobrist::get_hash(&board_after_first_move.position), board.position.hash);
+        assert_eq!(&[start.position.hash], history.hashes().as_slice());
+        assert_eq!(Some(moves[0]), history.last_move());
+
+        assert_eq!(Some(moves[0]), board.pop(&mut history));
+        assert_eq!(start, board);
+        assert_eq!(BoardHistory::new(), history);
+        assert_eq!(None, board.pop(&mut history));
+        assert_eq!(start, board);
+    }
+
+    #[test]
+    fn test_push_onto_full_history() {
+        let mut board = Board::default();
+        let mut history = BoardHistory::new();
+        let knight_moves = [
+            Ply::new(square::G1, square::F3, Piece::Knight, None, false),
+            Ply::new(square::G8, square::F6, Piece::Knight, None, false),
+            Ply::new(square::F3, square::G1, Piece::Knight, None, false),
+            Ply::new(square::F6, square::G8, Piece::Knight, None, false),
+        ];
+        for ply in knight_moves.iter().cycle().take(MAX_HISTORY_LENGTH) {
+            board.push(*ply, &mut history).unwrap();
+        }
+
+        // a full history neither records the move nor plays it
+        let full_board = board;
+        assert_eq!(Err(HistoryError::Full), board.push(knight_moves[0], &mut history));
+        assert_eq!(full_board, board);
+        assert_eq!(MAX_HISTORY_LENGTH, history.moves().len());
+    }
+
     #[test]
     fn test_is_draw() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-        
         let mut board_history: ArrayVec<u64, 1000> = ArrayVec::new();
         let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3 1").unwrap();
         
@@ -3,6 +3,7 @@ use arrayvec::ArrayVec;
 use crate::board::file::{File, NUM_FILES};
 use crate::board::rank::{NUM_RANKS, Rank};
 use crate::board::square::{Square};
+use crate::lookup::{NOT_A_FILE, NOT_H_FILE};
 
 /// A bitboard representing the state of the board for one type of piece for one color.
 ///
@@ -18,15 +19,56 @@ pub struct Bitboard {
 
 impl Bitboard {
     /// Constructs a new bitboard from an u64.
-    pub fn new(value: u64) -> Self {
+    pub const fn new(value: u64) -> Self {
         Self { value }
     }
 
     /// Returns a bitboard with the bit at the specified square set to 1
-    pub fn from_square(square: Square) -> Self {
+    pub const fn from_square(square: Square) -> Self {
         Self { value: 1 << square.index }
     }
 
+    /// Returns a bitboard with all squares on the specified rank set to 1.
+    pub fn from_rank(rank: Rank) -> Self {
+        Self { value: 0xff << (rank.to_index() * NUM_FILES) }
+    }
+
+    /// Returns the bitboard with every set square moved one rank up.
+    /// Squares on the eighth rank are shifted off the board.
+    pub const fn shift_up(&self) -> Self {
+        Self { value: self.value << 8 }
+    }
+
+    /// Returns the bitboard with every set square moved one rank down.
+    /// Squares on the first rank are shifted off the board.
+    pub const fn shift_down(&self) -> Self {
+        Self { value: self.value >> 8 }
+    }
+
+    /// Returns the bitboard with every set square moved one square diagonally up and to the left.
+    /// Squares on the eighth rank or the A file are shifted off the board.
+    pub const fn shift_up_left(&self) -> Self {
+        Self { value: (self.value << 7) & NOT_H_FILE.value }
+    }
+
+    /// Returns the bitboard with every set square moved one square diagonally up and to the right.
+    /// Squares on the eighth rank or the H file are shifted off the board.
+    pub const fn shift_up_right(&self) -> Self {
+        Self { value: (self.value << 9) & NOT_A_FILE.value }
+    }
+
+    /// Returns the bitboard with every set square moved one square diagonally down and to the left.
+    /// Squares on the first rank or the A file are shifted off the board.
+    pub const fn shift_down_left(&self) -> Self {
+        Self { value: (self.value >> 9) & NOT_H_FILE.value }
+    }
+
+    /// Returns the bitboard with every set square moved one square diagonally down and to the right.
+    /// Squares on the first rank or the H file are shifted off the board.
+    pub const fn shift_down_right(&self) -> Self {
+        Self { value: (self.value >> 7) & NOT_A_FILE.value }
+    }
+
     /// Returns true if the bit at the specified square is set to 1, false if not.
     pub fn get_bit(&self, square: Square) -> bool {
         (1 << square.index) & self.value > 0
@@ -116,6 +158,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_rank_returns_bitboard_with_all_squares_on_rank_set() {
+        assert_eq!(0xff, Bitboard::from_rank(Rank::First).value);
+        assert_eq!(0xff000000, Bitboard::from_rank(Rank::Fourth).value);
+        assert_eq!(0xff00000000000000, Bitboard::from_rank(Rank::Eighth).value);
+    }
+
+    #[test]
+    fn shift_moves_squares_by_one_rank() {
+        // squares e2 and h8, of which h8 is shifted off the board when moving up
+        let bitboard = Bitboard::new(0x8000000000001000);
+        assert_eq!(Bitboard::from_square(square::E3), bitboard.shift_up());
+        assert_eq!(Bitboard::new(0x80000000000010), bitboard.shift_down());
+        assert_eq!(Bitboard::new(0), Bitboard::from_rank(Rank::First).shift_down());
+    }
+
+    #[test]
+    fn diagonal_shift_moves_squares_without_wrapping_around() {
+        // squares a2 and h7, which are shifted off the board towards their edge files
+        let bitboard = Bitboard::new(0x80000000000100);
+        assert_eq!(Bitboard::from_square(square::G8), bitboard.shift_up_left());
+        assert_eq!(Bitboard::from_square(square::B3), bitboard.shift_up_right());
+        assert_eq!(Bitboard::from_square(square::G6), bitboard.shift_down_left());
+        assert_eq!(Bitboard::from_square(square::B1), bitboard.shift_down_right());
+    }
+
     #[test]
     fn bitboard_formats_correctly() {
         let bitboard = Bitboard::new(0x8000000800000010); // Bitboard with squares h8, d5, e1 occupied
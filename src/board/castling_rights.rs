@@ -1,35 +1,144 @@
 use std::fmt::{Display, Formatter};
+use crate::board::color::Color;
+use crate::board::file::File;
 
-/// Contains information on what (if any) castling rights a player has.
+/// The side of the board a player castles to.
 #[derive(Copy, Clone, PartialEq, Debug)]
-pub enum CastlingRights {
-    NoRights,
-    KingSide,
-    QueenSide,
-    Both,
+pub enum CastlingSide {
+    KingSide = 0,
+    QueenSide = 1,
 }
 
-/// Prints the castling rights as text.
+impl CastlingSide {
+    /// Returns the side whose rook starts on the given file, or None if no rook starts there.
+    pub fn from_rook_file(file: File) -> Option<CastlingSide> {
+        match file {
+            File::H => Some(CastlingSide::KingSide),
+            File::A => Some(CastlingSide::QueenSide),
+            _other => None,
+        }
+    }
+}
+
+/// The castling rights of both players, stored as one flag per color and side.
+///
+/// The flags are ordered like in the FEN: white kingside (bit 0), white queenside (bit 1),
+/// black kingside (bit 2) and black queenside (bit 3).
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct CastlingRights {
+    flags: u8,
+}
+
+/// The letters of the castling rights in the FEN, in the order of the flags.
+const FEN_LETTERS: [char; 4] = ['K', 'Q', 'k', 'q'];
+
+impl CastlingRights {
+    /// No player may castle.
+    pub const NONE: CastlingRights = CastlingRights { flags: 0 };
+    /// Both players may castle to both sides.
+    pub const ALL: CastlingRights = CastlingRights { flags: 0b1111 };
+
+    /// Returns the index of the flag for the given color and side, which is also the index of its letter in the FEN.
+    fn index(color: Color, side: CastlingSide) -> usize {
+        (color.to_index() * 2 + side as u8) as usize
+    }
+
+    /// Returns whether the given color may castle to the given side.
+    pub fn allows(&self, color: Color, side: CastlingSide) -> bool {
+        self.flags & 1 << Self::index(color, side) != 0
+    }
+
+    /// Returns whether the given color may castle to any side.
+    pub fn allows_any(&self, color: Color) -> bool {
+        self.allows(color, CastlingSide::KingSide) || self.allows(color, CastlingSide::QueenSide)
+    }
+
+    /// Removes the right of the given color to castle to the given side.
+    pub fn remove(&mut self, color: Color, side: CastlingSide) {
+        self.flags &= !(1 << Self::index(color, side));
+    }
+
+    /// Removes the rights of the given color to castle to either side.
+    pub fn remove_all(&mut self, color: Color) {
+        self.remove(color, CastlingSide::KingSide);
+        self.remove(color, CastlingSide::QueenSide);
+    }
+
+    /// Returns the indices of the flags that are set, in ascending order.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..FEN_LETTERS.len()).filter(|index| self.flags & 1 << index != 0)
+    }
+
+    /// Parses the castling rights part of a FEN, which is either "-" or a non-empty subset of "KQkq" in this order.
+    /// Returns None if the string is not valid.
+    pub fn from_fen(fen: &str) -> Option<CastlingRights> {
+        if fen == "-" {
+            return Some(CastlingRights::NONE);
+        }
+        let mut castling_rights = CastlingRights::NONE;
+        // each letter must come after the previous one
+        let mut next_index = 0;
+        for letter in fen.chars() {
+            let index = FEN_LETTERS.iter().position(|fen_letter| *fen_letter == letter).filter(|index| *index >= next_index)?;
+            castling_rights.flags |= 1 << index;
+            next_index = index + 1;
+        }
+        match fen.is_empty() {
+            true => None,
+            false => Some(castling_rights),
+        }
+    }
+
+    /// Returns the castling rights as they appear in the FEN, e.g. "KQkq", or "-" if no player may castle.
+    pub fn to_fen(&self) -> String {
+        match self.flags {
+            0 => String::from("-"),
+            _other => self.indices().map(|index| FEN_LETTERS[index]).collect(),
+        }
+    }
+}
+
+/// Prints the castling rights as they appear in the FEN.
 impl Display for CastlingRights {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        match self {
-            CastlingRights::NoRights => write!(f, "NoRights"),
-            CastlingRights::KingSide => write!(f, "KingSide"),
-            CastlingRights::QueenSide => write!(f, "QueenSide"),
-            CastlingRights::Both => write!(f, "Both"),
-        }
+        write!(f, "{}", self.to_fen())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::board::castling_rights::CastlingRights;
+    use crate::board::castling_rights::{CastlingRights, CastlingSide};
+    use crate::board::castling_rights::CastlingSide::{KingSide, QueenSide};
+    use crate::board::color::Color::{Black, White};
+    use crate::board::file::File;
+
+    #[test]
+    fn allows_and_remove_affect_only_the_given_color_and_side() {
+        let mut castling_rights = CastlingRights::ALL;
+        assert!(castling_rights.allows(White, KingSide) && castling_rights.allows(Black, QueenSide));
+
+        castling_rights.remove(White, QueenSide);
+        assert!(!castling_rights.allows(White, QueenSide));
+        assert!(castling_rights.allows(White, KingSide));
+        assert!(castling_rights.allows(Black, QueenSide));
+
+        castling_rights.remove_all(Black);
+        assert!(!castling_rights.allows_any(Black));
+        assert!(castling_rights.allows_any(White));
+        assert_eq!("K", castling_rights.to_fen());
+    }
+
+    #[test]
+    fn from_rook_file_returns_side_of_rook() {
+        assert_eq!(Some(CastlingSide::KingSide), CastlingSide::from_rook_file(File::H));
+        assert_eq!(Some(CastlingSide::QueenSide), CastlingSide::from_rook_file(File::A));
+        assert_eq!(None, CastlingSide::from_rook_file(File::E));
+    }
 
     #[test]
     fn castling_rights_formats_correctly() {
-        assert_eq!("NoRights", format!("{}", CastlingRights::NoRights));
-        assert_eq!("KingSide", format!("{}", CastlingRights::KingSide));
-        assert_eq!("QueenSide", format!("{}", CastlingRights::QueenSide));
-        assert_eq!("Both", format!("{}", CastlingRights::Both));
+        assert_eq!("-", format!("{}", CastlingRights::NONE));
+        assert_eq!("KQkq", format!("{}", CastlingRights::ALL));
+        assert_eq!("Qk", CastlingRights::from_fen("Qk").unwrap().to_string());
     }
-}
\ No newline at end of file
+}
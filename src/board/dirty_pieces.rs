@@ -0,0 +1,51 @@
+use arrayvec::ArrayVec;
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::square::Square;
+
+/// A piece that was moved, added to the board or removed from the board by a move.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DirtyPiece {
+    /// The type of the piece.
+    pub piece: Piece,
+    /// The color of the piece.
+    pub color: Color,
+    /// The square the piece was removed from, or None if the piece was added to the board (promotion).
+    pub from: Option<Square>,
+    /// The square the piece was placed on, or None if the piece was removed from the board (capture, promotion).
+    pub to: Option<Square>,
+}
+
+/// The compact record of all piece changes caused by a single move.
+///
+/// Consumers that keep state derived from the piece placement, such as incremental evaluation, can update their state
+/// from this record instead of comparing the positions before and after the move.
+/// A move changes at most three pieces: a promotion with capture removes the pawn and the captured piece and adds the
+/// promotion piece, castling moves both the king and the rook.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct DirtyPieces {
+    /// The changed pieces.
+    pieces: ArrayVec<DirtyPiece, 3>,
+}
+
+impl DirtyPieces {
+    /// Adds a changed piece to the record.
+    pub fn push(&mut self, piece: Piece, color: Color, from: Option<Square>, to: Option<Square>) {
+        self.pieces.push(DirtyPiece { piece, color, from, to });
+    }
+
+    /// Returns an iterator over the changed pieces.
+    pub fn iter(&self) -> impl Iterator<Item = &DirtyPiece> {
+        self.pieces.iter()
+    }
+
+    /// Returns the number of changed pieces.
+    pub fn len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// Returns true if no pieces changed.
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+}
@@ -1,18 +1,87 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 use crate::board::bitboard::Bitboard;
 use crate::board::Board;
-use crate::board::castling_rights::CastlingRights;
-use crate::board::color::{Color, NUM_COLORS};
+use crate::board::castling_rights::{CastlingRights, CastlingSide};
+use crate::board::color::Color;
 use crate::board::color::Color::{Black, White};
 use crate::board::file::{File, NUM_FILES};
 use crate::board::piece::Piece::{Bishop, King, Knight, Pawn, Queen, Rook};
 use crate::board::position::Position;
 use crate::board::rank::{NUM_RANKS, Rank};
+use crate::board::piece::Piece;
+use crate::board::square;
 use crate::board::square::Square;
 
+/// The squares on the first and the eighth rank, which pawns can never stand on.
+const BACK_RANKS: u64 = 0xFF00_0000_0000_00FF;
+
+/// The reasons why a FEN can't be parsed.
+#[derive(Clone, PartialEq, Debug)]
+pub enum FenError {
+    /// The FEN does not consist of 4 to 6 fields.
+    WrongFieldCount(usize),
+    /// The piece placement does not consist of 8 ranks.
+    WrongRankCount(usize),
+    /// The piece placement contains a character that is neither a piece nor a number of empty squares.
+    InvalidPiece(char),
+    /// The given rank of the piece placement describes more than 8 squares.
+    RankTooLong(Rank),
+    /// The color to move is neither "w" nor "b".
+    InvalidColor(String),
+    /// The castling rights are neither "-" nor a subset of "KQkq" in this order.
+    InvalidCastlingRights(String),
+    /// The en passant field is neither "-" nor a square.
+    InvalidEnPassant(String),
+    /// The halfmove clock is not a number.
+    InvalidHalfmoveClock(String),
+    /// The fullmove counter is not a number larger than 0.
+    InvalidFullmoveCounter(String),
+    /// The given color does not have exactly one king.
+    WrongKingCount(Color, u32),
+    /// There is a pawn on the given square on the first or eighth rank.
+    PawnOnBackRank(Square),
+    /// The given color has castling rights, but the king or the rook is not on its initial square.
+    CastlingRightsMismatch(Color),
+    /// No pawn can be captured en passant on the given square, since the pawn that has just moved two squares is missing.
+    EnPassantMismatch(Square),
+}
+
+/// Prints the error as text.
+impl Display for FenError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => write!(f, "Invalid FEN: expected 4 to 6 fields, found {count}"),
+            FenError::WrongRankCount(count) => write!(f, "Invalid FEN: expected 8 ranks, found {count}"),
+            FenError::InvalidPiece(char) => write!(f, "Invalid FEN: invalid piece '{char}'"),
+            FenError::RankTooLong(rank) => write!(f, "Invalid FEN: rank {rank} has more than 8 squares"),
+            FenError::InvalidColor(color) => write!(f, "Invalid FEN: invalid color to move '{color}'"),
+            FenError::InvalidCastlingRights(castling_rights) => write!(f, "Invalid FEN: invalid castling rights '{castling_rights}'"),
+            FenError::InvalidEnPassant(en_passant) => write!(f, "Invalid FEN: invalid en passant square '{en_passant}'"),
+            FenError::InvalidHalfmoveClock(halfmove_clock) => write!(f, "Invalid FEN: invalid halfmove clock '{halfmove_clock}'"),
+            FenError::InvalidFullmoveCounter(fullmove_counter) => write!(f, "Invalid FEN: invalid fullmove counter '{fullmove_counter}'"),
+            FenError::WrongKingCount(color, count) => write!(f, "Invalid FEN: {color} has {count} kings instead of one"),
+            FenError::PawnOnBackRank(square) => write!(f, "Invalid FEN: pawn on {square}"),
+            FenError::CastlingRightsMismatch(color) => write!(f, "Invalid FEN: {color} can't castle without the king and rook on their initial squares"),
+            FenError::EnPassantMismatch(square) => write!(f, "Invalid FEN: no pawn can be captured en passant on {square}"),
+        }
+    }
+}
+
+impl Error for FenError {}
+
 impl Board {
     /// Parses a [FEN](https://www.chessprogramming.org/Forsyth-Edwards_Notation) string and returns a result.
     /// If the FEN could be parsed successfully, the result will contain a board. Otherwise, it will contain an error.
-    pub(super) fn parse_fen(fen: &str) -> Result<Board, String> {
+    /// Besides the syntax, the FEN must describe a position that can occur in a game of chess.
+    pub(super) fn parse_fen(fen: &str) -> Result<Board, FenError> {
+        let board = Self::parse_fen_unchecked(fen)?;
+        Self::validate_position(&board.position)?;
+        Ok(board)
+    }
+
+    /// Parses a FEN string like `parse_fen`, but only checks the syntax of the FEN.
+    pub(super) fn parse_fen_unchecked(fen: &str) -> Result<Board, FenError> {
         // split fen into its six parts
         let fen_parts = Self::split_fen(fen)?;
 
@@ -54,80 +123,57 @@ impl Board {
 
     /// Builds a FEN string representing the board state.
     pub fn to_fen(&self) -> String {
-        let mut fen = String::from("");
+        format!("{} {} {}", self.position.to_fen_fields(), self.halfmove_clock, self.fullmove_counter)
+    }
 
-        // pieces
-        for rank in (0..NUM_RANKS).rev() {
-            let mut files_to_skip = 0;
-            for file in 0..NUM_FILES {
-                let piece = self.position.get_piece(Square::from_file_rank(File::from_index(file), Rank::from_index(rank)));
-                match piece {
-                    Some((piece, color)) => {
-                        if files_to_skip > 0 {
-                            fen.push_str(format!("{files_to_skip}").as_str());
-                            files_to_skip = 0;
-                        }
-                        fen.push(piece.to_char(color));
-                    }
-                    None => {
-                        files_to_skip += 1;
-                    }
-                }
-            }
-            if files_to_skip > 0 {
-                fen.push_str(format!("{files_to_skip}").as_str());
-            }
-            if rank != 0 {
-                fen.push('/');
+    /// Checks that the position can occur in a game of chess: each color has exactly one king, there are no pawns on the back ranks,
+    /// castling rights require the king and the rook on their initial squares, and an en passant square requires the pawn that just moved past it.
+    fn validate_position(position: &Position) -> Result<(), FenError> {
+        let get_pieces = |piece: Piece, color: Color| position.piece_bb(color, piece);
+
+        for color in [White, Black] {
+            let king_count = get_pieces(King, color).value.count_ones();
+            if king_count != 1 {
+                return Err(FenError::WrongKingCount(color, king_count));
             }
         }
 
-        // color
-        match self.position.color_to_move {
-            White => fen.push_str(" w"),
-            Black => fen.push_str(" b"),
+        let pawns_on_back_ranks = (get_pieces(Pawn, White).value | get_pieces(Pawn, Black).value) & BACK_RANKS;
+        if pawns_on_back_ranks != 0 {
+            return Err(FenError::PawnOnBackRank(Square::new(pawns_on_back_ranks.trailing_zeros() as u8)));
         }
 
-        // castling rights
-        let mut castling_rights_str_both = String::from("");
-        for color_index in 0..NUM_COLORS {
-            let mut castling_rights_str = String::from("");
-            match self.position.castling_rights[color_index as usize] {
-                CastlingRights::NoRights => {}
-                CastlingRights::KingSide => castling_rights_str.push('K'),
-                CastlingRights::QueenSide => castling_rights_str.push('Q'),
-                CastlingRights::Both => castling_rights_str.push_str("KQ"),
-            }
-            if Color::from_index(color_index) == Black {
-                castling_rights_str = castling_rights_str.to_ascii_lowercase();
-            }
-            castling_rights_str_both += castling_rights_str.as_str();
-        }
-        match castling_rights_str_both.as_str() {
-            "" => fen.push_str(" -"),
-            other => {
-                fen.push_str(format!(" {other}").as_str());
+        for color in [White, Black] {
+            let (king_square, king_side_rook_square, queen_side_rook_square) = match color {
+                White => (square::E1, square::H1, square::A1),
+                Black => (square::E8, square::H8, square::A8),
+            };
+            let king_side = get_pieces(King, color).get_bit(king_square) && get_pieces(Rook, color).get_bit(king_side_rook_square);
+            let queen_side = get_pieces(King, color).get_bit(king_square) && get_pieces(Rook, color).get_bit(queen_side_rook_square);
+            let valid = (king_side || !position.castling_rights.allows(color, CastlingSide::KingSide))
+                && (queen_side || !position.castling_rights.allows(color, CastlingSide::QueenSide));
+            if !valid {
+                return Err(FenError::CastlingRightsMismatch(color));
             }
         }
 
-        // en passant
-        match self.position.en_passant {
-            None => fen.push_str(" -"),
-            Some(square) => fen.push_str(format!(" {square}").as_str()),
+        if let Some(en_passant) = position.en_passant {
+            // the pawn that just moved two squares stands in front of the en passant square, seen from the color to move
+            let pawn_moved = match position.color_to_move {
+                White => en_passant.get_rank() == Rank::Sixth && en_passant.try_down().is_some_and(|square| get_pieces(Pawn, Black).get_bit(square)),
+                Black => en_passant.get_rank() == Rank::Third && en_passant.try_up().is_some_and(|square| get_pieces(Pawn, White).get_bit(square)),
+            };
+            if !pawn_moved || position.get_occupancies().get_bit(en_passant) {
+                return Err(FenError::EnPassantMismatch(en_passant));
+            }
         }
 
-        // halfmove clock
-        fen.push_str(format!(" {}", self.halfmove_clock).as_str());
-
-        // fullmove counter
-        fen.push_str(format!(" {}", self.fullmove_counter).as_str());
-
-        fen
+        Ok(())
     }
 
     /// Takes a FEN and splits it into its 6 parts.
-    /// If the FEN has more than 4 but less than 6 parts, default parameters will be added for the remaining parts.
-    fn split_fen(fen: &str) -> Result<Vec<String>, String> {
+    /// If the FEN has 4 or 5 parts, default values are added for the missing halfmove clock and fullmove counter.
+    fn split_fen(fen: &str) -> Result<Vec<String>, FenError> {
         let mut fen_parts: Vec<String> = fen.split_whitespace().map(|s| s.to_string()).collect();
         match fen_parts.len() {
             4 => {
@@ -140,16 +186,16 @@ impl Board {
                 Ok(fen_parts)
             }
             6 => Ok(fen_parts),
-            _other => Err(String::from("Invalid FEN")),
+            other => Err(FenError::WrongFieldCount(other)),
         }
     }
 
     /// Parses the first part of the FEN (pieces).
-    fn parse_pieces(piece_fen: &str) -> Result<[[Bitboard; 6]; 2], String> {
+    fn parse_pieces(piece_fen: &str) -> Result<[[Bitboard; 6]; 2], FenError> {
         let mut pieces = [[Bitboard::new(0); 6]; 2];
         let piece_parts: Vec<String> = piece_fen.split('/').map(|s| s.to_string()).collect();
         if piece_parts.len() != 8 {
-            return Err(String::from("Invalid FEN"));
+            return Err(FenError::WrongRankCount(piece_parts.len()));
         }
         for (rank_index, piece_str) in piece_parts.iter().enumerate() {
             let mut file_index: usize = 0;
@@ -172,10 +218,10 @@ impl Board {
                         let files_to_skip = char.to_digit(10);
                         match files_to_skip {
                             Some(files_to_skip) => file_index += files_to_skip as usize - 1,
-                            None => return Err(String::from("Invalid FEN")),
+                            None => return Err(FenError::InvalidPiece(char)),
                         }
                     }
-                    _other => return Err(String::from("Invalid FEN")),
+                    other => return Err(FenError::InvalidPiece(other)),
                 }
                 if file_index > 7 {
                     // In a FEN string, pieces are specified using letters (P for a white pawn for example),
@@ -183,7 +229,7 @@ impl Board {
                     // If the file_index is larger than seven before the increment below,
                     // it means that the number of piece letters plus the sum of numbers used to notate empty squares was larger than 8.
                     // Since a chessboard only has 8 files, the FEN must be invalid.
-                    return Err(String::from("Invalid FEN"));
+                    return Err(FenError::RankTooLong(Rank::from_index(7 - rank_index as u8)));
                 }
                 file_index += 1;
             }
@@ -192,87 +238,119 @@ impl Board {
     }
 
     /// Parses the second part of the FEN (color to move).
-    fn parse_color_to_move(color_fen: &str) -> Result<Color, String> {
+    fn parse_color_to_move(color_fen: &str) -> Result<Color, FenError> {
         match color_fen {
             "w" => Ok(White),
             "b" => Ok(Black),
-            _other => Err(String::from("Invalid FEN")),
+            other => Err(FenError::InvalidColor(other.to_string())),
         }
     }
 
     /// Parses the third part of the FEN (castling rights).
-    fn parse_castling_rights(castling_rights_fen: &str) -> Result<[CastlingRights; 2], String> {
-        match castling_rights_fen {
-            "-" => Ok([CastlingRights::NoRights, CastlingRights::NoRights]),
-            "q" => Ok([CastlingRights::NoRights, CastlingRights::QueenSide]),
-            "k" => Ok([CastlingRights::NoRights, CastlingRights::KingSide]),
-            "kq" => Ok([CastlingRights::NoRights, CastlingRights::Both]),
-            "Q" => Ok([CastlingRights::QueenSide, CastlingRights::NoRights]),
-            "Qq" => Ok([CastlingRights::QueenSide, CastlingRights::QueenSide]),
-            "Qk" => Ok([CastlingRights::QueenSide, CastlingRights::KingSide]),
-            "Qkq" => Ok([CastlingRights::QueenSide, CastlingRights::Both]),
-            "K" => Ok([CastlingRights::KingSide, CastlingRights::NoRights]),
-            "Kq" => Ok([CastlingRights::KingSide, CastlingRights::QueenSide]),
-            "Kk" => Ok([CastlingRights::KingSide, CastlingRights::KingSide]),
-            "Kkq" => Ok([CastlingRights::KingSide, CastlingRights::Both]),
-            "KQ" => Ok([CastlingRights::Both, CastlingRights::NoRights]),
-            "KQq" => Ok([CastlingRights::Both, CastlingRights::QueenSide]),
-            "KQk" => Ok([CastlingRights::Both, CastlingRights::KingSide]),
-            "KQkq" => Ok([CastlingRights::Both, CastlingRights::Both]),
-            _other => Err(String::from("Invalid FEN")),
-        }
+    fn parse_castling_rights(castling_rights_fen: &str) -> Result<CastlingRights, FenError> {
+        CastlingRights::from_fen(castling_rights_fen).ok_or(FenError::InvalidCastlingRights(castling_rights_fen.to_string()))
     }
 
     /// Parses the fourth part of the FEN (en passant).
-    fn parse_en_passant(en_passant_fen: &str) -> Result<Option<Square>, String> {
+    fn parse_en_passant(en_passant_fen: &str) -> Result<Option<Square>, FenError> {
         match en_passant_fen {
             "-" => Ok(None),
             other => {
                 Square::from_string(other)
                     .map(Some)
-                    .map_err(|_| String::from("Invalid FEN"))
+                    .map_err(|_| FenError::InvalidEnPassant(other.to_string()))
             }
         }
     }
 
     /// Parses the fifth part of the FEN (halfmove clock).
-    fn parse_halfmove_clock(halfmove_clock_fen: &str) -> Result<u32, String> {
+    fn parse_halfmove_clock(halfmove_clock_fen: &str) -> Result<u32, FenError> {
         let halfmove_clock: Result<u32, _> = halfmove_clock_fen.parse();
         match halfmove_clock {
             Ok(halfmove_clock) => Ok(halfmove_clock),
-            Err(_) => Err(String::from("Invalid FEN")),
+            Err(_) => Err(FenError::InvalidHalfmoveClock(halfmove_clock_fen.to_string())),
         }
     }
 
     /// Parses the sixth part of the FEN (fullmove counter).
-    fn parse_fullmove_counter(fullmove_counter_fen: &str) -> Result<u32, String> {
+    fn parse_fullmove_counter(fullmove_counter_fen: &str) -> Result<u32, FenError> {
         let fullmove_counter: Result<u32, _> = fullmove_counter_fen.parse();
         match fullmove_counter {
             Ok(halfmove_clock) => match halfmove_clock {
-                0 => Err(String::from("Invalid FEN")), // The fullmove counter starts at 1, so it can't be 0.
+                0 => Err(FenError::InvalidFullmoveCounter(fullmove_counter_fen.to_string())), // The fullmove counter starts at 1, so it can't be 0.
                 other => Ok(other),
             }
-            Err(_) => Err(String::from("Invalid FEN")),
+            Err(_) => Err(FenError::InvalidFullmoveCounter(fullmove_counter_fen.to_string())),
         }
     }
 }
 
+impl Position {
+    /// Builds the first four fields of the FEN of the position: the pieces, the color to move, the castling rights and the en passant square.
+    /// The halfmove clock and the fullmove counter are not part of the position, see `Board::to_fen` for the full FEN.
+    pub fn to_fen_fields(&self) -> String {
+        let mut fen = String::from("");
+
+        // pieces
+        let mailbox = self.get_mailbox();
+        for rank in (0..NUM_RANKS).rev() {
+            let mut files_to_skip = 0;
+            for file in 0..NUM_FILES {
+                let piece = mailbox[Square::from_file_rank(File::from_index(file), Rank::from_index(rank)).index as usize];
+                match piece {
+                    Some((piece, color)) => {
+                        if files_to_skip > 0 {
+                            fen.push_str(format!("{files_to_skip}").as_str());
+                            files_to_skip = 0;
+                        }
+                        fen.push(piece.to_char(color));
+                    }
+                    None => {
+                        files_to_skip += 1;
+                    }
+                }
+            }
+            if files_to_skip > 0 {
+                fen.push_str(format!("{files_to_skip}").as_str());
+            }
+            if rank != 0 {
+                fen.push('/');
+            }
+        }
+
+        // color
+        match self.color_to_move {
+            White => fen.push_str(" w"),
+            Black => fen.push_str(" b"),
+        }
+
+        // castling rights
+        fen.push_str(format!(" {}", self.castling_rights.to_fen()).as_str());
+
+        // en passant
+        match self.en_passant {
+            None => fen.push_str(" -"),
+            Some(square) => fen.push_str(format!(" {square}").as_str()),
+        }
+
+        fen
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::board::bitboard::Bitboard;
-    use crate::board::castling_rights::CastlingRights;
+    use crate::board::castling_rights::{CastlingRights, CastlingSide};
     use crate::board::color::Color::{Black, White};
     use crate::board::piece::Piece::{Bishop, King, Knight, Pawn, Queen, Rook};
     use crate::board::{Board, square};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
+    use crate::board::color::Color;
+    use crate::board::fen::FenError;
+    use crate::board::rank::Rank;
+    use crate::move_gen;
 
     #[test]
     fn parse_fen_with_valid_fen_returns_board() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // -----------------------------------------------------------------------------------------
         // Test the parse_fen function with a lot of different positions to make sure it's working.
         // -----------------------------------------------------------------------------------------
@@ -289,7 +367,7 @@ mod tests {
         ];
         assert_eq!(bitboards, board.position.pieces);
         assert_eq!(White, board.position.color_to_move);
-        assert_eq!([CastlingRights::Both; 2], board.position.castling_rights);
+        assert_eq!(CastlingRights::ALL, board.position.castling_rights);
         assert_eq!(None, board.position.en_passant);
         assert_eq!(0, board.halfmove_clock);
         assert_eq!(1, board.fullmove_counter);
@@ -306,7 +384,7 @@ mod tests {
         ];
         assert_eq!(bitboards, board.position.pieces);
         assert_eq!(Black, board.position.color_to_move);
-        assert_eq!([CastlingRights::NoRights; 2], board.position.castling_rights);
+        assert_eq!(CastlingRights::NONE, board.position.castling_rights);
         assert_eq!(None, board.position.en_passant);
         assert_eq!(2, board.halfmove_clock);
         assert_eq!(23, board.fullmove_counter);
@@ -323,7 +401,7 @@ mod tests {
         ];
         assert_eq!(bitboards, board.position.pieces);
         assert_eq!(Black, board.position.color_to_move);
-        assert_eq!([CastlingRights::NoRights; 2], board.position.castling_rights);
+        assert_eq!(CastlingRights::NONE, board.position.castling_rights);
         assert_eq!(None, board.position.en_passant);
         assert_eq!(4, board.halfmove_clock);
         assert_eq!(33, board.fullmove_counter);
@@ -340,7 +418,7 @@ mod tests {
         ];
         assert_eq!(bitboards, board.position.pieces);
         assert_eq!(White, board.position.color_to_move);
-        assert_eq!([CastlingRights::Both, CastlingRights::NoRights], board.position.castling_rights);
+        assert_eq!("KQ", board.position.castling_rights.to_fen());
         assert_eq!(Some(square::A6), board.position.en_passant);
         assert_eq!(0, board.halfmove_clock);
         assert_eq!(13, board.fullmove_counter);
@@ -348,13 +426,39 @@ mod tests {
 
     #[test]
     fn parse_fen_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen(""));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("Rust is awesome!"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQKQ - 0 1"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 1"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR B KQkq - 0 1"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fen("rnbqkbnr/ppppp1ppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(Err(FenError::WrongFieldCount(0)), Board::parse_fen(""));
+        assert_eq!(Err(FenError::WrongFieldCount(3)), Board::parse_fen("Rust is awesome!"));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("KQKQ"))), Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQKQ - 0 1"));
+        assert_eq!(Err(FenError::WrongRankCount(7)), Board::parse_fen("rnbqkbnr/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 1"));
+        assert_eq!(Err(FenError::InvalidColor(String::from("B"))), Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR B KQkq - 0 1"));
+        assert_eq!(Err(FenError::InvalidPiece('9')), Board::parse_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(Err(FenError::RankTooLong(Rank::Seventh)), Board::parse_fen("rnbqkbnr/ppppp1ppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+    }
+
+    #[test]
+    fn parse_fen_with_impossible_position_returns_error() {
+        assert_eq!(Err(FenError::WrongKingCount(White, 0)), Board::parse_fen("4k3/8/8/8/8/8/8/8 w - - 0 1"));
+        assert_eq!(Err(FenError::WrongKingCount(Black, 2)), Board::parse_fen("4k2k/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert_eq!(Err(FenError::PawnOnBackRank(square::A8)), Board::parse_fen("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert_eq!(Err(FenError::PawnOnBackRank(square::H1)), Board::parse_fen("4k3/8/8/8/8/8/8/4K2p w - - 0 1"));
+        assert_eq!(Err(FenError::CastlingRightsMismatch(White)), Board::parse_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1"));
+        assert_eq!(Err(FenError::CastlingRightsMismatch(White)), Board::parse_fen("4k3/8/8/8/8/8/8/R3K3 w KQ - 0 1"));
+        assert_eq!(Err(FenError::CastlingRightsMismatch(Black)), Board::parse_fen("r2k3r/8/8/8/8/8/8/4K3 w k - 0 1"));
+        assert_eq!(Err(FenError::EnPassantMismatch(square::E3)), Board::parse_fen("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"));
+        assert_eq!(Err(FenError::EnPassantMismatch(square::D6)), Board::parse_fen("4k3/8/8/8/4P3/8/8/4K3 w - d6 0 1"));
+        assert_eq!(Err(FenError::EnPassantMismatch(square::E1)), Board::parse_fen("4k3/8/8/8/4P3/8/8/4K3 w - e1 0 1"));
+        assert!(Board::parse_fen("4k3/8/8/3pP3/8/8/8/R3K3 w Q d6 0 1").is_ok());
+
+        // the unchecked parser only checks the syntax
+        assert!(Board::parse_fen_unchecked("4k3/8/8/8/8/8/8/4K2p w K e3 0 1").is_ok());
+        assert_eq!(Err(FenError::InvalidColor(String::from("x"))), Board::parse_fen_unchecked("8/8/8/8/8/8/8/8 x - - 0 1"));
+    }
+
+    #[test]
+    fn fen_error_is_printed_as_text() {
+        assert_eq!("Invalid FEN: expected 8 ranks, found 7", FenError::WrongRankCount(7).to_string());
+        assert_eq!("Invalid FEN: rank 8 has more than 8 squares", FenError::RankTooLong(Rank::Eighth).to_string());
+        assert_eq!("Invalid FEN: invalid castling rights 'KQKQ'", FenError::InvalidCastlingRights(String::from("KQKQ")).to_string());
     }
 
     #[test]
@@ -392,9 +496,9 @@ mod tests {
 
     #[test]
     fn split_fen_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::split_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::split_fen("one two three four five six seven"));
-        assert_ne!(Err(String::from("Invalid FEN")), Board::split_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"));
+        assert_eq!(Err(FenError::WrongFieldCount(3)), Board::split_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq"));
+        assert_eq!(Err(FenError::WrongFieldCount(7)), Board::split_fen("one two three four five six seven"));
+        assert!(Board::split_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2").is_ok());
     }
 
     #[test]
@@ -433,9 +537,9 @@ mod tests {
 
     #[test]
     fn parse_pieces_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_pieces("/rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R/"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_pieces("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_pieces("rnbqk1bnr/8/8/8/8/8/8/8"));
+        assert_eq!(Err(FenError::WrongRankCount(10)), Board::parse_pieces("/rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R/"));
+        assert_eq!(Err(FenError::WrongRankCount(7)), Board::parse_pieces("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP"));
+        assert_eq!(Err(FenError::RankTooLong(Rank::Eighth)), Board::parse_pieces("rnbqk1bnr/8/8/8/8/8/8/8"));
     }
 
     #[test]
@@ -446,37 +550,30 @@ mod tests {
 
     #[test]
     fn parse_color_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_color_to_move("W"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_color_to_move(""));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_color_to_move("nonsense"));
+        assert_eq!(Err(FenError::InvalidColor(String::from("W"))), Board::parse_color_to_move("W"));
+        assert_eq!(Err(FenError::InvalidColor(String::from(""))), Board::parse_color_to_move(""));
+        assert_eq!(Err(FenError::InvalidColor(String::from("nonsense"))), Board::parse_color_to_move("nonsense"));
     }
 
     #[test]
     fn parse_castling_rights_with_valid_fen_returns_castling_rights() {
-        assert_eq!([CastlingRights::NoRights, CastlingRights::NoRights], Board::parse_castling_rights("-").unwrap());
-        assert_eq!([CastlingRights::NoRights, CastlingRights::QueenSide], Board::parse_castling_rights("q").unwrap());
-        assert_eq!([CastlingRights::NoRights, CastlingRights::KingSide], Board::parse_castling_rights("k").unwrap());
-        assert_eq!([CastlingRights::NoRights, CastlingRights::Both], Board::parse_castling_rights("kq").unwrap());
-        assert_eq!([CastlingRights::QueenSide, CastlingRights::NoRights], Board::parse_castling_rights("Q").unwrap());
-        assert_eq!([CastlingRights::QueenSide, CastlingRights::QueenSide], Board::parse_castling_rights("Qq").unwrap());
-        assert_eq!([CastlingRights::QueenSide, CastlingRights::KingSide], Board::parse_castling_rights("Qk").unwrap());
-        assert_eq!([CastlingRights::QueenSide, CastlingRights::Both], Board::parse_castling_rights("Qkq").unwrap());
-        assert_eq!([CastlingRights::KingSide, CastlingRights::NoRights], Board::parse_castling_rights("K").unwrap());
-        assert_eq!([CastlingRights::KingSide, CastlingRights::QueenSide], Board::parse_castling_rights("Kq").unwrap());
-        assert_eq!([CastlingRights::KingSide, CastlingRights::KingSide], Board::parse_castling_rights("Kk").unwrap());
-        assert_eq!([CastlingRights::KingSide, CastlingRights::Both], Board::parse_castling_rights("Kkq").unwrap());
-        assert_eq!([CastlingRights::Both, CastlingRights::NoRights], Board::parse_castling_rights("KQ").unwrap());
-        assert_eq!([CastlingRights::Both, CastlingRights::QueenSide], Board::parse_castling_rights("KQq").unwrap());
-        assert_eq!([CastlingRights::Both, CastlingRights::KingSide], Board::parse_castling_rights("KQk").unwrap());
-        assert_eq!([CastlingRights::Both, CastlingRights::Both], Board::parse_castling_rights("KQkq").unwrap());
+        for fen in ["-", "q", "k", "kq", "Q", "Qq", "Qk", "Qkq", "K", "Kq", "Kk", "Kkq", "KQ", "KQq", "KQk", "KQkq"] {
+            assert_eq!(fen, Board::parse_castling_rights(fen).unwrap().to_fen());
+        }
+        let castling_rights = Board::parse_castling_rights("Kq").unwrap();
+        assert!(castling_rights.allows(White, CastlingSide::KingSide));
+        assert!(!castling_rights.allows(White, CastlingSide::QueenSide));
+        assert!(!castling_rights.allows(Black, CastlingSide::KingSide));
+        assert!(castling_rights.allows(Black, CastlingSide::QueenSide));
     }
 
     #[test]
     fn parse_castling_rights_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_castling_rights("KQkqq"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_castling_rights("kqKQ"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_castling_rights("nonsense"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_castling_rights("12345"));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("KQkqq"))), Board::parse_castling_rights("KQkqq"));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("kqKQ"))), Board::parse_castling_rights("kqKQ"));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("nonsense"))), Board::parse_castling_rights("nonsense"));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::from("12345"))), Board::parse_castling_rights("12345"));
+        assert_eq!(Err(FenError::InvalidCastlingRights(String::new())), Board::parse_castling_rights(""));
     }
 
     #[test]
@@ -494,14 +591,14 @@ mod tests {
 
     #[test]
     fn parse_en_passant_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant(""));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("12345"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("Nonsense"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("G5"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("a9"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("e0"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("f-"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_en_passant("ab"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from(""))), Board::parse_en_passant(""));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("12345"))), Board::parse_en_passant("12345"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("Nonsense"))), Board::parse_en_passant("Nonsense"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("G5"))), Board::parse_en_passant("G5"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("a9"))), Board::parse_en_passant("a9"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("e0"))), Board::parse_en_passant("e0"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("f-"))), Board::parse_en_passant("f-"));
+        assert_eq!(Err(FenError::InvalidEnPassant(String::from("ab"))), Board::parse_en_passant("ab"));
     }
 
     #[test]
@@ -515,10 +612,10 @@ mod tests {
 
     #[test]
     fn parse_halfmove_clock_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_halfmove_clock("-5"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_halfmove_clock("Nonsense"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_halfmove_clock("a"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_halfmove_clock("I like Rust"));
+        assert_eq!(Err(FenError::InvalidHalfmoveClock(String::from("-5"))), Board::parse_halfmove_clock("-5"));
+        assert_eq!(Err(FenError::InvalidHalfmoveClock(String::from("Nonsense"))), Board::parse_halfmove_clock("Nonsense"));
+        assert_eq!(Err(FenError::InvalidHalfmoveClock(String::from("a"))), Board::parse_halfmove_clock("a"));
+        assert_eq!(Err(FenError::InvalidHalfmoveClock(String::from("I like Rust"))), Board::parse_halfmove_clock("I like Rust"));
     }
 
     #[test]
@@ -532,19 +629,15 @@ mod tests {
 
     #[test]
     fn parse_fullmove_counter_with_invalid_fen_returns_error() {
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("-5"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("Nonsense"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("a"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("I like Rust"));
-        assert_eq!(Err(String::from("Invalid FEN")), Board::parse_fullmove_counter("0"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("-5"))), Board::parse_fullmove_counter("-5"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("Nonsense"))), Board::parse_fullmove_counter("Nonsense"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("a"))), Board::parse_fullmove_counter("a"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("I like Rust"))), Board::parse_fullmove_counter("I like Rust"));
+        assert_eq!(Err(FenError::InvalidFullmoveCounter(String::from("0"))), Board::parse_fullmove_counter("0"));
     }
 
     #[test]
     fn test_to_fen() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
         let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
         assert_eq!(board, Board::from_fen(board.to_fen().as_str()).unwrap());
@@ -578,11 +671,76 @@ mod tests {
         assert_eq!(board, Board::from_fen(board.to_fen().as_str()).unwrap());
 
         // position 9
-        let board = Board::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").unwrap();
-        assert_eq!(board, Board::from_fen(board.to_fen().as_str()).unwrap());
+        let board = Board::from_fen_unchecked("8/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(board, Board::from_fen_unchecked(board.to_fen().as_str()).unwrap());
+    }
+
+    #[test]
+    fn test_to_fen_fields() {
+        let board = Board::from_fen("r1bqkb1r/pp3ppp/2n2n2/4p3/2P5/3P4/PP3PPP/RNBQKBNR w Kkq e6 0 6").unwrap();
+        assert_eq!("r1bqkb1r/pp3ppp/2n2n2/4p3/2P5/3P4/PP3PPP/RNBQKBNR w Kkq e6", board.position.to_fen_fields());
+
+        // the fields don't depend on the clocks
+        let board = Board::from_fen("1r3rk1/2RR1p1p/p3pQp1/1p6/6P1/1P5P/5PBK/1q6 w - - 17 28").unwrap();
+        assert_eq!("1r3rk1/2RR1p1p/p3pQp1/1p6/6P1/1P5P/5PBK/1q6 w - -", board.position.to_fen_fields());
 
         // position 10
-        let board = Board::from_fen("8/1k6/8/8/5K2/8/8/8 w - e3 0 1").unwrap();
-        assert_eq!(board, Board::from_fen(board.to_fen().as_str()).unwrap());
+        let board = Board::from_fen_unchecked("8/1k6/8/8/5K2/8/8/8 w - e3 0 1").unwrap();
+        assert_eq!(board, Board::from_fen_unchecked(board.to_fen().as_str()).unwrap());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_to_fen_round_trip_in_random_games() {
+        // a simple linear congruential generator, so that the games are the same in every test run
+        let mut seed: u64 = 0x2545F4914F6CDD1D;
+        let mut next_random = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            seed >> 33
+        };
+
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "4k3/8/8/8/8/8/8/4K2R b K - 17 63",
+        ];
+
+        for fen in fens {
+            for _game in 0..10 {
+                let mut board = Board::from_fen(fen).unwrap();
+                let mut halfmove_clock = board.halfmove_clock;
+                let mut fullmove_counter = board.fullmove_counter;
+
+                for _ply in 0..200 {
+                    let move_list = move_gen::generate_moves(board.position);
+                    if move_list.is_empty() {
+                        break;
+                    }
+                    let ply = move_list.get((next_random() % move_list.len() as u64) as u8);
+
+                    // track the clocks independently of the board
+                    halfmove_clock = match ply.piece() == Pawn || ply.is_capture() {
+                        true => 0,
+                        false => halfmove_clock + 1,
+                    };
+                    if board.position.color_to_move == Color::Black {
+                        fullmove_counter += 1;
+                    }
+
+                    board = board.make_move(ply);
+                    assert_eq!(halfmove_clock, board.halfmove_clock);
+                    assert_eq!(fullmove_counter, board.fullmove_counter);
+
+                    // the FEN describes the board completely, and parsing it results in the same FEN again
+                    let board_fen = board.to_fen();
+                    assert!(board_fen.ends_with(format!(" {halfmove_clock} {fullmove_counter}").as_str()), "{board_fen}");
+                    let parsed_board = Board::from_fen(board_fen.as_str()).unwrap();
+                    assert_eq!(board, parsed_board, "{board_fen}");
+                    assert_eq!(board_fen, parsed_board.to_fen());
+                }
+            }
+        }
+    }
+}
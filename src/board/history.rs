@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use arrayvec::ArrayVec;
+use crate::board::Board;
+use crate::move_gen::ply::Ply;
+
+/// The maximum number of moves a board history can hold, which is the length of the board history passed to the search.
+pub const MAX_HISTORY_LENGTH: usize = 1000;
+
+/// The reasons why a move can't be recorded in a board history.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HistoryError {
+    /// The history already holds `MAX_HISTORY_LENGTH` moves.
+    Full,
+}
+
+/// Prints the error as text.
+impl Display for HistoryError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            HistoryError::Full => write!(f, "The board history is longer than {MAX_HISTORY_LENGTH} moves"),
+        }
+    }
+}
+
+impl Error for HistoryError {}
+
+/// The moves that have been played on a board with `Board::push`, so that they can be taken back with `Board::pop`.
+///
+/// The history is kept apart from the board, so that the board stays a small value that the search can copy cheaply.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct BoardHistory {
+    /// The moves that have been played, together with the board they have been played on.
+    moves: Vec<(Ply, Board)>,
+    /// Contains the hashes of all positions that have been on the board before the current one.
+    hashes: ArrayVec<u64, MAX_HISTORY_LENGTH>,
+}
+
+impl BoardHistory {
+    /// Constructs an empty board history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the moves that have been played, starting with the first one, together with the board they have been played on.
+    pub fn moves(&self) -> &[(Ply, Board)] {
+        &self.moves
+    }
+
+    /// Returns the hashes of all positions that have been on the board before the current one, as used for the repetition detection.
+    pub fn hashes(&self) -> &ArrayVec<u64, MAX_HISTORY_LENGTH> {
+        &self.hashes
+    }
+
+    /// Returns the move that has been played last, or None if no move has been played yet.
+    pub fn last_move(&self) -> Option<Ply> {
+        self.moves.last().map(|(ply, _board)| *ply)
+    }
+
+    /// Records that the given move is played on the given board.
+    /// If the history is full, nothing is recorded and the result contains an error.
+    pub(crate) fn push(&mut self, ply: Ply, board: Board) -> Result<(), HistoryError> {
+        if self.hashes.try_push(board.position.hash).is_err() {
+            return Err(HistoryError::Full);
+        }
+        self.moves.push((ply, board));
+        Ok(())
+    }
+
+    /// Removes the move that has been played last and returns it, together with the board it has been played on.
+    pub(crate) fn pop(&mut self) -> Option<(Ply, Board)> {
+        let last = self.moves.pop()?;
+        self.hashes.pop();
+        Some(last)
+    }
+}
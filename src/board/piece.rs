@@ -57,6 +57,28 @@ impl Piece {
         }
     }
 
+    /// Returns the Unicode chess symbol of the piece, e.g. '♘' for a white knight.
+    pub fn to_unicode(&self, color: Color) -> char {
+        match color {
+            Color::White => match self {
+                Piece::Pawn => '♙',
+                Piece::Knight => '♘',
+                Piece::Bishop => '♗',
+                Piece::Rook => '♖',
+                Piece::Queen => '♕',
+                Piece::King => '♔',
+            }
+            Color::Black => match self {
+                Piece::Pawn => '♟',
+                Piece::Knight => '♞',
+                Piece::Bishop => '♝',
+                Piece::Rook => '♜',
+                Piece::Queen => '♛',
+                Piece::King => '♚',
+            }
+        }
+    }
+
     /// Constructs a piece from the given string slice.
     pub fn from_string(piece_str: &str) -> Option<Piece> {
         match piece_str.to_ascii_lowercase().as_str() {
@@ -135,6 +157,14 @@ mod tests {
         assert_eq!('k', King.to_char(Black));
     }
 
+    #[test]
+    fn to_unicode_returns_correct_char() {
+        assert_eq!('♙', Pawn.to_unicode(White));
+        assert_eq!('♔', King.to_unicode(White));
+        assert_eq!('♟', Pawn.to_unicode(Black));
+        assert_eq!('♚', King.to_unicode(Black));
+    }
+
     #[test]
     fn from_string_with_invalid_string_returns_none() {
         assert_eq!(None, Piece::from_string("test"));
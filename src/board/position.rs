@@ -1,17 +1,33 @@
 use std::fmt::{Display, Formatter};
+use arrayvec::ArrayVec;
 use crate::board::bitboard::Bitboard;
-use crate::board::castling_rights::CastlingRights;
+use crate::board::castling_rights::{CastlingRights, CastlingSide};
 use crate::board::color::{Color, NUM_COLORS};
+use crate::board::dirty_pieces::DirtyPieces;
 use crate::board::file::{File, NUM_FILES};
 use crate::board::piece::{NUM_PIECES, Piece};
 use crate::board::piece::Piece::{King};
 use crate::board::rank::{NUM_RANKS, Rank};
 use crate::board::square;
-use crate::board::square::Square;
+use crate::board::square::{NUM_SQUARES, Square};
+use crate::board::variant;
+use crate::board::variant::Variant;
+use crate::evaluation::pst;
 use crate::lookup::LOOKUP_TABLE;
+use crate::move_gen;
 use crate::move_gen::ply::Ply;
 use crate::zobrist;
 
+/// The squares that are light squares on the chess board.
+pub const LIGHT_SQUARES: u64 = 0x55AA_55AA_55AA_55AA;
+
+/// The game phase of the starting position, where the game is in the middlegame.
+/// A game phase of 0 means that only kings and pawns are left, which is a pure endgame.
+pub const MAX_GAME_PHASE: u8 = 24;
+
+/// The weight of each piece in the game phase, indexed by piece. Pawns and kings don't change the game phase.
+const GAME_PHASE_WEIGHTS: [u8; NUM_PIECES as usize] = [0, 1, 1, 2, 4, 0];
+
 /// This struct uniquely encodes a chess position.
 /// It contains 12 bitboards, one for each piece for each color.
 /// It also contains information on whether en passant is possible, whose side it is to move,
@@ -22,7 +38,7 @@ pub struct Position {
     pub pieces: [[Bitboard; 6]; 2],
 
     /// The castling rights for both White and Black.
-    pub castling_rights: [CastlingRights; 2],
+    pub castling_rights: CastlingRights,
 
     /// If en passant is possible, this Option contains the target square for the en passant move.
     pub en_passant: Option<Square>,
@@ -30,6 +46,9 @@ pub struct Position {
     /// The color whose turn it is.
     pub color_to_move: Color,
 
+    /// The variant the position is played in.
+    pub variant: &'static dyn Variant,
+
     //-------------------------------------------------------------------------------------------
     // fields not necessary to uniquely identify a chess position, but convenient
     //-------------------------------------------------------------------------------------------
@@ -47,9 +66,10 @@ impl Default for Position {
     fn default() -> Self {
         let mut position = Self {
             pieces: [[Bitboard::new(0); 6]; 2],
-            castling_rights: [CastlingRights::NoRights; 2],
+            castling_rights: CastlingRights::NONE,
             en_passant: None,
             color_to_move: Color::White,
+            variant: variant::STANDARD,
             hash: 0,
             attack_bb: [Bitboard::new(0); 2],
         };
@@ -65,18 +85,19 @@ impl PartialEq for Position {
     /// chess position should be compared. The attack bitboards are irrelevant.
     fn eq(&self, other: &Self) -> bool {
         self.pieces == other.pieces && self.castling_rights == other.castling_rights &&
-            self.en_passant == other.en_passant && self.color_to_move == other.color_to_move
+            self.en_passant == other.en_passant && self.color_to_move == other.color_to_move && self.variant.name() == other.variant.name()
     }
 }
 
 impl Position {
     /// Constructs a new Position.
-    pub fn new(pieces: [[Bitboard; 6]; 2], castling_rights: [CastlingRights; 2], en_passant: Option<Square>, color_to_move: Color) -> Self {
+    pub fn new(pieces: [[Bitboard; 6]; 2], castling_rights: CastlingRights, en_passant: Option<Square>, color_to_move: Color) -> Self {
         let mut position = Self {
             pieces,
             castling_rights,
             en_passant,
             color_to_move,
+            variant: variant::STANDARD,
             hash: 0,
             attack_bb: [Bitboard::new(0); 2],
         };
@@ -85,17 +106,42 @@ impl Position {
         position
     }
 
+    /// Sets the variant the position is played in.
+    /// If the variant does not allow castling, the castling rights are removed.
+    pub fn set_variant(&mut self, variant: &'static dyn Variant) {
+        self.variant = variant;
+        if !variant.allows_castling() {
+            self.castling_rights = CastlingRights::NONE;
+            self.hash = zobrist::get_hash(self);
+        }
+    }
+
+    /// Returns the color that has won by a rule of the variant, e.g. by bringing the king to the center in King of the Hill.
+    pub fn variant_winner(&self) -> Option<Color> {
+        self.variant.winner(self)
+    }
+
     /// Sets a piece of the specified color on the specified square.
     ///
     /// This method DOES NOT check if there already is another piece on that square,
     /// so use `get_piece` to check if the square is unoccupied first.
     pub fn set_piece(&mut self, piece: Piece, color: Color, square: Square) {
-        self.pieces[color.to_index() as usize][piece.to_index() as usize].set_bit(square);
+        self.piece_bb_mut(color, piece).set_bit(square);
     }
 
     /// Removes a piece of the given color from the given square.
     pub fn remove_piece(&mut self, piece: Piece, color: Color, square: Square) {
-        self.pieces[color.to_index() as usize][piece.to_index() as usize].pop_bit(square);
+        self.piece_bb_mut(color, piece).pop_bit(square);
+    }
+
+    /// Returns the bitboard of the pieces of the given type and color.
+    pub fn piece_bb(&self, color: Color, piece: Piece) -> Bitboard {
+        self.pieces[color.to_index() as usize][piece.to_index() as usize]
+    }
+
+    /// Returns a mutable reference to the bitboard of the pieces of the given type and color.
+    fn piece_bb_mut(&mut self, color: Color, piece: Piece) -> &mut Bitboard {
+        &mut self.pieces[color.to_index() as usize][piece.to_index() as usize]
     }
 
     /// Returns the piece and the piece's color on the specified square.
@@ -103,15 +149,35 @@ impl Position {
     pub fn get_piece(&self, square: Square) -> Option<(Piece, Color)> {
         for color_index in 0..NUM_COLORS {
             for piece_index in 0..NUM_PIECES {
-                match self.pieces[color_index as usize][piece_index as usize].get_bit(square) {
-                    true => return Some((Piece::from_index(piece_index), Color::from_index(color_index))),
-                    false => {}
+                if self.pieces[color_index as usize][piece_index as usize].get_bit(square) {
+                    return Some((Piece::from_index(piece_index), Color::from_index(color_index)));
                 }
             }
         }
         None
     }
 
+    /// Returns a lazy iterator over all pieces on the board, yielding the square, type and color of each piece.
+    /// White's pieces are returned first, ordered by piece type and square.
+    pub fn pieces_iter(&self) -> PiecesIter {
+        PiecesIter::new(self.pieces, Color::White, Color::Black)
+    }
+
+    /// Returns a lazy iterator over all pieces of the given color, yielding the square, type and color of each piece.
+    pub fn color_pieces_iter(&self, color: Color) -> PiecesIter {
+        PiecesIter::new(self.pieces, color, color)
+    }
+
+    /// Returns the pieces of the position as an array indexed by square (a so called mailbox),
+    /// which is convenient when the board needs to be traversed square by square.
+    pub fn get_mailbox(&self) -> [Option<(Piece, Color)>; NUM_SQUARES as usize] {
+        let mut mailbox = [None; NUM_SQUARES as usize];
+        for (square, piece, color) in self.pieces_iter() {
+            mailbox[square.index as usize] = Some((piece, color));
+        }
+        mailbox
+    }
+
     /// Returns the occupancy bitboard for the specified color.
     pub fn get_occupancy(&self, color: Color) -> Bitboard {
         let mut occupancy_bb = Bitboard::new(0);
@@ -146,11 +212,11 @@ impl Position {
     /// set that are attacked by White's bishops.
     pub fn get_piece_attack_bb(&self, piece: Piece, color: Color) -> Bitboard {
         // get a reference to the lookup table
-        let lookup = LOOKUP_TABLE.get().unwrap();
+        let lookup = &LOOKUP_TABLE;
         // the result attack_bb
         let mut attack_bb = Bitboard::new(0);
         // the bitboard for the given piece of the given color
-        let piece_bb = self.pieces[color.to_index() as usize][piece.to_index() as usize];
+        let piece_bb = self.piece_bb(color, piece);
         // get squares with pieces on them
         let active_squares = piece_bb.get_active_bits();
         // get blocker bitboard (needed for slider pieces)
@@ -178,187 +244,359 @@ impl Position {
 
     /// Returns whether the king of the given color is in check
     pub fn is_in_check(&self, color: Color) -> bool {
-        let king_square = self.pieces[color.to_index() as usize][Piece::King.to_index() as usize].get_active_bits()[0];
+        let king_square = self.piece_bb(color, Piece::King).get_active_bits()[0];
         self.is_square_attacked(king_square, color.other())
     }
 
+    /// Returns whether the color to move is checkmated, meaning it is in check and has no legal moves.
+    pub fn is_checkmate(&self) -> bool {
+        self.is_in_check(self.color_to_move) && move_gen::generate_moves(*self).is_empty()
+    }
+
+    /// Returns whether the color to move is stalemated, meaning it is not in check but has no legal moves.
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_in_check(self.color_to_move) && move_gen::generate_moves(*self).is_empty()
+    }
+
+    /// Returns whether neither side can checkmate the other, which is the case if only kings are left,
+    /// or the only other pieces are either a single knight or bishops that all stand on squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let get_pieces = |piece: Piece| self.piece_bb(Color::White, piece).value
+            | self.piece_bb(Color::Black, piece).value;
+
+        if get_pieces(Piece::Pawn) | get_pieces(Piece::Rook) | get_pieces(Piece::Queen) != 0 {
+            return false;
+        }
+
+        let knights = get_pieces(Piece::Knight);
+        let bishops = get_pieces(Piece::Bishop);
+        match knights.count_ones() {
+            0 => bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0,
+            1 => bishops == 0,
+            _other => false,
+        }
+    }
+
+    /// Returns a bitboard with all pieces of both colors that attack the given square, if the board was occupied as given.
+    ///
+    /// Passing a custom occupancy allows looking through pieces, which is useful for x-ray attacks and static exchange evaluation.
+    pub fn attackers_to(&self, square: Square, occupancy: Bitboard) -> Bitboard {
+        let lookup = &LOOKUP_TABLE;
+        let pieces = |piece: Piece| self.piece_bb(Color::White, piece).value | self.piece_bb(Color::Black, piece).value;
+
+        // a piece on the square attacks the same squares that attack the square
+        let mut attackers = lookup.get_pawn_attacks(square, Color::Black).value & self.piece_bb(Color::White, Piece::Pawn).value;
+        attackers |= lookup.get_pawn_attacks(square, Color::White).value & self.piece_bb(Color::Black, Piece::Pawn).value;
+        attackers |= lookup.get_knight_attacks(square).value & pieces(Piece::Knight);
+        attackers |= lookup.get_king_attacks(square).value & pieces(Piece::King);
+        attackers |= lookup.get_bishop_attacks(square, occupancy).value & (pieces(Piece::Bishop) | pieces(Piece::Queen));
+        attackers |= lookup.get_rook_attacks(square, occupancy).value & (pieces(Piece::Rook) | pieces(Piece::Queen));
+
+        // pieces that were removed from the occupancy don't attack
+        Bitboard::new(attackers & occupancy.value)
+    }
+
+    /// Returns the [static exchange evaluation](https://www.chessprogramming.org/Static_Exchange_Evaluation) of the given capture,
+    /// i.e. the material the side to move wins if both sides keep recapturing on the target square with their least valuable piece,
+    /// and each side may stop recapturing whenever that is better for it.
+    /// Pieces that are uncovered by a capture, e.g. a rook behind a rook, join the exchange. Pins are ignored.
+    pub fn see(&self, ply: Ply) -> i32 {
+        let value = |piece: Piece| pst::PIECE_VALUES[piece.to_index() as usize];
+        let target = ply.target();
+
+        // the gains of the captures of the exchange, each from the point of view of the side making it,
+        // assuming that the capturing piece is captured by the next capture
+        let mut gains: ArrayVec<i32, 32> = ArrayVec::new();
+        gains.push(self.get_captured_piece(ply).map_or(0, value));
+        let mut occupancy = self.get_occupancies().value & !(1 << ply.source().index);
+        if ply.piece() == Piece::Pawn && self.en_passant == Some(target) {
+            occupancy &= !(1 << (target.index ^ 8));
+        }
+        let mut next_victim = match ply.promotion_piece() {
+            Some(promotion_piece) => {
+                gains[0] += value(promotion_piece) - value(Piece::Pawn);
+                value(promotion_piece)
+            }
+            None => value(ply.piece()),
+        };
+
+        let mut color = self.color_to_move.other();
+        loop {
+            let attackers = self.attackers_to(target, Bitboard::new(occupancy)).value;
+            let own_attackers = attackers & self.get_occupancy(color).value;
+            let Some((piece, square)) = (0..NUM_PIECES).map(Piece::from_index)
+                .map(|piece| (piece, self.piece_bb(color, piece).value & own_attackers))
+                .find(|(_piece, bitboard)| *bitboard != 0)
+                .map(|(piece, bitboard)| (piece, bitboard.trailing_zeros())) else {
+                break;
+            };
+
+            // the king may only recapture if the square is not defended anymore
+            if piece == King && attackers & self.get_occupancy(color.other()).value & !(1 << square) != 0 {
+                break;
+            }
+
+            gains.push(next_victim - gains[gains.len() - 1]);
+            next_victim = value(piece);
+            occupancy &= !(1 << square);
+            color = color.other();
+        }
+
+        // going back from the last capture, each side only recaptures if that gains more than stopping
+        for index in (1..gains.len()).rev() {
+            gains[index - 1] = -(-gains[index - 1]).max(gains[index]);
+        }
+        gains[0]
+    }
+
+    /// Returns a bitboard with all enemy pieces that give check to the king of the color to move.
+    pub fn checkers(&self) -> Bitboard {
+        let king_square = self.piece_bb(self.color_to_move, King).get_active_bits()[0];
+        let attackers = self.attackers_to(king_square, self.get_occupancies());
+        Bitboard::new(attackers.value & self.get_occupancy(self.color_to_move.other()).value)
+    }
+
+    /// Returns a bitboard with all pieces of the given color that are pinned to their king by an enemy slider,
+    /// meaning they can only move along the line between the king and the pinning piece.
+    pub fn pinned(&self, color: Color) -> Bitboard {
+        let lookup = &LOOKUP_TABLE;
+        let king_square = self.piece_bb(color, King).get_active_bits()[0];
+        let enemy_occupancy = self.get_occupancy(color.other());
+        let occupancies = self.get_occupancies();
+        let friendly_occupancy = self.get_occupancy(color);
+
+        // enemy sliders that would attack the king if the friendly pieces were removed from the board
+        let queens = self.piece_bb(color.other(), Piece::Queen).value;
+        let diagonal_snipers = lookup.get_bishop_attacks(king_square, enemy_occupancy).value & (self.piece_bb(color.other(), Piece::Bishop).value | queens);
+        let straight_snipers = lookup.get_rook_attacks(king_square, enemy_occupancy).value & (self.piece_bb(color.other(), Piece::Rook).value | queens);
+
+        let mut pinned = Bitboard::new(0);
+        for sniper_square in Bitboard::new(diagonal_snipers | straight_snipers).get_active_bits() {
+            // a single friendly piece between the king and the sniper is pinned
+            let blockers = Bitboard::new(lookup.get_between(king_square, sniper_square).value & occupancies.value);
+            if blockers.get_num_active_bits() == 1 {
+                pinned.value |= blockers.value & friendly_occupancy.value;
+            }
+        }
+        pinned
+    }
+
     /// Returns whether the position is legal.
     /// Specifically, it validates that:
     /// - both sides have exactly 1 king
     /// - the side whose turn it not is, is not in check
     pub fn is_legal(&self) -> bool {
-        self.pieces[Color::White.to_index() as usize][King.to_index() as usize].get_active_bits().len() == 1 &&
-            self.pieces[Color::Black.to_index() as usize][King.to_index() as usize].get_active_bits().len() == 1 &&
+        self.piece_bb(Color::White, King).get_active_bits().len() == 1 &&
+            self.piece_bb(Color::Black, King).get_active_bits().len() == 1 &&
             !self.is_in_check(self.color_to_move.other())
     }
 
-    /// Returns a new position that reflects the board state where the given move (ply) has been played.
-    pub fn make_move(&self, ply: Ply) -> Position {
-        let mut position = *self;
+    /// Returns the type of the piece the given ply captures in this position, or None if the ply is not a capture.
+    /// For en passant captures, the captured pawn is not located on the target square of the ply.
+    pub fn get_captured_piece(&self, ply: Ply) -> Option<Piece> {
+        if !ply.is_capture() {
+            return None;
+        }
+        if ply.piece() == Piece::Pawn && self.en_passant == Some(ply.target()) {
+            return Some(Piece::Pawn);
+        }
+        self.get_piece(ply.target()).map(|(piece, _color)| piece)
+    }
 
-        // -----------------------------------------------------------------------------------------------------------------------
-        // remove piece from old position
-        // -----------------------------------------------------------------------------------------------------------------------
-        position.remove_piece(ply.piece, self.color_to_move, ply.source);
-        // update hash
-        position.hash ^= zobrist::random::get_random_piece(ply.piece, self.color_to_move, ply.source);
+    /// Returns whether the given ply is pseudo-legal in this position, meaning that it follows the movement rules
+    /// of the moving piece, but may leave the own king in check.
+    ///
+    /// This is much cheaper than generating all moves, and allows validating moves from untrusted sources,
+    /// such as moves sent by the GUI or moves stored in the transposition table.
+    /// Castling moves are only pseudo-legal if the king does not castle out of, through or into check.
+    pub fn is_pseudo_legal(&self, ply: Ply) -> bool {
+        let color = self.color_to_move;
+        let source = ply.source();
+        let target = ply.target();
+
+        // the moving piece must belong to the color to move
+        if !self.piece_bb(color, ply.piece()).get_bit(source) {
+            return false;
+        }
 
-        // -----------------------------------------------------------------------------------------------------------------------
-        // remove capture piece
-        // -----------------------------------------------------------------------------------------------------------------------
-        if let Some(piece) = ply.captured_piece {
-            position.remove_piece(piece, self.color_to_move.other(), ply.target);
-            // update hash
-            position.hash ^= zobrist::random::get_random_piece(piece, self.color_to_move.other(), ply.target);
-
-            // if the captured piece was a rook on its starting square, remove castling rights of opponent for the rooks side (queenside or kingside)
-            if ply.captured_piece == Some(Piece::Rook) && ply.target.get_rank() == position.color_to_move.other().back_rank() {
-                match ply.target.get_file() {
-                    File::A => {
-                        // remove queenside castling rights
-                        if self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::KingSide || self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::Both {
-                            position.castling_rights[self.color_to_move.other().to_index() as usize] = CastlingRights::KingSide;
-                        } else {
-                            position.castling_rights[self.color_to_move.other().to_index() as usize] = CastlingRights::NoRights;
-                        }
-                        // update hash
-                        if self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::QueenSide || self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::Both {
-                            position.hash ^= zobrist::random::get_random_castling(CastlingRights::QueenSide, self.color_to_move.other());
-                        }
-                    }
-                    File::H => {
-                        // remove kingside castling rights
-                        if self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::QueenSide || self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::Both {
-                            position.castling_rights[self.color_to_move.other().to_index() as usize] = CastlingRights::QueenSide;
-                        } else {
-                            position.castling_rights[self.color_to_move.other().to_index() as usize] = CastlingRights::NoRights;
-                        }
-                        // update hash
-                        if self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::KingSide || self.castling_rights[self.color_to_move.other().to_index() as usize] == CastlingRights::Both {
-                            position.hash ^= zobrist::random::get_random_castling(CastlingRights::KingSide, self.color_to_move.other());
-                        }
-                    }
-                    _other => {}
+        // the target square must not be occupied by a friendly piece or the enemy king
+        if self.get_occupancy(color).get_bit(target) || self.piece_bb(color.other(), King).get_bit(target) {
+            return false;
+        }
+
+        // the capture flag must match the target square
+        let is_en_passant = ply.piece() == Piece::Pawn && self.en_passant == Some(target);
+        if ply.is_capture() != (self.get_occupancy(color.other()).get_bit(target) || is_en_passant) {
+            return false;
+        }
+
+        // only pawns that reach the promotion rank promote, and they always do
+        let is_promotion_move = ply.piece() == Piece::Pawn && target.get_rank() == color.promotion_rank();
+        match ply.promotion_piece() {
+            None if is_promotion_move => return false,
+            Some(_) if !is_promotion_move => return false,
+            Some(Piece::Pawn) | Some(Piece::King) => return false,
+            _other => {}
+        }
+
+        let lookup = &LOOKUP_TABLE;
+        let occupancies = self.get_occupancies();
+        match ply.piece() {
+            Piece::Pawn => {
+                if ply.is_capture() {
+                    return lookup.get_pawn_attacks(source, color).get_bit(target);
                 }
+                let Some(single_push_target) = (match color {
+                    Color::White => source.try_up(),
+                    Color::Black => source.try_down(),
+                }) else {
+                    return false;
+                };
+                if target == single_push_target {
+                    return true;
+                }
+                let double_push_target = match color {
+                    Color::White => single_push_target.try_up(),
+                    Color::Black => single_push_target.try_down(),
+                };
+                source.get_rank() == color.pawn_rank() && Some(target) == double_push_target && !occupancies.get_bit(single_push_target)
             }
+            Piece::Knight => lookup.get_knight_attacks(source).get_bit(target),
+            Piece::Bishop => lookup.get_bishop_attacks(source, occupancies).get_bit(target),
+            Piece::Rook => lookup.get_rook_attacks(source, occupancies).get_bit(target),
+            Piece::Queen => lookup.get_queen_attacks(source, occupancies).get_bit(target),
+            Piece::King => lookup.get_king_attacks(source).get_bit(target) || self.is_castling_pseudo_legal(ply),
         }
+    }
 
-        // -----------------------------------------------------------------------------------------------------------------------
-        // set piece on new position
-        // -----------------------------------------------------------------------------------------------------------------------
-        match ply.promotion_piece {
-            // move is a promotion - set promotion piece
-            Some(piece) => {
-                position.set_piece(piece, self.color_to_move, ply.target);
-                // update hash
-                position.hash ^= zobrist::random::get_random_piece(piece, self.color_to_move, ply.target);
+    /// Returns whether the given king move is a castling move that is possible in this position.
+    fn is_castling_pseudo_legal(&self, ply: Ply) -> bool {
+        let color = self.color_to_move;
+        let rank = color.back_rank();
+        if ply.source() != Square::from_file_rank(File::E, rank) || ply.is_capture() {
+            return false;
+        }
+
+        // the files the rook starts on, the squares between king and rook, and the squares the king passes
+        let (rook_file, empty_files, safe_files) = if ply.target() == Square::from_file_rank(File::G, rank) {
+            if !self.castling_rights.allows(color, CastlingSide::KingSide) {
+                return false;
             }
-            // move is not a promotion - set piece specified in ply
-            None => {
-                position.set_piece(ply.piece, self.color_to_move, ply.target);
-                // update hash
-                position.hash ^= zobrist::random::get_random_piece(ply.piece, self.color_to_move, ply.target);
+            (File::H, [File::F, File::G].as_slice(), [File::E, File::F, File::G])
+        } else if ply.target() == Square::from_file_rank(File::C, rank) {
+            if !self.castling_rights.allows(color, CastlingSide::QueenSide) {
+                return false;
+            }
+            (File::A, [File::B, File::C, File::D].as_slice(), [File::E, File::D, File::C])
+        } else {
+            return false;
+        };
+
+        let occupancies = self.get_occupancies();
+        let attack_bb = self.get_attack_bb(color.other());
+        self.piece_bb(color, Piece::Rook).get_bit(Square::from_file_rank(rook_file, rank))
+            && empty_files.iter().all(|file| !occupancies.get_bit(Square::from_file_rank(*file, rank)))
+            && safe_files.iter().all(|file| !attack_bb.get_bit(Square::from_file_rank(*file, rank)))
+    }
+
+    /// Returns whether the given ply is legal in this position, meaning that it is pseudo-legal and does not leave the own king in check.
+    pub fn is_legal_move(&self, ply: Ply) -> bool {
+        self.is_pseudo_legal(ply) && self.make_move(ply).is_legal()
+    }
+
+    /// Returns a new position that reflects the board state where the given move (ply) has been played.
+    pub fn make_move(&self, ply: Ply) -> Position {
+        self.make_move_with_dirty_pieces(ply).0
+    }
+
+    /// Returns the pieces that are moved, added to or removed from the board when the given ply is played in this position.
+    pub fn get_dirty_pieces(&self, ply: Ply) -> DirtyPieces {
+        let mut dirty_pieces = DirtyPieces::default();
+
+        // the moving piece - in case of a promotion, the pawn is removed and the promotion piece is added instead
+        match ply.promotion_piece() {
+            None => dirty_pieces.push(ply.piece(), self.color_to_move, Some(ply.source()), Some(ply.target())),
+            Some(promotion_piece) => {
+                dirty_pieces.push(Piece::Pawn, self.color_to_move, Some(ply.source()), None);
+                dirty_pieces.push(promotion_piece, self.color_to_move, None, Some(ply.target()));
             }
         }
 
-        // -----------------------------------------------------------------------------------------------------------------------
-        // in case of castling, set the rook
-        // -----------------------------------------------------------------------------------------------------------------------
-        let mut rook_source: Option<Square> = None;
-        let mut rook_target: Option<Square> = None;
-        if ply.piece == Piece::King {
-            match (ply.source, ply.target) {
+        // the captured piece - in case of en passant, the captured pawn is not located on the target square
+        if let Some(captured_piece) = self.get_captured_piece(ply) {
+            let square = match ply.piece() == Piece::Pawn && self.en_passant == Some(ply.target()) {
+                true => Square::from_file_rank(ply.target().get_file(), self.color_to_move.other().double_pawn_push_target_rank()),
+                false => ply.target(),
+            };
+            dirty_pieces.push(captured_piece, self.color_to_move.other(), Some(square), None);
+        }
+
+        // in case of castling, the rook moves as well
+        if ply.piece() == King {
+            let rook_squares = match (ply.source(), ply.target()) {
                 // black castles queenside
-                (square::E8, square::C8) => {
-                    // remove rook from old position
-                    position.remove_piece(Piece::Rook, self.color_to_move, square::A8);
-                    rook_source = Some(square::A8);
-                    // set rook on new position
-                    position.set_piece(Piece::Rook, self.color_to_move, square::D8);
-                    rook_target = Some(square::D8);
-                }
+                (square::E8, square::C8) => Some((square::A8, square::D8)),
                 // black castles kingside
-                (square::E8, square::G8) => {
-                    // remove rook from old position
-                    position.remove_piece(Piece::Rook, self.color_to_move, square::H8);
-                    rook_source = Some(square::H8);
-                    // set rook on new position
-                    position.set_piece(Piece::Rook, self.color_to_move, square::F8);
-                    rook_target = Some(square::F8);
-                }
+                (square::E8, square::G8) => Some((square::H8, square::F8)),
                 // white castles queenside
-                (square::E1, square::C1) => {
-                    // remove rook from old position
-                    position.remove_piece(Piece::Rook, self.color_to_move, square::A1);
-                    rook_source = Some(square::A1);
-                    // set rook on new position
-                    position.set_piece(Piece::Rook, self.color_to_move, square::D1);
-                    rook_target = Some(square::D1);
-                }
+                (square::E1, square::C1) => Some((square::A1, square::D1)),
                 // white castles kingside
-                (square::E1, square::G1) => {
-                    // remove rook from old position
-                    position.remove_piece(Piece::Rook, self.color_to_move, square::H1);
-                    rook_source = Some(square::H1);
-                    // set rook on new position
-                    position.set_piece(Piece::Rook, self.color_to_move, square::F1);
-                    rook_target = Some(square::F1);
-                }
-                _other => {}
-            }
-            if let Some(square) = rook_source {
-                // update hash
-                position.hash ^= zobrist::random::get_random_piece(Piece::Rook, self.color_to_move, square);
-            }
-            if let Some(square) = rook_target {
-                // update hash
-                position.hash ^= zobrist::random::get_random_piece(Piece::Rook, self.color_to_move, square);
+                (square::E1, square::G1) => Some((square::H1, square::F1)),
+                _other => None,
+            };
+            if let Some((rook_source, rook_target)) = rook_squares {
+                dirty_pieces.push(Piece::Rook, self.color_to_move, Some(rook_source), Some(rook_target));
             }
         }
 
+        dirty_pieces
+    }
+
+    /// Returns a new position that reflects the board state where the given move (ply) has been played,
+    /// together with the record of the pieces that changed.
+    pub fn make_move_with_dirty_pieces(&self, ply: Ply) -> (Position, DirtyPieces) {
+        let mut position = *self;
+
         // -----------------------------------------------------------------------------------------------------------------------
-        // in case of en passant, remove opponent pawn from 4th or 5th rank
+        // move, remove and add the pieces
         // -----------------------------------------------------------------------------------------------------------------------
-        if let Some(square) = self.en_passant {
-            if ply.piece == Piece::Pawn && square == ply.target {
-                position.remove_piece(Piece::Pawn, self.color_to_move.other(), Square::from_file_rank(ply.target.get_file(), self.color_to_move.other().double_pawn_push_target_rank()));
+        let dirty_pieces = self.get_dirty_pieces(ply);
+        for dirty_piece in dirty_pieces.iter() {
+            if let Some(square) = dirty_piece.from {
+                position.remove_piece(dirty_piece.piece, dirty_piece.color, square);
+                // update hash
+                position.hash ^= zobrist::random::get_random_piece(dirty_piece.piece, dirty_piece.color, square);
+            }
+            if let Some(square) = dirty_piece.to {
+                position.set_piece(dirty_piece.piece, dirty_piece.color, square);
                 // update hash
-                position.hash ^= zobrist::random::get_random_piece(Piece::Pawn, self.color_to_move.other(), Square::from_file_rank(ply.target.get_file(), self.color_to_move.other().double_pawn_push_target_rank()));
-                // move is en passant - restore wrongly removed pawn hash (on en passant target square)
-                position.hash ^= zobrist::random::get_random_piece(Piece::Pawn, self.color_to_move.other(), ply.target);
+                position.hash ^= zobrist::random::get_random_piece(dirty_piece.piece, dirty_piece.color, square);
             }
         }
 
         // -----------------------------------------------------------------------------------------------------------------------
-        // update castling_rights
+        // update castling rights
         // -----------------------------------------------------------------------------------------------------------------------
-        if ply.piece == King {
-            // move is a king move - no rights
-            position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::NoRights;
-            // update hash
-            position.hash ^= zobrist::random::get_random_castling(self.castling_rights[self.color_to_move.to_index() as usize], self.color_to_move);
-        } else if ply.piece == Piece::Rook && ply.source == Square::from_file_rank(File::A, self.color_to_move.back_rank()) {
-            // move is A file rook move - remove queenside rights
-            match self.castling_rights[self.color_to_move.to_index() as usize] {
-                CastlingRights::Both => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::KingSide,
-                CastlingRights::KingSide => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::KingSide,
-                _other => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::NoRights,
+        let mut castling_rights = self.castling_rights;
+        // if the captured piece was a rook on its starting square, the opponent can no longer castle to the side of the rook
+        if self.get_captured_piece(ply) == Some(Piece::Rook) && ply.target().get_rank() == self.color_to_move.other().back_rank() {
+            if let Some(side) = CastlingSide::from_rook_file(ply.target().get_file()) {
+                castling_rights.remove(self.color_to_move.other(), side);
             }
-            // update hash
-            if self.castling_rights[self.color_to_move as usize] == CastlingRights::QueenSide || self.castling_rights[self.color_to_move as usize] == CastlingRights::Both {
-                position.hash ^= zobrist::random::get_random_castling(CastlingRights::QueenSide, self.color_to_move);
-            }
-        } else if ply.piece == Piece::Rook && ply.source == Square::from_file_rank(File::H, self.color_to_move.back_rank()) {
-            // move is H file rook move - remove kingside rights
-            match self.castling_rights[self.color_to_move.to_index() as usize] {
-                CastlingRights::Both => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::QueenSide,
-                CastlingRights::QueenSide => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::QueenSide,
-                _other => position.castling_rights[self.color_to_move.to_index() as usize] = CastlingRights::NoRights,
+        }
+        if ply.piece() == King {
+            // move is a king move - no rights
+            castling_rights.remove_all(self.color_to_move);
+        } else if ply.piece() == Piece::Rook && ply.source().get_rank() == self.color_to_move.back_rank() {
+            // move is a rook move from its starting square - remove the rights for the side of the rook
+            if let Some(side) = CastlingSide::from_rook_file(ply.source().get_file()) {
+                castling_rights.remove(self.color_to_move, side);
             }
+        }
+        if castling_rights != self.castling_rights {
+            position.castling_rights = castling_rights;
             // update hash
-            if self.castling_rights[self.color_to_move as usize] == CastlingRights::KingSide || self.castling_rights[self.color_to_move as usize] == CastlingRights::Both {
-                position.hash ^= zobrist::random::get_random_castling(CastlingRights::KingSide, self.color_to_move);
-            }
+            position.hash ^= zobrist::random::get_random_castle(self.castling_rights) ^ zobrist::random::get_random_castle(castling_rights);
         }
 
         // -----------------------------------------------------------------------------------------------------------------------
@@ -369,11 +607,11 @@ impl Position {
             // if the previous position had an en passant square set, remove it from the hash
             position.hash ^= zobrist::random::get_random_en_passant(Some(square.get_file()));
         }
-        if ply.piece == Piece::Pawn && ply.source.get_rank() == self.color_to_move.pawn_rank() &&
-            ply.target.get_rank() == self.color_to_move.double_pawn_push_target_rank() {
-            position.en_passant = Some(Square::from_file_rank(ply.source.get_file(), self.color_to_move.other().en_passant_target_rank()));
+        if ply.piece() == Piece::Pawn && ply.source().get_rank() == self.color_to_move.pawn_rank() &&
+            ply.target().get_rank() == self.color_to_move.double_pawn_push_target_rank() {
+            position.en_passant = Some(Square::from_file_rank(ply.source().get_file(), self.color_to_move.other().en_passant_target_rank()));
             // update hash
-            position.hash ^= zobrist::random::get_random_en_passant(Some(ply.source.get_file()));
+            position.hash ^= zobrist::random::get_random_en_passant(Some(ply.source().get_file()));
         } else {
             position.en_passant = None;
         }
@@ -388,7 +626,7 @@ impl Position {
         // initialize the attack bitboards for the new position
         position.initialize_attack_bb();
 
-        position
+        (position, dirty_pieces)
     }
 
     /// Initializes the attack bitboards for both colors.
@@ -408,65 +646,144 @@ impl Position {
 
     /// Returns the number of pieces of the given type and color.
     pub fn get_num_pieces(&self, piece: Piece, color: Color) -> u8 {
-        self.pieces[color.to_index() as usize][piece.to_index() as usize].get_num_active_bits()
+        self.piece_bb(color, piece).get_num_active_bits()
+    }
+
+    /// Returns the game phase from the remaining material, on a scale from 0 (endgame) to `MAX_GAME_PHASE` (middlegame).
+    /// Knights and bishops count 1, rooks 2 and queens 4. Promotions can't raise the game phase above the maximum.
+    pub fn game_phase(&self) -> u8 {
+        let phase: u8 = self.pieces_iter().map(|(_square, piece, _color)| GAME_PHASE_WEIGHTS[piece.to_index() as usize]).sum();
+        phase.min(MAX_GAME_PHASE)
     }
 }
 
-/// Prints the position with '.' marking empty squares, capital letters marking white pieces,
-/// and lower case letter marking black pieces.
-/// It also prints the color to move, castling rights, and en passant target square.
-impl Display for Position {
-    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+/// A lazy iterator over the pieces of a position, created by `Position::pieces_iter` and `Position::color_pieces_iter`.
+pub struct PiecesIter {
+    /// The piece bitboards of the position.
+    pieces: [[Bitboard; 6]; 2],
+    /// The index of the color currently being iterated.
+    color_index: u8,
+    /// The index of the last color to iterate.
+    last_color_index: u8,
+    /// The index of the piece currently being iterated.
+    piece_index: u8,
+    /// The squares of the current bitboard that have not been returned yet.
+    remaining: u64,
+}
+
+impl PiecesIter {
+    /// Constructs an iterator over the pieces of all colors from `first_color` to `last_color`.
+    fn new(pieces: [[Bitboard; 6]; 2], first_color: Color, last_color: Color) -> Self {
+        Self {
+            pieces,
+            color_index: first_color.to_index(),
+            last_color_index: last_color.to_index(),
+            piece_index: 0,
+            remaining: pieces[first_color.to_index() as usize][0].value,
+        }
+    }
+}
+
+impl Iterator for PiecesIter {
+    type Item = (Square, Piece, Color);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.remaining != 0 {
+                let square = Square::new(self.remaining.trailing_zeros() as u8);
+                self.remaining &= self.remaining - 1;
+                return Some((square, Piece::from_index(self.piece_index), Color::from_index(self.color_index)));
+            }
+
+            // the current bitboard is exhausted - move on to the next one
+            self.piece_index += 1;
+            if self.piece_index == NUM_PIECES {
+                self.piece_index = 0;
+                self.color_index += 1;
+            }
+            if self.color_index > self.last_color_index {
+                return None;
+            }
+            self.remaining = self.pieces[self.color_index as usize][self.piece_index as usize].value;
+        }
+    }
+}
+
+impl Position {
+    /// Returns the board as text, with '.' marking empty squares, followed by the color to move,
+    /// castling rights, and en passant target square.
+    ///
+    /// The pieces are either shown with their FEN letters or with their Unicode symbols.
+    /// If the board is flipped, it is shown from Black's point of view.
+    pub fn render(&self, unicode: bool, flipped: bool) -> String {
         let mut output: String = String::from("");
-        for rank in (0..NUM_RANKS).rev() {
+        let mailbox = self.get_mailbox();
+        let ranks: Vec<u8> = match flipped {
+            false => (0..NUM_RANKS).rev().collect(),
+            true => (0..NUM_RANKS).collect(),
+        };
+        let files: Vec<u8> = match flipped {
+            false => (0..NUM_FILES).collect(),
+            true => (0..NUM_FILES).rev().collect(),
+        };
+        for rank in ranks {
             output += format!("{}  ", rank + 1).as_str();
-            for file in 0..NUM_FILES {
-                let piece = self.get_piece(Square::from_file_rank(File::from_index(file), Rank::from_index(rank)));
-                if piece.is_none() {
-                    output += ".  ";
-                } else {
-                    let (piece, color) = piece.unwrap();
-                    output.push(piece.to_char(color));
-                    output += "  ";
+            for file in files.iter() {
+                let piece = mailbox[Square::from_file_rank(File::from_index(*file), Rank::from_index(rank)).index as usize];
+                match piece {
+                    None => output += ".  ",
+                    Some((piece, color)) => {
+                        output.push(match unicode {
+                            false => piece.to_char(color),
+                            true => piece.to_unicode(color),
+                        });
+                        output += "  ";
+                    }
                 }
             }
             output += "\n";
         }
-        output += "   a  b  c  d  e  f  g  h\n";
+        let file_names: Vec<String> = files.iter().map(|file| File::from_index(*file).to_string()).collect();
+        output += format!("   {}\n", file_names.join("  ")).as_str();
         output += format!("\nMove: {}", self.color_to_move).as_str();
-        output += format!("\nCastling: {} - {}", self.castling_rights[0], self.castling_rights[1]).as_str();
+        output += format!("\nCastling: {}", self.castling_rights).as_str();
         match self.en_passant {
             None => output += "\nEn Passant: None\n",
             Some(square) => output += format!("\nEn Passant: {square}\n").as_str(),
         }
-        write!(f, "{}", output)
+        output
+    }
+}
+
+/// Prints the position with '.' marking empty squares, capital letters marking white pieces,
+/// and lower case letter marking black pieces.
+/// It also prints the color to move, castling rights, and en passant target square.
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.render(false, false))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::board::bitboard::Bitboard;
-    use crate::board::castling_rights::CastlingRights::NoRights;
+    use crate::board::castling_rights::CastlingRights;
     use crate::board::color::Color::{Black, White};
     use crate::board::{Board, square};
     use crate::board::color::Color;
+    use crate::board::dirty_pieces::DirtyPiece;
     use crate::board::piece::Piece;
     use crate::board::piece::Piece::{Bishop, King, Knight, Pawn, Queen, Rook};
-    use crate::board::position::Position;
-    use crate::board::square::{A1, A3, E1, E4, F2, F3, G3, H7, H8};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
+    use crate::board::position::{MAX_GAME_PHASE, Position};
+    use crate::board::square::{A1, A3, E1, E4, F2, F3, G3, H7, H8, Square};
+    use crate::move_gen;
     use crate::move_gen::ply::Ply;
 
     #[test]
     fn default_returns_position_with_default_values() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let position = Position::default();
         assert_eq!([[Bitboard::new(0); 6]; 2], position.pieces);
-        assert_eq!([NoRights; 2], position.castling_rights);
+        assert_eq!(CastlingRights::NONE, position.castling_rights);
         assert_eq!(None, position.en_passant);
         assert_eq!(White, position.color_to_move);
     }
@@ -483,39 +800,35 @@ mod tests {
         let mut position = Position::default();
 
         position.set_piece(Knight, White, E4);
-        assert!(position.pieces[White.to_index() as usize][Knight.to_index() as usize].get_bit(E4));
-        assert!(!position.pieces[White.to_index() as usize][Knight.to_index() as usize].get_bit(H7));
+        assert!(position.piece_bb(White, Knight).get_bit(E4));
+        assert!(!position.piece_bb(White, Knight).get_bit(H7));
 
         position.set_piece(Knight, White, H7);
-        assert!(position.pieces[White.to_index() as usize][Knight.to_index() as usize].get_bit(H7));
-        assert!(!position.pieces[Black.to_index() as usize][Knight.to_index() as usize].get_bit(H7));
-        assert!(!position.pieces[White.to_index() as usize][Queen.to_index() as usize].get_bit(H7));
+        assert!(position.piece_bb(White, Knight).get_bit(H7));
+        assert!(!position.piece_bb(Black, Knight).get_bit(H7));
+        assert!(!position.piece_bb(White, Queen).get_bit(H7));
 
         position.set_piece(Knight, Black, H7);
-        assert!(position.pieces[Black.to_index() as usize][Knight.to_index() as usize].get_bit(H7));
+        assert!(position.piece_bb(Black, Knight).get_bit(H7));
 
         let position_before = Position::default();
         let mut position_after = position_before;
         position_after.set_piece(Bishop, Black, G3);
 
         // test that black's knight bitboard changed
-        assert_ne!(position_before.pieces[Black.to_index() as usize][Bishop.to_index() as usize], position_after.pieces[Black.to_index() as usize][Bishop.to_index() as usize]);
+        assert_ne!(position_before.piece_bb(Black, Bishop), position_after.piece_bb(Black, Bishop));
 
         // test that other bitboards are still the same
         assert_eq!(position_before.pieces[White.to_index() as usize], position_after.pieces[White.to_index() as usize]);
-        assert_eq!(position_before.pieces[Black.to_index() as usize][Pawn.to_index() as usize], position_after.pieces[Black.to_index() as usize][Pawn.to_index() as usize]);
-        assert_eq!(position_before.pieces[Black.to_index() as usize][Knight.to_index() as usize], position_after.pieces[Black.to_index() as usize][Knight.to_index() as usize]);
-        assert_eq!(position_before.pieces[Black.to_index() as usize][Rook.to_index() as usize], position_after.pieces[Black.to_index() as usize][Rook.to_index() as usize]);
-        assert_eq!(position_before.pieces[Black.to_index() as usize][Queen.to_index() as usize], position_after.pieces[Black.to_index() as usize][Queen.to_index() as usize]);
-        assert_eq!(position_before.pieces[Black.to_index() as usize][King.to_index() as usize], position_after.pieces[Black.to_index() as usize][King.to_index() as usize]);
+        assert_eq!(position_before.piece_bb(Black, Pawn), position_after.piece_bb(Black, Pawn));
+        assert_eq!(position_before.piece_bb(Black, Knight), position_after.piece_bb(Black, Knight));
+        assert_eq!(position_before.piece_bb(Black, Rook), position_after.piece_bb(Black, Rook));
+        assert_eq!(position_before.piece_bb(Black, Queen), position_after.piece_bb(Black, Queen));
+        assert_eq!(position_before.piece_bb(Black, King), position_after.piece_bb(Black, King));
     }
 
     #[test]
     fn test_remove_piece() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let mut position = Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         position.remove_piece(Piece::Pawn, Color::Black, square::E2);
         assert_eq!(position, Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position);
@@ -534,11 +847,19 @@ mod tests {
     }
 
     #[test]
-    fn get_piece_returns_piece_on_specified_square() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn test_piece_bb() {
+        let mut position = Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(0xff00, position.piece_bb(Color::White, Piece::Pawn).value);
+        assert_eq!(0x4200000000000000, position.piece_bb(Color::Black, Piece::Knight).value);
+        assert_eq!(0x10, position.piece_bb(Color::White, Piece::King).value);
 
+        position.set_piece(Piece::Queen, Color::Black, square::E4);
+        assert_eq!(0x800000010000000, position.piece_bb(Color::Black, Piece::Queen).value);
+        assert_eq!(0x8, position.piece_bb(Color::White, Piece::Queen).value);
+    }
+
+    #[test]
+    fn get_piece_returns_piece_on_specified_square() {
         let mut position = Position::default();
         position.set_piece(Knight, Black, E4);
         position.set_piece(King, White, H8);
@@ -556,11 +877,30 @@ mod tests {
     }
 
     #[test]
-    fn get_occupancy_returns_occupancy_bb() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn test_pieces_iter() {
+        let position = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w Q - 0 1").unwrap().position;
+        let pieces: Vec<(Square, Piece, Color)> = position.pieces_iter().collect();
+        assert_eq!(vec![(square::E2, Pawn, White), (A1, Rook, White), (E1, King, White), (square::E8, King, Black)], pieces);
+
+        let pieces: Vec<(Square, Piece, Color)> = position.color_pieces_iter(Black).collect();
+        assert_eq!(vec![(square::E8, King, Black)], pieces);
+
+        assert_eq!(0, Position::default().pieces_iter().count());
+        assert_eq!(32, Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position.pieces_iter().count());
+    }
 
+    #[test]
+    fn test_get_mailbox() {
+        let position = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w Q - 0 1").unwrap().position;
+        let mailbox = position.get_mailbox();
+        assert_eq!(Some((Rook, White)), mailbox[A1.index as usize]);
+        assert_eq!(Some((King, Black)), mailbox[square::E8.index as usize]);
+        assert_eq!(None, mailbox[H8.index as usize]);
+        assert_eq!(3, mailbox.iter().filter(|piece| piece.is_some_and(|(_, color)| color == White)).count());
+    }
+
+    #[test]
+    fn get_occupancy_returns_occupancy_bb() {
         // position 1 (starting position)
         let position = Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(0xffff, position.get_occupancy(White).value);
@@ -579,10 +919,6 @@ mod tests {
 
     #[test]
     fn get_occupancies_returns_occupancy_bb_for_both_colors() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
         let position = Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(0xffff00000000ffff, position.get_occupancies().value);
@@ -598,25 +934,29 @@ mod tests {
 
     #[test]
     fn position_formats_correctly() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let position = Board::parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        let expected_output = "8  r  n  b  q  k  b  n  r  \n7  p  p  p  p  p  p  p  p  \n6  .  .  .  .  .  .  .  .  \n5  .  .  .  .  .  .  .  .  \n4  .  .  .  .  .  .  .  .  \n3  .  .  .  .  .  .  .  .  \n2  P  P  P  P  P  P  P  P  \n1  R  N  B  Q  K  B  N  R  \n   a  b  c  d  e  f  g  h\n\nMove: White\nCastling: Both - Both\nEn Passant: None\n";
+        let expected_output = "8  r  n  b  q  k  b  n  r  \n7  p  p  p  p  p  p  p  p  \n6  .  .  .  .  .  .  .  .  \n5  .  .  .  .  .  .  .  .  \n4  .  .  .  .  .  .  .  .  \n3  .  .  .  .  .  .  .  .  \n2  P  P  P  P  P  P  P  P  \n1  R  N  B  Q  K  B  N  R  \n   a  b  c  d  e  f  g  h\n\nMove: White\nCastling: KQkq\nEn Passant: None\n";
         assert_eq!(expected_output, format!("{}", position));
 
         let position = Board::parse_fen("r1bq1rk1/1pp1bppp/p1n2n2/4p3/2PpP1P1/P2P1Q1P/1P1N1P2/R1B1KBNR b KQ g3 0 9").unwrap().position;
-        let expected_output = "8  r  .  b  q  .  r  k  .  \n7  .  p  p  .  b  p  p  p  \n6  p  .  n  .  .  n  .  .  \n5  .  .  .  .  p  .  .  .  \n4  .  .  P  p  P  .  P  .  \n3  P  .  .  P  .  Q  .  P  \n2  .  P  .  N  .  P  .  .  \n1  R  .  B  .  K  B  N  R  \n   a  b  c  d  e  f  g  h\n\nMove: Black\nCastling: Both - NoRights\nEn Passant: g3\n";
+        let expected_output = "8  r  .  b  q  .  r  k  .  \n7  .  p  p  .  b  p  p  p  \n6  p  .  n  .  .  n  .  .  \n5  .  .  .  .  p  .  .  .  \n4  .  .  P  p  P  .  P  .  \n3  P  .  .  P  .  Q  .  P  \n2  .  P  .  N  .  P  .  .  \n1  R  .  B  .  K  B  N  R  \n   a  b  c  d  e  f  g  h\n\nMove: Black\nCastling: KQ\nEn Passant: g3\n";
         assert_eq!(expected_output, format!("{}", position));
     }
 
     #[test]
-    fn test_get_attack_bb() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn position_renders_correctly() {
+        let position = Board::parse_fen("4k3/8/8/8/8/8/4P3/R3K3 b Q - 0 1").unwrap().position;
+        assert_eq!(format!("{}", position), position.render(false, false));
+
+        let expected_output = "8  .  .  .  .  ♚  .  .  .  \n7  .  .  .  .  .  .  .  .  \n6  .  .  .  .  .  .  .  .  \n5  .  .  .  .  .  .  .  .  \n4  .  .  .  .  .  .  .  .  \n3  .  .  .  .  .  .  .  .  \n2  .  .  .  .  ♙  .  .  .  \n1  ♖  .  .  .  ♔  .  .  .  \n   a  b  c  d  e  f  g  h\n\nMove: Black\nCastling: Q\nEn Passant: None\n";
+        assert_eq!(expected_output, position.render(true, false));
+
+        let expected_output = "1  .  .  .  K  .  .  .  R  \n2  .  .  .  P  .  .  .  .  \n3  .  .  .  .  .  .  .  .  \n4  .  .  .  .  .  .  .  .  \n5  .  .  .  .  .  .  .  .  \n6  .  .  .  .  .  .  .  .  \n7  .  .  .  .  .  .  .  .  \n8  .  .  .  k  .  .  .  .  \n   h  g  f  e  d  c  b  a\n\nMove: Black\nCastling: Q\nEn Passant: None\n";
+        assert_eq!(expected_output, position.render(false, true));
+    }
 
+    #[test]
+    fn test_get_attack_bb() {
         // -----------------------------------------------------------------------------------------
         // position 1 (starting position)
         // -----------------------------------------------------------------------------------------
@@ -644,10 +984,6 @@ mod tests {
 
     #[test]
     fn test_get_piece_attack_bb() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // -----------------------------------------------------------------------------------------
         // position 1 (starting position)
         // -----------------------------------------------------------------------------------------
@@ -708,10 +1044,6 @@ mod tests {
 
     #[test]
     fn test_is_square_attacked() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let position = Board::from_fen("5rk1/pppr1pp1/7p/3q4/3P4/P3R1PP/1P2Q1PK/8 w - - 5 33").unwrap().position;
 
         assert!(position.is_square_attacked(square::B3, White));
@@ -729,10 +1061,6 @@ mod tests {
 
     #[test]
     fn test_is_in_check() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1
         let position = Board::from_fen("5rk1/pppr1pp1/7p/3q4/3P4/P3R1PP/1P2Q1PK/8 w - - 5 33").unwrap().position;
         assert!(!position.is_in_check(Color::White));
@@ -770,11 +1098,109 @@ mod tests {
     }
 
     #[test]
-    fn is_legal_with_legal_position_returns_true() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn test_attackers_to() {
+        let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
+        let occupancies = position.get_occupancies();
+
+        // d5 is attacked by the knights on c3 and b6, the pawns on e4 and e6, the knight on f6, and the queen on f3 through e4 is blocked
+        let attackers = position.attackers_to(square::D5, occupancies);
+        assert_eq!(vec![square::C3, square::E4, square::B6, square::E6, square::F6], attackers.get_active_bits().to_vec());
+
+        // the bishop on g7 x-rays the knight on f6 towards e5 and d4
+        assert!(!position.attackers_to(square::D4, occupancies).get_bit(square::G7));
+        let mut occupancy = occupancies;
+        occupancy.pop_bit(square::F6);
+        occupancy.pop_bit(square::E5);
+        assert!(position.attackers_to(square::D4, occupancy).get_bit(square::G7));
+
+        // removed pieces don't attack
+        let mut occupancy = occupancies;
+        occupancy.pop_bit(square::C3);
+        assert!(!position.attackers_to(square::D5, occupancy).get_bit(square::C3));
+    }
+
+    #[test]
+    fn test_see() {
+        let see = |fen: &str, ply: &str| {
+            let position = Board::from_fen(fen).unwrap().position;
+            position.see(Ply::from_string(ply, position).unwrap())
+        };
+
+        // an undefended pawn, a defended pawn captured by the queen, and an even trade of knights
+        assert_eq!(100, see("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5"));
+        assert_eq!(100 - 950, see("4k3/2p5/3p4/8/8/8/8/3QK3 w - - 0 1", "d1d6"));
+        assert_eq!(0, see("4k3/8/2p5/3n4/8/4N3/8/4K3 w - - 0 1", "e3d5"));
+
+        // the rook on d1 joins the exchange once the rook on d2 has captured
+        assert_eq!(100, see("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5"));
+        assert_eq!(100 - 500, see("3rk3/8/8/3p4/8/8/3R4/4K3 w - - 0 1", "d2d5"));
 
+        // the king may only recapture if the square is not defended anymore
+        assert_eq!(320 - 500, see("3rk3/8/8/8/8/8/3N4/4K3 b - - 0 1", "d8d2"));
+        assert_eq!(320, see("3rk3/3r4/8/8/8/8/3N4/4K3 b - - 0 1", "d7d2"));
+
+        // promotions and en passant
+        assert_eq!(950 - 100, see("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"));
+        assert_eq!(100, see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"));
+    }
+
+    #[test]
+    fn test_checkers() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(Bitboard::new(0), position.checkers());
+
+        let position = Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap().position;
+        assert_eq!(Bitboard::from_square(square::H4), position.checkers());
+
+        // double check by the knight and the rook
+        let position = Board::from_fen("4r1k1/8/8/8/8/3n4/8/4K3 w - - 0 1").unwrap().position;
+        assert_eq!(vec![square::D3, square::E8], position.checkers().get_active_bits().to_vec());
+    }
+
+    #[test]
+    fn test_is_checkmate_and_is_stalemate() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert!(!position.is_checkmate());
+        assert!(!position.is_stalemate());
+
+        let position = Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap().position;
+        assert!(position.is_checkmate());
+        assert!(!position.is_stalemate());
+
+        // the king is in check, but can escape
+        let position = Board::from_fen("4k3/8/8/8/8/8/8/r3K3 w - - 0 1").unwrap().position;
+        assert!(!position.is_checkmate());
+        assert!(!position.is_stalemate());
+
+        let position = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap().position;
+        assert!(!position.is_checkmate());
+        assert!(position.is_stalemate());
+    }
+
+    #[test]
+    fn test_pinned() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(Bitboard::new(0), position.pinned(White));
+        assert_eq!(Bitboard::new(0), position.pinned(Black));
+
+        // pins along the ranks, while two pieces between king and rook are no pin
+        let position = Board::from_fen("8/8/8/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
+        assert_eq!(Bitboard::from_square(square::B5), position.pinned(White));
+        assert_eq!(Bitboard::from_square(square::F4), position.pinned(Black));
+        let position = Board::from_fen("8/8/8/KPp4r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
+        assert_eq!(Bitboard::new(0), position.pinned(White));
+
+        // diagonal and straight pins, while an enemy piece between king and slider is no pin
+        let position = Board::from_fen("6k1/8/8/b7/8/8/3Q4/r1n1K3 w - - 0 1").unwrap().position;
+        assert_eq!(Bitboard::from_square(square::D2), position.pinned(White));
+        let position = Board::from_fen("6k1/8/8/b7/8/2N5/3Q4/r2NK3 w - - 0 1").unwrap().position;
+        assert_eq!(Bitboard::from_square(square::D1), position.pinned(White));
+        let position = Board::from_fen("6k1/8/8/b7/8/8/3Q4/r2NK3 w - - 0 1").unwrap().position;
+        assert_eq!(vec![square::D1, square::D2], position.pinned(White).get_active_bits().to_vec());
+    }
+
+    #[test]
+    fn is_legal_with_legal_position_returns_true() {
         // position 1
         assert!(Board::from_fen("5rk1/pppr1pp1/7p/3q4/3P4/P3R1PP/1P2Q1PK/8 w - - 5 33").unwrap().position.is_legal());
 
@@ -796,401 +1222,298 @@ mod tests {
 
     #[test]
     fn is_legal_with_illegal_position_returns_false() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1
-        assert!(!Board::from_fen("8/8/7Q/8/6p1/5pBk/R4K2/8 w - - 0 67").unwrap().position.is_legal());
+        assert!(!Board::from_fen_unchecked("8/8/7Q/8/6p1/5pBk/R4K2/8 w - - 0 67").unwrap().position.is_legal());
 
         // position 2
-        assert!(!Board::from_fen("rn2k2r/2pq1ppp/p2b4/1p4B1/2bP4/5N2/PP3PPP/R2QR1K1 w kq - 1 14").unwrap().position.is_legal());
+        assert!(!Board::from_fen_unchecked("rn2k2r/2pq1ppp/p2b4/1p4B1/2bP4/5N2/PP3PPP/R2QR1K1 w kq - 1 14").unwrap().position.is_legal());
 
         // position 3
-        assert!(!Board::from_fen("8/ppp3kp/2b5/5P2/3P2N1/P2B4/1r3K2/8 b - - 0 28").unwrap().position.is_legal());
+        assert!(!Board::from_fen_unchecked("8/ppp3kp/2b5/5P2/3P2N1/P2B4/1r3K2/8 b - - 0 28").unwrap().position.is_legal());
 
         // position 4
-        assert!(!Board::from_fen("7Q/ppp2Qkp/2b5/4BP2/3P2N1/P2B4/1r6/8 w - - 0 28").unwrap().position.is_legal());
+        assert!(!Board::from_fen_unchecked("7Q/ppp2Qkp/2b5/4BP2/3P2N1/P2B4/1r6/8 w - - 0 28").unwrap().position.is_legal());
 
         // position 5
-        assert!(!Board::from_fen("7Q/ppp2Qkp/2b5/4BP2/3P2N1/P2B4/1r6/8 b - - 0 28").unwrap().position.is_legal());
+        assert!(!Board::from_fen_unchecked("7Q/ppp2Qkp/2b5/4BP2/3P2N1/P2B4/1r6/8 b - - 0 28").unwrap().position.is_legal());
 
         // position 6
-        assert!(!Board::from_fen("2kR3r/pp5p/5p1b/2p5/8/4N3/PqP1NPPP/5RK1 w - - 2 19").unwrap().position.is_legal());
+        assert!(!Board::from_fen_unchecked("2kR3r/pp5p/5p1b/2p5/8/4N3/PqP1NPPP/5RK1 w - - 2 19").unwrap().position.is_legal());
 
         // position 7
-        assert!(!Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2PP2/P2P2PP/RN1QKB1R b KQkq - 1 8").unwrap().position.is_legal());
+        assert!(!Board::from_fen_unchecked("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2PP2/P2P2PP/RN1QKB1R b KQkq - 1 8").unwrap().position.is_legal());
     }
 
     #[test]
-    fn test_make_move() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn test_is_pseudo_legal() {
+        let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
+        // quiet moves, captures and castling
+        assert!(position.is_pseudo_legal(Ply::new(square::A2, square::A4, Pawn, None, false)));
+        assert!(position.is_pseudo_legal(Ply::new(square::E5, square::F7, Knight, None, true)));
+        assert!(position.is_pseudo_legal(Ply::new(square::E1, square::C1, King, None, false)));
+        assert!(position.is_pseudo_legal(Ply::new(square::E1, square::G1, King, None, false)));
+        // wrong piece, wrong capture flag, blocked paths, and moves of the opponent
+        assert!(!position.is_pseudo_legal(Ply::new(square::A2, square::A4, Knight, None, false)));
+        assert!(!position.is_pseudo_legal(Ply::new(square::E5, square::F7, Knight, None, false)));
+        assert!(!position.is_pseudo_legal(Ply::new(square::E5, square::D3, Knight, None, true)));
+        assert!(!position.is_pseudo_legal(Ply::new(square::D2, square::H6, Bishop, None, true)));
+        assert!(!position.is_pseudo_legal(Ply::new(square::E4, square::E5, Pawn, None, false)));
+        assert!(!position.is_pseudo_legal(Ply::new(square::A7, square::A6, Pawn, None, false)));
+        assert!(!position.is_pseudo_legal(Ply::default()));
+
+        // the pinned pawn may not capture en passant, but the capture is pseudo-legal
+        let position = Board::from_fen("8/8/3p4/KPp4r/1R3p1k/8/4P1P1/8 w - c6 0 1").unwrap().position;
+        let ply = Ply::new(square::B5, square::C6, Pawn, None, true);
+        assert!(position.is_pseudo_legal(ply));
+        assert!(!position.is_legal_move(ply));
+
+        // pawns on the seventh rank have to promote
+        let position = Board::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap().position;
+        assert!(position.is_pseudo_legal(Ply::new(square::B7, square::B8, Pawn, Some(Queen), false)));
+        assert!(!position.is_pseudo_legal(Ply::new(square::B7, square::B8, Pawn, None, false)));
+        assert!(!position.is_pseudo_legal(Ply::new(square::B7, square::B8, Pawn, Some(King), false)));
+    }
+
+    #[test]
+    fn test_is_legal_move_matches_move_generation() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "r3k2r/8/8/8/3pPp2/8/8/R3K1RR b KQkq e3 0 1",
+            "r3k2r/8/8/2b5/8/8/8/R3K2R w KQkq - 0 1",
+        ];
+
+        for fen in fens {
+            let position = Board::from_fen(fen).unwrap().position;
+            let move_list = move_gen::generate_moves(position);
+            let legal_moves: Vec<Ply> = (0..move_list.len()).map(|i| move_list.get(i)).collect();
+
+            // every legal move is accepted
+            for ply in legal_moves.iter() {
+                assert!(position.is_legal_move(*ply), "{fen}: {ply}");
+            }
+
+            // every other move of the own pieces is rejected
+            for (source, piece, _color) in position.color_pieces_iter(position.color_to_move) {
+                for target_index in 0..64 {
+                    for promotion_piece in [None, Some(Knight), Some(Bishop), Some(Rook), Some(Queen)] {
+                        for is_capture in [false, true] {
+                            let ply = Ply::new(source, Square::new(target_index), piece, promotion_piece, is_capture);
+                            assert_eq!(legal_moves.contains(&ply), position.is_legal_move(ply), "{fen}: {ply}");
+                        }
+                    }
+                }
+            }
+        }
+    }
 
+    #[test]
+    fn test_make_move() {
         // position 1
 
         // e2-e4
-        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position.make_move(Ply {
-            source: square::E2,
-            target: square::E4,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position.make_move(Ply::new(square::E2, square::E4, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap().position, position);
 
         // e7-e6
-        let position = position.make_move(Ply {
-            source: square::E7,
-            target: square::E6,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::E7, square::E6, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqkbnr/pppp1ppp/4p3/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap().position, position);
 
         // g1-f3
-        let position = position.make_move(Ply {
-            source: square::G1,
-            target: square::F3,
-            piece: Piece::Knight,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::G1, square::F3, Piece::Knight, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqkbnr/pppp1ppp/4p3/8/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2").unwrap().position, position);
 
         // d7-d5
-        let position = position.make_move(Ply {
-            source: square::D7,
-            target: square::D5,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::D7, square::D5, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqkbnr/ppp2ppp/4p3/3p4/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq d6 0 3").unwrap().position, position);
 
         // e4-d5
-        let position = position.make_move(Ply {
-            source: square::E4,
-            target: square::D5,
-            piece: Piece::Pawn,
-            captured_piece: Some(Piece::Pawn),
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::E4, square::D5, Piece::Pawn, None, true));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqkbnr/ppp2ppp/4p3/3P4/8/5N2/PPPP1PPP/RNBQKB1R b KQkq - 0 3").unwrap().position, position);
 
         // e6-d5
-        let position = position.make_move(Ply {
-            source: square::E6,
-            target: square::D5,
-            piece: Piece::Pawn,
-            captured_piece: Some(Piece::Pawn),
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::E6, square::D5, Piece::Pawn, None, true));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqkbnr/ppp2ppp/8/3p4/8/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 4").unwrap().position, position);
 
         // d1-e2
-        let position = position.make_move(Ply {
-            source: square::D1,
-            target: square::E2,
-            piece: Piece::Queen,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::D1, square::E2, Piece::Queen, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqkbnr/ppp2ppp/8/3p4/8/5N2/PPPPQPPP/RNB1KB1R b KQkq - 1 4").unwrap().position, position);
 
         // f8-e7
-        let position = position.make_move(Ply {
-            source: square::F8,
-            target: square::E7,
-            piece: Piece::Bishop,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::F8, square::E7, Piece::Bishop, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk1nr/ppp1bppp/8/3p4/8/5N2/PPPPQPPP/RNB1KB1R w KQkq - 2 5").unwrap().position, position);
 
         // e2-e7
-        let position = position.make_move(Ply {
-            source: square::E2,
-            target: square::E7,
-            piece: Piece::Queen,
-            captured_piece: Some(Bishop),
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::E2, square::E7, Piece::Queen, None, true));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk1nr/ppp1Qppp/8/3p4/8/5N2/PPPP1PPP/RNB1KB1R b KQkq - 0 5").unwrap().position, position);
 
         // g8-e7
-        let position = position.make_move(Ply {
-            source: square::G8,
-            target: square::E7,
-            piece: Piece::Knight,
-            captured_piece: Some(Queen),
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::G8, square::E7, Piece::Knight, None, true));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk2r/ppp1nppp/8/3p4/8/5N2/PPPP1PPP/RNB1KB1R w KQkq - 0 6").unwrap().position, position);
 
         // e1-e2
-        let position = position.make_move(Ply {
-            source: square::E1,
-            target: square::E2,
-            piece: Piece::King,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::E1, square::E2, Piece::King, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk2r/ppp1nppp/8/3p4/8/5N2/PPPPKPPP/RNB2B1R b kq - 1 6").unwrap().position, position);
 
         // h8-g8
-        let position = position.make_move(Ply {
-            source: square::H8,
-            target: square::G8,
-            piece: Piece::Rook,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::H8, square::G8, Piece::Rook, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk1r1/ppp1nppp/8/3p4/8/5N2/PPPPKPPP/RNB2B1R w q - 2 7").unwrap().position, position);
 
         // h2-h4
-        let position = position.make_move(Ply {
-            source: square::H2,
-            target: square::H4,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::H2, square::H4, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk1r1/ppp1nppp/8/3p4/7P/5N2/PPPPKPP1/RNB2B1R b q h3 0 7").unwrap().position, position);
 
         // g7-g5
-        let position = position.make_move(Ply {
-            source: square::G7,
-            target: square::G5,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::G7, square::G5, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk1r1/ppp1np1p/8/3p2p1/7P/5N2/PPPPKPP1/RNB2B1R w q g6 0 8").unwrap().position, position);
 
         // h4-g5
-        let position = position.make_move(Ply {
-            source: square::H4,
-            target: square::G5,
-            piece: Piece::Pawn,
-            captured_piece: Some(Pawn),
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::H4, square::G5, Piece::Pawn, None, true));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk1r1/ppp1np1p/8/3p2P1/8/5N2/PPPPKPP1/RNB2B1R b q - 0 8").unwrap().position, position);
 
         // h7-h6
-        let position = position.make_move(Ply {
-            source: square::H7,
-            target: square::H6,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::H7, square::H6, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk1r1/ppp1np2/7p/3p2P1/8/5N2/PPPPKPP1/RNB2B1R w q - 0 9").unwrap().position, position);
 
         // g5-h6
-        let position = position.make_move(Ply {
-            source: square::G5,
-            target: square::H6,
-            piece: Piece::Pawn,
-            captured_piece: Some(Piece::Pawn),
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::G5, square::H6, Piece::Pawn, None, true));
         println!("{position}");
         assert_eq!(Board::from_fen("rnbqk1r1/ppp1np2/7P/3p4/8/5N2/PPPPKPP1/RNB2B1R b q - 0 9").unwrap().position, position);
 
         // b8-c6
-        let position = position.make_move(Ply {
-            source: square::B8,
-            target: square::C6,
-            piece: Piece::Knight,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::B8, square::C6, Piece::Knight, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("r1bqk1r1/ppp1np2/2n4P/3p4/8/5N2/PPPPKPP1/RNB2B1R w q - 1 10").unwrap().position, position);
 
         // h6-h7
-        let position = position.make_move(Ply {
-            source: square::H6,
-            target: square::H7,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::H6, square::H7, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("r1bqk1r1/ppp1np1P/2n5/3p4/8/5N2/PPPPKPP1/RNB2B1R b q - 0 10").unwrap().position, position);
 
         // c8-h3
-        let position = position.make_move(Ply {
-            source: square::C8,
-            target: square::H3,
-            piece: Piece::Bishop,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::C8, square::H3, Piece::Bishop, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("r2qk1r1/ppp1np1P/2n5/3p4/8/5N1b/PPPPKPP1/RNB2B1R w q - 1 11").unwrap().position, position);
 
         // h1-h3
-        let position = position.make_move(Ply {
-            source: square::H1,
-            target: square::H3,
-            piece: Piece::Rook,
-            captured_piece: Some(Bishop),
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::H1, square::H3, Piece::Rook, None, true));
         println!("{position}");
         assert_eq!(Board::from_fen("r2qk1r1/ppp1np1P/2n5/3p4/8/5N1R/PPPPKPP1/RNB2B2 b q - 0 11").unwrap().position, position);
 
         // d8-d7
-        let position = position.make_move(Ply {
-            source: square::D8,
-            target: square::D7,
-            piece: Piece::Queen,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::D8, square::D7, Piece::Queen, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("r3k1r1/pppqnp1P/2n5/3p4/8/5N1R/PPPPKPP1/RNB2B2 w q - 1 12").unwrap().position, position);
 
         // a2-a4
-        let position = position.make_move(Ply {
-            source: square::A2,
-            target: square::A4,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::A2, square::A4, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("r3k1r1/pppqnp1P/2n5/3p4/P7/5N1R/1PPPKPP1/RNB2B2 b q a3 0 12").unwrap().position, position);
 
         // e8-C8
-        let position = position.make_move(Ply {
-            source: square::E8,
-            target: square::C8,
-            piece: Piece::King,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::E8, square::C8, Piece::King, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("2kr2r1/pppqnp1P/2n5/3p4/P7/5N1R/1PPPKPP1/RNB2B2 w - - 1 13").unwrap().position, position);
 
         // h7-g8
-        let position = position.make_move(Ply {
-            source: square::H7,
-            target: square::G8,
-            piece: Piece::Pawn,
-            captured_piece: Some(Rook),
-            promotion_piece: Some(Queen),
-        });
+        let position = position.make_move(Ply::new(square::H7, square::G8, Piece::Pawn, Some(Queen), true));
         println!("{position}");
         assert_eq!(Board::from_fen("2kr2Q1/pppqnp2/2n5/3p4/P7/5N1R/1PPPKPP1/RNB2B2 b - - 0 13").unwrap().position, position);
 
         // d5-d4
-        let position = position.make_move(Ply {
-            source: square::D5,
-            target: square::D4,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::D5, square::D4, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("2kr2Q1/pppqnp2/2n5/8/P2p4/5N1R/1PPPKPP1/RNB2B2 w - - 0 14").unwrap().position, position);
 
         // c2-c4
-        let position = position.make_move(Ply {
-            source: square::C2,
-            target: square::C4,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::C2, square::C4, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("2kr2Q1/pppqnp2/2n5/8/P1Pp4/5N1R/1P1PKPP1/RNB2B2 b - c3 0 14").unwrap().position, position);
 
         // d4-c3
-        let position = position.make_move(Ply {
-            source: square::D4,
-            target: square::C3,
-            piece: Piece::Pawn,
-            captured_piece: Some(Pawn),
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::D4, square::C3, Piece::Pawn, None, true));
         println!("{position}");
         assert_eq!(Board::from_fen("2kr2Q1/pppqnp2/2n5/8/P7/2p2N1R/1P1PKPP1/RNB2B2 w - - 0 15").unwrap().position, position);
 
         // a4-a5
-        let position = position.make_move(Ply {
-            source: square::A4,
-            target: square::A5,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::A4, square::A5, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("2kr2Q1/pppqnp2/2n5/P7/8/2p2N1R/1P1PKPP1/RNB2B2 b - - 0 15").unwrap().position, position);
 
         // b7-b5
-        let position = position.make_move(Ply {
-            source: square::B7,
-            target: square::B5,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::B7, square::B5, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("2kr2Q1/p1pqnp2/2n5/Pp6/8/2p2N1R/1P1PKPP1/RNB2B2 w - b6 0 16").unwrap().position, position);
 
         // a5-b6
-        let position = position.make_move(Ply {
-            source: square::A5,
-            target: square::B6,
-            piece: Piece::Pawn,
-            captured_piece: Some(Pawn),
-            promotion_piece: None,
-        });
+        let position = position.make_move(Ply::new(square::A5, square::B6, Piece::Pawn, None, true));
         println!("{position}");
         assert_eq!(Board::from_fen("2kr2Q1/p1pqnp2/1Pn5/8/8/2p2N1R/1P1PKPP1/RNB2B2 b - - 0 16").unwrap().position, position);
 
         // position 2
 
         // e2-e4
-        let position = Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2P3/P2P1PPP/RN1QKB1R w KQkq - 1 8").unwrap().position.make_move(Ply {
-            source: square::F2,
-            target: square::F3,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        });
+        let position = Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2P3/P2P1PPP/RN1QKB1R w KQkq - 1 8").unwrap().position.make_move(Ply::new(square::F2, square::F3, Piece::Pawn, None, false));
         println!("{position}");
         assert_eq!(Board::from_fen("r1b1kbnr/1pp3pp/p1n5/4Bp2/2P4q/1P2PP2/P2P2PP/RN1QKB1R b KQkq - 1 8").unwrap().position, position);
     }
 
     #[test]
-    fn test_get_num_pieces() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn test_get_dirty_pieces() {
+        // quiet move
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        let dirty_pieces = position.get_dirty_pieces(Ply::from_string("g1f3", position).unwrap());
+        assert_eq!(vec![&DirtyPiece { piece: Piece::Knight, color: Color::White, from: Some(square::G1), to: Some(square::F3) }], dirty_pieces.iter().collect::<Vec<_>>());
+
+        // en passant
+        let position = Board::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap().position;
+        let dirty_pieces = position.get_dirty_pieces(Ply::from_string("e5f6", position).unwrap());
+        assert_eq!(vec![
+            &DirtyPiece { piece: Piece::Pawn, color: Color::White, from: Some(square::E5), to: Some(square::F6) },
+            &DirtyPiece { piece: Piece::Pawn, color: Color::Black, from: Some(square::F5), to: None },
+        ], dirty_pieces.iter().collect::<Vec<_>>());
+
+        // promotion with capture
+        let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1").unwrap().position;
+        let dirty_pieces = position.get_dirty_pieces(Ply::from_string("b2a1q", position).unwrap());
+        assert_eq!(vec![
+            &DirtyPiece { piece: Piece::Pawn, color: Color::Black, from: Some(square::B2), to: None },
+            &DirtyPiece { piece: Piece::Queen, color: Color::Black, from: None, to: Some(square::A1) },
+            &DirtyPiece { piece: Piece::Rook, color: Color::White, from: Some(square::A1), to: None },
+        ], dirty_pieces.iter().collect::<Vec<_>>());
+
+        // castling
+        let position = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap().position;
+        let (new_position, dirty_pieces) = position.make_move_with_dirty_pieces(Ply::from_string("e8c8", position).unwrap());
+        assert_eq!(vec![
+            &DirtyPiece { piece: Piece::King, color: Color::Black, from: Some(square::E8), to: Some(square::C8) },
+            &DirtyPiece { piece: Piece::Rook, color: Color::Black, from: Some(square::A8), to: Some(square::D8) },
+        ], dirty_pieces.iter().collect::<Vec<_>>());
+        assert_eq!(Board::from_fen("2kr3r/8/8/8/8/8/8/R3K2R w KQ - 1 2").unwrap().position, new_position);
+    }
 
+    #[test]
+    fn test_get_num_pieces() {
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(8, position.get_num_pieces(Piece::Pawn, Color::White));
         assert_eq!(8, position.get_num_pieces(Piece::Pawn, Color::Black));
@@ -1219,4 +1542,13 @@ mod tests {
         assert_eq!(1, position.get_num_pieces(Piece::King, Color::White));
         assert_eq!(1, position.get_num_pieces(Piece::King, Color::Black));
     }
+
+    #[test]
+    fn test_game_phase() {
+        assert_eq!(MAX_GAME_PHASE, Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position.game_phase());
+        assert_eq!(8, Board::from_fen("4nrk1/5pp1/2p3bp/2N1p3/4P1P1/2N1P2P/2P4K/4R3 b - - 0 27").unwrap().position.game_phase());
+        assert_eq!(0, Board::from_fen("8/2k5/8/3PK3/8/8/8/8 w - - 0 1").unwrap().position.game_phase());
+        // the game phase doesn't exceed the maximum after promotions
+        assert_eq!(MAX_GAME_PHASE, Board::from_fen("QQQQkbnr/8/8/8/8/8/8/RNBQKBNR w KQ - 0 1").unwrap().position.game_phase());
+    }
 }
\ No newline at end of file
@@ -1,7 +1,32 @@
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 use crate::board::file::File;
 use crate::board::rank::Rank;
 
+/// The reasons why a square can't be parsed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SquareParseError {
+    /// The string does not consist of exactly two characters.
+    WrongLength(usize),
+    /// The file is not a lowercase letter from a to h.
+    InvalidFile(char),
+    /// The rank is not a number from 1 to 8.
+    InvalidRank(char),
+}
+
+/// Prints the error as text.
+impl Display for SquareParseError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            SquareParseError::WrongLength(length) => write!(f, "Invalid square: expected 2 characters, found {length}"),
+            SquareParseError::InvalidFile(file) => write!(f, "Invalid square: invalid file '{file}'"),
+            SquareParseError::InvalidRank(rank) => write!(f, "Invalid square: invalid rank '{rank}'"),
+        }
+    }
+}
+
+impl Error for SquareParseError {}
+
 /// A square on the chessboard, represented by an index ranging from 0 to 63.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Square {
@@ -13,7 +38,7 @@ pub const NUM_SQUARES: u8 = 64;
 
 impl Square {
     /// Constructs a new square from a given index.
-    pub fn new(index: u8) -> Self {
+    pub const fn new(index: u8) -> Self {
         Self { index }
     }
 
@@ -23,40 +48,33 @@ impl Square {
     }
 
     /// Constructs a new square from a string.
-    pub fn from_string(square_str: &str) -> Result<Self, String> {
-        // square_str can not be longer than 2
-        if square_str.len() != 2 {
-            return Err(String::from("Invalid square string"));
-        }
-        
+    pub fn from_string(square_str: &str) -> Result<Self, SquareParseError> {
         // get chars
         let chars: Vec<char> = square_str.chars().collect();
+
+        // square_str can not be longer than 2
+        if chars.len() != 2 {
+            return Err(SquareParseError::WrongLength(chars.len()));
+        }
         
         // first char must be ascii a-h
         match chars[0] {
             file_char if file_char.is_ascii_lowercase() && ('a'..='h').contains(&file_char) => {}
-            _other => return Err(String::from("Invalid square string")) 
+            other => return Err(SquareParseError::InvalidFile(other))
         }
         
         // second char must be a number 1-8
         match chars[1] {
             rank_char if rank_char.is_numeric() && ('1'..='8').contains(&rank_char) => {}
-            _other => return Err(String::from("Invalid square string"))
+            other => return Err(SquareParseError::InvalidRank(other))
         }
 
         // get file
-        let file = File::from_char(&chars[0]);
-        if file.is_err() {
-            return Err(String::from("Invalid square string"));
-        }
-        let file = file.unwrap();
+        let file = File::from_char(&chars[0]).map_err(|_| SquareParseError::InvalidFile(chars[0]))?;
         
         // get rank
-        let rank = chars[1].to_digit(10);
-        if rank.is_none() {
-            return Err(String::from("Invalid square string"));
-        }
-        let rank = Rank::from_index(rank.unwrap() as u8 - 1);
+        let rank = chars[1].to_digit(10).ok_or(SquareParseError::InvalidRank(chars[1]))?;
+        let rank = Rank::from_index(rank as u8 - 1);
         
         // get square
         Ok(Square::from_file_rank(file, rank))
@@ -73,14 +91,55 @@ impl Square {
     }
 
     /// Returns the square above.
+    /// On the eighth rank, this wraps around to the first rank - use `try_up` if the square may be on the edge of the board.
     pub fn up(&self) -> Square {
         Square::from_file_rank(self.get_file(), self.get_rank().up())
     }
 
     /// Returns the square below.
+    /// On the first rank, this wraps around to the eighth rank - use `try_down` if the square may be on the edge of the board.
     pub fn down(&self) -> Square {
         Square::from_file_rank(self.get_file(), self.get_rank().down())
     }
+
+    /// Returns the square above, or None if the square is on the eighth rank.
+    pub fn try_up(&self) -> Option<Square> {
+        match self.index < 56 {
+            true => Some(Square::new(self.index + 8)),
+            false => None,
+        }
+    }
+
+    /// Returns the square below, or None if the square is on the first rank.
+    pub fn try_down(&self) -> Option<Square> {
+        match self.index >= 8 {
+            true => Some(Square::new(self.index - 8)),
+            false => None,
+        }
+    }
+
+    /// Returns the square that is the given number of files to the right and ranks up from this square.
+    /// Negative deltas move to the left and down. Returns None if the square would be off the board.
+    pub fn offset(&self, file_delta: i8, rank_delta: i8) -> Option<Square> {
+        let file_index = self.get_file().to_index() as i8 + file_delta;
+        let rank_index = self.get_rank().to_index() as i8 + rank_delta;
+        match (0..8).contains(&file_index) && (0..8).contains(&rank_index) {
+            true => Some(Square::new((rank_index * 8 + file_index) as u8)),
+            false => None,
+        }
+    }
+
+    /// Returns the number of king moves it takes to get from this square to the other square on an empty board.
+    pub fn distance(&self, other: Square) -> u8 {
+        let file_distance = (self.index % 8).abs_diff(other.index % 8);
+        let rank_distance = (self.index / 8).abs_diff(other.index / 8);
+        file_distance.max(rank_distance)
+    }
+
+    /// Returns whether the square is a light square, e.g. h1 and a8.
+    pub fn is_light(&self) -> bool {
+        (self.index % 8 + self.index / 8) % 2 == 1
+    }
 }
 
 /// Prints the square as text.
@@ -187,13 +246,14 @@ mod tests {
     
     #[test]
     fn from_string_with_invalid_string_returns_error() {
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("ab2"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("123"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("h9"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("j1"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("nonsense"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("2e"));
-        assert_eq!(Err(String::from("Invalid square string")), Square::from_string("G9"));
+        assert_eq!(Err(SquareParseError::WrongLength(3)), Square::from_string("ab2"));
+        assert_eq!(Err(SquareParseError::WrongLength(3)), Square::from_string("123"));
+        assert_eq!(Err(SquareParseError::InvalidRank('9')), Square::from_string("h9"));
+        assert_eq!(Err(SquareParseError::InvalidFile('j')), Square::from_string("j1"));
+        assert_eq!(Err(SquareParseError::WrongLength(8)), Square::from_string("nonsense"));
+        assert_eq!(Err(SquareParseError::InvalidFile('2')), Square::from_string("2e"));
+        assert_eq!(Err(SquareParseError::InvalidFile('G')), Square::from_string("G9"));
+        assert_eq!("Invalid square: invalid rank '9'", Square::from_string("h9").unwrap_err().to_string());
     }
 
     #[test]
@@ -218,6 +278,47 @@ mod tests {
         assert_eq!(D8, D1.down()); // wrap around
     }
 
+    #[test]
+    fn try_up_and_try_down_return_none_on_the_edge() {
+        assert_eq!(Some(A4), A3.try_up());
+        assert_eq!(Some(H8), H7.try_up());
+        assert_eq!(None, D8.try_up());
+        assert_eq!(Some(A1), A2.try_down());
+        assert_eq!(Some(H7), H8.try_down());
+        assert_eq!(None, D1.try_down());
+    }
+
+    #[test]
+    fn offset_returns_none_off_the_board() {
+        assert_eq!(Some(F6), E4.offset(1, 2));
+        assert_eq!(Some(C3), E4.offset(-2, -1));
+        assert_eq!(Some(E4), E4.offset(0, 0));
+        assert_eq!(Some(H8), A1.offset(7, 7));
+        assert_eq!(None, A1.offset(-1, 0));
+        assert_eq!(None, H4.offset(1, 0));
+        assert_eq!(None, G7.offset(1, 2));
+        assert_eq!(None, B2.offset(0, -2));
+    }
+
+    #[test]
+    fn distance_returns_number_of_king_moves() {
+        assert_eq!(0, E4.distance(E4));
+        assert_eq!(1, E4.distance(F5));
+        assert_eq!(2, E4.distance(C3));
+        assert_eq!(7, A1.distance(H8));
+        assert_eq!(7, H1.distance(A2));
+    }
+
+    #[test]
+    fn is_light_returns_color_of_square() {
+        assert!(!A1.is_light());
+        assert!(H1.is_light());
+        assert!(A8.is_light());
+        assert!(!H8.is_light());
+        assert!(E4.is_light());
+        assert!(!D4.is_light());
+    }
+
     #[test]
     fn get_file_returns_correct_file() {
         assert_eq!(File::A, A1.get_file());
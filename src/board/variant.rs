@@ -0,0 +1,116 @@
+//! The variant module contains the rules in which chess variants differ from standard chess.
+//!
+//! Each variant implements the `Variant` trait, whose default methods describe the rules of standard chess,
+//! so that a variant only overrides the rules it changes. The position holds the variant it is played in,
+//! and the move generation and the search consult it for castling, promotions and the win condition.
+//!
+//! The variants are selected with the "UCI_Variant" option, using the names of Lichess-style hosts.
+
+use std::fmt::Debug;
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+
+/// The pieces a pawn can promote to in standard chess, in the order the promotions are generated.
+const STANDARD_PROMOTION_PIECES: [Piece; 4] = [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+
+/// The four center squares d4, e4, d5 and e5, which the king has to reach in King of the Hill.
+const CENTER_SQUARES: u64 = 0x0000_0018_1800_0000;
+
+/// The rules of a chess variant that may differ from standard chess.
+pub trait Variant: Debug + Sync {
+    /// Returns the name of the variant, as used by the "UCI_Variant" option.
+    fn name(&self) -> &'static str;
+
+    /// Returns whether the players may castle.
+    fn allows_castling(&self) -> bool {
+        true
+    }
+
+    /// Returns the pieces a pawn can promote to.
+    fn promotion_pieces(&self) -> &'static [Piece] {
+        &STANDARD_PROMOTION_PIECES
+    }
+
+    /// Returns the color that has won the game in the given position by a rule of the variant, if any.
+    /// Checkmate is not a rule of the variant, so it is not reported here.
+    fn winner(&self, _position: &Position) -> Option<Color> {
+        None
+    }
+}
+
+/// Standard chess.
+#[derive(Debug)]
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "chess"
+    }
+}
+
+/// Standard chess without castling.
+#[derive(Debug)]
+pub struct NoCastling;
+
+impl Variant for NoCastling {
+    fn name(&self) -> &'static str {
+        "nocastle"
+    }
+
+    fn allows_castling(&self) -> bool {
+        false
+    }
+}
+
+/// [King of the Hill](https://lichess.org/variant/kingOfTheHill), where bringing the king to the center of the board wins the game as well.
+#[derive(Debug)]
+pub struct KingOfTheHill;
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "kingofthehill"
+    }
+
+    fn winner(&self, position: &Position) -> Option<Color> {
+        [Color::White, Color::Black].into_iter().find(|color| position.piece_bb(*color, Piece::King).value & CENTER_SQUARES != 0)
+    }
+}
+
+/// The variant of standard chess.
+pub const STANDARD: &dyn Variant = &Standard;
+
+/// All variants, with standard chess first.
+pub const VARIANTS: [&dyn Variant; 3] = [STANDARD, &NoCastling, &KingOfTheHill];
+
+/// Returns the variant with the given name, ignoring the case.
+pub fn from_name(name: &str) -> Option<&'static dyn Variant> {
+    VARIANTS.into_iter().find(|variant| variant.name().eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::board::variant;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Some("chess"), variant::from_name("chess").map(|variant| variant.name()));
+        assert_eq!(Some("kingofthehill"), variant::from_name("KingOfTheHill").map(|variant| variant.name()));
+        assert!(variant::from_name("crazyhouse").is_none());
+    }
+
+    #[test]
+    fn test_king_of_the_hill_winner() {
+        let king_of_the_hill = variant::from_name("kingofthehill").unwrap();
+        let winner = |fen: &str| king_of_the_hill.winner(&Board::from_fen(fen).unwrap().position);
+
+        assert_eq!(None, winner("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(Some(Color::White), winner("4k3/8/8/8/3K4/8/8/8 b - - 0 1"));
+        assert_eq!(Some(Color::Black), winner("8/8/8/4k3/8/8/8/4K3 w - - 0 1"));
+
+        // the center only wins in King of the Hill
+        assert_eq!(None, variant::STANDARD.winner(&Board::from_fen("4k3/8/8/8/3K4/8/8/8 b - - 0 1").unwrap().position));
+    }
+}
@@ -0,0 +1,315 @@
+//! The book module provides support for opening books in the [Polyglot](http://hgm.nubati.net/book_format.html) format.
+//! A Polyglot book is a binary file containing 16 byte entries, sorted by the zobrist key of the position.
+//! Ladybug's zobrist keys are taken directly from the Polyglot format, so the position hash can be used to probe the book.
+//!
+//! Besides books loaded from files, Ladybug has a small internal book of standard openings, see the `internal` submodule.
+
+use std::collections::HashMap;
+use std::fs;
+use crate::board::Board;
+use crate::board::file::File;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::board::square::Square;
+use crate::game::START_FEN;
+use crate::lookup::LOOKUP_TABLE;
+use crate::move_gen::ply::Ply;
+use crate::zobrist;
+
+pub mod internal;
+
+/// The size of a single book entry in bytes.
+const ENTRY_SIZE: usize = 16;
+
+/// A single entry of a Polyglot book.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BookEntry {
+    /// The Polyglot key of the position.
+    pub key: u64,
+    /// The encoded move.
+    pub encoded_move: u16,
+    /// The weight of the move. Moves with a higher weight are played more often.
+    pub weight: u16,
+}
+
+/// An opening book, holding all entries of a Polyglot book file.
+pub struct Book {
+    /// The book entries, sorted by key.
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    /// Loads the Polyglot book from the given file.
+    pub fn from_file(path: &str) -> Result<Book, String> {
+        match fs::read(path) {
+            Err(_) => Err(String::from("Could not read book file")),
+            Ok(bytes) => Self::from_bytes(&bytes),
+        }
+    }
+
+    /// Parses a Polyglot book from the given bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Book, String> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(String::from("Invalid book file"));
+        }
+
+        let mut entries: Vec<BookEntry> = bytes.chunks_exact(ENTRY_SIZE).map(|chunk| BookEntry {
+            key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+            encoded_move: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+            // the remaining 4 bytes are used for learning, which Ladybug does not support
+        }).collect();
+
+        // the entries of a valid book are already sorted, but make sure the binary search works anyway
+        entries.sort_by_key(|entry| entry.key);
+
+        Ok(Book { entries })
+    }
+
+    /// Builds a book from the given opening lines, each of which is a sequence of moves in UCI notation from the starting position.
+    /// The weight of a move is the number of lines that play it in the position, so moves shared by many lines are played more often.
+    pub fn from_lines(lines: &[&str]) -> Result<Book, String> {
+        let mut weights: HashMap<(u64, u16), u16> = HashMap::new();
+        for line in lines {
+            let mut position = Board::from_fen(START_FEN).unwrap().position;
+            for move_str in line.split_whitespace() {
+                let ply = Ply::from_string(move_str, position).map_err(|_| format!("Illegal move {move_str} in book line {line}"))?;
+                *weights.entry((get_polyglot_key(&position), encode_move(ply))).or_default() += 1;
+                position = position.make_move(ply);
+            }
+        }
+
+        let mut entries: Vec<BookEntry> = weights.into_iter()
+            .map(|((key, encoded_move), weight)| BookEntry { key, encoded_move, weight })
+            .collect();
+        // the moves of a position are ordered as well, so that the book doesn't depend on the iteration order of the map
+        entries.sort_by_key(|entry| (entry.key, entry.encoded_move));
+
+        Ok(Book { entries })
+    }
+
+    /// Returns the number of entries in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the book contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns all book entries for the given position.
+    pub fn get_entries(&self, position: Position) -> Vec<BookEntry> {
+        let key = get_polyglot_key(&position);
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        self.entries[start..].iter().take_while(|entry| entry.key == key).copied().collect()
+    }
+
+    /// Returns the book move with the highest weight for the given position.
+    /// Returns None if the position is not in the book, or if none of the book moves are legal.
+    pub fn get_move(&self, position: Position) -> Option<Ply> {
+        let mut entries = self.get_entries(position);
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.weight));
+        entries.iter().find_map(|entry| decode_move(entry.encoded_move, position))
+    }
+
+    /// Returns a book move for the given position, picked with the given random number so that each move is played
+    /// in proportion to its weight. Returns None if the position is not in the book, or if none of the book moves are legal.
+    pub fn get_weighted_move(&self, position: Position, random: u64) -> Option<Ply> {
+        let moves: Vec<(Ply, u64)> = self.get_entries(position).iter()
+            .filter_map(|entry| decode_move(entry.encoded_move, position).map(|ply| (ply, entry.weight as u64)))
+            .collect();
+        let total_weight: u64 = moves.iter().map(|(_ply, weight)| weight).sum();
+        if total_weight == 0 {
+            // moves with zero weight are only played if all moves have zero weight
+            return moves.first().map(|(ply, _weight)| *ply);
+        }
+
+        let mut remaining = random % total_weight;
+        for (ply, weight) in moves {
+            if remaining < weight {
+                return Some(ply);
+            }
+            remaining -= weight;
+        }
+        None
+    }
+}
+
+/// Returns the Polyglot key of the given position.
+///
+/// The Polyglot format only includes the en passant file in the key if a pawn of the side to move
+/// can actually capture en passant, while Ladybug's hash always includes it.
+pub fn get_polyglot_key(position: &Position) -> u64 {
+    let mut key = position.hash;
+    if let Some(square) = position.en_passant {
+        // the squares from which a pawn of the side to move could capture en passant
+        let attackers = LOOKUP_TABLE.get_pawn_attacks(square, position.color_to_move.other());
+        let pawns = position.piece_bb(position.color_to_move, Piece::Pawn);
+        if attackers.value & pawns.value == 0 {
+            key ^= zobrist::random::get_random_en_passant(Some(square.get_file()));
+        }
+    }
+    key
+}
+
+/// Encodes the given ply as a Polyglot move, see `decode_move` for the format.
+/// Castling is encoded as the king capturing its own rook.
+fn encode_move(ply: Ply) -> u16 {
+    let (source, mut target) = (ply.source(), ply.target());
+    if ply.piece() == Piece::King && source.get_file() == File::E {
+        target = match target.get_file() {
+            File::G => Square::from_file_rank(File::H, target.get_rank()),
+            File::C => Square::from_file_rank(File::A, target.get_rank()),
+            _other => target,
+        };
+    }
+    let promotion = match ply.promotion_piece() {
+        Some(Piece::Knight) => 1,
+        Some(Piece::Bishop) => 2,
+        Some(Piece::Rook) => 3,
+        Some(Piece::Queen) => 4,
+        _other => 0,
+    };
+    promotion << 12 | (source.index as u16) << 6 | target.index as u16
+}
+
+/// Decodes a Polyglot move into a legal ply for the given position.
+///
+/// The format is as follows:
+/// 0000 0000 0000 0111 : to file
+/// 0000 0000 0011 1000 : to row
+/// 0000 0001 1100 0000 : from file
+/// 0000 1110 0000 0000 : from row
+/// 0111 0000 0000 0000 : promotion piece (none, knight, bishop, rook, queen)
+fn decode_move(encoded_move: u16, position: Position) -> Option<Ply> {
+    let to = (encoded_move & 0x3f) as u8;
+    let from = ((encoded_move >> 6) & 0x3f) as u8;
+    let promotion = match (encoded_move >> 12) & 0x7 {
+        1 => "n",
+        2 => "b",
+        3 => "r",
+        4 => "q",
+        _other => "",
+    };
+
+    let source = Square::new(from);
+    let target = Square::new(to);
+
+    // Polyglot encodes castling as the king capturing its own rook, which `Ply::from_string` understands as well
+    Ply::from_string(format!("{source}{target}{promotion}").as_str(), position).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::board::Board;
+    use crate::book::{Book, decode_move, encode_move, get_polyglot_key, internal};
+    use crate::move_gen::ply::Ply;
+
+    /// Helper function to encode a book entry.
+    fn entry(key: u64, encoded_move: u16, weight: u16) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&key.to_be_bytes());
+        bytes.extend_from_slice(&encoded_move.to_be_bytes());
+        bytes.extend_from_slice(&weight.to_be_bytes());
+        bytes.extend_from_slice(&0u32.to_be_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_get_polyglot_key() {
+        // keys taken from http://hgm.nubati.net/book_format.html
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(0x463b96181691fc9c, get_polyglot_key(&position));
+
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap().position;
+        assert_eq!(0x823c9b50fd114196, get_polyglot_key(&position));
+
+        let position = Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2").unwrap().position;
+        assert_eq!(0x0756b94461c50fb0, get_polyglot_key(&position));
+
+        let position = Board::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap().position;
+        assert_eq!(0x22a48b5a8e47ff78, get_polyglot_key(&position));
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        assert!(Book::from_bytes(&[0; 15]).is_err());
+        assert!(Book::from_bytes(&[]).unwrap().is_empty());
+        assert_eq!(2, Book::from_bytes(&[entry(1, 2, 3), entry(4, 5, 6)].concat()).unwrap().len());
+    }
+
+    #[test]
+    fn test_get_move() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+
+        // e2e4 with weight 10, d2d4 with weight 20, and an entry for an unrelated position
+        let bytes = [entry(0x463b96181691fc9c, 0x031c, 10), entry(0x463b96181691fc9c, 0x02db, 20), entry(0x1234, 0x031c, 100)].concat();
+        let book = Book::from_bytes(&bytes).unwrap();
+        assert_eq!(2, book.get_entries(position).len());
+        assert_eq!("d2d4", format!("{}", book.get_move(position).unwrap()));
+
+        // the position after 1. e4 is not in the book
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap().position;
+        assert_eq!(None, book.get_move(position));
+    }
+
+    #[test]
+    fn test_get_move_castling() {
+        let position = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap().position;
+        // e1h1 in Polyglot encoding
+        let bytes = entry(get_polyglot_key(&position), (4 << 6) | 7, 1);
+        let book = Book::from_bytes(&bytes).unwrap();
+        assert_eq!("e1g1", format!("{}", book.get_move(position).unwrap()));
+    }
+
+    #[test]
+    fn test_encode_move() {
+        let position = Board::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap().position;
+        for move_str in ["e1g1", "e1c1", "b7a8q", "b7b8n", "a1a8"] {
+            let ply = Ply::from_string(move_str, position).unwrap();
+            assert_eq!(Some(ply), decode_move(encode_move(ply), position));
+        }
+        // castling is encoded as the king capturing its own rook
+        assert_eq!((4 << 6) | 7, encode_move(Ply::from_string("e1g1", position).unwrap()));
+    }
+
+    #[test]
+    fn test_from_lines() {
+        let book = Book::from_lines(&["e2e4 e7e5", "e2e4 c7c5", "d2d4"]).unwrap();
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        let entries = book.get_entries(position);
+        assert_eq!(2, entries.len());
+        assert_eq!(1, entries.iter().find(|entry| entry.encoded_move == 0x02db).unwrap().weight);
+        assert_eq!(2, entries.iter().find(|entry| entry.encoded_move == 0x031c).unwrap().weight);
+
+        assert!(Book::from_lines(&["e2e4 e2e4"]).is_err());
+        assert!(Book::from_lines(&["e2e5"]).is_err());
+    }
+
+    #[test]
+    fn test_get_weighted_move() {
+        let book = Book::from_lines(&["e2e4", "e2e4", "d2d4"]).unwrap();
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        // e2e4 has twice the weight of d2d4
+        let moves: Vec<String> = (0..3).map(|random| format!("{}", book.get_weighted_move(position, random).unwrap())).collect();
+        assert_eq!(2, moves.iter().filter(|move_str| *move_str == "e2e4").count());
+        assert!(moves.contains(&String::from("d2d4")));
+
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap().position;
+        assert_eq!(None, book.get_weighted_move(position, 0));
+    }
+
+    #[test]
+    fn test_internal_book() {
+        let book = internal::book();
+        assert!(book.len() > internal::LINES.len());
+        // the internal book knows several first moves for both colors
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        let first_moves: HashSet<String> = (0..100).map(|random| format!("{}", book.get_weighted_move(position, random).unwrap())).collect();
+        assert!(first_moves.len() >= 4);
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap().position;
+        assert!(book.get_entries(position).len() >= 4);
+    }
+}
@@ -0,0 +1,132 @@
+//! The config module reads and writes the configuration file, which stores the values of the UCI options,
+//! so that they don't have to be set again in every session.
+//!
+//! The file is written in TOML, with one option per line. Option names containing spaces are quoted:
+//!
+//! ```text
+//! # Ladybug configuration
+//! Hash = 64
+//! "Skill Level" = 15
+//! BookFile = "books/performance.bin"
+//! ```
+//!
+//! Ladybug loads the file from the working directory at startup, and the "saveconfig" command writes it.
+
+use std::fs;
+
+/// The configuration file Ladybug loads at startup, and the "saveconfig" command writes by default.
+pub const CONFIG_FILE: &str = "ladybug.toml";
+
+/// Parses the option names and values from the given TOML text.
+/// Empty lines and comments are skipped, and the quotes around names and values are removed.
+/// An array may continue over several lines, its value is returned on a single line including the brackets.
+pub fn parse(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut options = Vec::new();
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (name, value) = line.split_once('=').ok_or(format!("Expected '=' in '{line}'"))?;
+        let name = unquote(name.trim());
+        if name.is_empty() {
+            return Err(format!("Missing option name in '{line}'"));
+        }
+        let mut value = value.trim().to_string();
+        if value.starts_with('[') {
+            while !value.ends_with(']') {
+                let line = lines.next().ok_or(format!("Unterminated array for '{name}'"))?;
+                value += " ";
+                value += strip_comment(line).trim();
+            }
+        }
+        options.push((name.to_string(), unquote(value.as_str()).to_string()));
+    }
+    Ok(options)
+}
+
+/// Reads the option names and values from the given configuration file.
+pub fn read_file(path: &str) -> Result<Vec<(String, String)>, String> {
+    match fs::read_to_string(path) {
+        Err(_) => Err(String::from("Could not read config file")),
+        Ok(text) => parse(text.as_str()),
+    }
+}
+
+/// Returns the given option names and values as TOML, which can be loaded with `parse` again.
+/// Numbers and booleans are written as they are, all other values are written as strings.
+pub fn to_toml(options: &[(&str, String)]) -> String {
+    let mut toml = String::from("# Ladybug configuration\n");
+    for (name, value) in options {
+        let name = match name.contains(' ') {
+            true => format!("\"{name}\""),
+            false => name.to_string(),
+        };
+        let value = match value.parse::<i64>().is_ok() || value == "true" || value == "false" {
+            true => value.clone(),
+            false => format!("\"{value}\""),
+        };
+        toml += format!("{name} = {value}\n").as_str();
+    }
+    toml
+}
+
+/// Removes the comment from the given line, unless the '#' is part of a quoted string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..index],
+            _other => {}
+        }
+    }
+    line
+}
+
+/// Removes the quotes around the given string, if there are any.
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"').and_then(|text| text.strip_suffix('"')).unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{parse, read_file, to_toml};
+
+    #[test]
+    fn test_parse() {
+        let options = parse("# a comment\nHash = 64 # another comment\n\n\"Skill Level\" = 15\nBookFile = \"books/#1.bin\"\n").unwrap();
+        assert_eq!(vec![
+            (String::from("Hash"), String::from("64")),
+            (String::from("Skill Level"), String::from("15")),
+            (String::from("BookFile"), String::from("books/#1.bin")),
+        ], options);
+
+        let options = parse("values = [1, 2, # a comment\n  3,\n]\n").unwrap();
+        assert_eq!(vec![(String::from("values"), String::from("[1, 2, 3, ]"))], options);
+
+        assert!(parse("values = [1, 2,\n3").is_err());
+        assert!(parse("Hash 64").is_err());
+        assert!(parse("= 64").is_err());
+        assert_eq!(Vec::<(String, String)>::new(), parse("").unwrap());
+    }
+
+    #[test]
+    fn test_to_toml() {
+        let options = [("OwnBook", String::from("true")), ("BookFile", String::new()), ("Move Overhead", String::from("10")), ("Contempt", String::from("-20"))];
+        let toml = to_toml(&options);
+        assert_eq!("# Ladybug configuration\nOwnBook = true\nBookFile = \"\"\n\"Move Overhead\" = 10\nContempt = -20\n", toml);
+
+        let parsed = parse(toml.as_str()).unwrap();
+        assert_eq!(options.iter().map(|(name, value)| (name.to_string(), value.clone())).collect::<Vec<(String, String)>>(), parsed);
+    }
+
+    #[test]
+    fn test_read_file() {
+        let path = std::env::temp_dir().join("ladybug_test_read_config.toml");
+        std::fs::write(&path, "Hash = 32\n").unwrap();
+        assert_eq!(vec![(String::from("Hash"), String::from("32"))], read_file(path.to_str().unwrap()).unwrap());
+        assert!(read_file("/this/file/does/not/exist.toml").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! The engine module lets Rust applications use Ladybug as a library, without the input, Ladybug and search threads
+//! of the UCI binary.
+//!
+//! An `Engine` keeps the current game and a search, and searches the current position when asked to:
+//!
+//! ```no_run
+//! use ladybug::engine::Engine;
+//!
+//! let best_move = Engine::new()
+//!     .set_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap()
+//!     .search_depth(8);
+//! ```
+//!
+//! The lookup tables are generated at compile time, so nothing has to be initialized beforehand.
+//! The searches run on a separate thread while the engine forwards their info lines, but each search call
+//! returns only once the search is finished. WebAssembly builds with the "wasm" feature can't spawn threads,
+//! so they search on the calling thread and forward the info lines afterwards. Info lines that don't fit into
+//! the message queue are dropped, but the best move is always returned.
+
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+#[cfg(not(feature = "wasm"))]
+use std::thread;
+use crate::board::Board;
+use crate::board::fen::FenError;
+use crate::game::Game;
+use crate::ladybug::{Message, MESSAGE_CHANNEL_CAPACITY};
+use crate::move_gen::ply::Ply;
+use crate::search::{Search, SearchCommand};
+
+/// The limits a search of the engine can be given.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SearchLimit {
+    /// Search until the given depth is reached.
+    Depth(u64),
+    /// Search for the given amount of milliseconds.
+    Time(u64),
+    /// Search until the given number of nodes is searched.
+    Nodes(u64),
+}
+
+/// Ladybug as a library: the current game and the search that searches its positions.
+pub struct Engine {
+    /// The game whose current position is searched.
+    game: Game,
+    /// The search, which runs on its own thread during each search call.
+    search: Search,
+    /// Used to send commands to the search.
+    command_sender: Sender<SearchCommand>,
+    /// Used to receive the output of the search.
+    message_receiver: Receiver<Message>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    /// Constructs an engine with the starting position and the default settings of the search.
+    pub fn new() -> Engine {
+        let (command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+        let mut search = Search::new(command_receiver, message_sender);
+        search.set_synchronous(cfg!(feature = "wasm"));
+        Engine {
+            game: Game::new(),
+            search,
+            command_sender,
+            message_receiver,
+        }
+    }
+
+    /// Starts a new game from the position described by the given FEN string.
+    /// If the FEN could not be parsed, the game is not changed and the result contains an error.
+    pub fn set_fen(&mut self, fen: &str) -> Result<&mut Engine, FenError> {
+        self.game = Game::from_fen(fen)?;
+        self.send_search(SearchCommand::NewGame);
+        Ok(self)
+    }
+
+    /// Plays the given move in pure algebraic coordinate notation, e.g. "e2e4" or "e7e8q".
+    /// If the move is not legal, the game is not changed and the result contains an error.
+    pub fn play_move(&mut self, move_str: &str) -> Result<&mut Engine, String> {
+        let ply = self.game.parse_move(move_str).map_err(|error| error.to_string())?;
+        self.game.make_move(ply)?;
+        Ok(self)
+    }
+
+    /// Returns the game whose current position is searched.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Returns the current board.
+    pub fn board(&self) -> Board {
+        self.game.board()
+    }
+
+    /// Resizes the transposition table of the following searches to the given number of megabytes.
+    pub fn set_hash_size(&mut self, size_mb: usize) -> &mut Engine {
+        self.send_search(SearchCommand::HashSize(size_mb));
+        self
+    }
+
+    /// Sets the number of threads that the following searches to a fixed depth split the root moves among.
+    /// WebAssembly builds always search with a single thread.
+    pub fn set_threads(&mut self, threads: usize) -> &mut Engine {
+        self.send_search(SearchCommand::Threads(threads));
+        self
+    }
+
+    /// Searches the current position to the given depth and returns the best move,
+    /// or None if there are no legal moves.
+    pub fn search_depth(&mut self, depth: u64) -> Option<Ply> {
+        self.search(SearchLimit::Depth(depth), |_info| {})
+    }
+
+    /// Searches the current position for the given amount of milliseconds and returns the best move,
+    /// or None if there are no legal moves.
+    pub fn search_time(&mut self, time: u64) -> Option<Ply> {
+        self.search(SearchLimit::Time(time), |_info| {})
+    }
+
+    /// Searches the current position until the given number of nodes is searched and returns the best move,
+    /// or None if there are no legal moves.
+    pub fn search_nodes(&mut self, nodes: u64) -> Option<Ply> {
+        self.search(SearchLimit::Nodes(nodes), |_info| {})
+    }
+
+    /// Searches the current position with the given limit and returns the best move, or None if there are no legal moves.
+    /// The info lines of the search, e.g. "info depth 5 ...", are passed to the given callback while the search is running.
+    pub fn search(&mut self, limit: SearchLimit, on_info: impl FnMut(&str)) -> Option<Ply> {
+        if self.game.legal_moves().is_empty() {
+            return None;
+        }
+
+        let (board, board_history) = (self.game.board(), self.game.board_history().clone());
+        self.send_search(match limit {
+            SearchLimit::Depth(depth) => SearchCommand::SearchDepth(board, board_history, depth),
+            SearchLimit::Time(time) => SearchCommand::SearchTime(board, board_history, time),
+            SearchLimit::Nodes(nodes) => SearchCommand::SearchNodes(board, board_history, nodes),
+        });
+        // the search thread terminates after the search, so that the search is available for the next call
+        self.send_search(SearchCommand::Quit);
+
+        #[cfg(not(feature = "wasm"))]
+        {
+            let (search, message_receiver) = (&mut self.search, &self.message_receiver);
+            thread::scope(|scope| {
+                scope.spawn(|| search.run());
+                read_best_move(message_receiver.iter(), board, on_info)
+            })
+        }
+        #[cfg(feature = "wasm")]
+        {
+            // the search handles the queued search and quits afterwards, so that it returns once the search is finished
+            self.search.run();
+            // if the queue was full, the search kept the best move instead of sending it
+            let messages = self.message_receiver.try_iter().chain(self.search.take_unsent_best_move());
+            read_best_move(messages, board, on_info)
+        }
+    }
+
+    /// Sends the given command to the search, which handles it when it runs the next time.
+    fn send_search(&self, command: SearchCommand) {
+        // the engine owns the search and its receiver, so the channel can't be closed
+        let _ = self.command_sender.send(command);
+    }
+}
+
+/// Passes the info lines among the given messages of the search to the given callback, until the search sends its best move.
+/// Returns the best move, or None if the search didn't send one.
+pub(crate) fn read_best_move(messages: impl Iterator<Item = Message>, board: Board, mut on_info: impl FnMut(&str)) -> Option<Ply> {
+    for message in messages {
+        let Message::SearchMessage(output) = message else {
+            continue;
+        };
+        match output.strip_prefix("bestmove ") {
+            Some(best_move) => return Ply::from_string(best_move, board.position).ok(),
+            None => on_info(output.as_str()),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{Engine, SearchLimit};
+
+    #[test]
+    fn test_search_depth() {
+        let mut engine = Engine::new();
+        let best_move = engine.search_depth(3);
+        assert!(best_move.is_some_and(|ply| engine.board().position.is_legal_move(ply)));
+
+        // the same engine can search again
+        let best_move = engine.play_move("e2e4").unwrap().search_depth(3);
+        assert!(best_move.is_some_and(|ply| engine.board().position.is_legal_move(ply)));
+    }
+
+    #[test]
+    fn test_search_finds_mate_in_1() {
+        let best_move = Engine::new()
+            .set_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap()
+            .search_nodes(10000);
+        assert_eq!("a1a8", best_move.unwrap().to_string());
+    }
+
+    #[test]
+    fn test_search_without_legal_moves() {
+        let mut engine = Engine::new();
+        engine.set_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(None, engine.search_time(100));
+    }
+
+    #[test]
+    fn test_search_passes_info_lines_to_the_callback() {
+        let mut engine = Engine::new();
+        let mut info_lines = Vec::new();
+        let best_move = engine.search(SearchLimit::Depth(2), |info| info_lines.push(info.to_string()));
+        assert!(best_move.is_some());
+        assert!(info_lines.iter().any(|info| info.starts_with("info depth 2 ")), "{info_lines:?}");
+        assert!(!info_lines.iter().any(|info| info.starts_with("bestmove")));
+    }
+
+    #[test]
+    fn test_set_fen_and_play_move_reject_invalid_input() {
+        let mut engine = Engine::new();
+        assert!(engine.set_fen("not a fen").is_err());
+        assert!(engine.play_move("e2e5").is_err());
+        assert!(engine.game().moves().is_empty());
+        assert!(engine.play_move("e2e4").is_ok());
+        assert_eq!("e2e4", engine.game().last_move().unwrap().to_string());
+    }
+}
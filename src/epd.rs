@@ -0,0 +1,185 @@
+//! The epd module reads test suites in the [EPD](https://www.chessprogramming.org/Extended_Position_Description) format.
+//! Test suites such as WAC or STS are used to measure the quality of the search, by counting how many of the expected best moves are found.
+//! EPD files are also used as opening suites, whose positions don't need any opcodes.
+
+use std::fs;
+use crate::board::Board;
+use crate::move_gen::ply::Ply;
+
+/// A single position of an EPD test suite.
+#[derive(Clone, PartialEq, Debug)]
+pub struct EpdEntry {
+    /// The position to search.
+    pub board: Board,
+    /// The id of the position, if one was provided.
+    pub id: Option<String>,
+    /// The best moves for the position ("bm" opcode). Any of them solves the position.
+    pub best_moves: Vec<Ply>,
+    /// The moves to avoid in the position ("am" opcode). None of them may be played.
+    pub avoid_moves: Vec<Ply>,
+}
+
+impl EpdEntry {
+    /// Parses a single line of an EPD file.
+    ///
+    /// The line consists of the first four fields of a FEN, followed by opcodes that are separated by semicolons,
+    /// e.g. `r1b1k2r/ppppnppp/2n2q2/2b5/3NP3/2P1B3/PP3PPP/RN1QKB1R w KQkq - bm Nxc6; id "example";`.
+    /// Moves are given in standard algebraic notation. Opcodes other than "bm", "am" and "id" are ignored.
+    pub fn parse(line: &str) -> Result<EpdEntry, String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 {
+            return Err(String::from("Invalid EPD"));
+        }
+
+        // the first four fields describe the position
+        let board = Board::from_fen(fields[0..4].join(" ").as_str()).map_err(|error| error.to_string())?;
+
+        let mut entry = EpdEntry {
+            board,
+            id: None,
+            best_moves: Vec::new(),
+            avoid_moves: Vec::new(),
+        };
+
+        // the remaining fields are the opcodes
+        let operations = fields[4..].join(" ");
+        for operation in operations.split(';') {
+            let mut parts = operation.split_whitespace();
+            let opcode = match parts.next() {
+                None => continue,
+                Some(opcode) => opcode,
+            };
+            match opcode {
+                "bm" | "am" => {
+                    let mut moves = Vec::new();
+                    for san in parts {
+                        match Ply::from_san(san, board.position) {
+                            None => return Err(format!("Invalid move {san} in EPD")),
+                            Some(ply) => moves.push(ply),
+                        }
+                    }
+                    if opcode == "bm" {
+                        entry.best_moves = moves;
+                    } else {
+                        entry.avoid_moves = moves;
+                    }
+                }
+                "id" => entry.id = Some(parts.collect::<Vec<&str>>().join(" ").trim_matches('"').to_string()),
+                _other => {}
+            }
+        }
+
+        if entry.best_moves.is_empty() && entry.avoid_moves.is_empty() {
+            return Err(String::from("EPD contains neither best moves nor moves to avoid"));
+        }
+
+        Ok(entry)
+    }
+
+    /// Returns true if the given move solves the position.
+    pub fn is_solved_by(&self, ply: Ply) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(&ply)) && !self.avoid_moves.contains(&ply)
+    }
+}
+
+/// Reads all positions from the given EPD file. Empty lines and lines starting with '#' are skipped.
+pub fn read_file(path: &str) -> Result<Vec<EpdEntry>, String> {
+    let content = match fs::read_to_string(path) {
+        Err(_) => return Err(String::from("Could not read EPD file")),
+        Ok(content) => content,
+    };
+
+    content.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(EpdEntry::parse)
+        .collect()
+}
+
+/// Reads the positions of the given EPD file, e.g. an opening suite, ignoring all opcodes.
+/// Empty lines and lines starting with '#' are skipped.
+pub fn read_positions(path: &str) -> Result<Vec<Board>, String> {
+    let content = match fs::read_to_string(path) {
+        Err(_) => return Err(String::from("Could not read EPD file")),
+        Ok(content) => content,
+    };
+
+    content.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+            Board::from_fen(fields.join(" ").as_str()).map_err(|error| error.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::epd::{EpdEntry, read_file, read_positions};
+    use crate::move_gen::ply::Ply;
+
+    #[test]
+    fn test_parse() {
+        let entry = EpdEntry::parse("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";").unwrap();
+        let board = Board::from_fen("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1").unwrap();
+        assert_eq!(board, entry.board);
+        assert_eq!(Some(String::from("WAC.001")), entry.id);
+        assert_eq!(vec![Ply::from_string("g3g6", board.position).unwrap()], entry.best_moves);
+        assert!(entry.avoid_moves.is_empty());
+
+        // multiple moves, additional opcodes, and an id with spaces
+        let entry = EpdEntry::parse("r1b1k2r/ppppnppp/2n2q2/2b5/3NP3/2P1B3/PP3PPP/RN1QKB1R w KQkq - am Nb5 Nf3; bm Nxc6 Be2; c0 \"comment\"; id \"test position\";").unwrap();
+        let position = entry.board.position;
+        assert_eq!(vec![Ply::from_string("d4c6", position).unwrap(), Ply::from_string("f1e2", position).unwrap()], entry.best_moves);
+        assert_eq!(vec![Ply::from_string("d4b5", position).unwrap(), Ply::from_string("d4f3", position).unwrap()], entry.avoid_moves);
+        assert_eq!(Some(String::from("test position")), entry.id);
+    }
+
+    #[test]
+    fn test_parse_with_invalid_epd_returns_error() {
+        assert!(EpdEntry::parse("").is_err());
+        assert!(EpdEntry::parse("this is no epd").is_err());
+        assert!(EpdEntry::parse("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - -").is_err());
+        assert!(EpdEntry::parse("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qh8;").is_err());
+        assert!(EpdEntry::parse("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - id \"no moves\";").is_err());
+    }
+
+    #[test]
+    fn test_is_solved_by() {
+        let entry = EpdEntry::parse("r1b1k2r/ppppnppp/2n2q2/2b5/3NP3/2P1B3/PP3PPP/RN1QKB1R w KQkq - bm Nxc6 Be2;").unwrap();
+        let position = entry.board.position;
+        assert!(entry.is_solved_by(Ply::from_string("d4c6", position).unwrap()));
+        assert!(entry.is_solved_by(Ply::from_string("f1e2", position).unwrap()));
+        assert!(!entry.is_solved_by(Ply::from_string("d4b5", position).unwrap()));
+
+        let entry = EpdEntry::parse("r1b1k2r/ppppnppp/2n2q2/2b5/3NP3/2P1B3/PP3PPP/RN1QKB1R w KQkq - am Nb5;").unwrap();
+        assert!(entry.is_solved_by(Ply::from_string("d4c6", position).unwrap()));
+        assert!(!entry.is_solved_by(Ply::from_string("d4b5", position).unwrap()));
+    }
+
+    #[test]
+    fn test_read_file() {
+        let path = std::env::temp_dir().join("ladybug_test_read_file.epd");
+        std::fs::write(&path, "# a comment\n\n2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";\n8/7p/5k2/5p2/p1p2P2/Pr1pPK2/1P1R3P/8 b - - bm Rxb2; id \"WAC.002\";\n").unwrap();
+        let entries = read_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!(Some(String::from("WAC.002")), entries[1].id);
+
+        assert!(read_file("/this/file/does/not/exist.epd").is_err());
+    }
+
+    #[test]
+    fn test_read_positions() {
+        let path = std::env::temp_dir().join("ladybug_test_read_positions.epd");
+        std::fs::write(&path, "# an opening suite\nrnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3\n\nrnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 c0 \"Sicilian\";\n").unwrap();
+        let positions = read_positions(path.to_str().unwrap()).unwrap();
+        assert_eq!(2, positions.len());
+        assert_eq!("rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 1", positions[1].to_fen());
+
+        std::fs::write(&path, "this is not a position\n").unwrap();
+        assert!(read_positions(path.to_str().unwrap()).is_err());
+        assert!(read_positions("/this/file/does/not/exist.epd").is_err());
+    }
+}
@@ -1,37 +1,56 @@
-use crate::board::color::{Color, NUM_COLORS};
-use crate::board::piece::{NUM_PIECES, Piece};
+use crate::board::color::Color;
+use crate::board::dirty_pieces::DirtyPieces;
 use crate::board::position::Position;
+use crate::evaluation::scaling::SCALE_NORMAL;
 
+pub mod bishops;
+pub mod coordination;
+pub mod kpk;
 pub mod pst;
+pub mod params;
+pub mod passed_pawns;
+pub mod scaling;
+pub mod threats;
+pub mod tuner;
+pub mod wdl;
+
 /// The highest possible value.
 pub const POSITIVE_INFINITY: i32 = i32::MAX - 1;
 /// The lowest possible value.
 pub const NEGATIVE_INFINITY: i32 = i32::MIN + 1;
 
+/// The bonus for the side to move, since having the move is usually worth something.
+/// Without it, the evaluation would swing back and forth between odd and even search depths.
+pub const TEMPO: i32 = 10;
+
 
 /// Returns the static evaluation for the given position.
 ///
 /// The evaluation is always done from the point of view of the side whose turn it is.
 /// E.g. if it is Black's turn, and black is up a queen, the evaluation will return +900, 
 /// even though chess players usually refer to such a position, from White's point of view, as -9.
+///
+/// In drawish endgames, the evaluation is scaled towards a draw (see the scaling module).
+/// Endgames of a king and pawn against a king are evaluated exactly with the KPK bitbase (see the kpk module).
 pub fn evaluate(position: Position) -> i32 {
-    evaluate_material(position)
+    let score = evaluate_material(position) + evaluate_positional(position);
+    if let Some((strong_color, is_win)) = kpk::probe(position) {
+        return match (is_win, strong_color == position.color_to_move) {
+            (false, _) => 0,
+            (true, true) => score + kpk::KNOWN_WIN,
+            (true, false) => score - kpk::KNOWN_WIN,
+        };
+    }
+    let strong_color = match score >= 0 {
+        true => position.color_to_move,
+        false => position.color_to_move.other(),
+    };
+    score * scaling::scale_factor(position, strong_color) / SCALE_NORMAL
 }
 
 /// Returns the purely materialistic evaluation of the position.
 fn evaluate_material(position: Position) -> i32 {
-    let mut material_score: i32 = 0;
-    for color_index in 0..NUM_COLORS {
-        for piece_index in 0..NUM_PIECES {
-            let active_bits = position.pieces[color_index as usize][piece_index as usize].get_active_bits();
-            for square in active_bits {
-                match Color::from_index(color_index) {
-                    Color::White => material_score += pst::get_piece_value(Piece::from_index(piece_index), square, Color::from_index(color_index)),
-                    Color::Black => material_score -= pst::get_piece_value(Piece::from_index(piece_index), square, Color::from_index(color_index)),
-                }
-            }
-        }
-    }
+    let mut material_score = evaluate_material_for(position, Color::White) - evaluate_material_for(position, Color::Black);
     // if it is Black's move, negate the material score so that the evaluation is from Black's perspective
     if position.color_to_move == Color::Black {
         material_score = -material_score;
@@ -39,19 +58,176 @@ fn evaluate_material(position: Position) -> i32 {
     material_score
 }
 
+/// Returns the evaluation of the passed pawns, the coordination of the pieces, the bishops and the threats of both colors plus the tempo bonus,
+/// from the point of view of the side to move.
+fn evaluate_positional(position: Position) -> i32 {
+    let params = params::EVAL_PARAMS.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let score = passed_pawns::evaluate(position, Color::White, &params) - passed_pawns::evaluate(position, Color::Black, &params)
+        + coordination::evaluate(position, Color::White, &params) - coordination::evaluate(position, Color::Black, &params)
+        + bishops::evaluate(position, Color::White, &params) - bishops::evaluate(position, Color::Black, &params)
+        + threats::evaluate(position, Color::White, &params) - threats::evaluate(position, Color::Black, &params);
+    match position.color_to_move {
+        Color::White => score + params.tempo,
+        Color::Black => -score + params.tempo,
+    }
+}
+
+/// Returns the material of the given color, including the piece-square-table bonuses.
+fn evaluate_material_for(position: Position, color: Color) -> i32 {
+    let params = params::EVAL_PARAMS.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    position.color_pieces_iter(color).map(|(square, piece, _color)| params.get_piece_value(piece, square, color)).sum()
+}
+
+/// The terms that make up the static evaluation of one color.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct EvaluationTerms {
+    /// The sum of the base values of the pieces.
+    pub material: i32,
+    /// The sum of the piece-square-table bonuses of the pieces.
+    pub pst: i32,
+    /// The bonuses and penalties of the passed pawns.
+    pub passed_pawns: i32,
+    /// The bonuses for rooks and queens on the seventh rank, doubled rooks, batteries and x-rays.
+    pub coordination: i32,
+    /// The bonus for the bishop pair and the penalty for bad bishops, weighted by the game phase.
+    pub bishops: i32,
+    /// The bonuses for hanging enemy pieces, enemy pieces attacked by lesser pieces and pawn pushes that fork enemy pieces.
+    pub threats: i32,
+    /// The bonus for having the move, which only the side to move gets.
+    pub tempo: i32,
+}
+
+impl EvaluationTerms {
+    /// Returns the sum of all terms.
+    pub fn total(&self) -> i32 {
+        self.material + self.pst + self.passed_pawns + self.coordination + self.bishops + self.threats + self.tempo
+    }
+}
+
+/// Returns the terms of the static evaluation of the given color, which explain how the evaluation is made up.
+/// This is slower than `evaluate`, and only meant to be shown to the user.
+pub fn evaluation_terms(position: Position, color: Color) -> EvaluationTerms {
+    let params = params::EVAL_PARAMS.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut terms = EvaluationTerms::default();
+    for (square, piece, _color) in position.color_pieces_iter(color) {
+        let material = params.piece_values[piece.to_index() as usize];
+        terms.material += material;
+        terms.pst += params.get_piece_value(piece, square, color) - material;
+    }
+    terms.passed_pawns = passed_pawns::evaluate(position, color, &params);
+    terms.coordination = coordination::evaluate(position, color, &params);
+    terms.bishops = bishops::evaluate(position, color, &params);
+    terms.threats = threats::evaluate(position, color, &params);
+    if color == position.color_to_move {
+        terms.tempo = params.tempo;
+    }
+    terms
+}
+
+/// Returns the change of the material evaluation caused by a move, from White's point of view.
+/// This allows updating the material evaluation incrementally from the dirty pieces of the move,
+/// instead of evaluating the new position from scratch.
+pub fn evaluate_dirty_pieces(dirty_pieces: &DirtyPieces) -> i32 {
+    let params = params::EVAL_PARAMS.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut delta = 0;
+    for dirty_piece in dirty_pieces.iter() {
+        let sign = match dirty_piece.color {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        if let Some(square) = dirty_piece.from {
+            delta -= sign * params.get_piece_value(dirty_piece.piece, square, dirty_piece.color);
+        }
+        if let Some(square) = dirty_piece.to {
+            delta += sign * params.get_piece_value(dirty_piece.piece, square, dirty_piece.color);
+        }
+    }
+    delta
+}
+
 #[cfg(test)]
 mod tests {
     use crate::board::Board;
-    use crate::evaluation::{evaluate_material};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
+    use crate::board::color::Color;
+    use crate::evaluation::{evaluate, evaluate_dirty_pieces, evaluate_material, evaluate_material_for, evaluation_terms, TEMPO};
+    use crate::move_gen;
+
+    /// Returns the FEN of the given position with the colors swapped: the board is mirrored vertically,
+    /// the pieces change their color, and the other side is to move.
+    fn flip_colors(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let swap_case = |c: char| match c.is_ascii_uppercase() {
+            true => c.to_ascii_lowercase(),
+            false => c.to_ascii_uppercase(),
+        };
+        let board = fields[0].split('/').rev().map(|rank| rank.chars().map(swap_case).collect::<String>()).collect::<Vec<String>>().join("/");
+        let color = match fields[1] {
+            "w" => "b",
+            _other => "w",
+        };
+        let castling: String = match fields[2] {
+            "-" => String::from("-"),
+            rights => "KQkq".chars().filter(|c| rights.contains(swap_case(*c))).collect(),
+        };
+        let en_passant = match fields[3] {
+            "-" => String::from("-"),
+            square => square.replace('3', "x").replace('6', "3").replace('x', "6"),
+        };
+        format!("{board} {color} {castling} {en_passant} {} {}", fields[4], fields[5])
+    }
+
+    /// Returns the FEN of the given position mirrored horizontally, i.e. with the queen side and the king side swapped.
+    /// The castling rights are removed, since they can't be mirrored.
+    fn mirror_files(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let board = fields[0].split('/').map(|rank| rank.chars().rev().collect::<String>()).collect::<Vec<String>>().join("/");
+        let en_passant = match fields[3] {
+            "-" => String::from("-"),
+            square => square.chars().map(|c| match c.is_ascii_lowercase() {
+                true => (b'h' - (c as u8 - b'a')) as char,
+                false => c,
+            }).collect(),
+        };
+        format!("{board} {} - {en_passant} {} {}", fields[1], fields[4], fields[5])
+    }
 
     #[test]
-    fn test_evaluate_material() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
+    fn test_evaluation_symmetry() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r1b2rk1/pp3ppp/2n1pn2/2pp4/3P4/2PBPN2/P4PPP/R1B2RK1 b - - 0 10",
+            "3r2k1/5p2/1P6/8/3p4/8/5PPP/1R4K1 b - - 0 1",
+            "8/2k5/8/3PK3/8/8/8/8 w - - 0 1",
+            "8/3k4/8/3K4/3P4/8/8/8 w - - 0 1",
+            "3k4/8/3K4/3P4/8/8/8/8 b - - 0 1",
+            "8/5k2/4b3/1p6/PP6/8/2K5/2B5 w - - 0 1",
+            "7k/8/6KP/8/8/8/8/3B4 w - - 0 1",
+            "3k4/7r/8/3PK3/8/8/8/R7 b - - 0 1",
+            "8/5k2/4b3/8/8/8/2K5/3R4 w - - 0 1",
+        ];
+
+        for fen in fens {
+            let position = Board::from_fen(fen).unwrap().position;
+            let flipped = Board::from_fen(flip_colors(fen).as_str()).unwrap().position;
 
+            // the evaluation is from the point of view of the side to move, so it is the same after swapping the colors
+            assert_eq!(evaluate(position), evaluate(flipped), "{fen}");
+            assert_eq!(evaluation_terms(position, Color::White), evaluation_terms(flipped, Color::Black), "{fen}");
+            assert_eq!(evaluation_terms(position, Color::Black), evaluation_terms(flipped, Color::White), "{fen}");
+
+            // the queen PST is not symmetric between the queen side and the king side, so only positions without queens are mirrored
+            if !fen.split_whitespace().next().unwrap().contains(['q', 'Q']) {
+                let mirrored = Board::from_fen(mirror_files(fen).as_str()).unwrap().position;
+                assert_eq!(evaluate(position), evaluate(mirrored), "{fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_material() {
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(0, evaluate_material(position));
 
@@ -71,4 +247,48 @@ mod tests {
         let position = Board::from_fen("rnbqkb1r/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap().position;
         assert!(evaluate_material(position) < -200);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_evaluation_terms() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1").unwrap().position;
+        let white = evaluation_terms(position, Color::White);
+        let black = evaluation_terms(position, Color::Black);
+        assert_eq!(black.material - 950, white.material);
+        assert_eq!(evaluate_material_for(position, Color::White) + white.bishops + TEMPO, white.total());
+        assert_eq!(evaluate_material_for(position, Color::Black) + black.bishops, black.total());
+
+        // the passed d-pawn is part of the terms as well as the evaluation
+        let position = Board::from_fen("1n2k3/8/8/3P4/8/8/8/1N2K3 w - - 0 1").unwrap().position;
+        let white = evaluation_terms(position, Color::White);
+        let black = evaluation_terms(position, Color::Black);
+        assert!(white.passed_pawns > 0);
+        assert_eq!(0, black.passed_pawns);
+        assert_eq!(evaluate_material_for(position, Color::White) + white.passed_pawns + TEMPO, white.total());
+        assert_eq!(white.total() - black.total(), evaluate(position));
+    }
+
+    #[test]
+    fn test_evaluate_dirty_pieces() {
+        // the incrementally updated evaluation must match the evaluation from scratch for all moves,
+        // including castling, en passant and promotions with captures
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 2",
+        ];
+        for fen in fens {
+            let position = Board::from_fen(fen).unwrap().position;
+            let material = evaluate_material_for(position, Color::White) - evaluate_material_for(position, Color::Black);
+            let move_list = move_gen::generate_moves(position);
+            for i in 0..move_list.len() {
+                let (new_position, dirty_pieces) = position.make_move_with_dirty_pieces(move_list.get(i));
+                let new_material = evaluate_material_for(new_position, Color::White) - evaluate_material_for(new_position, Color::Black);
+                assert_eq!(new_material, material + evaluate_dirty_pieces(&dirty_pieces));
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! The bishops module evaluates the [bishop pair](https://www.chessprogramming.org/Bishop_Pair) and
+//! [bad bishops](https://www.chessprogramming.org/Bad_Bishop).
+//!
+//! A side with bishops on both square colors gets a bonus, and each bishop gets a penalty for every own pawn that is fixed
+//! on the bishop's square color, i.e. blocked by an enemy pawn, since the bishop can't attack the squares the pawn covers.
+//! Both terms matter more in the endgame, so their weights are interpolated between a middlegame and an endgame value
+//! according to the game phase.
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::{LIGHT_SQUARES, MAX_GAME_PHASE, Position};
+use crate::evaluation::params::EvalParams;
+
+/// The bonus for the bishop pair in the middlegame.
+pub const BISHOP_PAIR_MIDDLEGAME: i32 = 25;
+/// The bonus for the bishop pair in the endgame.
+pub const BISHOP_PAIR_ENDGAME: i32 = 50;
+/// The bonus for each own pawn fixed on the square color of a bishop in the middlegame, which is negative.
+pub const BAD_BISHOP_MIDDLEGAME: i32 = -3;
+/// The bonus for each own pawn fixed on the square color of a bishop in the endgame, which is negative.
+pub const BAD_BISHOP_ENDGAME: i32 = -8;
+
+/// How often each bishop parameter contributes to the evaluation of one color, before the game phase is applied.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct BishopFeatures {
+    /// 1 if the color has bishops on both square colors, 0 otherwise.
+    pub pair: i32,
+    /// The number of own pawns fixed on the square color of each bishop, summed over all bishops.
+    pub fixed_pawns: i32,
+}
+
+impl BishopFeatures {
+    /// Returns the evaluation of the features with the given parameters in the given game phase.
+    pub fn evaluate(&self, params: &EvalParams, phase: u8) -> i32 {
+        let [pair_middlegame, pair_endgame, fixed_middlegame, fixed_endgame] = self.weighted_counts(phase);
+        (pair_middlegame * params.bishop_pair_middlegame + pair_endgame * params.bishop_pair_endgame
+            + fixed_middlegame * params.bad_bishop_middlegame + fixed_endgame * params.bad_bishop_endgame) / MAX_GAME_PHASE as i32
+    }
+
+    /// Returns the counts of the middlegame and endgame parameters of the bishop pair and of bad bishops, in this order,
+    /// weighted by the game phase. The evaluation is their weighted sum divided by `MAX_GAME_PHASE`.
+    pub fn weighted_counts(&self, phase: u8) -> [i32; 4] {
+        let middlegame = phase.min(MAX_GAME_PHASE) as i32;
+        let endgame = MAX_GAME_PHASE as i32 - middlegame;
+        [self.pair * middlegame, self.pair * endgame, self.fixed_pawns * middlegame, self.fixed_pawns * endgame]
+    }
+}
+
+/// Returns the evaluation of the bishops of the given color with the given parameters.
+pub fn evaluate(position: Position, color: Color, params: &EvalParams) -> i32 {
+    features(position, color).evaluate(params, position.game_phase())
+}
+
+/// Returns how often each bishop parameter contributes to the evaluation of the given color.
+pub fn features(position: Position, color: Color) -> BishopFeatures {
+    let bishops = position.piece_bb(color, Piece::Bishop).value;
+    let own_pawns = position.piece_bb(color, Piece::Pawn).value;
+    let enemy_pawns = position.piece_bb(color.other(), Piece::Pawn).value;
+
+    // the own pawns with an enemy pawn directly in front of them
+    let fixed_pawns = match color {
+        Color::White => own_pawns & (enemy_pawns >> 8),
+        Color::Black => own_pawns & (enemy_pawns << 8),
+    };
+
+    let mut features = BishopFeatures::default();
+    for square_color in [LIGHT_SQUARES, !LIGHT_SQUARES] {
+        let count = (bishops & square_color).count_ones() as i32;
+        features.fixed_pawns += count * (fixed_pawns & square_color).count_ones() as i32;
+    }
+    if bishops & LIGHT_SQUARES != 0 && bishops & !LIGHT_SQUARES != 0 {
+        features.pair = 1;
+    }
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::evaluation::bishops::{evaluate, features, BishopFeatures};
+    use crate::evaluation::params::EvalParams;
+
+    fn features_of(fen: &str, color: Color) -> BishopFeatures {
+        features(Board::from_fen(fen).unwrap().position, color)
+    }
+
+    #[test]
+    fn test_bishop_pair() {
+        assert_eq!(1, features_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Color::White).pair);
+        assert_eq!(1, features_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Color::Black).pair);
+
+        // two bishops on the same square color are no bishop pair
+        assert_eq!(0, features_of("4k3/8/8/8/8/8/8/B1B1K3 w - - 0 1", Color::White).pair);
+        assert_eq!(0, features_of("4k3/8/8/8/8/8/8/B3K3 w - - 0 1", Color::White).pair);
+    }
+
+    #[test]
+    fn test_bad_bishop() {
+        // the pawns on d4 and e5 are fixed by the pawns in front of them, both on the dark squares of the bishop on c1
+        let fen = "4k3/8/4p3/3pP3/3P4/8/8/2B1K3 w - - 0 1";
+        assert_eq!(2, features_of(fen, Color::White).fixed_pawns);
+        assert_eq!(0, features_of("4k3/8/4p3/3pP3/3P4/8/8/4KB2 w - - 0 1", Color::White).fixed_pawns);
+        // Black has fixed pawns as well, but no bishop
+        assert_eq!(0, features_of(fen, Color::Black).fixed_pawns);
+
+        // a pawn that can still advance is not fixed
+        assert_eq!(0, features_of("4k3/8/8/8/4P3/8/8/2B1K3 w - - 0 1", Color::White).fixed_pawns);
+
+        // for Black, the square in front of a pawn is below it
+        assert_eq!(1, features_of("2b1k3/8/8/3p4/3P4/8/8/4K3 b - - 0 1", Color::Black).fixed_pawns);
+    }
+
+    #[test]
+    fn test_game_phase() {
+        let params = EvalParams::DEFAULT;
+        let pair = BishopFeatures { pair: 1, fixed_pawns: 0 };
+        assert_eq!(params.bishop_pair_middlegame, pair.evaluate(&params, 24));
+        assert_eq!(params.bishop_pair_endgame, pair.evaluate(&params, 0));
+        assert_eq!((params.bishop_pair_middlegame + params.bishop_pair_endgame) / 2, pair.evaluate(&params, 12));
+
+        // the bishop pair is worth more once the queens and rooks are traded
+        let middlegame = evaluate(Board::from_fen("r1bqkb1r/pppppppp/8/8/8/8/PPPPPPPP/R1BQKB1R w KQkq - 0 1").unwrap().position, Color::White, &params);
+        let endgame = evaluate(Board::from_fen("2b1kb2/pppppppp/8/8/8/8/PPPPPPPP/2B1KB2 w - - 0 1").unwrap().position, Color::White, &params);
+        assert!(endgame > middlegame);
+    }
+}
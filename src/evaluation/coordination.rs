@@ -0,0 +1,172 @@
+//! The coordination module evaluates how well the rooks, queens and bishops of a side work together.
+//!
+//! Rooks and queens on the seventh rank get a bonus if the enemy king is confined to the back rank or enemy pawns are still on
+//! their home rank. Two rooks that defend each other on a file are doubled, and a rook and a queen that defend each other
+//! on a file or rank form a battery. Sliders that would attack the enemy king or queen if a single piece between them was gone
+//! put x-ray pressure on them, e.g. a rook behind a pinned piece.
+//! All terms are computed from the slider attacks of the lookup table.
+
+use crate::board::bitboard::Bitboard;
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::board::square::Square;
+use crate::evaluation::params::EvalParams;
+use crate::lookup::LOOKUP_TABLE;
+
+/// The bonus for a rook on the seventh rank, from the point of view of the rook's color.
+pub const ROOK_ON_SEVENTH: i32 = 20;
+/// The bonus for a queen on the seventh rank, from the point of view of the queen's color.
+pub const QUEEN_ON_SEVENTH: i32 = 10;
+/// The bonus for two rooks that defend each other on a file.
+pub const DOUBLED_ROOKS: i32 = 15;
+/// The bonus for a rook and a queen that defend each other on a file or rank.
+pub const ROOK_QUEEN_BATTERY: i32 = 10;
+/// The bonus for each slider that x-rays the enemy king or queen through a single piece.
+pub const XRAY: i32 = 5;
+
+/// The bitboard of the a-file.
+const A_FILE: u64 = 0x0101_0101_0101_0101;
+
+/// The bitboard of the first rank.
+const FIRST_RANK: u64 = 0xFF;
+
+/// How often each coordination parameter contributes to the evaluation of one color.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct CoordinationFeatures {
+    /// The number of rooks on the seventh rank, if the enemy king is on the back rank or enemy pawns are on the seventh rank.
+    pub rook_on_seventh: i32,
+    /// The number of queens on the seventh rank, under the same condition as the rooks.
+    pub queen_on_seventh: i32,
+    /// The number of pairs of rooks that defend each other on a file.
+    pub doubled_rooks: i32,
+    /// The number of pairs of a rook and a queen that defend each other on a file or rank.
+    pub battery: i32,
+    /// The number of times a slider x-rays the enemy king or queen through a single piece.
+    pub xray: i32,
+}
+
+impl CoordinationFeatures {
+    /// Returns the evaluation of the features with the given parameters.
+    pub fn evaluate(&self, params: &EvalParams) -> i32 {
+        self.rook_on_seventh * params.rook_on_seventh
+            + self.queen_on_seventh * params.queen_on_seventh
+            + self.doubled_rooks * params.doubled_rooks
+            + self.battery * params.rook_queen_battery
+            + self.xray * params.xray
+    }
+}
+
+/// Returns the evaluation of the coordination of the pieces of the given color with the given parameters.
+pub fn evaluate(position: Position, color: Color, params: &EvalParams) -> i32 {
+    features(position, color).evaluate(params)
+}
+
+/// Returns how often each coordination parameter contributes to the evaluation of the given color.
+pub fn features(position: Position, color: Color) -> CoordinationFeatures {
+    let mut features = CoordinationFeatures::default();
+    let occupancy = position.get_occupancies();
+    let rooks = position.piece_bb(color, Piece::Rook).value;
+    let queens = position.piece_bb(color, Piece::Queen).value;
+
+    // the seventh and the back rank from the point of view of the color
+    let (seventh_rank, back_rank) = match color {
+        Color::White => (FIRST_RANK << 48, FIRST_RANK << 56),
+        Color::Black => (FIRST_RANK << 8, FIRST_RANK),
+    };
+    let enemy_king = position.piece_bb(color.other(), Piece::King).value;
+    let enemy_pawns = position.piece_bb(color.other(), Piece::Pawn).value;
+    if enemy_king & back_rank != 0 || enemy_pawns & seventh_rank != 0 {
+        features.rook_on_seventh = (rooks & seventh_rank).count_ones() as i32;
+        features.queen_on_seventh = (queens & seventh_rank).count_ones() as i32;
+    }
+
+    let mut doubled_rooks = 0;
+    for rook in Bitboard::new(rooks).get_active_bits() {
+        let attacks = LOOKUP_TABLE.get_rook_attacks(rook, occupancy).value;
+        doubled_rooks += (attacks & rooks & (A_FILE << rook.get_file().to_index())).count_ones() as i32;
+        features.battery += (attacks & queens).count_ones() as i32;
+    }
+    // each pair of doubled rooks is seen from both rooks
+    features.doubled_rooks = doubled_rooks / 2;
+
+    let targets = enemy_king | position.piece_bb(color.other(), Piece::Queen).value;
+    for piece in [Piece::Bishop, Piece::Rook, Piece::Queen] {
+        for square in position.piece_bb(color, piece).get_active_bits() {
+            features.xray += (xray_attacks(piece, square, occupancy) & targets).count_ones() as i32;
+        }
+    }
+    features
+}
+
+/// Returns the squares the given slider attacks through exactly one piece, which it would attack if the piece was gone.
+fn xray_attacks(piece: Piece, square: Square, occupancy: Bitboard) -> u64 {
+    let attacks = |blockers: Bitboard| match piece {
+        Piece::Bishop => LOOKUP_TABLE.get_bishop_attacks(square, blockers),
+        Piece::Rook => LOOKUP_TABLE.get_rook_attacks(square, blockers),
+        _other => LOOKUP_TABLE.get_queen_attacks(square, blockers),
+    }.value;
+    let direct_attacks = attacks(occupancy);
+    let first_blockers = direct_attacks & occupancy.value;
+    attacks(Bitboard::new(occupancy.value & !first_blockers)) & !direct_attacks
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::evaluation::coordination::{evaluate, features, CoordinationFeatures};
+    use crate::evaluation::params::EvalParams;
+
+    fn features_of(fen: &str, color: Color) -> CoordinationFeatures {
+        features(Board::from_fen(fen).unwrap().position, color)
+    }
+
+    #[test]
+    fn test_seventh_rank() {
+        // the king on the back rank is confined by the rook and the queen
+        let white = features_of("6k1/R3Q3/8/8/8/8/8/6K1 w - - 0 1", Color::White);
+        assert_eq!((1, 1), (white.rook_on_seventh, white.queen_on_seventh));
+
+        // without pawns on the seventh rank, a rook on it doesn't confine a king that left the back rank
+        assert_eq!(0, features_of("8/R7/7k/8/8/8/8/6K1 w - - 0 1", Color::White).rook_on_seventh);
+        assert_eq!(1, features_of("8/R4pk1/8/8/8/8/8/6K1 w - - 0 1", Color::White).rook_on_seventh);
+
+        // for Black, the seventh rank is the second rank
+        assert_eq!(1, features_of("6k1/8/8/8/8/8/r7/6K1 b - - 0 1", Color::Black).rook_on_seventh);
+    }
+
+    #[test]
+    fn test_doubled_rooks_and_battery() {
+        let white = features_of("6k1/8/8/8/3R4/8/8/3RK3 w - - 0 1", Color::White);
+        assert_eq!((1, 0), (white.doubled_rooks, white.battery));
+
+        // a piece between the rooks breaks the connection, rooks on a rank are not doubled
+        assert_eq!(0, features_of("6k1/8/8/8/3R4/3N4/8/3RK3 w - - 0 1", Color::White).doubled_rooks);
+        assert_eq!(0, features_of("6k1/8/8/8/8/8/8/R2R2K1 w - - 0 1", Color::White).doubled_rooks);
+
+        // the queen forms a battery with the rook on its file, and with the rook on its rank
+        let white = features_of("6k1/8/8/8/3Q4/8/8/3R1RK1 w - - 0 1", Color::White);
+        assert_eq!((0, 1), (white.doubled_rooks, white.battery));
+        assert_eq!(2, features_of("6k1/8/8/8/3Q3R/8/8/3R2K1 w - - 0 1", Color::White).battery);
+    }
+
+    #[test]
+    fn test_xray() {
+        // the rook x-rays the king through the knight, and the bishop x-rays the queen through the pawn
+        let fen = "4k3/8/7q/8/4n3/8/3P4/2B1R1K1 w - - 0 1";
+        assert_eq!(2, features_of(fen, Color::White).xray);
+        assert_eq!(0, features_of(fen, Color::Black).xray);
+
+        // direct attacks and x-rays through two pieces don't count
+        assert_eq!(0, features_of("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1", Color::White).xray);
+        assert_eq!(0, features_of("4k3/4p3/8/8/4n3/8/8/4R1K1 w - - 0 1", Color::White).xray);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let params = EvalParams::DEFAULT;
+        assert_eq!(0, evaluate(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position, Color::White, &params));
+        assert_eq!(params.doubled_rooks, evaluate(Board::from_fen("6k1/8/8/8/3R4/8/8/3RK3 w - - 0 1").unwrap().position, Color::White, &params));
+    }
+}
@@ -0,0 +1,262 @@
+//! The kpk module contains a [bitbase](https://www.chessprogramming.org/KPK) for endgames of a king and pawn against a king.
+//!
+//! For each such position, the bitbase stores whether the side with the pawn wins, or whether the position is a draw.
+//! It is generated by retrograde analysis the first time it is needed, which takes a moment, so it is never generated
+//! in the middlegame. Only positions with the pawn on the files a to d are stored, since the others are mirror images.
+
+use std::sync::OnceLock;
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::board::square::Square;
+
+/// The bonus for positions that the bitbase knows to be won, on top of the evaluation of the material.
+/// It makes sure that won positions are preferred over positions with more material, but an unclear result.
+pub const KNOWN_WIN: i32 = 1000;
+
+/// The number of squares the pawn can stand on: the ranks 2 to 7 of the files a to d.
+const NUM_PAWN_SQUARES: usize = 24;
+/// The number of positions stored in the bitbase, for each side to move, pawn square, and square of both kings.
+const NUM_POSITIONS: usize = 2 * NUM_PAWN_SQUARES * 64 * 64;
+
+/// The bitbase, with one bit for each position, which is set if the side with the pawn wins.
+static BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+
+/// The result of a position during the generation of the bitbase.
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum KpkResult {
+    /// The position can't occur in a game, e.g. because the kings are next to each other.
+    Invalid,
+    /// The result of the position is not known yet.
+    Unknown,
+    Draw,
+    Win,
+}
+
+/// A position of the bitbase, always with White as the side with the pawn, and the pawn on the files a to d.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct KpkPosition {
+    white_to_move: bool,
+    white_king: u8,
+    pawn: u8,
+    black_king: u8,
+}
+
+/// Returns the side with the pawn, and whether it wins, if the given position is a king and pawn against a king.
+/// Returns None for all other positions.
+pub fn probe(position: Position) -> Option<(Color, bool)> {
+    let pieces = |color: Color| position.pieces[color.to_index() as usize].iter().map(|bitboard| bitboard.value.count_ones()).sum::<u32>();
+    if pieces(Color::White) + pieces(Color::Black) != 3 {
+        return None;
+    }
+    let strong = match (position.get_num_pieces(Piece::Pawn, Color::White), position.get_num_pieces(Piece::Pawn, Color::Black)) {
+        (1, 0) => Color::White,
+        (0, 1) => Color::Black,
+        _other => return None,
+    };
+
+    let square = |color: Color, piece: Piece| position.piece_bb(color, piece).value.trailing_zeros() as u8;
+    let kpk_position = KpkPosition::normalized(
+        position.color_to_move == strong,
+        square(strong, Piece::King),
+        square(strong, Piece::Pawn),
+        square(strong.other(), Piece::King),
+        strong,
+    );
+    let bitbase = BITBASE.get_or_init(generate);
+    let index = kpk_position.index();
+    Some((strong, bitbase[index / 64] & (1 << (index % 64)) != 0))
+}
+
+/// Generates the bitbase by first classifying the positions that are decided immediately,
+/// and then classifying the others from their successors, until no more positions can be classified.
+/// The positions that are still unknown at the end are draws, since the side with the pawn can't force its promotion.
+fn generate() -> Vec<u64> {
+    let positions: Vec<KpkPosition> = (0..NUM_POSITIONS).map(KpkPosition::from_index).collect();
+    let mut results: Vec<KpkResult> = positions.iter().map(KpkPosition::initial_result).collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (index, position) in positions.iter().enumerate() {
+            if results[index] == KpkResult::Unknown {
+                results[index] = position.result_from_successors(&results);
+                changed |= results[index] != KpkResult::Unknown;
+            }
+        }
+    }
+
+    let mut bitbase = vec![0; NUM_POSITIONS / 64];
+    for (index, result) in results.iter().enumerate() {
+        if *result == KpkResult::Win {
+            bitbase[index / 64] |= 1 << (index % 64);
+        }
+    }
+    bitbase
+}
+
+impl KpkPosition {
+    /// Constructs a position of the bitbase from the given squares, flipping the board vertically if Black has the pawn,
+    /// and mirroring it horizontally if the pawn is on the files e to h.
+    fn normalized(strong_to_move: bool, strong_king: u8, pawn: u8, weak_king: u8, strong: Color) -> Self {
+        let flip = match strong {
+            Color::White => 0,
+            Color::Black => 56,
+        };
+        let mirror = match pawn % 8 >= 4 {
+            true => 7,
+            false => 0,
+        };
+        Self {
+            white_to_move: strong_to_move,
+            white_king: strong_king ^ flip ^ mirror,
+            pawn: pawn ^ flip ^ mirror,
+            black_king: weak_king ^ flip ^ mirror,
+        }
+    }
+
+    /// Constructs the position with the given index in the bitbase.
+    fn from_index(index: usize) -> Self {
+        let pawn_index = (index / 4096) % NUM_PAWN_SQUARES;
+        Self {
+            white_to_move: index < NUM_POSITIONS / 2,
+            white_king: ((index / 64) % 64) as u8,
+            pawn: (8 + (pawn_index / 4) * 8 + pawn_index % 4) as u8,
+            black_king: (index % 64) as u8,
+        }
+    }
+
+    /// Returns the index of the position in the bitbase.
+    fn index(&self) -> usize {
+        let pawn_index = (self.pawn as usize / 8 - 1) * 4 + self.pawn as usize % 8;
+        let side_index = usize::from(!self.white_to_move);
+        ((side_index * NUM_PAWN_SQUARES + pawn_index) * 64 + self.white_king as usize) * 64 + self.black_king as usize
+    }
+
+    /// Returns the result of the position if it is decided immediately, or `KpkResult::Unknown` otherwise.
+    fn initial_result(&self) -> KpkResult {
+        let (white_king, pawn, black_king) = (Square::new(self.white_king), Square::new(self.pawn), Square::new(self.black_king));
+        if white_king == black_king || white_king == pawn || black_king == pawn || white_king.distance(black_king) <= 1
+            || (self.white_to_move && is_attacked_by_pawn(black_king, pawn)) {
+            return KpkResult::Invalid;
+        }
+
+        if self.white_to_move {
+            // the pawn promotes, and the queen can't be captured
+            let promotion_square = pawn.up();
+            if pawn.get_rank().to_index() == 6 && promotion_square != white_king && promotion_square != black_king
+                && (black_king.distance(promotion_square) > 1 || white_king.distance(promotion_square) <= 1) {
+                return KpkResult::Win;
+            }
+        } else {
+            // Black is stalemated, or captures the pawn
+            if self.black_king_moves().next().is_none() || (black_king.distance(pawn) <= 1 && white_king.distance(pawn) > 1) {
+                return KpkResult::Draw;
+            }
+        }
+        KpkResult::Unknown
+    }
+
+    /// Returns the result of the position derived from the results of its successors, or `KpkResult::Unknown` if they don't decide it yet.
+    /// White wins if any move wins, and Black draws if any move draws.
+    fn result_from_successors(&self, results: &[KpkResult]) -> KpkResult {
+        let (good, bad) = match self.white_to_move {
+            true => (KpkResult::Win, KpkResult::Draw),
+            false => (KpkResult::Draw, KpkResult::Win),
+        };
+        let successors: Vec<KpkResult> = match self.white_to_move {
+            true => self.white_moves().map(|successor| results[successor.index()]).collect(),
+            false => self.black_king_moves().map(|successor| results[successor.index()]).collect(),
+        };
+
+        if successors.contains(&good) {
+            good
+        } else if successors.iter().all(|result| *result == bad) {
+            bad
+        } else {
+            KpkResult::Unknown
+        }
+    }
+
+    /// Returns the positions after all moves of White, except for the promotions, which are already covered by `initial_result`.
+    fn white_moves(&self) -> impl Iterator<Item = KpkPosition> + '_ {
+        let (white_king, pawn, black_king) = (Square::new(self.white_king), Square::new(self.pawn), Square::new(self.black_king));
+        let king_moves = king_targets(white_king)
+            .filter(move |target| *target != pawn && target.distance(black_king) > 1)
+            .map(move |target| KpkPosition { white_to_move: false, white_king: target.index, ..*self });
+
+        let is_free = move |square: Square| square != white_king && square != black_king;
+        let single_push = (pawn.get_rank().to_index() < 6 && is_free(pawn.up())).then_some(pawn.up());
+        let double_push = single_push.filter(|push| pawn.get_rank().to_index() == 1 && is_free(push.up())).map(|push| push.up());
+        let pawn_moves = single_push.into_iter().chain(double_push)
+            .map(move |target| KpkPosition { white_to_move: false, pawn: target.index, ..*self });
+
+        king_moves.chain(pawn_moves)
+    }
+
+    /// Returns the positions after all moves of the black king, except for capturing the pawn, which is already covered by `initial_result`.
+    fn black_king_moves(&self) -> impl Iterator<Item = KpkPosition> + '_ {
+        let (white_king, pawn, black_king) = (Square::new(self.white_king), Square::new(self.pawn), Square::new(self.black_king));
+        king_targets(black_king)
+            .filter(move |target| *target != pawn && target.distance(white_king) > 1 && !is_attacked_by_pawn(*target, pawn))
+            .map(move |target| KpkPosition { white_to_move: true, black_king: target.index, ..*self })
+    }
+}
+
+/// Returns the squares a king on the given square can move to on an empty board.
+fn king_targets(square: Square) -> impl Iterator<Item = Square> {
+    (-1..=1).flat_map(move |file_delta| (-1..=1).filter_map(move |rank_delta| match (file_delta, rank_delta) {
+        (0, 0) => None,
+        _other => square.offset(file_delta, rank_delta),
+    }))
+}
+
+/// Returns whether the given square is attacked by a white pawn on the given square.
+fn is_attacked_by_pawn(square: Square, pawn: Square) -> bool {
+    pawn.offset(-1, 1) == Some(square) || pawn.offset(1, 1) == Some(square)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::evaluation::kpk::{probe, KpkPosition, NUM_POSITIONS};
+
+    fn probe_fen(fen: &str) -> Option<(Color, bool)> {
+        probe(Board::from_fen(fen).unwrap().position)
+    }
+
+    #[test]
+    fn test_index() {
+        for index in [0, 1, 4095, 4096, NUM_POSITIONS / 2, NUM_POSITIONS - 1] {
+            assert_eq!(index, KpkPosition::from_index(index).index());
+        }
+    }
+
+    #[test]
+    fn test_other_positions() {
+        assert_eq!(None, probe_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert_eq!(None, probe_fen("8/5k2/8/3P4/8/8/2KP4/8 w - - 0 1"));
+        assert_eq!(None, probe_fen("8/5k2/8/3p4/8/8/2KP4/8 w - - 0 1"));
+        assert_eq!(None, probe_fen("8/5k2/8/8/8/8/2K5/3N4 w - - 0 1"));
+    }
+
+    #[test]
+    fn test_won_positions() {
+        // the king stands in front of its pawn on the sixth rank, has the opposition, or the pawn runs away from the defending king
+        assert_eq!(Some((Color::White, true)), probe_fen("3k4/8/3K4/3P4/8/8/8/8 w - - 0 1"));
+        assert_eq!(Some((Color::White, true)), probe_fen("3k4/8/3K4/3P4/8/8/8/8 b - - 0 1"));
+        assert_eq!(Some((Color::White, true)), probe_fen("8/3k4/8/3K4/3P4/8/8/8 b - - 0 1"));
+        assert_eq!(Some((Color::White, true)), probe_fen("8/8/8/8/P7/8/8/K6k w - - 0 1"));
+        assert_eq!(Some((Color::Black, true)), probe_fen("8/8/8/8/3p4/3k4/8/3K4 w - - 0 1"));
+    }
+
+    #[test]
+    fn test_drawn_positions() {
+        // the defending king has the opposition, reaches the corner in front of a rook pawn, or catches the pawn
+        assert_eq!(Some((Color::White, false)), probe_fen("8/3k4/8/3K4/3P4/8/8/8 w - - 0 1"));
+        assert_eq!(Some((Color::White, false)), probe_fen("k7/8/8/8/8/1K6/P7/8 w - - 0 1"));
+        assert_eq!(Some((Color::White, false)), probe_fen("8/8/8/3k4/P7/8/8/K7 b - - 0 1"));
+        assert_eq!(Some((Color::Black, false)), probe_fen("8/8/8/3p4/3k4/8/3K4/8 b - - 0 1"));
+    }
+}
@@ -0,0 +1,347 @@
+//! This module contains the evaluation parameters, which can be loaded from a file at runtime.
+//!
+//! The file contains the piece values, the piece-square-tables, the passed pawn parameters, the coordination parameters,
+//! the bishop parameters, the threat parameters and the tempo bonus, either in TOML or in JSON:
+//!
+//! ```text
+//! # TOML
+//! piece_values = [100, 320, 330, 500, 950, 20000]
+//! pawn_pst = [0, 0, 0, ...]
+//! passed_pawn_blocked = -10
+//! ```
+//!
+//! ```text
+//! {"piece_values": [100, 320, 330, 500, 950, 20000], "pawn_pst": [0, 0, 0, ...], "passed_pawn_blocked": -10}
+//! ```
+//!
+//! The tables are written from White's point of view, with a8 first, just like the tables in the pst module.
+//! Parameters missing from the file keep their default values.
+
+use std::fs;
+use std::sync::RwLock;
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::square::Square;
+use crate::config;
+use crate::evaluation::bishops::{BAD_BISHOP_ENDGAME, BAD_BISHOP_MIDDLEGAME, BISHOP_PAIR_ENDGAME, BISHOP_PAIR_MIDDLEGAME};
+use crate::evaluation::coordination::{DOUBLED_ROOKS, QUEEN_ON_SEVENTH, ROOK_ON_SEVENTH, ROOK_QUEEN_BATTERY, XRAY};
+use crate::evaluation::passed_pawns::{PASSED_PAWN_BLOCKED, PASSED_PAWN_ENEMY_KING, PASSED_PAWN_OWN_KING, PASSED_PAWN_RANK, PASSED_PAWN_ROOK_BEHIND};
+use crate::evaluation::pst::{PIECE_VALUES, PST};
+use crate::evaluation::threats::{HANGING_PIECE, PAWN_PUSH_FORK, THREAT_BY_LESSER};
+use crate::evaluation::TEMPO;
+use crate::json::Json;
+
+/// The evaluation parameters used by the evaluation.
+/// They are only replaced between searches, so that the evaluation stays consistent during a search.
+pub static EVAL_PARAMS: RwLock<EvalParams> = RwLock::new(EvalParams::DEFAULT);
+
+/// The keys of the piece-square-tables, in the order of the piece indices.
+const PST_KEYS: [&str; 6] = ["pawn_pst", "knight_pst", "bishop_pst", "rook_pst", "queen_pst", "king_pst"];
+
+/// The number of parameters that consist of a single value.
+pub const NUM_SCALARS: usize = 17;
+
+/// The keys of the parameters that consist of a single value, in the order of `EvalParams::scalars`.
+/// The tempo bonus comes last, since the tuner relies on it.
+const SCALAR_KEYS: [&str; NUM_SCALARS] = ["passed_pawn_own_king", "passed_pawn_enemy_king", "passed_pawn_rook_behind", "passed_pawn_blocked",
+    "rook_on_seventh", "queen_on_seventh", "doubled_rooks", "rook_queen_battery", "xray",
+    "bishop_pair_middlegame", "bishop_pair_endgame", "bad_bishop_middlegame", "bad_bishop_endgame",
+    "hanging_piece", "threat_by_lesser", "pawn_push_fork", "tempo"];
+
+/// The parameters used by the evaluation.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EvalParams {
+    /// The base values of the pieces.
+    pub piece_values: [i32; 6],
+    /// The PST for all pieces.
+    pub pst: [[i32; 64]; 6],
+    /// The bonus for a passed pawn on each rank, from the point of view of the pawn's color (see the passed_pawns module).
+    pub passed_pawn_rank: [i32; 8],
+    /// The bonus for each square between the own king and the square in front of a passed pawn.
+    pub passed_pawn_own_king: i32,
+    /// The bonus for each square between the enemy king and the square in front of a passed pawn.
+    pub passed_pawn_enemy_king: i32,
+    /// The bonus for a rook behind a passed pawn of the same color.
+    pub passed_pawn_rook_behind: i32,
+    /// The bonus for a passed pawn with an enemy piece on the square in front of it.
+    pub passed_pawn_blocked: i32,
+    /// The bonus for a rook on the seventh rank (see the coordination module).
+    pub rook_on_seventh: i32,
+    /// The bonus for a queen on the seventh rank.
+    pub queen_on_seventh: i32,
+    /// The bonus for two rooks that defend each other on a file.
+    pub doubled_rooks: i32,
+    /// The bonus for a rook and a queen that defend each other on a file or rank.
+    pub rook_queen_battery: i32,
+    /// The bonus for each slider that x-rays the enemy king or queen through a single piece.
+    pub xray: i32,
+    /// The bonus for the bishop pair in the middlegame (see the bishops module).
+    pub bishop_pair_middlegame: i32,
+    /// The bonus for the bishop pair in the endgame.
+    pub bishop_pair_endgame: i32,
+    /// The bonus for each own pawn fixed on the square color of a bishop in the middlegame.
+    pub bad_bishop_middlegame: i32,
+    /// The bonus for each own pawn fixed on the square color of a bishop in the endgame.
+    pub bad_bishop_endgame: i32,
+    /// The bonus for each enemy piece that is attacked and not defended (see the threats module).
+    pub hanging_piece: i32,
+    /// The bonus for each enemy piece that is attacked by a piece of lesser value.
+    pub threat_by_lesser: i32,
+    /// The bonus for each pawn push that attacks two enemy pieces from a safe square.
+    pub pawn_push_fork: i32,
+    /// The bonus for the side to move.
+    pub tempo: i32,
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl EvalParams {
+    /// The built-in evaluation parameters.
+    pub const DEFAULT: EvalParams = EvalParams {
+        piece_values: PIECE_VALUES,
+        pst: PST,
+        passed_pawn_rank: PASSED_PAWN_RANK,
+        passed_pawn_own_king: PASSED_PAWN_OWN_KING,
+        passed_pawn_enemy_king: PASSED_PAWN_ENEMY_KING,
+        passed_pawn_rook_behind: PASSED_PAWN_ROOK_BEHIND,
+        passed_pawn_blocked: PASSED_PAWN_BLOCKED,
+        rook_on_seventh: ROOK_ON_SEVENTH,
+        queen_on_seventh: QUEEN_ON_SEVENTH,
+        doubled_rooks: DOUBLED_ROOKS,
+        rook_queen_battery: ROOK_QUEEN_BATTERY,
+        xray: XRAY,
+        bishop_pair_middlegame: BISHOP_PAIR_MIDDLEGAME,
+        bishop_pair_endgame: BISHOP_PAIR_ENDGAME,
+        bad_bishop_middlegame: BAD_BISHOP_MIDDLEGAME,
+        bad_bishop_endgame: BAD_BISHOP_ENDGAME,
+        hanging_piece: HANGING_PIECE,
+        threat_by_lesser: THREAT_BY_LESSER,
+        pawn_push_fork: PAWN_PUSH_FORK,
+        tempo: TEMPO,
+    };
+
+    /// Loads the evaluation parameters from the given TOML or JSON file.
+    pub fn from_file(path: &str) -> Result<EvalParams, String> {
+        match fs::read_to_string(path) {
+            Err(_) => Err(String::from("Could not read evaluation parameter file")),
+            Ok(text) => Self::parse(text.as_str()),
+        }
+    }
+
+    /// Parses the evaluation parameters from the given TOML or JSON text.
+    /// A text starting with '{' is read as a JSON object, any other text is read as TOML with the reader of the config module.
+    /// The value of each parameter is an array of integers, or a single integer.
+    pub fn parse(text: &str) -> Result<EvalParams, String> {
+        let entries = match text.trim_start().starts_with('{') {
+            true => Self::parse_json_entries(text)?,
+            false => Self::parse_toml_entries(text)?,
+        };
+
+        let mut params = EvalParams::DEFAULT;
+        for (key, values) in entries {
+            if key == "piece_values" {
+                params.piece_values = values.try_into().map_err(|_| String::from("Expected 6 values for 'piece_values'"))?;
+            } else if let Some(index) = PST_KEYS.iter().position(|pst_key| *pst_key == key) {
+                params.pst[index] = values.try_into().map_err(|_| format!("Expected 64 values for '{key}'"))?;
+            } else if key == "passed_pawn_rank" {
+                params.passed_pawn_rank = values.try_into().map_err(|_| String::from("Expected 8 values for 'passed_pawn_rank'"))?;
+            } else if let Some(index) = SCALAR_KEYS.iter().position(|scalar_key| *scalar_key == key) {
+                let [value] = values[..] else {
+                    return Err(format!("Expected a single value for '{key}'"));
+                };
+                *params.scalars_mut()[index] = value;
+            } else {
+                return Err(format!("Unknown parameter '{key}'"));
+            }
+        }
+        Ok(params)
+    }
+
+    /// Reads the keys and values of the parameters from the given JSON object.
+    fn parse_json_entries(text: &str) -> Result<Vec<(String, Vec<i32>)>, String> {
+        let Json::Object(entries) = Json::parse(text)? else {
+            return Err(String::from("Expected a JSON object"));
+        };
+        entries.into_iter().map(|(key, value)| {
+            let values = match value {
+                Json::Array(values) => values,
+                value => vec![value],
+            };
+            let values = values.iter().map(|value| value.as_i32().ok_or(format!("Invalid value for '{key}'"))).collect::<Result<Vec<i32>, String>>()?;
+            Ok((key, values))
+        }).collect()
+    }
+
+    /// Reads the keys and values of the parameters from the given TOML text.
+    fn parse_toml_entries(text: &str) -> Result<Vec<(String, Vec<i32>)>, String> {
+        config::parse(text)?.into_iter().map(|(key, value)| {
+            let values = match value.strip_prefix('[').and_then(|array| array.strip_suffix(']')) {
+                // TOML allows a comma after the last value of an array
+                Some(array) => {
+                    let array = array.trim();
+                    let array = array.strip_suffix(',').unwrap_or(array);
+                    match array.is_empty() {
+                        true => Vec::new(),
+                        false => array.split(',').map(|value| value.trim()).collect(),
+                    }
+                }
+                None => vec![value.as_str()],
+            };
+            let values = values.iter().map(|value| value.parse::<i32>().map_err(|_| format!("Invalid value '{value}' for '{key}'"))).collect::<Result<Vec<i32>, String>>()?;
+            Ok((key, values))
+        }).collect()
+    }
+
+    /// Returns the evaluation parameters as TOML, which can be loaded with `parse` again.
+    /// The tables are written with one rank per line.
+    pub fn to_toml(&self) -> String {
+        let join = |values: &[i32]| values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(", ");
+        let mut toml = format!("piece_values = [{}]\n", join(&self.piece_values));
+        for (key, table) in PST_KEYS.iter().zip(self.pst.iter()) {
+            toml += format!("\n{key} = [\n").as_str();
+            for rank in table.chunks(8) {
+                toml += format!("    {},\n", join(rank)).as_str();
+            }
+            toml += "]\n";
+        }
+        toml += format!("\npassed_pawn_rank = [{}]\n", join(&self.passed_pawn_rank)).as_str();
+        for (key, value) in SCALAR_KEYS.iter().zip(self.scalars()) {
+            toml += format!("{key} = {value}\n").as_str();
+        }
+        toml
+    }
+
+    /// Returns the parameters that consist of a single value, in the order of their keys.
+    pub fn scalars(&self) -> [i32; NUM_SCALARS] {
+        [self.passed_pawn_own_king, self.passed_pawn_enemy_king, self.passed_pawn_rook_behind, self.passed_pawn_blocked,
+            self.rook_on_seventh, self.queen_on_seventh, self.doubled_rooks, self.rook_queen_battery, self.xray,
+            self.bishop_pair_middlegame, self.bishop_pair_endgame, self.bad_bishop_middlegame, self.bad_bishop_endgame,
+            self.hanging_piece, self.threat_by_lesser, self.pawn_push_fork, self.tempo]
+    }
+
+    /// Returns mutable references to the parameters that consist of a single value, in the order of their keys.
+    pub fn scalars_mut(&mut self) -> [&mut i32; NUM_SCALARS] {
+        [&mut self.passed_pawn_own_king, &mut self.passed_pawn_enemy_king, &mut self.passed_pawn_rook_behind, &mut self.passed_pawn_blocked,
+            &mut self.rook_on_seventh, &mut self.queen_on_seventh, &mut self.doubled_rooks, &mut self.rook_queen_battery, &mut self.xray,
+            &mut self.bishop_pair_middlegame, &mut self.bishop_pair_endgame, &mut self.bad_bishop_middlegame, &mut self.bad_bishop_endgame,
+            &mut self.hanging_piece, &mut self.threat_by_lesser, &mut self.pawn_push_fork, &mut self.tempo]
+    }
+
+    /// Returns the relative value of the given piece, based on its color and square.
+    pub fn get_piece_value(&self, piece: Piece, square: Square, color: Color) -> i32 {
+        let square_index = match color {
+            Color::White => square.index ^ 56,
+            Color::Black => square.index,
+        };
+        self.piece_values[piece.to_index() as usize] + self.pst[piece.to_index() as usize][square_index as usize]
+    }
+}
+
+/// Replaces the evaluation parameters used by the evaluation.
+pub fn set(params: EvalParams) {
+    *EVAL_PARAMS.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = params;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::color::Color;
+    use crate::board::piece::Piece;
+    use crate::board::square::Square;
+    use crate::evaluation::params::EvalParams;
+    use crate::evaluation::pst::{PIECE_VALUES, PST};
+
+    #[test]
+    fn test_parse_toml() {
+        let pst = (0..64).map(|i| i.to_string()).collect::<Vec<String>>().join(", ");
+        let text = format!("# a comment\npiece_values = [90, 300, 310, 480, 900, 20000] # another comment\n\nknight_pst = [\n{pst}\n]\n");
+        let params = EvalParams::parse(text.as_str()).unwrap();
+        assert_eq!([90, 300, 310, 480, 900, 20000], params.piece_values);
+        assert_eq!(std::array::from_fn::<i32, 64, _>(|i| i as i32), params.pst[1]);
+        // parameters missing from the file keep their default values
+        assert_eq!(PST[0], params.pst[0]);
+        assert_eq!(PST[5], params.pst[5]);
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let params = EvalParams::parse("{\n  \"piece_values\": [90, 300, 310, 480, 900, 20000]\n}").unwrap();
+        assert_eq!([90, 300, 310, 480, 900, 20000], params.piece_values);
+        assert_eq!(PST, params.pst);
+
+        let params = EvalParams::parse("{}").unwrap();
+        assert_eq!(EvalParams::DEFAULT, params);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(EvalParams::parse("piece_values = [100, 320, 330, 500, 950]").is_err());
+        assert!(EvalParams::parse("piece_values = [100, 320, 330, 500, 950, 20000, 1]").is_err());
+        assert!(EvalParams::parse("piece_values = [100, 320, 330, 500, 950, pawn]").is_err());
+        assert!(EvalParams::parse("piece_values = [100, 320, 330, 500, 950, 20000").is_err());
+        assert!(EvalParams::parse("piece_values [100, 320, 330, 500, 950, 20000]").is_err());
+        assert!(EvalParams::parse("piece_values = 100").is_err());
+        assert!(EvalParams::parse("passed_pawn_blocked = [1, 2]").is_err());
+        assert!(EvalParams::parse("passed_pawn_blocked = ").is_err());
+        assert!(EvalParams::parse("passed_pawn_rank = [1, 2]").is_err());
+        assert!(EvalParams::parse("pawn_pst = [0, 0, 0]").is_err());
+        assert!(EvalParams::parse("mobility = [1, 2, 3]").is_err());
+
+        // JSON and TOML syntax can't be mixed
+        assert!(EvalParams::parse("piece_values: [100, 320, 330, 500, 950, 20000]").is_err());
+        assert!(EvalParams::parse("{\"piece_values\" = [100, 320, 330, 500, 950, 20000]}").is_err());
+        assert!(EvalParams::parse("{\"tempo\": 10}}").is_err());
+        assert!(EvalParams::parse("tempo = 10,").is_err());
+        assert!(EvalParams::parse("tempo = 10 }").is_err());
+        assert!(EvalParams::parse("piece_values = [100, 320, 330, 500, 950, 20000], tempo = 10").is_err());
+        assert!(EvalParams::parse("{\"tempo\": 10.5}").is_err());
+        assert!(EvalParams::parse("[10]").is_err());
+    }
+
+    #[test]
+    fn test_parse_passed_pawn_params() {
+        let params = EvalParams::parse("passed_pawn_rank = [0, 1, 2, 3, 4, 5, 6, 0]\npassed_pawn_blocked = -20\npassed_pawn_own_king = [-3]").unwrap();
+        assert_eq!([0, 1, 2, 3, 4, 5, 6, 0], params.passed_pawn_rank);
+        assert_eq!(-20, params.passed_pawn_blocked);
+        assert_eq!(-3, params.passed_pawn_own_king);
+        assert_eq!(EvalParams::DEFAULT.passed_pawn_rook_behind, params.passed_pawn_rook_behind);
+
+        let params = EvalParams::parse("{\"passed_pawn_enemy_king\": 7, \"passed_pawn_rook_behind\": 12}").unwrap();
+        assert_eq!([EvalParams::DEFAULT.passed_pawn_own_king, 7, 12, EvalParams::DEFAULT.passed_pawn_blocked], params.scalars()[..4]);
+        assert_eq!(15, EvalParams::parse("tempo = 15").unwrap().tempo);
+    }
+
+    #[test]
+    fn test_parse_coordination_params() {
+        let params = EvalParams::parse("rook_on_seventh = 25
+queen_on_seventh = 5
+doubled_rooks = 12
+rook_queen_battery = 8
+xray = 3").unwrap();
+        assert_eq!((25, 5, 12, 8, 3), (params.rook_on_seventh, params.queen_on_seventh, params.doubled_rooks, params.rook_queen_battery, params.xray));
+        assert_eq!(EvalParams::DEFAULT.tempo, params.tempo);
+    }
+
+    #[test]
+    fn test_to_toml() {
+        let mut params = EvalParams::DEFAULT;
+        params.piece_values[1] = 310;
+        params.pst[2][17] = -7;
+        params.passed_pawn_rank[3] = 12;
+        params.passed_pawn_blocked = -25;
+        let toml = params.to_toml();
+        assert!(toml.starts_with("piece_values = [100, 310, 330, 500, 950, 20000]\n"));
+        assert_eq!(params, EvalParams::parse(toml.as_str()).unwrap());
+    }
+
+    #[test]
+    fn test_get_piece_value() {
+        let params = EvalParams::DEFAULT;
+        // a White pawn on e4 and a Black pawn on e5 get the same bonus
+        assert_eq!(PIECE_VALUES[0] + 20, params.get_piece_value(Piece::Pawn, Square::new(28), Color::White));
+        assert_eq!(PIECE_VALUES[0] + 20, params.get_piece_value(Piece::Pawn, Square::new(36), Color::Black));
+    }
+}
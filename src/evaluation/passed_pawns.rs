@@ -0,0 +1,196 @@
+//! The passed pawns module evaluates [passed pawns](https://www.chessprogramming.org/Passed_Pawn), i.e. pawns that no enemy pawn can stop.
+//!
+//! Passed pawns get a bonus depending on how far they are advanced, which grows if the own king is close to the pawn,
+//! the enemy king is far away from it, or a rook supports it from behind. Passed pawns blocked by an enemy piece get a penalty.
+//! The king distances are measured to the square in front of the pawn, and only count from the fourth rank on,
+//! with more weight the further the pawn is advanced, since that is when pawn races are decided.
+
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::board::square::Square;
+use crate::evaluation::params::EvalParams;
+
+/// The bonus for a passed pawn on each rank, from the point of view of the pawn's color.
+pub const PASSED_PAWN_RANK: [i32; 8] = [0, 5, 5, 10, 20, 35, 60, 0];
+/// The bonus for each square between the own king and the square in front of a passed pawn, which is negative, since the king should be close.
+pub const PASSED_PAWN_OWN_KING: i32 = -2;
+/// The bonus for each square between the enemy king and the square in front of a passed pawn.
+pub const PASSED_PAWN_ENEMY_KING: i32 = 4;
+/// The bonus for a rook behind a passed pawn of the same color.
+pub const PASSED_PAWN_ROOK_BEHIND: i32 = 15;
+/// The bonus for a passed pawn with an enemy piece on the square in front of it, which is negative.
+pub const PASSED_PAWN_BLOCKED: i32 = -10;
+
+/// The largest king distance that counts, since a king that is further away makes no difference in a pawn race.
+const MAX_KING_DISTANCE: u8 = 5;
+
+/// The bitboard of the a-file.
+const A_FILE: u64 = 0x0101_0101_0101_0101;
+
+/// How often each passed pawn parameter contributes to the evaluation of one color.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct PassedPawnFeatures {
+    /// The number of passed pawns on each rank, from the point of view of the pawn's color.
+    pub rank: [i32; 8],
+    /// The sum of the weighted distances between the own king and the squares in front of the passed pawns.
+    pub own_king: i32,
+    /// The sum of the weighted distances between the enemy king and the squares in front of the passed pawns.
+    pub enemy_king: i32,
+    /// The number of passed pawns with a rook of the same color behind them.
+    pub rook_behind: i32,
+    /// The number of passed pawns with an enemy piece on the square in front of them.
+    pub blocked: i32,
+}
+
+impl PassedPawnFeatures {
+    /// Returns the evaluation of the features with the given parameters.
+    pub fn evaluate(&self, params: &EvalParams) -> i32 {
+        let rank_bonus: i32 = self.rank.iter().zip(params.passed_pawn_rank.iter()).map(|(count, bonus)| count * bonus).sum();
+        rank_bonus
+            + self.own_king * params.passed_pawn_own_king
+            + self.enemy_king * params.passed_pawn_enemy_king
+            + self.rook_behind * params.passed_pawn_rook_behind
+            + self.blocked * params.passed_pawn_blocked
+    }
+}
+
+/// Returns the evaluation of the passed pawns of the given color with the given parameters.
+pub fn evaluate(position: Position, color: Color, params: &EvalParams) -> i32 {
+    features(position, color).evaluate(params)
+}
+
+/// Returns how often each passed pawn parameter contributes to the evaluation of the given color.
+pub fn features(position: Position, color: Color) -> PassedPawnFeatures {
+    let mut features = PassedPawnFeatures::default();
+    let enemy_pawns = position.piece_bb(color.other(), Piece::Pawn).value;
+    let own_king = Square::new(position.piece_bb(color, Piece::King).value.trailing_zeros() as u8);
+    let enemy_king = Square::new(position.piece_bb(color.other(), Piece::King).value.trailing_zeros() as u8);
+    let enemy_occupancy = position.get_occupancy(color.other()).value;
+    let rank_delta = match color {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+
+    for pawn in position.piece_bb(color, Piece::Pawn).get_active_bits() {
+        if enemy_pawns & front_span(pawn, color) != 0 {
+            continue;
+        }
+        let relative_rank = match color {
+            Color::White => pawn.get_rank().to_index(),
+            Color::Black => 7 - pawn.get_rank().to_index(),
+        };
+        features.rank[relative_rank as usize] += 1;
+
+        // pawns never stand on the last rank, so there is always a square in front of them
+        let Some(stop_square) = pawn.offset(0, rank_delta) else {
+            continue;
+        };
+        let weight = relative_rank.saturating_sub(2) as i32;
+        features.own_king += weight * own_king.distance(stop_square).min(MAX_KING_DISTANCE) as i32;
+        features.enemy_king += weight * enemy_king.distance(stop_square).min(MAX_KING_DISTANCE) as i32;
+
+        if enemy_occupancy & (1 << stop_square.index) != 0 {
+            features.blocked += 1;
+        }
+        if is_rook_behind(position, pawn, color, -rank_delta) {
+            features.rook_behind += 1;
+        }
+    }
+    features
+}
+
+/// Returns the squares on the file of the given pawn and the adjacent files that lie in front of the pawn.
+/// The pawn is passed if there are no enemy pawns on these squares.
+fn front_span(pawn: Square, color: Color) -> u64 {
+    let file = pawn.get_file().to_index();
+    let mut files = A_FILE << file;
+    if file > 0 {
+        files |= A_FILE << (file - 1);
+    }
+    if file < 7 {
+        files |= A_FILE << (file + 1);
+    }
+
+    let rank = pawn.get_rank().to_index();
+    let ranks = match color {
+        Color::White => u64::MAX << (8 * (rank + 1)),
+        Color::Black => (1 << (8 * rank)) - 1,
+    };
+    files & ranks
+}
+
+/// Returns whether the first piece behind the given pawn, looking in the given rank direction, is a rook of the pawn's color.
+fn is_rook_behind(position: Position, pawn: Square, color: Color, rank_delta: i8) -> bool {
+    let mut square = pawn;
+    while let Some(next) = square.offset(0, rank_delta) {
+        if let Some(piece) = position.get_piece(next) {
+            return piece == (Piece::Rook, color);
+        }
+        square = next;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::evaluation::params::EvalParams;
+    use crate::evaluation::passed_pawns::{evaluate, features, PassedPawnFeatures};
+
+    fn features_of(fen: &str, color: Color) -> PassedPawnFeatures {
+        features(Board::from_fen(fen).unwrap().position, color)
+    }
+
+    #[test]
+    fn test_passed_pawns() {
+        // no pawn is passed in the start position
+        assert_eq!(PassedPawnFeatures::default(), features_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Color::White));
+
+        // the e-pawn is passed, the a-pawn is stopped by the pawn on b6, and the g-pawn by the pawn in front of it
+        let fen = "4k3/8/1p4p1/2p5/P3P3/8/6P1/4K3 w - - 0 1";
+        assert_eq!([0, 0, 0, 1, 0, 0, 0, 0], features_of(fen, Color::White).rank);
+        // only the c-pawn is passed, which stands on the fourth rank from Black's point of view
+        assert_eq!([0, 0, 0, 1, 0, 0, 0, 0], features_of(fen, Color::Black).rank);
+    }
+
+    #[test]
+    fn test_king_distances() {
+        // the pawn on d6 has the square d7 in front of it, which is two squares away from the White king and four squares away from the Black king
+        let white = features_of("7k/8/3P4/2K5/8/8/8/8 w - - 0 1", Color::White);
+        assert_eq!(3 * 2, white.own_king);
+        assert_eq!(3 * 4, white.enemy_king);
+
+        // pawns on the first three ranks don't count
+        let white = features_of("7k/8/8/8/8/3P4/2K5/8 w - - 0 1", Color::White);
+        assert_eq!(0, white.own_king);
+        assert_eq!(0, white.enemy_king);
+    }
+
+    #[test]
+    fn test_rook_behind_and_blockade() {
+        let white = features_of("3n3k/8/8/3P4/8/8/8/3R3K w - - 0 1", Color::White);
+        assert_eq!(1, white.rook_behind);
+        assert_eq!(0, white.blocked);
+
+        // a piece between the rook and the pawn, and a blockader in front of the pawn
+        let white = features_of("7k/8/3n4/3P4/8/3B4/8/3R3K w - - 0 1", Color::White);
+        assert_eq!(0, white.rook_behind);
+        assert_eq!(1, white.blocked);
+
+        let black = features_of("3r3k/8/8/8/3p4/3N4/8/7K b - - 0 1", Color::Black);
+        assert_eq!(1, black.rook_behind);
+        assert_eq!(1, black.blocked);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        // an advanced passed pawn escorted by its king is worth more than one blocked by the enemy king
+        let params = EvalParams::DEFAULT;
+        let escorted = evaluate(Board::from_fen("8/2k5/8/3PK3/8/8/8/8 w - - 0 1").unwrap().position, Color::White, &params);
+        let blocked = evaluate(Board::from_fen("8/8/3k4/3P4/4K3/8/8/8 w - - 0 1").unwrap().position, Color::White, &params);
+        assert!(escorted > blocked);
+        assert_eq!(0, evaluate(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap().position, Color::White, &params));
+    }
+}
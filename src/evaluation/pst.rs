@@ -6,23 +6,10 @@
 //! Otherwise, it would just shuffle pieces around aimlessly.
 //!
 //! The values here are directly taken from the [Simplified Evaluation Function](https://www.chessprogramming.org/Simplified_Evaluation_Function#See_also).
-
-
-use crate::board::color::Color;
-use crate::board::piece::Piece;
-use crate::board::square::Square;
-
-/// Returns the relative value of the given piece, based on its color and square.
-pub fn get_piece_value(piece: Piece, square: Square, color: Color) -> i32 {
-    let square_index = match color {
-        Color::White => square.index ^ 56,
-        Color::Black => square.index,
-    };
-    PIECE_VALUES[piece.to_index() as usize] + PST[piece.to_index() as usize][square_index as usize]
-}
+//! They are the default evaluation parameters, which can be replaced at runtime (see the params module).
 
 /// The PST for all pieces.
-const PST: [[i32; 64]; 6] = [
+pub const PST: [[i32; 64]; 6] = [
     PAWN_VALUES,
     KNIGHT_VALUES,
     BISHOP_VALUES,
@@ -0,0 +1,150 @@
+//! The scaling module recognizes endgames that are drawish despite a material advantage.
+//!
+//! The evaluation of such endgames is multiplied with a scale factor, which ranges from `SCALE_DRAW` to `SCALE_NORMAL`.
+//! This way, Ladybug doesn't overpress endgames she can't win, and prefers trading into endgames she can win.
+//! The scale factor always depends on the side that is ahead, since only that side has anything to lose.
+
+use crate::board::color::Color;
+use crate::board::file::File;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::board::square::Square;
+
+/// The scale factor of positions without any special endgame knowledge.
+pub const SCALE_NORMAL: i32 = 64;
+/// The scale factor of endgames with opposite-colored bishops and pawns only, which are often drawn even with several extra pawns.
+const SCALE_OPPOSITE_BISHOPS: i32 = 16;
+/// The scale factor of endgames that are drawn with correct defense, e.g. a rook and pawn against a rook, if the defending king blocks the pawn.
+const SCALE_DRAWISH: i32 = 8;
+/// The scale factor of endgames that are dead drawn, even though the stronger side has more material.
+const SCALE_DRAW: i32 = 0;
+
+/// The number of pawns each piece is worth, which is what decides whether an advantage without pawns is enough to win.
+/// Fixed values are used instead of the evaluation parameters, since the rules only hold for the textbook values.
+const PAWN_UNITS: [i32; 6] = [1, 3, 3, 5, 9, 0];
+
+/// Returns the scale factor of the given position, if the given color is the stronger side.
+pub fn scale_factor(position: Position, strong: Color) -> i32 {
+    let weak = strong.other();
+
+    // most positions have too many pieces for any of the rules to apply
+    let count = |color: Color, piece: Piece| position.piece_bb(color, piece).value.count_ones();
+    let num_pieces = |color: Color| [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen].iter().map(|piece| count(color, *piece)).sum::<u32>();
+    let (strong_pieces, weak_pieces) = (num_pieces(strong), num_pieces(weak));
+    if strong_pieces > 2 || weak_pieces > 2 {
+        return SCALE_NORMAL;
+    }
+
+    // without pawns, the stronger side needs more than a minor piece ahead to win, and two knights can't force mate
+    if count(strong, Piece::Pawn) == 0 {
+        let piece_units = |color: Color| [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen].iter()
+            .map(|piece| count(color, *piece) as i32 * PAWN_UNITS[piece.to_index() as usize]).sum::<i32>();
+        let only_knights = strong_pieces == 2 && count(strong, Piece::Knight) == 2 && weak_pieces == 0;
+        if piece_units(strong) - piece_units(weak) <= PAWN_UNITS[Piece::Bishop.to_index() as usize] || only_knights {
+            return SCALE_DRAWISH;
+        }
+    }
+
+    // only bishops and pawns are left, and the bishops are on squares of different colors
+    if strong_pieces == 1 && weak_pieces == 1 && count(strong, Piece::Bishop) == 1 && count(weak, Piece::Bishop) == 1 {
+        let strong_bishop = Square::new(position.piece_bb(strong, Piece::Bishop).value.trailing_zeros() as u8);
+        let weak_bishop = Square::new(position.piece_bb(weak, Piece::Bishop).value.trailing_zeros() as u8);
+        if strong_bishop.is_light() != weak_bishop.is_light() {
+            return SCALE_OPPOSITE_BISHOPS;
+        }
+    }
+
+    if strong_pieces == 1 && weak_pieces == 1 && count(strong, Piece::Rook) == 1 && count(weak, Piece::Rook) == 1
+        && count(strong, Piece::Pawn) == 1 && count(weak, Piece::Pawn) == 0 && is_rook_pawn_blocked(position, strong) {
+        return SCALE_DRAWISH;
+    }
+
+    if strong_pieces == 1 && weak_pieces == 0 && count(strong, Piece::Bishop) == 1 && count(weak, Piece::Pawn) == 0 && is_wrong_bishop(position, strong) {
+        return SCALE_DRAW;
+    }
+
+    SCALE_NORMAL
+}
+
+/// Returns whether the defending king stands in front of the single pawn of the stronger side, in an endgame of a rook and pawn against a rook.
+/// The endgame is then drawn with correct defense, e.g. with the Philidor position.
+fn is_rook_pawn_blocked(position: Position, strong: Color) -> bool {
+    let pawn = Square::new(position.piece_bb(strong, Piece::Pawn).value.trailing_zeros() as u8);
+    let weak_king = Square::new(position.piece_bb(strong.other(), Piece::King).value.trailing_zeros() as u8);
+
+    let file_distance = pawn.get_file().to_index().abs_diff(weak_king.get_file().to_index());
+    let in_front = match strong {
+        Color::White => weak_king.get_rank().to_index() > pawn.get_rank().to_index(),
+        Color::Black => weak_king.get_rank().to_index() < pawn.get_rank().to_index(),
+    };
+    file_distance <= 1 && in_front
+}
+
+/// Returns whether the stronger side only has rook pawns on a single edge file, and a bishop that doesn't control the promotion square,
+/// while the defending king stands next to the promotion square. The defending king can then never be driven out of the corner.
+fn is_wrong_bishop(position: Position, strong: Color) -> bool {
+    let pawns = position.piece_bb(strong, Piece::Pawn).value;
+    let a_file = 0x0101_0101_0101_0101_u64;
+    let file = match pawns {
+        0 => return false,
+        pawns if pawns & !a_file == 0 => File::A,
+        pawns if pawns & !(a_file << 7) == 0 => File::H,
+        _other => return false,
+    };
+
+    let promotion_square = Square::from_file_rank(file, strong.promotion_rank());
+    let bishop = Square::new(position.piece_bb(strong, Piece::Bishop).value.trailing_zeros() as u8);
+    let weak_king = Square::new(position.piece_bb(strong.other(), Piece::King).value.trailing_zeros() as u8);
+    bishop.is_light() != promotion_square.is_light() && weak_king.distance(promotion_square) <= 1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::evaluation::scaling::{scale_factor, SCALE_DRAW, SCALE_DRAWISH, SCALE_NORMAL, SCALE_OPPOSITE_BISHOPS};
+
+    fn scale_factor_of(fen: &str, strong: Color) -> i32 {
+        scale_factor(Board::from_fen(fen).unwrap().position, strong)
+    }
+
+    #[test]
+    fn test_normal_positions() {
+        assert_eq!(SCALE_NORMAL, scale_factor_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", Color::White));
+        assert_eq!(SCALE_NORMAL, scale_factor_of("8/5k2/8/3P4/8/8/2K5/8 w - - 0 1", Color::White));
+        assert_eq!(SCALE_NORMAL, scale_factor_of("8/5k2/8/8/8/8/2K5/3R4 w - - 0 1", Color::White));
+        assert_eq!(SCALE_NORMAL, scale_factor_of("8/5k2/8/8/8/8/2K5/2BN4 w - - 0 1", Color::White));
+    }
+
+    #[test]
+    fn test_insufficient_winning_material() {
+        // a minor piece ahead without pawns, or two knights
+        assert_eq!(SCALE_DRAWISH, scale_factor_of("8/5k2/8/8/8/8/2K5/3N4 w - - 0 1", Color::White));
+        assert_eq!(SCALE_DRAWISH, scale_factor_of("8/5k2/8/8/8/8/2K5/2NN4 w - - 0 1", Color::White));
+        assert_eq!(SCALE_DRAWISH, scale_factor_of("8/5k2/4b3/8/8/8/2K5/3R4 w - - 0 1", Color::White));
+        assert_eq!(SCALE_DRAWISH, scale_factor_of("8/5kp1/8/8/8/8/2K5/3N4 w - - 0 1", Color::White));
+    }
+
+    #[test]
+    fn test_opposite_colored_bishops() {
+        assert_eq!(SCALE_OPPOSITE_BISHOPS, scale_factor_of("8/5k2/4b3/1p6/PP6/8/2K5/2B5 w - - 0 1", Color::White));
+        assert_eq!(SCALE_NORMAL, scale_factor_of("8/5k2/3b4/1p6/PP6/8/2K5/2B5 w - - 0 1", Color::White));
+    }
+
+    #[test]
+    fn test_rook_and_pawn_against_rook() {
+        // the defending king blocks the pawn, or it is cut off
+        assert_eq!(SCALE_DRAWISH, scale_factor_of("3k4/7r/8/3PK3/8/8/8/R7 b - - 0 1", Color::White));
+        assert_eq!(SCALE_NORMAL, scale_factor_of("8/7r/8/3PK3/8/8/k7/3R4 b - - 0 1", Color::White));
+        assert_eq!(SCALE_DRAWISH, scale_factor_of("8/8/8/8/3pk3/7r/7R/3K4 w - - 0 1", Color::Black));
+    }
+
+    #[test]
+    fn test_wrong_bishop() {
+        // the bishop doesn't control the promotion square h8, and the defending king reached the corner
+        assert_eq!(SCALE_DRAW, scale_factor_of("7k/8/6KP/8/8/8/8/3B4 w - - 0 1", Color::White));
+        assert_eq!(SCALE_NORMAL, scale_factor_of("7k/8/6KP/8/8/8/8/2B5 w - - 0 1", Color::White));
+        assert_eq!(SCALE_NORMAL, scale_factor_of("8/3k4/6KP/8/8/8/8/3B4 w - - 0 1", Color::White));
+        assert_eq!(SCALE_DRAW, scale_factor_of("8/8/8/8/8/p1k5/8/K2b4 w - - 0 1", Color::Black));
+    }
+}
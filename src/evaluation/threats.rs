@@ -0,0 +1,137 @@
+//! The threats module evaluates the [threats](https://www.chessprogramming.org/Evaluation_of_Pieces#Threats) a side makes
+//! against the pieces of the other side.
+//!
+//! Enemy pieces that are attacked and not defended are hanging, and enemy pieces that are attacked by a piece of lesser value,
+//! e.g. a rook attacked by a knight, are threatened even if they are defended. A pawn that can be pushed safely to a square
+//! from which it attacks two enemy pieces threatens a fork.
+//! All terms are computed from the attack bitboards of the position, so pawns and kings are never threatened themselves,
+//! though a king can be one of the forked pieces.
+
+use crate::board::bitboard::Bitboard;
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::evaluation::params::EvalParams;
+use crate::lookup::LOOKUP_TABLE;
+
+/// The bonus for each enemy piece that is attacked and not defended.
+pub const HANGING_PIECE: i32 = 20;
+/// The bonus for each enemy piece that is attacked by a piece of lesser value.
+pub const THREAT_BY_LESSER: i32 = 25;
+/// The bonus for each pawn push that attacks two enemy pieces from a safe square.
+pub const PAWN_PUSH_FORK: i32 = 30;
+
+/// How often each threat parameter contributes to the evaluation of one color.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct ThreatFeatures {
+    /// The number of enemy pieces, except pawns and the king, that are attacked and not defended.
+    pub hanging: i32,
+    /// The number of enemy pieces, except pawns and the king, that are attacked by a piece of lesser value.
+    pub by_lesser: i32,
+    /// The number of pawn pushes to a safe square from which the pawn attacks two enemy pieces, including the king.
+    pub pawn_push_fork: i32,
+}
+
+impl ThreatFeatures {
+    /// Returns the evaluation of the features with the given parameters.
+    pub fn evaluate(&self, params: &EvalParams) -> i32 {
+        self.hanging * params.hanging_piece
+            + self.by_lesser * params.threat_by_lesser
+            + self.pawn_push_fork * params.pawn_push_fork
+    }
+}
+
+/// Returns the evaluation of the threats of the given color against the pieces of the other color with the given parameters.
+pub fn evaluate(position: Position, color: Color, params: &EvalParams) -> i32 {
+    features(position, color).evaluate(params)
+}
+
+/// Returns how often each threat parameter contributes to the evaluation of the given color.
+pub fn features(position: Position, color: Color) -> ThreatFeatures {
+    let mut features = ThreatFeatures::default();
+    let attacks = position.get_attack_bb(color).value;
+    let enemy_attacks = position.get_attack_bb(color.other()).value;
+    let enemy_pieces = |piece: Piece| position.piece_bb(color.other(), piece).value;
+    let enemy_king = enemy_pieces(Piece::King);
+    let targets = position.get_occupancy(color.other()).value & !enemy_pieces(Piece::Pawn) & !enemy_king;
+
+    features.hanging = (targets & attacks & !enemy_attacks).count_ones() as i32;
+
+    // pawns threaten all targets, minor pieces threaten rooks and queens, and rooks threaten queens
+    let piece_attacks = |piece: Piece| position.get_piece_attack_bb(piece, color).value;
+    let major_pieces = enemy_pieces(Piece::Rook) | enemy_pieces(Piece::Queen);
+    let threatened = piece_attacks(Piece::Pawn) & targets
+        | (piece_attacks(Piece::Knight) | piece_attacks(Piece::Bishop)) & major_pieces
+        | piece_attacks(Piece::Rook) & enemy_pieces(Piece::Queen);
+    features.by_lesser = threatened.count_ones() as i32;
+
+    // a pushed pawn is safe if the enemy doesn't attack the square, or if another piece defends it
+    let pawns = position.piece_bb(color, Piece::Pawn).value;
+    let empty = !position.get_occupancies().value;
+    let pushes = match color {
+        Color::White => pawns << 8 & empty,
+        Color::Black => pawns >> 8 & empty,
+    };
+    let safe_pushes = pushes & (!enemy_attacks | attacks);
+    for square in Bitboard::new(safe_pushes).get_active_bits() {
+        if (LOOKUP_TABLE.get_pawn_attacks(square, color).value & (targets | enemy_king)).count_ones() >= 2 {
+            features.pawn_push_fork += 1;
+        }
+    }
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::evaluation::params::EvalParams;
+    use crate::evaluation::threats::{evaluate, features, ThreatFeatures};
+
+    fn features_of(fen: &str, color: Color) -> ThreatFeatures {
+        features(Board::from_fen(fen).unwrap().position, color)
+    }
+
+    #[test]
+    fn test_hanging() {
+        // the knight on e5 is attacked by the rook and not defended, the bishop on b7 is not attacked
+        let fen = "4k3/1b6/8/4n3/8/8/8/4R1K1 w - - 0 1";
+        assert_eq!(1, features_of(fen, Color::White).hanging);
+        assert_eq!(0, features_of("4k3/1b6/3p4/4n3/8/8/8/4R1K1 w - - 0 1", Color::White).hanging);
+
+        // pawns and kings are never hanging
+        assert_eq!(0, features_of("4k3/8/8/4p3/8/8/8/4R1K1 w - - 0 1", Color::White).hanging);
+    }
+
+    #[test]
+    fn test_threat_by_lesser() {
+        // the pawn attacks the defended knight, the knight attacks the rook, and the rook attacks the queen, which both hang
+        let fen = "1k6/1p5r/1p3q2/2n3N1/1P6/8/8/K4R2 w - - 0 1";
+        let white = features_of(fen, Color::White);
+        assert_eq!((2, 3), (white.hanging, white.by_lesser));
+
+        // a queen attacking a rook is no threat by a lesser piece
+        assert_eq!(0, features_of("1k6/1p5r/8/8/8/8/8/K6Q w - - 0 1", Color::White).by_lesser);
+    }
+
+    #[test]
+    fn test_pawn_push_fork() {
+        // d4-d5 forks the knights on c6 and e6
+        let fen = "4k3/8/2n1n3/8/3P4/8/8/4K3 w - - 0 1";
+        assert_eq!(1, features_of(fen, Color::White).pawn_push_fork);
+
+        // the push is not safe if the enemy attacks the square, and not possible if the square is occupied
+        assert_eq!(0, features_of("3rk3/8/2n1n3/8/3P4/8/8/4K3 w - - 0 1", Color::White).pawn_push_fork);
+        assert_eq!(0, features_of("4k3/8/2n1n3/3b4/3P4/8/8/4K3 w - - 0 1", Color::White).pawn_push_fork);
+
+        // the pushed pawn may fork the king, and for Black the pawns are pushed downwards
+        assert_eq!(1, features_of("8/2kp4/8/2K1R3/8/8/8/8 b - - 0 1", Color::Black).pawn_push_fork);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let params = EvalParams::DEFAULT;
+        assert_eq!(0, evaluate(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position, Color::White, &params));
+        assert_eq!(params.hanging_piece, evaluate(Board::from_fen("4k3/1b6/8/4n3/8/8/8/4R1K1 w - - 0 1").unwrap().position, Color::White, &params));
+    }
+}
@@ -0,0 +1,304 @@
+//! This module contains a [Texel tuner](https://www.chessprogramming.org/Texel%27s_Tuning_Method) for the evaluation parameters.
+//!
+//! The tuner loads positions together with the results of the games they were taken from,
+//! and adjusts the evaluation parameters with a local search, so that the
+//! static evaluation predicts the results as well as possible.
+//! The tuned parameters can be written to a file, which can be loaded with the "EvalParamsFile" option.
+//!
+//! Each line of a record file contains a FEN, followed by the result from White's point of view,
+//! e.g. `rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1 [0.5]`.
+//! The result can be given as "1.0", "0.5" and "0.0", or as "1-0", "1/2-1/2" and "0-1",
+//! and may be enclosed in brackets or quotes.
+
+use std::fs;
+use crate::board::Board;
+use crate::board::color::Color;
+use crate::board::piece::NUM_PIECES;
+use crate::evaluation::params::{EvalParams, NUM_SCALARS};
+use crate::board::position::MAX_GAME_PHASE;
+use crate::evaluation::{bishops, coordination, passed_pawns, threats};
+
+/// The index of the first passed pawn parameter, which follow the piece values and the piece-square-tables.
+const PASSED_PAWN_INDEX: usize = NUM_PIECES as usize + NUM_PIECES as usize * 64;
+
+/// The index of the first parameter that consists of a single value, which follow the passed pawn bonuses for each rank.
+const SCALAR_INDEX: usize = PASSED_PAWN_INDEX + 8;
+
+/// The index of the first coordination parameter, which follow the passed pawn parameters that consist of a single value.
+const COORDINATION_INDEX: usize = SCALAR_INDEX + 4;
+
+/// The index of the first bishop parameter, which follow the coordination parameters.
+const BISHOP_INDEX: usize = COORDINATION_INDEX + 5;
+
+/// The index of the first threat parameter, which follow the bishop parameters.
+const THREAT_INDEX: usize = BISHOP_INDEX + 4;
+
+/// The index of the tempo bonus, which is the last of the parameters that consist of a single value.
+const TEMPO_INDEX: usize = SCALAR_INDEX + NUM_SCALARS - 1;
+
+/// The number of tunable parameters: the piece values, the piece-square-tables,
+/// the passed pawn bonuses for each rank, and the parameters that consist of a single value.
+pub const NUM_PARAMS: usize = SCALAR_INDEX + NUM_SCALARS;
+
+/// The index of the king's piece value, which is not tuned, since both sides always have a king.
+const KING_VALUE_INDEX: usize = 5;
+
+/// The largest scaling constant of the sigmoid the tuner considers.
+const MAX_K: f64 = 10.0;
+
+/// A position together with the result of the game it was taken from.
+/// Instead of the position itself, the record stores how often each parameter contributes to the evaluation.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TuningRecord {
+    /// The parameters used by the evaluation of the position, together with how often they are used, with the sign from White's point of view.
+    features: Vec<(u16, i16)>,
+    /// The parameters of the terms that depend on the game phase, for White and for Black, together with their counts weighted by the game phase.
+    /// The evaluation of each color divides their weighted sum by the maximum game phase, just like the evaluation does.
+    tapered_features: [Vec<(u16, i16)>; 2],
+    /// The result of the game from White's point of view: 1.0 for a win, 0.5 for a draw, and 0.0 for a loss.
+    result: f64,
+}
+
+impl TuningRecord {
+    /// Parses a record from a line containing a FEN followed by the result.
+    pub fn parse(line: &str) -> Result<TuningRecord, String> {
+        let mut parts: Vec<&str> = line.split_whitespace().collect();
+        let result_str = parts.pop().ok_or(String::from("Empty tuning record"))?;
+        let result = match result_str.trim_matches(['[', ']', '"', ';']) {
+            "1.0" | "1-0" | "1" => 1.0,
+            "0.5" | "1/2-1/2" => 0.5,
+            "0.0" | "0-1" | "0" => 0.0,
+            _other => return Err(format!("Invalid result in tuning record: {result_str}")),
+        };
+
+        // EPD records only contain the first four fields of the FEN, which the FEN parser accepts as well
+        let fen = parts.join(" ");
+        let position = Board::from_fen(fen.as_str()).map_err(|_| format!("Invalid FEN in tuning record: {fen}"))?.position;
+
+        let mut features = Vec::new();
+        let mut tapered_features = [Vec::new(), Vec::new()];
+        for color in [Color::White, Color::Black] {
+            let (sign, square_mask) = match color {
+                Color::White => (1, 56),
+                Color::Black => (-1, 0),
+            };
+            for (square, piece, _color) in position.color_pieces_iter(color) {
+                let piece_index = piece.to_index() as usize;
+                features.push((piece_index as u16, sign));
+                features.push(((NUM_PIECES as usize + piece_index * 64 + (square.index ^ square_mask) as usize) as u16, sign));
+            }
+
+            let passed_pawns = passed_pawns::features(position, color);
+            let counts = passed_pawns.rank.iter().copied()
+                .chain([passed_pawns.own_king, passed_pawns.enemy_king, passed_pawns.rook_behind, passed_pawns.blocked]);
+            for (index, count) in counts.enumerate().filter(|(_index, count)| *count != 0) {
+                features.push(((PASSED_PAWN_INDEX + index) as u16, sign * count as i16));
+            }
+
+            let coordination = coordination::features(position, color);
+            let counts = [coordination.rook_on_seventh, coordination.queen_on_seventh, coordination.doubled_rooks, coordination.battery, coordination.xray];
+            for (index, count) in counts.into_iter().enumerate().filter(|(_index, count)| *count != 0) {
+                features.push(((COORDINATION_INDEX + index) as u16, sign * count as i16));
+            }
+
+            let counts = bishops::features(position, color).weighted_counts(position.game_phase());
+            for (index, count) in counts.into_iter().enumerate().filter(|(_index, count)| *count != 0) {
+                tapered_features[color.to_index() as usize].push(((BISHOP_INDEX + index) as u16, count as i16));
+            }
+
+            let threats = threats::features(position, color);
+            let counts = [threats.hanging, threats.by_lesser, threats.pawn_push_fork];
+            for (index, count) in counts.into_iter().enumerate().filter(|(_index, count)| *count != 0) {
+                features.push(((THREAT_INDEX + index) as u16, sign * count as i16));
+            }
+        }
+
+        let tempo_sign = match position.color_to_move {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        features.push((TEMPO_INDEX as u16, tempo_sign));
+
+        Ok(TuningRecord { features, tapered_features, result })
+    }
+
+    /// Returns the static evaluation of the position with the given parameters, from White's point of view.
+    fn evaluate(&self, params: &[i32; NUM_PARAMS]) -> i32 {
+        let weighted_sum = |features: &[(u16, i16)]| features.iter().map(|(index, count)| params[*index as usize] * *count as i32).sum::<i32>();
+        let [white, black] = &self.tapered_features;
+        weighted_sum(&self.features) + weighted_sum(white) / MAX_GAME_PHASE as i32 - weighted_sum(black) / MAX_GAME_PHASE as i32
+    }
+}
+
+/// Loads the tuning records from the given file, skipping empty lines and lines starting with '#'.
+pub fn load_records(path: &str) -> Result<Vec<TuningRecord>, String> {
+    let text = fs::read_to_string(path).map_err(|_| String::from("Could not read tuning record file"))?;
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(TuningRecord::parse)
+        .collect()
+}
+
+/// Converts the evaluation parameters into the flat list of parameters used by the tuner.
+pub fn to_flat(params: &EvalParams) -> [i32; NUM_PARAMS] {
+    let mut flat = [0; NUM_PARAMS];
+    flat[..NUM_PIECES as usize].copy_from_slice(&params.piece_values);
+    for (piece_index, table) in params.pst.iter().enumerate() {
+        let start = NUM_PIECES as usize + piece_index * 64;
+        flat[start..start + 64].copy_from_slice(table);
+    }
+    flat[PASSED_PAWN_INDEX..SCALAR_INDEX].copy_from_slice(&params.passed_pawn_rank);
+    flat[SCALAR_INDEX..].copy_from_slice(&params.scalars());
+    flat
+}
+
+/// Converts the flat list of parameters used by the tuner back into evaluation parameters.
+pub fn from_flat(flat: &[i32; NUM_PARAMS]) -> EvalParams {
+    let mut params = EvalParams::DEFAULT;
+    params.piece_values.copy_from_slice(&flat[..NUM_PIECES as usize]);
+    for (piece_index, table) in params.pst.iter_mut().enumerate() {
+        let start = NUM_PIECES as usize + piece_index * 64;
+        table.copy_from_slice(&flat[start..start + 64]);
+    }
+    params.passed_pawn_rank.copy_from_slice(&flat[PASSED_PAWN_INDEX..SCALAR_INDEX]);
+    for (scalar, value) in params.scalars_mut().into_iter().zip(&flat[SCALAR_INDEX..]) {
+        *scalar = *value;
+    }
+    params
+}
+
+/// Maps an evaluation in centipawns to the expected result from White's point of view.
+fn sigmoid(evaluation: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * evaluation as f64 / 400.0))
+}
+
+/// Returns the mean squared error between the expected results of the evaluations and the actual results.
+pub fn mean_squared_error(records: &[TuningRecord], params: &[i32; NUM_PARAMS], k: f64) -> f64 {
+    if records.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = records.iter().map(|record| (record.result - sigmoid(record.evaluate(params), k)).powi(2)).sum();
+    total / records.len() as f64
+}
+
+/// Finds the scaling constant of the sigmoid that minimizes the error for the given parameters.
+/// This has to be done once before tuning, so that the tuner does not just scale all parameters.
+pub fn find_k(records: &[TuningRecord], params: &[i32; NUM_PARAMS]) -> f64 {
+    let mut best_k = 1.0;
+    let mut best_error = mean_squared_error(records, params, best_k);
+    let mut step = 0.5;
+    while step > 0.001 {
+        let mut improved = false;
+        for k in [best_k - step, best_k + step] {
+            let error = mean_squared_error(records, params, k);
+            if k > 0.0 && k <= MAX_K && error < best_error {
+                best_k = k;
+                best_error = error;
+                improved = true;
+            }
+        }
+        if !improved {
+            step /= 2.0;
+        }
+    }
+    best_k
+}
+
+/// Tunes the given evaluation parameters with a local search, which changes one parameter at a time by one centipawn,
+/// and keeps the change if it reduces the error.
+///
+/// The search stops once no change reduces the error, or after the given number of iterations over all parameters.
+/// After each iteration, the report function is called with the number of the iteration and the current error.
+pub fn tune(records: &[TuningRecord], params: &EvalParams, iterations: usize, mut report: impl FnMut(usize, f64)) -> EvalParams {
+    let mut flat = to_flat(params);
+    let k = find_k(records, &flat);
+    let mut best_error = mean_squared_error(records, &flat, k);
+
+    for iteration in 1..=iterations {
+        let mut improved = false;
+        for index in (0..NUM_PARAMS).filter(|index| *index != KING_VALUE_INDEX) {
+            for delta in [1, -1] {
+                flat[index] += delta;
+                let error = mean_squared_error(records, &flat, k);
+                if error < best_error {
+                    best_error = error;
+                    improved = true;
+                    break;
+                }
+                flat[index] -= delta;
+            }
+        }
+        report(iteration, best_error);
+        if !improved {
+            break;
+        }
+    }
+
+    from_flat(&flat)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::evaluation::evaluation_terms;
+    use crate::evaluation::params::EvalParams;
+    use crate::evaluation::tuner::{find_k, from_flat, mean_squared_error, to_flat, tune, TuningRecord};
+
+    #[test]
+    fn test_parse_record() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let record = TuningRecord::parse(format!("{fen} [0.5]").as_str()).unwrap();
+        assert_eq!(0.5, record.result);
+
+        // the features evaluate to the same value as the evaluation, also with passed pawns, coordinated pieces, bishops and threats
+        for fen in [fen, "3r2k1/5p2/1P6/8/3p4/8/5PPP/1R4K1 b - - 0 1", "4k3/3R4/7q/8/4n3/8/3P4/2B1R1K1 w - - 0 1",
+            "2b1kb2/p4ppp/1p2p3/3pP3/3P4/8/PPP2PPP/2B1KB2 b - - 0 1", "1k6/1p5r/1p3q2/2n3N1/1P6/8/8/K4R2 w - - 0 1"] {
+            let record = TuningRecord::parse(format!("{fen} [0.5]").as_str()).unwrap();
+            let position = Board::from_fen(fen).unwrap().position;
+            let expected = evaluation_terms(position, Color::White).total() - evaluation_terms(position, Color::Black).total();
+            assert_eq!(expected, record.evaluate(&to_flat(&EvalParams::DEFAULT)));
+        }
+
+        assert_eq!(1.0, TuningRecord::parse(format!("{fen} 1-0").as_str()).unwrap().result);
+        assert_eq!(0.0, TuningRecord::parse(format!("{fen} \"0-1\";").as_str()).unwrap().result);
+        assert_eq!(0.5, TuningRecord::parse("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 1/2-1/2").unwrap().result);
+        assert!(TuningRecord::parse(format!("{fen} 2-0").as_str()).is_err());
+        assert!(TuningRecord::parse("this is not a fen 1-0").is_err());
+        assert!(TuningRecord::parse("").is_err());
+    }
+
+    #[test]
+    fn test_flat_params() {
+        let mut params = EvalParams::DEFAULT;
+        assert_eq!(params, from_flat(&to_flat(&params)));
+        params.passed_pawn_rank[6] = 80;
+        params.passed_pawn_blocked = -30;
+        params.tempo = 20;
+        assert_eq!(params, from_flat(&to_flat(&params)));
+    }
+
+    #[test]
+    fn test_tune() {
+        // White is a knight up, but draws - the knight should lose value
+        let records: Vec<TuningRecord> = [
+            "4k3/8/8/8/8/8/8/3NK3 w - - 0 1 0.5",
+            "4k3/8/8/8/8/8/8/3NK3 b - - 0 1 0.5",
+            "4k3/8/8/8/8/8/3P4/4K3 w - - 0 1 1.0",
+            "4k3/3p4/8/8/8/8/8/4K3 w - - 0 1 0.0",
+        ].iter().map(|line| TuningRecord::parse(line).unwrap()).collect();
+
+        let params = EvalParams::DEFAULT;
+        let flat = to_flat(&params);
+        let k = find_k(&records, &flat);
+        let error = mean_squared_error(&records, &flat, k);
+
+        let mut reports = 0;
+        let tuned = tune(&records, &params, 3, |_iteration, _error| reports += 1);
+        assert_eq!(3, reports);
+        assert!(mean_squared_error(&records, &to_flat(&tuned), k) < error);
+        assert!(tuned.piece_values[1] < params.piece_values[1]);
+        assert_eq!(params.piece_values[5], tuned.piece_values[5]);
+    }
+}
@@ -0,0 +1,54 @@
+//! The wdl module converts scores into win, draw and loss probabilities, which GUIs show with the "UCI_ShowWDL" option.
+//!
+//! The model is a logistic curve: the win probability rises from almost 0 to almost 1 as the score goes from a large
+//! disadvantage to a large advantage, and is 50% at `WIN_SCORE`. The loss probability is the same curve for the opponent,
+//! and whatever is left over is the draw probability. This matches the observation that even positions are usually drawn,
+//! and that an advantage of a few pawns is usually enough to win.
+
+/// The score in centipawns at which the side to move wins half of the games.
+const WIN_SCORE: f64 = 200.0;
+/// How quickly the win probability rises with the score, in centipawns. Smaller values make the curve steeper.
+const WIN_SCORE_SCALE: f64 = 80.0;
+
+/// Returns the win, draw and loss probabilities in per mille for the given score, from the point of view of the side to move.
+/// The three probabilities always add up to 1000.
+pub fn wdl(score: i32) -> (u32, u32, u32) {
+    let win = win_rate(score as f64);
+    let loss = win_rate(-(score as f64)).min(1000 - win);
+    (win, 1000 - win - loss, loss)
+}
+
+/// Returns the probability in per mille that the side to move wins with the given score.
+fn win_rate(score: f64) -> u32 {
+    (1000.0 / (1.0 + ((WIN_SCORE - score) / WIN_SCORE_SCALE).exp())).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::{NEGATIVE_INFINITY, POSITIVE_INFINITY};
+    use crate::evaluation::wdl::wdl;
+
+    #[test]
+    fn test_wdl() {
+        // even positions are mostly drawn
+        let (win, draw, loss) = wdl(0);
+        assert_eq!(win, loss);
+        assert!(draw > 800);
+
+        // an advantage of a few pawns mostly wins
+        let (win, draw, loss) = wdl(300);
+        assert!(win > 700);
+        assert_eq!(1000, win + draw + loss);
+        assert_eq!((loss, draw, win), wdl(-300));
+
+        // the probabilities rise with the score
+        assert!(wdl(100).0 < wdl(150).0);
+        assert!(wdl(100).2 > wdl(150).2);
+    }
+
+    #[test]
+    fn test_wdl_for_mate_scores() {
+        assert_eq!((1000, 0, 0), wdl(POSITIVE_INFINITY - 5000));
+        assert_eq!((0, 0, 1000), wdl(NEGATIVE_INFINITY + 5000));
+    }
+}
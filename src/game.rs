@@ -0,0 +1,441 @@
+//! The game module allows using Ladybug as a chess library.
+//! A game owns the current board together with the moves that led to it, so that moves can be taken back,
+//! and detects the end of the game by checkmate, stalemate or one of the draw rules.
+
+use arrayvec::ArrayVec;
+use crate::board::Board;
+use crate::board::fen::FenError;
+use crate::board::color::Color;
+use crate::board::history::{BoardHistory, MAX_HISTORY_LENGTH};
+use crate::move_gen;
+use crate::move_gen::move_list::MoveList;
+use crate::move_gen::ply::{Ply, PlyParseError};
+
+/// The FEN of the starting position.
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// The maximum number of moves a game can hold, limited by the board history that is passed to the search.
+pub const MAX_GAME_LENGTH: usize = MAX_HISTORY_LENGTH;
+
+/// The maximum length of a line of PGN movetext.
+const PGN_LINE_LENGTH: usize = 80;
+
+/// The result of a finished game of chess.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    /// Returns the result as written in PGN files, e.g. "1-0" if White wins.
+    pub fn to_pgn(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        }
+    }
+}
+
+/// The ways a game of chess can end.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Outcome {
+    /// The given color has checkmated the opponent.
+    Checkmate(Color),
+    /// The color to move has no legal moves, but is not in check.
+    Stalemate,
+    /// The same position has occurred three times.
+    ThreefoldRepetition,
+    /// No pawn has been moved and no piece has been captured in the last fifty moves.
+    FiftyMoveRule,
+    /// Neither side has enough material left to checkmate the opponent.
+    InsufficientMaterial,
+    /// The given color has won by a rule of the variant, e.g. by bringing the king to the center in King of the Hill.
+    VariantWin(Color),
+}
+
+impl Outcome {
+    /// Returns the winner of the game, or None if the game is drawn.
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate(color) | Outcome::VariantWin(color) => Some(*color),
+            _draw => None,
+        }
+    }
+
+    /// Returns the result of the game that ended this way.
+    pub fn result(&self) -> GameResult {
+        match self.winner() {
+            Some(Color::White) => GameResult::WhiteWins,
+            Some(Color::Black) => GameResult::BlackWins,
+            None => GameResult::Draw,
+        }
+    }
+}
+
+/// Represents a game of chess, containing the current board as well as the board history.
+#[derive(Clone, Debug)]
+pub struct Game {
+    /// The current board.
+    board: Board,
+    /// The moves that have been played on the board, which can be taken back.
+    history: BoardHistory,
+}
+
+impl Default for Game {
+    /// Default constructor for Game.
+    /// Returns a game starting from an empty board.
+    fn default() -> Self {
+        Game::from_board(Board::default())
+    }
+}
+
+impl Game {
+    /// Constructs a game starting from the starting position.
+    pub fn new() -> Game {
+        Game::from_fen(START_FEN).expect("The starting position is valid")
+    }
+
+    /// Constructs a game starting from the given board.
+    pub fn from_board(board: Board) -> Game {
+        Game {
+            board,
+            history: BoardHistory::new(),
+        }
+    }
+
+    /// Constructs a game starting from the position described by the given FEN string.
+    /// If the FEN could not be parsed, the result will contain an error.
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        Ok(Game::from_board(Board::from_fen(fen)?))
+    }
+
+    /// Returns the current board.
+    pub fn board(&self) -> Board {
+        self.board
+    }
+
+    /// Returns the board the game started from, before any moves were played.
+    pub fn initial_board(&self) -> Board {
+        self.history.moves().first().map(|(_ply, board)| *board).unwrap_or(self.board)
+    }
+
+    /// Returns the hashes of all positions that have been on the board before the current one.
+    pub fn board_history(&self) -> &ArrayVec<u64, MAX_GAME_LENGTH> {
+        self.history.hashes()
+    }
+
+    /// Returns the moves that have been played, starting with the first one.
+    pub fn moves(&self) -> Vec<Ply> {
+        self.history.moves().iter().map(|(ply, _board)| *ply).collect()
+    }
+
+    /// Returns the move that has been played last, or None if no move has been played yet.
+    pub fn last_move(&self) -> Option<Ply> {
+        self.history.last_move()
+    }
+
+    /// Returns all legal moves in the current position.
+    pub fn legal_moves(&self) -> MoveList {
+        move_gen::generate_moves(self.board.position)
+    }
+
+    /// Finds the legal move given in pure algebraic coordinate notation, e.g. "e2e4" or "e7e8q".
+    /// Promotions without a promotion piece are rejected as ambiguous (see `Ply::from_string`).
+    pub fn parse_move(&self, move_str: &str) -> Result<Ply, PlyParseError> {
+        Ply::from_string(move_str, self.board.position)
+    }
+
+    /// Finds the legal move given in standard algebraic notation, e.g. "Nf3", "exd5", "e8=Q" or "O-O".
+    /// Check and annotation symbols are ignored, and castling may also be written with zeros.
+    pub fn parse_san(&self, san: &str) -> Result<Ply, String> {
+        let normalize = |san: &str| san.trim_end_matches(['+', '#', '!', '?']).replace('0', "O");
+        let normalized = normalize(san);
+        let move_list = self.legal_moves();
+        for i in 0..move_list.len() {
+            let ply = move_list.get(i);
+            if normalize(ply.to_san(self.board.position).as_str()) == normalized {
+                return Ok(ply);
+            }
+        }
+        Err(format!("Illegal move: {san}"))
+    }
+
+    /// Plays the given move.
+    /// If the move is not legal, the game is not changed and the result contains an error.
+    ///
+    /// Moves can still be played after a draw by threefold repetition or the fifty move rule,
+    /// since these draws have to be claimed by the players.
+    pub fn make_move(&mut self, ply: Ply) -> Result<(), String> {
+        if !self.board.position.is_legal_move(ply) {
+            return Err(format!("Illegal move: {ply}"));
+        }
+        self.board.push(ply, &mut self.history).map_err(|error| error.to_string())
+    }
+
+    /// Takes back the last move and returns it, or returns None if no move has been played yet.
+    pub fn undo(&mut self) -> Option<Ply> {
+        self.board.pop(&mut self.history)
+    }
+
+    /// Returns how the game ended, or None if the game is not over yet.
+    ///
+    /// Draws by threefold repetition and the fifty move rule are reported as soon as they can be claimed.
+    pub fn outcome(&self) -> Option<Outcome> {
+        let position = self.board.position;
+
+        if let Some(color) = position.variant_winner() {
+            return Some(Outcome::VariantWin(color));
+        }
+        if position.is_checkmate() {
+            return Some(Outcome::Checkmate(position.color_to_move.other()));
+        }
+        if position.is_stalemate() {
+            return Some(Outcome::Stalemate);
+        }
+        if self.board.halfmove_clock >= 100 {
+            return Some(Outcome::FiftyMoveRule);
+        }
+        if self.is_threefold_repetition() {
+            return Some(Outcome::ThreefoldRepetition);
+        }
+        if self.board.position.is_insufficient_material() {
+            return Some(Outcome::InsufficientMaterial);
+        }
+        None
+    }
+
+    /// Returns the result of the game, or None if the game is not over yet.
+    pub fn result(&self) -> Option<GameResult> {
+        self.outcome().map(|outcome| outcome.result())
+    }
+
+    /// Returns the game in [PGN](https://www.chessprogramming.org/Portable_Game_Notation), starting with the given tags,
+    /// followed by the result and, if the game didn't start from the starting position, the FEN of the first position.
+    ///
+    /// If no result is given, the result of the game is used, or "*" if the game is not over yet.
+    pub fn to_pgn(&self, tags: &[(&str, String)], result: Option<GameResult>) -> String {
+        let result = result.or(self.result()).map_or("*", |result| result.to_pgn());
+        let first_board = self.initial_board();
+
+        let mut pgn = String::new();
+        for (name, value) in tags {
+            pgn += format!("[{name} \"{value}\"]\n").as_str();
+        }
+        pgn += format!("[Result \"{result}\"]\n").as_str();
+        if first_board.to_fen() != START_FEN {
+            pgn += format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", first_board.to_fen()).as_str();
+        }
+        pgn += "\n";
+
+        // the movetext, with a move number in front of each move of White, and in front of the first move if Black moves first
+        let mut tokens = Vec::new();
+        for (index, (ply, board)) in self.history.moves().iter().enumerate() {
+            match board.position.color_to_move {
+                Color::White => tokens.push(format!("{}.", board.fullmove_counter)),
+                Color::Black if index == 0 => tokens.push(format!("{}...", board.fullmove_counter)),
+                Color::Black => {}
+            }
+            tokens.push(ply.to_san(board.position));
+        }
+        tokens.push(result.to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > PGN_LINE_LENGTH {
+                pgn += format!("{line}\n").as_str();
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += token.as_str();
+        }
+        pgn += format!("{line}\n").as_str();
+        pgn
+    }
+
+    /// Returns whether the current position has occurred at least three times.
+    /// Only positions since the last capture or pawn move can repeat the current one.
+    fn is_threefold_repetition(&self) -> bool {
+        let repetitions = self.history.hashes().iter().rev()
+            .take(self.board.halfmove_clock as usize)
+            .filter(|hash| **hash == self.board.position.hash)
+            .count();
+        // the current position is the third occurrence
+        repetitions >= 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::board::color::Color;
+    use crate::board::piece::Piece;
+    use crate::board::{square, variant};
+    use crate::game::{Game, GameResult, Outcome, START_FEN};
+    use crate::move_gen::ply::{Ply, PlyParseError};
+
+    /// Plays the given moves, which are given in pure algebraic coordinate notation.
+    fn play(game: &mut Game, moves: &str) {
+        for move_string in moves.split_whitespace() {
+            let ply = Ply::from_string(move_string, game.board().position).unwrap();
+            game.make_move(ply).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_make_move_and_undo() {
+        let mut game = Game::new();
+        assert_eq!(START_FEN, game.board().to_fen());
+        assert_eq!(20, game.legal_moves().len());
+        assert_eq!(None, game.undo());
+        assert_eq!(None, game.last_move());
+
+        play(&mut game, "e2e4 e7e5 g1f3");
+        assert_eq!("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2", game.board().to_fen());
+        assert_eq!(START_FEN, game.initial_board().to_fen());
+        assert_eq!(3, game.moves().len());
+        assert_eq!(3, game.board_history().len());
+
+        // illegal moves are rejected
+        let illegal_ply = Ply::new(square::E8, square::E6, Piece::King, None, false);
+        assert!(game.make_move(illegal_ply).is_err());
+        assert_eq!(3, game.moves().len());
+
+        // moves are taken back in reverse order
+        assert_eq!(Some(Ply::new(square::G1, square::F3, Piece::Knight, None, false)), game.last_move());
+        assert_eq!(Some(Ply::new(square::G1, square::F3, Piece::Knight, None, false)), game.undo());
+        assert_eq!(Some(Ply::new(square::E7, square::E5, Piece::Pawn, None, false)), game.last_move());
+        assert_eq!("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2", game.board().to_fen());
+        assert_eq!(2, game.board_history().len());
+        game.undo();
+        game.undo();
+        assert_eq!(START_FEN, game.board().to_fen());
+        assert!(game.moves().is_empty());
+        assert!(game.board_history().is_empty());
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let game = Game::new();
+        assert_eq!("e2e4", game.parse_move("e2e4").unwrap().to_string());
+        assert_eq!("g1f3", game.parse_move("G1F3").unwrap().to_string());
+        assert_eq!(Err(PlyParseError::IllegalMove(String::from("e2e5"))), game.parse_move("e2e5"));
+        assert_eq!(Err(PlyParseError::NoPieceToMove(square::E7)), game.parse_move("e7e5"));
+        assert_eq!(Err(PlyParseError::WrongLength(String::from("e2"))), game.parse_move("e2"));
+
+        let game = Game::from_fen("3r4/4P3/8/8/8/8/k7/4K3 w - - 0 1").unwrap();
+        assert_eq!("e7e8q", game.parse_move("e7e8q").unwrap().to_string());
+        assert_eq!("e7d8n", game.parse_move("e7d8N").unwrap().to_string());
+        assert_eq!(Err(PlyParseError::MissingPromotionPiece(String::from("e7e8"))), game.parse_move("e7e8"));
+        assert_eq!(Err(PlyParseError::InvalidPromotionPiece('k')), game.parse_move("e7e8k"));
+    }
+
+    #[test]
+    fn test_parse_san() {
+        let mut game = Game::new();
+        assert_eq!("e2e4", game.parse_san("e4").unwrap().to_string());
+        assert_eq!("g1f3", game.parse_san("Nf3!?").unwrap().to_string());
+        assert_eq!(Err(String::from("Illegal move: e5")), game.parse_san("e5"));
+        assert_eq!(Err(String::from("Illegal move: e2e4")), game.parse_san("e2e4"));
+
+        play(&mut game, "e2e4 e7e5 g1f3 b8c6 f1c4 g8f6");
+        assert_eq!("e1g1", game.parse_san("O-O").unwrap().to_string());
+        assert_eq!("e1g1", game.parse_san("0-0").unwrap().to_string());
+        assert_eq!("c4f7", game.parse_san("Bxf7+").unwrap().to_string());
+        assert_eq!("c4f7", game.parse_san("Bxf7").unwrap().to_string());
+
+        let game = Game::from_fen("3r4/4P3/8/8/8/8/k7/4K3 w - - 0 1").unwrap();
+        assert_eq!("e7d8n", game.parse_san("exd8=N").unwrap().to_string());
+        assert_eq!("e7e8q", game.parse_san("e8=Q").unwrap().to_string());
+        assert_eq!(Err(String::from("Illegal move: e8")), game.parse_san("e8"));
+    }
+
+    #[test]
+    fn test_game_result_to_pgn() {
+        assert_eq!("1-0", GameResult::WhiteWins.to_pgn());
+        assert_eq!("0-1", GameResult::BlackWins.to_pgn());
+        assert_eq!("1/2-1/2", GameResult::Draw.to_pgn());
+    }
+
+    #[test]
+    fn test_to_pgn() {
+        let mut game = Game::new();
+        play(&mut game, "f2f3 e7e5 g2g4 d8h4");
+        let tags = [("Event", String::from("Test")), ("Round", String::from("1"))];
+        assert_eq!("[Event \"Test\"]\n[Round \"1\"]\n[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n", game.to_pgn(&tags, None));
+
+        // games that don't start from the starting position contain the FEN of the first position
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40").unwrap();
+        play(&mut game, "e8d7 e2e4");
+        assert_eq!("[Result \"1/2-1/2\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 40\"]\n\n40... Kd7 41. e4 1/2-1/2\n",
+            game.to_pgn(&[], Some(GameResult::Draw)));
+        assert!(game.to_pgn(&[], None).ends_with(" *\n"));
+
+        // long games are wrapped
+        let mut game = Game::new();
+        play(&mut game, "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8 b1c3 b8c6 c3b1 c6b8 b1c3 b8c6 c3b1 c6b8");
+        let pgn = game.to_pgn(&[], None);
+        assert!(pgn.lines().all(|line| line.len() <= 80));
+        assert!(pgn.ends_with(" 7. Nc3 Nc6 8.\nNb1 Nb8 1/2-1/2\n"));
+    }
+
+    #[test]
+    fn test_outcome() {
+        // checkmate
+        let mut game = Game::new();
+        play(&mut game, "f2f3 e7e5 g2g4");
+        assert_eq!(None, game.outcome());
+        play(&mut game, "d8h4");
+        assert_eq!(Some(Outcome::Checkmate(Color::Black)), game.outcome());
+        assert_eq!(Some(Color::Black), game.outcome().unwrap().winner());
+        assert_eq!(Some(GameResult::BlackWins), game.result());
+        assert!(game.legal_moves().is_empty());
+        let ply = Ply::new(square::A2, square::A3, Piece::Pawn, None, false);
+        assert!(game.make_move(ply).is_err());
+
+        // the game continues after the checkmate is taken back
+        game.undo();
+        assert_eq!(None, game.outcome());
+        assert_eq!(None, game.result());
+
+        // stalemate
+        let game = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(Some(Outcome::Stalemate), game.outcome());
+        assert_eq!(None, game.outcome().unwrap().winner());
+        assert_eq!(Some(GameResult::Draw), game.result());
+
+        // threefold repetition
+        let mut game = Game::new();
+        play(&mut game, "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1");
+        assert_eq!(None, game.outcome());
+        play(&mut game, "f6g8");
+        assert_eq!(Some(Outcome::ThreefoldRepetition), game.outcome());
+
+        // fifty move rule
+        let game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 100 80").unwrap();
+        assert_eq!(Some(Outcome::FiftyMoveRule), game.outcome());
+        let game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 99 80").unwrap();
+        assert_eq!(None, game.outcome());
+
+        // insufficient material
+        for fen in ["4k3/8/8/8/8/8/8/4K3 w - - 0 1", "4k3/8/8/8/8/8/8/4KN2 w - - 0 1", "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"] {
+            assert_eq!(Some(Outcome::InsufficientMaterial), Game::from_fen(fen).unwrap().outcome(), "{fen}");
+        }
+        for fen in ["4k3/8/8/8/8/8/8/3NKN2 w - - 0 1", "4kb2/8/8/8/8/8/8/3BK3 w - - 0 1", "4k3/8/8/8/8/8/8/4KR2 w - - 0 1"] {
+            assert_eq!(None, Game::from_fen(fen).unwrap().outcome(), "{fen}");
+        }
+
+        // the king reaches the center in King of the Hill
+        let mut board = Board::from_fen("4k3/8/8/8/8/3K4/8/7R w - - 0 1").unwrap();
+        board.position.set_variant(variant::from_name("kingofthehill").unwrap());
+        let mut game = Game::from_board(board);
+        assert_eq!(None, game.outcome());
+        play(&mut game, "d3d4");
+        assert_eq!(Some(Outcome::VariantWin(Color::White)), game.outcome());
+        assert_eq!(Some(GameResult::WhiteWins), game.result());
+    }
+}
@@ -0,0 +1,234 @@
+//! The json module contains a small JSON parser, which reads the events of the Lichess Bot API and evaluation parameter files.
+
+use std::collections::HashMap;
+
+/// A JSON value.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(HashMap<String, Json>),
+}
+
+impl Json {
+    /// Parses the given text as a single JSON value.
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = JsonParser { chars: text.chars().collect(), index: 0 };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        match parser.index == parser.chars.len() {
+            true => Ok(value),
+            false => Err(format!("Unexpected characters after JSON value at {}", parser.index)),
+        }
+    }
+
+    /// Returns the value of the given key, if the value is an object that contains the key.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.get(key),
+            _other => None,
+        }
+    }
+
+    /// Returns the string, if the value is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string.as_str()),
+            _other => None,
+        }
+    }
+
+    /// Returns the number, if the value is an integer that fits into an i32.
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Json::Number(number) if number.fract() == 0.0 && (i32::MIN as f64..=i32::MAX as f64).contains(number) => Some(*number as i32),
+            _other => None,
+        }
+    }
+
+    /// Returns the number, if the value is a non-negative number.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(number) if *number >= 0.0 => Some(*number as u64),
+            _other => None,
+        }
+    }
+}
+
+/// Parses JSON text character by character.
+struct JsonParser {
+    /// The characters of the text.
+    chars: Vec<char>,
+    /// The index of the next character.
+    index: usize,
+}
+
+impl JsonParser {
+    /// Skips the whitespace in front of the next token.
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.index).is_some_and(|c| c.is_whitespace()) {
+            self.index += 1;
+        }
+    }
+
+    /// Consumes the given character, or returns an error if the next character is a different one.
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.get(self.index) {
+            Some(c) if *c == expected => {
+                self.index += 1;
+                Ok(())
+            }
+            _other => Err(format!("Expected '{expected}' at {}", self.index)),
+        }
+    }
+
+    /// Consumes the given keyword, e.g. "true".
+    fn parse_keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        let end = self.index + keyword.len();
+        match self.chars.get(self.index..end).is_some_and(|chars| chars.iter().copied().eq(keyword.chars())) {
+            true => {
+                self.index = end;
+                Ok(value)
+            }
+            false => Err(format!("Invalid JSON value at {}", self.index)),
+        }
+    }
+
+    /// Parses the next value.
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.index) {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => self.parse_string().map(Json::String),
+            Some('t') => self.parse_keyword("true", Json::Bool(true)),
+            Some('f') => self.parse_keyword("false", Json::Bool(false)),
+            Some('n') => self.parse_keyword("null", Json::Null),
+            Some(_c) => self.parse_number(),
+            None => Err(String::from("Unexpected end of JSON")),
+        }
+    }
+
+    /// Parses the next object.
+    fn parse_object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut entries = HashMap::new();
+        self.skip_whitespace();
+        if self.chars.get(self.index) == Some(&'}') {
+            self.index += 1;
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            entries.insert(key, self.parse_value()?);
+            self.skip_whitespace();
+            match self.chars.get(self.index) {
+                Some(',') => self.index += 1,
+                Some('}') => {
+                    self.index += 1;
+                    return Ok(Json::Object(entries));
+                }
+                _other => return Err(format!("Expected ',' or '}}' at {}", self.index)),
+            }
+        }
+    }
+
+    /// Parses the next array.
+    fn parse_array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.chars.get(self.index) == Some(&']') {
+            self.index += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.chars.get(self.index) {
+                Some(',') => self.index += 1,
+                Some(']') => {
+                    self.index += 1;
+                    return Ok(Json::Array(values));
+                }
+                _other => return Err(format!("Expected ',' or ']' at {}", self.index)),
+            }
+        }
+    }
+
+    /// Parses the next string, replacing the escape sequences by the characters they stand for.
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            let Some(c) = self.chars.get(self.index).copied() else {
+                return Err(String::from("Unterminated JSON string"));
+            };
+            self.index += 1;
+            match c {
+                '"' => return Ok(string),
+                '\\' => {
+                    let Some(escaped) = self.chars.get(self.index).copied() else {
+                        return Err(String::from("Unterminated JSON string"));
+                    };
+                    self.index += 1;
+                    match escaped {
+                        'n' => string.push('\n'),
+                        't' => string.push('\t'),
+                        'r' => string.push('\r'),
+                        'b' => string.push('\u{8}'),
+                        'f' => string.push('\u{c}'),
+                        'u' => {
+                            let hex: String = self.chars.get(self.index..self.index + 4).unwrap_or_default().iter().collect();
+                            let code = u32::from_str_radix(hex.as_str(), 16).map_err(|_| format!("Invalid unicode escape at {}", self.index))?;
+                            string.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                            self.index += 4;
+                        }
+                        other => string.push(other),
+                    }
+                }
+                other => string.push(other),
+            }
+        }
+    }
+
+    /// Parses the next number.
+    fn parse_number(&mut self) -> Result<Json, String> {
+        let start = self.index;
+        while self.chars.get(self.index).is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+            self.index += 1;
+        }
+        let number: String = self.chars[start..self.index].iter().collect();
+        number.parse::<f64>().map(Json::Number).map_err(|_| format!("Invalid JSON value at {start}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::json::Json;
+
+    #[test]
+    fn test_parse_json() {
+        let json = Json::parse(r#" {"a": [1, -2.5, true, null], "b": {"c": "d\"eA"}, "f": {}} "#).unwrap();
+        assert_eq!(Some(&Json::Array(vec![Json::Number(1.0), Json::Number(-2.5), Json::Bool(true), Json::Null])), json.get("a"));
+        assert_eq!(Some("d\"eA"), json.get("b").and_then(|b| b.get("c")).and_then(|c| c.as_str()));
+        assert_eq!(Some(&Json::Object(HashMap::new())), json.get("f"));
+        assert_eq!(None, json.get("g"));
+        assert_eq!(Some(-3), Json::parse("-3").unwrap().as_i32());
+        assert_eq!(None, Json::parse("-2.5").unwrap().as_i32());
+        assert_eq!(None, Json::parse("3000000000").unwrap().as_i32());
+
+        assert!(Json::parse("{\"a\": }").is_err());
+        assert!(Json::parse("[1, 2").is_err());
+        assert!(Json::parse("\"abc").is_err());
+        assert!(Json::parse("tru").is_err());
+        assert!(Json::parse("1 2").is_err());
+    }
+}
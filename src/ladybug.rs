@@ -1,33 +1,178 @@
-use std::sync::mpsc::{Receiver, Sender};
-use arrayvec::ArrayVec;
+use std::cell::Cell;
+use std::fs;
+use std::io::BufRead;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+use crate::analysis;
+use crate::analysis::{Analysis, AnalysisCommand, HINT_DEPTH};
 use crate::board::Board;
 use crate::board::color::Color;
+use crate::board::position::Position;
+use crate::board::variant;
+use crate::board::variant::Variant;
+use crate::book::{internal, Book};
+use crate::config;
+use crate::config::CONFIG_FILE;
+use crate::epd;
+use crate::evaluation::params::EvalParams;
+use crate::game::{Game, START_FEN};
+use crate::logger::Logger;
+use crate::move_gen::perft::MAX_PERFT_DEPTH;
 use crate::move_gen::ply::Ply;
-use crate::search::SearchCommand;
+use crate::search::bench;
+use crate::search::bench::BENCH_DEPTH;
+use crate::search::speedtest::SPEEDTEST_DEPTH;
+use crate::search::perft_suite;
+use crate::search::ponder::{DEFAULT_PONDER_CREDIT, MAX_PONDER_CREDIT};
+use crate::search::negamax::{DEFAULT_REVERSE_FUTILITY_DEPTH, DEFAULT_REVERSE_FUTILITY_MARGIN, MAX_REVERSE_FUTILITY_DEPTH, MAX_REVERSE_FUTILITY_MARGIN};
+use crate::search::root_split::MAX_THREADS;
+use crate::search::opening_variety::{get_random_seed, xorshift};
+use crate::search::adjudication::{Adjudication, DrawRule, ResignRule};
+use crate::search::engine_match::MatchSettings;
+use crate::search::selfplay::SelfPlaySettings;
+use crate::search::{SearchCommand, SearchFeature};
+use crate::search::skill_level::MAX_SKILL_LEVEL;
+use crate::search::time_manager;
+use crate::search::transposition_table::{DEFAULT_HASH_SIZE, MAX_HASH_SIZE, MIN_HASH_SIZE};
 use crate::uci;
 use crate::uci::{UciCommand};
 
+/// The maximum number of messages that can be queued for Ladybug.
+/// If the queue is full, the input thread blocks, and the search drops info messages instead of queueing them.
+pub const MESSAGE_CHANNEL_CAPACITY: usize = 1024;
+
+/// The maximum number of lines that can be queued for the output thread.
+/// If the queue is full, Ladybug blocks until the output thread catches up.
+pub const OUTPUT_CHANNEL_CAPACITY: usize = 1024;
+
+/// The maximum value of the "OpeningVariety" option.
+pub const MAX_OPENING_VARIETY: u32 = 100;
+
+/// The maximum value of the "Variety" option in centipawns.
+pub const MAX_VARIETY: i32 = 100;
+
+/// The maximum absolute value of the "Contempt" option in centipawns.
+pub const MAX_CONTEMPT: i32 = 100;
+
+/// The default value of the "Move Overhead" option in milliseconds.
+pub const DEFAULT_MOVE_OVERHEAD: u64 = 10;
+
+/// The maximum value of the "Move Overhead" option in milliseconds.
+pub const MAX_MOVE_OVERHEAD: u64 = 5000;
+
+/// The search never uses more than this fraction of the remaining time for a single move,
+/// so that a large increment can't make Ladybug lose on time when she is low on the clock.
+const MAX_TIME_FRACTION: u64 = 2;
+
+/// Returns the time in milliseconds to search for the next move, given the remaining time and the increment.
+///
+/// The move overhead is the time lost to communication between Ladybug and the GUI, which is reserved for each move.
+/// The search always gets at least 1 millisecond, so that it can return a best move.
+pub fn allocate_time(time_left: u64, increment: u64, move_overhead: u64) -> u64 {
+    let available = time_left.saturating_sub(move_overhead);
+    let time = (available / 40 + increment).min(available / MAX_TIME_FRACTION);
+    time.max(1)
+}
+
+/// Reads the input lines from the given reader, e.g. Stdin, and sends them to Ladybug.
+///
+/// Protocol adapters and GUIs may send carriage returns, byte order marks or invalid UTF-8, and may close the input
+/// without sending "quit". Line endings and byte order marks are removed, invalid bytes are replaced, and empty lines are skipped.
+/// If the input is closed or can't be read anymore, Ladybug is told to quit, so that she doesn't keep running without a GUI.
+pub fn read_input(mut reader: impl BufRead, sender: SyncSender<Message>) {
+    loop {
+        let mut bytes = Vec::new();
+        if reader.read_until(b'\n', &mut bytes).is_err() {
+            bytes.clear();
+        }
+        let Some(line) = clean_input_line(&bytes) else {
+            continue;
+        };
+
+        let quit = line == "quit";
+        // if the Ladybug thread was terminated, terminate the input thread as well
+        if sender.send(Message::ConsoleMessage(line)).is_err() || quit {
+            return;
+        }
+    }
+}
+
+/// Cleans up a line of input read up to and including its line ending, for `read_input` and `async_io::read_input`.
+/// The line ending and byte order marks are removed, and invalid bytes are replaced. Returns None for a blank line, which is skipped.
+/// No bytes at all mean that the input was closed or could not be read, which is turned into "quit".
+pub(crate) fn clean_input_line(bytes: &[u8]) -> Option<String> {
+    if bytes.is_empty() {
+        return Some(String::from("quit"));
+    }
+    let line = String::from_utf8_lossy(bytes).trim_matches(['\u{feff}', '\r', '\n']).to_string();
+    match line.trim().is_empty() {
+        true => None,
+        false => Some(line),
+    }
+}
+
 /// The main character in this project!
 /// The Ladybug struct acts as the UCI client and can receive and handle UCI commands.
 pub struct Ladybug {
     /// The current game on which all searches and commands will be performed on.
     game: Game,
     /// The current state of Ladybug.
+    #[allow(dead_code)]
     state: State,
     /// Used to send commands to the search thread.
     search_command_sender: Sender<SearchCommand>,
     /// Used to send output to the console.
-    console_output_sender: Sender<String>,
+    console_output_sender: SyncSender<String>,
     /// Used to receive input from both the console and the search thread.
     input_receiver: Receiver<Message>,
-}
-
-/// Represents a game of chess, containing the current board as well as the board history.
-pub struct Game {
-    /// The current board.
-    board: Board,
-    /// Contains the hashes of all positions that have been on the board before.
-    board_history: ArrayVec<u64, 1000>,
+    /// Whether Ladybug should play moves from the opening book.
+    own_book: bool,
+    /// The opening book, if one has been loaded.
+    book: Option<Book>,
+    /// The internal opening book, which is used if no book has been loaded.
+    internal_book: Book,
+    /// The path of the opening book, if one has been loaded.
+    book_file: Option<String>,
+    /// The number of moves at the beginning of the game in which the best move is picked randomly among the best root moves,
+    /// unless the opening book is used.
+    opening_variety: u32,
+    /// The margin in centipawns within which the best move is picked randomly among the best root moves throughout the game.
+    variety: i32,
+    /// The skill level of the search.
+    skill_level: u32,
+    /// The contempt of the search in centipawns.
+    contempt: i32,
+    /// The size of the transposition table in megabytes.
+    hash_size: usize,
+    /// The number of threads of the search.
+    threads: usize,
+    /// The time in milliseconds that is reserved for each move, to make up for the latency of the GUI.
+    move_overhead: u64,
+    /// The percentage of the time spent pondering that counts towards the time of the move after a ponderhit.
+    ponder_credit: u32,
+    /// Whether the "go" command that is currently handled is a ponder search.
+    pondering: bool,
+    /// The path of the evaluation parameters, if they have been loaded from a file.
+    eval_params_file: Option<String>,
+    /// The move that is excluded from the searches of the current position, if any.
+    excluded_move: Option<Ply>,
+    /// The root moves the current search is restricted to with "searchmoves", or an empty list if all root moves are searched.
+    search_moves: Vec<Ply>,
+    /// The variant of chess that is played, which applies to the positions set up with the "position" command.
+    variant: &'static dyn Variant,
+    /// The state of the interactive analysis mode, if Ladybug is in the analysis mode.
+    analysis: Option<Analysis>,
+    /// The position for which a hint is being searched, if any.
+    hint_position: Option<Position>,
+    /// Writes the communication with the GUI to the log file, if one is set.
+    logger: Option<Logger>,
+    /// Whether the connection to the output thread or the search thread has been closed, which shuts Ladybug down.
+    disconnected: Cell<bool>,
+    /// Flag shared with the search, which stops the current search as soon as it is set.
+    stop_flag: Arc<AtomicBool>,
+    /// Flag shared with the search, which is set while the current search ponders, and cleared on the ponderhit.
+    ponder_flag: Arc<AtomicBool>,
 }
 
 /// The two types of messages Ladybug can receive.
@@ -37,51 +182,72 @@ pub enum Message {
 }
 
 /// The two possible states of Ladybug.
+#[allow(dead_code)]
 enum State {
     Idle,
     Busy,
 }
 
-impl Default for Game {
-    /// Default constructor for Game.
-    fn default() -> Self {
-        Game {
-            board: Board::default(),
-            board_history: ArrayVec::new(),
-        }
-    }
-}
-
 impl Ladybug {
     /// Constructs Ladybug.
-    pub fn new(search_command_sender: Sender<SearchCommand>, console_output_sender: Sender<String>, input_receiver: Receiver<Message>) -> Self {
+    /// The stop flag and the ponder flag have to be the ones of the search, see `Search::stop_flag` and `Search::ponder_flag`.
+    pub fn new(search_command_sender: Sender<SearchCommand>, console_output_sender: SyncSender<String>, input_receiver: Receiver<Message>, stop_flag: Arc<AtomicBool>, ponder_flag: Arc<AtomicBool>) -> Self {
         Self {
             game: Game::default(),
             state: State::Idle,
             search_command_sender,
             console_output_sender,
             input_receiver,
+            own_book: false,
+            book: None,
+            internal_book: internal::book(),
+            book_file: None,
+            opening_variety: 0,
+            variety: 0,
+            skill_level: MAX_SKILL_LEVEL,
+            contempt: 0,
+            hash_size: DEFAULT_HASH_SIZE,
+            threads: 1,
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            ponder_credit: DEFAULT_PONDER_CREDIT,
+            pondering: false,
+            eval_params_file: None,
+            excluded_move: None,
+            search_moves: Vec::new(),
+            variant: variant::STANDARD,
+            analysis: None,
+            hint_position: None,
+            logger: None,
+            disconnected: Cell::new(false),
+            stop_flag,
+            ponder_flag,
         }
     }
     
-    /// Starts running Ladybug.
+    /// Starts running Ladybug, until she receives "quit" or the connection to another thread is closed.
+    /// Afterwards, the search and output threads are told to terminate as well.
     pub fn run(&mut self) {
-        loop {
+        while !self.disconnected.get() {
             // blocks until Ladybug receives input
-            let input = self.input_receiver.recv();
-
             // if the input thread closes the connection, Ladybug must not continue running
-            if input.is_err() {
-                panic!("The input thread has unexpectedly closed the channel connection.")
-            }
+            let Ok(message) = self.input_receiver.recv() else {
+                break;
+            };
 
-            // get the message from the result
-            let message = input.unwrap();
+            if let (Message::ConsoleMessage(msg), Some(logger)) = (&message, &self.logger) {
+                logger.input(msg);
+            }
             
             match message {
                 // print search messages to the console
                 Message::SearchMessage(msg) => {
-                    self.send_console(msg);
+                    self.handle_search_message(msg);
+                }
+                // in the analysis mode, console messages are analysis commands
+                Message::ConsoleMessage(msg) if self.analysis.is_some() => {
+                    if !self.handle_analysis_command(msg) {
+                        break;
+                    }
                 }
                 // parse console messages as uci and delegate them to the respective handler methods
                 Message::ConsoleMessage(msg) => {
@@ -89,9 +255,12 @@ impl Ladybug {
                     let uci_command = uci::parse_uci(msg);
 
                     let uci_command = match uci_command {
-                        // if the uci command cannot be parsed, send the error message to the output thread
-                        Err(message) => {
-                            self.send_console(message);
+                        // if the uci command cannot be parsed, tell the GUI that the command is unknown
+                        Err(error) => {
+                            if let Some(logger) = &self.logger {
+                                logger.warning(error.to_string().as_str());
+                            }
+                            self.send_console(String::from("info string unknown command"));
                             continue;
                         }
                         Ok(command) => command
@@ -107,35 +276,64 @@ impl Ladybug {
                         UciCommand::GoMoveTime(time) => self.handle_go_move_time(time),
                         UciCommand::GoDepth(depth) => self.handle_depth(depth),
                         UciCommand::GoPerft(depth) => self.handle_go_perft(depth),
-                        UciCommand::Quit => {
-                            self.handle_quit();
-                            break;
-                        }
+                        UciCommand::GoMate(moves) => self.handle_go_mate(moves),
+                        UciCommand::GoNodes(nodes) => self.handle_go_nodes(nodes),
+                        UciCommand::GoSearchMoves(moves, go_command) => self.handle_go_search_moves(moves, *go_command),
+                        UciCommand::GoPonder(go_command) => self.handle_go_ponder(*go_command),
+                        UciCommand::PonderHit => self.handle_ponderhit(),
+                        UciCommand::Stop => self.handle_stop(),
+                        UciCommand::SetOption(name, value) => self.handle_set_option(name, value),
+                        UciCommand::Test(path, time) => self.handle_test(path, time),
+                        UciCommand::PerftSuite(path) => self.handle_perft_suite(path),
+                        UciCommand::PerftDiff(engine_path, depth) => self.handle_perft_diff(engine_path, depth),
+                        UciCommand::SelfPlay(args) => self.handle_selfplay(args),
+                        UciCommand::Match(args) => self.handle_match(args),
+                        UciCommand::Bench(depth) => self.handle_bench(depth),
+                        UciCommand::BenchCompare(path) => self.handle_bench_compare(path),
+                        UciCommand::Speedtest(depth) => self.handle_speedtest(depth),
+                        UciCommand::Debug(debug) => self.handle_debug(debug),
+                        UciCommand::Exclude(ply) => self.handle_exclude(ply),
+                        UciCommand::ResetOptions => self.handle_reset_options(),
+                        UciCommand::SaveConfig(path) => self.handle_save_config(path),
+                        UciCommand::Analyze => self.handle_analyze(),
+                        UciCommand::Quit => break,
                         UciCommand::Help => self.handle_help(),
-                        UciCommand::Display => self.handle_display()
+                        UciCommand::Display(clock) => self.handle_display(clock),
+                        UciCommand::Board(unicode, flip) => self.handle_board(unicode, flip),
+                        UciCommand::HashStats => self.send_search(SearchCommand::HashStats),
                     }
                 }
             }
         }
+
+        self.handle_quit();
     }
 
     /// Sends the given string to the output thread.
     fn send_console(&self, output: String) {
+        if let Some(logger) = &self.logger {
+            logger.output(output.as_str());
+        }
         let send_result = self.console_output_sender.send(output);
 
         // if the output thread closes the connection, Ladybug must not continue running
         if send_result.is_err() {
-            panic!("The output thread has unexpectedly closed the channel connection.")
+            self.disconnected.set(true);
         }
     }
 
     /// Sends the given search command to the search thread.
     fn send_search(&self, search_command: SearchCommand) {
+        // a new task must not be stopped by a "stop" that was meant for the previous one, and only ponder searches ponder
+        if search_command.starts_task() {
+            self.stop_flag.store(false, Ordering::Relaxed);
+            self.ponder_flag.store(matches!(search_command, SearchCommand::Ponder(..)), Ordering::Relaxed);
+        }
         let send_result = self.search_command_sender.send(search_command);
 
         // if the search thread closes the connection, Ladybug must not continue running
         if send_result.is_err() {
-            panic!("The search thread has unexpectedly closed the channel connection.")
+            self.disconnected.set(true);
         }
     }
 
@@ -143,24 +341,59 @@ impl Ladybug {
     fn handle_uci(&self) {
         self.send_console("id name Ladybug 0.5.0".to_string());
         self.send_console("id author Felix O.".to_string());
+        self.send_console(String::from("option name OwnBook type check default false"));
+        self.send_console(String::from("option name BookFile type string default <empty>"));
+        self.send_console(format!("option name OpeningVariety type spin default 0 min 0 max {MAX_OPENING_VARIETY}"));
+        self.send_console(format!("option name Variety type spin default 0 min 0 max {MAX_VARIETY}"));
+        self.send_console(format!("option name Skill Level type spin default {MAX_SKILL_LEVEL} min 0 max {MAX_SKILL_LEVEL}"));
+        self.send_console(format!("option name Contempt type spin default 0 min -{MAX_CONTEMPT} max {MAX_CONTEMPT}"));
+        self.send_console(format!("option name Hash type spin default {DEFAULT_HASH_SIZE} min {MIN_HASH_SIZE} max {MAX_HASH_SIZE}"));
+        self.send_console(String::from("option name Clear Hash type button"));
+        self.send_console(format!("option name Threads type spin default 1 min 1 max {MAX_THREADS}"));
+        self.send_console(format!("option name Move Overhead type spin default {DEFAULT_MOVE_OVERHEAD} min 0 max {MAX_MOVE_OVERHEAD}"));
+        self.send_console(String::from("option name Ponder type check default false"));
+        self.send_console(format!("option name Ponder Credit type spin default {DEFAULT_PONDER_CREDIT} min 0 max {MAX_PONDER_CREDIT}"));
+        self.send_console(String::from("option name EvalParamsFile type string default <empty>"));
+        self.send_console(String::from("option name LogFile type string default <empty>"));
+        let mut variant_option = format!("option name UCI_Variant type combo default {}", variant::STANDARD.name());
+        for variant in variant::VARIANTS {
+            variant_option += format!(" var {}", variant.name()).as_str();
+        }
+        self.send_console(variant_option);
+        self.send_console(String::from("option name UCI_ShowWDL type check default false"));
         self.send_console(String::from("uciok"));
     }
 
+    /// Sends a message of the search to the console.
+    /// The best move of a hint is shown in SAN, since the analysis mode is meant to be used by humans.
+    fn handle_search_message(&mut self, msg: String) {
+        if let (Some(position), Some(best_move)) = (self.hint_position, msg.strip_prefix("bestmove ")) {
+            self.hint_position = None;
+            // the best move may be followed by the expected reply
+            let best_move = best_move.split_whitespace().next().unwrap_or_default();
+            if let Ok(ply) = Ply::from_string(best_move, position) {
+                self.send_console(format!("Hint: {}", ply.to_san(position)));
+                return;
+            }
+        }
+        self.send_console(msg);
+    }
+
     /// Handles the "isready" command.
     fn handle_is_ready(&self) {
         self.send_console(String::from("readyok"));
     }
 
     /// Handles the "ucinewgame" command.
+    /// Only the state of the current game and the results of the previous searches are reset, while the options keep their values (see `handle_reset_options`).
     fn hande_uci_new_game(&mut self) {
         self.game = Game::default();
+        self.clear_excluded_move();
+        self.send_search(SearchCommand::NewGame);
     }
     
     /// Handles the "position" command.
     fn handle_position(&mut self, args: Vec<String>) {
-        // reset the game
-        self.game = Game::default();
-        
         if args.is_empty() {
             self.send_console(String::from("info string unknown command"));
             return;
@@ -171,7 +404,7 @@ impl Ladybug {
         // build the fen string from the provided args
         match args[0].as_str() {
             "startpos" => {
-                fen += "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+                fen += START_FEN;
             }
             "fen" => {
                 for (index, arg) in args.iter().enumerate() {
@@ -191,45 +424,355 @@ impl Ladybug {
             }
         };
 
-        // try to parse the fen
-        let board = Board::from_fen(fen.as_str());
-        if board.is_err() {
+        // try to parse the fen, the current game is only replaced once the whole command is valid
+        let Ok(mut board) = Board::from_fen(fen.as_str()) else {
             self.send_console(String::from("info string invalid fen"));
             return;
+        };
+        board.position.set_variant(self.variant);
+
+        // the moves follow the "moves" keyword
+        let moves = match args.iter().position(|r| r == "moves") {
+            Some(moves_index) => &args[moves_index + 1..],
+            None => &[],
+        };
+
+        // GUIs send the whole game after every move, so if the moves continue the current game, only the new moves are played
+        let played_moves = self.game.moves();
+        let continues_game = self.game.initial_board() == board && played_moves.len() <= moves.len()
+            && played_moves.iter().zip(moves).all(|(ply, move_string)| ply.to_string() == move_string.to_ascii_lowercase());
+        let (mut game, first_new_move) = match continues_game {
+            true => (self.game.clone(), played_moves.len()),
+            false => (Game::from_board(board), 0),
+        };
+
+        // validate each move against the legal moves of the position it is played in
+        for (index, move_string) in moves.iter().enumerate().skip(first_new_move) {
+            if let Err(message) = game.parse_move(move_string).map_err(|error| error.to_string()).and_then(|ply| game.make_move(ply)) {
+                self.send_console(format!("info string invalid moves: {message} (move {})", index + 1));
+                return;
+            }
+        }
+
+        self.game = game;
+        self.clear_excluded_move();
+    }
+
+    /// Handles the "setoption name <name> value <value>" command.
+    fn handle_set_option(&mut self, name: String, value: String) {
+        match name.to_ascii_lowercase().as_str() {
+            "ownbook" => match value.as_str() {
+                "true" => self.own_book = true,
+                "false" => self.own_book = false,
+                _other => self.send_console(String::from("info string invalid option value")),
+            }
+            "bookfile" => {
+                if value.is_empty() || value == "<empty>" {
+                    self.book = None;
+                    self.book_file = None;
+                } else {
+                    match Book::from_file(value.as_str()) {
+                        Ok(book) => {
+                            self.send_console(format!("info string loaded book with {} entries", book.len()));
+                            self.book = Some(book);
+                            self.book_file = Some(value);
+                        }
+                        Err(_) => {
+                            self.book = None;
+                            self.book_file = None;
+                            self.send_console(String::from("info string could not load book"));
+                        }
+                    }
+                }
+            }
+            "openingvariety" => match value.parse::<u32>() {
+                Ok(moves) if moves <= MAX_OPENING_VARIETY => self.opening_variety = moves,
+                _other => self.send_console(String::from("info string invalid option value")),
+            }
+            "variety" => {
+                match value.parse::<i32>() {
+                    Ok(margin) if (0..=MAX_VARIETY).contains(&margin) => {
+                        self.variety = margin;
+                        self.send_search(SearchCommand::Variety(margin));
+                    }
+                    _other => self.send_console(String::from("info string invalid option value")),
+                }
+                return;
+            }
+            "skill level" => {
+                match value.parse::<u32>() {
+                    Ok(skill_level) if skill_level <= MAX_SKILL_LEVEL => {
+                        self.skill_level = skill_level;
+                        self.send_search(SearchCommand::SkillLevel(skill_level));
+                    }
+                    _other => self.send_console(String::from("info string invalid option value")),
+                }
+                return;
+            }
+            "contempt" => {
+                match value.parse::<i32>() {
+                    Ok(contempt) if contempt.abs() <= MAX_CONTEMPT => {
+                        self.contempt = contempt;
+                        self.send_search(SearchCommand::Contempt(contempt));
+                    }
+                    _other => self.send_console(String::from("info string invalid option value")),
+                }
+                return;
+            }
+            "hash" => {
+                self.handle_hash(value);
+                return;
+            }
+            "clear hash" => {
+                self.send_search(SearchCommand::ClearHash);
+                return;
+            }
+            "threads" => {
+                match value.parse::<usize>() {
+                    Ok(threads) if (1..=MAX_THREADS).contains(&threads) => {
+                        self.threads = threads;
+                        self.send_search(SearchCommand::Threads(threads));
+                    }
+                    _other => self.send_console(String::from("info string invalid option value")),
+                }
+                return;
+            }
+            "move overhead" => match value.parse::<u64>() {
+                Ok(move_overhead) if move_overhead <= MAX_MOVE_OVERHEAD => self.move_overhead = move_overhead,
+                _other => self.send_console(String::from("info string invalid option value")),
+            }
+            "ponder" => {
+                match value.as_str() {
+                    "true" => self.send_search(SearchCommand::ShowPonderMove(true)),
+                    "false" => self.send_search(SearchCommand::ShowPonderMove(false)),
+                    _other => self.send_console(String::from("info string invalid option value")),
+                }
+                return;
+            }
+            "ponder credit" => {
+                match value.parse::<u32>() {
+                    Ok(credit) if credit <= MAX_PONDER_CREDIT => {
+                        self.ponder_credit = credit;
+                        self.send_search(SearchCommand::PonderCredit(credit));
+                    }
+                    _other => self.send_console(String::from("info string invalid option value")),
+                }
+                return;
+            }
+            "evalparamsfile" => {
+                self.handle_eval_params_file(value);
+                return;
+            }
+            "logfile" => {
+                self.handle_log_file(value);
+                return;
+            }
+            "uci_variant" => match variant::from_name(value.as_str()) {
+                Some(variant) => self.variant = variant,
+                None => self.send_console(String::from("info string unknown variant")),
+            }
+            "uci_showwdl" => {
+                match value.as_str() {
+                    "true" => self.send_search(SearchCommand::ShowWdl(true)),
+                    "false" => self.send_search(SearchCommand::ShowWdl(false)),
+                    _other => self.send_console(String::from("info string invalid option value")),
+                }
+                return;
+            }
+            "reversefutilitymargin" => {
+                // the reverse futility margins are hidden options as well, so that they can be tuned in matches
+                match value.parse::<i32>() {
+                    Ok(margin) if (0..=MAX_REVERSE_FUTILITY_MARGIN).contains(&margin) => self.send_search(SearchCommand::ReverseFutilityMargin(margin)),
+                    _other => self.send_console(String::from("info string invalid option value")),
+                }
+                return;
+            }
+            "reversefutilitydepth" => {
+                match value.parse::<u64>() {
+                    Ok(depth) if depth <= MAX_REVERSE_FUTILITY_DEPTH => self.send_search(SearchCommand::ReverseFutilityDepth(depth)),
+                    _other => self.send_console(String::from("info string invalid option value")),
+                }
+                return;
+            }
+            other => {
+                // the search features are hidden options, which are not listed by the "uci" command
+                match SearchFeature::from_option_name(other) {
+                    Some(feature) => self.handle_search_feature(feature, value),
+                    None => self.send_console(String::from("info string unknown option")),
+                }
+                return;
+            }
         }
-        let mut board = board.unwrap();
 
-        // split the args vector to only contain the moves
-        let moves_index = args.iter().position(|r| r == "moves");
-        if moves_index.is_none() {
-            // command contains no moves - finish
-            self.game.board = board;
+        // the book options decide whether the opening variety is used
+        self.send_opening_variety();
+    }
+
+    /// Handles the "setoption name Hash value <value>" command.
+    /// Values outside the advertised bounds are clamped, since GUIs may not respect them.
+    fn handle_hash(&mut self, value: String) {
+        let Ok(size_mb) = value.parse::<u64>() else {
+            self.send_console(String::from("info string invalid option value"));
             return;
+        };
+        let clamped_size_mb = size_mb.clamp(MIN_HASH_SIZE as u64, MAX_HASH_SIZE as u64) as usize;
+        if clamped_size_mb as u64 != size_mb {
+            self.send_console(format!("info string hash size clamped to {clamped_size_mb} MB"));
+        }
+        self.set_hash_size(clamped_size_mb);
+    }
+
+    /// Handles the hidden "setoption name <feature> value <true | false>" options, which enable or disable a search feature.
+    /// They allow measuring the effect of a search feature in matches without recompiling Ladybug.
+    fn handle_search_feature(&self, feature: SearchFeature, value: String) {
+        match value.as_str() {
+            "true" => self.send_search(SearchCommand::Feature(feature, true)),
+            "false" => self.send_search(SearchCommand::Feature(feature, false)),
+            _other => self.send_console(String::from("info string invalid option value")),
         }
-        let moves_index = moves_index.unwrap() + 1;
-        if moves_index > args.len() {
+    }
+
+    /// Handles the "setoption name EvalParamsFile value <path>" command.
+    /// An empty path restores the built-in evaluation parameters.
+    /// If the file can't be loaded, the current evaluation parameters are kept.
+    fn handle_eval_params_file(&mut self, path: String) {
+        if path.is_empty() || path == "<empty>" {
+            self.eval_params_file = None;
+            self.send_search(SearchCommand::EvalParams(Box::default()));
             return;
         }
-        let (_, moves) = args.split_at(moves_index);
+        match EvalParams::from_file(path.as_str()) {
+            Ok(eval_params) => {
+                self.eval_params_file = Some(path);
+                self.send_search(SearchCommand::EvalParams(Box::new(eval_params)));
+                self.send_console(String::from("info string loaded evaluation parameters"));
+            }
+            Err(message) => self.send_console(format!("info string could not load evaluation parameters: {message}")),
+        }
+    }
 
-        // loop over moves strings and try to make the moves on the board
-        for move_string in moves {
-            let ply = Ply::from_string(move_string, board.position);
-            match ply {
-                Some(ply) => {
-                    self.game.board_history.push(board.position.hash);
-                    board = board.make_move(ply)
-                }
-                None => {
-                    // reset the game
-                    self.game = Game::default();
-                    self.send_console(String::from("info string invalid moves"));
-                    return;
+    /// Handles the "setoption name LogFile value <path>" command.
+    /// An empty path stops logging. If the file can't be opened, the current log file is kept.
+    fn handle_log_file(&mut self, path: String) {
+        if path.is_empty() || path == "<empty>" {
+            self.logger = None;
+            return;
+        }
+        match Logger::open(path.as_str()) {
+            Ok(logger) => self.logger = Some(logger),
+            Err(message) => self.send_console(format!("info string {message}")),
+        }
+    }
+
+    /// Resizes the transposition table of the search, unless it already has the given size.
+    fn set_hash_size(&mut self, size_mb: usize) {
+        if self.hash_size != size_mb {
+            self.hash_size = size_mb;
+            self.send_search(SearchCommand::HashSize(size_mb));
+        }
+    }
+
+    /// Handles the "resetoptions" command, which restores the default values of all options.
+    fn handle_reset_options(&mut self) {
+        self.own_book = false;
+        self.book = None;
+        self.book_file = None;
+        self.opening_variety = 0;
+        self.variety = 0;
+        self.skill_level = MAX_SKILL_LEVEL;
+        self.contempt = 0;
+        self.move_overhead = DEFAULT_MOVE_OVERHEAD;
+        self.ponder_credit = DEFAULT_PONDER_CREDIT;
+        self.variant = variant::STANDARD;
+        self.eval_params_file = None;
+        self.logger = None;
+        self.send_opening_variety();
+        self.set_hash_size(DEFAULT_HASH_SIZE);
+        self.threads = 1;
+        self.send_search(SearchCommand::Threads(1));
+        self.send_search(SearchCommand::Variety(0));
+        self.send_search(SearchCommand::SkillLevel(MAX_SKILL_LEVEL));
+        self.send_search(SearchCommand::Contempt(0));
+        self.send_search(SearchCommand::ShowWdl(false));
+        self.send_search(SearchCommand::ShowPonderMove(false));
+        self.send_search(SearchCommand::PonderCredit(DEFAULT_PONDER_CREDIT));
+        self.send_search(SearchCommand::EvalParams(Box::default()));
+        for feature in SearchFeature::ALL {
+            self.send_search(SearchCommand::Feature(feature, true));
+        }
+        self.send_search(SearchCommand::ReverseFutilityMargin(DEFAULT_REVERSE_FUTILITY_MARGIN));
+        self.send_search(SearchCommand::ReverseFutilityDepth(DEFAULT_REVERSE_FUTILITY_DEPTH));
+    }
+
+    /// Loads the option values from the given configuration file, as if they were set with "setoption".
+    pub fn load_config(&mut self, path: &str) {
+        match config::read_file(path) {
+            Err(message) => self.send_console(format!("info string {message}")),
+            Ok(options) => {
+                for (name, value) in options {
+                    self.handle_set_option(name, value);
                 }
+                self.send_console(format!("info string loaded config from {path}"));
             }
         }
+    }
+
+    /// Handles the "saveconfig [<file>]" command, which writes the current option values to the given configuration file,
+    /// or to the one loaded at startup if no file is given.
+    fn handle_save_config(&self, path: Option<String>) {
+        let path = path.unwrap_or(String::from(CONFIG_FILE));
+        // the variant, Ponder and UCI_ShowWDL are not saved, since the GUI sets them
+        let options = [
+            ("OwnBook", self.own_book.to_string()),
+            ("BookFile", self.book_file.clone().unwrap_or_default()),
+            ("OpeningVariety", self.opening_variety.to_string()),
+            ("Variety", self.variety.to_string()),
+            ("Skill Level", self.skill_level.to_string()),
+            ("Contempt", self.contempt.to_string()),
+            ("Hash", self.hash_size.to_string()),
+            ("Threads", self.threads.to_string()),
+            ("Move Overhead", self.move_overhead.to_string()),
+            ("Ponder Credit", self.ponder_credit.to_string()),
+            ("EvalParamsFile", self.eval_params_file.clone().unwrap_or_default()),
+            ("LogFile", self.logger.as_ref().map(|logger| logger.path.clone()).unwrap_or_default()),
+        ];
+        match fs::write(path.as_str(), config::to_toml(&options)) {
+            Ok(_) => self.send_console(format!("info string saved config to {path}")),
+            Err(_) => self.send_console(String::from("info string Could not write config file")),
+        }
+    }
 
-        self.game.board = board;
+    /// Sends the number of moves in which the search varies its best move to the search.
+    /// The opening variety is only used if Ladybug doesn't play moves from an opening book.
+    fn send_opening_variety(&self) {
+        let moves = match self.own_book {
+            true => 0,
+            false => self.opening_variety,
+        };
+        self.send_search(SearchCommand::OpeningVariety(moves));
+    }
+
+    /// Tries to find a move for the current position in the opening book, or in the internal book if no book has been loaded.
+    /// The moves of the internal book are picked randomly, so that the games don't always start with the same moves.
+    /// If Ladybug is allowed to use the book and a book move was found, the move is sent as best move and true is returned.
+    /// A ponder search never plays a book move, since its best move must not be sent before the ponderhit.
+    fn play_book_move(&self) -> bool {
+        if !self.own_book || self.pondering {
+            return false;
+        }
+        let position = self.game.board().position;
+        let book_move = match &self.book {
+            Some(book) => book.get_move(position),
+            None => self.internal_book.get_weighted_move(position, xorshift(get_random_seed())),
+        }
+            .filter(|ply| self.search_moves.is_empty() || self.search_moves.contains(ply));
+        match book_move {
+            None => false,
+            Some(ply) => {
+                self.send_console(format!("bestmove {ply}"));
+                true
+            }
+        }
     }
 
     /// Handles the "go wtime <time> btime <time>" command.
@@ -261,20 +804,39 @@ impl Ladybug {
             return;
         }
         
-        let time =  match self.game.board.position.color_to_move{
+        let time =  match self.game.board().position.color_to_move{
             Color::White => w_time.unwrap(),
             Color::Black => b_time.unwrap(),
         };
 
 
-        let increment =  match self.game.board.position.color_to_move{
+        let increment =  match self.game.board().position.color_to_move{
             Color::White => w_inc.unwrap(),
             Color::Black => b_inc.unwrap(),
         };
         
-        let time = (time / 40) + increment;
-        
-        self.send_search(SearchCommand::SearchTime(self.game.board, self.game.board_history.clone(), time));
+        let optimum_time = allocate_time(time, increment, self.move_overhead);
+        let max_time = time_manager::max_time(optimum_time, time.saturating_sub(self.move_overhead), MAX_TIME_FRACTION);
+
+        if self.play_book_move() {
+            return;
+        }
+
+        // the ponder search uses the optimum time after the ponderhit, since the time spent pondering is credited to it
+        match self.pondering {
+            true => self.send_timed_search(optimum_time),
+            false => self.send_search(SearchCommand::SearchClock(self.game.board(), self.game.board_history().clone(), optimum_time, max_time)),
+        }
+    }
+
+    /// Sends a search of the current position for the given amount of milliseconds to the search thread.
+    /// If the current "go" command is a ponder search, the time only starts running with the ponderhit.
+    fn send_timed_search(&self, time: u64) {
+        let (board, board_history) = (self.game.board(), self.game.board_history().clone());
+        match self.pondering {
+            true => self.send_search(SearchCommand::Ponder(board, board_history, time)),
+            false => self.send_search(SearchCommand::SearchTime(board, board_history, time)),
+        }
     }
 
     /// Handles the "go movetime <time>" command.
@@ -285,7 +847,12 @@ impl Ladybug {
                 self.send_console(String::from("info string unknown command"));
             }
             Ok(time) => {
-                self.send_search(SearchCommand::SearchTime(self.game.board, self.game.board_history.clone(), time));
+                if self.play_book_move() {
+                    return;
+                }
+                // the move overhead is reserved, so that the move arrives at the GUI in time
+                let time = time.saturating_sub(self.move_overhead).max(1);
+                self.send_timed_search(time);
             }
         }
     }
@@ -298,26 +865,278 @@ impl Ladybug {
                 self.send_console(String::from("info string unknown command"));
             }
             Ok(depth) => {
-                self.send_search(SearchCommand::SearchDepth(self.game.board, self.game.board_history.clone(), depth));
+                if self.play_book_move() {
+                    return;
+                }
+                self.send_search(SearchCommand::SearchDepth(self.game.board(), self.game.board_history().clone(), depth));
             }
         }
     }
 
     /// Handles the "go perft <depth>" command.
     fn handle_go_perft(&self, depth_str: String) {
-        let depth = depth_str.parse::<u64>();
-        match depth {
+        match depth_str.parse::<u64>() {
+            Ok(depth) if depth <= MAX_PERFT_DEPTH => self.send_search(SearchCommand::Perft(self.game.board().position, depth)),
+            _other => self.send_console(String::from("info string unknown command")),
+        }
+    }
+
+    /// Handles the "go mate <moves>" command.
+    fn handle_go_mate(&self, moves_str: String) {
+        match moves_str.parse::<u64>() {
+            Ok(moves) if moves > 0 => self.send_search(SearchCommand::Mate(self.game.board().position, moves)),
+            _other => self.send_console(String::from("info string unknown command")),
+        }
+    }
+
+    /// Handles the "go nodes <nodes>" command.
+    fn handle_go_nodes(&self, nodes_str: String) {
+        match nodes_str.parse::<u64>() {
+            Ok(nodes) if nodes > 0 => {
+                if self.play_book_move() {
+                    return;
+                }
+                self.send_search(SearchCommand::SearchNodes(self.game.board(), self.game.board_history().clone(), nodes));
+            }
+            _other => self.send_console(String::from("info string unknown command")),
+        }
+    }
+
+    /// Handles the "go ... searchmoves <move> ..." command, which restricts the search to the given root moves.
+    /// The restriction only applies to this search, and a book move is only played if it is one of the given moves.
+    fn handle_go_search_moves(&mut self, moves: Vec<String>, go_command: UciCommand) {
+        let position = self.game.board().position;
+        let Ok(search_moves) = moves.iter().map(|ply_str| Ply::from_string(ply_str.as_str(), position)).collect::<Result<Vec<Ply>, _>>() else {
+            self.send_console(String::from("info string invalid move"));
+            return;
+        };
+
+        self.search_moves = search_moves.clone();
+        self.send_search(SearchCommand::SearchMoves(search_moves));
+        match go_command {
+            UciCommand::GoClockTime(args) => self.handle_go_clock_time(args),
+            UciCommand::GoMoveTime(time) => self.handle_go_move_time(time),
+            UciCommand::GoDepth(depth) => self.handle_depth(depth),
+            UciCommand::GoNodes(nodes) => self.handle_go_nodes(nodes),
+            _other => self.send_console(String::from("info string unknown command")),
+        }
+        self.search_moves.clear();
+        self.send_search(SearchCommand::SearchMoves(Vec::new()));
+    }
+
+    /// Handles the "go ponder ..." command, which ponders on the current position until the ponderhit,
+    /// and searches it with the given search limit afterwards.
+    fn handle_go_ponder(&mut self, go_command: UciCommand) {
+        self.pondering = true;
+        match go_command {
+            UciCommand::GoClockTime(args) => self.handle_go_clock_time(args),
+            UciCommand::GoMoveTime(time) => self.handle_go_move_time(time),
+            UciCommand::GoSearchMoves(moves, go_command) => self.handle_go_search_moves(moves, *go_command),
+            _other => self.send_console(String::from("info string unknown command")),
+        }
+        self.pondering = false;
+    }
+
+    /// Handles the "ponderhit" command, which tells the ponder search that the opponent played the expected move.
+    /// Like the stop flag, the ponder flag is cleared right away instead of waiting in the command queue of the search thread.
+    fn handle_ponderhit(&self) {
+        self.ponder_flag.store(false, Ordering::Relaxed);
+    }
+
+    /// Handles the "stop" command.
+    /// The search thread prints the best move found so far, while the command is ignored if no search is running.
+    /// Instead of waiting in the command queue of the search thread, the stop flag is set, which the search checks at every node.
+    fn handle_stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Handles the "test <file> <movetime>" command.
+    fn handle_test(&self, path: String, time_str: String) {
+        let time = match time_str.parse::<u64>() {
             Err(_) => {
                 self.send_console(String::from("info string unknown command"));
+                return;
             }
-            Ok(depth) => {
-                self.send_search(SearchCommand::Perft(self.game.board.position, depth));
+            Ok(time) => time,
+        };
+
+        match epd::read_file(path.as_str()) {
+            Err(message) => self.send_console(format!("info string {message}")),
+            Ok(entries) => self.send_search(SearchCommand::Test(entries, time)),
+        }
+    }
+
+    /// Handles the "perftsuite <file>" command.
+    fn handle_perft_suite(&self, path: String) {
+        match perft_suite::read_file(path.as_str()) {
+            Err(message) => self.send_console(format!("info string {message}")),
+            Ok(entries) => self.send_search(SearchCommand::PerftSuite(entries)),
+        }
+    }
+
+    /// Handles the "perftdiff <engine> <depth>" command.
+    fn handle_perft_diff(&self, engine_path: String, depth_str: String) {
+        match depth_str.parse::<u64>() {
+            Ok(depth) if depth > 0 => self.send_search(SearchCommand::PerftDiff(self.game.board(), depth, engine_path)),
+            _other => self.send_console(String::from("info string unknown command")),
+        }
+    }
+
+    /// Handles the "selfplay <n> <movetime> [<options>]" command.
+    fn handle_selfplay(&self, args: Vec<String>) {
+        let games = args[0].parse::<u64>().ok().filter(|games| *games > 0);
+        let move_time = args[1].parse::<u64>().ok().filter(|move_time| *move_time > 0);
+        let (Some(games), Some(move_time)) = (games, move_time) else {
+            self.send_console(String::from("info string unknown command"));
+            return;
+        };
+
+        if let Some((openings, pgn_path, adjudication)) = self.parse_game_options(&args[2..]) {
+            self.send_search(SearchCommand::SelfPlay(SelfPlaySettings { games, move_time, openings, pgn_path, adjudication }));
+        }
+    }
+
+    /// Handles the "match <engine> <n> <time> <increment> [<options>]" command.
+    fn handle_match(&self, args: Vec<String>) {
+        let games = args[1].parse::<u64>().ok().filter(|games| *games > 0);
+        let time = args[2].parse::<u64>().ok().filter(|time| *time > 0);
+        let increment = args[3].parse::<u64>().ok();
+        let (Some(games), Some(time), Some(increment)) = (games, time, increment) else {
+            self.send_console(String::from("info string unknown command"));
+            return;
+        };
+
+        if let Some((openings, pgn_path, adjudication)) = self.parse_game_options(&args[4..]) {
+            self.send_search(SearchCommand::Match(MatchSettings { engine_path: args[0].clone(), games, time, increment, openings, pgn_path, adjudication }));
+        }
+    }
+
+    /// Parses the options of the "selfplay" and "match" commands, which are the openings file, the PGN file and the adjudication rules:
+    /// "openings <file>", "pgn <file>", "resign <movecount> <score>" and "draw <movenumber> <movecount> <score>".
+    /// If the options are invalid, or the openings can't be read, this is reported and None is returned.
+    fn parse_game_options(&self, args: &[String]) -> Option<(Vec<Board>, Option<String>, Adjudication)> {
+        let mut openings = Vec::new();
+        let mut pgn_path = None;
+        let mut adjudication = Adjudication::default();
+        let mut args = args.iter().map(|arg| arg.as_str());
+        while let Some(option) = args.next() {
+            let valid = match option {
+                "openings" => match args.next().map(epd::read_positions) {
+                    Some(Err(message)) => {
+                        self.send_console(format!("info string {message}"));
+                        return None;
+                    }
+                    Some(Ok(positions)) => {
+                        openings = positions;
+                        true
+                    }
+                    None => false,
+                },
+                "pgn" => args.next().map(|path| pgn_path = Some(path.to_string())).is_some(),
+                "resign" => match (next_count(&mut args), next_score(&mut args)) {
+                    (Some(move_count), Some(score)) => {
+                        adjudication.resign = Some(ResignRule { move_count, score });
+                        true
+                    }
+                    _other => false,
+                },
+                "draw" => match (next_count(&mut args), next_count(&mut args), next_score(&mut args)) {
+                    (Some(move_number), Some(move_count), Some(score)) => {
+                        adjudication.draw = Some(DrawRule { move_number, move_count, score });
+                        true
+                    }
+                    _other => false,
+                },
+                _other => false,
+            };
+            if !valid {
+                self.send_console(String::from("info string unknown command"));
+                return None;
+            }
+        }
+        Some((openings, pgn_path, adjudication))
+    }
+
+    /// Handles the "bench [<depth>]" command.
+    fn handle_bench(&self, depth_str: Option<String>) {
+        let depth = match depth_str {
+            None => BENCH_DEPTH,
+            Some(depth_str) => match depth_str.parse::<u64>() {
+                Ok(depth) if depth > 0 => depth,
+                _other => {
+                    self.send_console(String::from("info string unknown command"));
+                    return;
+                }
+            },
+        };
+        self.send_search(SearchCommand::Bench(depth));
+    }
+
+    /// Handles the "speedtest [<depth>]" command.
+    fn handle_speedtest(&self, depth_str: Option<String>) {
+        let depth = match depth_str {
+            None => SPEEDTEST_DEPTH,
+            Some(depth_str) => match depth_str.parse::<u64>() {
+                Ok(depth) if depth > 0 => depth,
+                _other => {
+                    self.send_console(String::from("info string unknown command"));
+                    return;
+                }
+            },
+        };
+        self.send_search(SearchCommand::Speedtest(depth));
+    }
+
+    /// Handles the "bench --compare <file>" command.
+    /// The bench is compared to the last baseline of the file, which belongs to the most recent version.
+    fn handle_bench_compare(&self, path: String) {
+        match bench::read_baselines(path.as_str()) {
+            Err(message) => self.send_console(format!("info string {message}")),
+            Ok(mut baselines) => match baselines.pop() {
+                None => self.send_console(String::from("info string no bench baseline found")),
+                Some(baseline) => self.send_search(SearchCommand::BenchCompare(baseline)),
+            },
+        }
+    }
+
+    /// Handles the "debug [on | off]" command.
+    fn handle_debug(&self, debug: bool) {
+        self.send_search(SearchCommand::Debug(debug));
+    }
+
+    /// Handles the "exclude [<move>]" command.
+    /// The given move is excluded from the searches of the current position, until a new position is set up.
+    /// If no move is given, the previously excluded move is searched again.
+    fn handle_exclude(&mut self, ply_str: Option<String>) {
+        let Some(ply_str) = ply_str else {
+            self.clear_excluded_move();
+            return;
+        };
+
+        let position = self.game.board().position;
+        match Ply::from_string(ply_str.as_str(), position) {
+            Err(_error) => self.send_console(String::from("info string invalid move")),
+            Ok(_ply) if self.game.legal_moves().len() == 1 => self.send_console(String::from("info string no other legal move")),
+            Ok(ply) => {
+                self.excluded_move = Some(ply);
+                self.send_search(SearchCommand::ExcludeMove(Some(ply)));
             }
         }
     }
 
-    /// Handles the "quit" command.
+    /// Stops excluding a move from the searches, if one is excluded.
+    fn clear_excluded_move(&mut self) {
+        if self.excluded_move.take().is_some() {
+            self.send_search(SearchCommand::ExcludeMove(None));
+        }
+    }
+
+    /// Handles the "quit" command, which is also used to shut Ladybug down if a connection is closed.
+    /// The search is stopped and the search thread terminates, and the output thread terminates once all output is written.
     fn handle_quit(&self) {
+        // a running search is stopped, so that the search thread can receive the command
+        self.stop_flag.store(true, Ordering::Relaxed);
+        self.send_search(SearchCommand::Quit);
         self.send_console(String::from("quit"));
     }
 
@@ -333,49 +1152,199 @@ impl Ladybug {
         self.send_console(String::from("go movetime <time>                                      : Search for the specified time"));
         self.send_console(String::from("go depth <depth>                                        : Search to the specified depth"));
         self.send_console(String::from("go perft <depth>                                        : Perform a perft test"));
-        self.send_console(String::from("display                                                 : Print the fen of the current position"));
+        self.send_console(String::from("go mate <moves>                                         : Prove a mate in the specified number of moves"));
+        self.send_console(String::from("go nodes <nodes>                                        : Search the specified number of nodes"));
+        self.send_console(String::from("go ... searchmoves <moves>                              : Restrict the search to the specified moves"));
+        self.send_console(String::from("go ponder ...                                           : Ponder until ponderhit, then search with the specified time"));
+        self.send_console(String::from("ponderhit                                               : Tell the ponder search that the expected move was played"));
+        self.send_console(String::from("stop                                                    : Stop the search and print the best move found so far"));
+        self.send_console(String::from("setoption name <name> value <value>                     : Set an engine option"));
+        self.send_console(String::from("resetoptions                                            : Restore the default values of all options"));
+        self.send_console(String::from("saveconfig [<file>]                                     : Save the option values, which are loaded at startup"));
+        self.send_console(String::from("test <file> <movetime>                                  : Run an EPD test suite"));
+        self.send_console(String::from("perftsuite <file>                                       : Verify the move generation with a perft suite"));
+        self.send_console(String::from("perftdiff <engine> <depth>                              : Find the first position whose perft differs from the engine's"));
+        self.send_console(String::from("selfplay <n> <movetime> [openings <file>] [pgn <file>]  : Play games of Ladybug against herself"));
+        self.send_console(String::from("selfplay ... resign <movecount> <score>                 : Adjudicate a loss once a side's score was below -<score> for <movecount> moves"));
+        self.send_console(String::from("selfplay ... draw <movenumber> <movecount> <score>      : Adjudicate a draw once both scores were within <score> for <movecount> moves"));
+        self.send_console(String::from("match <engine> <n> <time> <increment> [<options>]       : Play a match against another engine, with the options of selfplay"));
+        self.send_console(String::from("bench [<depth>]                                         : Search the bench positions"));
+        self.send_console(String::from("bench --compare <file>                                  : Compare the bench to the last baseline in the file"));
+        self.send_console(String::from("speedtest [<depth>]                                     : Measure the speed of the move generation, the evaluation and the search"));
+        self.send_console(String::from("debug [on | off]                                        : Enable or disable debug output"));
+        self.send_console(String::from("exclude [<move>]                                        : Exclude a move from the search of the current position"));
+        self.send_console(String::from("analyze                                                 : Enter the interactive analysis mode"));
+        self.send_console(String::from("board [unicode] [flip]                                  : Print the board of the current position"));
+        self.send_console(String::from("display [noclock]                                       : Print the fen of the current position, optionally without the clocks"));
+        self.send_console(String::from("hashstats                                               : Print the statistics of the transposition table"));
         self.send_console(String::from("quit                                                    : Quit Ladybug"));
     }
 
-    /// Handles the "display" command.
-    fn handle_display(&self) {
-        self.send_console(self.game.board.to_fen());
+    /// Handles the "analyze" command, which enters the interactive analysis mode for the current game.
+    /// If no position has been set up, the analysis starts from the starting position.
+    fn handle_analyze(&mut self) {
+        self.clear_excluded_move();
+        let mut game = std::mem::take(&mut self.game);
+        if game.board().position.get_occupancy(Color::White).value == 0 {
+            game = Game::new();
+        }
+        let analysis = Analysis::new(game);
+        self.send_console(String::from("Entered the analysis mode, type \"help\" to see a list of all commands."));
+        self.send_lines(analysis.render());
+        self.analysis = Some(analysis);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::sync::mpsc;
-    use std::sync::mpsc::{Receiver, Sender};
-    use std::{thread};
-    use std::time::Duration;
-    use crate::ladybug::{Ladybug, Message};
-    use crate::ladybug::Message::ConsoleMessage;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
-    use crate::search::{Search, SearchCommand};
+    /// Handles a command of the analysis mode.
+    /// Returns false if Ladybug should quit.
+    fn handle_analysis_command(&mut self, msg: String) -> bool {
+        let Some(analysis) = self.analysis.as_mut() else {
+            return true;
+        };
+        let command = match analysis::parse_analysis_command(msg.as_str()) {
+            Ok(command) => command,
+            Err(message) => {
+                self.send_console(message);
+                return true;
+            }
+        };
 
-    /// Creates a new Ladybug thread and returns the input_sender and output_receiver.
-    fn setup() -> (Sender<Message>, Receiver<String>) {
-        initialize_lookup_table();
+        match command {
+            AnalysisCommand::Move(move_str) => match analysis.make_move(move_str.as_str()) {
+                Ok(_san) => {
+                    let lines = analysis.render();
+                    self.send_lines(lines);
+                }
+                Err(message) => self.send_console(message),
+            }
+            AnalysisCommand::Undo => match analysis.undo() {
+                Some(san) => {
+                    let lines = analysis.render();
+                    self.send_console(format!("Took back {san}"));
+                    self.send_lines(lines);
+                }
+                None => self.send_console(String::from("No move to take back")),
+            }
+            AnalysisCommand::Redo => match analysis.redo() {
+                Some(san) => {
+                    let lines = analysis.render();
+                    self.send_console(format!("Replayed {san}"));
+                    self.send_lines(lines);
+                }
+                None => self.send_console(String::from("No move to replay")),
+            }
+            AnalysisCommand::Eval => {
+                let lines = analysis.eval_breakdown();
+                self.send_lines(lines);
+            }
+            AnalysisCommand::Hint => {
+                if self.hint_position.is_some() {
+                    self.send_console(String::from("Already searching for a hint"));
+                    return true;
+                }
+                let game = analysis.game();
+                if game.legal_moves().is_empty() {
+                    self.send_console(String::from("No legal moves"));
+                    return true;
+                }
+                let command = SearchCommand::SearchDepth(game.board(), game.board_history().clone(), HINT_DEPTH);
+                self.hint_position = Some(game.board().position);
+                self.send_search(command);
+            }
+            AnalysisCommand::Board => {
+                let lines = analysis.render();
+                self.send_lines(lines);
+            }
+            AnalysisCommand::Fen => {
+                let fen = analysis.game().board().to_fen();
+                self.send_console(fen);
+            }
+            AnalysisCommand::Help => self.send_lines(Analysis::help()),
+            AnalysisCommand::Exit => {
+                // the analyzed game becomes the current game
+                if let Some(analysis) = self.analysis.take() {
+                    self.game = analysis.into_game();
+                }
+                self.send_console(String::from("Left the analysis mode."));
+            }
+            AnalysisCommand::Quit => return false,
+        }
+        true
+    }
+
+    /// Sends the given lines to the output thread.
+    fn send_lines(&self, lines: Vec<String>) {
+        for line in lines {
+            self.send_console(line);
+        }
+    }
+
+    /// Handles the "display [noclock]" command, which prints the fen of the current position.
+    /// With "noclock", the halfmove clock and the fullmove counter are left out.
+    fn handle_display(&self, clock: bool) {
+        match clock {
+            true => self.send_console(self.game.board().to_fen()),
+            false => self.send_console(self.game.board().position.to_fen_fields()),
+        }
+    }
 
+    /// Handles the "board [unicode] [flip]" command, which prints the board followed by the fen.
+    /// With "flip", the board is shown from Black's point of view if Black is to move.
+    fn handle_board(&self, unicode: bool, flip: bool) {
+        let position = self.game.board().position;
+        let flipped = flip && position.color_to_move == Color::Black;
+        for line in position.render(unicode, flipped).lines() {
+            self.send_console(line.to_string());
+        }
+        self.send_console(format!("Fen: {}", self.game.board().to_fen()));
+    }
+}
+
+/// Parses the next argument as a positive count, e.g. the number of moves of an adjudication rule.
+fn next_count<'a>(args: &mut impl Iterator<Item = &'a str>) -> Option<u32> {
+    args.next().and_then(|arg| arg.parse::<u32>().ok()).filter(|count| *count > 0)
+}
+
+/// Parses the next argument as a score in centipawns, which must not be negative.
+fn next_score<'a>(args: &mut impl Iterator<Item = &'a str>) -> Option<i32> {
+    args.next().and_then(|arg| arg.parse::<i32>().ok()).filter(|score| *score >= 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use std::{thread};
+    use std::time::Duration;
+    use crate::ladybug::{allocate_time, clean_input_line, read_input, Ladybug, Message, MESSAGE_CHANNEL_CAPACITY, OUTPUT_CHANNEL_CAPACITY};
+    use crate::board::Board;
+    use crate::ladybug::Message::ConsoleMessage;
+    use crate::move_gen::perft::MAX_PERFT_DEPTH;
+    use crate::move_gen::ply::Ply;
+    use crate::search::{Search, SearchCommand};
+
+    /// Creates a new Ladybug thread and returns the input_sender and output_receiver.
+    fn setup() -> (SyncSender<Message>, Receiver<String>) {
         // create search_command_sender and search_command_receiver so that the ladybug thread can send commands to the search thread
         let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
 
         // create message_sender and message_receiver so that the input and search threads can send input to the ladybug thread
-        let (message_sender, message_receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
 
         // create output_sender and output_receiver so that the ladybug thread can send output to the output thread.
-        let (output_sender, output_receiver): (Sender<String>, Receiver<String>) = mpsc::channel();
+        let (output_sender, output_receiver): (SyncSender<String>, Receiver<String>) = mpsc::sync_channel(OUTPUT_CHANNEL_CAPACITY);
         
         // initialize the search
         let mut search = Search::new(search_command_receiver, message_sender.clone());
+        let stop_flag = search.stop_flag();
+        let ponder_flag = search.ponder_flag();
         
         // spawn the search thread
         thread::spawn(move || search.run());
 
         // initialize Ladybug
-        let mut ladybug = Ladybug::new(search_command_sender, output_sender.clone(), message_receiver);
+        let mut ladybug = Ladybug::new(search_command_sender, output_sender.clone(), message_receiver, stop_flag, ponder_flag);
 
         // spawn the Ladybug thread
         thread::spawn(move || ladybug.run());
@@ -383,13 +1352,6 @@ mod tests {
         (message_sender, output_receiver)
     }
 
-    /// helper function to initialize the lookup table
-    fn initialize_lookup_table() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-    }
-
     #[test]
     fn test_ladybug_with_invalid_uci_input_prints_error_message() {
         let (input_sender, output_receiver) = setup();
@@ -417,9 +1379,411 @@ mod tests {
         let _ = input_sender.send(ConsoleMessage(String::from("uci")));
         assert_eq!("id name Ladybug 0.5.0", output_receiver.recv().unwrap());
         assert_eq!("id author Felix O.", output_receiver.recv().unwrap());
+        assert_eq!("option name OwnBook type check default false", output_receiver.recv().unwrap());
+        assert_eq!("option name BookFile type string default <empty>", output_receiver.recv().unwrap());
+        assert_eq!("option name OpeningVariety type spin default 0 min 0 max 100", output_receiver.recv().unwrap());
+        assert_eq!("option name Variety type spin default 0 min 0 max 100", output_receiver.recv().unwrap());
+        assert_eq!("option name Skill Level type spin default 20 min 0 max 20", output_receiver.recv().unwrap());
+        assert_eq!("option name Contempt type spin default 0 min -100 max 100", output_receiver.recv().unwrap());
+        assert_eq!("option name Hash type spin default 16 min 1 max 65536", output_receiver.recv().unwrap());
+        assert_eq!("option name Clear Hash type button", output_receiver.recv().unwrap());
+        assert_eq!("option name Threads type spin default 1 min 1 max 64", output_receiver.recv().unwrap());
+        assert_eq!("option name Move Overhead type spin default 10 min 0 max 5000", output_receiver.recv().unwrap());
+        assert_eq!("option name Ponder type check default false", output_receiver.recv().unwrap());
+        assert_eq!("option name Ponder Credit type spin default 50 min 0 max 100", output_receiver.recv().unwrap());
+        assert_eq!("option name EvalParamsFile type string default <empty>", output_receiver.recv().unwrap());
+        assert_eq!("option name LogFile type string default <empty>", output_receiver.recv().unwrap());
+        assert_eq!("option name UCI_Variant type combo default chess var chess var nocastle var kingofthehill", output_receiver.recv().unwrap());
+        assert_eq!("option name UCI_ShowWDL type check default false", output_receiver.recv().unwrap());
         assert_eq!("uciok", output_receiver.recv().unwrap());
     }
 
+    #[test]
+    fn test_ladybug_for_own_book() {
+        let (input_sender, output_receiver) = setup();
+
+        // a book containing only 1. d4 for the starting position
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0x463b96181691fc9cu64.to_be_bytes());
+        bytes.extend_from_slice(&0x02dbu16.to_be_bytes());
+        bytes.extend_from_slice(&1u16.to_be_bytes());
+        bytes.extend_from_slice(&0u32.to_be_bytes());
+        let path = std::env::temp_dir().join("ladybug_test_book.bin");
+        std::fs::write(&path, bytes).unwrap();
+
+        let _ = input_sender.send(ConsoleMessage(format!("setoption name BookFile value {}", path.display())));
+        assert_eq!("info string loaded book with 1 entries", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name OwnBook value true")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 3")));
+        assert_eq!("bestmove d2d4", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name BookFile value /this/file/does/not/exist.bin")));
+        assert_eq!("info string could not load book", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_internal_book() {
+        let (input_sender, output_receiver) = setup();
+
+        // without a book file, the move is taken from the internal book instead of searched
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name OwnBook value true")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 3")));
+        let best_move = output_receiver.recv().unwrap();
+        assert!(["bestmove e7e5", "bestmove c7c5", "bestmove e7e6", "bestmove c7c6", "bestmove d7d6", "bestmove g7g6",
+            "bestmove d7d5", "bestmove g8f6"].contains(&best_move.as_str()), "{best_move}");
+    }
+
+    #[test]
+    fn test_ladybug_for_hash() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Hash value big")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Hash value -1")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Hash value 0")));
+        assert_eq!("info string hash size clamped to 1 MB", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Hash value 99999999999999999999999")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+
+        // the search still works with the resized table
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Hash value 2")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 3")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.starts_with("bestmove "));
+
+        // the search filled the table, which the button empties
+        let _ = input_sender.send(ConsoleMessage(String::from("hashstats")));
+        assert!(output_receiver.recv().unwrap().starts_with("Hash entries: 87381, used "));
+        assert!(output_receiver.recv().unwrap().starts_with("Stored results: "));
+        assert!(output_receiver.recv().unwrap().starts_with("Average depth: "));
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Clear Hash")));
+        let _ = input_sender.send(ConsoleMessage(String::from("hashstats")));
+        assert_eq!("Hash entries: 87381, used 0 (0.0%), by the current search 0 (0.0%)", output_receiver.recv().unwrap());
+        assert_eq!("Stored results: 0 new, 0 replaced, 0 overwritten, 0 discarded", output_receiver.recv().unwrap());
+        assert_eq!("Average depth: 0.00", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_threads() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Threads value 0")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Threads value 65")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+
+        // searches to a fixed depth report a single line for the whole search
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Threads value 4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 2 4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 3")));
+        let output = output_receiver.recv().unwrap();
+        assert!(output.starts_with("info depth 3 "));
+        assert!(output.ends_with(" pv h5f7"));
+        assert_eq!("bestmove h5f7", output_receiver.recv().unwrap());
+
+        // the perft results are reported in the order of the move list
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go perft 2")));
+        let output: Vec<String> = (0..21).map(|_| output_receiver.recv().unwrap()).collect();
+        assert_eq!("a2a3: 20", output[0]);
+        assert!(output[20].starts_with("Searched 400 nodes"));
+    }
+
+    #[test]
+    fn test_ladybug_for_options_and_new_game() {
+        let (input_sender, output_receiver) = setup();
+
+        // a book containing only 1. d4 for the starting position
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0x463b96181691fc9cu64.to_be_bytes());
+        bytes.extend_from_slice(&0x02dbu16.to_be_bytes());
+        bytes.extend_from_slice(&1u16.to_be_bytes());
+        bytes.extend_from_slice(&0u32.to_be_bytes());
+        let path = std::env::temp_dir().join("ladybug_test_book_options.bin");
+        std::fs::write(&path, bytes).unwrap();
+
+        let _ = input_sender.send(ConsoleMessage(format!("setoption name BookFile value {}", path.display())));
+        assert_eq!("info string loaded book with 1 entries", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name OwnBook value true")));
+
+        // the options survive a new game, while the position is reset
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("ucinewgame")));
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("8/8/8/8/8/8/8/8 w - - 0 1", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 3")));
+        assert_eq!("bestmove d2d4", output_receiver.recv().unwrap());
+
+        // after the options are reset, the book is no longer used, so the position is searched
+        let _ = input_sender.send(ConsoleMessage(String::from("resetoptions")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv().unwrap().starts_with("info depth 1"));
+        assert!(output_receiver.recv().unwrap().starts_with("bestmove "));
+
+        // a new game doesn't bring back the old option values
+        let _ = input_sender.send(ConsoleMessage(String::from("ucinewgame")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv().unwrap().starts_with("info depth 1"));
+    }
+
+    #[test]
+    fn test_read_input() {
+        let (sender, receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+        let input: &[u8] = b"\xef\xbb\xbfuci\r\n\r\n\n  \nposition startpos moves e2e4\ngo \xff depth 1\r\n";
+        read_input(input, sender);
+
+        let lines: Vec<String> = receiver.try_iter().map(|message| match message {
+            ConsoleMessage(line) => line,
+            Message::SearchMessage(line) => panic!("unexpected search message {line}"),
+        }).collect();
+        // the end of the input is treated like "quit"
+        assert_eq!(vec!["uci", "position startpos moves e2e4", "go \u{fffd} depth 1", "quit"], lines);
+    }
+
+    #[test]
+    fn test_clean_input_line() {
+        assert_eq!(Some(String::from("uci")), clean_input_line(b"\xef\xbb\xbfuci\r\n"));
+        assert_eq!(Some(String::from("go \u{fffd} depth 1")), clean_input_line(b"go \xff depth 1\n"));
+        assert_eq!(Some(String::from("isready")), clean_input_line(b"isready"));
+        assert_eq!(None, clean_input_line(b"  \r\n"));
+        assert_eq!(Some(String::from("quit")), clean_input_line(b""));
+    }
+
+    #[test]
+    fn test_read_input_stops_at_quit() {
+        let (sender, receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+        read_input(&b"isready\nquit\nisready\n"[..], sender);
+        assert_eq!(2, receiver.try_iter().count());
+    }
+
+    #[test]
+    fn test_ladybug_for_config() {
+        let (input_sender, output_receiver) = setup();
+
+        let path = std::env::temp_dir().join("ladybug_test_config.toml");
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Skill Level value 12")));
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Contempt value -15")));
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Hash value 4")));
+        let _ = input_sender.send(ConsoleMessage(format!("saveconfig {}", path.display())));
+        assert_eq!(format!("info string saved config to {}", path.display()), output_receiver.recv().unwrap());
+
+        let config = std::fs::read_to_string(&path).unwrap();
+        assert!(config.contains("\n\"Skill Level\" = 12\n"));
+        assert!(config.contains("\nContempt = -15\n"));
+        assert!(config.contains("\nHash = 4\n"));
+        assert!(config.contains("\nBookFile = \"\"\n"));
+
+        // a new session starts with the default values, until the config is loaded
+        let (input_sender, output_receiver) = setup();
+        let _ = input_sender.send(ConsoleMessage(String::from("saveconfig /this/directory/does/not/exist.toml")));
+        assert_eq!("info string Could not write config file", output_receiver.recv().unwrap());
+
+        let defaults_path = std::env::temp_dir().join("ladybug_test_default_config.toml");
+        let _ = input_sender.send(ConsoleMessage(format!("saveconfig {}", defaults_path.display())));
+        assert!(output_receiver.recv().unwrap().starts_with("info string saved config"));
+        assert!(std::fs::read_to_string(&defaults_path).unwrap().contains("\n\"Skill Level\" = 20\n"));
+    }
+
+    #[test]
+    fn test_ladybug_for_log_file() {
+        let (input_sender, output_receiver) = setup();
+
+        let path = std::env::temp_dir().join("ladybug_test_log_file.txt");
+        let _ = std::fs::remove_file(&path);
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name LogFile value /this/directory/does/not/exist.log")));
+        assert_eq!("info string Could not open log file", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(format!("setoption name LogFile value {}", path.display())));
+        let _ = input_sender.send(ConsoleMessage(String::from("isready\n")));
+        assert_eq!("readyok", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("xboard")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+
+        // after logging is stopped, nothing is appended to the log anymore
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name LogFile value <empty>")));
+        let _ = input_sender.send(ConsoleMessage(String::from("isready")));
+        assert_eq!("readyok", output_receiver.recv().unwrap());
+
+        let log: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(|line| line.split_once(' ').unwrap().1.to_string()).collect();
+        assert_eq!(vec![
+            String::from(">> isready"),
+            String::from("<< readyok"),
+            String::from(">> xboard"),
+            String::from("!! Unknown command 'xboard'"),
+            String::from("<< info string unknown command"),
+            String::from(">> setoption name LogFile value <empty>"),
+        ], log);
+    }
+
+    #[test]
+    fn test_stop_flag() {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (_message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+        let (output_sender, _output_receiver): (SyncSender<String>, Receiver<String>) = mpsc::sync_channel(OUTPUT_CHANNEL_CAPACITY);
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let ladybug = Ladybug::new(search_command_sender, output_sender, message_receiver, Arc::clone(&stop_flag), Arc::new(AtomicBool::new(false)));
+
+        // "stop" doesn't wait for the search thread to receive it
+        ladybug.handle_stop();
+        assert!(stop_flag.load(Ordering::Relaxed));
+        assert!(search_command_receiver.try_recv().is_err());
+
+        // options don't reset the flag, but the next search does
+        ladybug.send_search(SearchCommand::Debug(true));
+        assert!(stop_flag.load(Ordering::Relaxed));
+        ladybug.handle_depth(String::from("1"));
+        assert!(!stop_flag.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_load_config() {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (_message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+        let (output_sender, output_receiver): (SyncSender<String>, Receiver<String>) = mpsc::sync_channel(OUTPUT_CHANNEL_CAPACITY);
+        let mut ladybug = Ladybug::new(search_command_sender, output_sender, message_receiver, Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+
+        let path = std::env::temp_dir().join("ladybug_test_load_config.toml");
+        std::fs::write(&path, "# a comment\n\"Skill Level\" = 7\nMove Overhead = 50\nBogus = 4\n").unwrap();
+        ladybug.load_config(path.to_str().unwrap());
+        assert_eq!(7, ladybug.skill_level);
+        assert_eq!(50, ladybug.move_overhead);
+        assert!(matches!(search_command_receiver.try_recv(), Ok(SearchCommand::SkillLevel(7))));
+
+        // unknown options are reported, while the other options are still loaded
+        assert_eq!("info string unknown option", output_receiver.recv().unwrap());
+        assert_eq!(format!("info string loaded config from {}", path.display()), output_receiver.recv().unwrap());
+
+        ladybug.load_config("/this/file/does/not/exist.toml");
+        assert_eq!("info string Could not read config file", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_opening_variety() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name OpeningVariety value 101")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name OpeningVariety value many")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+
+        // the best move is picked among the best root moves, so only check that it is legal
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name OpeningVariety value 4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 3")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert!(Ply::from_string(output.strip_prefix("bestmove ").unwrap(), board.position).is_ok());
+    }
+
+    #[test]
+    fn test_ladybug_for_variety() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Variety value 101")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Variety value -1")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+
+        // the variety also applies after the opening, so only check that the best move is legal
+        let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 20";
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Variety value 30")));
+        let _ = input_sender.send(ConsoleMessage(format!("position fen {fen}")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 3")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        let board = Board::from_fen(fen).unwrap();
+        assert!(Ply::from_string(output.strip_prefix("bestmove ").unwrap(), board.position).is_ok());
+    }
+
+    #[test]
+    fn test_ladybug_for_skill_level() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Skill Level value 21")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Skill Level value -1")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+
+        // at skill level 0, the search stops after depth 1, and the best move is picked among the root moves
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Skill Level value 0")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 5")));
+        assert!(output_receiver.recv().unwrap().starts_with("info depth 1 "));
+        let output = output_receiver.recv().unwrap();
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert!(Ply::from_string(output.strip_prefix("bestmove ").unwrap(), board.position).is_ok());
+
+        // resetting the options restores the full strength
+        let _ = input_sender.send(ConsoleMessage(String::from("resetoptions")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        assert!(output_receiver.recv().unwrap().starts_with("info depth 2 "));
+    }
+
+    #[test]
+    fn test_ladybug_for_contempt() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Contempt value 101")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Contempt value -101")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+
+        // every move leads to a draw by insufficient material, which Ladybug scores with the negative contempt
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Contempt value 30")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 4k3/8/8/8/8/8/8/4KN2 w - - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv().unwrap().contains("score cp -30 "));
+        assert!(output_receiver.recv().unwrap().starts_with("bestmove"));
+
+        // the opponent's draws are scored with the positive contempt from Ladybug's point of view
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 4k3/8/8/8/8/8/8/4KN2 b - - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv().unwrap().contains("score cp -30 "));
+        assert!(output_receiver.recv().unwrap().starts_with("bestmove"));
+
+        // resetting the options scores draws as equal again
+        let _ = input_sender.send(ConsoleMessage(String::from("resetoptions")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv().unwrap().contains("score cp 0 "));
+        assert!(output_receiver.recv().unwrap().starts_with("bestmove"));
+    }
+
+    #[test]
+    fn test_ladybug_for_show_wdl() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name UCI_ShowWDL value yes")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+
+        // every move leads to a draw by insufficient material, which is mostly drawn according to the model
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name UCI_ShowWDL value true")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 4k3/8/8/8/8/8/8/4KN2 w - - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv().unwrap().contains("score cp 0 wdl 76 848 76 pv "));
+        assert!(output_receiver.recv().unwrap().starts_with("bestmove"));
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name UCI_ShowWDL value false")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 1")));
+        assert!(output_receiver.recv().unwrap().contains("score cp 0 pv "));
+        assert!(output_receiver.recv().unwrap().starts_with("bestmove"));
+    }
+
     #[test]
     fn test_ladybug_for_isready() {
         let (input_sender, output_receiver) = setup();
@@ -441,6 +1805,33 @@ mod tests {
         assert_eq!("8/8/8/8/8/8/8/8 w - - 0 1", output_receiver.recv().unwrap());
     }
 
+    #[test]
+    fn test_ladybug_for_position_continuing_the_game() {
+        let (input_sender, output_receiver) = setup();
+
+        // the new moves are played on top of the current game
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 e7e5")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 e7e5 g1f3 b8c6")));
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", output_receiver.recv().unwrap());
+
+        // the moves are counted from the start of the game, and the game is left untouched
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 e7e5 g1f3 b8c6 f3f5")));
+        assert_eq!("info string invalid moves: Illegal move: f3f5 (move 5)", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", output_receiver.recv().unwrap());
+
+        // moves that differ from the current game, or a different starting position, start a new game
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves d2d4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 1", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("display noclock")));
+        assert_eq!("rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq d3", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq - 0 1 moves d7d5")));
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("rnbqkbnr/ppp1pppp/8/3p4/3P4/8/PPP1PPPP/RNBQKBNR w KQkq d6 0 2", output_receiver.recv().unwrap());
+    }
+
     #[test]
     fn test_ladybug_for_position() {
         let (input_sender, output_receiver) = setup();
@@ -460,6 +1851,21 @@ mod tests {
         let _ = input_sender.send(ConsoleMessage(String::from("position fen r1bqkbnr/pp1ppppp/2n5/2p5/3PP3/2P5/PP3PPP/RNBQKBNR b KQkq d3 0 3 moves c5d4 h2h4 d4c3 g1f3 c3b2 f1b5 b2c1q")));
         let _ = input_sender.send(ConsoleMessage(String::from("display")));
         assert_eq!("r1bqkbnr/pp1ppppp/2n5/1B6/4P2P/5N2/P4PP1/RNqQK2R w KQkq - 0 7", output_receiver.recv().unwrap());
+
+        // invalid commands leave the previous position untouched
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 e7e5 e4e5")));
+        assert_eq!("info string invalid moves: Illegal move: e4e5 (move 3)", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 8/4P3/8/8/8/8/k7/4K3 w - - 0 1 moves e7e8")));
+        assert_eq!("info string invalid moves: Ambiguous move: e7e8 is missing the promotion piece (move 1)", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen this is invalid fen")));
+        assert_eq!("info string invalid fen", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("r1bqkbnr/pp1ppppp/2n5/1B6/4P2P/5N2/P4PP1/RNqQK2R w KQkq - 0 7", output_receiver.recv().unwrap());
+
+        // promotions are accepted with the promotion piece
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 8/4P3/8/8/8/8/k7/4K3 w - - 0 1 moves e7e8n")));
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("4N3/8/8/8/8/8/k7/4K3 b - - 0 1", output_receiver.recv().unwrap());
     }
 
     #[test]
@@ -505,6 +1911,173 @@ mod tests {
         assert!(output.iter().any(|r| r.contains("bestmove")));
     }
 
+    #[test]
+    fn test_allocate_time() {
+        // a fortieth of the remaining time plus the increment
+        assert_eq!(1000 + 500, allocate_time(40_000, 500, 0));
+
+        // the move overhead is reserved before the time is divided
+        assert_eq!(990 + 500, allocate_time(40_000, 500, 400));
+
+        // when low on the clock, the increment can't use up more than half of the remaining time
+        assert_eq!(450, allocate_time(1000, 2000, 100));
+
+        // the search always gets at least 1 millisecond
+        assert_eq!(1, allocate_time(50, 0, 100));
+        assert_eq!(1, allocate_time(0, 0, 0));
+    }
+
+    #[test]
+    fn test_ladybug_for_move_overhead() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Move Overhead value 5001")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Move Overhead value soon")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+
+        // even with an overhead larger than the remaining time, a best move is sent
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Move Overhead value 1000")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go wtime 500 btime 500")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.starts_with("bestmove "));
+    }
+
+    #[test]
+    fn test_ladybug_for_eval_params_file() {
+        let (input_sender, output_receiver) = setup();
+
+        // the file contains the default values, since other tests share the evaluation parameters
+        let path = std::env::temp_dir().join("ladybug_test_eval_params.toml");
+        std::fs::write(&path, "# default piece values\npiece_values = [100, 320, 330, 500, 950, 20000]\n").unwrap();
+        let _ = input_sender.send(ConsoleMessage(format!("setoption name EvalParamsFile value {}", path.display())));
+        assert_eq!("info string loaded evaluation parameters", output_receiver.recv().unwrap());
+
+        std::fs::write(&path, "piece_values = [100, 320, 330]\n").unwrap();
+        let _ = input_sender.send(ConsoleMessage(format!("setoption name EvalParamsFile value {}", path.display())));
+        assert_eq!("info string could not load evaluation parameters: Expected 6 values for 'piece_values'", output_receiver.recv().unwrap());
+        let _ = std::fs::remove_file(&path);
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name EvalParamsFile value /this/file/does/not/exist.toml")));
+        assert_eq!("info string could not load evaluation parameters: Could not read evaluation parameter file", output_receiver.recv().unwrap());
+
+        // the search still works after restoring the built-in parameters
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name EvalParamsFile value <empty>")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.starts_with("bestmove "));
+    }
+
+    #[test]
+    fn test_ladybug_for_analysis_mode() {
+        let (input_sender, output_receiver) = setup();
+
+        // without a position, the analysis starts from the starting position
+        let _ = input_sender.send(ConsoleMessage(String::from("analyze")));
+        assert!(output_receiver.recv().unwrap().starts_with("Entered the analysis mode"));
+        assert_eq!("8  r  n  b  q  k  b  n  r  ", output_receiver.recv().unwrap());
+        let mut output = output_receiver.recv().unwrap();
+        while !output.starts_with("Fen: ") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("Fen: rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", output);
+
+        // moves are accepted in SAN and coordinate notation, and uci commands are not available
+        let _ = input_sender.send(ConsoleMessage(String::from("f3")));
+        let _ = input_sender.send(ConsoleMessage(String::from("e7e5")));
+        let _ = input_sender.send(ConsoleMessage(String::from("g4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("Nf3")));
+        let _ = input_sender.send(ConsoleMessage(String::from("undo")));
+        let _ = input_sender.send(ConsoleMessage(String::from("redo")));
+        let _ = input_sender.send(ConsoleMessage(String::from("redo")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let _ = input_sender.send(ConsoleMessage(String::from("fen")));
+        let mut output = output_receiver.recv().unwrap();
+        while !output.starts_with("Illegal") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("Illegal move: Nf3", output);
+        while !output.starts_with("Took back") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("Took back g4", output);
+        while !output.starts_with("Replayed") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("Replayed g4", output);
+        while !output.starts_with("No move") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("No move to replay", output);
+        assert!(output_receiver.recv().unwrap().starts_with("Unknown command"));
+        assert_eq!("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("eval")));
+        assert!(output_receiver.recv().unwrap().starts_with("Term"));
+        assert!(output_receiver.recv().unwrap().starts_with("Material"));
+        assert!(output_receiver.recv().unwrap().starts_with("Piece-square"));
+        assert!(output_receiver.recv().unwrap().starts_with("Passed pawns"));
+        assert!(output_receiver.recv().unwrap().starts_with("Coordination"));
+        assert!(output_receiver.recv().unwrap().starts_with("Bishops"));
+        assert!(output_receiver.recv().unwrap().starts_with("Threats"));
+        assert!(output_receiver.recv().unwrap().starts_with("Tempo"));
+        assert!(output_receiver.recv().unwrap().starts_with("Evaluation"));
+        assert!(output_receiver.recv().unwrap().starts_with("Game phase"));
+
+        // after leaving the analysis mode, the analyzed game is the current game
+        let _ = input_sender.send(ConsoleMessage(String::from("exit")));
+        assert_eq!("Left the analysis mode.", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2", output_receiver.recv().unwrap());
+
+        // the analysis continues from the current position, and the hint is given in SAN
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("analyze")));
+        let _ = input_sender.send(ConsoleMessage(String::from("hint")));
+        let mut output = output_receiver.recv().unwrap();
+        while !output.starts_with("Hint") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("Hint: Ra8#", output);
+    }
+
+    #[test]
+    fn test_ladybug_for_search_features() {
+        let (input_sender, output_receiver) = setup();
+
+        // the search features are hidden options
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name KillerMoves value maybe")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name NullMove value false")));
+        assert_eq!("info string unknown option", output_receiver.recv().unwrap());
+
+        // so are the reverse futility margins
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name ReverseFutilityMargin value -5")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name ReverseFutilityDepth value 21")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name ReverseFutilityMargin value 120")));
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name ReverseFutilityDepth value 4")));
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name CheckExtension value false")));
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name transpositiontable value false")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 2 4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("bestmove h5f7", output);
+    }
+
     #[test]
     fn test_ladybug_for_go_move_time() {
         let (input_sender, output_receiver) = setup();
@@ -539,6 +2112,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ladybug_for_seldepth() {
+        let (input_sender, output_receiver) = setup();
+
+        // the captures on e5 are resolved by the quiescence search, so the selective depth exceeds the nominal depth
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen r1bqkbnr/pppp1ppp/2n5/4p3/3PP3/5N2/PPP2PPP/RNBQKB1R b KQkq - 0 3")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 3")));
+
+        let mut output = output_receiver.recv().unwrap();
+        let mut max_seldepth = 0;
+        while output.starts_with("info depth") {
+            let value = |key: &str| -> u64 { output.split_whitespace().skip_while(|part| *part != key).nth(1).unwrap().parse().unwrap() };
+            assert!(value("seldepth") >= value("depth"), "{output}");
+            max_seldepth = max_seldepth.max(value("seldepth"));
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.starts_with("bestmove "));
+        assert!(max_seldepth > 3);
+    }
+
     #[test]
     fn test_ladybug_for_go_perft() {
         let (input_sender, output_receiver) = setup();
@@ -555,8 +2148,338 @@ mod tests {
         }
 
         assert!(output.iter().any(|r| r.contains("Searched")));
+
+        // at depth 0, only the position itself is counted, and depths beyond the maximum are rejected
+        let _ = input_sender.send(ConsoleMessage(String::from("go perft 0")));
+        assert!(output_receiver.recv().unwrap().starts_with("Searched 1 nodes in "));
+        let _ = input_sender.send(ConsoleMessage(format!("go perft {}", MAX_PERFT_DEPTH + 1)));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
     }
-    
+
+    #[test]
+    fn test_ladybug_for_go_mate() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go mate 2")));
+        assert!(output_receiver.recv().unwrap().ends_with("score mate 2 pv d5f6 g7f6 c4f7"));
+        assert_eq!("bestmove d5f6", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("go mate 0")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_exclude() {
+        let (input_sender, output_receiver) = setup();
+
+        // the best move other than the mate is found
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("exclude h5f7")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            assert!(!output.contains(" pv h5f7"));
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.starts_with("bestmove "));
+        assert_ne!("bestmove h5f7", output);
+
+        // the excluded move is searched again once the exclusion is cleared
+        let _ = input_sender.send(ConsoleMessage(String::from("exclude")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("bestmove h5f7", output);
+
+        // setting up a new position clears the exclusion
+        let _ = input_sender.send(ConsoleMessage(String::from("exclude h5f7")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("bestmove h5f7", output);
+
+        let _ = input_sender.send(ConsoleMessage(String::from("exclude e2e5")));
+        assert_eq!("info string invalid move", output_receiver.recv().unwrap());
+
+        // the only legal move can't be excluded
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen 7k/8/8/8/8/8/6q1/7K w - - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("exclude h1g2")));
+        assert_eq!("info string no other legal move", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_variants() {
+        let (input_sender, output_receiver) = setup();
+
+        // in King of the Hill, the king wins by reaching the center
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name UCI_Variant value kingofthehill")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen r3k3/8/8/8/8/3K4/8/7R w - - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output == "bestmove d3d4" || output == "bestmove d3e4", "{output}");
+
+        // without castling, castling moves are illegal
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name UCI_Variant value nocastle")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("exclude e1g1")));
+        assert_eq!("info string invalid move", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name UCI_Variant value crazyhouse")));
+        assert_eq!("info string unknown variant", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_go_search_moves() {
+        let (input_sender, output_receiver) = setup();
+
+        // the mate is not among the search moves
+        let _ = input_sender.send(ConsoleMessage(String::from("position fen r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2 searchmoves a2a3 b2b3")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            assert!(output.contains(" pv a2a3") || output.contains(" pv b2b3"));
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output == "bestmove a2a3" || output == "bestmove b2b3");
+
+        // the restriction only applies to one search
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("bestmove h5f7", output);
+
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2 searchmoves a2a3 e2e5")));
+        assert_eq!("info string invalid move", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_ponder() {
+        let (input_sender, output_receiver) = setup();
+
+        // the ponder search ignores its time limit, and the best move includes the expected reply
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Ponder value true")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 e7e5")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go ponder movetime 50")));
+        while let Ok(output) = output_receiver.recv_timeout(Duration::from_millis(500)) {
+            assert!(output.starts_with("info"), "{output}");
+        }
+        let _ = input_sender.send(ConsoleMessage(String::from("ponderhit")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        let moves: Vec<&str> = output.split_whitespace().collect();
+        assert_eq!(4, moves.len(), "{output}");
+        assert_eq!(("bestmove", "ponder"), (moves[0], moves[2]));
+
+        // if the opponent plays another move, the GUI stops the ponder search
+        let _ = input_sender.send(ConsoleMessage(String::from("go wtime 100000 btime 100000 ponder")));
+        thread::sleep(Duration::from_millis(100));
+        let _ = input_sender.send(ConsoleMessage(String::from("stop")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.starts_with("bestmove "));
+
+        // a regular search after a ponder search uses its time limit right away
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Ponder value false")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go movetime 50")));
+        let mut output = output_receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+        assert_eq!(2, output.split_whitespace().count(), "{output}");
+
+        let _ = input_sender.send(ConsoleMessage(String::from("setoption name Ponder Credit value 101")));
+        assert_eq!("info string invalid option value", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_go_nodes() {
+        let (input_sender, output_receiver) = setup();
+
+        // searching the same number of nodes twice in a new game results in the same search
+        let mut results = Vec::new();
+        for _ in 0..2 {
+            let _ = input_sender.send(ConsoleMessage(String::from("ucinewgame")));
+            let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 e7e5")));
+            let _ = input_sender.send(ConsoleMessage(String::from("go nodes 5000")));
+            let mut lines = Vec::new();
+            let mut output = output_receiver.recv().unwrap();
+            while output.starts_with("info") {
+                let nodes: u128 = output.split_whitespace().skip_while(|part| *part != "nodes").nth(1).unwrap().parse().unwrap();
+                assert!(nodes <= 5000);
+                lines.push(output.split(" nps ").next().unwrap().to_string());
+                output = output_receiver.recv().unwrap();
+            }
+            assert!(output.starts_with("bestmove "));
+            lines.push(output);
+            results.push(lines);
+        }
+        assert_eq!(results[0], results[1]);
+
+        let _ = input_sender.send(ConsoleMessage(String::from("go nodes 0")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("go nodes many")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_test() {
+        let (input_sender, output_receiver) = setup();
+
+        let path = std::env::temp_dir().join("ladybug_test_suite.epd");
+        std::fs::write(&path, "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7#; id \"mate\";\n").unwrap();
+
+        let _ = input_sender.send(ConsoleMessage(format!("test {} 100", path.display())));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("mate: Qxf7# solved", output);
+        assert!(output_receiver.recv().unwrap().starts_with("Solved 1 of 1 positions"));
+
+        let _ = input_sender.send(ConsoleMessage(format!("test {} soon", path.display())));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("test /this/file/does/not/exist.epd 100")));
+        assert_eq!("info string Could not read EPD file", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_perft_suite() {
+        let (input_sender, output_receiver) = setup();
+
+        let path = std::env::temp_dir().join("ladybug_test_perft_suite.epd");
+        std::fs::write(&path, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400\n").unwrap();
+
+        let _ = input_sender.send(ConsoleMessage(format!("perftsuite {}", path.display())));
+        assert_eq!("1: depth 1 nodes 20 passed", output_receiver.recv().unwrap());
+        assert_eq!("2: depth 2 nodes 400 passed", output_receiver.recv().unwrap());
+        assert!(output_receiver.recv().unwrap().starts_with("Passed 2 of 2 perft tests"));
+
+        let _ = input_sender.send(ConsoleMessage(String::from("perftsuite /this/file/does/not/exist.epd")));
+        assert_eq!("info string Could not read perft suite", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_selfplay() {
+        let (input_sender, output_receiver) = setup();
+
+        let path = std::env::temp_dir().join("ladybug_test_selfplay_openings.epd");
+        std::fs::write(&path, "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq -\n").unwrap();
+
+        let _ = input_sender.send(ConsoleMessage(format!("selfplay 1 10 openings {}", path.display())));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("Game 1 of 1: 1-0 (Checkmate(White))", output);
+        assert_eq!("Played 1 games: White won 1, Black won 0, 0 draws", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("selfplay 0 10")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("selfplay 1 10 pgn")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("selfplay 1 10 resign 3")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("selfplay 1 10 draw 40 8 -10")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("selfplay 1 10 openings /this/file/does/not/exist.epd")));
+        assert_eq!("info string Could not read EPD file", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_bench() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("bench 1")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info") || output.starts_with("Position") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.ends_with(" nps"));
+
+        let _ = input_sender.send(ConsoleMessage(String::from("bench 0")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("bench deep")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+
+        // the bench is compared to the last baseline of the file
+        let path = std::env::temp_dir().join("ladybug_test_baselines.txt");
+        std::fs::write(&path, "# baselines\nold 1 1 0 1\nnew 1 1 0 1\n").unwrap();
+        let _ = input_sender.send(ConsoleMessage(format!("bench --compare {}", path.display())));
+        let mut output = output_receiver.recv().unwrap();
+        while !output.starts_with("Bench ") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert_eq!("Bench deviates from baseline new", output);
+
+        let _ = input_sender.send(ConsoleMessage(String::from("bench --compare /this/file/does/not/exist.txt")));
+        assert_eq!("info string Could not read bench baseline file", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_speedtest() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("speedtest 1")));
+        let mut output = output_receiver.recv().unwrap();
+        while !output.starts_with("search: ") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.ends_with(" nps"));
+
+        let _ = input_sender.send(ConsoleMessage(String::from("speedtest 0")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+        let _ = input_sender.send(ConsoleMessage(String::from("speedtest fast")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_debug() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("debug on")));
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info depth") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.starts_with("info string nodes main "));
+        assert!(output.contains(" qsearch "));
+        assert!(output.contains(" probcut "));
+        let output = output_receiver.recv().unwrap();
+        assert!(output.starts_with("info string extensions check "));
+        assert!(output.contains(" denied "));
+        assert!(output_receiver.recv().unwrap().starts_with("info string probcut cutoffs "));
+        assert!(output_receiver.recv().unwrap().starts_with("info string iir reductions "));
+        assert!(output_receiver.recv().unwrap().starts_with("info string reverse futility cutoffs "));
+        assert!(output_receiver.recv().unwrap().starts_with("info string eval cache hits "));
+        assert!(output_receiver.recv().unwrap().starts_with("bestmove"));
+
+        let _ = input_sender.send(ConsoleMessage(String::from("debug off")));
+        let _ = input_sender.send(ConsoleMessage(String::from("go depth 2")));
+        let mut output = output_receiver.recv().unwrap();
+        while output.starts_with("info depth") {
+            output = output_receiver.recv().unwrap();
+        }
+        assert!(output.starts_with("bestmove"));
+    }
+
     #[test]
     fn test_ladybug_for_quit() {
         let (input_sender, output_receiver) = setup();
@@ -565,6 +2488,21 @@ mod tests {
         assert_eq!("quit", output_receiver.recv().unwrap());
     }
 
+    #[test]
+    fn test_ladybug_quits_if_search_thread_is_gone() {
+        let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+        let (output_sender, output_receiver): (SyncSender<String>, Receiver<String>) = mpsc::sync_channel(OUTPUT_CHANNEL_CAPACITY);
+        drop(search_command_receiver);
+
+        // instead of panicking, Ladybug shuts down and still tells the output thread to terminate
+        let mut ladybug = Ladybug::new(search_command_sender, output_sender, message_receiver, Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let _ = message_sender.send(ConsoleMessage(String::from("go depth 1")));
+        let _ = message_sender.send(ConsoleMessage(String::from("isready")));
+        ladybug.run();
+        assert_eq!(vec!["quit"], output_receiver.try_iter().collect::<Vec<String>>());
+    }
+
     #[test]
     fn test_ladybug_for_help() {
         let (input_sender, output_receiver) = setup();
@@ -580,7 +2518,31 @@ mod tests {
         assert_eq!("go movetime <time>                                      : Search for the specified time", output_receiver.recv().unwrap());
         assert_eq!("go depth <depth>                                        : Search to the specified depth", output_receiver.recv().unwrap());
         assert_eq!("go perft <depth>                                        : Perform a perft test", output_receiver.recv().unwrap());
-        assert_eq!("display                                                 : Print the fen of the current position", output_receiver.recv().unwrap());
+        assert_eq!("go mate <moves>                                         : Prove a mate in the specified number of moves", output_receiver.recv().unwrap());
+        assert_eq!("go nodes <nodes>                                        : Search the specified number of nodes", output_receiver.recv().unwrap());
+        assert_eq!("go ... searchmoves <moves>                              : Restrict the search to the specified moves", output_receiver.recv().unwrap());
+        assert_eq!("go ponder ...                                           : Ponder until ponderhit, then search with the specified time", output_receiver.recv().unwrap());
+        assert_eq!("ponderhit                                               : Tell the ponder search that the expected move was played", output_receiver.recv().unwrap());
+        assert_eq!("stop                                                    : Stop the search and print the best move found so far", output_receiver.recv().unwrap());
+        assert_eq!("setoption name <name> value <value>                     : Set an engine option", output_receiver.recv().unwrap());
+        assert_eq!("resetoptions                                            : Restore the default values of all options", output_receiver.recv().unwrap());
+        assert_eq!("saveconfig [<file>]                                     : Save the option values, which are loaded at startup", output_receiver.recv().unwrap());
+        assert_eq!("test <file> <movetime>                                  : Run an EPD test suite", output_receiver.recv().unwrap());
+        assert_eq!("perftsuite <file>                                       : Verify the move generation with a perft suite", output_receiver.recv().unwrap());
+        assert_eq!("perftdiff <engine> <depth>                              : Find the first position whose perft differs from the engine's", output_receiver.recv().unwrap());
+        assert_eq!("selfplay <n> <movetime> [openings <file>] [pgn <file>]  : Play games of Ladybug against herself", output_receiver.recv().unwrap());
+        assert_eq!("selfplay ... resign <movecount> <score>                 : Adjudicate a loss once a side's score was below -<score> for <movecount> moves", output_receiver.recv().unwrap());
+        assert_eq!("selfplay ... draw <movenumber> <movecount> <score>      : Adjudicate a draw once both scores were within <score> for <movecount> moves", output_receiver.recv().unwrap());
+        assert_eq!("match <engine> <n> <time> <increment> [<options>]       : Play a match against another engine, with the options of selfplay", output_receiver.recv().unwrap());
+        assert_eq!("bench [<depth>]                                         : Search the bench positions", output_receiver.recv().unwrap());
+        assert_eq!("bench --compare <file>                                  : Compare the bench to the last baseline in the file", output_receiver.recv().unwrap());
+        assert_eq!("speedtest [<depth>]                                     : Measure the speed of the move generation, the evaluation and the search", output_receiver.recv().unwrap());
+        assert_eq!("debug [on | off]                                        : Enable or disable debug output", output_receiver.recv().unwrap());
+        assert_eq!("exclude [<move>]                                        : Exclude a move from the search of the current position", output_receiver.recv().unwrap());
+        assert_eq!("analyze                                                 : Enter the interactive analysis mode", output_receiver.recv().unwrap());
+        assert_eq!("board [unicode] [flip]                                  : Print the board of the current position", output_receiver.recv().unwrap());
+        assert_eq!("display [noclock]                                       : Print the fen of the current position, optionally without the clocks", output_receiver.recv().unwrap());
+        assert_eq!("hashstats                                               : Print the statistics of the transposition table", output_receiver.recv().unwrap());
         assert_eq!("quit                                                    : Quit Ladybug", output_receiver.recv().unwrap());
     }
 
@@ -595,6 +2557,35 @@ mod tests {
         let _ = input_sender.send(ConsoleMessage(String::from("position fen r1bqk1nr/ppp1bBpp/3p4/n7/3PP3/1Q3N2/P4PPP/RNB1K2R b KQkq - 0 9")));
         let _ = input_sender.send(ConsoleMessage(String::from("display")));
         assert_eq!("r1bqk1nr/ppp1bBpp/3p4/n7/3PP3/1Q3N2/P4PPP/RNB1K2R b KQkq - 0 9", output_receiver.recv().unwrap());
+
+        // the clocks are updated by the moves
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4 e7e5 g1f3 b8c6 f1b5 g8f6 e1g1")));
+        let _ = input_sender.send(ConsoleMessage(String::from("display")));
+        assert_eq!("r1bqkb1r/pppp1ppp/2n2n2/1B2p3/4P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4", output_receiver.recv().unwrap());
+    }
+
+    #[test]
+    fn test_ladybug_for_board() {
+        let (input_sender, output_receiver) = setup();
+
+        let _ = input_sender.send(ConsoleMessage(String::from("position startpos moves e2e4")));
+        let _ = input_sender.send(ConsoleMessage(String::from("board")));
+        assert_eq!("8  r  n  b  q  k  b  n  r  ", output_receiver.recv().unwrap());
+        for _ in 0..12 {
+            output_receiver.recv().unwrap();
+        }
+        assert_eq!("Fen: rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", output_receiver.recv().unwrap());
+
+        // Black is to move, so the flipped board starts with the first rank
+        let _ = input_sender.send(ConsoleMessage(String::from("board unicode flip")));
+        assert_eq!("1  ♖  ♘  ♗  ♔  ♕  ♗  ♘  ♖  ", output_receiver.recv().unwrap());
+        for _ in 0..12 {
+            output_receiver.recv().unwrap();
+        }
+        assert_eq!("Fen: rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", output_receiver.recv().unwrap());
+
+        let _ = input_sender.send(ConsoleMessage(String::from("board sideways")));
+        assert_eq!("info string unknown command", output_receiver.recv().unwrap());
     }
 }
 
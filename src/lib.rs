@@ -6,3 +6,17 @@ pub mod uci;
 pub mod search;
 pub mod evaluation;
 pub mod zobrist;
+pub mod book;
+pub mod epd;
+pub mod game;
+pub mod analysis;
+pub mod config;
+pub mod logger;
+pub mod json;
+#[cfg(feature = "lichess")]
+pub mod lichess;
+pub mod engine;
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "wasm")]
+pub mod wasm;
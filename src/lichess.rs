@@ -0,0 +1,210 @@
+//! The lichess module translates the [Lichess Bot API](https://lichess.org/api#tag/Bot) into commands for the engine,
+//! so that the "ladybug-lichess" binary can play games on Lichess.
+//!
+//! Lichess streams events and game states as newline delimited JSON. The module parses the lines with the parser of the json module,
+//! decides which challenges are accepted, and turns the game states into "position" and "go" commands.
+//! The binary itself only handles the HTTP requests and is built with the "lichess" feature.
+
+use crate::board::color::Color;
+use crate::game::{Game, START_FEN};
+use crate::json::Json;
+
+/// An event of the event stream of the bot account.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Event {
+    /// Another player challenged the bot to a game of the given variant.
+    Challenge { id: String, variant: String },
+    /// A game of the bot started.
+    GameStart { id: String },
+    /// Any other event, which the bot ignores.
+    Other,
+}
+
+impl Event {
+    /// Parses a line of the event stream. Empty lines are sent to keep the connection alive, and are parsed as `Event::Other`.
+    pub fn parse(line: &str) -> Result<Event, String> {
+        if line.trim().is_empty() {
+            return Ok(Event::Other);
+        }
+        let json = Json::parse(line)?;
+        let string = |value: Option<&Json>| value.and_then(|value| value.as_str()).map(|value| value.to_string());
+        match json.get("type").and_then(|value| value.as_str()) {
+            Some("challenge") => {
+                let challenge = json.get("challenge");
+                let id = string(challenge.and_then(|challenge| challenge.get("id"))).ok_or("Challenge without id")?;
+                let variant = string(challenge.and_then(|challenge| challenge.get("variant")).and_then(|variant| variant.get("key")))
+                    .unwrap_or(String::from("standard"));
+                Ok(Event::Challenge { id, variant })
+            }
+            Some("gameStart") => {
+                let game = json.get("game");
+                let id = string(game.and_then(|game| game.get("gameId")).or(game.and_then(|game| game.get("id")))).ok_or("Game without id")?;
+                Ok(Event::GameStart { id })
+            }
+            _other => Ok(Event::Other),
+        }
+    }
+}
+
+/// Returns the value of the "UCI_Variant" option for the given Lichess variant, or None if Ladybug can't play the variant.
+pub fn uci_variant(variant: &str) -> Option<&'static str> {
+    match variant {
+        "standard" | "fromPosition" => Some("chess"),
+        "kingOfTheHill" => Some("kingofthehill"),
+        _other => None,
+    }
+}
+
+/// The state of a game, as sent by the game stream.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct GameState {
+    /// The moves played so far in pure algebraic coordinate notation, separated by spaces.
+    pub moves: String,
+    /// The remaining time of White in milliseconds.
+    pub wtime: u64,
+    /// The remaining time of Black in milliseconds.
+    pub btime: u64,
+    /// The increment of White in milliseconds.
+    pub winc: u64,
+    /// The increment of Black in milliseconds.
+    pub binc: u64,
+    /// The status of the game, which is "started" while the game is running.
+    pub status: String,
+}
+
+impl GameState {
+    /// Reads the game state from the given JSON object.
+    fn from_json(json: &Json) -> GameState {
+        let number = |key: &str| json.get(key).and_then(|value| value.as_u64()).unwrap_or(0);
+        GameState {
+            moves: json.get("moves").and_then(|value| value.as_str()).unwrap_or_default().to_string(),
+            wtime: number("wtime"),
+            btime: number("btime"),
+            winc: number("winc"),
+            binc: number("binc"),
+            status: json.get("status").and_then(|value| value.as_str()).unwrap_or("started").to_string(),
+        }
+    }
+
+    /// Returns whether the game is still running.
+    pub fn is_running(&self) -> bool {
+        self.status == "started" || self.status == "created"
+    }
+}
+
+/// A message of the game stream.
+#[derive(Clone, PartialEq, Debug)]
+pub enum GameEvent {
+    /// The first message of the stream, with the players, the variant, the initial position and the current state.
+    GameFull { white: String, black: String, variant: String, initial_fen: String, state: GameState },
+    /// The state of the game changed, e.g. because a move was played.
+    GameState(GameState),
+    /// Any other message, e.g. a chat line, which the bot ignores.
+    Other,
+}
+
+impl GameEvent {
+    /// Parses a line of the game stream. Empty lines are sent to keep the connection alive, and are parsed as `GameEvent::Other`.
+    pub fn parse(line: &str) -> Result<GameEvent, String> {
+        if line.trim().is_empty() {
+            return Ok(GameEvent::Other);
+        }
+        let json = Json::parse(line)?;
+        match json.get("type").and_then(|value| value.as_str()) {
+            Some("gameFull") => {
+                let player = |color: &str| json.get(color).and_then(|player| player.get("id")).and_then(|id| id.as_str()).unwrap_or_default().to_string();
+                let initial_fen = match json.get("initialFen").and_then(|value| value.as_str()) {
+                    None | Some("startpos") => START_FEN.to_string(),
+                    Some(fen) => fen.to_string(),
+                };
+                Ok(GameEvent::GameFull {
+                    white: player("white"),
+                    black: player("black"),
+                    variant: json.get("variant").and_then(|variant| variant.get("key")).and_then(|key| key.as_str()).unwrap_or("standard").to_string(),
+                    initial_fen,
+                    state: json.get("state").map(GameState::from_json).unwrap_or_default(),
+                })
+            }
+            Some("gameState") => Ok(GameEvent::GameState(GameState::from_json(&json))),
+            _other => Ok(GameEvent::Other),
+        }
+    }
+}
+
+/// Returns the "position" and "go" commands that make the engine search the position of the given game state,
+/// or None if it is not the bot's turn, or the moves are not legal.
+pub fn engine_commands(initial_fen: &str, state: &GameState, bot_is_white: bool) -> Option<[String; 2]> {
+    let mut game = Game::from_fen(initial_fen).ok()?;
+    for move_str in state.moves.split_whitespace() {
+        let ply = game.parse_move(move_str).ok()?;
+        game.make_move(ply).ok()?;
+    }
+    if (game.board().position.color_to_move == Color::White) != bot_is_white {
+        return None;
+    }
+
+    let mut position = format!("position fen {initial_fen}");
+    if !state.moves.trim().is_empty() {
+        position += format!(" moves {}", state.moves.trim()).as_str();
+    }
+    let go = format!("go wtime {} btime {} winc {} binc {}", state.wtime, state.btime, state.winc, state.binc);
+    Some([position, go])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::START_FEN;
+    use crate::lichess::{engine_commands, uci_variant, Event, GameEvent, GameState};
+
+    #[test]
+    fn test_parse_event() {
+        let challenge = r#"{"type":"challenge","challenge":{"id":"7pGLxJ4F","variant":{"key":"kingOfTheHill","name":"King of the Hill"},"speed":"blitz"}}"#;
+        assert_eq!(Ok(Event::Challenge { id: String::from("7pGLxJ4F"), variant: String::from("kingOfTheHill") }), Event::parse(challenge));
+        let game_start = r#"{"type":"gameStart","game":{"gameId":"1lsvP62l","fullId":"1lsvP62lxyz"}}"#;
+        assert_eq!(Ok(Event::GameStart { id: String::from("1lsvP62l") }), Event::parse(game_start));
+        assert_eq!(Ok(Event::Other), Event::parse(""));
+        assert_eq!(Ok(Event::Other), Event::parse(r#"{"type":"challengeDeclined"}"#));
+        assert!(Event::parse(r#"{"type":"challenge"}"#).is_err());
+    }
+
+    #[test]
+    fn test_uci_variant() {
+        assert_eq!(Some("chess"), uci_variant("standard"));
+        assert_eq!(Some("kingofthehill"), uci_variant("kingOfTheHill"));
+        assert_eq!(None, uci_variant("crazyhouse"));
+    }
+
+    #[test]
+    fn test_parse_game_event() {
+        let game_full = r#"{"type":"gameFull","id":"5IrD6Gzz","variant":{"key":"standard"},"white":{"id":"ladybug","name":"Ladybug"},
+            "black":{"id":"human","name":"Human"},"initialFen":"startpos",
+            "state":{"type":"gameState","moves":"e2e4","wtime":60000,"btime":59000,"winc":1000,"binc":1000,"status":"started"}}"#;
+        let GameEvent::GameFull { white, black, variant, initial_fen, state } = GameEvent::parse(game_full).unwrap() else {
+            panic!("expected the full game");
+        };
+        assert_eq!(("ladybug", "human", "standard", START_FEN), (white.as_str(), black.as_str(), variant.as_str(), initial_fen.as_str()));
+        assert_eq!(GameState { moves: String::from("e2e4"), wtime: 60000, btime: 59000, winc: 1000, binc: 1000, status: String::from("started") }, state);
+        assert!(state.is_running());
+
+        let game_state = r#"{"type":"gameState","moves":"e2e4 e7e5","wtime":59000,"btime":58000,"winc":0,"binc":0,"status":"mate"}"#;
+        let GameEvent::GameState(state) = GameEvent::parse(game_state).unwrap() else {
+            panic!("expected the game state");
+        };
+        assert!(!state.is_running());
+        assert_eq!(Ok(GameEvent::Other), GameEvent::parse(r#"{"type":"chatLine","text":"hi"}"#));
+    }
+
+    #[test]
+    fn test_engine_commands() {
+        let state = GameState { moves: String::from("e2e4"), wtime: 60000, btime: 59000, winc: 1000, binc: 2000, status: String::from("started") };
+        assert_eq!(Some([format!("position fen {START_FEN} moves e2e4"), String::from("go wtime 60000 btime 59000 winc 1000 binc 2000")]),
+                   engine_commands(START_FEN, &state, false));
+        // it's Black's turn, and illegal moves are rejected
+        assert_eq!(None, engine_commands(START_FEN, &state, true));
+        let state = GameState { moves: String::from("e2e5"), ..state };
+        assert_eq!(None, engine_commands(START_FEN, &state, false));
+
+        let state = GameState::default();
+        assert_eq!(Some(String::from("position fen 8/8/8/8/8/8/8/K1k5 w - - 0 1")), engine_commands("8/8/8/8/8/8/8/K1k5 w - - 0 1", &state, true).map(|commands| commands[0].clone()));
+    }
+}
@@ -0,0 +1,98 @@
+//! The logger module writes the communication between the GUI and Ladybug to a file, which is set with the "LogFile" option.
+//!
+//! Each line starts with the UTC time, followed by the direction of the message and the message itself:
+//!
+//! ```text
+//! 18:04:12.317 >> go wtime 60000 btime 60000
+//! 18:04:13.902 << bestmove e2e4
+//! 18:04:14.001 !! Unknown command 'xboard'
+//! ```
+//!
+//! Lines starting with ">>" were received from the GUI, lines starting with "<<" were sent to the GUI,
+//! and lines starting with "!!" are warnings that are not sent to the GUI.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Writes the communication with the GUI to a log file.
+#[derive(Debug)]
+pub struct Logger {
+    /// The file the messages are appended to.
+    file: File,
+    /// The path of the file.
+    pub path: String,
+}
+
+impl Logger {
+    /// Opens the given log file. If the file already exists, the messages are appended to it.
+    pub fn open(path: &str) -> Result<Logger, String> {
+        match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Ok(Logger { file, path: path.to_string() }),
+            Err(_) => Err(String::from("Could not open log file")),
+        }
+    }
+
+    /// Logs a message received from the GUI.
+    pub fn input(&self, message: &str) {
+        self.write(">>", message);
+    }
+
+    /// Logs a message sent to the GUI.
+    pub fn output(&self, message: &str) {
+        self.write("<<", message);
+    }
+
+    /// Logs a warning, which is not sent to the GUI.
+    pub fn warning(&self, message: &str) {
+        self.write("!!", message);
+    }
+
+    /// Writes a single line to the log file.
+    /// Errors are ignored, since logging must never interrupt the communication with the GUI.
+    fn write(&self, direction: &str, message: &str) {
+        let _ = writeln!(&self.file, "{} {direction} {}", timestamp(), message.trim_end());
+    }
+}
+
+/// Returns the current UTC time of day, e.g. "18:04:12.317".
+fn timestamp() -> String {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_millis());
+    let seconds = millis / 1000;
+    format!("{:02}:{:02}:{:02}.{:03}", seconds / 3600 % 24, seconds / 60 % 60, seconds % 60, millis % 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::logger::{timestamp, Logger};
+
+    #[test]
+    fn test_log() {
+        let path = std::env::temp_dir().join("ladybug_test_log.txt");
+        let _ = std::fs::remove_file(&path);
+
+        let logger = Logger::open(path.to_str().unwrap()).unwrap();
+        logger.input("uci\n");
+        logger.output("uciok");
+        logger.warning("Unknown command 'xboard'");
+
+        let lines: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(|line| line.to_string()).collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].ends_with(" >> uci"));
+        assert!(lines[1].ends_with(" << uciok"));
+        assert!(lines[2].ends_with(" !! Unknown command 'xboard'"));
+
+        // the messages are appended to an existing log
+        Logger::open(path.to_str().unwrap()).unwrap().input("quit");
+        assert_eq!(4, std::fs::read_to_string(&path).unwrap().lines().count());
+
+        assert!(Logger::open("/this/directory/does/not/exist.log").is_err());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let timestamp = timestamp();
+        assert_eq!(12, timestamp.len());
+        assert_eq!(vec![2, 2, 6], timestamp.split(':').map(|part| part.len()).collect::<Vec<usize>>());
+    }
+}
@@ -1,8 +1,10 @@
 //! The lookup module is responsible for generating and storing the lookup tables used by the move generator.
 //! The submodule `lookup_table` contains a struct to store these tables, while the generation logic
-//! is provided by functions in submodules such as `pawn_attacks` or `knight_attacks`.
+//! is provided by functions in submodules such as `pawn_attacks`, `knight_attacks` or `rays`.
+//!
+//! Since the tables are generated at compile time, `LOOKUP_TABLE` never has to be initialized,
+//! and library users can create positions and generate moves without any setup.
 
-use std::sync::OnceLock;
 use crate::board::bitboard::Bitboard;
 use crate::lookup::lookup_table::LookupTable;
 
@@ -14,23 +16,21 @@ pub mod bishop_occupancies;
 pub mod rook_occupancies;
 pub mod bishop_attacks;
 pub mod rook_attacks;
+pub mod rays;
 
-/// This static `OnceLock` variable will contain the `LookupTable` instance.
-/// Because calculating the lookup table is so expensive, it should only be done once.
-/// 
-/// The `OnceLock` type allows us to use its `set()` method to set the lookup table exactly once, and after that,
-/// the lookup table can be obtained by calling the `get()` method.
-pub static LOOKUP_TABLE: OnceLock<LookupTable> = OnceLock::new();
+/// The lookup table used by the move generator.
+/// Its tables are generated at compile time, so it can be used right away without any initialization.
+pub static LOOKUP_TABLE: LookupTable = LookupTable::new();
 
 // ---------------------------------------------------------------
 // Constants used to mask the attack bitboards for various pieces
 // ---------------------------------------------------------------
 
 /// A bitboard with all bits set to 1, except for those on the A file.
-const NOT_A_FILE: Bitboard = Bitboard { value: 0xfefefefefefefefe };
+pub(crate) const NOT_A_FILE: Bitboard = Bitboard { value: 0xfefefefefefefefe };
 
 /// A bitboard with all bits set to 1, except for those on the H file.
-const NOT_H_FILE: Bitboard = Bitboard { value: 0x7f7f7f7f7f7f7f7f };
+pub(crate) const NOT_H_FILE: Bitboard = Bitboard { value: 0x7f7f7f7f7f7f7f7f };
 
 /// A bitboard with all bits set to 1, except for those on the A and B files.
 const NOT_A_B_FILES: Bitboard = Bitboard { value: 0xfcfcfcfcfcfcfcfc };
@@ -5,16 +5,19 @@ use crate::lookup::lookup_table::LookupTable;
 
 impl LookupTable {
     /// Generates the king attack table.
-    pub(super) fn generate_king_attacks() -> [Bitboard; 64] {
+    pub(super) const fn generate_king_attacks() -> [Bitboard; 64] {
         let mut king_attacks = [Bitboard::new(0); 64];
-        for square_index in 0..NUM_SQUARES {
+        // iterators can't be used in const functions
+        let mut square_index = 0;
+        while square_index < NUM_SQUARES {
             king_attacks[square_index as usize] = Self::get_king_attack_bb(Square::new(square_index));
+            square_index += 1;
         }
         king_attacks
     }
 
     /// Returns the attack bitboard for a king on a specified square.
-    fn get_king_attack_bb(square: Square) -> Bitboard {
+    const fn get_king_attack_bb(square: Square) -> Bitboard {
         let mut attack_bb = Bitboard::new(0); // the result attack bitboard
         let king_bb = Bitboard::from_square(square); // bitboard with the square of the king set
 
@@ -5,17 +5,20 @@ use crate::lookup::lookup_table::LookupTable;
 
 impl LookupTable {
     /// Generates the knight attack table.
-    pub(super) fn generate_knight_attacks() -> [Bitboard; 64] {
+    pub(super) const fn generate_knight_attacks() -> [Bitboard; 64] {
         let mut knight_attacks = [Bitboard::new(0); 64];
-        for square_index in 0..NUM_SQUARES {
+        // iterators can't be used in const functions
+        let mut square_index = 0;
+        while square_index < NUM_SQUARES {
             knight_attacks[square_index as usize] = Self::get_knight_attack_bb(Square::new(square_index));
+            square_index += 1;
         }
         knight_attacks
     }
 
     /// Returns the attack bitboard for a knight on a specified square.
     /// See [Knight Attacks](https://www.chessprogramming.org/Knight_Pattern#Knight_Attacks)
-    fn get_knight_attack_bb(square: Square) -> Bitboard {
+    const fn get_knight_attack_bb(square: Square) -> Bitboard {
         let mut attack_bb = Bitboard::new(0); // the result attack bitboard
         let knight_bb = Bitboard::from_square(square); // bitboard with the square of the knight set
 
@@ -3,31 +3,34 @@ use crate::board::color::Color;
 use crate::board::square::Square;
 
 /// This is the lookup table for the move generator.
+/// The attack tables of the leapers and the ray and between tables are generated at compile time,
+/// while the attacks of the sliders are computed from the blockers on demand.
 #[derive(Debug)]
 pub struct LookupTable {
     pawn_attacks: [[Bitboard; 64]; 2],
     knight_attacks: [Bitboard; 64],
     king_attacks: [Bitboard; 64],
+    rays: [[Bitboard; 64]; 64],
+    between: [[Bitboard; 64]; 64],
 }
 
 impl Default for LookupTable {
-    /// Default constructor for LookupTable.
-    /// Make sure to call `initialize_tables` before using this instance.
     fn default() -> Self {
-        LookupTable {
-            pawn_attacks: [[Bitboard::new(0); 64]; 2],
-            knight_attacks: [Bitboard::new(0); 64],
-            king_attacks: [Bitboard::new(0); 64],
-        }
+        Self::new()
     }
 }
 
 impl LookupTable {
-    /// Initializes the lookup tables for all pieces.
-    pub fn initialize_tables(&mut self) {
-        self.pawn_attacks = Self::generate_pawn_attacks();
-        self.knight_attacks = Self::generate_knight_attacks();
-        self.king_attacks = Self::generate_king_attacks();
+    /// Generates the lookup tables for all pieces.
+    /// Since this is a const function, the tables of `LOOKUP_TABLE` are computed by the compiler.
+    pub const fn new() -> Self {
+        LookupTable {
+            pawn_attacks: Self::generate_pawn_attacks(),
+            knight_attacks: Self::generate_knight_attacks(),
+            king_attacks: Self::generate_king_attacks(),
+            rays: Self::generate_rays(),
+            between: Self::generate_between(),
+        }
     }
 
     /// Returns the attack bitboard for a pawn of the specified color on the specified square.
@@ -59,27 +62,44 @@ impl LookupTable {
     pub fn get_king_attacks(&self, square: Square) -> Bitboard {
         self.king_attacks[square.index as usize]
     }
+
+    /// Returns the bitboard of the squares strictly between the two specified squares, if they share a rank, file or diagonal.
+    /// Otherwise, an empty bitboard is returned.
+    pub fn get_between(&self, square_1: Square, square_2: Square) -> Bitboard {
+        self.between[square_1.index as usize][square_2.index as usize]
+    }
+
+    /// Returns the bitboard of the squares from the first square in the direction of the second square up to the edge of the board,
+    /// without the first square, if they share a rank, file or diagonal. Otherwise, an empty bitboard is returned.
+    /// This is the line a slider on the first square would attack through the second square on an empty board.
+    pub fn get_ray(&self, from: Square, to: Square) -> Bitboard {
+        self.rays[from.index as usize][to.index as usize]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::board::bitboard::Bitboard;
     use crate::board::color::Color::{Black, White};
+    use crate::board::square;
     use crate::board::square::{A5, B2, B5, B7, B8, C2, C3, C4, C7, D4, D8, E4, E5, F4, F7, F8, G2, G6, G7, H1, H5, H7, H8, NUM_SQUARES, Square};
+    use crate::lookup::LOOKUP_TABLE;
     use crate::lookup::lookup_table::LookupTable;
 
     #[test]
-    fn default_returns_lookup_table_with_empty_bitboards() {
-        let lookup_table = LookupTable::default();
-        assert_eq!([[Bitboard::new(0); 64]; 2], lookup_table.pawn_attacks);
-        assert_eq!([Bitboard::new(0); 64], lookup_table.knight_attacks);
-        assert_eq!([Bitboard::new(0); 64], lookup_table.king_attacks);
+    fn lookup_table_is_generated_at_compile_time() {
+        // the tables can be read in a const context, so they don't need to be initialized at runtime
+        const LOOKUP: LookupTable = LookupTable::new();
+        const KNIGHT_ATTACKS: Bitboard = LOOKUP.knight_attacks[0];
+        assert_eq!(0x20400, KNIGHT_ATTACKS.value);
+        assert_eq!(LookupTable::generate_pawn_attacks(), LOOKUP_TABLE.pawn_attacks);
+        assert_eq!(LookupTable::generate_king_attacks(), LOOKUP_TABLE.king_attacks);
+        assert_eq!(LookupTable::generate_between(), LOOKUP_TABLE.between);
     }
 
     #[test]
     fn get_pawn_attacks_returns_bitboard_with_attacked_bits_set() {
-        let mut lookup_table = LookupTable::default();
-        lookup_table.initialize_tables();
+        let lookup_table = LookupTable::new();
 
         // Testing the get_pawn_attacks method using fixed hex values for the result bitboard.
         assert_eq!(0xa0000, lookup_table.get_pawn_attacks(C2, White).value);
@@ -96,8 +116,7 @@ mod tests {
 
     #[test]
     fn get_knight_attacks_returns_bitboard_with_attacked_bits_set() {
-        let mut lookup_table = LookupTable::default();
-        lookup_table.initialize_tables();
+        let lookup_table = LookupTable::new();
 
         // Testing the get_knight_attacks method using fixed hex values for the result bitboard.
         assert_eq!(0xa110011, lookup_table.get_knight_attacks(C2).value);
@@ -111,8 +130,7 @@ mod tests {
 
     #[test]
     fn get_bishop_attacks_returns_bitboard_with_attacked_bits_set() {
-        let mut lookup_table = LookupTable::default();
-        lookup_table.initialize_tables();
+        let lookup_table = LookupTable::new();
 
         // Testing the get_bishop_attacks method using fixed hex values for the result and blocker bitboards.
         assert_eq!(0x8041221400142241, lookup_table.get_bishop_attacks(D4, Bitboard::new(0)).value);
@@ -130,8 +148,7 @@ mod tests {
 
     #[test]
     fn get_rook_attacks_returns_bitboard_with_attacked_bits_set() {
-        let mut lookup_table = LookupTable::default();
-        lookup_table.initialize_tables();
+        let lookup_table = LookupTable::new();
 
         // Testing the get_rook_attacks method using fixed hex values for the result and blocker bitboards.
         assert_eq!(0x101010ef10101010, lookup_table.get_rook_attacks(E5, Bitboard::new(0)).value);
@@ -146,8 +163,7 @@ mod tests {
 
     #[test]
     fn get_queen_attacks_returns_bitboard_with_attacked_bits_set() {
-        let mut lookup_table = LookupTable::default();
-        lookup_table.initialize_tables();
+        let lookup_table = LookupTable::new();
 
         for square_index in 0..NUM_SQUARES {
             // get attacks for square
@@ -173,8 +189,7 @@ mod tests {
 
     #[test]
     fn get_king_attacks_returns_bitboard_with_attacked_bits_set() {
-        let mut lookup_table = LookupTable::default();
-        lookup_table.initialize_tables();
+        let lookup_table = LookupTable::new();
 
         // Testing the get_king_attacks method using fixed hex values for the result bitboard.
         assert_eq!(0xc040c00000000000, lookup_table.get_king_attacks(H7).value);
@@ -183,4 +198,47 @@ mod tests {
         assert_eq!(0x382838000000, lookup_table.get_king_attacks(E5).value);
         assert_eq!(0x70507, lookup_table.get_king_attacks(B2).value);
     }
+
+    #[test]
+    fn test_get_between() {
+        let lookup = LookupTable::new();
+
+        assert_eq!(vec![D4], lookup.get_between(C4, E4).get_active_bits().to_vec());
+        assert_eq!(vec![C3, D4, E5, square::F6], lookup.get_between(B2, G7).get_active_bits().to_vec());
+        assert_eq!(vec![H5, square::H6, H7], lookup.get_between(H8, square::H4).get_active_bits().to_vec());
+        assert_eq!(Bitboard::new(0), lookup.get_between(D4, E5));
+        assert_eq!(Bitboard::new(0), lookup.get_between(C3, D8));
+        assert_eq!(Bitboard::new(0), lookup.get_between(A5, A5));
+    }
+
+    #[test]
+    fn test_get_between_matches_the_slider_attacks() {
+        let lookup = LookupTable::new();
+
+        // the squares between are attacked from both squares by a slider that is only blocked by the other square
+        for square_1 in (0..NUM_SQUARES).map(Square::new) {
+            for square_2 in (0..NUM_SQUARES).map(Square::new).filter(|square_2| *square_2 != square_1) {
+                let expected = Bitboard::new(lookup.get_queen_attacks(square_1, Bitboard::from_square(square_2)).value
+                    & lookup.get_queen_attacks(square_2, Bitboard::from_square(square_1)).value
+                    & (lookup.get_ray(square_1, square_2).value | lookup.get_ray(square_2, square_1).value));
+                assert_eq!(expected, lookup.get_between(square_1, square_2));
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_ray() {
+        let lookup = LookupTable::new();
+
+        assert_eq!(vec![D4, E4, F4, square::G4, square::H4], lookup.get_ray(C4, E4).get_active_bits().to_vec());
+        assert_eq!(vec![square::A1, B2], lookup.get_ray(C3, B2).get_active_bits().to_vec());
+        assert_eq!(vec![C3, D4, E5, square::F6, G7, H8], lookup.get_ray(B2, D4).get_active_bits().to_vec());
+        assert_eq!(vec![square::H1, square::H2, square::H3, square::H4, H5, square::H6, H7], lookup.get_ray(H8, H5).get_active_bits().to_vec());
+        assert_eq!(Bitboard::new(0), lookup.get_ray(C3, D8));
+        assert_eq!(Bitboard::new(0), lookup.get_ray(A5, A5));
+
+        // the rays from both squares towards each other overlap on the squares between them
+        assert_eq!(lookup.get_ray(B2, G7), lookup.get_ray(B2, C3));
+        assert_eq!(lookup.get_between(B2, G7).value, lookup.get_ray(B2, G7).value & lookup.get_ray(G7, B2).value);
+    }
 }
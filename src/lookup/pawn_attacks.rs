@@ -7,17 +7,20 @@ use crate::lookup::lookup_table::LookupTable;
 
 impl LookupTable {
     /// Generates the pawn attack table.
-    pub(super) fn generate_pawn_attacks() -> [[Bitboard; 64]; 2] {
+    pub(super) const fn generate_pawn_attacks() -> [[Bitboard; 64]; 2] {
         let mut pawn_attacks = [[Bitboard::new(0); 64]; 2];
-        for square_index in 0..NUM_SQUARES {
+        // iterators can't be used in const functions
+        let mut square_index = 0;
+        while square_index < NUM_SQUARES {
             pawn_attacks[0][square_index as usize] = Self::get_pawn_attack_bb(Square::new(square_index), White);
             pawn_attacks[1][square_index as usize] = Self::get_pawn_attack_bb(Square::new(square_index), Black);
+            square_index += 1;
         }
         pawn_attacks
     }
 
     /// Returns the attack bitboard for a pawn of a specified color on a specified square.
-    fn get_pawn_attack_bb(square: Square, color: Color) -> Bitboard {
+    const fn get_pawn_attack_bb(square: Square, color: Color) -> Bitboard {
         let mut attack_bb = Bitboard::new(0); // the result attack bitboard
         let pawn_bb = Bitboard::from_square(square); // bitboard with the square of the pawn set
 
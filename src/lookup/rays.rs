@@ -0,0 +1,67 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::square::NUM_SQUARES;
+use crate::lookup::lookup_table::LookupTable;
+
+/// The file and rank steps of the eight directions a queen can move in.
+const DIRECTIONS: [(i8, i8); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+impl LookupTable {
+    /// Generates the ray table, which contains for each pair of squares on a shared rank, file or diagonal
+    /// the squares from the first square in the direction of the second square up to the edge of the board.
+    pub(super) const fn generate_rays() -> [[Bitboard; 64]; 64] {
+        let mut rays = [[Bitboard::new(0); 64]; 64];
+        // iterators can't be used in const functions
+        let mut square_index = 0;
+        while square_index < NUM_SQUARES {
+            let mut direction_index = 0;
+            while direction_index < DIRECTIONS.len() {
+                let ray = Self::get_ray_bb(square_index, DIRECTIONS[direction_index]);
+                // every square on the ray has the same ray
+                let mut target_index = 0;
+                while target_index < NUM_SQUARES {
+                    if ray.value & (1 << target_index) != 0 {
+                        rays[square_index as usize][target_index as usize] = ray;
+                    }
+                    target_index += 1;
+                }
+                direction_index += 1;
+            }
+            square_index += 1;
+        }
+        rays
+    }
+
+    /// Generates the between table, which contains for each pair of squares on a shared rank, file or diagonal
+    /// the squares strictly between them.
+    pub(super) const fn generate_between() -> [[Bitboard; 64]; 64] {
+        let rays = Self::generate_rays();
+        let mut between = [[Bitboard::new(0); 64]; 64];
+        let mut square_index = 0;
+        while square_index < NUM_SQUARES {
+            let mut target_index = 0;
+            while target_index < NUM_SQUARES {
+                // the ray from the target in the opposite direction overlaps the ray from the square exactly between them
+                between[square_index as usize][target_index as usize] = Bitboard::new(
+                    rays[square_index as usize][target_index as usize].value & rays[target_index as usize][square_index as usize].value
+                );
+                target_index += 1;
+            }
+            square_index += 1;
+        }
+        between
+    }
+
+    /// Returns the bitboard of the squares from the specified square in the specified direction up to the edge of the board,
+    /// without the square itself.
+    const fn get_ray_bb(square_index: u8, direction: (i8, i8)) -> Bitboard {
+        let mut ray_bb = Bitboard::new(0);
+        let mut file = (square_index % 8) as i8 + direction.0;
+        let mut rank = (square_index / 8) as i8 + direction.1;
+        while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+            ray_bb.value |= 1 << (rank * 8 + file);
+            file += direction.0;
+            rank += direction.1;
+        }
+        ray_bb
+    }
+}
@@ -1,23 +1,49 @@
 use std::sync::mpsc;
-use std::sync::mpsc::{Receiver, Sender};
-use std::{io, thread};
-use ladybug::ladybug::{Ladybug, Message};
-use ladybug::lookup::LOOKUP_TABLE;
-use ladybug::lookup::lookup_table::LookupTable;
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+#[cfg(not(feature = "async"))]
+use std::io;
+use std::thread;
+use std::path::Path;
+use ladybug::config::CONFIG_FILE;
+use ladybug::evaluation::params::EvalParams;
+use ladybug::evaluation::tuner;
+#[cfg(feature = "async")]
+use ladybug::async_io::AsyncConsole;
+#[cfg(not(feature = "async"))]
+use ladybug::ladybug::read_input;
+use ladybug::ladybug::{Ladybug, Message, MESSAGE_CHANNEL_CAPACITY, OUTPUT_CHANNEL_CAPACITY};
+use ladybug::search::bench;
+use ladybug::search::bench::BENCH_DEPTH;
 use ladybug::search::{Search, SearchCommand};
 
-/// Initializes the lookup table, spawns the input and output threads, and starts running Ladybug.
+/// The maximum number of iterations of the tuner, unless another number is given.
+const DEFAULT_TUNING_ITERATIONS: usize = 100;
+
+/// Spawns the input and output threads, and starts running Ladybug.
+/// If Ladybug is started with the "bench" argument, the bench positions are searched instead, and Ladybug exits afterwards.
+/// With "bench --compare <file>", the bench is compared to the last baseline of the file, and Ladybug exits with an error on deviations.
+/// With "tune <records> <output> [<iterations>]", the evaluation parameters are tuned on the records and written to the output file.
 fn main() {
     println!("\nLadybug 0.5.0\n");
 
-    print!("Initializing tables... ");
-
-    // initialize the lookup table
-    let mut lookup = LookupTable::default();
-    lookup.initialize_tables();
-    let _ = LOOKUP_TABLE.set(lookup);
-
-    println!("Done!");
+    // testing frameworks run "ladybug bench" to verify the identity of the build
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|arg| arg.as_str()) == Some("bench") {
+        let baseline_path = match args.get(2).map(|arg| arg.as_str()) {
+            Some("--compare") => args.get(3).cloned(),
+            _other => None,
+        };
+        if !run_bench(baseline_path) {
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(|arg| arg.as_str()) == Some("tune") {
+        if !run_tuner(&args[2..]) {
+            std::process::exit(1);
+        }
+        return;
+    }
 
     println!("Type \"help\" to see a list of all commands.\n");
 
@@ -25,55 +51,160 @@ fn main() {
     let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
 
     // create message_sender and message_receiver so that the input and search threads can send messages to the ladybug thread
-    let (message_sender, message_receiver) : (Sender<Message>, Receiver<Message>) = mpsc::channel();
+    let (message_sender, message_receiver) : (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
 
     // create output_sender and output_receiver so that the ladybug thread can send output to the output thread.
-    let (output_sender, output_receiver) : (Sender<String>, Receiver<String>) = mpsc::channel();
+    let (output_sender, output_receiver) : (SyncSender<String>, Receiver<String>) = mpsc::sync_channel(OUTPUT_CHANNEL_CAPACITY);
     
     // make a copy of the message_sender for the input thread
     let message_sender_copy = message_sender.clone();
-    
+
+    // with the "async" feature, the input and output are handled by tokio tasks instead of the input and output threads
+    #[cfg(feature = "async")]
+    let console = match AsyncConsole::start(message_sender_copy, output_receiver) {
+        Ok(console) => console,
+        Err(error) => {
+            println!("Could not start the async runtime: {error}");
+            std::process::exit(1);
+        }
+    };
+
     // spawn the input thread
-    let _ = thread::Builder::new().name("console_in".to_string()).spawn(move || read_input(message_sender_copy));
+    #[cfg(not(feature = "async"))]
+    let _ = thread::Builder::new().name("console_in".to_string()).spawn(move || read_input(io::stdin().lock(), message_sender_copy));
 
     // spawn the output thread
-    let _ = thread::Builder::new().name("console_out".to_string()).spawn(move || write_output(output_receiver));
+    #[cfg(not(feature = "async"))]
+    let output_thread = thread::Builder::new().name("console_out".to_string()).spawn(move || write_output(output_receiver));
     
     // initialize the search
     let mut search = Search::new(search_command_receiver, message_sender);
+    let stop_flag = search.stop_flag();
+    let ponder_flag = search.ponder_flag();
     
     // spawn the search thread
-    let _ = thread::Builder::new().name("search".to_string()).spawn(move || search.run());
+    let search_thread = thread::Builder::new().name("search".to_string()).spawn(move || search.run());
 
     // initialize Ladybug
-    let mut ladybug = Ladybug::new(search_command_sender, output_sender, message_receiver);
+    let mut ladybug = Ladybug::new(search_command_sender, output_sender, message_receiver, stop_flag, ponder_flag);
+
+    // the option values saved with "saveconfig" are loaded, so that they don't have to be set again in every session
+    if Path::new(CONFIG_FILE).exists() {
+        ladybug.load_config(CONFIG_FILE);
+    }
     
     // start running Ladybug
     ladybug.run();
+
+    // the search thread terminates after the current search, so that a running match can shut down its engine
+    // dropping Ladybug closes the message channel, so that the search thread can't block on a full message queue
+    drop(ladybug);
+    if let Ok(search_thread) = search_thread {
+        let _ = search_thread.join();
+    }
+
+    // the output thread terminates after printing the remaining output, which has to be printed before the process exits
+    #[cfg(not(feature = "async"))]
+    if let Ok(output_thread) = output_thread {
+        let _ = output_thread.join();
+    }
+    #[cfg(feature = "async")]
+    console.join();
 }
 
-/// Reads input from Stdin and sends it to Ladybug.
-pub fn read_input(sender: Sender<Message>) {
-    loop {
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).expect("Failed to read line");
-
-        // try to send the input to Ladybug
-        let result = sender.send(Message::ConsoleMessage(input));
-        
-        if result.is_err() {
-            // the Ladybug thread was terminated, terminate the input thread
-            return;
+/// Searches the bench positions and prints the results.
+/// If the path of a baseline file is given, the results are compared to the last baseline of the file.
+/// Returns false if the baseline file can't be read or the results deviate from the baseline.
+fn run_bench(baseline_path: Option<String>) -> bool {
+    let baseline = match baseline_path.map(|path| bench::read_baselines(path.as_str())) {
+        None => None,
+        Some(Ok(mut baselines)) => match baselines.pop() {
+            Some(baseline) => Some(baseline),
+            None => {
+                println!("No bench baseline found");
+                return false;
+            }
+        },
+        Some(Err(message)) => {
+            println!("{message}");
+            return false;
+        }
+    };
+
+    let (_search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+    let (message_sender, message_receiver) : (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+
+    // print the search output while the bench is running
+    let output_thread = thread::Builder::new().name("console_out".to_string()).spawn(move || {
+        while let Ok(Message::SearchMessage(output)) = message_receiver.recv() {
+            println!("{}", output);
+        }
+    });
+
+    let mut search = Search::new(search_command_receiver, message_sender);
+    let matches_baseline = match baseline {
+        None => {
+            search.bench(BENCH_DEPTH);
+            true
+        }
+        Some(baseline) => search.bench_compare(&baseline),
+    };
+
+    // closing the channel terminates the output thread once all output is printed
+    drop(search);
+    if let Ok(output_thread) = output_thread {
+        let _ = output_thread.join();
+    }
+
+    matches_baseline
+}
+
+/// Tunes the evaluation parameters on the records of the given file and writes them to the output file.
+/// The arguments are the record file, the output file and optionally the maximum number of iterations.
+/// Returns false if the arguments are invalid, or the files can't be read or written.
+fn run_tuner(args: &[String]) -> bool {
+    let (records_path, output_path, iterations) = match args {
+        [records_path, output_path] => (records_path, output_path, Ok(DEFAULT_TUNING_ITERATIONS)),
+        [records_path, output_path, iterations] => (records_path, output_path, iterations.parse::<usize>()),
+        _other => {
+            println!("Usage: ladybug tune <records> <output> [<iterations>]");
+            return false;
         }
+    };
+    let Ok(iterations) = iterations else {
+        println!("Invalid number of iterations");
+        return false;
+    };
+
+    let records = match tuner::load_records(records_path) {
+        Ok(records) => records,
+        Err(message) => {
+            println!("{message}");
+            return false;
+        }
+    };
+    println!("Loaded {} tuning records", records.len());
+
+    let params = tuner::tune(&records, &EvalParams::DEFAULT, iterations, |iteration, error| {
+        println!("Iteration {iteration}: error {error:.6}");
+    });
+
+    if std::fs::write(output_path, params.to_toml()).is_err() {
+        println!("Could not write the tuned parameters to {output_path}");
+        return false;
     }
+    println!("Wrote the tuned parameters to {output_path}");
+    true
 }
 
 /// Receives output from Ladybug and writes it to Stdout.
+#[cfg(not(feature = "async"))]
 pub fn write_output(receiver: Receiver<String>) {
     loop {
-        let output = receiver.recv().unwrap();
-        
-        // if the output thread receives "quit", terminate it
+        // if Ladybug closes the connection or sends "quit", terminate the output thread
+        let Ok(output) = receiver.recv() else {
+            return;
+        };
         if output == "quit" {
             return;
         }
@@ -1,6 +1,7 @@
 //! The move_gen module is responsible for the move generation logic.
 
 use crate::board::position::Position;
+use crate::move_gen::evasions::generate_evasions;
 use crate::move_gen::leaper_moves::generate_leaper_moves;
 use crate::move_gen::move_list::MoveList;
 use crate::move_gen::pawn_moves::generate_pawn_moves;
@@ -8,12 +9,19 @@ use crate::move_gen::slider_moves::generate_slider_moves;
 
 pub mod ply;
 pub mod move_list;
+pub mod perft;
 mod pawn_moves;
 mod slider_moves;
 mod leaper_moves;
+mod evasions;
 
 /// Generates all legal moves for the given position.
+/// If the color to move is in check, only the moves that resolve the check are generated (see `generate_evasions`).
 pub fn generate_moves(position: Position) -> MoveList {
+    if position.is_in_check(position.color_to_move) {
+        return generate_evasions(position);
+    }
+
     let mut move_list = MoveList::default();
     generate_pawn_moves(position, &mut move_list);
     generate_leaper_moves(position, &mut move_list);
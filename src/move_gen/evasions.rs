@@ -0,0 +1,183 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::color::Color;
+use crate::board::piece::Piece;
+use crate::board::position::Position;
+use crate::board::square::Square;
+use crate::lookup::LOOKUP_TABLE;
+use crate::move_gen::move_list::MoveList;
+use crate::move_gen::ply::Ply;
+
+/// Generates all legal moves for the given position, in which the color to move is in check.
+///
+/// Only moves that can resolve the check are considered: king moves, captures of the checking piece,
+/// and moves that block the line between a checking slider and the king.
+/// In double check, only king moves are possible.
+pub fn generate_evasions(position: Position) -> MoveList {
+    let mut move_list = MoveList::default();
+
+    // get a reference to the lookup table
+    let lookup = &LOOKUP_TABLE;
+
+    let color = position.color_to_move;
+    let king_square = position.piece_bb(color, Piece::King).get_active_bits()[0];
+    let enemy_occupancy = position.get_occupancy(color.other());
+
+    // the king can move to any square that is not occupied by a friendly piece
+    let king_targets = lookup.get_king_attacks(king_square).value & !position.get_occupancy(color).value;
+    for target in Bitboard::new(king_targets).get_active_bits() {
+        push_if_legal(position, Ply::new(king_square, target, Piece::King, None, enemy_occupancy.get_bit(target)), &mut move_list);
+    }
+
+    // in double check, the king has to move
+    let checkers = position.checkers();
+    if checkers.get_num_active_bits() > 1 {
+        return move_list;
+    }
+
+    // the other pieces can only capture the checking piece or block the check
+    let checker_square = checkers.get_active_bits()[0];
+    let target_mask = Bitboard::new(checkers.value | lookup.get_between(king_square, checker_square).value);
+
+    generate_pawn_evasions(position, checker_square, target_mask, &mut move_list);
+
+    let occupancies = position.get_occupancies();
+    for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        for source in position.piece_bb(color, piece).get_active_bits() {
+            let attack_bb = match piece {
+                Piece::Knight => lookup.get_knight_attacks(source),
+                Piece::Bishop => lookup.get_bishop_attacks(source, occupancies),
+                Piece::Rook => lookup.get_rook_attacks(source, occupancies),
+                _queen => lookup.get_queen_attacks(source, occupancies),
+            };
+            for target in Bitboard::new(attack_bb.value & target_mask.value).get_active_bits() {
+                push_if_legal(position, Ply::new(source, target, piece, None, target == checker_square), &mut move_list);
+            }
+        }
+    }
+
+    move_list
+}
+
+/// Generates all pawn moves that capture the checking piece or block the check.
+fn generate_pawn_evasions(position: Position, checker_square: Square, target_mask: Bitboard, move_list: &mut MoveList) {
+    // get a reference to the lookup table
+    let lookup = &LOOKUP_TABLE;
+
+    let color = position.color_to_move;
+    let occupancies = position.get_occupancies();
+
+    for source in position.piece_bb(color, Piece::Pawn).get_active_bits() {
+        // captures of the checking piece
+        if lookup.get_pawn_attacks(source, color).get_bit(checker_square) {
+            push_pawn_move_if_legal(position, source, checker_square, true, move_list);
+        }
+
+        // en passant captures of the checking pawn, or en passant moves that block the check
+        if let Some(en_passant) = position.en_passant {
+            let captured_square = match color {
+                Color::White => en_passant.try_down(),
+                Color::Black => en_passant.try_up(),
+            };
+            if lookup.get_pawn_attacks(source, color).get_bit(en_passant) && (captured_square == Some(checker_square) || target_mask.get_bit(en_passant)) {
+                push_if_legal(position, Ply::new(source, en_passant, Piece::Pawn, None, true), move_list);
+            }
+        }
+
+        // pushes that block the check
+        let Some(single_push_target) = (match color {
+            Color::White => source.try_up(),
+            Color::Black => source.try_down(),
+        }) else {
+            continue;
+        };
+        if occupancies.get_bit(single_push_target) {
+            continue;
+        }
+        if target_mask.get_bit(single_push_target) {
+            push_pawn_move_if_legal(position, source, single_push_target, false, move_list);
+        }
+        if source.get_rank() == color.pawn_rank() {
+            let double_push_target = match color {
+                Color::White => single_push_target.try_up(),
+                Color::Black => single_push_target.try_down(),
+            };
+            if let Some(double_push_target) = double_push_target.filter(|square| !occupancies.get_bit(*square) && target_mask.get_bit(*square)) {
+                push_if_legal(position, Ply::new(source, double_push_target, Piece::Pawn, None, false), move_list);
+            }
+        }
+    }
+}
+
+/// Adds the pawn move to the move list if it is legal. Moves to the promotion rank are added once for each promotion piece.
+fn push_pawn_move_if_legal(position: Position, source: Square, target: Square, is_capture: bool, move_list: &mut MoveList) {
+    if target.get_rank() == position.color_to_move.promotion_rank() {
+        for promotion_piece in position.variant.promotion_pieces() {
+            push_if_legal(position, Ply::new(source, target, Piece::Pawn, Some(*promotion_piece), is_capture), move_list);
+        }
+    } else {
+        push_if_legal(position, Ply::new(source, target, Piece::Pawn, None, is_capture), move_list);
+    }
+}
+
+/// Adds the ply to the move list if it does not leave the king in check.
+fn push_if_legal(position: Position, ply: Ply, move_list: &mut MoveList) {
+    if position.make_move(ply).is_legal() {
+        move_list.push(ply);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::move_gen::evasions::generate_evasions;
+    use crate::move_gen::leaper_moves::generate_leaper_moves;
+    use crate::move_gen::move_list::MoveList;
+    use crate::move_gen::pawn_moves::generate_pawn_moves;
+    use crate::move_gen::ply::Ply;
+    use crate::move_gen::slider_moves::generate_slider_moves;
+
+    #[test]
+    fn test_generate_evasions_matches_full_move_generation() {
+        let fens = [
+            // checks by every piece type
+            "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+            "4k3/8/8/8/8/3n4/1B6/R3K2R w KQ - 0 1",
+            "4k3/8/8/8/1b6/8/3PN3/R2QK2R w KQ - 0 1",
+            "4k3/8/8/8/8/8/3Pr3/R2QK2R w KQ - 0 1",
+            "4k3/8/8/8/8/8/5p2/R2QK2R w KQ - 0 1",
+            "3qk3/8/8/8/8/8/8/R2QK2r w Q - 0 1",
+            // double check
+            "4r1k1/8/8/8/8/3n4/8/4K3 w - - 0 1",
+            // check that can be blocked by a promotion, and a pinned blocker
+            "1r5k/P7/8/8/8/8/8/K7 w - - 0 1",
+            "4k3/8/8/8/b7/8/2R5/r3K3 w - - 0 1",
+            // the checking pawn can be captured en passant
+            "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+            "8/8/8/8/k2Pp2Q/8/8/4K3 b - d3 0 1",
+            // kiwipete variations
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "r3k2r/p1pNqpb1/bn2pnp1/3P4/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+        ];
+
+        for fen in fens {
+            let position = Board::from_fen(fen).unwrap().position;
+            if !position.is_in_check(position.color_to_move) {
+                continue;
+            }
+
+            let mut full_move_list = MoveList::default();
+            generate_pawn_moves(position, &mut full_move_list);
+            generate_leaper_moves(position, &mut full_move_list);
+            generate_slider_moves(position, &mut full_move_list);
+            let mut expected: Vec<Ply> = (0..full_move_list.len()).map(|i| full_move_list.get(i)).collect();
+
+            let evasions = generate_evasions(position);
+            let mut actual: Vec<Ply> = (0..evasions.len()).map(|i| evasions.get(i)).collect();
+
+            expected.sort_by_key(|ply| ply.encode());
+            actual.sort_by_key(|ply| ply.encode());
+            assert_eq!(expected, actual, "{fen}");
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::board::castling_rights::CastlingRights;
+use crate::board::castling_rights::CastlingSide;
 use crate::board::color::{Color};
 use crate::board::file::File;
 use crate::board::piece::Piece;
@@ -19,10 +19,10 @@ pub fn generate_leaper_moves(position: Position, move_list: &mut MoveList) {
 /// Generates all legal leaper moves (knights and kings) for a given leaper piece type in the given position.
 fn generate_leaper_moves_by_piece(position: Position, piece: Piece, move_list: &mut MoveList) {
     // get a reference to the lookup table
-    let lookup = LOOKUP_TABLE.get().unwrap();
+    let lookup = &LOOKUP_TABLE;
 
     // get all squares with the piece type on it
-    let active_squares = position.pieces[position.color_to_move.to_index() as usize][piece.to_index() as usize].get_active_bits();
+    let active_squares = position.piece_bb(position.color_to_move, piece).get_active_bits();
 
     // loop over squares and calculate possible moves
     for source_square in active_squares {
@@ -47,7 +47,7 @@ fn generate_leaper_moves_by_piece(position: Position, piece: Piece, move_list: &
             // get the type of the attacked piece
             let attacked_piece = position.get_piece(target_square).map(|(piece, _color)| piece);
 
-            let ply = Ply { source: source_square, target: target_square, piece: piece, captured_piece: attacked_piece, promotion_piece: None };
+            let ply = Ply::new(source_square, target_square, piece, None, attacked_piece.is_some());
             if position.make_move(ply).is_legal() {
                 move_list.push(ply);
             }
@@ -57,10 +57,10 @@ fn generate_leaper_moves_by_piece(position: Position, piece: Piece, move_list: &
 
 /// Generates all legal castling moves for the given position.
 fn generate_castling_moves(position: Position, move_list: &mut MoveList) {
-    let castling_rights = position.castling_rights[position.color_to_move as usize];
+    let castling_rights = position.castling_rights;
 
     // the color to move has no castling rights - return empty list
-    if castling_rights == CastlingRights::NoRights {
+    if !castling_rights.allows_any(position.color_to_move) {
         return;
     }
 
@@ -82,27 +82,27 @@ fn generate_castling_moves(position: Position, move_list: &mut MoveList) {
     let occupancies = position.get_occupancies();
 
     // queenside
-    if (castling_rights == CastlingRights::QueenSide || castling_rights == CastlingRights::Both) // color to move has castling rights for queenside
+    if castling_rights.allows(position.color_to_move, CastlingSide::QueenSide) // color to move has castling rights for queenside
         && !occupancies.get_bit(Square::from_file_rank(File::B, rank))  // B file square is unoccupied
         && !occupancies.get_bit(Square::from_file_rank(File::C, rank)) // C file square is unoccupied
         && !occupancies.get_bit(Square::from_file_rank(File::D, rank)) // D file square is unoccupied
         && !attack_bb.get_bit(Square::from_file_rank(File::C, rank))  // C file square is not attacked
         && !attack_bb.get_bit(Square::from_file_rank(File::D, rank)) // D file square is not attacked
-        && position.pieces[position.color_to_move.to_index() as usize][Piece::Rook.to_index() as usize].get_bit(Square::from_file_rank(File::A, rank)) // Rook is still on A file square
+        && position.piece_bb(position.color_to_move, Piece::Rook).get_bit(Square::from_file_rank(File::A, rank)) // Rook is still on A file square
     {
-        let ply = Ply { source: Square::from_file_rank(File::E, rank), target: Square::from_file_rank(File::C, rank), piece: Piece::King, captured_piece: None, promotion_piece: None };
+        let ply = Ply::new(Square::from_file_rank(File::E, rank), Square::from_file_rank(File::C, rank), Piece::King, None, false);
         move_list.push(ply);
     }
 
     // kingside
-    if (castling_rights == CastlingRights::KingSide || castling_rights == CastlingRights::Both) // color to move has castling rights for kingside
+    if castling_rights.allows(position.color_to_move, CastlingSide::KingSide) // color to move has castling rights for kingside
         && !occupancies.get_bit(Square::from_file_rank(File::F, rank))  // F file square is unoccupied
         && !occupancies.get_bit(Square::from_file_rank(File::G, rank)) // G file square is unoccupied
         && !attack_bb.get_bit(Square::from_file_rank(File::F, rank))  // F file square is not attacked
         && !attack_bb.get_bit(Square::from_file_rank(File::G, rank)) // G file square is not attacked
-        && position.pieces[position.color_to_move.to_index() as usize][Piece::Rook.to_index() as usize].get_bit(Square::from_file_rank(File::H, rank)) // Rook is still on H file square
+        && position.piece_bb(position.color_to_move, Piece::Rook).get_bit(Square::from_file_rank(File::H, rank)) // Rook is still on H file square
     {
-        let ply = Ply { source: Square::from_file_rank(File::E, rank), target: Square::from_file_rank(File::G, rank), piece: Piece::King, captured_piece: None, promotion_piece: None };
+        let ply = Ply::new(Square::from_file_rank(File::E, rank), Square::from_file_rank(File::G, rank), Piece::King, None, false);
         move_list.push(ply);
     }
 }
@@ -111,17 +111,11 @@ fn generate_castling_moves(position: Position, move_list: &mut MoveList) {
 mod tests {
     use crate::board::Board;
     use crate::board::piece::Piece;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
     use crate::move_gen::leaper_moves::{generate_castling_moves, generate_leaper_moves, generate_leaper_moves_by_piece};
     use crate::move_gen::move_list::MoveList;
 
     #[test]
     fn test_generate_leaper_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -195,10 +189,6 @@ mod tests {
 
     #[test]
     fn test_generate_leaper_moves_by_piece_for_knights() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -272,10 +262,6 @@ mod tests {
 
     #[test]
     fn test_generate_leaper_moves_by_piece_for_kings() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -349,10 +335,6 @@ mod tests {
 
     #[test]
     fn test_generate_castling_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -1,5 +1,6 @@
 use std::cmp::Reverse;
 use arrayvec::ArrayVec;
+use crate::board::position::Position;
 use crate::move_gen::ply::Ply;
 use crate::search::SearchInfo;
 
@@ -41,17 +42,21 @@ impl MoveList {
     }
     
     /// Sorts the move list by MVV-LVA and various other heuristics.
-    pub fn sort(&mut self, search_info: &mut SearchInfo, ply_index: u64) {
+    /// The position is required to look up the captured pieces, since they are not part of the ply.
+    /// The hash move is the best move stored in the transposition table for the position, if any, which is searched right after the pv move.
+    pub fn sort(&mut self, position: &Position, search_info: &mut SearchInfo, ply_index: u64, hash_move: Option<Ply>) {
         // flag to signal whether the pv move of the last search iteration is contained in this move list
         let mut contains_pv = false;
         
-        self.moves.sort_by_key(|encoded_ply| {
+        // score every move once, instead of every time it is compared
+        let mut scored_moves: ArrayVec<(i32, u32), 255> = ArrayVec::new();
+        for encoded_ply in &self.moves {
             // score the move based on MVV-LVA
             let ply = Ply::decode(*encoded_ply);
-            let mut score = ply.score();
+            let mut score = ply.score(position);
 
             // check if move the move is quiet, if yes, apply move ordering heuristics
-            if ply.captured_piece.is_none() {
+            if !ply.is_capture() {
                 // first killer move
                 if search_info.killer_moves[0][ply_index as usize] == ply {
                     score += 70;
@@ -62,18 +67,28 @@ impl MoveList {
                 } 
                 // history move
                 else {
-                    score += search_info.history_moves[ply.piece.to_index() as usize][ply.target.index as usize];
+                    score += search_info.history_moves[ply.piece().to_index() as usize][ply.target().index as usize];
                 }
             }
             
+            // the hash move was the best move when the position was searched before
+            if hash_move == Some(ply) {
+                score += 500_000;
+            }
+
             // check if we are following the pv line
             if search_info.follow_pv && ply == search_info.pv_table[0][ply_index as usize] {
                 contains_pv = true;
                 score += 1_000_000;
             }
 
-            Reverse(score)
-        });
+            scored_moves.push((score, *encoded_ply));
+        }
+        
+        scored_moves.sort_by_key(|(score, _encoded_ply)| Reverse(*score));
+        for (encoded_ply, (_score, sorted_ply)) in self.moves.iter_mut().zip(scored_moves) {
+            *encoded_ply = sorted_ply;
+        }
         
         // If the move list does not contain the pv move from the last iteration, we are no longer following the pv line
         if !contains_pv {
@@ -86,7 +101,7 @@ impl MoveList {
         let mut capture_list = MoveList::default();
 
         for ply in &self.moves {
-            if Ply::decode(*ply).captured_piece.is_some() {
+            if Ply::decode(*ply).is_capture() {
                 capture_list.moves.push(*ply);
             }
         }
@@ -97,6 +112,7 @@ impl MoveList {
 
 #[cfg(test)]
 mod tests {
+    use crate::board::Board;
     use crate::board::piece::Piece;
     use crate::board::square;
     use crate::move_gen::move_list::MoveList;
@@ -105,11 +121,11 @@ mod tests {
 
     #[test]
     fn test_move_list() {
-        let ply1 = Ply {source: square::A1, target: square::A2, piece: Piece::Rook, captured_piece: None, promotion_piece: None};
-        let ply2 = Ply {source: square::H8, target: square::A8, piece: Piece::Rook, captured_piece: Some(Piece::Rook), promotion_piece: None};
-        let ply3 = Ply {source: square::E4, target: square::D5, piece: Piece::Pawn, captured_piece: Some(Piece::Pawn), promotion_piece: None};
-        let ply4 = Ply {source: square::G7, target: square::H8, piece: Piece::Pawn, captured_piece: Some(Piece::Queen), promotion_piece: Some(Piece::Knight)};
-        let ply5 = Ply {source: square::H3, target: square::C8, piece: Piece::Bishop, captured_piece: Some(Piece::Rook), promotion_piece: None};
+        let ply1 = Ply::new(square::A1, square::A2, Piece::Rook, None, false);
+        let ply2 = Ply::new(square::A1, square::A8, Piece::Rook, None, true);
+        let ply3 = Ply::new(square::E4, square::D5, Piece::Pawn, None, true);
+        let ply4 = Ply::new(square::G7, square::H8, Piece::Pawn, Some(Piece::Knight), true);
+        let ply5 = Ply::new(square::H3, square::C8, Piece::Bishop, None, true);
         
         let mut move_list = MoveList::default();
         assert_eq!(0, move_list.len());
@@ -136,7 +152,7 @@ mod tests {
         
         let mut move_list = MoveList::default();
         for _i in 0..255 {
-            move_list.push(Ply {source: square::G7, target: square::H8, piece: Piece::Pawn, captured_piece: Some(Piece::Queen), promotion_piece: Some(Piece::Knight)});
+            move_list.push(Ply::new(square::G7, square::H8, Piece::Pawn, Some(Piece::Knight), true));
         }
         assert!(!move_list.is_empty());
         assert_eq!(255, move_list.len());
@@ -145,12 +161,13 @@ mod tests {
     #[test]
     fn test_sort() {
         let mut search_info = SearchInfo::default();
+        let position = Board::from_fen("r1r1k2q/6P1/8/3p4/4P3/7B/8/R3K3 w - - 0 1").unwrap().position;
         
-        let ply1 = Ply {source: square::A1, target: square::A2, piece: Piece::Rook, captured_piece: None, promotion_piece: None};
-        let ply2 = Ply {source: square::H8, target: square::A8, piece: Piece::Rook, captured_piece: Some(Piece::Rook), promotion_piece: None};
-        let ply3 = Ply {source: square::E4, target: square::D5, piece: Piece::Pawn, captured_piece: Some(Piece::Pawn), promotion_piece: None};
-        let ply4 = Ply {source: square::G7, target: square::H8, piece: Piece::Pawn, captured_piece: Some(Piece::Queen), promotion_piece: Some(Piece::Knight)};
-        let ply5 = Ply {source: square::H3, target: square::C8, piece: Piece::Bishop, captured_piece: Some(Piece::Rook), promotion_piece: None};
+        let ply1 = Ply::new(square::A1, square::A2, Piece::Rook, None, false);
+        let ply2 = Ply::new(square::A1, square::A8, Piece::Rook, None, true);
+        let ply3 = Ply::new(square::E4, square::D5, Piece::Pawn, None, true);
+        let ply4 = Ply::new(square::G7, square::H8, Piece::Pawn, Some(Piece::Knight), true);
+        let ply5 = Ply::new(square::H3, square::C8, Piece::Bishop, None, true);
         
         let mut move_list = MoveList::default();
         
@@ -162,7 +179,7 @@ mod tests {
         
         assert_eq!(5, move_list.len());
         
-        move_list.sort(&mut search_info, 0);
+        move_list.sort(&position, &mut search_info, 0, None);
 
         assert_eq!(5, move_list.len());
         
@@ -175,11 +192,11 @@ mod tests {
     
     #[test]
     fn test_get_captures() {
-        let ply1 = Ply {source: square::A1, target: square::A2, piece: Piece::Rook, captured_piece: None, promotion_piece: None};
-        let ply2 = Ply {source: square::H8, target: square::A8, piece: Piece::Rook, captured_piece: Some(Piece::Rook), promotion_piece: None};
-        let ply3 = Ply {source: square::E4, target: square::D5, piece: Piece::Pawn, captured_piece: Some(Piece::Pawn), promotion_piece: None};
-        let ply4 = Ply {source: square::G7, target: square::H8, piece: Piece::Pawn, captured_piece: Some(Piece::Queen), promotion_piece: Some(Piece::Knight)};
-        let ply5 = Ply {source: square::H3, target: square::C8, piece: Piece::Bishop, captured_piece: Some(Piece::Rook), promotion_piece: None};
+        let ply1 = Ply::new(square::A1, square::A2, Piece::Rook, None, false);
+        let ply2 = Ply::new(square::A1, square::A8, Piece::Rook, None, true);
+        let ply3 = Ply::new(square::E4, square::D5, Piece::Pawn, None, true);
+        let ply4 = Ply::new(square::G7, square::H8, Piece::Pawn, Some(Piece::Knight), true);
+        let ply5 = Ply::new(square::H3, square::C8, Piece::Bishop, None, true);
 
         let mut move_list = MoveList::default();
 
@@ -1,9 +1,9 @@
+use crate::board::bitboard::Bitboard;
 use crate::board::color::Color;
 use crate::board::file::File;
 use crate::board::piece::Piece;
 use crate::board::position::Position;
 use crate::board::square::Square;
-use crate::lookup::LOOKUP_TABLE;
 use crate::move_gen::move_list::MoveList;
 use crate::move_gen::ply::Ply;
 
@@ -15,108 +15,113 @@ pub fn generate_pawn_moves(position: Position, move_list: &mut MoveList)  {
 }
 
 /// Generates all legal quiet pawn moves for the given position.
+///
+/// The push targets of all pawns are computed at once by shifting the pawn bitboard,
+/// so only the squares the pawns can actually move to are visited.
 fn generate_quiet_pawn_moves(position: Position, move_list: &mut MoveList) {
-    // get occupancies
-    let occupancies = position.get_occupancies();
-
-    // get pawn bitboard for the color to move
-    let pawn_bb = position.pieces[position.color_to_move.to_index() as usize][Piece::Pawn.to_index() as usize];
-
-    // get all squares with a pawn on it
-    let active_squares = pawn_bb.get_active_bits();
-
-    // loop over squares and calculate possible moves
-    for source in active_squares {
-        let target = match position.color_to_move {
-            Color::White => source.up(),
-            Color::Black => source.down(),
+    // the color to move
+    let color = position.color_to_move;
+
+    // get empty squares
+    let empty = !position.get_occupancies().value;
+
+    // moves the given bitboard one rank forward for the color to move
+    let forward = |bitboard: Bitboard| match color {
+        Color::White => bitboard.shift_up(),
+        Color::Black => bitboard.shift_down(),
+    };
+
+    // every pawn can move to the empty square in front of it, and from there to the next one if it started on its pawn rank
+    let single_pushes = forward(position.piece_bb(color, Piece::Pawn)).value & empty;
+    let double_pushes = Bitboard::new(forward(Bitboard::new(single_pushes)).value & empty & Bitboard::from_rank(color.double_pawn_push_target_rank()).value);
+
+    // the targets are visited in the order of their pawns, so that each double push directly follows the single push
+    for target in Bitboard::new(single_pushes).get_active_bits() {
+        let source = match color {
+            Color::White => Square::new(target.index - 8),
+            Color::Black => Square::new(target.index + 8),
         };
 
-        // check if target square is empty
-        if occupancies.get_bit(target) {
-            continue;
-        }
-
         // check if target square is on the promotion rank
-        if target.get_rank() == position.color_to_move.promotion_rank() {
+        if target.get_rank() == color.promotion_rank() {
             // move is a promotion - add all possible promotion moves
-            for piece_index in Piece::Knight.to_index() as usize..Piece::Queen.to_index() as usize + 1 {
-                let ply = Ply { source, target, piece: Piece::Pawn, captured_piece: None, promotion_piece: Some(Piece::from_index(piece_index as u8))};
+            for promotion_piece in position.variant.promotion_pieces() {
+                let ply = Ply::new(source, target, Piece::Pawn, Some(*promotion_piece), false);
                 if position.make_move(ply).is_legal() {
                     move_list.push(ply);
                 }
             }
-        } else {
-            // move is not a promotion
-            let ply = Ply { source, target, piece: Piece::Pawn, captured_piece: None, promotion_piece: None};
+            continue;
+        }
+
+        let ply = Ply::new(source, target, Piece::Pawn, None, false);
+        if position.make_move(ply).is_legal() {
+            move_list.push(ply);
+        }
+
+        // the target is not on the promotion rank, so the square beyond it is on the board
+        let double_pawn_push_target = match color {
+            Color::White => Square::new(target.index + 8),
+            Color::Black => Square::new(target.index - 8),
+        };
+        if double_pushes.get_bit(double_pawn_push_target) {
+            let ply = Ply::new(source, double_pawn_push_target, Piece::Pawn, None, false);
             if position.make_move(ply).is_legal() {
                 move_list.push(ply);
             }
-
-            // check if double pawn push is possible
-            if source.get_rank() == position.color_to_move.pawn_rank() {
-                let mut double_pawn_push_target = target;
-                match position.color_to_move {
-                    Color::White => double_pawn_push_target = double_pawn_push_target.up(),
-                    Color::Black => double_pawn_push_target = double_pawn_push_target.down(),
-                }
-                if !occupancies.get_bit(double_pawn_push_target) {
-                    // no piece on double pawn push target square, so double pawn move is possible
-                    let ply = Ply { source, target: double_pawn_push_target, piece: Piece::Pawn, captured_piece: None, promotion_piece: None};
-                    if position.make_move(ply).is_legal() {
-                        move_list.push(ply);
-                    }
-                }
-            }
         }
     }
 }
 
 /// Generates all legal attacking pawn moves for the given position.
+///
+/// Like the pushes, the captures of all pawns are computed at once by shifting the pawn bitboard diagonally,
+/// so only the pawns that can capture something are visited.
 fn generate_attacking_pawn_moves(position: Position, move_list: &mut MoveList) {
-    // get a reference to the lookup table
-    let lookup = LOOKUP_TABLE.get().unwrap();
+    // the color to move
+    let color = position.color_to_move;
 
     // get opposite color occupancy
-    let occupancy = position.get_occupancy(position.color_to_move.other());
+    let occupancy = position.get_occupancy(color.other()).value;
 
     // get pawn bitboard for the color to move
-    let pawn_bb = position.pieces[position.color_to_move.to_index() as usize][Piece::Pawn.to_index() as usize];
-
-    // get all squares with a pawn on it
-    let active_squares = pawn_bb.get_active_bits();
+    let pawn_bb = position.piece_bb(color, Piece::Pawn);
+
+    // the captures towards the A file and towards the H file, i.e. the attacked squares that are occupied by an enemy piece
+    let (left_captures, right_captures) = match color {
+        Color::White => (pawn_bb.shift_up_left(), pawn_bb.shift_up_right()),
+        Color::Black => (pawn_bb.shift_down_left(), pawn_bb.shift_down_right()),
+    };
+    let left_captures = Bitboard::new(left_captures.value & occupancy);
+    let right_captures = Bitboard::new(right_captures.value & occupancy);
+
+    // shift the captures back to find the pawns that make them
+    let sources = match color {
+        Color::White => left_captures.shift_down_right().value | right_captures.shift_down_left().value,
+        Color::Black => left_captures.shift_up_right().value | right_captures.shift_up_left().value,
+    };
+
+    // loop over source squares and create moves, so that the moves of each pawn stay together
+    for source in Bitboard::new(sources).get_active_bits() {
+        let source_bb = Bitboard::from_square(source);
+        let targets = match color {
+            Color::White => source_bb.shift_up_left().value | source_bb.shift_up_right().value,
+            Color::Black => source_bb.shift_down_left().value | source_bb.shift_down_right().value,
+        };
 
-    // loop over source squares and calculate possible moves
-    for source in active_squares {
-        // lookup the attack bb for the pawn on the source square
-        let mut target_attack_bb = lookup.get_pawn_attacks(source, position.color_to_move);
-        
-        // `and` the attack bb with the opponent's occupancy (because a capture is only possible if an enemy pawn occupies the target square)
-        target_attack_bb.value &= occupancy.value;
-
-        // these are the targets that we know are occupied by an enemy pawn
-        let active_squares = target_attack_bb.get_active_bits();
-
-        // loop over target squares and create moves
-        for target in active_squares {
-            // get the type of the attacked piece
-            let attacked_piece= match position.get_piece(target) {
-                Some((piece, _color)) => piece,
-                None => continue,
-            };
-            
+        for target in Bitboard::new(targets & occupancy).get_active_bits() {
             // check if target square is on the promotion rank
-            if target.get_rank() == position.color_to_move.promotion_rank() {
+            if target.get_rank() == color.promotion_rank() {
                 // move is a promotion - add all possible promotion moves
-                for piece_index in Piece::Knight.to_index() as usize..Piece::Queen.to_index() as usize + 1 {
-                    let ply = Ply { source, target, piece: Piece::Pawn, captured_piece: Some(attacked_piece), promotion_piece: Some(Piece::from_index(piece_index as u8))};
+                for promotion_piece in position.variant.promotion_pieces() {
+                    let ply = Ply::new(source, target, Piece::Pawn, Some(*promotion_piece), true);
                     if position.make_move(ply).is_legal() {
                         move_list.push(ply);
                     }
                 }
             } else {
                 // move is not a promotion
-                let ply = Ply { source, target, piece: Piece::Pawn, captured_piece: Some(attacked_piece), promotion_piece: None};
+                let ply = Ply::new(source, target, Piece::Pawn, None, true);
                 if position.make_move(ply).is_legal() {
                     move_list.push(ply);
                 }
@@ -127,18 +132,21 @@ fn generate_attacking_pawn_moves(position: Position, move_list: &mut MoveList) {
 
 /// Generates all legal en passant moves for the given position.
 fn generate_en_passant_moves(position: Position, move_list: &mut MoveList) {
+    // the color to move
+    let color = position.color_to_move;
+
     if let Some(target_square) = position.en_passant {
         // get pawn bitboard for the color to move
-        let pawn_bb = position.pieces[position.color_to_move.to_index() as usize][Piece::Pawn.to_index() as usize];
+        let pawn_bb = position.piece_bb(color, Piece::Pawn);
         
         // the rank of the pawns that can capture en passant
-        let source_rank = position.color_to_move.other().double_pawn_push_target_rank();
+        let source_rank = color.other().double_pawn_push_target_rank();
         
         // check file to the left for pawn that can capture en passant
         if target_square.get_file() != File::A {
             let source = Square::from_file_rank(target_square.get_file().left(), source_rank);
             if pawn_bb.get_bit(source) {
-                let ply = Ply { source, target: target_square, piece: Piece::Pawn, captured_piece: Some(Piece::Pawn), promotion_piece: None};
+                let ply = Ply::new(source, target_square, Piece::Pawn, None, true);
                 if position.make_move(ply).is_legal() {
                     move_list.push(ply);
                 }
@@ -148,7 +156,7 @@ fn generate_en_passant_moves(position: Position, move_list: &mut MoveList) {
         if target_square.get_file() != File::H {
             let source = Square::from_file_rank(target_square.get_file().right(), source_rank);
             if pawn_bb.get_bit(source) {
-                let ply = Ply { source, target: target_square, piece: Piece::Pawn, captured_piece: Some(Piece::Pawn), promotion_piece: None};
+                let ply = Ply::new(source, target_square, Piece::Pawn, None, true);
                 if position.make_move(ply).is_legal() {
                     move_list.push(ply);
                 }
@@ -160,17 +168,13 @@ fn generate_en_passant_moves(position: Position, move_list: &mut MoveList) {
 #[cfg(test)]
 mod tests {
     use crate::board::{Board, square};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
+    use crate::board::piece::Piece;
     use crate::move_gen::move_list::MoveList;
     use crate::move_gen::pawn_moves;
+    use crate::move_gen::ply::Ply;
 
     #[test]
     fn test_generate_quiet_pawn_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -251,10 +255,6 @@ mod tests {
 
     #[test]
     fn test_generate_attacking_pawn_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -335,10 +335,6 @@ mod tests {
 
     #[test]
     fn test_generate_en_passant_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -352,8 +348,8 @@ mod tests {
         let mut move_list = MoveList::default();
         pawn_moves::generate_en_passant_moves(position, &mut move_list);
         assert_eq!(1, move_list.len());
-        assert_eq!(square::E5, move_list.get(0).source);
-        assert_eq!(square::D6, move_list.get(0).target);
+        assert_eq!(square::E5, move_list.get(0).source());
+        assert_eq!(square::D6, move_list.get(0).target());
 
         // position 3
 
@@ -361,10 +357,10 @@ mod tests {
         let mut move_list = MoveList::default();
         pawn_moves::generate_en_passant_moves(position, &mut move_list);
         assert_eq!(2, move_list.len());
-        assert_eq!(square::E5, move_list.get(0).source);
-        assert_eq!(square::F6, move_list.get(0).target);
-        assert_eq!(square::G5, move_list.get(1).source);
-        assert_eq!(square::F6, move_list.get(1).target);
+        assert_eq!(square::E5, move_list.get(0).source());
+        assert_eq!(square::F6, move_list.get(0).target());
+        assert_eq!(square::G5, move_list.get(1).source());
+        assert_eq!(square::F6, move_list.get(1).target());
 
         // position 4
 
@@ -372,8 +368,8 @@ mod tests {
         let mut move_list = MoveList::default();
         pawn_moves::generate_en_passant_moves(position, &mut move_list);
         assert_eq!(1, move_list.len());
-        assert_eq!(square::G5, move_list.get(0).source);
-        assert_eq!(square::H6, move_list.get(0).target);
+        assert_eq!(square::G5, move_list.get(0).source());
+        assert_eq!(square::H6, move_list.get(0).target());
 
         // position 5
 
@@ -388,8 +384,8 @@ mod tests {
         let mut move_list = MoveList::default();
         pawn_moves::generate_en_passant_moves(position, &mut move_list);
         assert_eq!(1, move_list.len());
-        assert_eq!(square::D5, move_list.get(0).source);
-        assert_eq!(square::C6, move_list.get(0).target);
+        assert_eq!(square::D5, move_list.get(0).source());
+        assert_eq!(square::C6, move_list.get(0).target());
 
         // position 7
 
@@ -397,10 +393,10 @@ mod tests {
         let mut move_list = MoveList::default();
         pawn_moves::generate_en_passant_moves(position, &mut move_list);
         assert_eq!(2, move_list.len());
-        assert_eq!(square::A4,  move_list.get(0).source);
-        assert_eq!(square::B3,  move_list.get(0).target);
-        assert_eq!(square::C4,  move_list.get(1).source);
-        assert_eq!(square::B3,  move_list.get(1).target);
+        assert_eq!(square::A4,  move_list.get(0).source());
+        assert_eq!(square::B3,  move_list.get(0).target());
+        assert_eq!(square::C4,  move_list.get(1).source());
+        assert_eq!(square::B3,  move_list.get(1).target());
 
         // position 8
 
@@ -415,8 +411,8 @@ mod tests {
         let mut move_list = MoveList::default();
         pawn_moves::generate_en_passant_moves(position, &mut move_list);
         assert_eq!(1, move_list.len());
-        assert_eq!(square::H4,  move_list.get(0).source);
-        assert_eq!(square::G3,  move_list.get(0).target);
+        assert_eq!(square::H4,  move_list.get(0).source());
+        assert_eq!(square::G3,  move_list.get(0).target());
 
         // position 10
 
@@ -424,16 +420,12 @@ mod tests {
         let mut move_list = MoveList::default();
         pawn_moves::generate_en_passant_moves(position, &mut move_list);
         assert_eq!(1, move_list.len());
-        assert_eq!(square::G4,  move_list.get(0).source);
-        assert_eq!(square::F3,  move_list.get(0).target);
+        assert_eq!(square::G4,  move_list.get(0).source());
+        assert_eq!(square::F3,  move_list.get(0).target());
     }
 
     #[test]
     fn test_generate_pawn_moves() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // position 1 (starting position)
 
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -504,4 +496,19 @@ mod tests {
         pawn_moves::generate_pawn_moves(position, &mut move_list);
         assert_eq!(11, move_list.len());
     }
+
+    #[test]
+    fn pawns_on_the_back_rank_do_not_wrap_around() {
+        // positions that can't occur in a game, where a push would wrap around to the other edge of the board
+        let position = Board::from_fen_unchecked("P3k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap().position;
+        let mut move_list = MoveList::default();
+        pawn_moves::generate_quiet_pawn_moves(position, &mut move_list);
+        assert_eq!(0, move_list.len());
+
+        let position = Board::from_fen_unchecked("4k3/8/8/8/8/8/8/4K2p b - - 0 1").unwrap().position;
+        let mut move_list = MoveList::default();
+        pawn_moves::generate_quiet_pawn_moves(position, &mut move_list);
+        assert_eq!(0, move_list.len());
+        assert!(!position.is_pseudo_legal(Ply::new(square::H1, square::H8, Piece::Pawn, None, false)));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,143 @@
+//! The perft module counts the leaf nodes of the move generation tree of a position,
+//! see [Perft](https://www.chessprogramming.org/Perft).
+//!
+//! A perft serves two purposes: it verifies that the move generation is working correctly, by comparing the node counts
+//! with the known results of other engines, and it measures the speed of the move generation.
+//! The functions of this module don't need a search, so they can be used by any user of the library.
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+use crate::board::position::Position;
+use crate::move_gen;
+use crate::move_gen::ply::Ply;
+use crate::search::clock::Instant;
+
+/// The maximum depth of a perft. Deeper perfts are performed to this depth instead,
+/// since they wouldn't finish in any reasonable time anyway.
+pub const MAX_PERFT_DEPTH: u64 = 20;
+
+/// The result of a perft.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PerftResult {
+    /// The number of leaf nodes, which saturates at `u64::MAX` instead of overflowing.
+    pub nodes: u64,
+    /// The number of leaf nodes below each root move, in the order of the move generation.
+    pub divide: Vec<(Ply, u64)>,
+    /// The time the perft took.
+    pub elapsed: Duration,
+}
+
+impl PerftResult {
+    /// Constructs the result of a perft to the given depth from the node counts of the root moves.
+    pub fn new(depth: u64, divide: Vec<(Ply, u64)>, elapsed: Duration) -> Self {
+        // at depth 0, the position itself is the only leaf node
+        let nodes = match depth {
+            0 => 1,
+            _other => divide.iter().fold(0, |nodes: u64, (_ply, root_nodes)| nodes.saturating_add(*root_nodes)),
+        };
+        Self { nodes, divide, elapsed }
+    }
+}
+
+/// Prints the node count of each root move, followed by the total node count and the elapsed time.
+impl Display for PerftResult {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        for (ply, nodes) in self.divide.iter() {
+            writeln!(f, "{ply}: {nodes}")?;
+        }
+        write!(f, "Searched {} nodes in {:?}", self.nodes, self.elapsed)
+    }
+}
+
+/// Performs a perft of the given position to the given depth, which is capped at `MAX_PERFT_DEPTH`.
+pub fn perft(position: Position, depth: u64) -> PerftResult {
+    let time = Instant::now();
+    let divide = divide(position, depth);
+    PerftResult::new(depth, divide, time.elapsed())
+}
+
+/// Returns the number of leaf nodes below each legal move of the given position, for a perft to the given depth,
+/// which is capped at `MAX_PERFT_DEPTH`. At depth 0, no moves are played, so no root moves are returned.
+pub fn divide(position: Position, depth: u64) -> Vec<(Ply, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let depth = depth.min(MAX_PERFT_DEPTH);
+    let move_list = move_gen::generate_moves(position);
+    (0..move_list.len())
+        .map(|i| move_list.get(i))
+        .map(|ply| (ply, perft_driver(position.make_move(ply), depth - 1)))
+        .collect()
+}
+
+/// This is the recursive perft driver function, which traverses the tree and returns the number of leaf nodes.
+/// The node count saturates at `u64::MAX` instead of overflowing.
+pub fn perft_driver(position: Position, depth: u64) -> u64 {
+    // if depth is zero, return a node count of 1 to break out of the recursion
+    if depth == 0 {
+        return 1;
+    }
+
+    // the number of leaf nodes
+    let mut node_count: u64 = 0;
+
+    // generate all legal moves for the position
+    let move_list = move_gen::generate_moves(position);
+
+    // call the perft_driver function recursively for all legal moves and add the results to node_count
+    for i in 0..move_list.len() {
+        let ply = move_list.get(i);
+        node_count = node_count.saturating_add(perft_driver(position.make_move(ply), depth - 1));
+    }
+
+    node_count
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::board::Board;
+    use crate::board::piece::Piece;
+    use crate::board::square;
+    use crate::move_gen::perft::{divide, perft, perft_driver, PerftResult};
+    use crate::move_gen::ply::Ply;
+
+    #[test]
+    fn test_perft() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        let result = perft(position, 3);
+        assert_eq!(8_902, result.nodes);
+        assert_eq!(20, result.divide.len());
+        assert_eq!(result.nodes, result.divide.iter().map(|(_ply, nodes)| nodes).sum::<u64>());
+        assert_eq!(perft_driver(position, 3), result.nodes);
+    }
+
+    #[test]
+    fn test_perft_depth_0() {
+        // the position itself is the only leaf node
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(1, perft_driver(position, 0));
+        assert!(divide(position, 0).is_empty());
+        let result = perft(position, 0);
+        assert_eq!((1, Vec::new()), (result.nodes, result.divide));
+    }
+
+    #[test]
+    fn test_perft_result_saturates() {
+        let e4 = Ply::new(square::E2, square::E4, Piece::Pawn, None, false);
+        let d4 = Ply::new(square::D2, square::D4, Piece::Pawn, None, false);
+        assert_eq!(u64::MAX, PerftResult::new(20, vec![(e4, u64::MAX - 1), (d4, 5)], Duration::ZERO).nodes);
+        assert_eq!(6, PerftResult::new(2, vec![(e4, 1), (d4, 5)], Duration::ZERO).nodes);
+    }
+
+    #[test]
+    fn test_perft_result_formats_correctly() {
+        let position = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap().position;
+        let result = perft(position, 1);
+        let output = result.to_string();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(16, lines.len());
+        assert!(lines.contains(&"e1g1: 1"));
+        assert!(lines[15].starts_with("Searched 15 nodes in "));
+    }
+}
@@ -1,219 +1,389 @@
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 use crate::board::color::Color;
+use crate::board::file::File;
 use crate::board::piece::Piece;
 use crate::board::position::Position;
-use crate::board::square;
-use crate::board::square::Square;
+use crate::board::rank::Rank;
+use crate::board::square::{Square, SquareParseError};
 use crate::evaluation::pst;
 use crate::move_gen;
 
-const SOURCE_SQUARE_MASK: u32 = 0b11111100_00000000_00000000_00000000;
-const SHIFT_SOURCE_SQUARE: u32 = 26;
-
-const TARGET_SQUARE_MASK: u32 = 0b00000011_11110000_00000000_00000000;
-const SHIFT_TARGET_SQUARE: u32 = 20;
-
-const PIECE_MASK: u32 = 0b00000000_00001110_00000000_00000000;
-const SHIFT_PIECE: u32 = 17;
-
-const CAPTURED_PIECE_MASK: u32 = 0b00000000_00000001_11000000_00000000;
-const SHIFT_CAPTURED_PIECE: u32 = 14;
-
-const PROMOTION_PIECE_MASK: u32 = 0b00000000_00000000_00111000_00000000;
-const SHIFT_PROMOTION_PIECE: u32 = 11;
+const SOURCE_SQUARE_MASK: u32 = 0b00000000_00000000_00000000_00111111;
+const SHIFT_SOURCE_SQUARE: u32 = 0;
+
+const TARGET_SQUARE_MASK: u32 = 0b00000000_00000000_00001111_11000000;
+const SHIFT_TARGET_SQUARE: u32 = 6;
+
+const PIECE_MASK: u32 = 0b00000000_00000000_01110000_00000000;
+const SHIFT_PIECE: u32 = 12;
+
+const PROMOTION_PIECE_MASK: u32 = 0b00000000_00000011_10000000_00000000;
+const SHIFT_PROMOTION_PIECE: u32 = 15;
+
+const CAPTURE_FLAG: u32 = 0b00000000_00000100_00000000_00000000;
+
+/// The reasons why a string can't be parsed as a move in a position.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PlyParseError {
+    /// The move does not consist of 4 or 5 characters.
+    WrongLength(String),
+    /// The source or the target square is invalid.
+    InvalidSquare(SquareParseError),
+    /// The given character is not a piece pawns can promote to.
+    InvalidPromotionPiece(char),
+    /// There is no piece of the color to move on the given square.
+    NoPieceToMove(Square),
+    /// A pawn reaches the last rank, but the promotion piece is missing.
+    MissingPromotionPiece(String),
+    /// A promotion piece is given, but the move is not a pawn reaching the last rank.
+    UnexpectedPromotion(String),
+    /// The move can't be played in the position, e.g. because the piece can't move like that, or the king would be in check.
+    IllegalMove(String),
+}
 
+/// Prints the error as text.
+impl Display for PlyParseError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PlyParseError::WrongLength(ply_str) => write!(f, "Invalid move: expected 4 or 5 characters, found '{ply_str}'"),
+            PlyParseError::InvalidSquare(error) => write!(f, "Invalid move: {error}"),
+            PlyParseError::InvalidPromotionPiece(piece) => write!(f, "Invalid move: invalid promotion piece '{piece}'"),
+            PlyParseError::NoPieceToMove(square) => write!(f, "Invalid move: no piece of the color to move on {square}"),
+            PlyParseError::MissingPromotionPiece(ply_str) => write!(f, "Ambiguous move: {ply_str} is missing the promotion piece"),
+            PlyParseError::UnexpectedPromotion(ply_str) => write!(f, "Invalid move: {ply_str} is not a promotion"),
+            PlyParseError::IllegalMove(ply_str) => write!(f, "Illegal move: {ply_str}"),
+        }
+    }
+}
 
+impl Error for PlyParseError {}
 
 /// This struct represents a halfmove, also known as [ply](https://www.chessprogramming.org/Ply).
 ///
 /// In the comments, I will often refer to a ply as a move, even though a move technically involves
 /// both White's and Black's responses. Unless stated otherwise, move and ply mean basically the same in this repository.
 /// Moves in the actual sense will be referred to as "fullmove".
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub struct Ply {
-    /// The source square.
-    pub source: Square,
-    /// The target square.
-    pub target: Square,
-    /// The type of the piece to move.
-    pub piece: Piece,
-    /// If the move is a capture move, this field will contain the type of the captured piece.
-    pub captured_piece: Option<Piece>,
-    /// If the move is a pawn promotion, this field will contain the promotion piece.
-    pub promotion_piece: Option<Piece>,
-}
-
-impl Default for Ply {
-    /// Returns an illegal default ply of a pawn move from a1 to a1.
-    /// It is only used to initialize a ply array with default values.
-    fn default() -> Self {
-        Ply {
-            source: square::A1,
-            target: square::A1,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        }
-    }
-}
+///
+/// The ply is packed into a 32-bit unsigned integer, which keeps move lists and search tables small.
+/// The format is as follows:
+/// 00000000 00000000 00000000 00111111 : source square
+/// 00000000 00000000 00001111 11000000 : target square
+/// 00000000 00000000 01110000 00000000 : piece
+/// 00000000 00000011 10000000 00000000 : promotion piece (0 means no promotion, since pawns can't be promoted to)
+/// 00000000 00000100 00000000 00000000 : capture flag
+/// 11111111 11111000 00000000 00000000 : unused bits - may use later
+///
+/// The type of the captured piece is not part of the ply. It can be obtained from the position the ply is played in
+/// with `Position::get_captured_piece`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub struct Ply(u32);
 
 /// Prints the ply as text.
 impl Display for Ply {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         let mut output: String = String::from("");
-        output += format!("{}", self.source).as_str();
-        output += format!("{}", self.target).as_str();
-        if self.promotion_piece.is_some() {
-            output.push(self.promotion_piece.unwrap().to_char(Color::Black));
+        output += format!("{}", self.source()).as_str();
+        output += format!("{}", self.target()).as_str();
+        if let Some(promotion_piece) = self.promotion_piece() {
+            output.push(promotion_piece.to_char(Color::Black));
         }
         write!(f, "{}", output)
     }
 }
 
 impl Ply {
-    /// Returns the score of the ply based on [MVV-LVA](https://www.chessprogramming.org/MVV-LVA).
-    pub fn score(&self) -> i32 {
+    /// Constructs a new ply.
+    pub fn new(source: Square, target: Square, piece: Piece, promotion_piece: Option<Piece>, is_capture: bool) -> Ply {
+        let mut encoded_ply: u32 = 0;
+
+        // set the source and target square bits
+        encoded_ply |= (source.index as u32) << SHIFT_SOURCE_SQUARE;
+        encoded_ply |= (target.index as u32) << SHIFT_TARGET_SQUARE;
+
+        // set the piece bits
+        encoded_ply |= (piece.to_index() as u32) << SHIFT_PIECE;
+
+        // set the promotion piece bits
+        if let Some(promotion_piece) = promotion_piece {
+            encoded_ply |= (promotion_piece.to_index() as u32) << SHIFT_PROMOTION_PIECE;
+        }
+
+        // set the capture flag
+        if is_capture {
+            encoded_ply |= CAPTURE_FLAG;
+        }
+
+        Ply(encoded_ply)
+    }
+
+    /// Returns the source square.
+    pub fn source(&self) -> Square {
+        Square::new(((self.0 & SOURCE_SQUARE_MASK) >> SHIFT_SOURCE_SQUARE) as u8)
+    }
+
+    /// Returns the target square.
+    pub fn target(&self) -> Square {
+        Square::new(((self.0 & TARGET_SQUARE_MASK) >> SHIFT_TARGET_SQUARE) as u8)
+    }
+
+    /// Returns the type of the piece to move.
+    pub fn piece(&self) -> Piece {
+        Piece::from_index(((self.0 & PIECE_MASK) >> SHIFT_PIECE) as u8)
+    }
+
+    /// If the move is a pawn promotion, returns the promotion piece.
+    pub fn promotion_piece(&self) -> Option<Piece> {
+        match ((self.0 & PROMOTION_PIECE_MASK) >> SHIFT_PROMOTION_PIECE) as u8 {
+            0 => None,
+            piece_index => Some(Piece::from_index(piece_index)),
+        }
+    }
+
+    /// Returns true if the move captures a piece (including en passant).
+    pub fn is_capture(&self) -> bool {
+        self.0 & CAPTURE_FLAG != 0
+    }
+
+    /// Returns the score of the ply in the given position based on [MVV-LVA](https://www.chessprogramming.org/MVV-LVA).
+    pub fn score(&self, position: &Position) -> i32 {
         let mut score = 0;
         
         // add the value of the captured piece (if any)
-        if let Some(piece) = self.captured_piece {
+        if let Some(piece) = position.get_captured_piece(*self) {
             score += pst::PIECE_VALUES[piece.to_index() as usize];
         }
         
         // add the value of the promotion piece (if any)
-        if let Some(piece) = self.promotion_piece {
+        if let Some(piece) = self.promotion_piece() {
             score += pst::PIECE_VALUES[piece.to_index() as usize];
         }
         
         // subtract the index of the moving piece
-        score -= self.piece.to_index() as i32;
+        score -= self.piece().to_index() as i32;
         
         score
     }
     
-    /// Encodes the ply as 32-bit unsigned integer.
-    ///
-    /// The format is as follows:
-    /// 11111100 00000000 00000000 00000000 : source square
-    /// 00000011 11110000 00000000 00000000 : target square
-    /// 00000000 00001110 00000000 00000000 : piece
-    /// 00000000 00000001 11000000 00000000 : captured piece
-    /// 00000000 00000000 00111000 00000000 : promotion piece
-    /// 00000000 00000000 00000111 11111111 : unused bits - may use later
+    /// Returns the ply as 32-bit unsigned integer.
     pub fn encode(&self) -> u32 {
-        let mut encoded_ply: u32 = 0;
-        
-        // set the source square bits
-        encoded_ply |= (self.source.index as u32) << SHIFT_SOURCE_SQUARE;
-
-        // set the target square bits
-        encoded_ply |= (self.target.index as u32) << SHIFT_TARGET_SQUARE;
-        
-        // set the piece bits
-        encoded_ply |= (self.piece.to_index() as u32) << SHIFT_PIECE;
-
-        // set the captured piece bits
-        encoded_ply |= match self.captured_piece {
-            None => 6 << SHIFT_CAPTURED_PIECE, // 6 represents no piece
-            Some(captured_piece) => (captured_piece.to_index() as u32) << SHIFT_CAPTURED_PIECE,
-        };
-
-        // set the promotion piece bits
-        encoded_ply |= match self.promotion_piece {
-            None => 6 << SHIFT_PROMOTION_PIECE, // 6 represents no piece
-            Some(captured_piece) => (captured_piece.to_index() as u32) << SHIFT_PROMOTION_PIECE,
-        };
-        
-        encoded_ply
+        self.0
     }
 
-    /// Decodes a 32-bit unsigned integer into a ply.
+    /// Constructs a ply from a 32-bit unsigned integer returned by `encode`.
     pub fn decode(encoded_ply: u32) -> Ply {
-        // decode source square
-        let source = Square::new(((encoded_ply & SOURCE_SQUARE_MASK) >> SHIFT_SOURCE_SQUARE) as u8);
-        
-        // decode target square
-        let target = Square::new(((encoded_ply & TARGET_SQUARE_MASK) >> SHIFT_TARGET_SQUARE) as u8);
-        
-        // decode piece
-        let piece = Piece::from_index(((encoded_ply & PIECE_MASK) >> SHIFT_PIECE) as u8);
-        
-        // decode captured piece
-        let piece_index = ((encoded_ply & CAPTURED_PIECE_MASK) >> SHIFT_CAPTURED_PIECE) as u8;
-        let captured_piece = match piece_index {
-            6 => None,
-            other => Some(Piece::from_index(other)),
+        Ply(encoded_ply)
+    }
+    
+    /// Tries to construct a ply from the given string in pure coordinate notation for the given position, e.g. "e2e4" or "e7e8q".
+    /// The promotion piece may be given in upper or lower case, and castling may also be given as the king capturing its own rook,
+    /// e.g. "e1h1", as some GUIs send it.
+    /// If the string is not a legal move in the position, the error describes why.
+    pub fn from_string(ply_str: &str, position: Position) -> Result<Ply, PlyParseError> {
+        let ply_str = ply_str.to_ascii_lowercase();
+        let chars: Vec<char> = ply_str.chars().collect();
+        if !(4..=5).contains(&chars.len()) {
+            return Err(PlyParseError::WrongLength(ply_str));
+        }
+
+        // try to construct squares from the strings
+        let source_square = Square::from_string(chars[0..2].iter().collect::<String>().as_str()).map_err(PlyParseError::InvalidSquare)?;
+        let mut target_square = Square::from_string(chars[2..4].iter().collect::<String>().as_str()).map_err(PlyParseError::InvalidSquare)?;
+
+        // the promotion piece has to be one that pawns can promote to in the variant
+        let promotion_piece = match chars.get(4) {
+            None => None,
+            Some(promotion_char) => match Piece::from_string(promotion_char.to_string().as_str()) {
+                Some(piece) if position.variant.promotion_pieces().contains(&piece) => Some(piece),
+                _other => return Err(PlyParseError::InvalidPromotionPiece(*promotion_char)),
+            },
         };
 
-        // decode promotion piece
-        let piece_index = ((encoded_ply & PROMOTION_PIECE_MASK) >> SHIFT_PROMOTION_PIECE) as u8;
-        let promotion_piece = match piece_index {
-            6 => None,
-            other => Some(Piece::from_index(other)),
+        // the moving piece has to belong to the color to move
+        let piece = match position.get_piece(source_square) {
+            Some((piece, color)) if color == position.color_to_move => piece,
+            _other => return Err(PlyParseError::NoPieceToMove(source_square)),
         };
-        
-        Ply {
-            source,
-            target,
-            piece,
-            captured_piece,
-            promotion_piece,
+
+        // castling given as the king capturing its own rook is converted to the target square of the king
+        let color = position.color_to_move;
+        if piece == Piece::King && source_square.get_file() == File::E && position.piece_bb(color, Piece::Rook).get_bit(target_square)
+            && source_square.get_rank() == target_square.get_rank() {
+            target_square = match target_square.get_file() {
+                File::H => Square::from_file_rank(File::G, target_square.get_rank()),
+                File::A => Square::from_file_rank(File::C, target_square.get_rank()),
+                _other => target_square,
+            };
         }
-    }
-    
-    /// Tries to construct a ply from the given string for the given position.
-    pub fn from_string(ply_str: &str, position: Position) -> Option<Ply> {
-        // get the chars from the ply string
-        let char_vec: Vec<char> = ply_str.chars().collect();
-        if !(4..=5).contains(&char_vec.len()) {
-            return None;
+
+        // pawns that reach the last rank have to promote, and other moves can't
+        let is_promotion_move = piece == Piece::Pawn && target_square.get_rank() == color.promotion_rank();
+        match (is_promotion_move, promotion_piece) {
+            (true, None) => return Err(PlyParseError::MissingPromotionPiece(ply_str)),
+            (false, Some(_piece)) => return Err(PlyParseError::UnexpectedPromotion(ply_str)),
+            _other => {}
         }
 
-        // source square as string
-        let mut source_str = String::from("");
-        source_str.push(char_vec[0]);
-        source_str.push(char_vec[1]);
+        // the move is a capture if an enemy piece occupies the target square, or if a pawn captures en passant
+        let is_capture = position.get_occupancy(color.other()).get_bit(target_square)
+            || (piece == Piece::Pawn && position.en_passant == Some(target_square));
 
-        // target square as string
-        let mut target_str = String::from("");
-        target_str.push(char_vec[2]);
-        target_str.push(char_vec[3]);
+        // validate the move without generating all legal moves
+        let ply = Ply::new(source_square, target_square, piece, promotion_piece, is_capture);
+        match position.is_legal_move(ply) {
+            true => Ok(ply),
+            false => Err(PlyParseError::IllegalMove(ply_str)),
+        }
+    }
 
-        // try to construct squares from the strings
-        let source_square = Square::from_string(source_str.as_str());
-        let target_square = Square::from_string(target_str.as_str());
-        if source_square.is_err() || target_square.is_err() {
-            return None;
+    /// Returns the ply in [standard algebraic notation](https://www.chessprogramming.org/Algebraic_Chess_Notation#SAN) (SAN)
+    /// for the given position, e.g. "Nbd2", "exd5", "e8=Q+" or "O-O-O".
+    pub fn to_san(&self, position: Position) -> String {
+        let mut san = String::from("");
+
+        if self.piece() == Piece::King && self.source().get_file().to_index().abs_diff(self.target().get_file().to_index()) == 2 {
+            // move is castling
+            if self.target().get_file() == File::G {
+                san += "O-O";
+            } else {
+                san += "O-O-O";
+            }
+        } else if self.piece() == Piece::Pawn {
+            // pawn captures are identified by the source file
+            if self.is_capture() {
+                san += format!("{}x", self.source().get_file()).as_str();
+            }
+            san += format!("{}", self.target()).as_str();
+            if let Some(promotion_piece) = self.promotion_piece() {
+                san.push('=');
+                san.push(promotion_piece.to_char(Color::White));
+            }
+        } else {
+            san.push(self.piece().to_char(Color::White));
+
+            // check whether other pieces of the same type can move to the target square
+            let move_list = move_gen::generate_moves(position);
+            let mut ambiguous = false;
+            let mut same_file = false;
+            let mut same_rank = false;
+            for i in 0..move_list.len() {
+                let other = move_list.get(i);
+                if other.piece() == self.piece() && other.target() == self.target() && other.source() != self.source() {
+                    ambiguous = true;
+                    same_file |= other.source().get_file() == self.source().get_file();
+                    same_rank |= other.source().get_rank() == self.source().get_rank();
+                }
+            }
+
+            // disambiguate by file if possible, otherwise by rank, and only use the full square if neither is enough
+            if ambiguous {
+                if !same_file {
+                    san += format!("{}", self.source().get_file()).as_str();
+                } else if !same_rank {
+                    san += format!("{}", self.source().get_rank()).as_str();
+                } else {
+                    san += format!("{}", self.source()).as_str();
+                }
+            }
+
+            if self.is_capture() {
+                san.push('x');
+            }
+            san += format!("{}", self.target()).as_str();
         }
-        let source_square = source_square.unwrap();
-        let target_square = target_square.unwrap();
-
-        let mut promotion_piece: Option<Piece> = None;
-
-        if char_vec.len() == 5 {
-            // move is a promotion
-            let mut promotion_piece_string = String::from("");
-            promotion_piece_string.push(char_vec[4]);
-            match Piece::from_string(promotion_piece_string.as_str()) {
-                None => return None, // invalid promotion piece string
-                Some(piece) => promotion_piece = Some(piece),
-            };
+
+        // add check or checkmate suffix
+        let new_position = position.make_move(*self);
+        if new_position.is_in_check(new_position.color_to_move) {
+            if move_gen::generate_moves(new_position).is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
         }
 
+        san
+    }
+
+    /// Tries to construct a ply from the given string in standard algebraic notation (SAN) for the given position.
+    /// Check and annotation suffixes are ignored. Returns None if the move is illegal or ambiguous.
+    pub fn from_san(san_str: &str, position: Position) -> Option<Ply> {
+        let san = san_str.trim().trim_end_matches(['+', '#', '!', '?']);
+
         // generate all legal moves for the given position
         let move_list = move_gen::generate_moves(position);
         let mut move_list_vec: Vec<Ply> = Vec::new();
-
         for i in 0..move_list.len() {
             move_list_vec.push(move_list.get(i));
         }
 
-        // search for ply in the move list
-        let ply = match move_list_vec.iter().find(|r| r.source == source_square && r.target == target_square && r.promotion_piece == promotion_piece) {
-            None => return None, // if the move list does not contain a ply with the specified source and target squares, the move is not legal
-            Some(ply) => *ply,
+        // castling moves
+        let castling_file = match san {
+            "O-O" | "0-0" => Some(File::G),
+            "O-O-O" | "0-0-0" => Some(File::C),
+            _other => None,
+        };
+        if let Some(file) = castling_file {
+            return move_list_vec.into_iter().find(|ply| ply.piece() == Piece::King && ply.source().get_file() == File::E && ply.target().get_file() == file);
+        }
+
+        let mut chars: Vec<char> = san.chars().collect();
+
+        // the promotion piece is written at the end, usually (but not always) separated by '='
+        let mut promotion_piece = None;
+        if chars.len() > 2 && "NBRQ".contains(chars[chars.len() - 1]) {
+            promotion_piece = Piece::from_string(chars.pop().unwrap().to_string().as_str());
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        // the moving piece is written at the start, pawn moves have no piece letter
+        let piece = match chars.first() {
+            Some(char) if "NBRQK".contains(*char) => {
+                let piece = Piece::from_string(char.to_string().as_str());
+                chars.remove(0);
+                piece?
+            }
+            _other => Piece::Pawn,
         };
 
+        // captures are marked with an 'x'
+        let is_capture = chars.contains(&'x');
+        chars.retain(|char| *char != 'x');
+
+        // the target square is always the last part of the move
+        if chars.len() < 2 {
+            return None;
+        }
+        let target_str: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let target_square = Square::from_string(target_str.as_str()).ok()?;
+
+        // the remaining chars disambiguate the source square by file and/or rank
+        let mut source_file = None;
+        let mut source_rank = None;
+        for char in chars {
+            match char {
+                'a'..='h' => source_file = Some(File::from_char(&char).ok()?),
+                '1'..='8' => source_rank = Some(Rank::from_index(char as u8 - b'1')),
+                _other => return None,
+            }
+        }
+
+        // search for the ply in the move list, the move has to be unique
+        let mut matches = move_list_vec.into_iter().filter(|ply| {
+            ply.piece() == piece
+                && ply.target() == target_square
+                && ply.promotion_piece() == promotion_piece
+                && (!is_capture || ply.is_capture())
+                && source_file.is_none_or(|file| ply.source().get_file() == file)
+                && source_rank.is_none_or(|rank| ply.source().get_rank() == rank)
+        });
+        let ply = matches.next()?;
+        if matches.next().is_some() {
+            return None;
+        }
+
         Some(ply)
     }
 }
@@ -222,133 +392,191 @@ impl Ply {
 mod tests {
     use crate::board::piece::Piece;
     use crate::board::{Board, square};
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
-    use crate::move_gen::ply::Ply;
+    use crate::board::square::SquareParseError;
+    use crate::move_gen;
+    use crate::move_gen::ply::{Ply, PlyParseError};
     
     #[test]
     fn default_returns_illegal_ply() {
         let ply = Ply::default();
-        assert_eq!(square::A1, ply.source);
-        assert_eq!(square::A1, ply.target);
-        assert_eq!(Piece::Pawn, ply.piece);
-        assert_eq!(None, ply.captured_piece);
-        assert_eq!(None, ply.promotion_piece);
+        assert_eq!(square::A1, ply.source());
+        assert_eq!(square::A1, ply.target());
+        assert_eq!(Piece::Pawn, ply.piece());
+        assert_eq!(None, ply.promotion_piece());
+        assert!(!ply.is_capture());
     }
     
     #[test]
     fn test_score() {
-        let ply = Ply {source: square::A1, target: square::A2, piece: Piece::Rook, captured_piece: None, promotion_piece: None};
-        assert_eq!(-3, ply.score());
+        let position = Board::from_fen("6q1/7P/8/8/8/8/8/R3K2k w - - 0 1").unwrap().position;
+
+        let ply = Ply::new(square::A1, square::A2, Piece::Rook, None, false);
+        assert_eq!(-3, ply.score(&position));
 
-        let ply = Ply {source: square::A1, target: square::A2, piece: Piece::Pawn, captured_piece: None, promotion_piece: Some(Piece::Knight)};
-        assert_eq!(320, ply.score());
+        let ply = Ply::new(square::H7, square::H8, Piece::Pawn, Some(Piece::Knight), false);
+        assert_eq!(320, ply.score(&position));
 
-        let ply = Ply {source: square::H7, target: square::H8, piece: Piece::Pawn, captured_piece: Some(Piece::Queen), promotion_piece: Some(Piece::Knight)};
-        assert_eq!(1270, ply.score());
+        let ply = Ply::new(square::H7, square::G8, Piece::Pawn, Some(Piece::Knight), true);
+        assert_eq!(1270, ply.score(&position));
     }
     
     #[test]
     fn test_encode_and_decode() {
-        let ply = Ply {source: square::A1, target: square::A2, piece: Piece::Rook, captured_piece: None, promotion_piece: None};
+        let ply = Ply::new(square::A1, square::A2, Piece::Rook, None, false);
         assert_eq!(ply, Ply::decode(ply.encode()));
 
-        let ply = Ply {source: square::H8, target: square::A8, piece: Piece::Rook, captured_piece: Some(Piece::Rook), promotion_piece: None};
+        let ply = Ply::new(square::H8, square::A8, Piece::Rook, None, true);
         assert_eq!(ply, Ply::decode(ply.encode()));
 
-        let ply = Ply {source: square::E4, target: square::D5, piece: Piece::Pawn, captured_piece: Some(Piece::Pawn), promotion_piece: None};
+        let ply = Ply::new(square::G7, square::H8, Piece::Pawn, Some(Piece::Knight), true);
         assert_eq!(ply, Ply::decode(ply.encode()));
+        assert_eq!(square::G7, ply.source());
+        assert_eq!(square::H8, ply.target());
+        assert_eq!(Piece::Pawn, ply.piece());
+        assert_eq!(Some(Piece::Knight), ply.promotion_piece());
+        assert!(ply.is_capture());
 
-        let ply = Ply {source: square::G7, target: square::H8, piece: Piece::Pawn, captured_piece: Some(Piece::Queen), promotion_piece: Some(Piece::Knight)};
-        assert_eq!(ply, Ply::decode(ply.encode()));
-
-        let ply = Ply {source: square::H3, target: square::C8, piece: Piece::Bishop, captured_piece: Some(Piece::Rook), promotion_piece: None};
+        let ply = Ply::new(square::H3, square::C8, Piece::Bishop, None, true);
         assert_eq!(ply, Ply::decode(ply.encode()));
+        assert_eq!(None, ply.promotion_piece());
     }
 
     #[test]
     fn ply_formats_correctly() {
-        let mut ply = Ply {
-            source: square::E2,
-            target: square::E4,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        };
+        let ply = Ply::new(square::E2, square::E4, Piece::Pawn, None, false);
         assert_eq!("e2e4", format!("{ply}"));
 
-        ply.source = square::A4;
-        ply.target = square::E8;
+        let ply = Ply::new(square::A4, square::E8, Piece::Queen, None, false);
         assert_eq!("a4e8", format!("{ply}"));
 
-        ply.source = square::H3;
-        ply.target = square::H6;
+        let ply = Ply::new(square::H3, square::H6, Piece::Rook, None, true);
         assert_eq!("h3h6", format!("{ply}"));
         
-        ply.source = square::H7;
-        ply.target = square::H8;
-        ply.promotion_piece = Some(Piece::Queen);
+        let ply = Ply::new(square::H7, square::H8, Piece::Pawn, Some(Piece::Queen), false);
         assert_eq!("h7h8q", format!("{ply}"));
     }
 
     #[test]
-    fn from_string_with_invalid_move_returns_none() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
+    fn from_string_with_invalid_move_returns_error() {
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(None, Ply::from_string("nonsense", position));
-        assert_eq!(None, Ply::from_string("a1a1", position));
-        assert_eq!(None, Ply::from_string("e1d1", position));
+        assert_eq!(Err(PlyParseError::WrongLength(String::from("nonsense"))), Ply::from_string("nonsense", position));
+        assert_eq!(Err(PlyParseError::InvalidSquare(SquareParseError::InvalidFile('z'))), Ply::from_string("z2e4", position));
+        assert_eq!(Err(PlyParseError::InvalidPromotionPiece('k')), Ply::from_string("e2e4k", position));
+        assert_eq!(Err(PlyParseError::NoPieceToMove(square::E7)), Ply::from_string("e7e5", position));
+        assert_eq!(Err(PlyParseError::UnexpectedPromotion(String::from("e2e4q"))), Ply::from_string("e2e4q", position));
+        assert_eq!(Err(PlyParseError::IllegalMove(String::from("a1a1"))), Ply::from_string("a1a1", position));
+        assert_eq!(Err(PlyParseError::IllegalMove(String::from("e1d1"))), Ply::from_string("e1d1", position));
 
         let position = Board::from_fen("r1bqk1nr/pppp3p/2n2p2/2b1p2Q/2B1P2N/2P5/PP1P1P1p/RNB1K3 b Qkq - 1 9").unwrap().position;
-        assert_eq!(None, Ply::from_string("h2h1q", position));
+        assert_eq!(Err(PlyParseError::IllegalMove(String::from("h2h1q"))), Ply::from_string("h2h1q", position));
 
         let position = Board::from_fen("r1bqk1nr/pppp1p1p/2n5/2b1p3/2B1P3/2P2N2/PP1P1P1p/RNBQK3 w Qkq - 0 8").unwrap().position;
-        assert_eq!(None, Ply::from_string("b1c3", position));
+        assert_eq!(Err(PlyParseError::IllegalMove(String::from("b1c3"))), Ply::from_string("b1c3", position));
+
+        let position = Board::from_fen("r1bqk1nr/pppp1p1p/2n5/2b1p1N1/2B1P3/2P5/PP1P1P1p/RNBQK3 b Qkq - 1 8").unwrap().position;
+        assert_eq!(Err(PlyParseError::MissingPromotionPiece(String::from("h2h1"))), Ply::from_string("h2h1", position));
+        assert_eq!("Ambiguous move: h2h1 is missing the promotion piece", Ply::from_string("h2h1", position).unwrap_err().to_string());
     }
 
     #[test]
     fn from_string_with_valid_move_returns_ply() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
-        assert_eq!(Some(Ply {
-            source: square::E2,
-            target: square::E4,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        }), Ply::from_string("e2e4", position));
+        assert_eq!(Ok(Ply::new(square::E2, square::E4, Piece::Pawn, None, false)), Ply::from_string("e2e4", position));
+        assert_eq!(Ok(Ply::new(square::G1, square::F3, Piece::Knight, None, false)), Ply::from_string("G1F3", position));
 
         let position = Board::from_fen("r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap().position;
-        assert_eq!(Some(Ply {
-            source: square::E1,
-            target: square::G1,
-            piece: Piece::King,
-            captured_piece: None,
-            promotion_piece: None,
-        }), Ply::from_string("e1g1", position));
+        assert_eq!(Ok(Ply::new(square::E1, square::G1, Piece::King, None, false)), Ply::from_string("e1g1", position));
 
         let position = Board::from_fen("r1bqk1nr/pppp1p1p/2n5/2b1p1N1/2B1P3/2P5/PP1P1P1p/RNBQK3 b Qkq - 1 8").unwrap().position;
-        assert_eq!(Some(Ply {
-            source: square::H2,
-            target: square::H1,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: Some(Piece::Knight),
-        }), Ply::from_string("h2h1n", position));
+        assert_eq!(Ok(Ply::new(square::H2, square::H1, Piece::Pawn, Some(Piece::Knight), false)), Ply::from_string("h2h1n", position));
 
         let position = Board::from_fen("r1bq2nr/1pppk2p/2n2p2/p1b1p3/2B1P2N/2P5/PP1P1P1p/RNB1K1Q1 b Q - 1 11").unwrap().position;
-        assert_eq!(Some(Ply {
-            source: square::H2,
-            target: square::G1,
-            piece: Piece::Pawn,
-            captured_piece: Some(Piece::Queen),
-            promotion_piece: Some(Piece::Queen),
-        }), Ply::from_string("h2g1Q", position));
+        assert_eq!(Ok(Ply::new(square::H2, square::G1, Piece::Pawn, Some(Piece::Queen), true)), Ply::from_string("h2g1Q", position));
+    }
+
+    #[test]
+    fn from_string_with_king_capturing_own_rook_returns_castling() {
+        let position = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap().position;
+        assert_eq!(Ok(Ply::new(square::E1, square::G1, Piece::King, None, false)), Ply::from_string("e1h1", position));
+        assert_eq!(Ok(Ply::new(square::E1, square::C1, Piece::King, None, false)), Ply::from_string("e1a1", position));
+
+        let position = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap().position;
+        assert_eq!(Ok(Ply::new(square::E8, square::G8, Piece::King, None, false)), Ply::from_string("e8h8", position));
+
+        // without the castling rights, the king can't castle either way
+        let position = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1").unwrap().position;
+        assert_eq!(Err(PlyParseError::IllegalMove(String::from("e1h1"))), Ply::from_string("e1h1", position));
+    }
+
+    #[test]
+    fn test_to_san() {
+        // pawn and piece moves
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!("e4", Ply::from_string("e2e4", position).unwrap().to_san(position));
+        assert_eq!("Nf3", Ply::from_string("g1f3", position).unwrap().to_san(position));
+
+        // disambiguation by file, rank, and both
+        let position = Board::from_fen("4k3/8/8/8/8/2N3N1/8/4K3 w - - 0 1").unwrap().position;
+        assert_eq!("Nce2", Ply::from_string("c3e2", position).unwrap().to_san(position));
+        let position = Board::from_fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1").unwrap().position;
+        assert_eq!("R1a3", Ply::from_string("a1a3", position).unwrap().to_san(position));
+        let position = Board::from_fen("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1").unwrap().position;
+        assert_eq!("Qa1b2", Ply::from_string("a1b2", position).unwrap().to_san(position));
+
+        // castling
+        let position = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap().position;
+        assert_eq!("O-O", Ply::from_string("e1g1", position).unwrap().to_san(position));
+        assert_eq!("O-O-O", Ply::from_string("e1c1", position).unwrap().to_san(position));
+
+        // promotions, checks and en passant
+        let position = Board::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap().position;
+        assert_eq!("axb8=Q+", Ply::from_string("a7b8q", position).unwrap().to_san(position));
+        assert_eq!("a8=N", Ply::from_string("a7a8n", position).unwrap().to_san(position));
+        let position = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap().position;
+        assert_eq!("exd6", Ply::from_string("e5d6", position).unwrap().to_san(position));
+
+        // checkmate
+        let position = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 2 4").unwrap().position;
+        assert_eq!("Qxf7#", Ply::from_string("h5f7", position).unwrap().to_san(position));
+    }
+
+    #[test]
+    fn test_from_san() {
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(Ply::from_string("e2e4", position).ok(), Ply::from_san("e4", position));
+        assert_eq!(Ply::from_string("g1f3", position).ok(), Ply::from_san("Nf3", position));
+        assert_eq!(Ply::from_string("g1f3", position).ok(), Ply::from_san("Ngf3!?", position));
+        assert_eq!(None, Ply::from_san("e5", position));
+        assert_eq!(None, Ply::from_san("Nxf3", position));
+        assert_eq!(None, Ply::from_san("nonsense", position));
+        assert_eq!(None, Ply::from_san("", position));
+
+        // ambiguous moves are rejected
+        let position = Board::from_fen("4k3/8/8/8/8/2N3N1/8/4K3 w - - 0 1").unwrap().position;
+        assert_eq!(None, Ply::from_san("Ne2", position));
+        assert_eq!(Ply::from_string("g3e2", position).ok(), Ply::from_san("Nge2", position));
+        let position = Board::from_fen("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1").unwrap().position;
+        assert_eq!(None, Ply::from_san("Qab2", position));
+        assert_eq!(Ply::from_string("a3b2", position).ok(), Ply::from_san("Q3b2", position));
+        assert_eq!(Ply::from_string("a1b2", position).ok(), Ply::from_san("Qa1b2", position));
+
+        // castling
+        let position = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap().position;
+        assert_eq!(Ply::from_string("e1g1", position).ok(), Ply::from_san("O-O", position));
+        assert_eq!(Ply::from_string("e1c1", position).ok(), Ply::from_san("0-0-0", position));
+
+        // promotions
+        let position = Board::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap().position;
+        assert_eq!(Ply::from_string("a7b8q", position).ok(), Ply::from_san("axb8=Q+", position));
+        assert_eq!(Ply::from_string("a7a8n", position).ok(), Ply::from_san("a8N", position));
+        assert_eq!(None, Ply::from_san("a8", position));
+
+        // every legal move survives a round trip
+        let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
+        let move_list = move_gen::generate_moves(position);
+        for i in 0..move_list.len() {
+            let ply = move_list.get(i);
+            assert_eq!(Some(ply), Ply::from_san(ply.to_san(position).as_str(), position));
+        }
     }
-}
\ No newline at end of file
+}
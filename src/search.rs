@@ -1,32 +1,282 @@
-use std::sync::mpsc::{Receiver, Sender};
-use std::time::{Duration, Instant};
+use std::any::Any;
+use std::cell::Cell;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::time::Duration;
 use arrayvec::ArrayVec;
 use crate::board::Board;
 use crate::board::piece::NUM_PIECES;
 use crate::board::position::Position;
 use crate::board::square::NUM_SQUARES;
+use crate::epd::EpdEntry;
+use crate::evaluation::params;
+use crate::evaluation::params::EvalParams;
+use crate::evaluation::wdl;
 use crate::ladybug::Message;
 use crate::move_gen;
 use crate::move_gen::ply::Ply;
+use crate::search::bench::BenchBaseline;
+use crate::search::clock::Instant;
+use crate::search::engine_match::MatchSettings;
+use crate::search::perft_suite::PerftEntry;
+use crate::search::selfplay::SelfPlaySettings;
+use crate::search::opening_variety::{get_random_seed, OPENING_VARIETY_MARGIN};
+use crate::search::ponder::DEFAULT_PONDER_CREDIT;
+use crate::search::skill_level::MAX_SKILL_LEVEL;
+use crate::search::eval_cache::EvalCache;
+use crate::search::negamax::{DEFAULT_REVERSE_FUTILITY_DEPTH, DEFAULT_REVERSE_FUTILITY_MARGIN};
+use crate::search::transposition_table::{TranspositionTable, DEFAULT_HASH_SIZE};
 
+pub mod adjudication;
+pub mod bench;
+pub mod clock;
+pub mod engine_match;
+pub mod eval_cache;
 pub mod perft;
+pub mod perft_diff;
+pub mod perft_suite;
+pub mod test_suite;
 pub mod negamax;
+pub mod opening_variety;
+pub mod pns;
+pub mod ponder;
+pub mod root_split;
+pub mod selfplay;
+pub mod skill_level;
+pub mod speedtest;
+pub mod time_manager;
+pub mod transposition_table;
 mod quiescence_search;
 
 /// The maximum number of plies Ladybug is able to search.
 /// This number shouldn't ever be reached.
 pub const MAX_PLY: usize = 100;
 
+/// The best move that is sent if there is no legal move, since the GUI waits for a best move after every search.
+pub const NULL_BEST_MOVE: &str = "bestmove 0000";
+
+/// The number of plies by which the killer moves are moved up between the searches of a game.
+pub const AGED_KILLER_PLIES: usize = 2;
+
 /// Encodes the commands the search can receive from Ladybug.
 pub enum SearchCommand {
     /// Search the given position for the given amount of milliseconds.
     SearchTime(Board, ArrayVec<u64, 1000>, u64),
+    /// Search the given position with the time of a clock: the search takes about the first amount of milliseconds,
+    /// less if the best move is stable and more if it keeps changing, but never more than the second amount.
+    SearchClock(Board, ArrayVec<u64, 1000>, u64, u64),
     /// Search the given position until the given depth is reached.
     SearchDepth(Board, ArrayVec<u64, 1000>, u64),
+    /// Search the given position until the given number of nodes is searched.
+    SearchNodes(Board, ArrayVec<u64, 1000>, u64),
+    /// Ponder on the given position until the ponderhit, and search it for the given amount of milliseconds afterwards.
+    Ponder(Board, ArrayVec<u64, 1000>, u64),
     /// Perform a perft for the given position up to the specified depth.
     Perft(Position, u64),
-    /// Stop the search immediately.
-    Stop,
+    /// Search for a mate in the given number of moves with proof-number search.
+    Mate(Position, u64),
+    /// Run the given EPD test suite, searching each position for the given amount of milliseconds.
+    Test(Vec<EpdEntry>, u64),
+    /// Run the given perft suite.
+    PerftSuite(Vec<PerftEntry>),
+    /// Find the first position in which a perft of the given board to the given depth differs from the one of the external engine at the given path.
+    PerftDiff(Board, u64, String),
+    /// Play games of Ladybug against herself with the given settings.
+    SelfPlay(SelfPlaySettings),
+    /// Play a match against an external engine with the given settings.
+    Match(MatchSettings),
+    /// Search the bench positions to the given depth.
+    Bench(u64),
+    /// Search the bench positions and compare the result to the given baseline.
+    BenchCompare(BenchBaseline),
+    /// Measure the speed of each phase on the bench positions, perfting and searching them to the given depth.
+    Speedtest(u64),
+    /// Enable or disable debug output.
+    Debug(bool),
+    /// Set the number of moves at the beginning of the game in which the best move is picked randomly among the best root moves.
+    OpeningVariety(u32),
+    /// Set the margin in centipawns within which the best move is picked randomly among the best root moves throughout the game.
+    Variety(i32),
+    /// Set the skill level, which weakens the search below the maximum skill level.
+    SkillLevel(u32),
+    /// Set the contempt in centipawns, which is subtracted from the score of draws from Ladybug's point of view.
+    Contempt(i32),
+    /// Enable or disable the win, draw and loss probabilities in the info lines of the search.
+    ShowWdl(bool),
+    /// Enable or disable the expected reply in the best move of a search, which the GUI may let Ladybug ponder on.
+    ShowPonderMove(bool),
+    /// Set the percentage of the time spent pondering that counts towards the time of the move after a ponderhit.
+    PonderCredit(u32),
+    /// Resize the transposition table to the given number of megabytes.
+    HashSize(usize),
+    /// Remove all entries from the transposition table.
+    ClearHash,
+    /// Report the statistics of the transposition table.
+    HashStats,
+    /// Set the number of threads that perft and searches to a fixed depth split the root moves among.
+    Threads(usize),
+    /// Replace the evaluation parameters used by the next searches.
+    EvalParams(Box<EvalParams>),
+    /// Enable or disable the given search feature.
+    Feature(SearchFeature, bool),
+    /// Set the margin in centipawns per ply by which the static evaluation has to beat beta for reverse futility pruning.
+    ReverseFutilityMargin(i32),
+    /// Set the maximum depth at which reverse futility pruning is tried.
+    ReverseFutilityDepth(u64),
+    /// Exclude the given move from the next searches, or stop excluding a move if None is given.
+    ExcludeMove(Option<Ply>),
+    /// Restrict the next searches to the given root moves, or search all root moves if no moves are given.
+    SearchMoves(Vec<Ply>),
+    /// Forget the results of the previous searches, because the next searches belong to a new game.
+    NewGame,
+    /// Terminate the search thread.
+    Quit,
+}
+
+impl SearchCommand {
+    /// Returns the board of the command, if it is a search that sends a best move.
+    pub fn search_board(&self) -> Option<Board> {
+        match self {
+            SearchCommand::SearchTime(board, ..) | SearchCommand::SearchClock(board, ..) | SearchCommand::SearchDepth(board, ..)
+            | SearchCommand::SearchNodes(board, ..) | SearchCommand::Ponder(board, ..) => Some(*board),
+            _other => None,
+        }
+    }
+
+    /// Returns whether the command starts a task that can be stopped with the stop flag, e.g. a search.
+    pub fn starts_task(&self) -> bool {
+        matches!(self, SearchCommand::Perft(..) | SearchCommand::Mate(..) | SearchCommand::Test(..) | SearchCommand::PerftSuite(..) | SearchCommand::PerftDiff(..)
+            | SearchCommand::SelfPlay(..) | SearchCommand::Match(..) | SearchCommand::Bench(..) | SearchCommand::BenchCompare(..) | SearchCommand::Speedtest(..)
+            | SearchCommand::SearchTime(..) | SearchCommand::SearchClock(..) | SearchCommand::SearchDepth(..) | SearchCommand::SearchNodes(..) | SearchCommand::Ponder(..))
+    }
+}
+
+/// The stages of the search that searched nodes are attributed to.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SearchStage {
+    /// The main alpha-beta search.
+    Main = 0,
+    /// The quiescence search at the leaves of the main search.
+    Quiescence = 1,
+    /// The verification searches of ProbCut, including their quiescence searches.
+    ProbCut = 2,
+}
+
+/// The number of search stages.
+pub const NUM_SEARCH_STAGES: u8 = 3;
+
+impl SearchStage {
+    /// All search stages, in the order they are reported.
+    pub const ALL: [SearchStage; NUM_SEARCH_STAGES as usize] = [SearchStage::Main, SearchStage::Quiescence, SearchStage::ProbCut];
+
+    /// Returns the index of the search stage.
+    pub fn to_index(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Returns the name of the search stage, as used in the debug output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchStage::Main => "main",
+            SearchStage::Quiescence => "qsearch",
+            SearchStage::ProbCut => "probcut",
+        }
+    }
+}
+
+/// The extensions that can extend the search of a move beyond the nominal depth.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Extension {
+    /// Moves that give check are extended, since the opponent's replies are forced.
+    Check = 0,
+    /// Recaptures of a piece of roughly equal value are extended, since the exchange has to be resolved before the position can be judged.
+    Recapture = 1,
+}
+
+/// The number of extensions.
+pub const NUM_EXTENSIONS: u8 = 2;
+
+impl Extension {
+    /// All extensions, in the order they are reported.
+    pub const ALL: [Extension; NUM_EXTENSIONS as usize] = [Extension::Check, Extension::Recapture];
+
+    /// Returns the index of the extension.
+    pub fn to_index(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Returns the name of the extension, as used in the debug output.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Extension::Check => "check",
+            Extension::Recapture => "recapture",
+        }
+    }
+}
+
+/// The features of the search that can be disabled, so that their effect can be measured in matches without recompiling.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SearchFeature {
+    /// Extend moves that give check.
+    CheckExtension = 0,
+    /// Extend recaptures.
+    RecaptureExtension = 1,
+    /// Cut off the search with the scores stored in the transposition table.
+    TranspositionTable = 2,
+    /// Search the quiet moves that caused beta cutoffs in sibling nodes first.
+    KillerMoves = 3,
+    /// Search the quiet moves that raised alpha in other positions first.
+    HistoryMoves = 4,
+    /// Prune nodes in which a capture beats beta by a margin in a reduced search.
+    ProbCut = 5,
+    /// Reduce the depth of nodes that have no move stored in the transposition table.
+    InternalIterativeReduction = 6,
+    /// Prune shallow nodes whose static evaluation beats beta by a margin that grows with the depth.
+    ReverseFutilityPruning = 7,
+}
+
+/// The number of search features.
+pub const NUM_SEARCH_FEATURES: u8 = 8;
+
+impl SearchFeature {
+    /// All search features.
+    pub const ALL: [SearchFeature; NUM_SEARCH_FEATURES as usize] = [
+        SearchFeature::CheckExtension,
+        SearchFeature::RecaptureExtension,
+        SearchFeature::TranspositionTable,
+        SearchFeature::KillerMoves,
+        SearchFeature::HistoryMoves,
+        SearchFeature::ProbCut,
+        SearchFeature::InternalIterativeReduction,
+        SearchFeature::ReverseFutilityPruning,
+    ];
+
+    /// Returns the index of the search feature.
+    pub fn to_index(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Returns the name of the hidden UCI option that enables or disables the search feature.
+    pub fn option_name(&self) -> &'static str {
+        match self {
+            SearchFeature::CheckExtension => "CheckExtension",
+            SearchFeature::RecaptureExtension => "RecaptureExtension",
+            SearchFeature::TranspositionTable => "TranspositionTable",
+            SearchFeature::KillerMoves => "KillerMoves",
+            SearchFeature::HistoryMoves => "HistoryMoves",
+            SearchFeature::ProbCut => "ProbCut",
+            SearchFeature::InternalIterativeReduction => "InternalIterativeReduction",
+            SearchFeature::ReverseFutilityPruning => "ReverseFutilityPruning",
+        }
+    }
+
+    /// Returns the search feature with the given option name, ignoring the case.
+    pub fn from_option_name(name: &str) -> Option<SearchFeature> {
+        SearchFeature::ALL.into_iter().find(|feature| feature.option_name().eq_ignore_ascii_case(name))
+    }
 }
 
 /// The search struct is responsible for performing all tasks involving calculation and search.
@@ -34,19 +284,113 @@ pub struct Search {
     /// Used to receive search commands from Ladybug.
     command_receiver: Receiver<SearchCommand>,
     /// Used to send search results to Ladybug.
-    message_sender: Sender<Message>,
+    message_sender: SyncSender<Message>,
     /// Used to measure the total expired time across all iterations during search.
     total_time: Option<Instant>,
+    /// The maximum number of nodes the current search may search across all iterations, if any.
+    node_limit: Option<u128>,
+    /// The time after which the current search doesn't start another iteration, before it is scaled by the stability of the best move.
+    /// Only searches with a clock have an optimum time.
+    optimum_time: Option<Duration>,
+    /// The maximum number of nodes the current iteration may search, which is what remains of the node limit.
+    iteration_node_limit: Option<u128>,
     /// Flag to signal that the search should stop immediately.
     stop: bool,
+    /// Flag shared with Ladybug, which sets it to stop the current task as soon as possible.
+    stop_flag: Arc<AtomicBool>,
+    /// Flag to signal that the search thread should terminate, because Ladybug quit or closed the connection.
+    quit: Cell<bool>,
+    /// Whether the search runs on the thread that reads its messages, like in WebAssembly builds,
+    /// so that nothing empties the message queue while the search is running.
+    synchronous: bool,
+    /// The best move a synchronous search couldn't send, because the message queue was full.
+    unsent_best_move: Cell<Option<String>>,
+    /// Flag shared with Ladybug, which is set while the current search ponders, and cleared by Ladybug on the ponderhit.
+    ponder_flag: Arc<AtomicBool>,
+    /// Whether the current search ponders, in which case its time limit does not apply yet.
+    pondering: bool,
+    /// The percentage of the time spent pondering that counts towards the time of the move after a ponderhit.
+    ponder_credit: u32,
+    /// Whether the best move of a search includes the expected reply, which the GUI may let Ladybug ponder on.
+    show_ponder_move: bool,
+    /// The expected reply to the best move of the most recent search, if the principal variation contains one.
+    ponder_move: Option<Ply>,
     /// Contains information collected and used during the search.
     search_info: SearchInfo,
+    /// Stores the results of previously searched positions.
+    transposition_table: TranspositionTable,
+    /// Stores the static evaluations of recently evaluated positions.
+    eval_cache: EvalCache,
+    /// The memory in megabytes set with the "Hash" option, which the transposition tables of all threads share.
+    hash_size: usize,
+    /// The transposition tables and evaluation caches of the threads of the root split but the first one,
+    /// which uses those of this search. They are only allocated again when the "Hash" or "Threads" option changes.
+    split_tables: Vec<(TranspositionTable, EvalCache)>,
+    /// Whether additional debug information should be sent during the search.
+    debug: bool,
+    /// The number of moves at the beginning of the game in which the best move is picked randomly among the best root moves.
+    opening_variety: u32,
+    /// The margin in centipawns within which the best move is picked randomly among the best root moves throughout the game,
+    /// or 0 if the best move is always played.
+    variety: i32,
+    /// The skill level, which weakens the search below the maximum skill level.
+    skill_level: u32,
+    /// The contempt in centipawns, which is subtracted from the score of draws from Ladybug's point of view,
+    /// so that she avoids draws against weaker opponents.
+    contempt: i32,
+    /// Whether the info lines of the search show the win, draw and loss probabilities of the score.
+    show_wdl: bool,
+    /// The margin in centipawns within which the current search picks its best move randomly among the root moves,
+    /// or 0 if the best move is always played.
+    root_move_margin: i32,
+    /// The state of the random number generator used to pick the best move among the best root moves.
+    random_state: u64,
+    /// The move that is excluded from the searches of the current position, if any.
+    excluded_move: Option<Ply>,
+    /// The root move that is excluded from the current search, if any.
+    excluded_root_move: Option<Ply>,
+    /// The root moves the searches are restricted to, or an empty list if all root moves are searched.
+    search_moves: Vec<Ply>,
+    /// The root moves the current search is restricted to, or an empty list if all root moves are searched.
+    searched_root_moves: Vec<Ply>,
+    /// Whether each search feature is enabled.
+    features: [bool; NUM_SEARCH_FEATURES as usize],
+    /// The margin in centipawns per ply of depth by which the static evaluation has to beat beta for reverse futility pruning.
+    reverse_futility_margin: i32,
+    /// The maximum depth at which reverse futility pruning is tried.
+    reverse_futility_depth: u64,
+    /// The number of threads that perft and searches to a fixed depth split the root moves among.
+    threads: usize,
+    /// The score of the last completed iteration of the most recent search from the point of view of the side to move,
+    /// or None if no iteration was completed.
+    last_score: Option<i32>,
 }
 
 /// Contains information collected and used during the search.
 pub struct SearchInfo {
     /// The number of nodes evaluated during the current iteration of the search.
     pub node_count: u128,
+    /// The highest ply index reached during the current iteration of the search, including the quiescence search.
+    pub seldepth: u64,
+    /// The number of nodes searched in each search stage, across all iterations of the search.
+    pub stage_node_counts: [u128; NUM_SEARCH_STAGES as usize],
+    /// The stage of the verification search that is currently running, if any.
+    /// All nodes of a verification search are attributed to its stage, instead of the main or the quiescence search.
+    pub verification_stage: Option<SearchStage>,
+    /// The number of times each extension was applied, across all iterations of the search.
+    pub extension_counts: [u128; NUM_EXTENSIONS as usize],
+    /// The number of extensions that were denied because the extension budget of the branch was used up.
+    pub denied_extensions: u128,
+    /// The number of nodes pruned by ProbCut, across all iterations of the search.
+    pub probcut_cutoffs: u128,
+    /// The number of nodes reduced because they had no hash move, across all iterations of the search.
+    pub iir_reductions: u128,
+    /// The number of nodes pruned by reverse futility pruning, across all iterations of the search.
+    pub reverse_futility_cutoffs: u128,
+    /// The number of static evaluations that were looked up in the evaluation cache, across all iterations of the search.
+    pub eval_cache_probes: u128,
+    /// The number of static evaluations that were found in the evaluation cache, across all iterations of the search.
+    pub eval_cache_hits: u128,
     /// Stores the lengths of the principe variations.
     pub pv_length: [u8; MAX_PLY],
     /// Stores the principle variations.
@@ -58,6 +402,8 @@ pub struct SearchInfo {
     pub history_moves: [[i32; NUM_SQUARES as usize]; NUM_PIECES as usize],
     /// This flag signals whether the search is currently following the pv line from the previous iteration.
     pub follow_pv: bool,
+    /// The scores of the root moves during the current iteration, if the root move is varied.
+    pub root_scores: Vec<(Ply, i32)>,
 }
 
 impl Default for SearchInfo {
@@ -65,6 +411,16 @@ impl Default for SearchInfo {
     fn default() -> Self {
         Self {
             node_count: 0,
+            seldepth: 0,
+            stage_node_counts: [0; NUM_SEARCH_STAGES as usize],
+            verification_stage: None,
+            extension_counts: [0; NUM_EXTENSIONS as usize],
+            denied_extensions: 0,
+            probcut_cutoffs: 0,
+            iir_reductions: 0,
+            reverse_futility_cutoffs: 0,
+            eval_cache_probes: 0,
+            eval_cache_hits: 0,
             pv_length: [0; MAX_PLY],
             // initialize the pv table with null moves (a1 to a1)
             pv_table: [[Ply::default(); MAX_PLY];MAX_PLY],
@@ -72,21 +428,75 @@ impl Default for SearchInfo {
             killer_moves: [[Ply::default(); MAX_PLY]; 2],
             history_moves: [[0; NUM_SQUARES as usize]; NUM_PIECES as usize],
             follow_pv: true,
+            root_scores: Vec::new(),
         }
     }
 }
 
 impl SearchInfo {
+    /// Counts a node searched in the given stage at the given ply index.
+    /// If a verification search is running, the node is attributed to the stage of the verification search instead.
+    pub fn count_node(&mut self, stage: SearchStage, ply_index: u64) {
+        let stage = self.verification_stage.unwrap_or(stage);
+        self.node_count += 1;
+        self.stage_node_counts[stage.to_index() as usize] += 1;
+        self.seldepth = self.seldepth.max(ply_index);
+    }
+
+    /// Returns the number of plies a move is extended by, if the given extension applies to it.
+    ///
+    /// Each branch of the search tree has an extension budget, so that extensions can't compound into a search explosion.
+    /// If the budget of the branch is used up, the extension is denied.
+    pub fn extend(&mut self, extension: Extension, extension_budget: u64) -> u64 {
+        if extension_budget == 0 {
+            self.denied_extensions += 1;
+            return 0;
+        }
+        self.extension_counts[extension.to_index() as usize] += 1;
+        1
+    }
+
     /// Clears the search information that is not relevant for the next iteration.
     pub fn clear_iteration(&mut self) {
         self.node_count = 0;
+        self.seldepth = 0;
         self.pv_length = [0; MAX_PLY];
         self.follow_pv = true;
+        self.root_scores.clear();
     }
 
-    /// Clears all search information.
+    /// Clears all search information, except for the killer and history moves, which are kept for the next search of the game.
     pub fn clear_all(&mut self) {
         self.clear_iteration();
+        self.stage_node_counts = [0; NUM_SEARCH_STAGES as usize];
+        self.verification_stage = None;
+        self.extension_counts = [0; NUM_EXTENSIONS as usize];
+        self.denied_extensions = 0;
+        self.probcut_cutoffs = 0;
+        self.iir_reductions = 0;
+        self.reverse_futility_cutoffs = 0;
+        self.eval_cache_probes = 0;
+        self.eval_cache_hits = 0;
+        // the pv of the previous search must not influence the move ordering of the next one
+        self.pv_table = [[Ply::default(); MAX_PLY]; MAX_PLY];
+    }
+
+    /// Ages the killer and history moves, so that the next search of the game can use them for its move ordering.
+    ///
+    /// The next search usually starts two plies after the root of this one, so the killer moves are moved up by two plies.
+    /// The history scores are halved, so that the moves found by the next search soon outweigh the older ones.
+    pub fn age_heuristics(&mut self) {
+        for killer_moves in self.killer_moves.iter_mut() {
+            killer_moves.rotate_left(AGED_KILLER_PLIES);
+            killer_moves[MAX_PLY - AGED_KILLER_PLIES..].fill(Ply::default());
+        }
+        for score in self.history_moves.iter_mut().flatten() {
+            *score /= 2;
+        }
+    }
+
+    /// Clears the killer and history moves, e.g. at the start of a new game.
+    pub fn clear_heuristics(&mut self) {
         self.killer_moves = [[Ply::default(); MAX_PLY]; 2];
         self.history_moves = [[0; NUM_SQUARES as usize]; NUM_PIECES as usize];
     }
@@ -94,54 +504,212 @@ impl SearchInfo {
 
 impl Search {
     /// Constructs a new search instance.
-    pub fn new(input_receiver: Receiver<SearchCommand>, output_sender: Sender<Message>) -> Self {
+    pub fn new(input_receiver: Receiver<SearchCommand>, output_sender: SyncSender<Message>) -> Self {
         Self {
             command_receiver: input_receiver,
             message_sender: output_sender,
             total_time: None,
+            node_limit: None,
+            optimum_time: None,
+            iteration_node_limit: None,
             stop: true,
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            quit: Cell::new(false),
+            synchronous: false,
+            unsent_best_move: Cell::new(None),
+            ponder_flag: Arc::new(AtomicBool::new(false)),
+            pondering: false,
+            ponder_credit: DEFAULT_PONDER_CREDIT,
+            show_ponder_move: false,
+            ponder_move: None,
             search_info: SearchInfo::default(),
+            transposition_table: TranspositionTable::default(),
+            eval_cache: EvalCache::default(),
+            hash_size: DEFAULT_HASH_SIZE,
+            split_tables: Vec::new(),
+            debug: false,
+            opening_variety: 0,
+            variety: 0,
+            skill_level: MAX_SKILL_LEVEL,
+            contempt: 0,
+            show_wdl: false,
+            root_move_margin: 0,
+            random_state: get_random_seed(),
+            excluded_move: None,
+            excluded_root_move: None,
+            search_moves: Vec::new(),
+            searched_root_moves: Vec::new(),
+            features: [true; NUM_SEARCH_FEATURES as usize],
+            reverse_futility_margin: DEFAULT_REVERSE_FUTILITY_MARGIN,
+            reverse_futility_depth: DEFAULT_REVERSE_FUTILITY_DEPTH,
+            threads: 1,
+            last_score: None,
         }
     }
 
-    /// Start accepting search commands from Ladybug.
+    /// Start accepting search commands from Ladybug, until Ladybug quits or closes the connection.
+    ///
+    /// If a command panics because of a bug, the error is reported to the GUI, and a search still sends a legal best move,
+    /// so that the search thread keeps running and the GUI doesn't wait for a best move forever.
     pub fn run(&mut self) {
-        loop {
-            // blocks until the search receives a command from Ladybug
-            let input = self.command_receiver.recv();
+        while !self.quit.get() {
+            // block until the search receives a command from Ladybug
+            let Ok(command) = self.command_receiver.recv() else {
+                // if the main thread closes the connection, the search thread must not continue running
+                return;
+            };
 
-            // if the main thread closes the connection, the search thread must not continue running
-            if input.is_err() {
+            // the search may be run again after it quit, e.g. by the engine, so quitting doesn't set the quit flag
+            if matches!(command, SearchCommand::Quit) {
                 return;
             }
 
-            // get the input string from the result
-            let command = input.unwrap();
-            
-            match command { 
-                SearchCommand::Perft(position, depth) => self.handle_perft(position, depth),
-                SearchCommand::SearchTime(board, board_history, time) => self.handle_search(board, None, Some(time), board_history),
-                SearchCommand::SearchDepth(board, board_history, depth) => self.handle_search(board, Some(depth), None, board_history),
-                _other => {},
+            let search_board = command.search_board();
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| self.handle_command(command))) {
+                self.handle_panic(payload.as_ref(), search_board);
+            }
+        }
+    }
+
+    /// Handles the given command from Ladybug.
+    fn handle_command(&mut self, command: SearchCommand) {
+        match command {
+            // quitting ends the run loop before the command is handled
+            SearchCommand::Quit => {}
+            SearchCommand::NewGame => self.new_game(),
+            SearchCommand::Perft(position, depth) => self.handle_perft(position, depth),
+            SearchCommand::Mate(position, moves) => self.handle_mate(position, moves),
+            SearchCommand::Test(entries, time) => self.handle_test(entries, time),
+            SearchCommand::PerftSuite(entries) => self.handle_perft_suite(entries),
+            SearchCommand::PerftDiff(board, depth, engine_path) => self.handle_perft_diff(board, depth, engine_path),
+            SearchCommand::SelfPlay(settings) => self.handle_selfplay(settings),
+            SearchCommand::Match(settings) => self.handle_match(settings),
+            SearchCommand::Bench(depth) => self.handle_bench(depth),
+            SearchCommand::BenchCompare(baseline) => self.handle_bench_compare(baseline),
+            SearchCommand::Speedtest(depth) => self.speedtest(depth),
+            SearchCommand::Debug(debug) => self.debug = debug,
+            SearchCommand::OpeningVariety(moves) => self.opening_variety = moves,
+            SearchCommand::Variety(margin) => self.variety = margin,
+            SearchCommand::SkillLevel(skill_level) => self.skill_level = skill_level,
+            SearchCommand::Contempt(contempt) => self.contempt = contempt,
+            SearchCommand::ShowWdl(show_wdl) => self.show_wdl = show_wdl,
+            SearchCommand::ShowPonderMove(show_ponder_move) => self.show_ponder_move = show_ponder_move,
+            SearchCommand::PonderCredit(credit) => self.ponder_credit = credit,
+            SearchCommand::ExcludeMove(ply) => self.excluded_move = ply,
+            SearchCommand::SearchMoves(plies) => self.search_moves = plies,
+            SearchCommand::HashSize(size_mb) => self.handle_hash_size(size_mb),
+            SearchCommand::ClearHash => self.clear_hash(),
+            SearchCommand::HashStats => self.handle_hash_stats(),
+            SearchCommand::Threads(threads) => self.handle_threads(threads),
+            SearchCommand::EvalParams(eval_params) => {
+                params::set(*eval_params);
+                // the cached evaluations and the search results were made with the previous parameters
+                self.eval_cache.clear();
+                self.split_tables.iter_mut().for_each(|(_transposition_table, eval_cache)| eval_cache.clear());
+                self.new_game();
+            }
+            SearchCommand::Feature(feature, enabled) => self.features[feature.to_index() as usize] = enabled,
+            SearchCommand::ReverseFutilityMargin(margin) => self.reverse_futility_margin = margin,
+            SearchCommand::ReverseFutilityDepth(depth) => self.reverse_futility_depth = depth,
+            SearchCommand::SearchTime(board, board_history, time) => self.handle_search(board, None, Some(time), None, board_history),
+            SearchCommand::SearchClock(board, board_history, optimum_time, max_time) => {
+                self.optimum_time = Some(Duration::from_millis(optimum_time));
+                self.handle_search(board, None, Some(max_time), None, board_history);
+                self.optimum_time = None;
+            }
+            SearchCommand::Ponder(board, board_history, time) => self.handle_ponder(board, time, board_history),
+            SearchCommand::SearchDepth(board, board_history, depth) => self.handle_search(board, Some(depth), None, None, board_history),
+            SearchCommand::SearchNodes(board, board_history, nodes) => self.handle_search(board, None, None, Some(nodes), board_history),
+        }
+    }
+
+    /// Reports the panic of a command with the given payload, and sends a legal best move if the command was a search of the given board.
+    /// The state of the failed command is reset, so that the next commands are handled as usual.
+    fn handle_panic(&mut self, payload: &(dyn Any + Send), search_board: Option<Board>) {
+        let message = match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+            (Some(message), _) => message.to_string(),
+            (None, Some(message)) => message.clone(),
+            (None, None) => String::from("unknown panic"),
+        };
+        self.send_output(format!("info string error: {message}"));
+
+        self.total_time = None;
+        self.node_limit = None;
+        self.iteration_node_limit = None;
+        self.optimum_time = None;
+        self.root_move_margin = 0;
+        self.excluded_root_move = None;
+        self.searched_root_moves.clear();
+        self.search_info.clear_all();
+        self.search_info.clear_heuristics();
+
+        // a panic during the root split loses the tables that were lent to its threads
+        if self.transposition_table.is_empty() || self.eval_cache.is_empty() {
+            self.eval_cache = EvalCache::default();
+            if self.allocate_tables(self.hash_size, self.threads).is_err() {
+                self.threads = 1;
+                self.transposition_table = TranspositionTable::new(1);
             }
         }
+
+        let Some(board) = search_board else {
+            self.pondering = false;
+            return;
+        };
+        // the move generation may be what failed, in which case there is no move to fall back on
+        let output = panic::catch_unwind(AssertUnwindSafe(|| fallback_best_move(board.position))).unwrap_or(String::from(NULL_BEST_MOVE));
+        self.wait_for_ponderhit();
+        self.send_output(output);
     }
 
     /// Sends the given String to the main thread.
+    ///
+    /// A synchronous search must not wait for space in a full queue, since it would wait forever. It drops the output instead,
+    /// except for the best move, which it keeps until it is taken with `take_unsent_best_move`.
     fn send_output(&self, output: String) {
+        if self.synchronous {
+            match self.message_sender.try_send(Message::SearchMessage(output)) {
+                Err(TrySendError::Full(Message::SearchMessage(output))) if output.starts_with("bestmove") => self.unsent_best_move.set(Some(output)),
+                Err(TrySendError::Disconnected(_message)) => self.quit.set(true),
+                _other => {}
+            }
+            return;
+        }
+
         let send_result = self.message_sender.send(Message::SearchMessage(output));
 
         // if the main thread closes the connection, the search thread must not continue running
         if send_result.is_err() {
-            panic!("The main thread has unexpectedly closed the channel connection.")
+            self.quit.set(true);
+        }
+    }
+
+    /// Sends the given info String to the main thread, unless the main thread is lagging behind.
+    ///
+    /// Info messages are superseded by the ones of the next iteration, so if the message queue is full,
+    /// the message is dropped instead of blocking the search or letting the queue grow.
+    fn send_info(&self, output: String) {
+        let send_result = self.message_sender.try_send(Message::SearchMessage(output));
+
+        // if the main thread closes the connection, the search thread must not continue running
+        if let Err(TrySendError::Disconnected(_)) = send_result {
+            self.quit.set(true);
         }
     }
 
     /// Handles the various "Search" commands.
-    fn handle_search(&mut self, board: Board, depth_limit: Option<u64>, time_limit: Option<u64>, board_history: ArrayVec<u64, 1000>) {
+    fn handle_search(&mut self, board: Board, depth_limit: Option<u64>, time_limit: Option<u64>, node_limit: Option<u64>, board_history: ArrayVec<u64, 1000>) {
+        // the excluded move and the search moves only apply to searches requested by the user, not to the bench or test suites
+        self.excluded_root_move = self.excluded_move;
+        self.searched_root_moves = self.search_moves.clone();
+
         let move_list = move_gen::generate_moves(board.position);
-        if move_list.is_empty() {
+        if !(0..move_list.len()).any(|i| self.is_searched_root_move(move_list.get(i))) {
+            self.excluded_root_move = None;
+            self.searched_root_moves.clear();
             self.send_output(String::from("info string no legal moves"));
+            self.wait_for_ponderhit();
+            self.send_output(String::from(NULL_BEST_MOVE));
             return;
         }
 
@@ -149,33 +717,276 @@ impl Search {
         let depth_limit = depth_limit.unwrap_or(MAX_PLY as u64);
 
         // check if a time limit was provided
-        let time_limit = match time_limit {
-            // if no time limit ws provided, use a default limit of 72 hours
-            None => Duration::from_secs(72 * 60 * 60),
-            Some(time) => Duration::from_millis(time),
+        // without a time limit, the search never stops because of the clock, so that searches to a fixed depth are reproducible
+        let time_limit = time_limit.map(Duration::from_millis);
+
+        self.root_move_margin = self.get_root_move_margin(&board);
+
+        // below the maximum skill level, the search depth is limited as well
+        let depth_limit = skill_level::skill_depth(self.skill_level).map_or(depth_limit, |skill_depth| depth_limit.min(skill_depth));
+
+        // the node limit makes the search reproducible, since it does not depend on the speed of the machine
+        self.node_limit = node_limit.map(|nodes| nodes as u128);
+
+        // with several threads, searches to a fixed depth are split at the root, while all other searches use a single thread
+        let split_result = match self.threads > 1 && time_limit.is_none() && node_limit.is_none() && self.root_move_margin == 0 {
+            true => self.split_search(board, depth_limit, &board_history),
+            false => None,
+        };
+        self.ponder_move = None;
+        let (best_move, _node_count) = match split_result {
+            Some(result) => result,
+            None => self.iterative_search(board, depth_limit, time_limit, board_history),
         };
+        self.root_move_margin = 0;
+        self.excluded_root_move = None;
+        self.searched_root_moves.clear();
+        self.node_limit = None;
 
-        self.iterative_search(board, depth_limit, time_limit, board_history);
+        // send the best move to the main thread, which a ponder search must not do before the ponderhit
+        self.wait_for_ponderhit();
+        match (self.show_ponder_move, self.ponder_move) {
+            (true, Some(ponder_move)) => self.send_output(format!("bestmove {best_move} ponder {ponder_move}")),
+            _other => self.send_output(format!("bestmove {best_move}")),
+        }
     }
     
+    /// Returns the margin within which the best move is picked randomly among the best root moves.
+    /// This happens in the first moves of the game, with a variety, and below the maximum skill level.
+    fn get_root_move_margin(&self, board: &Board) -> i32 {
+        let opening_margin = match board.fullmove_counter <= self.opening_variety {
+            true => OPENING_VARIETY_MARGIN,
+            false => 0,
+        };
+        opening_margin.max(self.variety).max(skill_level::skill_margin(self.skill_level))
+    }
+
+    /// Returns whether the given root move is searched by the current search,
+    /// i.e. it is not excluded, and it is one of the search moves if the search is restricted to some root moves.
+    fn is_searched_root_move(&self, ply: Ply) -> bool {
+        Some(ply) != self.excluded_root_move && (self.searched_root_moves.is_empty() || self.searched_root_moves.contains(&ply))
+    }
+
+    /// Returns whether the given search feature is enabled.
+    fn is_enabled(&self, feature: SearchFeature) -> bool {
+        self.features[feature.to_index() as usize]
+    }
+
+    /// Returns the static evaluation of the given position, which is looked up in the evaluation cache if possible.
+    fn evaluate(&mut self, position: Position) -> i32 {
+        self.search_info.eval_cache_probes += 1;
+        if let Some(score) = self.eval_cache.probe(&position) {
+            self.search_info.eval_cache_hits += 1;
+            return score;
+        }
+        self.eval_cache.evaluate(position)
+    }
+
+    /// Returns the score of a draw for the side to move at the given ply.
+    /// Ladybug moves at even plies, so the contempt lowers the score of draws for her, and raises it for her opponent.
+    fn draw_score(&self, ply_index: u64) -> i32 {
+        match ply_index.is_multiple_of(2) {
+            true => -self.contempt,
+            false => self.contempt,
+        }
+    }
+
+    /// Returns the score of an info line, followed by its win, draw and loss probabilities if they are shown.
+    fn score_info(&self, score: i32) -> String {
+        match self.show_wdl {
+            true => {
+                let (win, draw, loss) = wdl::wdl(score);
+                format!("score cp {score} wdl {win} {draw} {loss}")
+            }
+            false => format!("score cp {score}"),
+        }
+    }
+
+    /// Returns whether the current search has to stop because the time limit or the node limit is reached,
+    /// or because Ladybug told the search to stop.
+    /// If so, the stop flag is set, so that the search breaks out of recursion immediately.
+    /// While the search ponders, the time limit does not apply.
+    fn is_limit_reached(&mut self, time_limit: Option<Duration>) -> bool {
+        self.check_ponderhit();
+        let time_limit_reached = !self.pondering && time_limit.is_some_and(|limit| self.total_time.is_some_and(|instant| instant.elapsed() > limit));
+        let node_limit_reached = self.iteration_node_limit.is_some_and(|limit| self.search_info.node_count >= limit);
+        if time_limit_reached || node_limit_reached || self.is_aborted() {
+            self.stop = true;
+        }
+        self.stop
+    }
+
+    /// Returns whether Ladybug stopped the current task or quit, in which case tasks consisting of several searches,
+    /// like a self-play session, must not start another search.
+    fn is_aborted(&self) -> bool {
+        self.quit.get() || self.stop_flag.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the search runs on the thread that reads its messages, see `send_output`.
+    pub fn set_synchronous(&mut self, synchronous: bool) {
+        self.synchronous = synchronous;
+    }
+
+    /// Returns the best move message the synchronous search couldn't send, because the message queue was full, if any.
+    pub fn take_unsent_best_move(&self) -> Option<Message> {
+        self.unsent_best_move.take().map(Message::SearchMessage)
+    }
+
+    /// Returns the flag that stops the current task, which is shared with Ladybug.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop_flag)
+    }
+
     /// Handles the "Perft" command.
     fn handle_perft(&self, position: Position, depth: u64) {
         self.perft(position, depth);
     }
+
+    /// Handles the "Mate" command.
+    fn handle_mate(&self, position: Position, moves: u64) {
+        if move_gen::generate_moves(position).is_empty() {
+            self.send_output(String::from("info string no legal moves"));
+            self.send_output(String::from(NULL_BEST_MOVE));
+            return;
+        }
+        self.mate_search(position, moves);
+    }
+
+    /// Handles the "Test" command.
+    fn handle_test(&mut self, entries: Vec<EpdEntry>, time: u64) {
+        self.run_test_suite(entries, time);
+    }
+
+    /// Handles the "PerftSuite" command.
+    fn handle_perft_suite(&self, entries: Vec<PerftEntry>) {
+        self.run_perft_suite(entries);
+    }
+
+    /// Handles the "PerftDiff" command.
+    fn handle_perft_diff(&self, board: Board, depth: u64, engine_path: String) {
+        self.perft_diff(board, depth, engine_path.as_str());
+    }
+
+    /// Handles the "SelfPlay" command.
+    fn handle_selfplay(&mut self, settings: SelfPlaySettings) {
+        self.run_selfplay(&settings);
+    }
+
+    /// Handles the "Match" command.
+    fn handle_match(&mut self, settings: MatchSettings) {
+        self.run_match(&settings);
+    }
+
+    /// Forgets the results of the previous searches and the killer and history moves, so that the next search starts a new game.
+    pub(crate) fn new_game(&mut self) {
+        self.clear_hash();
+        self.search_info.clear_heuristics();
+    }
+
+    /// Removes all entries from the transposition tables of all threads.
+    fn clear_hash(&mut self) {
+        self.transposition_table.clear();
+        self.split_tables.iter_mut().for_each(|(transposition_table, _eval_cache)| transposition_table.clear());
+    }
+
+    /// Handles the "HashSize" command.
+    /// If the memory for the new tables can't be allocated, the previous tables are kept.
+    fn handle_hash_size(&mut self, size_mb: usize) {
+        if let Err(message) = self.allocate_tables(size_mb, self.threads) {
+            self.send_output(format!("info string {message}"));
+        }
+    }
+
+    /// Handles the "Threads" command.
+    /// If the memory for the tables of the threads can't be allocated, the previous number of threads is kept.
+    fn handle_threads(&mut self, threads: usize) {
+        // WebAssembly builds can't spawn threads, so they always use a single thread
+        let threads = match cfg!(feature = "wasm") {
+            true => 1,
+            false => threads,
+        };
+        if threads == self.threads {
+            return;
+        }
+        if let Err(message) = self.allocate_tables(self.hash_size, threads) {
+            self.send_output(format!("info string {message}"));
+        }
+    }
+
+    /// Allocates the transposition tables for the given number of threads, which together use the given memory in megabytes.
+    /// The evaluation caches of the threads are kept, since they don't depend on the memory.
+    fn allocate_tables(&mut self, size_mb: usize, threads: usize) -> Result<(), String> {
+        let mut tables = TranspositionTable::try_new_split(size_mb, threads)?.into_iter();
+        self.transposition_table = tables.next().expect("at least one table is constructed");
+        self.split_tables.truncate(threads.saturating_sub(1));
+        for (index, transposition_table) in tables.enumerate() {
+            match self.split_tables.get_mut(index) {
+                Some(split_table) => split_table.0 = transposition_table,
+                None => self.split_tables.push((transposition_table, EvalCache::default())),
+            }
+        }
+        self.hash_size = size_mb;
+        self.threads = threads;
+        Ok(())
+    }
+
+    /// Handles the "HashStats" command.
+    fn handle_hash_stats(&self) {
+        let stats = self.transposition_table.stats();
+        let percent = |entries: usize| entries as f64 * 100.0 / stats.capacity as f64;
+        self.send_output(format!("Hash entries: {}, used {} ({:.1}%), by the current search {} ({:.1}%)",
+                                 stats.capacity, stats.used, percent(stats.used), stats.current, percent(stats.current)));
+        self.send_output(format!("Stored results: {} new, {} replaced, {} overwritten, {} discarded", stats.new, stats.replaced, stats.overwritten, stats.discarded));
+        self.send_output(format!("Average depth: {:.2}", stats.average_depth));
+    }
+
+    /// Handles the "Bench" command.
+    fn handle_bench(&mut self, depth: u64) {
+        self.bench(depth);
+    }
+
+    /// Handles the "BenchCompare" command.
+    fn handle_bench_compare(&mut self, baseline: BenchBaseline) {
+        self.bench_compare(&baseline);
+    }
+}
+
+/// Returns the "bestmove" message with the first legal move of the given position, or with the null move if there is none.
+fn fallback_best_move(position: Position) -> String {
+    let move_list = move_gen::generate_moves(position);
+    match move_list.is_empty() {
+        true => String::from(NULL_BEST_MOVE),
+        false => format!("bestmove {}", move_list.get(0)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    #![allow(clippy::field_reassign_with_default)]
+
+    use crate::board::color::Color;
     use crate::board::piece::{NUM_PIECES, Piece};
     use crate::board::square;
     use crate::board::square::NUM_SQUARES;
     use crate::move_gen::ply::Ply;
-    use crate::search::{MAX_PLY, SearchInfo};
+    use crate::search::clock::Instant;
+    use crate::search::negamax::CURRMOVE_DELAY;
+    use crate::search::{Extension, AGED_KILLER_PLIES, MAX_PLY, NULL_BEST_MOVE, NUM_EXTENSIONS, NUM_SEARCH_STAGES, Search, SearchCommand, SearchFeature, SearchInfo, SearchStage};
+    use crate::engine::read_best_move;
+    use crate::evaluation::{NEGATIVE_INFINITY, POSITIVE_INFINITY};
+    use crate::ladybug::{Message, MESSAGE_CHANNEL_CAPACITY};
+    use crate::board::Board;
+    use arrayvec::ArrayVec;
+    use crate::move_gen;
+    use std::sync::atomic::Ordering;
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
 
     #[test]
     fn test_default() {
         let search_info = SearchInfo::default();
         assert_eq!(0, search_info.node_count);
+        assert_eq!(0, search_info.seldepth);
         assert_eq!([0; MAX_PLY], search_info.pv_length);
         assert_eq!([[Ply::default(); MAX_PLY];MAX_PLY], search_info.pv_table);
         assert_eq!([[Ply::default(); MAX_PLY]; 2], search_info.killer_moves);
@@ -187,22 +998,11 @@ mod tests {
     fn test_search_info_clear_iteration() {
         let mut search_info = SearchInfo::default();
         search_info.node_count = 50000;
+        search_info.seldepth = 12;
         search_info.pv_length[0] = 5;
-        let pv_ply = Ply {
-            source: square::E2,
-            target: square::E8,
-            piece: Piece::Rook,
-            captured_piece: None,
-            promotion_piece: None,
-        };
+        let pv_ply = Ply::new(square::E2, square::E8, Piece::Rook, None, false);
         search_info.pv_table[4][4] = pv_ply;
-        let killer_move = Ply {
-            source: square::H7,
-            target: square::H8,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        };
+        let killer_move = Ply::new(square::H7, square::H8, Piece::Pawn, None, false);
         search_info.killer_moves[0][5] = killer_move;
         search_info.history_moves[2][13] = 40;
         search_info.follow_pv = false;
@@ -211,6 +1011,7 @@ mod tests {
 
         // these should be cleared
         assert_eq!(0, search_info.node_count);
+        assert_eq!(0, search_info.seldepth);
         assert_eq!([0; MAX_PLY], search_info.pv_length);
         assert!(search_info.follow_pv);
 
@@ -220,20 +1021,327 @@ mod tests {
         assert_eq!(40, search_info.history_moves[2][13]);
     }
 
+    #[test]
+    fn test_search_info_count_node() {
+        let mut search_info = SearchInfo::default();
+        search_info.count_node(SearchStage::Main, 3);
+        search_info.count_node(SearchStage::Main, 1);
+        search_info.count_node(SearchStage::Quiescence, 6);
+
+        assert_eq!(3, search_info.node_count);
+        assert_eq!(6, search_info.seldepth);
+        assert_eq!([2, 1, 0], search_info.stage_node_counts);
+
+        // all nodes of a verification search are attributed to its stage
+        search_info.verification_stage = Some(SearchStage::ProbCut);
+        search_info.count_node(SearchStage::Main, 2);
+        search_info.count_node(SearchStage::Quiescence, 3);
+        search_info.verification_stage = None;
+        search_info.count_node(SearchStage::Quiescence, 2);
+        assert_eq!([2, 2, 2], search_info.stage_node_counts);
+
+        // the stage node counts are kept across iterations
+        search_info.clear_iteration();
+        assert_eq!(0, search_info.node_count);
+        assert_eq!([2, 2, 2], search_info.stage_node_counts);
+    }
+
+    #[test]
+    fn test_search_info_extend() {
+        let mut search_info = SearchInfo::default();
+        assert_eq!(1, search_info.extend(Extension::Check, 2));
+        assert_eq!(1, search_info.extend(Extension::Recapture, 1));
+
+        // the extension budget is used up, no matter which extension applies
+        assert_eq!(0, search_info.extend(Extension::Check, 0));
+        assert_eq!(0, search_info.extend(Extension::Recapture, 0));
+        assert_eq!([1, 1], search_info.extension_counts);
+        assert_eq!(2, search_info.denied_extensions);
+    }
+
     #[test]
     fn test_search_info_clear_all() {
         let mut search_info = SearchInfo::default();
-        search_info.killer_moves[0][4] = Ply {
-            source: square::H7,
-            target: square::H8,
-            piece: Piece::Pawn,
-            captured_piece: None,
-            promotion_piece: None,
-        };
+        let killer_move = Ply::new(square::H7, square::H8, Piece::Pawn, None, false);
+        search_info.killer_moves[0][4] = killer_move;
+        search_info.history_moves[0][12] = 6;
+        search_info.pv_table[0][0] = Ply::new(square::E2, square::E4, Piece::Pawn, None, false);
+        search_info.stage_node_counts = [100, 200, 300];
+        search_info.verification_stage = Some(SearchStage::ProbCut);
+        search_info.extension_counts = [50, 20];
+        search_info.denied_extensions = 5;
+        search_info.probcut_cutoffs = 7;
+        search_info.iir_reductions = 9;
+        search_info.reverse_futility_cutoffs = 11;
+        search_info.eval_cache_probes = 20;
+        search_info.eval_cache_hits = 11;
 
         search_info.clear_all();
 
+        assert_eq!([0; NUM_SEARCH_STAGES as usize], search_info.stage_node_counts);
+        assert_eq!(None, search_info.verification_stage);
+        assert_eq!([0; NUM_EXTENSIONS as usize], search_info.extension_counts);
+        assert_eq!(0, search_info.denied_extensions);
+        assert_eq!(0, search_info.probcut_cutoffs);
+        assert_eq!(0, search_info.iir_reductions);
+        assert_eq!(0, search_info.reverse_futility_cutoffs);
+        assert_eq!(0, search_info.eval_cache_probes);
+        assert_eq!(0, search_info.eval_cache_hits);
+        assert_eq!([[Ply::default(); MAX_PLY]; MAX_PLY], search_info.pv_table);
+
+        // the killer and history moves are kept for the next search of the game, until they are cleared for a new game
+        assert_eq!(killer_move, search_info.killer_moves[0][4]);
+        assert_eq!(6, search_info.history_moves[0][12]);
+        search_info.clear_heuristics();
         assert_eq!([[Ply::default(); MAX_PLY]; 2], search_info.killer_moves);
         assert_eq!([[0; NUM_SQUARES as usize]; NUM_PIECES as usize], search_info.history_moves);
     }
+
+    #[test]
+    fn test_search_info_age_heuristics() {
+        let mut search_info = SearchInfo::default();
+        let killer_move = Ply::new(square::H7, square::H8, Piece::Pawn, None, false);
+        search_info.killer_moves[1][5] = killer_move;
+        search_info.killer_moves[0][1] = killer_move;
+        search_info.killer_moves[0][MAX_PLY - 1] = killer_move;
+        search_info.history_moves[3][20] = 7;
+
+        search_info.age_heuristics();
+
+        // the killer moves move up by two plies, and those of the first two plies are dropped
+        assert_eq!(killer_move, search_info.killer_moves[1][5 - AGED_KILLER_PLIES]);
+        assert_eq!(Ply::default(), search_info.killer_moves[1][5]);
+        assert_eq!(1, search_info.killer_moves[0].iter().filter(|ply| **ply == killer_move).count());
+        assert_eq!(Ply::default(), search_info.killer_moves[0][MAX_PLY - 1]);
+        assert_eq!(3, search_info.history_moves[3][20]);
+    }
+
+    #[test]
+    fn test_send_info_drops_messages_if_queue_is_full() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(2);
+        let search = Search::new(command_receiver, message_sender);
+
+        // the third info message does not fit into the queue and must be dropped without blocking
+        search.send_info(String::from("info 1"));
+        search.send_info(String::from("info 2"));
+        search.send_info(String::from("info 3"));
+
+        let messages: Vec<String> = message_receiver.try_iter().map(|message| match message {
+            Message::SearchMessage(msg) => msg,
+            Message::ConsoleMessage(msg) => msg,
+        }).collect();
+        assert_eq!(vec![String::from("info 1"), String::from("info 2")], messages);
+    }
+
+    #[test]
+    fn test_synchronous_search_never_blocks_on_a_full_queue() {
+        let (command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+        let mut search = Search::new(command_receiver, message_sender);
+        search.set_synchronous(true);
+        search.stop = false;
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+        // like in WebAssembly builds, nothing reads the messages while the search runs,
+        // and a search that has been running for a while reports each of the 20 root moves
+        search.total_time = Instant::now().checked_sub(CURRMOVE_DELAY);
+        for _search in 0..=MESSAGE_CHANNEL_CAPACITY / 20 {
+            search.negamax(board, 1, 0, NEGATIVE_INFINITY, POSITIVE_INFINITY, 0, None, None, None, &mut ArrayVec::new());
+        }
+
+        // the search still finishes, and keeps the best move that didn't fit into the queue
+        let _ = command_sender.send(SearchCommand::SearchDepth(board, ArrayVec::new(), 2));
+        let _ = command_sender.send(SearchCommand::Quit);
+        search.run();
+        let unsent_best_move = search.take_unsent_best_move();
+        assert!(unsent_best_move.is_some());
+        let best_move = read_best_move(message_receiver.try_iter().chain(unsent_best_move), board, |_info| {});
+        assert!(best_move.is_some_and(|ply| board.position.is_legal_move(ply)));
+    }
+
+    #[test]
+    fn test_search_feature_option_names() {
+        for feature in SearchFeature::ALL {
+            assert_eq!(Some(feature), SearchFeature::from_option_name(feature.option_name()));
+        }
+        assert_eq!(Some(SearchFeature::KillerMoves), SearchFeature::from_option_name("killermoves"));
+        assert_eq!(None, SearchFeature::from_option_name("NullMove"));
+    }
+
+    #[test]
+    fn test_search_features_can_be_disabled() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 2 4").unwrap();
+
+        let (_best_move, node_count) = search.iterative_search(board, 3, None, ArrayVec::new());
+
+        // without any search feature, the search tree changes, but the search still finds the mate
+        for feature in SearchFeature::ALL {
+            search.features[feature.to_index() as usize] = false;
+        }
+        let (best_move, node_count_without_features) = search.iterative_search(board, 3, None, ArrayVec::new());
+        assert_eq!("h5f7", best_move.to_string());
+        assert_eq!(0, search.transposition_table.hashfull());
+        assert_ne!(node_count, node_count_without_features);
+    }
+
+    #[test]
+    fn test_transposition_table_cutoffs_reduce_the_search() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+        // only the transposition table is disabled, so that its effect is measured on its own
+        search.features[SearchFeature::TranspositionTable.to_index() as usize] = false;
+        let (best_move_without_table, node_count_without_table) = search.iterative_search(board, 4, None, ArrayVec::new());
+        assert_eq!(0, search.transposition_table.hashfull());
+
+        search.new_game();
+        search.features[SearchFeature::TranspositionTable.to_index() as usize] = true;
+        let (best_move, node_count) = search.iterative_search(board, 4, None, ArrayVec::new());
+        assert!(node_count < node_count_without_table, "{node_count} {node_count_without_table}");
+        assert!(board.position.is_legal_move(best_move) && board.position.is_legal_move(best_move_without_table));
+    }
+
+    #[test]
+    fn test_depth_search_is_reproducible() {
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        let mut results = Vec::new();
+        for _run in 0..2 {
+            let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+            let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+            let mut search = Search::new(command_receiver, message_sender);
+            // the same search repeated on the same instance in a new game must not depend on the state of the previous search either
+            results.push(search.iterative_search(board, 3, None, ArrayVec::new()));
+            search.new_game();
+            results.push(search.iterative_search(board, 3, None, ArrayVec::new()));
+        }
+        assert!(results.iter().all(|result| *result == results[0]));
+    }
+
+    #[test]
+    fn test_transposition_table_persists_between_searches() {
+        let (command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+        // the second search of the same position uses the results of the first one
+        let first_result = search.iterative_search(board, 4, None, ArrayVec::new());
+        let (_best_move, second_node_count) = search.iterative_search(board, 4, None, ArrayVec::new());
+        assert!(second_node_count < first_result.1, "{second_node_count} {first_result:?}");
+        // the history moves are kept as well
+        assert!(search.search_info.history_moves.iter().flatten().any(|score| *score > 0));
+
+        // a new game forgets the results
+        let _ = command_sender.send(SearchCommand::NewGame);
+        let _ = command_sender.send(SearchCommand::Quit);
+        search.run();
+        assert_eq!(0, search.transposition_table.hashfull());
+        assert_eq!(first_result, search.iterative_search(board, 4, None, ArrayVec::new()));
+    }
+
+    #[test]
+    fn test_stop_flag() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+        // once the flag is set, the search stops at the first node, but still returns a legal move
+        search.stop_flag().store(true, Ordering::Relaxed);
+        let (best_move, node_count) = search.iterative_search(board, MAX_PLY as u64, None, ArrayVec::new());
+        assert_eq!(0, node_count);
+        let move_list = move_gen::generate_moves(board.position);
+        assert!((0..move_list.len()).any(|index| move_list.get(index) == best_move));
+
+        // the flag is only reset by Ladybug, so that it can't be missed by a search that has yet to start
+        assert!(search.stop_flag().load(Ordering::Relaxed));
+        search.stop_flag().store(false, Ordering::Relaxed);
+        let (_best_move, node_count) = search.iterative_search(board, 2, None, ArrayVec::new());
+        assert_ne!(0, node_count);
+    }
+
+    #[test]
+    fn test_run_terminates_on_closed_channels() {
+        // the queued commands are handled before the search thread terminates
+        let (command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        let _ = command_sender.send(SearchCommand::Debug(true));
+        drop(command_sender);
+        search.run();
+        assert!(search.debug);
+
+        // if Ladybug stops receiving messages, the search ends instead of panicking
+        let (command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        drop(message_receiver);
+        let _ = command_sender.send(SearchCommand::SearchDepth(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap(), ArrayVec::new(), 4));
+        let _ = command_sender.send(SearchCommand::Debug(true));
+        search.run();
+        assert!(search.quit.get());
+        assert!(!search.debug);
+    }
+
+    #[test]
+    fn test_run_survives_panics() {
+        let (command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+
+        // a position without a black king can't occur in a game, so the search panics on it
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        board.position.remove_piece(Piece::King, Color::Black, square::E8);
+        let _ = command_sender.send(SearchCommand::SearchDepth(board, ArrayVec::new(), 3));
+        let _ = command_sender.send(SearchCommand::SearchDepth(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap(), ArrayVec::new(), 1));
+        drop(command_sender);
+        search.run();
+
+        // the failed search reports the error and still plays a legal move, and the next search works as usual
+        let messages: Vec<String> = message_receiver.try_iter()
+            .filter_map(|message| match message {
+                Message::SearchMessage(output) if !output.starts_with("info depth") => Some(output),
+                _other => None,
+            })
+            .collect();
+        assert_eq!(3, messages.len(), "{messages:?}");
+        assert!(messages[0].starts_with("info string error: "), "{messages:?}");
+        assert!(messages[1].starts_with("bestmove "), "{messages:?}");
+        assert!(messages[2].starts_with("bestmove "), "{messages:?}");
+    }
+
+    #[test]
+    fn test_best_move_is_always_sent() {
+        let (command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+
+        // in checkmate and stalemate, every kind of search sends the null move
+        let checkmate = Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        let stalemate = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        let _ = command_sender.send(SearchCommand::SearchDepth(checkmate, ArrayVec::new(), 3));
+        let _ = command_sender.send(SearchCommand::SearchClock(stalemate, ArrayVec::new(), 100, 200));
+        let _ = command_sender.send(SearchCommand::Ponder(stalemate, ArrayVec::new(), 100));
+        let _ = command_sender.send(SearchCommand::Mate(checkmate.position, 2));
+        // a search to depth 0 still plays a legal move
+        let _ = command_sender.send(SearchCommand::SearchDepth(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap(), ArrayVec::new(), 0));
+        drop(command_sender);
+        search.run();
+
+        let best_moves: Vec<String> = message_receiver.try_iter()
+            .filter_map(|message| match message {
+                Message::SearchMessage(output) if output.starts_with("bestmove ") => Some(output),
+                _other => None,
+            })
+            .collect();
+        assert_eq!(vec![NULL_BEST_MOVE; 4], best_moves[..4], "{best_moves:?}");
+        assert_eq!(5, best_moves.len(), "{best_moves:?}");
+        assert_ne!(NULL_BEST_MOVE, best_moves[4]);
+    }
 }
@@ -0,0 +1,145 @@
+//! The adjudication module ends the games of selfplay and engine matches early once their outcome is clear,
+//! so that long test matches finish quickly.
+//!
+//! The rules follow the adjudication settings of cutechess-cli: a side resigns once its own score was at or below
+//! the negative resign score for the given number of consecutive moves, and the game is drawn once the scores of both
+//! sides stayed within the draw score for the given number of consecutive moves, starting at the given move number.
+
+use crate::board::Board;
+use crate::board::color::Color;
+use crate::game::GameResult;
+
+/// The rule for resigning a game, like `-resign movecount=<n> score=<score>` of cutechess-cli.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ResignRule {
+    /// The number of consecutive moves the score of a side has to be at or below the negative resign score.
+    pub move_count: u32,
+    /// The resign score in centipawns.
+    pub score: i32,
+}
+
+/// The rule for drawing a game, like `-draw movenumber=<m> movecount=<n> score=<score>` of cutechess-cli.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DrawRule {
+    /// The move number from which on the scores are counted.
+    pub move_number: u32,
+    /// The number of consecutive moves the scores of both sides have to be within the draw score.
+    pub move_count: u32,
+    /// The draw score in centipawns.
+    pub score: i32,
+}
+
+/// The adjudication rules of a match. Without any rules, the games are played until they end.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Adjudication {
+    /// The rule for resigning, if any.
+    pub resign: Option<ResignRule>,
+    /// The rule for drawing, if any.
+    pub draw: Option<DrawRule>,
+}
+
+/// Keeps track of the scores of the moves of a game, and decides when the game is adjudicated.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Adjudicator {
+    /// The adjudication rules.
+    adjudication: Adjudication,
+    /// The number of consecutive moves each side's score was at or below the negative resign score.
+    resign_counts: [u32; 2],
+    /// The number of consecutive plies whose score was within the draw score.
+    draw_count: u32,
+}
+
+impl Adjudicator {
+    /// Constructs an adjudicator for a new game with the given rules.
+    pub fn new(adjudication: Adjudication) -> Adjudicator {
+        Adjudicator { adjudication, ..Adjudicator::default() }
+    }
+
+    /// Records the score in centipawns, from the point of view of the side to move, with which the move from the given board was played.
+    /// If the score is unknown, the counts of the rules are reset, as it can't support an adjudication.
+    /// Returns the result of the game and the reason for it, if the game is adjudicated.
+    pub fn add_score(&mut self, board: &Board, score: Option<i32>) -> Option<(GameResult, String)> {
+        let color = board.position.color_to_move;
+        let color_index = color.to_index() as usize;
+
+        if let Some(resign) = self.adjudication.resign {
+            match score {
+                Some(score) if score <= -resign.score => self.resign_counts[color_index] += 1,
+                _other => self.resign_counts[color_index] = 0,
+            }
+            if self.resign_counts[color_index] >= resign.move_count {
+                let result = match color {
+                    Color::White => GameResult::BlackWins,
+                    Color::Black => GameResult::WhiteWins,
+                };
+                return Some((result, format!("{color:?} resigns")));
+            }
+        }
+
+        if let Some(draw) = self.adjudication.draw {
+            match score {
+                Some(score) if board.fullmove_counter >= draw.move_number && score.abs() <= draw.score => self.draw_count += 1,
+                _other => self.draw_count = 0,
+            }
+            // both sides have to play the given number of moves
+            if self.draw_count >= 2 * draw.move_count {
+                return Some((GameResult::Draw, String::from("Adjudicated draw")));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::game::GameResult;
+    use crate::search::adjudication::{Adjudication, Adjudicator, DrawRule, ResignRule};
+
+    #[test]
+    fn test_resign() {
+        let mut adjudicator = Adjudicator::new(Adjudication { resign: Some(ResignRule { move_count: 2, score: 500 }), draw: None });
+        let white = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 20").unwrap();
+        let black = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 20").unwrap();
+
+        assert_eq!(None, adjudicator.add_score(&white, Some(900)));
+        assert_eq!(None, adjudicator.add_score(&black, Some(-900)));
+        assert_eq!(None, adjudicator.add_score(&white, Some(900)));
+        // a missing score resets the count
+        assert_eq!(None, adjudicator.add_score(&black, None));
+        assert_eq!(None, adjudicator.add_score(&white, Some(900)));
+        assert_eq!(None, adjudicator.add_score(&black, Some(-500)));
+        assert_eq!(None, adjudicator.add_score(&white, Some(900)));
+        assert_eq!(Some((GameResult::WhiteWins, String::from("Black resigns"))), adjudicator.add_score(&black, Some(-900)));
+    }
+
+    #[test]
+    fn test_draw() {
+        let rule = DrawRule { move_number: 30, move_count: 2, score: 10 };
+        let mut adjudicator = Adjudicator::new(Adjudication { resign: None, draw: Some(rule) });
+        let early = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 29").unwrap();
+        let white = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 30").unwrap();
+        let black = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 30").unwrap();
+
+        // the scores before the move number don't count
+        assert_eq!(None, adjudicator.add_score(&early, Some(0)));
+        assert_eq!(None, adjudicator.add_score(&white, Some(0)));
+        assert_eq!(None, adjudicator.add_score(&black, Some(-10)));
+        // a score outside the draw score resets the count
+        assert_eq!(None, adjudicator.add_score(&white, Some(11)));
+        assert_eq!(None, adjudicator.add_score(&black, Some(5)));
+        assert_eq!(None, adjudicator.add_score(&white, Some(-5)));
+        assert_eq!(None, adjudicator.add_score(&black, Some(0)));
+        assert_eq!(Some((GameResult::Draw, String::from("Adjudicated draw"))), adjudicator.add_score(&white, Some(10)));
+    }
+
+    #[test]
+    fn test_no_rules() {
+        let mut adjudicator = Adjudicator::default();
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 100").unwrap();
+        for _ in 0..10 {
+            assert_eq!(None, adjudicator.add_score(&board, Some(-10000)));
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::fs;
+use arrayvec::ArrayVec;
+use crate::board::Board;
+use crate::search::clock::Instant;
+use crate::search::Search;
+
+/// The depth each bench position is searched to, if no depth is specified.
+pub const BENCH_DEPTH: u64 = 5;
+
+/// The positions searched by the bench command.
+/// The set covers the opening, the middlegame and the endgame, and must not be changed, since the resulting node count
+/// serves as the signature of the build.
+pub const BENCH_POSITIONS: [&str; 16] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "r1bq1rk1/pp2ppbp/2np1np1/8/3NP3/2N1BP2/PPPQ2PP/R3KB1R w KQ - 3 9",
+    "2rq1rk1/pp1bppbp/2np1np1/8/3NP3/1BN1BP2/PPPQ2PP/2KR3R w - - 7 12",
+    "r1bqk2r/pp2bppp/2n1pn2/2pp4/3P4/2PBPN2/PP1N1PPP/R1BQK2R w KQkq - 0 7",
+    "3r2k1/pp3ppp/2n5/2b5/2P5/1P3N2/P4PPP/3R2K1 w - - 0 20",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+    "8/8/4k3/8/2p5/8/B2P4/4K3 w - - 0 1",
+    "8/5k2/8/3P4/8/8/5K2/8 w - - 0 1",
+    "4k3/8/8/8/8/8/3PPP2/4K2R w K - 0 1",
+    "8/p4pk1/1p4p1/3Q4/8/1P3qP1/P4P1P/6K1 b - - 0 40",
+];
+
+/// The expected result of the bench for a tagged version of Ladybug.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BenchBaseline {
+    /// The tag of the version the baseline was recorded for.
+    pub tag: String,
+    /// The depth the bench positions were searched to.
+    pub depth: u64,
+    /// The total number of searched nodes, which must match exactly.
+    pub nodes: u128,
+    /// The lowest number of nodes per second that is not considered a slowdown.
+    pub min_nps: u128,
+    /// The highest number of nodes per second that is not considered suspicious.
+    pub max_nps: u128,
+}
+
+impl BenchBaseline {
+    /// Parses a single line of a baseline file, which consists of the tag, the depth, the node count,
+    /// and the range of nodes per second, separated by whitespace, e.g. `v0.5.0 5 2905703 50000 2000000`.
+    pub fn parse(line: &str) -> Result<BenchBaseline, String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("Invalid bench baseline: {line}"));
+        }
+        let parse_number = |field: &str| field.parse::<u128>().map_err(|_| format!("Invalid bench baseline: {line}"));
+
+        let baseline = BenchBaseline {
+            tag: fields[0].to_string(),
+            depth: parse_number(fields[1])? as u64,
+            nodes: parse_number(fields[2])?,
+            min_nps: parse_number(fields[3])?,
+            max_nps: parse_number(fields[4])?,
+        };
+        if baseline.depth == 0 || baseline.min_nps > baseline.max_nps {
+            return Err(format!("Invalid bench baseline: {line}"));
+        }
+        Ok(baseline)
+    }
+}
+
+/// Reads the bench baselines from the file at the given path.
+/// Empty lines and lines starting with '#' are ignored. The baselines are returned in the order of the file,
+/// so the last one belongs to the most recent version.
+pub fn read_baselines(path: &str) -> Result<Vec<BenchBaseline>, String> {
+    let content = match fs::read_to_string(path) {
+        Err(_) => return Err(String::from("Could not read bench baseline file")),
+        Ok(content) => content,
+    };
+
+    content.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(BenchBaseline::parse)
+        .collect()
+}
+
+impl Search {
+    /// Searches all bench positions to the given depth and reports the total number of searched nodes and the nodes per second.
+    ///
+    /// Since the search is deterministic for a fixed depth, the node count serves as the signature of the build:
+    /// any change to the search or the evaluation changes it, while changes that only affect the speed leave it as is.
+    /// Returns the total number of searched nodes.
+    pub fn bench(&mut self, depth: u64) -> u128 {
+        self.run_bench(depth).0
+    }
+
+    /// Runs the bench at the depth of the given baseline and reports whether the result deviates from it.
+    /// Any difference in the node count is a deviation, while the nodes per second only have to lie in the range of the baseline,
+    /// since they depend on the machine.
+    /// Returns true if the result matches the baseline.
+    pub fn bench_compare(&mut self, baseline: &BenchBaseline) -> bool {
+        let (nodes, nps) = self.run_bench(baseline.depth);
+
+        let nodes_match = nodes == baseline.nodes;
+        let nps_match = (baseline.min_nps..=baseline.max_nps).contains(&nps);
+        let verdict = |matches: bool| match matches {
+            true => "ok",
+            false => "deviation",
+        };
+        self.send_output(format!("Nodes: {nodes}, expected {} ({})", baseline.nodes, verdict(nodes_match)));
+        self.send_output(format!("Nps: {nps}, expected {} to {} ({})", baseline.min_nps, baseline.max_nps, verdict(nps_match)));
+
+        let matches = nodes_match && nps_match;
+        match matches {
+            true => self.send_output(format!("Bench matches baseline {}", baseline.tag)),
+            false => self.send_output(format!("Bench deviates from baseline {}", baseline.tag)),
+        }
+        matches
+    }
+
+    /// Searches all bench positions to the given depth, reports the results,
+    /// and returns the total number of searched nodes and the nodes per second.
+    fn run_bench(&mut self, depth: u64) -> (u128, u128) {
+        // used to measure the elapsed time
+        let start_time = Instant::now();
+
+        let mut total_node_count: u128 = 0;
+
+        for (index, fen) in BENCH_POSITIONS.iter().enumerate() {
+            let board = Board::from_fen(fen).expect("The bench positions are valid");
+
+            // each position starts like a new game, so that its node count doesn't depend on the previous positions
+            self.new_game();
+            // no time limit is used, so the search only stops at the given depth
+            let (_best_move, node_count) = self.iterative_search(board, depth, None, ArrayVec::new());
+            total_node_count += node_count;
+
+            self.send_output(format!("Position {}/{}: {node_count} nodes", index + 1, BENCH_POSITIONS.len()));
+        }
+
+        let time_elapsed = start_time.elapsed().as_millis();
+        let nps = (total_node_count * 1000).checked_div(time_elapsed).unwrap_or(total_node_count);
+        self.send_output(format!("{total_node_count} nodes {nps} nps"));
+
+        (total_node_count, nps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use crate::board::Board;
+    use crate::ladybug::Message;
+    use crate::search::{Search, SearchCommand};
+    use crate::search::bench::{BENCH_POSITIONS, BenchBaseline, read_baselines};
+
+    #[test]
+    fn test_bench_positions_are_valid() {
+        for fen in BENCH_POSITIONS {
+            assert!(Board::from_fen(fen).is_ok(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_parse_bench_baseline() {
+        let baseline = BenchBaseline::parse("v0.5.0 5 2905703 50000 2000000").unwrap();
+        assert_eq!(BenchBaseline { tag: String::from("v0.5.0"), depth: 5, nodes: 2905703, min_nps: 50000, max_nps: 2000000 }, baseline);
+
+        assert!(BenchBaseline::parse("v0.5.0 5 2905703 50000").is_err());
+        assert!(BenchBaseline::parse("v0.5.0 five 2905703 50000 2000000").is_err());
+        assert!(BenchBaseline::parse("v0.5.0 0 2905703 50000 2000000").is_err());
+        assert!(BenchBaseline::parse("v0.5.0 5 2905703 2000000 50000").is_err());
+    }
+
+    #[test]
+    fn test_read_baselines() {
+        let baselines = read_baselines(concat!(env!("CARGO_MANIFEST_DIR"), "/benches/baselines.txt")).unwrap();
+        assert!(!baselines.is_empty());
+        assert!(baselines.iter().all(|baseline| baseline.depth == super::BENCH_DEPTH));
+
+        assert!(read_baselines("/this/file/does/not/exist.txt").is_err());
+    }
+
+    #[test]
+    fn test_bench_compare() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+
+        let nodes = search.bench(1);
+        let mut baseline = BenchBaseline { tag: String::from("test"), depth: 1, nodes, min_nps: 0, max_nps: u128::MAX };
+        assert!(search.bench_compare(&baseline));
+
+        // a different node count is a deviation, even if the speed is fine
+        baseline.nodes += 1;
+        assert!(!search.bench_compare(&baseline));
+        let output: Vec<String> = message_receiver.try_iter().filter_map(|message| match message {
+            Message::SearchMessage(output) if output.starts_with("Bench ") => Some(output),
+            _other => None,
+        }).collect();
+        assert_eq!(vec!["Bench matches baseline test", "Bench deviates from baseline test"], output);
+    }
+
+    #[test]
+    fn test_bench_is_deterministic() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+
+        let node_count = search.bench(2);
+        let output: Vec<String> = message_receiver.try_iter().filter_map(|message| match message {
+            Message::SearchMessage(output) if !output.starts_with("info") => Some(output),
+            _other => None,
+        }).collect();
+        assert_eq!(BENCH_POSITIONS.len() + 1, output.len());
+        assert!(output[0].starts_with("Position 1/16: "));
+        assert!(output[16].starts_with(format!("{node_count} nodes ").as_str()));
+        assert!(output[16].ends_with(" nps"));
+
+        // searching the same positions again results in the same signature
+        assert_eq!(node_count, search.bench(2));
+    }
+}
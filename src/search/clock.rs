@@ -0,0 +1,9 @@
+//! The clock module provides the clocks used by the search.
+//!
+//! The clocks of the standard library are not available in browsers, so WebAssembly builds with the "wasm" feature
+//! use the clocks of the `web-time` crate instead, which have the same API.
+
+#[cfg(not(feature = "wasm"))]
+pub use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "wasm")]
+pub use web_time::{Instant, SystemTime, UNIX_EPOCH};
@@ -0,0 +1,397 @@
+//! The engine match module lets Ladybug play a match against an external UCI engine, which allows measuring her strength
+//! without a separate tournament manager.
+//!
+//! The external engine is started as a child process and receives the moves and the remaining time on both clocks,
+//! just like from a GUI. Each opening is played twice, so that both engines play each side once.
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use crate::board::Board;
+use crate::board::color::Color;
+use crate::evaluation::{NEGATIVE_INFINITY, POSITIVE_INFINITY};
+use crate::game::{Game, GameResult, MAX_GAME_LENGTH, START_FEN};
+use crate::ladybug::allocate_time;
+use crate::search::clock::Instant;
+use crate::search::Search;
+use crate::search::adjudication::{Adjudication, Adjudicator};
+
+/// The time in milliseconds the external engine may take to answer a command that doesn't involve searching.
+/// While searching, the engine may exceed its clock by this time before it is considered unresponsive.
+const ENGINE_TIMEOUT: u64 = 10000;
+
+/// The time in milliseconds the external engine may take to count the leaf nodes of a single root move in a perft.
+const PERFT_TIMEOUT: u64 = 600000;
+
+/// The settings of a match against an external engine.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MatchSettings {
+    /// The path of the external engine.
+    pub engine_path: String,
+    /// The number of games to play.
+    pub games: u64,
+    /// The time on each clock at the start of a game in milliseconds.
+    pub time: u64,
+    /// The time added to the clock after each move in milliseconds.
+    pub increment: u64,
+    /// The positions the games start from. Each position is played twice, with both engines playing each side once.
+    /// If there are none, the games start from the starting position.
+    pub openings: Vec<Board>,
+    /// The file the games are written to in PGN, if any.
+    pub pgn_path: Option<String>,
+    /// The rules for ending the games early.
+    pub adjudication: Adjudication,
+}
+
+/// The results of a match, from Ladybug's point of view.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct MatchResult {
+    /// The number of games won by Ladybug.
+    pub wins: u64,
+    /// The number of games lost by Ladybug.
+    pub losses: u64,
+    /// The number of drawn games.
+    pub draws: u64,
+}
+
+impl MatchResult {
+    /// Returns Ladybug's score in points, where a win counts one point and a draw half a point.
+    pub fn score(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    /// Returns the estimated Elo difference between Ladybug and her opponent,
+    /// or None if no games were played or one of the engines scored all points.
+    pub fn elo_difference(&self) -> Option<f64> {
+        let games = self.wins + self.losses + self.draws;
+        let fraction = self.score() / games as f64;
+        match fraction > 0.0 && fraction < 1.0 {
+            true => Some(400.0 * (fraction / (1.0 - fraction)).log10()),
+            false => None,
+        }
+    }
+}
+
+/// An external UCI engine running as a child process.
+pub struct UciEngine {
+    /// The child process of the engine, which is killed when the engine is dropped.
+    child: Child,
+    /// Used to send commands to the engine.
+    stdin: ChildStdin,
+    /// Receives the lines the engine writes to its output, which are read by a separate thread.
+    lines: Receiver<String>,
+    /// The name the engine reported with "id name", or its path if it didn't report one.
+    pub name: String,
+}
+
+impl UciEngine {
+    /// Starts the engine at the given path and waits until it has completed the UCI handshake.
+    pub fn start(path: &str) -> Result<UciEngine, String> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|_| format!("Could not start engine {path}"))?;
+
+        let stdin = child.stdin.take().ok_or(format!("Could not start engine {path}"))?;
+        let stdout = child.stdout.take().ok_or(format!("Could not start engine {path}"))?;
+
+        // the output is read by a separate thread, so that waiting for an unresponsive engine can time out
+        let (line_sender, line_receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if line_sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+
+        let mut engine = UciEngine { child, stdin, lines: line_receiver, name: path.to_string() };
+        engine.send("uci")?;
+        loop {
+            let line = engine.receive(ENGINE_TIMEOUT)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            }
+            if line.trim() == "uciok" {
+                return Ok(engine);
+            }
+        }
+    }
+
+    /// Tells the engine that the next position is from a new game, and waits until the engine is ready.
+    pub fn new_game(&mut self) -> Result<(), String> {
+        self.send("ucinewgame")?;
+        self.send("isready")?;
+        while self.receive(ENGINE_TIMEOUT)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    /// Lets the engine search the current position of the game, which started from the given board,
+    /// with the given remaining times and increment, and returns the move it sent with "bestmove"
+    /// together with the last score it sent in its info lines, if any.
+    pub fn best_move(&mut self, first_board: &Board, game: &Game, white_time: u64, black_time: u64, increment: u64) -> Result<(String, Option<i32>), String> {
+        let position = match first_board.to_fen().as_str() {
+            START_FEN => String::from("position startpos"),
+            fen => format!("position fen {fen}"),
+        };
+        let moves: Vec<String> = game.moves().iter().map(|ply| ply.to_string()).collect();
+        match moves.is_empty() {
+            true => self.send(position.as_str())?,
+            false => self.send(format!("{position} moves {}", moves.join(" ")).as_str())?,
+        }
+        self.send(format!("go wtime {white_time} btime {black_time} winc {increment} binc {increment}").as_str())?;
+
+        let timeout = white_time.max(black_time) + ENGINE_TIMEOUT;
+        let mut score = None;
+        loop {
+            let line = self.receive(timeout)?;
+            if let Some(best_move) = line.strip_prefix("bestmove") {
+                let best_move = best_move.split_whitespace().next().ok_or(String::from("The engine sent an empty best move"))?;
+                return Ok((best_move.to_string(), score));
+            }
+            if line.starts_with("info") {
+                score = parse_score(line.as_str()).or(score);
+            }
+        }
+    }
+
+    /// Lets the engine perform a perft of the given board to the given depth with "go perft", and returns the node count
+    /// of each root move it reported, in the "<move>: <nodes>" format of the divide output of Ladybug and Stockfish.
+    pub fn divide(&mut self, board: &Board, depth: u64) -> Result<Vec<(String, u64)>, String> {
+        self.send(format!("position fen {}", board.to_fen()).as_str())?;
+        self.send(format!("go perft {depth}").as_str())?;
+
+        let mut node_counts = Vec::new();
+        loop {
+            let line = self.receive(PERFT_TIMEOUT)?;
+            // Ladybug ends the divide output with "Searched <nodes> nodes in <time>", Stockfish with "Nodes searched: <nodes>"
+            if line.starts_with("Searched ") || line.starts_with("Nodes searched") {
+                return Ok(node_counts);
+            }
+            if let Some((ply, node_count)) = line.split_once(':') {
+                if let (false, Ok(node_count)) = (ply.contains(char::is_whitespace), node_count.trim().parse::<u64>()) {
+                    node_counts.push((ply.to_string(), node_count));
+                }
+            }
+        }
+    }
+
+    /// Sends the given command to the engine.
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{command}").and_then(|_| self.stdin.flush()).map_err(|_| String::from("The engine has closed the connection"))
+    }
+
+    /// Waits for the next line of output of the engine for at most the given number of milliseconds.
+    fn receive(&self, timeout: u64) -> Result<String, String> {
+        match self.lines.recv_timeout(Duration::from_millis(timeout)) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err(String::from("The engine is not responding")),
+            Err(RecvTimeoutError::Disconnected) => Err(String::from("The engine has closed the connection")),
+        }
+    }
+}
+
+/// Parses the score of the given info line, e.g. "info depth 10 score cp 25 pv e2e4", in centipawns.
+/// Mate scores are converted to infinite scores. Returns None if the line has no valid score.
+fn parse_score(line: &str) -> Option<i32> {
+    let mut parts = line.split_whitespace().skip_while(|part| *part != "score").skip(1);
+    match (parts.next(), parts.next().and_then(|value| value.parse::<i32>().ok())) {
+        (Some("cp"), Some(score)) => Some(score),
+        (Some("mate"), Some(moves)) if moves > 0 => Some(POSITIVE_INFINITY),
+        (Some("mate"), Some(_moves)) => Some(NEGATIVE_INFINITY),
+        _other => None,
+    }
+}
+
+impl Drop for UciEngine {
+    /// Asks the engine to quit, and kills it if it is still running, so that no process is left behind.
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Search {
+    /// Plays a match against the external engine with the given settings and reports the result of each game,
+    /// followed by the score table. Games are adjudicated according to the adjudication rules,
+    /// and games that reach the maximum game length are adjudicated as draws.
+    /// If the engine can't be started or stops responding, the match is aborted.
+    /// Returns the result of the games played.
+    pub fn run_match(&mut self, settings: &MatchSettings) -> MatchResult {
+        let mut result = MatchResult::default();
+
+        let mut engine = match UciEngine::start(settings.engine_path.as_str()) {
+            Ok(engine) => engine,
+            Err(message) => {
+                self.send_output(format!("info string {message}"));
+                return result;
+            }
+        };
+        let ladybug_name = format!("Ladybug {}", env!("CARGO_PKG_VERSION"));
+
+        let mut pgn = String::new();
+        let mut pgn_path = settings.pgn_path.clone();
+
+        for game_index in 0..settings.games {
+            // the match is abandoned if Ladybug stops it or quits in the meantime
+            if self.is_aborted() {
+                break;
+            }
+            // each opening is played twice in a row, with swapped colors
+            let first_board = match settings.openings.is_empty() {
+                true => Game::new().board(),
+                false => settings.openings[(game_index / 2) as usize % settings.openings.len()],
+            };
+            let ladybug_color = match game_index.is_multiple_of(2) {
+                true => Color::White,
+                false => Color::Black,
+            };
+
+            let (game, game_result, reason) = match self.play_match_game(&mut engine, settings, first_board, ladybug_color) {
+                Ok(played) => played,
+                Err(message) => {
+                    self.send_output(format!("info string {message}"));
+                    break;
+                }
+            };
+
+            let ladybug_result = match (game_result, ladybug_color) {
+                (GameResult::Draw, _color) => &mut result.draws,
+                (GameResult::WhiteWins, Color::White) | (GameResult::BlackWins, Color::Black) => &mut result.wins,
+                (GameResult::WhiteWins, Color::Black) | (GameResult::BlackWins, Color::White) => &mut result.losses,
+            };
+            *ladybug_result += 1;
+
+            let (white, black) = match ladybug_color {
+                Color::White => (ladybug_name.clone(), engine.name.clone()),
+                Color::Black => (engine.name.clone(), ladybug_name.clone()),
+            };
+            self.send_output(format!("Game {} of {}: {white} - {black} {} ({reason})", game_index + 1, settings.games, game_result.to_pgn()));
+
+            // the file is rewritten after each game, so that the finished games are kept if the match is aborted
+            if let Some(path) = &pgn_path {
+                let tags = [
+                    ("Event", String::from("Ladybug match")),
+                    ("Site", String::from("?")),
+                    ("Date", String::from("????.??.??")),
+                    ("Round", format!("{}", game_index + 1)),
+                    ("White", white),
+                    ("Black", black),
+                ];
+                pgn += game.to_pgn(&tags, Some(game_result)).as_str();
+                pgn += "\n";
+                if !self.save_pgn(path, pgn.as_str()) {
+                    pgn_path = None;
+                }
+            }
+        }
+
+        let games = result.wins + result.losses + result.draws;
+        let opponent = MatchResult { wins: result.losses, losses: result.wins, draws: result.draws };
+        self.send_output(format!("{:<24} {:>6} {:>6} {:>6} {:>8}", "Engine", "Wins", "Losses", "Draws", "Score"));
+        for (name, engine_result) in [(ladybug_name.as_str(), result), (engine.name.as_str(), opponent)] {
+            self.send_output(format!("{name:<24} {:>6} {:>6} {:>6} {:>8}", engine_result.wins, engine_result.losses, engine_result.draws, format!("{:.1}/{games}", engine_result.score())));
+        }
+        match result.elo_difference() {
+            Some(elo) => self.send_output(format!("Elo difference: {elo:+.0}")),
+            None => self.send_output(String::from("Elo difference: unknown")),
+        }
+
+        result
+    }
+
+    /// Plays a single game of the match from the given board, with Ladybug playing the given color.
+    /// Returns the game together with its result and the reason for the result.
+    /// If the engine stops responding, the result contains an error.
+    fn play_match_game(&mut self, engine: &mut UciEngine, settings: &MatchSettings, first_board: Board, ladybug_color: Color) -> Result<(Game, GameResult, String), String> {
+        let mut game = Game::from_board(first_board);
+        let mut clocks = [settings.time, settings.time];
+        let mut adjudicator = Adjudicator::new(settings.adjudication);
+        self.new_game();
+        engine.new_game()?;
+
+        while game.board_history().len() < MAX_GAME_LENGTH && !self.is_aborted() {
+            if let Some(outcome) = game.outcome() {
+                return Ok((game, outcome.result(), format!("{outcome:?}")));
+            }
+
+            let color_to_move = game.board().position.color_to_move;
+            let clock_index = color_to_move.to_index() as usize;
+            let loss = match color_to_move {
+                Color::White => GameResult::BlackWins,
+                Color::Black => GameResult::WhiteWins,
+            };
+
+            let board = game.board();
+            let start_time = Instant::now();
+            let (best_move, score) = match color_to_move == ladybug_color {
+                true => {
+                    let (best_move, score) = self.search_game_move(&game, allocate_time(clocks[clock_index], settings.increment, 0));
+                    (best_move.to_string(), score)
+                }
+                false => engine.best_move(&first_board, &game, clocks[0], clocks[1], settings.increment)?,
+            };
+            let elapsed = start_time.elapsed().as_millis() as u64;
+
+            if elapsed > clocks[clock_index] {
+                return Ok((game, loss, format!("{color_to_move:?} loses on time")));
+            }
+            clocks[clock_index] = clocks[clock_index] - elapsed + settings.increment;
+
+            match game.parse_move(best_move.as_str()) {
+                Ok(ply) => game.make_move(ply)?,
+                Err(_) => return Ok((game, loss, format!("{color_to_move:?} plays the illegal move {best_move}"))),
+            }
+
+            // the game ends by the rules before it is adjudicated
+            if let (None, Some((result, reason))) = (game.outcome(), adjudicator.add_score(&board, score)) {
+                return Ok((game, result, reason));
+            }
+        }
+
+        Ok((game, GameResult::Draw, String::from("Adjudicated")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluation::{NEGATIVE_INFINITY, POSITIVE_INFINITY};
+    use crate::search::engine_match::{parse_score, MatchResult, UciEngine};
+
+    #[test]
+    fn test_score() {
+        let result = MatchResult { wins: 3, losses: 1, draws: 2 };
+        assert_eq!(4.0, result.score());
+        assert_eq!(0.0, MatchResult::default().score());
+    }
+
+    #[test]
+    fn test_elo_difference() {
+        assert_eq!(Some(0.0), MatchResult { wins: 1, losses: 1, draws: 2 }.elo_difference());
+        assert_eq!(191, MatchResult { wins: 3, losses: 1, draws: 0 }.elo_difference().unwrap().round() as i32);
+        assert_eq!(-191, MatchResult { wins: 1, losses: 3, draws: 0 }.elo_difference().unwrap().round() as i32);
+        assert_eq!(None, MatchResult { wins: 2, losses: 0, draws: 0 }.elo_difference());
+        assert_eq!(None, MatchResult::default().elo_difference());
+    }
+
+    #[test]
+    fn test_parse_score() {
+        assert_eq!(Some(25), parse_score("info depth 10 seldepth 14 score cp 25 nodes 1000 pv e2e4"));
+        assert_eq!(Some(-130), parse_score("info depth 8 score cp -130 lowerbound"));
+        assert_eq!(Some(POSITIVE_INFINITY), parse_score("info depth 12 score mate 3 pv a1a8"));
+        assert_eq!(Some(NEGATIVE_INFINITY), parse_score("info depth 12 score mate -2"));
+        assert_eq!(None, parse_score("info depth 5 nodes 1000"));
+        assert_eq!(None, parse_score("info string score cp"));
+    }
+
+    #[test]
+    fn test_start_missing_engine() {
+        assert!(UciEngine::start("/this/engine/does/not/exist").is_err());
+    }
+}
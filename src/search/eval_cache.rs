@@ -0,0 +1,98 @@
+use crate::board::position::Position;
+use crate::evaluation;
+
+/// The number of entries of the evaluation cache, which take up 1 MB.
+const EVAL_CACHE_LEN: usize = 1 << 16;
+
+/// An entry in the evaluation cache.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+struct EvalCacheEntry {
+    /// The zobrist hash of the position. A hash of 0 marks an empty entry.
+    hash: u64,
+    /// The static evaluation of the position.
+    score: i32,
+}
+
+/// The evaluation cache stores the static evaluations of recently evaluated positions,
+/// so that positions that are evaluated again, e.g. in the quiescence search of a later iteration, don't have to be evaluated from scratch.
+///
+/// Unlike the transposition table, the cache stays valid across searches, since the static evaluation only depends on the position
+/// and the evaluation parameters. It has to be cleared when the evaluation parameters change.
+pub struct EvalCache {
+    /// The entries of the cache, indexed by the position hash.
+    entries: Vec<EvalCacheEntry>,
+}
+
+impl Default for EvalCache {
+    /// Constructs an empty evaluation cache.
+    fn default() -> Self {
+        Self {
+            entries: vec![EvalCacheEntry::default(); EVAL_CACHE_LEN],
+        }
+    }
+}
+
+impl EvalCache {
+    /// Returns the cached static evaluation of the given position, if the cache contains one.
+    pub fn probe(&self, position: &Position) -> Option<i32> {
+        let entry = self.entries[Self::index(position.hash)];
+        match entry.hash == position.hash && entry.hash != 0 {
+            true => Some(entry.score),
+            false => None,
+        }
+    }
+
+    /// Evaluates the given position and stores the result, replacing any previous entry in the same slot.
+    pub fn evaluate(&mut self, position: Position) -> i32 {
+        let score = evaluation::evaluate(position);
+        self.entries[Self::index(position.hash)] = EvalCacheEntry { hash: position.hash, score };
+        score
+    }
+
+    /// Moves the entries of this cache into the returned cache, which leaves this cache empty until a cache is assigned to it again.
+    pub(crate) fn take(&mut self) -> Self {
+        Self {
+            entries: std::mem::take(&mut self.entries),
+        }
+    }
+
+    /// Returns true if the cache can not hold any entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all entries from the cache.
+    pub fn clear(&mut self) {
+        self.entries.fill(EvalCacheEntry::default());
+    }
+
+    /// Returns the index of the slot for the given hash.
+    fn index(hash: u64) -> usize {
+        hash as usize & (EVAL_CACHE_LEN - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::evaluation;
+    use crate::search::eval_cache::EvalCache;
+
+    #[test]
+    fn test_evaluate_and_probe() {
+        let mut eval_cache = EvalCache::default();
+        let position = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap().position;
+        assert_eq!(None, eval_cache.probe(&position));
+
+        let score = eval_cache.evaluate(position);
+        assert_eq!(evaluation::evaluate(position), score);
+        assert_eq!(Some(score), eval_cache.probe(&position));
+
+        // the same pieces with the other side to move are a different position
+        let other_position = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 2 3").unwrap().position;
+        assert_eq!(None, eval_cache.probe(&other_position));
+
+        eval_cache.clear();
+        assert_eq!(None, eval_cache.probe(&position));
+    }
+}
@@ -4,96 +4,243 @@ use std::time::Duration;
 use arrayvec::ArrayVec;
 use crate::{evaluation, move_gen};
 use crate::board::Board;
+use crate::board::piece::Piece;
+use crate::board::square::Square;
 use crate::evaluation::{NEGATIVE_INFINITY, POSITIVE_INFINITY};
-use crate::search::{MAX_PLY, Search};
+use crate::evaluation::pst::PIECE_VALUES;
+use crate::move_gen::move_list::MoveList;
+use crate::move_gen::ply::Ply;
+use crate::search::{Extension, MAX_PLY, Search, SearchFeature, SearchStage};
+use crate::search::clock::Instant;
+use crate::search::time_manager::BestMoveStability;
+use crate::search::transposition_table::{Bound, MATE_THRESHOLD};
+
+/// A capture is a recapture if the piece it captures is worth at most this number of centipawns more or less than the piece captured before.
+pub const RECAPTURE_MARGIN: i32 = 50;
+
+/// The minimum depth at which ProbCut is tried, since the reduced search is too unreliable at lower depths.
+const PROBCUT_MIN_DEPTH: u64 = 5;
+/// The number of plies the search that verifies a ProbCut is reduced by.
+const PROBCUT_REDUCTION: u64 = 4;
+/// The margin by which a capture has to beat beta in the reduced search, so that the full search most likely fails high as well.
+const PROBCUT_MARGIN: i32 = 200;
+/// The default margin in centipawns per ply of depth by which the static evaluation has to beat beta for reverse futility pruning.
+pub const DEFAULT_REVERSE_FUTILITY_MARGIN: i32 = 80;
+/// The largest margin per ply the reverse futility pruning can be configured with.
+pub const MAX_REVERSE_FUTILITY_MARGIN: i32 = 1000;
+/// The default maximum depth at which reverse futility pruning is tried, since the static evaluation says little about deeper nodes.
+pub const DEFAULT_REVERSE_FUTILITY_DEPTH: u64 = 6;
+/// The largest maximum depth the reverse futility pruning can be configured with.
+pub const MAX_REVERSE_FUTILITY_DEPTH: u64 = 20;
+/// The minimum depth at which nodes without a hash move are reduced, since shallow nodes are cheap to search anyway.
+const IIR_MIN_DEPTH: u64 = 4;
+/// The minimum number of nodes searched between two iteration info lines, so that the shallow iterations don't flood the GUI.
+/// The number of nodes is used instead of the time, so that searches to a fixed depth always send the same info lines.
+pub const INFO_NODE_INTERVAL: u128 = 10_000;
+/// The time after which the search reports the root move it is searching, so that GUIs can show the progress of long iterations.
+pub const CURRMOVE_DELAY: Duration = Duration::from_secs(3);
+
+/// Returns whether the ply, capturing the given piece (if any), recaptures on the square of the previous capture,
+/// and the captured pieces are of roughly equal value.
+pub fn is_recapture(ply: Ply, captured_piece: Option<Piece>, previous_capture: Option<(Square, Piece)>) -> bool {
+    match (captured_piece, previous_capture) {
+        (Some(piece), Some((square, previous_piece))) => {
+            ply.target() == square && (PIECE_VALUES[piece.to_index() as usize] - PIECE_VALUES[previous_piece.to_index() as usize]).abs() <= RECAPTURE_MARGIN
+        }
+        _other => false,
+    }
+}
 
 impl Search {
     /// Search the given position with iterative deepening.
-    pub fn iterative_search(&mut self, board: Board, max_depth: u64, time_limit: Duration, mut board_history: ArrayVec<u64, 1000>) {
+    /// Returns the best move and the number of nodes searched across all iterations.
+    ///
+    /// Without a time limit, the search does not depend on the clock, so that searching the same position
+    /// to the same depth always returns the same best move and node count.
+    pub fn iterative_search(&mut self, board: Board, max_depth: u64, time_limit: Option<Duration>, mut board_history: ArrayVec<u64, 1000>) -> (Ply, u128) {
         // reset the stop flag to allow searching
         self.stop = false;
+        self.last_score = None;
+        self.ponder_move = None;
 
         // start the total time
-        self.total_time = Some(std::time::Instant::now());
+        let start_time = Instant::now();
+        self.total_time = Some(start_time);
+
+        // the transposition table persists between the searches of a game, but the entries of previous searches are replaced first
+        self.transposition_table.new_search();
+
+        // the number of nodes searched across all iterations
+        let mut total_node_count: u128 = 0;
 
-        // initialize the best move to the first legal one, in case the search stops prematurely
-        let mut best_move = move_gen::generate_moves(board.position).get(0);
+        // the root move that must not be played, if any
+        let excluded_move = self.excluded_root_move;
+
+        // initialize the best move to the first legal one that is searched, in case the search stops prematurely
+        let move_list = move_gen::generate_moves(board.position);
+        let mut best_move = (0..move_list.len()).map(|i| move_list.get(i)).find(|ply| self.is_searched_root_move(*ply)).unwrap_or(move_list.get(0));
+
+        // the root move scores of the last completed iteration
+        let mut root_scores = Vec::new();
+
+        // how the best move changed between the completed iterations, which decides when a search with a clock stops
+        let mut stability = BestMoveStability::default();
+
+        // the info line of the last completed iteration, if it was held back, and the node count at the last info line
+        let mut pending_info = None;
+        let mut info_node_count: u128 = 0;
 
         // start at depth 1 and increment the depth until the max depth is reached or the time runs out
         for depth in 1..=max_depth {
-            // set the start time for this iteration
-            let iteration_time = std::time::Instant::now();
-            
+            // the iteration may only search the nodes that are left of the node limit
+            self.iteration_node_limit = self.node_limit.map(|limit| limit.saturating_sub(total_node_count));
+
             // search to the current depth and save the score
-            let score = self.negamax(board, depth, 0, NEGATIVE_INFINITY, POSITIVE_INFINITY, time_limit, &mut board_history);
+            // each branch may be extended by at most the current depth, so that it is searched at most twice as deep
+            let score = self.negamax(board, depth, 0, NEGATIVE_INFINITY, POSITIVE_INFINITY, depth, None, excluded_move, time_limit, &mut board_history);
 
             if self.stop {
                 // if the stop flag is set, break out of iterative deepening immediately
                 break;
             }
 
-            // calculate nodes per second
-            let mut nps: u128 = 0;
-            let iteration_time_elapsed = iteration_time.elapsed().as_millis();
-            if iteration_time_elapsed > 0 {
-                nps = (self.search_info.node_count / iteration_time_elapsed) * 1000;
-            }
-            else {
-                nps = self.search_info.node_count;
-            }
+            // calculate the elapsed time and nodes per second
+            total_node_count += self.search_info.node_count;
+            let time_elapsed = start_time.elapsed().as_millis();
+            let nps = (total_node_count * 1000).checked_div(time_elapsed).unwrap_or(total_node_count);
 
             // send the information for the current iteration
-            let mut output = format!("info depth {depth} score cp {score} nodes {nodes} time {iteration_time_elapsed} nps {nps} pv", nodes = self.search_info.node_count);
+            let mut output = format!("info depth {depth} seldepth {seldepth} nodes {total_node_count} nps {nps} time {time_elapsed} hashfull {hashfull} {score} pv",
+                                     seldepth = self.search_info.seldepth, hashfull = self.transposition_table.hashfull(), score = self.score_info(score));
             for ply_num in 0..self.search_info.pv_length[0] {
                 output += format!(" {}", self.search_info.pv_table[0][ply_num as usize]).as_str();
             }
-            self.send_output(output);
+            // the lines of iterations that finish quickly are held back, except for the line of the last iteration
+            if depth == max_depth || total_node_count - info_node_count >= INFO_NODE_INTERVAL {
+                self.send_info(output);
+                info_node_count = total_node_count;
+                pending_info = None;
+            } else {
+                pending_info = Some(output);
+            }
 
-            // set the best move to the result of this iteration
+            // set the best move and the score to the result of this iteration
             best_move = self.search_info.pv_table[0][0];
+            self.last_score = Some(score);
+            self.ponder_move = match self.search_info.pv_length[0] >= 2 {
+                true => Some(self.search_info.pv_table[0][1]),
+                false => None,
+            };
+            root_scores = std::mem::take(&mut self.search_info.root_scores);
 
             // clear the search info for this iteration
             self.search_info.clear_iteration();
+
+            // another iteration would most likely not change a stable best move, while a changing best move needs more time
+            stability.update(best_move);
+            if self.is_optimum_time_reached(&stability) {
+                break;
+            }
         }
 
-        // send the best move to the main thread
-        self.send_output(format!("bestmove {}", best_move));
+        // the search stopped before it sent the line of the last completed iteration
+        if let Some(output) = pending_info {
+            self.send_info(output);
+        }
+
+        // pick the best move randomly among the root moves that scored close to the best one
+        if self.root_move_margin > 0 {
+            let varied_move = self.pick_varied_root_move(&root_scores).unwrap_or(best_move);
+            // the expected reply belongs to the principal variation
+            if varied_move != best_move {
+                self.ponder_move = None;
+            }
+            best_move = varied_move;
+        }
+
+        // in debug mode, report how the nodes were distributed among the search stages
+        if self.debug {
+            let mut output = String::from("info string nodes");
+            for stage in SearchStage::ALL {
+                output += format!(" {} {}", stage.name(), self.search_info.stage_node_counts[stage.to_index() as usize]).as_str();
+            }
+            self.send_output(output);
 
-        // reset the total time
+            let mut output = String::from("info string extensions");
+            for extension in Extension::ALL {
+                output += format!(" {} {}", extension.name(), self.search_info.extension_counts[extension.to_index() as usize]).as_str();
+            }
+            output += format!(" denied {}", self.search_info.denied_extensions).as_str();
+            self.send_output(output);
+
+            self.send_output(format!("info string probcut cutoffs {}", self.search_info.probcut_cutoffs));
+            self.send_output(format!("info string iir reductions {}", self.search_info.iir_reductions));
+            self.send_output(format!("info string reverse futility cutoffs {}", self.search_info.reverse_futility_cutoffs));
+
+            let hit_rate = (self.search_info.eval_cache_hits * 100).checked_div(self.search_info.eval_cache_probes).unwrap_or(0);
+            self.send_output(format!("info string eval cache hits {} probes {} hitrate {hit_rate}%", self.search_info.eval_cache_hits, self.search_info.eval_cache_probes));
+        }
+
+        // reset the total time and the node limit of the iteration
         self.total_time = None;
+        self.iteration_node_limit = None;
 
-        // clear all search info
+        // clear all search info, and keep the aged killer and history moves for the next search of the game
         self.search_info.clear_all();
+        self.search_info.age_heuristics();
+
+        (best_move, total_node_count)
     }
 
     /// A basic implementation of the [negamax](https://www.chessprogramming.org/Negamax) algorithm with alpha beta pruning.
     ///
     /// Instead of implementing two routines for the maximizing and minimizing players, this method
     /// negates the scores for each recursive call, making minimax easier to implement.
-    pub fn negamax(&mut self, board: Board, depth: u64, ply_index: u64, mut alpha: i32, beta: i32, time_limit: Duration, board_history: &mut ArrayVec<u64, 1000>) -> i32 {
+    ///
+    /// The extension budget is the number of plies the current branch may still be extended by.
+    /// The previous capture is the square and the piece captured by the move leading to this position, if that move was a capture.
+    ///
+    /// If an excluded move is given, it is skipped, so that the search returns the best score of all other moves.
+    /// Since that score does not describe the position, it is neither probed from nor stored in the transposition table.
+    pub fn negamax(&mut self, board: Board, depth: u64, ply_index: u64, mut alpha: i32, beta: i32, extension_budget: u64, previous_capture: Option<(Square, Piece)>, excluded_move: Option<Ply>, time_limit: Option<Duration>, board_history: &mut ArrayVec<u64, 1000>) -> i32 {
         // check if the max ply number is reached
         if ply_index as usize >= MAX_PLY {
             // the maximum number of plies is reached - return static evaluation to avoid overflows
-            return evaluation::evaluate(board.position);
+            return self.evaluate(board.position);
         }
 
-        // check if the time limit is reached
-        if let Some(instant) = self.total_time {
-            if instant.elapsed() > time_limit {
-                // the time limit is reached - break out of recursion immediately
-                self.stop = true;
-                return 0;
-            }
+        // check if the time limit or the node limit is reached
+        if self.is_limit_reached(time_limit) {
+            // break out of recursion immediately
+            return 0;
         }
 
+        // increment the number of nodes searched
+        self.search_info.count_node(SearchStage::Main, ply_index);
+
         // set the pv length
         self.search_info.pv_length[ply_index as usize] = ply_index as u8;
 
+        // in some variants, the game can be lost without being checkmated, which is scored like a checkmate
+        // (not at the root, since the root needs a pv)
+        if ply_index > 0 && board.position.variant_winner().is_some() {
+            return evaluation::NEGATIVE_INFINITY + (ply_index as i32 * 5000);
+        }
+
         // generate all legal moves for the current position
         let mut move_list = move_gen::generate_moves(board.position);
 
+        // look up the position in the transposition table, whose best move is searched first
+        // if the root moves are restricted, the score of the root does not describe the position either
+        let use_transposition_table = excluded_move.is_none() && (ply_index > 0 || self.searched_root_moves.is_empty()) && self.is_enabled(SearchFeature::TranspositionTable);
+        let tt_entry = match use_transposition_table {
+            true => self.transposition_table.probe(board.position.hash, ply_index),
+            false => None,
+        };
+        let hash_move = tt_entry.and_then(|entry| entry.best_move);
+
         // sort the  move list
-        move_list.sort(&mut self.search_info, ply_index);
+        move_list.sort(&board.position, &mut self.search_info, ply_index, hash_move);
 
         // if there are no legal moves, check for mate or stalemate
         if move_list.is_empty() {
@@ -104,23 +251,95 @@ impl Search {
                 // It will also prefer shorter mates when being on the winning side.
                 evaluation::NEGATIVE_INFINITY + (ply_index as i32 * 5000)
             } else {
-                0
+                self.draw_score(ply_index)
             };
         }
         
-        // check if the position is a draw by 3-fold repetition or the 50 move rule
-         if board.is_draw(board_history) {
-             return 0;
-         }
+        // check if the position is a draw by 3-fold repetition, the 50 move rule or insufficient material
+        // (not at the root, since the root needs a pv)
+        if ply_index > 0 && (board.is_draw(board_history) || board.position.is_insufficient_material()) {
+            return self.draw_score(ply_index);
+        }
 
         // if depth 0 is reached, start the quiescence search
         if depth == 0 {
             return self.quiescence_search(board.position, ply_index, alpha, beta, time_limit);
         }
 
+        // check if the position has already been searched to a sufficient depth (not at the root, since the root needs a pv)
+        if ply_index > 0 {
+            if let Some(entry) = tt_entry {
+                if entry.depth as u64 >= depth {
+                    match entry.bound {
+                        Bound::Exact => return entry.score,
+                        Bound::Lower if entry.score >= beta => return beta,
+                        Bound::Upper if entry.score <= alpha => return alpha,
+                        _other => {}
+                    }
+                }
+            }
+        }
+
+        // if the static evaluation beats beta by a margin that grows with the depth, the side to move is unlikely to fall below beta
+        // in a shallow search, so the node is pruned (reverse futility pruning, also known as static null move pruning)
+        // nodes whose window is still open on either side are the first of their parent to be searched, which decide the pv,
+        // so they are never pruned, just like the pv of the previous iteration
+        if ply_index > 0 && excluded_move.is_none() && depth <= self.reverse_futility_depth && alpha.abs() < MATE_THRESHOLD && beta.abs() < MATE_THRESHOLD && !self.search_info.follow_pv
+            && self.is_enabled(SearchFeature::ReverseFutilityPruning) && !board.position.is_in_check(board.position.color_to_move)
+            && self.evaluate(board.position) - self.reverse_futility_margin * depth as i32 >= beta {
+            self.search_info.reverse_futility_cutoffs += 1;
+            return beta;
+        }
+
+        // if a capture beats beta by a margin in a reduced search, the node is pruned
+        // the pv of the previous iteration is never pruned, since the reduced search would stop the search from following it
+        if ply_index > 0 && excluded_move.is_none() && depth >= PROBCUT_MIN_DEPTH && beta.abs() < MATE_THRESHOLD && !self.search_info.follow_pv
+            && self.is_enabled(SearchFeature::ProbCut) && !board.position.is_in_check(board.position.color_to_move)
+            && self.probcut(board, &move_list, depth, ply_index, beta, extension_budget, time_limit, board_history) {
+            self.search_info.probcut_cutoffs += 1;
+            return beta;
+        }
+
+        // without a hash move, the move ordering of the node is poor, so it is searched one ply less deep
+        // (internal iterative reduction), which is cheaper than an internal search to find a hash move,
+        // since the next iteration finds the node in the transposition table anyway
+        let mut depth = depth;
+        if ply_index > 0 && depth >= IIR_MIN_DEPTH && use_transposition_table && hash_move.is_none() && self.is_enabled(SearchFeature::InternalIterativeReduction) {
+            depth -= 1;
+            self.search_info.iir_reductions += 1;
+        }
+
+        // if no move raises alpha, the score is only an upper bound
+        let mut bound = Bound::Upper;
+        let mut best_move = None;
+
+        // if the root move is varied, the root moves are searched with a lowered alpha,
+        // so that moves scoring within the margin of the best move get exact scores
+        let root_margin = match ply_index == 0 {
+            true => self.root_move_margin,
+            false => 0,
+        };
+
+        // the number of moves searched so far, which is reported with the current root move
+        let mut move_number = 0;
+
         // iterate over all possible moves and call negamax recursively for the arising positions
         for i in 0..move_list.len() {
             let ply = move_list.get(i);
+
+            // skip the excluded move, and the root moves the search is not restricted to
+            if Some(ply) == excluded_move || (ply_index == 0 && !self.searched_root_moves.is_empty() && !self.searched_root_moves.contains(&ply)) {
+                continue;
+            }
+            move_number += 1;
+
+            // once the search has been running for a while, the current root move is reported
+            if ply_index == 0 && self.total_time.is_some_and(|instant| instant.elapsed() >= CURRMOVE_DELAY) {
+                self.send_info(format!("info currmove {ply} currmovenumber {move_number}"));
+            }
+
+            // the piece captured by the move, which has to be looked up before the move is made
+            let captured_piece = board.position.get_captured_piece(ply);
             
             // make the move
             let new_board = board.make_move(ply);
@@ -128,22 +347,42 @@ impl Search {
             // push the new position's hash to the board history
             board_history.push(new_board.position.hash);
             
+            // extend moves that give check, since the replies are forced, and recaptures, since they resolve an exchange
+            // a move is extended by at most one ply, even if several extensions apply to it
+            let mut extension = 0;
+            if self.is_enabled(SearchFeature::CheckExtension) && new_board.position.is_in_check(new_board.position.color_to_move) {
+                extension = self.search_info.extend(Extension::Check, extension_budget);
+            } else if self.is_enabled(SearchFeature::RecaptureExtension) && is_recapture(ply, captured_piece, previous_capture) {
+                extension = self.search_info.extend(Extension::Recapture, extension_budget);
+            }
+
             // the score of the new position
-            let score = -self.negamax(new_board, depth - 1, ply_index + 1, -beta, -alpha, time_limit, board_history);
+            let child_alpha = alpha.saturating_sub(root_margin).max(NEGATIVE_INFINITY);
+            let score = -self.negamax(new_board, depth - 1 + extension, ply_index + 1, -beta, -child_alpha, extension_budget - extension, captured_piece.map(|piece| (ply.target(), piece)), None, time_limit, board_history);
 
             // pop the new position's hash from the board history
             board_history.pop();
 
+            // remember the root move scores for the opening variety
+            if root_margin > 0 {
+                self.search_info.root_scores.push((ply, score));
+            }
+
             // fail-hard beta cutoff
             if score >= beta {
                 // move fails high - the opponent won't allow this move because it's too good
 
                 // check if move is a quiet move
-                if ply.captured_piece.is_none() {
+                if !ply.is_capture() && self.is_enabled(SearchFeature::KillerMoves) {
                     // store the killer moves
                     self.search_info.killer_moves[1][ply_index as usize] = self.search_info.killer_moves[0][ply_index as usize];
                     self.search_info.killer_moves[0][ply_index as usize] = ply;
                 }
+
+                // store the lower bound in the transposition table
+                if !self.stop && use_transposition_table {
+                    self.transposition_table.store(board.position.hash, depth, ply_index, beta, Bound::Lower, Some(ply));
+                }
                 return beta;
             }
             
@@ -151,12 +390,14 @@ impl Search {
             if score > alpha {
                 // update alpha to the better score
                 alpha = score;
+                bound = Bound::Exact;
+                best_move = Some(ply);
                 
                 // check if move is a quiet move
-                if ply.captured_piece.is_none() {
+                if !ply.is_capture() && self.is_enabled(SearchFeature::HistoryMoves) {
                     // store history move bonus
                     // moves closer to the root get a bigger bonus
-                    self.search_info.history_moves[ply.piece.to_index() as usize][ply.target.index as usize] = depth as i32;
+                    self.search_info.history_moves[ply.piece().to_index() as usize][ply.target().index as usize] = depth as i32;
                 }
 
                 // update the pv table
@@ -170,6 +411,210 @@ impl Search {
             // move fails low
             // if score < alpha, it means we have already found a better move
         }
+
+        // store the result in the transposition table
+        if !self.stop && use_transposition_table {
+            self.transposition_table.store(board.position.hash, depth, ply_index, alpha, bound, best_move);
+        }
         alpha
     }
-}
\ No newline at end of file
+
+    /// Returns whether a capture from the given move list beats beta by `PROBCUT_MARGIN` in a search reduced by `PROBCUT_REDUCTION` plies.
+    ///
+    /// Only captures that win enough material according to the static exchange evaluation are searched.
+    /// Each of them is verified with the quiescence search first, which is much cheaper than the reduced search.
+    /// The nodes of these verification searches are attributed to the ProbCut search stage.
+    fn probcut(&mut self, board: Board, move_list: &MoveList, depth: u64, ply_index: u64, beta: i32, extension_budget: u64, time_limit: Option<Duration>, board_history: &mut ArrayVec<u64, 1000>) -> bool {
+        let probcut_beta = beta + PROBCUT_MARGIN;
+        let static_evaluation = self.evaluate(board.position);
+        let capture_list = move_list.get_captures();
+        let previous_stage = self.search_info.verification_stage.replace(SearchStage::ProbCut);
+        let mut cutoff = false;
+
+        for i in 0..capture_list.len() {
+            let ply = capture_list.get(i);
+            if board.position.see(ply) < probcut_beta - static_evaluation {
+                continue;
+            }
+
+            let captured_piece = board.position.get_captured_piece(ply);
+            let new_board = board.make_move(ply);
+            board_history.push(new_board.position.hash);
+
+            let mut score = -self.quiescence_search(new_board.position, ply_index + 1, -probcut_beta, -probcut_beta + 1, time_limit);
+            if score >= probcut_beta {
+                score = -self.negamax(new_board, depth - PROBCUT_REDUCTION, ply_index + 1, -probcut_beta, -probcut_beta + 1, extension_budget,
+                                      captured_piece.map(|piece| (ply.target(), piece)), None, time_limit, board_history);
+            }
+            board_history.pop();
+
+            if score >= probcut_beta {
+                cutoff = true;
+                break;
+            }
+        }
+        self.search_info.verification_stage = previous_stage;
+        cutoff
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use arrayvec::ArrayVec;
+    use crate::board::Board;
+    use crate::board::piece::Piece;
+    use crate::board::square;
+    use crate::evaluation::{NEGATIVE_INFINITY, POSITIVE_INFINITY};
+    use crate::ladybug::{Message, MESSAGE_CHANNEL_CAPACITY};
+    use crate::move_gen;
+    use crate::move_gen::ply::Ply;
+    use crate::search::clock::Instant;
+    use crate::search::negamax::{is_recapture, CURRMOVE_DELAY, DEFAULT_REVERSE_FUTILITY_MARGIN, INFO_NODE_INTERVAL};
+    use crate::search::{Search, SearchCommand, SearchStage};
+
+    /// Returns a search that is ready to search, together with the receiver of its messages.
+    fn test_search() -> (Search, Receiver<Message>) {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
+        let mut search = Search::new(command_receiver, message_sender);
+        search.stop = false;
+        (search, message_receiver)
+    }
+
+    #[test]
+    fn test_is_recapture() {
+        let bxd5 = Ply::new(square::C4, square::D5, Piece::Bishop, None, true);
+
+        // recaptures of pieces of roughly equal value on the same square
+        assert!(is_recapture(bxd5, Some(Piece::Knight), Some((square::D5, Piece::Knight))));
+        assert!(is_recapture(bxd5, Some(Piece::Knight), Some((square::D5, Piece::Bishop))));
+
+        // the previous capture happened on another square
+        assert!(!is_recapture(bxd5, Some(Piece::Knight), Some((square::E4, Piece::Knight))));
+
+        // the captured pieces are of different value
+        assert!(!is_recapture(bxd5, Some(Piece::Queen), Some((square::D5, Piece::Pawn))));
+        assert!(!is_recapture(bxd5, Some(Piece::Pawn), Some((square::D5, Piece::Rook))));
+
+        // either move is not a capture
+        assert!(!is_recapture(bxd5, None, Some((square::D5, Piece::Knight))));
+        assert!(!is_recapture(bxd5, Some(Piece::Knight), None));
+    }
+
+    #[test]
+    fn test_probcut() {
+        let (mut search, _message_receiver) = test_search();
+
+        let mut probcut = |fen: &str, beta: i32| {
+            let board = Board::from_fen(fen).unwrap();
+            let move_list = move_gen::generate_moves(board.position);
+            search.probcut(board, &move_list, 5, 1, beta, 5, None, &mut ArrayVec::new())
+        };
+
+        // the knight wins the queen, which beats beta by far
+        assert!(probcut("4k3/8/8/3q4/8/2N5/8/R3K3 w - - 0 1", 0));
+        // the knight only wins a pawn, which is not enough to beat beta by the margin, and without captures, there is nothing to try
+        assert!(!probcut("4k3/8/8/3p4/8/2N5/8/4K3 w - - 0 1", 300));
+        assert!(!probcut("4k3/8/8/8/8/2N5/8/4K3 w - - 0 1", -500));
+
+        // all nodes of the verification searches are attributed to ProbCut
+        assert_eq!(0, search.search_info.stage_node_counts[SearchStage::Main.to_index() as usize]);
+        assert_eq!(0, search.search_info.stage_node_counts[SearchStage::Quiescence.to_index() as usize]);
+        assert!(search.search_info.stage_node_counts[SearchStage::ProbCut.to_index() as usize] > 0);
+        assert_eq!(None, search.search_info.verification_stage);
+    }
+
+    #[test]
+    fn test_reverse_futility_pruning() {
+        let (mut search, _message_receiver) = test_search();
+
+        let negamax = |search: &mut Search, fen: &str, depth: u64, beta: i32| {
+            let board = Board::from_fen(fen).unwrap();
+            search.transposition_table.clear();
+            search.negamax(board, depth, 1, beta - 1, beta, 0, None, None, None, &mut ArrayVec::new())
+        };
+
+        // a queen up, the static evaluation beats beta by far more than the margin
+        let fen = "4k3/8/8/8/8/8/8/3QK3 w - - 0 1";
+        assert_eq!(0, negamax(&mut search, fen, 3, 0));
+        assert_eq!(1, search.search_info.reverse_futility_cutoffs);
+
+        // nodes in check, nodes with a margin larger than the advantage and nodes deeper than the maximum depth are searched
+        // (at depth 1, the children are searched by the quiescence search, which doesn't prune them either)
+        negamax(&mut search, "4k3/8/8/8/8/8/4q3/3QK3 w - - 0 1", 1, -1500);
+        search.reverse_futility_margin = 1000;
+        negamax(&mut search, fen, 1, 0);
+        search.reverse_futility_margin = DEFAULT_REVERSE_FUTILITY_MARGIN;
+        search.reverse_futility_depth = 0;
+        negamax(&mut search, fen, 1, 0);
+        assert_eq!(1, search.search_info.reverse_futility_cutoffs);
+    }
+
+    #[test]
+    fn test_internal_iterative_reduction() {
+        let (mut search, _message_receiver) = test_search();
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+        // the position is not in the transposition table yet, so it is searched one ply less deep
+        search.negamax(board, 4, 1, NEGATIVE_INFINITY, POSITIVE_INFINITY, 0, None, None, None, &mut ArrayVec::new());
+        assert_eq!(1, search.search_info.iir_reductions);
+        let entry = search.transposition_table.probe(board.position.hash, 1).unwrap();
+        assert_eq!(3, entry.depth);
+        assert!(entry.best_move.is_some());
+
+        // the second time, the hash move is known, so the position is searched to the full depth
+        search.negamax(board, 4, 1, NEGATIVE_INFINITY, POSITIVE_INFINITY, 0, None, None, None, &mut ArrayVec::new());
+        assert_eq!(1, search.search_info.iir_reductions);
+        assert_eq!(4, search.transposition_table.probe(board.position.hash, 1).unwrap().depth);
+    }
+
+    #[test]
+    fn test_info_lines_are_throttled() {
+        let (mut search, message_receiver) = test_search();
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let (_best_move, node_count) = search.iterative_search(board, 5, None, ArrayVec::new());
+
+        // slow builds may report the current root move as well
+        let lines: Vec<String> = message_receiver.try_iter()
+            .filter_map(|message| match message {
+                Message::SearchMessage(output) if output.starts_with("info depth ") => Some(output),
+                _other => None,
+            })
+            .collect();
+        let nodes = |line: &String| -> u128 { line.split_whitespace().skip_while(|part| *part != "nodes").nth(1).unwrap().parse().unwrap() };
+
+        // the shallow iterations are not reported, but the last one always is
+        assert!(!lines.iter().any(|line| line.starts_with("info depth 1 ")), "{lines:?}");
+        assert!(lines.last().unwrap().starts_with("info depth 5 "), "{lines:?}");
+        assert_eq!(node_count, nodes(lines.last().unwrap()));
+        for (line, next_line) in lines.iter().zip(lines.iter().skip(1)) {
+            assert!(nodes(next_line) - nodes(line) >= INFO_NODE_INTERVAL || next_line == lines.last().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_currmove() {
+        let (mut search, message_receiver) = test_search();
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+        // a search that just started doesn't report its root moves
+        search.total_time = Some(Instant::now());
+        search.negamax(board, 1, 0, NEGATIVE_INFINITY, POSITIVE_INFINITY, 0, None, None, None, &mut ArrayVec::new());
+        assert_eq!(0, message_receiver.try_iter().count());
+
+        // a search that has been running for a while reports each root move
+        search.total_time = Instant::now().checked_sub(CURRMOVE_DELAY);
+        search.negamax(board, 1, 0, NEGATIVE_INFINITY, POSITIVE_INFINITY, 0, None, None, None, &mut ArrayVec::new());
+        let lines: Vec<String> = message_receiver.try_iter()
+            .filter_map(|message| match message {
+                Message::SearchMessage(output) => Some(output),
+                Message::ConsoleMessage(_input) => None,
+            })
+            .collect();
+        assert_eq!(20, lines.len());
+        assert!(lines[0].starts_with("info currmove "));
+        assert!(lines[0].ends_with(" currmovenumber 1"));
+        assert!(lines[19].ends_with(" currmovenumber 20"));
+    }
+}
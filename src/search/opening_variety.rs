@@ -0,0 +1,130 @@
+use crate::search::clock::{SystemTime, UNIX_EPOCH};
+use crate::move_gen::ply::Ply;
+use crate::search::Search;
+
+/// Root moves must score less than this number of centipawns below the best move to be picked by the opening variety.
+pub const OPENING_VARIETY_MARGIN: i32 = 20;
+
+/// Returns a seed for the random number generator of the opening variety, based on the current time.
+pub fn get_random_seed() -> u64 {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_nanos() as u64).unwrap_or(0);
+    // the xorshift generator must not be seeded with zero
+    nanos | 1
+}
+
+/// Returns the next state of the [xorshift](https://en.wikipedia.org/wiki/Xorshift) random number generator with the given state,
+/// which is also the next random number.
+pub fn xorshift(mut state: u64) -> u64 {
+    state ^= state << 13;
+    state ^= state >> 7;
+    state ^= state << 17;
+    state
+}
+
+impl Search {
+    /// Randomly picks one of the given root moves that scored within the root move margin of the best score.
+    /// Scores at the lower end of the margin are only upper bounds, which is why those moves are excluded.
+    /// Returns None if no root moves are given.
+    pub fn pick_varied_root_move(&mut self, root_scores: &[(Ply, i32)]) -> Option<Ply> {
+        let best_score = root_scores.iter().map(|(_ply, score)| *score).max()?;
+        let candidates: Vec<Ply> = root_scores.iter()
+            .filter(|(_ply, score)| *score > best_score - self.root_move_margin)
+            .map(|(ply, _score)| *ply)
+            .collect();
+
+        let index = self.next_random() % candidates.len() as u64;
+        Some(candidates[index as usize])
+    }
+
+    /// Returns the next number of the random number generator of the search.
+    fn next_random(&mut self) -> u64 {
+        self.random_state = xorshift(self.random_state);
+        self.random_state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use arrayvec::ArrayVec;
+    use crate::board::Board;
+    use crate::board::piece::Piece;
+    use crate::board::square;
+    use crate::ladybug::Message;
+    use crate::move_gen::ply::Ply;
+    use crate::search::{Search, SearchCommand};
+    use crate::search::opening_variety::OPENING_VARIETY_MARGIN;
+
+    #[test]
+    fn test_pick_varied_root_move() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        search.root_move_margin = OPENING_VARIETY_MARGIN;
+
+        assert_eq!(None, search.pick_varied_root_move(&[]));
+
+        let e4 = Ply::new(square::E2, square::E4, Piece::Pawn, None, false);
+        let d4 = Ply::new(square::D2, square::D4, Piece::Pawn, None, false);
+        let nf3 = Ply::new(square::G1, square::F3, Piece::Knight, None, false);
+        let a4 = Ply::new(square::A2, square::A4, Piece::Pawn, None, false);
+        let root_scores = [(e4, 30), (d4, 31 - OPENING_VARIETY_MARGIN), (nf3, 25), (a4, 30 - OPENING_VARIETY_MARGIN)];
+
+        // only moves within the margin are picked, and all of them are picked eventually
+        let mut picked = Vec::new();
+        for _ in 0..100 {
+            let ply = search.pick_varied_root_move(&root_scores).unwrap();
+            assert_ne!(a4, ply);
+            if !picked.contains(&ply) {
+                picked.push(ply);
+            }
+        }
+        assert_eq!(3, picked.len());
+    }
+
+    #[test]
+    fn test_get_root_move_margin() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        let opening = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let middlegame = Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 20").unwrap();
+        assert_eq!(0, search.get_root_move_margin(&opening));
+
+        // the opening variety only applies to the first moves
+        search.opening_variety = 8;
+        assert_eq!(OPENING_VARIETY_MARGIN, search.get_root_move_margin(&opening));
+        assert_eq!(0, search.get_root_move_margin(&middlegame));
+
+        // the variety applies throughout the game, and the larger margin is used
+        search.variety = 50;
+        assert_eq!(50, search.get_root_move_margin(&opening));
+        assert_eq!(50, search.get_root_move_margin(&middlegame));
+    }
+
+    #[test]
+    fn test_iterative_search_varies_root_move() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+
+        // without the opening variety, the search always plays the same move
+        let (best_move, _node_count) = search.iterative_search(board, 3, None, ArrayVec::new());
+        for _ in 0..3 {
+            assert_eq!(best_move, search.iterative_search(board, 3, None, ArrayVec::new()).0);
+        }
+
+        // with the opening variety, several moves of the starting position are played
+        search.root_move_margin = OPENING_VARIETY_MARGIN;
+        let mut best_moves = Vec::new();
+        for _ in 0..10 {
+            let (best_move, _node_count) = search.iterative_search(board, 3, None, ArrayVec::new());
+            if !best_moves.contains(&best_move) {
+                best_moves.push(best_move);
+            }
+        }
+        assert!(best_moves.len() > 1);
+    }
+}
@@ -1,56 +1,37 @@
 use crate::board::position::Position;
 use crate::move_gen;
-use crate::search::Search;
+use crate::move_gen::perft::{perft_driver, PerftResult, MAX_PERFT_DEPTH};
+use crate::move_gen::ply::Ply;
+use crate::search::{root_split, Search};
+use crate::search::clock::Instant;
 
 impl Search {
-    /// This function performs a [Perft](https://www.chessprogramming.org/Perft) (Performance Test).
-    /// A perft counts the number of leaf nodes for a fixed depth, and serves two purposes:
-    /// - verify that the move generation is working correctly
-    /// - measure the speed of the move generation
+    /// Performs a perft of the given position to the given depth, see `move_gen::perft`,
+    /// sends the node count of each root move and the total node count, and returns the total node count.
+    ///
+    /// With several threads, the root moves are split among the threads, and the results are sent once all threads are finished.
     pub fn perft(&self, position: Position, depth: u64) -> u64 {
-        // used to measure the elapsed time
-        let time = std::time::Instant::now();
-
-        // the number of leaf nodes
-        let mut node_count: u64 = 0;
-
-        // generate all legal moves for the position
-        let move_list = move_gen::generate_moves(position);
-
-        // call the perft_driver function for all legal moves and add the results to node_count
-        for i in 0..move_list.len() {
-            let ply = move_list.get(i);
-            let node_count_inner = self.perft_driver(position.make_move(ply), depth - 1);
-            node_count += node_count_inner;
-            self.send_output(format!("{ply}: {node_count_inner}"));
+        let result = match self.threads > 1 && depth > 0 {
+            true => self.split_perft(position, depth),
+            false => move_gen::perft::perft(position, depth),
+        };
+        for line in result.to_string().lines() {
+            self.send_output(String::from(line));
         }
-
-        self.send_output(format!("Searched {node_count} nodes in {:?}", time.elapsed()));
-
-        node_count
+        result.nodes
     }
 
-    /// This is the recursive perft driver function, which is required by the `perft` function.
-    /// It is used to traverse the tree and count the number of leaf nodes.
-    fn perft_driver(&self, position: Position, depth: u64) -> u64 {
-        // if depth is zero, return a node count of 1 to break out of the recursion
-        if depth == 0 {
-            return 1;
-        }
-
-        // the number of leaf nodes
-        let mut node_count: u64 = 0;
-
-        // generate all legal moves for the position
+    /// Performs a perft of the given position to the given depth, which must not be 0,
+    /// with the root moves split among the threads of the search.
+    fn split_perft(&self, position: Position, depth: u64) -> PerftResult {
+        let time = Instant::now();
+        let depth = depth.min(MAX_PERFT_DEPTH);
         let move_list = move_gen::generate_moves(position);
+        let root_moves: Vec<Ply> = (0..move_list.len()).map(|i| move_list.get(i)).collect();
 
-        // call the perft_driver function recursively for all legal moves and add the results to node_count
-        for i in 0..move_list.len() {
-            let ply = move_list.get(i);
-            node_count += self.perft_driver(position.make_move(ply), depth - 1);
-        }
-
-        node_count
+        let workers = vec![(); self.threads.min(root_moves.len()).max(1)];
+        let (node_counts, _workers) = root_split::distribute(&root_moves, workers, |_worker, ply| perft_driver(position.make_move(*ply), depth - 1));
+        PerftResult::new(depth, root_moves.into_iter().zip(node_counts).collect(), time.elapsed())
     }
 }
 
@@ -63,21 +44,12 @@ mod tests {
     //! ----------------------------------------------------------------------------------------------------------------------------------------
 
     use std::sync::mpsc;
-    use std::sync::mpsc::{Receiver, Sender};
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
     use std::thread;
     use crate::board::Board;
-    use crate::ladybug::Message;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
+    use crate::ladybug::{Message, MESSAGE_CHANNEL_CAPACITY};
     use crate::search::{Search, SearchCommand};
 
-    /// Helper function to initialize the lookup table.
-    fn initialize_lookup_table() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-    }
-    
     /// Creates a search instance and spawns a test thread that will take the search thread's output.
     /// We don't actually care for the output since we only look at the returned u64 value,
     /// but since the search tries to send the results to the main thread, the receiver must not be dropped.
@@ -86,17 +58,16 @@ mod tests {
         let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
         
         // create a test_sender and test_receiver so that search thread can send output to the test thread
-        let (test_sender, test_receiver): (Sender<Message>, Receiver<Message>) = mpsc::channel();
+        let (test_sender, test_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
 
         // initialize the search
         let search = Search::new(search_command_receiver, test_sender);
         
         // spawn the test thread
         thread::spawn(move || {
-            loop {
-                let _search_output = test_receiver.recv();
-                search_command_sender.send(SearchCommand::Stop).unwrap();
-            };
+            // the command sender is kept alive, so that the search doesn't see a closed connection
+            let _search_command_sender = search_command_sender;
+            while test_receiver.recv().is_ok() {}
         });
         
         search
@@ -105,10 +76,20 @@ mod tests {
     // ----------------------------------------------------------------------------------------------------------------------------------------
     // Position 1 - Starting Position (https://www.chessprogramming.org/Perft_Results#Initial_Position)
     // ----------------------------------------------------------------------------------------------------------------------------------------
+    #[test]
+    // starting position depth 0, also with several threads
+    fn perft_position1_depth0() {
+        let mut search = setup();
+
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(1, search.perft(position, 0));
+        search.threads = 3;
+        assert_eq!(1, search.perft(position, 0));
+    }
+
     #[test]
     // starting position depth 1
     fn perft_position1_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -118,7 +99,6 @@ mod tests {
     #[test]
     // starting position depth 2
     fn perft_position1_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -128,18 +108,26 @@ mod tests {
     #[test]
     // starting position depth 3
     fn perft_position1_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(8_902, search.perft(position, 3));
     }
 
+    #[test]
+    // starting position depth 3, with the root moves split among several threads
+    fn perft_position1_depth3_threads() {
+        let mut search = setup();
+        search.threads = 3;
+
+        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
+        assert_eq!(8_902, search.perft(position, 3));
+    }
+
     #[test]
     #[ignore]
     // starting position depth 4
     fn perft_position1_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -150,7 +138,6 @@ mod tests {
     #[ignore]
     // starting position depth 5
     fn perft_position1_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
@@ -163,7 +150,6 @@ mod tests {
     #[test]
     // position 2 depth 1
     fn perft_position2_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
@@ -173,7 +159,6 @@ mod tests {
     #[test]
     // position 2 depth 2
     fn perft_position2_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
@@ -183,7 +168,6 @@ mod tests {
     #[test]
     // position 2 depth 3
     fn perft_position2_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
@@ -194,7 +178,6 @@ mod tests {
     #[ignore]
     // position 2 depth 4
     fn perft_position2_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
@@ -205,7 +188,6 @@ mod tests {
     #[ignore]
     // position 2 depth 5
     fn perft_position2_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap().position;
@@ -218,7 +200,6 @@ mod tests {
     #[test]
     // position 3 depth 1
     fn perft_position3_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
@@ -228,7 +209,6 @@ mod tests {
     #[test]
     // position 3 depth 2
     fn perft_position3_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
@@ -238,7 +218,6 @@ mod tests {
     #[test]
     // position 3 depth 3
     fn perft_position3_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
@@ -249,7 +228,6 @@ mod tests {
     #[ignore]
     // position 3 depth 4
     fn perft_position3_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
@@ -260,7 +238,6 @@ mod tests {
     #[ignore]
     // position 3 depth 5
     fn perft_position3_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap().position;
@@ -273,7 +250,6 @@ mod tests {
     #[test]
     // position 4 depth 1
     fn perft_position4_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
@@ -283,7 +259,6 @@ mod tests {
     #[test]
     // position 4 depth 2
     fn perft_position4_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
@@ -293,7 +268,6 @@ mod tests {
     #[test]
     // position 4 depth 3
     fn perft_position4_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
@@ -304,7 +278,6 @@ mod tests {
     #[ignore]
     // position 4 depth 4
     fn perft_position4_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
@@ -315,7 +288,6 @@ mod tests {
     #[ignore]
     // position 4 depth 5
     fn perft_position4_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap().position;
@@ -328,7 +300,6 @@ mod tests {
     #[test]
     // position 5 depth 1
     fn perft_position5_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
@@ -338,7 +309,6 @@ mod tests {
     #[test]
     // position 5 depth 2
     fn perft_position5_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
@@ -348,7 +318,6 @@ mod tests {
     #[test]
     // position 5 depth 3
     fn perft_position5_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
@@ -359,7 +328,6 @@ mod tests {
     #[ignore]
     // position 5 depth 4
     fn perft_position5_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
@@ -370,7 +338,6 @@ mod tests {
     #[ignore]
     // position 5 depth 5
     fn perft_position5_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap().position;
@@ -383,7 +350,6 @@ mod tests {
     #[test]
     // position 6 depth 1
     fn perft_position6_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
@@ -393,7 +359,6 @@ mod tests {
     #[test]
     // position 6 depth 2
     fn perft_position6_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
@@ -403,7 +368,6 @@ mod tests {
     #[test]
     // position 6 depth 3
     fn perft_position6_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
@@ -414,7 +378,6 @@ mod tests {
     #[ignore]
     // position 6 depth 4
     fn perft_position6_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
@@ -425,7 +388,6 @@ mod tests {
     #[ignore]
     // position 6 depth 5
     fn perft_position6_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").unwrap().position;
@@ -438,7 +400,6 @@ mod tests {
     #[test]
     // position 7 depth 1
     fn perft_position7_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
@@ -448,7 +409,6 @@ mod tests {
     #[test]
     // position 7 depth 2
     fn perft_position7_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
@@ -458,7 +418,6 @@ mod tests {
     #[test]
     // position 7 depth 3
     fn perft_position7_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
@@ -469,7 +428,6 @@ mod tests {
     #[ignore]
     // position 7 depth 4
     fn perft_position7_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
@@ -480,7 +438,6 @@ mod tests {
     #[ignore]
     // position 7 depth 5
     fn perft_position7_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
@@ -491,7 +448,6 @@ mod tests {
     #[ignore]
     // position 7 depth 6
     fn perft_position7_depth6() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1").unwrap().position;
@@ -505,7 +461,6 @@ mod tests {
     #[test]
     // position 8 depth 1
     fn perft_position8_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -515,7 +470,6 @@ mod tests {
     #[test]
     // position 8 depth 2
     fn perft_position8_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -525,7 +479,6 @@ mod tests {
     #[test]
     // position 8 depth 3
     fn perft_position8_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -536,7 +489,6 @@ mod tests {
     #[ignore]
     // position 8 depth 4
     fn perft_position8_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -547,7 +499,6 @@ mod tests {
     #[ignore]
     // position 8 depth 5
     fn perft_position8_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -558,7 +509,6 @@ mod tests {
     #[ignore]
     // position 8 depth 6
     fn perft_position8_depth6() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5bk1/8/2Pp4/8/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -571,7 +521,6 @@ mod tests {
     #[test]
     // position 9 depth 1
     fn perft_position9_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
@@ -581,7 +530,6 @@ mod tests {
     #[test]
     // position 9 depth 2
     fn perft_position9_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
@@ -591,7 +539,6 @@ mod tests {
     #[test]
     // position 9 depth 3
     fn perft_position9_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
@@ -601,7 +548,6 @@ mod tests {
     #[test]
     // position 9 depth 4
     fn perft_position9_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
@@ -612,7 +558,6 @@ mod tests {
     #[ignore]
     // position 9 depth 5
     fn perft_position9_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
@@ -623,7 +568,6 @@ mod tests {
     #[ignore]
     // position 9 depth 6
     fn perft_position9_depth6() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/8/2pP4/8/5BK1/8 b - d3 0 1").unwrap().position;
@@ -636,7 +580,6 @@ mod tests {
     #[test]
     // position 10 depth 1
     fn perft_position10_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -646,7 +589,6 @@ mod tests {
     #[test]
     // position 10 depth 2
     fn perft_position10_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -656,7 +598,6 @@ mod tests {
     #[test]
     // position 10 depth 3
     fn perft_position10_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -667,7 +608,6 @@ mod tests {
     #[ignore]
     // position 10 depth 4
     fn perft_position10_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -678,7 +618,6 @@ mod tests {
     #[ignore]
     // position 10 depth 5
     fn perft_position10_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -689,7 +628,6 @@ mod tests {
     #[ignore]
     // position 10 depth 6
     fn perft_position10_depth6() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/5k2/8/2Pp4/2B5/1K6/8/8 w - d6 0 1").unwrap().position;
@@ -702,7 +640,6 @@ mod tests {
     #[test]
     // position 11 depth 1
     fn perft_position11_depth1() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
@@ -712,7 +649,6 @@ mod tests {
     #[test]
     // position 11 depth 2
     fn perft_position11_depth2() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
@@ -722,7 +658,6 @@ mod tests {
     #[test]
     // position 11 depth 3
     fn perft_position11_depth3() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
@@ -733,7 +668,6 @@ mod tests {
     #[ignore]
     // position 11 depth 4
     fn perft_position11_depth4() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
@@ -744,7 +678,6 @@ mod tests {
     #[ignore]
     // position 11 depth 5
     fn perft_position11_depth5() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
@@ -755,7 +688,6 @@ mod tests {
     #[ignore]
     // position 11 depth 6
     fn perft_position11_depth6() {
-        initialize_lookup_table();
         let search = setup();
         
         let position = Board::from_fen("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1").unwrap().position;
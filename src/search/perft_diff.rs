@@ -0,0 +1,119 @@
+//! The perft diff module compares the move generation against an external reference engine, such as Stockfish,
+//! and finds the first position in which they disagree.
+//!
+//! Both generators divide a perft of the position into the node counts of the root moves. If the moves differ,
+//! the position is reported together with the moves only one of them generates. Otherwise, the first move with
+//! differing node counts is played and the search continues one ply deeper, until the moves of a position differ.
+//! This pinpoints rare bugs, like castling through check, which only show up in a single position deep in the tree.
+
+use crate::board::Board;
+use crate::move_gen::perft::divide;
+use crate::search::Search;
+use crate::search::engine_match::UciEngine;
+
+/// The first difference between the divide output of Ladybug and of the reference engine.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Divergence {
+    /// Both generate the same moves with the same node counts.
+    None,
+    /// The moves differ: the moves only the reference engine generates are missing, the moves only Ladybug generates are illegal.
+    Moves { missing: Vec<String>, illegal: Vec<String> },
+    /// Both generate the same moves, but the node counts of the given move differ.
+    NodeCount { ply: String, node_count: u64, reference: u64 },
+}
+
+/// Compares the node counts of the root moves of Ladybug with the ones of the reference engine, regardless of their order.
+pub fn compare_divides(node_counts: &[(String, u64)], reference: &[(String, u64)]) -> Divergence {
+    let missing: Vec<String> = reference.iter()
+        .filter(|(ply, _)| !node_counts.iter().any(|(own_ply, _)| own_ply == ply))
+        .map(|(ply, _)| ply.clone())
+        .collect();
+    let illegal: Vec<String> = node_counts.iter()
+        .filter(|(ply, _)| !reference.iter().any(|(reference_ply, _)| reference_ply == ply))
+        .map(|(ply, _)| ply.clone())
+        .collect();
+    if !missing.is_empty() || !illegal.is_empty() {
+        return Divergence::Moves { missing, illegal };
+    }
+
+    for (ply, node_count) in node_counts {
+        if let Some((_, reference)) = reference.iter().find(|(reference_ply, count)| reference_ply == ply && count != node_count) {
+            return Divergence::NodeCount { ply: ply.clone(), node_count: *node_count, reference: *reference };
+        }
+    }
+    Divergence::None
+}
+
+impl Search {
+    /// Compares a perft of the given board to the given depth with the one of the external engine at the given path,
+    /// and reports the first position in which the move generation differs, or that both agree.
+    /// Returns the FEN of the divergent position, if one was found.
+    pub fn perft_diff(&self, mut board: Board, mut depth: u64, engine_path: &str) -> Option<String> {
+        let mut engine = match UciEngine::start(engine_path) {
+            Ok(engine) => engine,
+            Err(message) => {
+                self.send_output(format!("info string {message}"));
+                return None;
+            }
+        };
+
+        while depth > 0 && !self.is_aborted() {
+            let node_counts = divide(board.position, depth);
+            let reference = match engine.divide(&board, depth) {
+                Ok(reference) => reference,
+                Err(message) => {
+                    self.send_output(format!("info string {message}"));
+                    return None;
+                }
+            };
+
+            let own: Vec<(String, u64)> = node_counts.iter().map(|(ply, node_count)| (ply.to_string(), *node_count)).collect();
+            match compare_divides(&own, &reference) {
+                Divergence::None => {
+                    let node_count: u64 = node_counts.iter().map(|(_, node_count)| node_count).sum();
+                    self.send_output(format!("Perft matches {} at depth {depth}: {node_count} nodes", engine.name));
+                    return None;
+                }
+                Divergence::Moves { missing, illegal } => {
+                    let fen = board.to_fen();
+                    self.send_output(format!("Divergent position: {fen}"));
+                    self.send_output(format!("Missing moves: {}", missing.join(" ")));
+                    self.send_output(format!("Illegal moves: {}", illegal.join(" ")));
+                    return Some(fen);
+                }
+                Divergence::NodeCount { ply, node_count, reference } => {
+                    self.send_output(format!("{ply}: {node_count} nodes, expected {reference} at depth {depth}"));
+                    let (ply, _) = node_counts.into_iter().find(|(own_ply, _)| own_ply.to_string() == ply).expect("the move was generated");
+                    board = board.make_move(ply);
+                    depth -= 1;
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::perft_diff::{compare_divides, Divergence};
+
+    /// Helper function that converts the given divide output to node counts.
+    fn to_node_counts(divide: &[(&str, u64)]) -> Vec<(String, u64)> {
+        divide.iter().map(|(ply, node_count)| (ply.to_string(), *node_count)).collect()
+    }
+
+    #[test]
+    fn test_compare_divides() {
+        let node_counts = to_node_counts(&[("e1g1", 20), ("e1c1", 22), ("h1h2", 18)]);
+
+        // the order of the moves doesn't matter
+        assert_eq!(Divergence::None, compare_divides(&node_counts, &to_node_counts(&[("h1h2", 18), ("e1c1", 22), ("e1g1", 20)])));
+
+        assert_eq!(Divergence::NodeCount { ply: String::from("e1c1"), node_count: 22, reference: 21 },
+                   compare_divides(&node_counts, &to_node_counts(&[("e1g1", 20), ("e1c1", 21), ("h1h2", 18)])));
+
+        // differing moves are reported before differing node counts
+        assert_eq!(Divergence::Moves { missing: vec![String::from("h1h3")], illegal: vec![String::from("e1c1")] },
+                   compare_divides(&node_counts, &to_node_counts(&[("e1g1", 21), ("h1h2", 18), ("h1h3", 18)])));
+    }
+}
@@ -0,0 +1,181 @@
+//! The perft suite module verifies the move generation against the node counts of other engines.
+//!
+//! Each line of a perft suite contains a FEN, followed by the expected node counts for one or more depths,
+//! e.g. `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400 ;D3 8902`.
+//! In addition to the node counts, the suite verifies that the incrementally updated Zobrist hash
+//! of every position in the tree matches the hash computed from scratch.
+
+use std::fs;
+use crate::board::Board;
+use crate::board::position::Position;
+use crate::move_gen;
+use crate::search::clock::Instant;
+use crate::search::Search;
+use crate::zobrist;
+
+/// A single test of a perft suite: a position, a depth, and the expected number of leaf nodes.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PerftEntry {
+    /// The position to count the leaf nodes of.
+    pub board: Board,
+    /// The depth to count the leaf nodes at.
+    pub depth: u64,
+    /// The expected number of leaf nodes.
+    pub node_count: u64,
+}
+
+impl PerftEntry {
+    /// Parses a single line of a perft suite, which contains one test for each depth.
+    /// The FEN may omit the halfmove clock and the fullmove number.
+    pub fn parse(line: &str) -> Result<Vec<PerftEntry>, String> {
+        let mut fields = line.split(';');
+        let fen = fields.next().unwrap_or("").trim();
+        // perft suites may contain positions that can't occur in a game, which still test the move generation
+        let board = Board::from_fen_unchecked(fen).map_err(|_| format!("Invalid FEN in perft suite: {fen}"))?;
+
+        let mut entries = Vec::new();
+        for field in fields.map(|field| field.trim()).filter(|field| !field.is_empty()) {
+            let invalid_count = || format!("Invalid node count in perft suite: {field}");
+            let (depth, node_count) = field.strip_prefix('D').and_then(|count| count.split_once(' ')).ok_or_else(invalid_count)?;
+            entries.push(PerftEntry {
+                board,
+                depth: depth.parse().map_err(|_| invalid_count())?,
+                node_count: node_count.trim().parse().map_err(|_| invalid_count())?,
+            });
+        }
+
+        if entries.is_empty() {
+            return Err(format!("No node counts in perft suite: {line}"));
+        }
+        Ok(entries)
+    }
+}
+
+/// Reads all tests from the given perft suite. Empty lines and lines starting with '#' are skipped.
+pub fn read_file(path: &str) -> Result<Vec<PerftEntry>, String> {
+    let content = fs::read_to_string(path).map_err(|_| String::from("Could not read perft suite"))?;
+    let lines = content.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(PerftEntry::parse)
+        .collect::<Result<Vec<Vec<PerftEntry>>, String>>()?;
+    Ok(lines.into_iter().flatten().collect())
+}
+
+impl Search {
+    /// Runs a perft suite and reports the result of each test, followed by the number of passed tests.
+    /// For the first failing test, the node count of each root move is reported as well, so that it can be compared
+    /// against the divide output of another engine to pinpoint the faulty move.
+    /// Returns the number of passed tests.
+    pub fn run_perft_suite(&self, entries: Vec<PerftEntry>) -> usize {
+        // used to measure the elapsed time
+        let start_time = Instant::now();
+
+        let mut passed = 0;
+        let mut divided = false;
+
+        for (index, entry) in entries.iter().enumerate() {
+            let position = entry.board.position;
+            match verified_perft(position, entry.depth) {
+                Ok(node_count) if node_count == entry.node_count => {
+                    passed += 1;
+                    self.send_output(format!("{}: depth {} nodes {node_count} passed", index + 1, entry.depth));
+                }
+                Ok(node_count) => {
+                    self.send_output(format!("{}: depth {} nodes {node_count} failed, expected {}", index + 1, entry.depth, entry.node_count));
+                    if !divided {
+                        divided = true;
+                        self.send_output(format!("Divide for {}", entry.board.to_fen()));
+                        let move_list = move_gen::generate_moves(position);
+                        for i in 0..move_list.len() {
+                            let ply = move_list.get(i);
+                            let node_count = verified_perft(position.make_move(ply), entry.depth.saturating_sub(1)).unwrap_or(0);
+                            self.send_output(format!("{ply}: {node_count}"));
+                        }
+                    }
+                }
+                Err(fen) => self.send_output(format!("{}: depth {} failed, wrong hash in {fen}", index + 1, entry.depth)),
+            }
+        }
+
+        self.send_output(format!("Passed {passed} of {} perft tests in {} ms", entries.len(), start_time.elapsed().as_millis()));
+
+        passed
+    }
+}
+
+/// Counts the leaf nodes of the given position at the given depth, like `perft`, without reporting anything.
+/// If the hash of a position in the tree does not match the hash computed from scratch, the FEN of the position is returned as error.
+fn verified_perft(position: Position, depth: u64) -> Result<u64, String> {
+    if position.hash != zobrist::get_hash(&position) {
+        return Err(Board { position, halfmove_clock: 0, fullmove_counter: 1 }.to_fen());
+    }
+    if depth == 0 {
+        return Ok(1);
+    }
+
+    let move_list = move_gen::generate_moves(position);
+    let mut node_count = 0;
+    for i in 0..move_list.len() {
+        node_count += verified_perft(position.make_move(move_list.get(i)), depth - 1)?;
+    }
+    Ok(node_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use crate::ladybug::Message;
+    use crate::search::perft_suite::{PerftEntry, read_file};
+    use crate::search::{Search, SearchCommand};
+
+    #[test]
+    fn test_parse() {
+        let entries = PerftEntry::parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400").unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!((1, 20), (entries[0].depth, entries[0].node_count));
+        assert_eq!((2, 400), (entries[1].depth, entries[1].node_count));
+
+        // the FEN may only contain the first four fields
+        let entries = PerftEntry::parse("4k3/8/8/8/8/8/8/4K2R w K - ;D1 15").unwrap();
+        assert_eq!("4k3/8/8/8/8/8/8/4K2R w K - 0 1", entries[0].board.to_fen());
+
+        assert!(PerftEntry::parse("4k3/8/8/8/8/8/8/4K2R w K - 0 1").is_err());
+        assert!(PerftEntry::parse("4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1").is_err());
+        assert!(PerftEntry::parse("4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;1 15").is_err());
+        assert!(PerftEntry::parse("4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 many").is_err());
+        assert!(PerftEntry::parse("this is no fen ;D1 15").is_err());
+    }
+
+    #[test]
+    fn test_read_file() {
+        let path = std::env::temp_dir().join("ladybug_test_read_perft_suite.epd");
+        std::fs::write(&path, "# a comment\n\nrnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400\n4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 15\n").unwrap();
+        assert_eq!(3, read_file(path.to_str().unwrap()).unwrap().len());
+        assert!(read_file("/this/file/does/not/exist.epd").is_err());
+    }
+
+    #[test]
+    fn test_run_perft_suite() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let search = Search::new(command_receiver, message_sender);
+
+        // the node count of the second test is wrong, which is reported together with the divide output
+        let mut entries = PerftEntry::parse("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1 ;D1 48 ;D2 2039").unwrap();
+        entries[1].node_count = 2040;
+        assert_eq!(1, search.run_perft_suite(entries));
+
+        let mut output = Vec::new();
+        while let Ok(Message::SearchMessage(line)) = message_receiver.try_recv() {
+            output.push(line);
+        }
+        assert_eq!("1: depth 1 nodes 48 passed", output[0]);
+        assert_eq!("2: depth 2 nodes 2039 failed, expected 2040", output[1]);
+        assert_eq!("Divide for r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", output[2]);
+        assert_eq!(48 + 4, output.len());
+        assert!(output[3..51].iter().all(|line| line.split(": ").nth(1).is_some_and(|count| count.parse::<u64>().is_ok())));
+        assert!(output[51].starts_with("Passed 1 of 2 perft tests"));
+    }
+}
@@ -0,0 +1,365 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::board::position::Position;
+use crate::move_gen;
+use crate::move_gen::ply::Ply;
+use crate::search::clock::Instant;
+use crate::search::{fallback_best_move, Search};
+
+/// The maximum number of nodes the proof-number search may store, across all mate lengths.
+pub const PNS_MAX_NODES: usize = 4_000_000;
+
+/// The proof or disproof number of a node that can't be proven or disproven anymore.
+const INFINITY: u32 = u32::MAX;
+
+/// A node of the proof-number search tree.
+/// The position of the node is not stored, but recreated by playing the moves from the root.
+#[derive(Copy, Clone, Debug)]
+struct PnsNode {
+    /// The move that leads to the node.
+    ply: Ply,
+    /// The index of the parent node.
+    parent: u32,
+    /// The index of the first child node, or 0 if the node has not been expanded.
+    first_child: u32,
+    /// The number of child nodes.
+    num_children: u8,
+    /// The minimum number of leaf nodes that have to be proven to prove the node.
+    proof: u32,
+    /// The minimum number of leaf nodes that have to be disproven to disprove the node.
+    disproof: u32,
+}
+
+/// The result of a proof-number search for a single mate length.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PnsResult {
+    /// The mate was proven, the principal variation leads to the mate.
+    Proven(Vec<Ply>),
+    /// There is no mate in the given number of moves.
+    Disproven,
+    /// The node limit was reached or the search was stopped before the mate could be proven or disproven.
+    Unknown,
+}
+
+/// A [proof-number search](https://www.chessprogramming.org/Proof-Number_Search) tree, which tries to prove a mate for the side to move.
+///
+/// Nodes where the attacker is to move are OR nodes, since a single mating move proves them.
+/// Nodes where the defender is to move are AND nodes, since all defending moves have to be refuted.
+/// The search always expands the most-proving node, which is the leaf whose solution changes the root the most.
+struct PnsTree {
+    /// All nodes of the tree, the root is stored at index 0.
+    nodes: Vec<PnsNode>,
+    /// The position at the root of the tree.
+    root_position: Position,
+    /// The maximum ply index of the tree. Defending nodes at this ply index that are not mated are disproven.
+    max_ply_index: u64,
+}
+
+impl PnsTree {
+    /// Constructs a tree that tries to prove a mate in the given number of moves for the side to move.
+    fn new(root_position: Position, moves: u64) -> PnsTree {
+        let max_ply_index = moves * 2 - 1;
+        let (proof, disproof) = Self::initialize(root_position, 0, max_ply_index);
+        let root = PnsNode { ply: Ply::default(), parent: 0, first_child: 0, num_children: 0, proof, disproof };
+        PnsTree { nodes: vec![root], root_position, max_ply_index }
+    }
+
+    /// Returns true if the attacker is to move at the given ply index.
+    fn is_or_node(ply_index: u64) -> bool {
+        ply_index.is_multiple_of(2)
+    }
+
+    /// Returns the initial proof and disproof numbers of a new node.
+    /// Mates and positions that can't lead to a mate anymore are solved immediately.
+    /// Otherwise, the numbers are initialized with the number of legal moves, since a node with many moves is harder to solve.
+    fn initialize(position: Position, ply_index: u64, max_ply_index: u64) -> (u32, u32) {
+        let num_moves = move_gen::generate_moves(position).len() as u32;
+        if num_moves == 0 {
+            // only a mate of the defender proves the node, stalemates and mates of the attacker disprove it
+            return match !Self::is_or_node(ply_index) && position.is_in_check(position.color_to_move) {
+                true => (0, INFINITY),
+                false => (INFINITY, 0),
+            };
+        }
+        if ply_index >= max_ply_index {
+            // the attacker has no moves left to deliver the mate
+            return (INFINITY, 0);
+        }
+        match Self::is_or_node(ply_index) {
+            true => (1, num_moves),
+            false => (num_moves, 1),
+        }
+    }
+
+    /// Returns the children of the given node.
+    fn children(&self, index: usize) -> &[PnsNode] {
+        let node = &self.nodes[index];
+        &self.nodes[node.first_child as usize..node.first_child as usize + node.num_children as usize]
+    }
+
+    /// Expands most-proving nodes until the root is solved, the tree contains the maximum number of nodes, or the stop flag is set.
+    fn search(&mut self, max_nodes: usize, stop_flag: &AtomicBool) {
+        while self.nodes[0].proof != 0 && self.nodes[0].disproof != 0 && self.nodes.len() < max_nodes && !stop_flag.load(Ordering::Relaxed) {
+            // find the most-proving node by following the children with the smallest proof numbers at OR nodes,
+            // and with the smallest disproof numbers at AND nodes
+            let mut index = 0;
+            let mut position = self.root_position;
+            let mut ply_index = 0;
+            while self.nodes[index].first_child != 0 {
+                let first_child = self.nodes[index].first_child as usize;
+                let children = self.children(index);
+                let best_child = match Self::is_or_node(ply_index) {
+                    true => children.iter().enumerate().min_by_key(|(_, child)| child.proof),
+                    false => children.iter().enumerate().min_by_key(|(_, child)| child.disproof),
+                }.map(|(child_index, _)| first_child + child_index).unwrap();
+
+                position = position.make_move(self.nodes[best_child].ply);
+                ply_index += 1;
+                index = best_child;
+            }
+
+            self.expand(index, position, ply_index);
+            self.update_ancestors(index, ply_index);
+        }
+    }
+
+    /// Adds the children of the given node to the tree.
+    fn expand(&mut self, index: usize, position: Position, ply_index: u64) {
+        let move_list = move_gen::generate_moves(position);
+        self.nodes[index].first_child = self.nodes.len() as u32;
+        self.nodes[index].num_children = move_list.len();
+
+        for i in 0..move_list.len() {
+            let ply = move_list.get(i);
+            let (proof, disproof) = Self::initialize(position.make_move(ply), ply_index + 1, self.max_ply_index);
+            self.nodes.push(PnsNode { ply, parent: index as u32, first_child: 0, num_children: 0, proof, disproof });
+        }
+    }
+
+    /// Recalculates the proof and disproof numbers of the given node and all of its ancestors.
+    fn update_ancestors(&mut self, mut index: usize, mut ply_index: u64) {
+        loop {
+            let children = self.children(index);
+            let min_proof = children.iter().map(|child| child.proof).min().unwrap_or(INFINITY);
+            let min_disproof = children.iter().map(|child| child.disproof).min().unwrap_or(INFINITY);
+            let sum_proof = children.iter().fold(0, |sum: u32, child| sum.saturating_add(child.proof));
+            let sum_disproof = children.iter().fold(0, |sum: u32, child| sum.saturating_add(child.disproof));
+
+            let node = &mut self.nodes[index];
+            match Self::is_or_node(ply_index) {
+                true => {
+                    node.proof = min_proof;
+                    node.disproof = sum_disproof;
+                }
+                false => {
+                    node.proof = sum_proof;
+                    node.disproof = min_disproof;
+                }
+            }
+
+            if index == 0 {
+                return;
+            }
+            index = node.parent as usize;
+            ply_index -= 1;
+        }
+    }
+
+    /// Returns the number of plies until the mate of the given proven node, assuming the defender delays the mate as long as possible.
+    fn mate_distance(&self, index: usize, ply_index: u64) -> u64 {
+        if self.nodes[index].first_child == 0 {
+            // a proven leaf is a mate
+            return 0;
+        }
+        let distances = self.children(index).iter().enumerate()
+            .filter(|(_, child)| child.proof == 0)
+            .map(|(child_index, _)| 1 + self.mate_distance(self.nodes[index].first_child as usize + child_index, ply_index + 1));
+        match Self::is_or_node(ply_index) {
+            true => distances.min().unwrap(),
+            false => distances.max().unwrap(),
+        }
+    }
+
+    /// Returns the principal variation of the proven root, where the attacker mates as fast as possible and the defender delays the mate.
+    fn principal_variation(&self) -> Vec<Ply> {
+        let mut pv = Vec::new();
+        let mut index = 0;
+        let mut ply_index = 0;
+        while self.nodes[index].first_child != 0 {
+            let first_child = self.nodes[index].first_child as usize;
+            let proven_children = self.children(index).iter().enumerate()
+                .filter(|(_, child)| child.proof == 0)
+                .map(|(child_index, _)| (first_child + child_index, self.mate_distance(first_child + child_index, ply_index + 1)));
+            let (best_child, _distance) = match Self::is_or_node(ply_index) {
+                true => proven_children.min_by_key(|(_, distance)| *distance),
+                false => proven_children.max_by_key(|(_, distance)| *distance),
+            }.unwrap();
+
+            pv.push(self.nodes[best_child].ply);
+            index = best_child;
+            ply_index += 1;
+        }
+        pv
+    }
+
+    /// Returns the result of the search.
+    fn result(&self) -> PnsResult {
+        match (self.nodes[0].proof, self.nodes[0].disproof) {
+            (0, _) => PnsResult::Proven(self.principal_variation()),
+            (_, 0) => PnsResult::Disproven,
+            _other => PnsResult::Unknown,
+        }
+    }
+}
+
+/// Tries to prove a mate in exactly the given number of moves or less for the side to move, using at most the given number of nodes.
+/// The search ends early once the given stop flag is set.
+/// Returns the result and the number of nodes that were used.
+pub fn prove_mate(position: Position, moves: u64, max_nodes: usize, stop_flag: &AtomicBool) -> (PnsResult, usize) {
+    let mut tree = PnsTree::new(position, moves);
+    tree.search(max_nodes, stop_flag);
+    (tree.result(), tree.nodes.len())
+}
+
+impl Search {
+    /// Searches for a mate in the given number of moves with proof-number search, which solves mate problems
+    /// that are too deep for the alpha-beta search.
+    ///
+    /// The mate lengths are tried in increasing order, so that the shortest mate is found.
+    /// The search can be stopped with the "stop" command, in which case the first legal move is sent as the best move.
+    /// Returns the principal variation of the mate, if one was found.
+    pub fn mate_search(&self, position: Position, moves: u64) -> Option<Vec<Ply>> {
+        // used to measure the elapsed time
+        let start_time = Instant::now();
+
+        let mut total_node_count = 0;
+
+        for mate_length in 1..=moves {
+            let (result, node_count) = prove_mate(position, mate_length, PNS_MAX_NODES.saturating_sub(total_node_count), &self.stop_flag);
+            total_node_count += node_count;
+
+            match result {
+                PnsResult::Proven(pv) => {
+                    let mut output = format!("info depth {} nodes {total_node_count} time {} score mate {mate_length} pv", pv.len(), start_time.elapsed().as_millis());
+                    for ply in pv.iter() {
+                        output += format!(" {ply}").as_str();
+                    }
+                    self.send_output(output);
+                    self.send_output(format!("bestmove {}", pv[0]));
+                    return Some(pv);
+                }
+                PnsResult::Disproven => {}
+                PnsResult::Unknown => {
+                    self.send_output(format!("info string mate search stopped after {total_node_count} nodes"));
+                    self.send_output(fallback_best_move(position));
+                    return None;
+                }
+            }
+        }
+
+        self.send_output(format!("info string no mate in {moves} found"));
+        self.send_output(fallback_best_move(position));
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use crate::board::Board;
+    use crate::ladybug::Message;
+    use crate::move_gen::ply::Ply;
+    use crate::search::{Search, SearchCommand};
+    use crate::search::pns::{PNS_MAX_NODES, PnsResult, prove_mate};
+
+    /// Helper function that converts the given moves to plies.
+    fn to_plies(fen: &str, moves: &[&str]) -> Vec<Ply> {
+        let mut board = Board::from_fen(fen).unwrap();
+        let mut plies = Vec::new();
+        for move_string in moves {
+            let ply = Ply::from_string(move_string, board.position).unwrap();
+            board = board.make_move(ply);
+            plies.push(ply);
+        }
+        plies
+    }
+
+    #[test]
+    fn test_prove_mate() {
+        // mate in one
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1";
+        let position = Board::from_fen(fen).unwrap().position;
+        assert_eq!(PnsResult::Proven(to_plies(fen, &["h5f7"])), prove_mate(position, 1, PNS_MAX_NODES, &AtomicBool::new(false)).0);
+
+        // mate in two
+        let fen = "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1";
+        let position = Board::from_fen(fen).unwrap().position;
+        assert_eq!(PnsResult::Disproven, prove_mate(position, 1, PNS_MAX_NODES, &AtomicBool::new(false)).0);
+        assert_eq!(PnsResult::Proven(to_plies(fen, &["d5f6", "g7f6", "c4f7"])), prove_mate(position, 2, PNS_MAX_NODES, &AtomicBool::new(false)).0);
+
+        // mate in three, for black
+        let fen = "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1";
+        let position = Board::from_fen(fen).unwrap().position;
+        assert_eq!(PnsResult::Disproven, prove_mate(position, 2, PNS_MAX_NODES, &AtomicBool::new(false)).0);
+        match prove_mate(position, 3, PNS_MAX_NODES, &AtomicBool::new(false)).0 {
+            PnsResult::Proven(pv) => assert_eq!(5, pv.len()),
+            other => panic!("expected a mate, got {other:?}"),
+        }
+
+        // stalemate is no mate
+        let position = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap().position;
+        assert_eq!(PnsResult::Disproven, prove_mate(position, 3, PNS_MAX_NODES, &AtomicBool::new(false)).0);
+
+        // the node limit is respected
+        let position = Board::from_fen("r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1").unwrap().position;
+        let (result, node_count) = prove_mate(position, 3, 100, &AtomicBool::new(false));
+        assert_eq!(PnsResult::Unknown, result);
+        assert!(node_count < 100 + 256);
+
+        // the stop flag ends the search
+        let (result, node_count) = prove_mate(position, 3, PNS_MAX_NODES, &AtomicBool::new(true));
+        assert_eq!(PnsResult::Unknown, result);
+        assert_eq!(1, node_count);
+    }
+
+    #[test]
+    fn test_mate_search() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let search = Search::new(command_receiver, message_sender);
+
+        // the shortest mate is reported, even if a longer one is searched for
+        let fen = "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1";
+        let pv = search.mate_search(Board::from_fen(fen).unwrap().position, 4).unwrap();
+        assert_eq!(to_plies(fen, &["d5f6", "g7f6", "c4f7"]), pv);
+
+        let output: Vec<String> = message_receiver.try_iter().map(|message| match message {
+            Message::SearchMessage(output) => output,
+            Message::ConsoleMessage(output) => output,
+        }).collect();
+        assert!(output[0].starts_with("info depth 3 nodes "));
+        assert!(output[0].ends_with(" score mate 2 pv d5f6 g7f6 c4f7"));
+        assert_eq!("bestmove d5f6", output[1]);
+
+        // no mate
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(None, search.mate_search(Board::from_fen(fen).unwrap().position, 2));
+        let output: Vec<String> = message_receiver.try_iter().map(|message| match message {
+            Message::SearchMessage(output) => output,
+            Message::ConsoleMessage(output) => output,
+        }).collect();
+        assert_eq!("info string no mate in 2 found", output[0]);
+        assert!(output[1].starts_with("bestmove "));
+
+        // stopped search
+        search.stop_flag().store(true, Ordering::Relaxed);
+        assert_eq!(None, search.mate_search(Board::from_fen(fen).unwrap().position, 5));
+        let output: Vec<String> = message_receiver.try_iter().map(|message| match message {
+            Message::SearchMessage(output) => output,
+            Message::ConsoleMessage(output) => output,
+        }).collect();
+        assert_eq!("info string mate search stopped after 1 nodes", output[0]);
+        assert!(output[1].starts_with("bestmove "));
+    }
+}
@@ -0,0 +1,117 @@
+//! Pondering lets Ladybug search during the opponent's time, on the position after the reply she expects the opponent to play.
+//!
+//! The GUI starts pondering with "go ponder" and the clock times for the move after the expected reply.
+//! The ponder search ignores its time limit until the opponent plays the expected reply, which the GUI reports with "ponderhit".
+//! Afterwards, it continues as a regular timed search, and the ponder credit decides how much of the time spent pondering
+//! counts towards the time of the move. If the opponent plays another move, the GUI stops the ponder search instead.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use arrayvec::ArrayVec;
+use crate::board::Board;
+use crate::search::clock::Instant;
+use crate::search::Search;
+
+/// The default percentage of the time spent pondering that counts towards the time of the move after a ponderhit.
+pub const DEFAULT_PONDER_CREDIT: u32 = 50;
+
+/// The maximum ponder credit, at which all of the time spent pondering counts towards the time of the move.
+pub const MAX_PONDER_CREDIT: u32 = 100;
+
+/// The time a finished ponder search waits between its checks for the ponderhit.
+const PONDERHIT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Returns the instant from which the time of the move is measured after a ponderhit,
+/// so that the given percentage of the time spent pondering since the given start is credited towards the time of the move.
+pub fn credited_start(ponder_start: Instant, ponderhit: Instant, credit: u32) -> Instant {
+    let credited = ponderhit.saturating_duration_since(ponder_start) * credit.min(MAX_PONDER_CREDIT) / MAX_PONDER_CREDIT;
+    ponderhit.checked_sub(credited).unwrap_or(ponder_start)
+}
+
+impl Search {
+    /// Handles the "Ponder" command.
+    pub(super) fn handle_ponder(&mut self, board: Board, time: u64, board_history: ArrayVec<u64, 1000>) {
+        self.pondering = true;
+        self.handle_search(board, None, Some(time), None, board_history);
+        self.pondering = false;
+    }
+
+    /// Turns the ponder search into a regular timed search once the GUI reported the ponderhit,
+    /// crediting the time spent pondering according to the ponder credit.
+    pub(super) fn check_ponderhit(&mut self) {
+        if !self.pondering || self.ponder_flag.load(Ordering::Relaxed) {
+            return;
+        }
+        self.pondering = false;
+        self.total_time = self.total_time.map(|ponder_start| credited_start(ponder_start, Instant::now(), self.ponder_credit));
+    }
+
+    /// Waits until the GUI reports the ponderhit or stops the search, since the best move of a ponder search
+    /// must not be sent before, even if the search finished early.
+    pub(super) fn wait_for_ponderhit(&mut self) {
+        while self.pondering && self.ponder_flag.load(Ordering::Relaxed) && !self.is_aborted() {
+            thread::sleep(PONDERHIT_POLL_INTERVAL);
+        }
+        self.pondering = false;
+    }
+
+    /// Returns the flag that is set while the current search ponders, which is shared with Ladybug.
+    /// Ladybug clears it when the GUI reports the ponderhit.
+    pub fn ponder_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.ponder_flag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use std::thread;
+    use std::time::Duration;
+    use arrayvec::ArrayVec;
+    use crate::board::Board;
+    use crate::ladybug::Message;
+    use crate::search::clock::Instant;
+    use crate::search::ponder::credited_start;
+    use crate::search::{Search, SearchCommand};
+
+    #[test]
+    fn test_credited_start() {
+        let ponder_start = Instant::now();
+        let ponderhit = ponder_start + Duration::from_millis(1000);
+        assert_eq!(ponderhit, credited_start(ponder_start, ponderhit, 0));
+        assert_eq!(ponder_start + Duration::from_millis(500), credited_start(ponder_start, ponderhit, 50));
+        assert_eq!(ponder_start, credited_start(ponder_start, ponderhit, 100));
+        assert_eq!(ponder_start, credited_start(ponder_start, ponderhit, 1000));
+    }
+
+    #[test]
+    fn test_ponder_search_waits_for_the_ponderhit() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        search.ponder_credit = 100;
+        let ponder_flag = search.ponder_flag();
+        ponder_flag.store(true, Ordering::Relaxed);
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+
+        // the time limit of 10 milliseconds is ignored until the ponderhit
+        let start_time = Instant::now();
+        let ponderhit = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            ponder_flag.store(false, Ordering::Relaxed);
+        });
+        search.handle_ponder(board, 10, ArrayVec::new());
+        let _ = ponderhit.join();
+        assert!(start_time.elapsed() >= Duration::from_millis(300));
+
+        let output: Vec<String> = message_receiver.try_iter().filter_map(|message| match message {
+            Message::SearchMessage(output) if output.starts_with("bestmove") => Some(output),
+            _other => None,
+        }).collect();
+        assert_eq!(1, output.len());
+    }
+}
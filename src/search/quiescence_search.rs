@@ -1,28 +1,30 @@
 use std::time::Duration;
 use crate::board::position::Position;
 use crate::{evaluation, move_gen};
-use crate::search::{Search};
+use crate::search::{Search, SearchStage};
 
 impl Search {
     /// The [Quiescence Search](https://www.chessprogramming.org/Quiescence_Search) function is very similar
     /// to the negamax function, but instead of looking at all moves, it only looks at captures.
     /// It also uses something called a "standing pat", which is initialized with the static evaluation and is
     /// used to cause beta-cutoffs earlier, thus reducing the number of nodes searched.
-    pub fn quiescence_search(&mut self, position: Position, ply_index: u64, mut alpha: i32, beta: i32, time_limit: Duration) -> i32 {
-        // check if the time limit is reached
-        if let Some(instant) = self.total_time {
-            if instant.elapsed() > time_limit {
-                // the time limit is reached - break out of recursion immediately
-                self.stop = true;
-                return 0;
-            }
+    pub fn quiescence_search(&mut self, position: Position, ply_index: u64, mut alpha: i32, beta: i32, time_limit: Option<Duration>) -> i32 {
+        // check if the time limit or the node limit is reached
+        if self.is_limit_reached(time_limit) {
+            // break out of recursion immediately
+            return 0;
         }
 
         // increment the number of nodes searched
-        self.search_info.node_count += 1;
+        self.search_info.count_node(SearchStage::Quiescence, ply_index);
+
+        // in some variants, the game can be lost without being checkmated, which is scored like a checkmate
+        if position.variant_winner().is_some() {
+            return evaluation::NEGATIVE_INFINITY + (ply_index as i32 * 5000);
+        }
 
         // Establish the lower bound of the score with the static evaluation
-        let standing_pat = evaluation::evaluate(position); 
+        let standing_pat = self.evaluate(position); 
         
         // fail-hard beta cutoff
         if standing_pat >= beta {
@@ -40,7 +42,7 @@ impl Search {
         let mut capture_list = move_gen::generate_moves(position).get_captures();
 
         // sort the capture list
-        capture_list.sort(&mut self.search_info, ply_index);
+        capture_list.sort(&position, &mut self.search_info, ply_index, None);
 
         // iterate over all capture moves and call the quiescence search recursively for the arising positions
         for i in 0..capture_list.len() {
@@ -0,0 +1,241 @@
+//! The root split module searches the root moves in parallel: the root moves are distributed among the threads set with the
+//! "Threads" option, and each thread searches its moves with its own part of the memory set with the "Hash" option.
+//! The first thread uses the transposition table of the search, the other threads use tables that are kept between the searches.
+//!
+//! Since the threads don't share any bounds, each thread only knows the best score among its own moves,
+//! which costs more nodes than searching the moves one after another.
+//! Therefore, the root split is only used for perft and for searches to a fixed depth, whose results don't depend on the timing of the threads.
+
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::sync::mpsc;
+use std::thread;
+use arrayvec::ArrayVec;
+use crate::board::Board;
+use crate::evaluation::{NEGATIVE_INFINITY, POSITIVE_INFINITY};
+use crate::move_gen;
+use crate::move_gen::ply::Ply;
+use crate::search::{Search, SearchFeature, SearchInfo};
+use crate::search::clock::Instant;
+use crate::search::eval_cache::EvalCache;
+use crate::search::transposition_table::TranspositionTable;
+
+/// The maximum number of threads the search can use.
+pub const MAX_THREADS: usize = 64;
+
+/// The result of searching a single root move.
+struct RootMoveResult {
+    /// The root move.
+    ply: Ply,
+    /// The score of the deepest completed iteration, or None if the search was stopped before the first one,
+    /// or if the move is not better than a move searched before by the same thread.
+    score: Option<i32>,
+    /// The principal variation of the deepest completed iteration, starting with the root move.
+    pv: Vec<Ply>,
+    /// The number of nodes searched across all iterations.
+    node_count: u128,
+    /// The highest ply index reached across all iterations.
+    seldepth: u64,
+}
+
+impl Search {
+    /// Searches the given position to the given depth, with the root moves distributed among the threads.
+    /// Each root move is searched with iterative deepening on its own, and the best move is the one with the highest score.
+    /// The moves of each thread are searched with the best score of the thread so far as lower bound.
+    /// Returns the best move and the number of nodes searched, or None if there are no root moves to search.
+    pub(super) fn split_search(&mut self, board: Board, depth: u64, board_history: &ArrayVec<u64, 1000>) -> Option<(Ply, u128)> {
+        let move_list = move_gen::generate_moves(board.position);
+        let root_moves: Vec<Ply> = (0..move_list.len()).map(|i| move_list.get(i)).filter(|ply| self.is_searched_root_move(*ply)).collect();
+        let first_move = *root_moves.first()?;
+
+        // the first thread borrows the tables of this search, the others use the tables allocated for the "Threads" option
+        let threads = self.threads.min(root_moves.len());
+        let mut tables = vec![(self.transposition_table.take(), self.eval_cache.take())];
+        tables.extend(self.split_tables.drain(..threads - 1));
+        let workers: Vec<_> = tables.into_iter().map(|(mut transposition_table, eval_cache)| {
+            transposition_table.new_search();
+            (self.split_worker(transposition_table, eval_cache), NEGATIVE_INFINITY)
+        }).collect();
+
+        let start_time = Instant::now();
+        let (results, workers) = distribute(&root_moves, workers, |(worker, best_score), ply| {
+            let result = worker.search_root_move(board, *ply, depth, *best_score, board_history);
+            *best_score = result.score.unwrap_or(*best_score);
+            result
+        });
+
+        let node_count: u128 = results.iter().map(|result| result.node_count).sum();
+        let hashfull = workers.iter().map(|(worker, _best_score)| worker.transposition_table.hashfull()).sum::<u64>() / workers.len() as u64;
+
+        let mut tables = workers.into_iter().map(|(worker, _best_score)| (worker.transposition_table, worker.eval_cache));
+        (self.transposition_table, self.eval_cache) = tables.next().expect("the first thread uses the tables of this search");
+        self.split_tables.splice(0..0, tables);
+
+        // among equal scores, the move that comes first in the move list is picked, so that the result doesn't depend on the threads
+        let Some(best) = results.iter().filter(|result| result.score.is_some()).min_by_key(|result| Reverse(result.score)) else {
+            return Some((first_move, node_count));
+        };
+
+        let time_elapsed = start_time.elapsed().as_millis();
+        let nps = (node_count * 1000).checked_div(time_elapsed).unwrap_or(node_count);
+        let seldepth = results.iter().map(|result| result.seldepth).max().unwrap_or(0);
+        let mut output = format!("info depth {depth} seldepth {seldepth} nodes {node_count} nps {nps} time {time_elapsed} hashfull {hashfull} {} pv", self.score_info(best.score.unwrap_or(0)));
+        for ply in best.pv.iter() {
+            output += format!(" {ply}").as_str();
+        }
+        self.send_info(output);
+
+        Some((best.ply, node_count))
+    }
+
+    /// Constructs a search that searches root moves on behalf of this search, with the given transposition table and evaluation cache.
+    /// It uses the same settings and stop flag as this search, but never receives commands.
+    fn split_worker(&self, transposition_table: TranspositionTable, eval_cache: EvalCache) -> Search {
+        let (_command_sender, command_receiver) = mpsc::channel();
+        Search {
+            command_receiver,
+            message_sender: self.message_sender.clone(),
+            total_time: None,
+            node_limit: None,
+            optimum_time: None,
+            iteration_node_limit: None,
+            stop: false,
+            stop_flag: self.stop_flag(),
+            quit: Cell::new(self.quit.get()),
+            synchronous: false,
+            unsent_best_move: Cell::new(None),
+            ponder_flag: self.ponder_flag(),
+            pondering: false,
+            ponder_credit: self.ponder_credit,
+            show_ponder_move: false,
+            ponder_move: None,
+            search_info: SearchInfo::default(),
+            transposition_table,
+            eval_cache,
+            hash_size: self.hash_size,
+            split_tables: Vec::new(),
+            debug: false,
+            opening_variety: 0,
+            variety: 0,
+            skill_level: self.skill_level,
+            contempt: self.contempt,
+            show_wdl: self.show_wdl,
+            root_move_margin: 0,
+            random_state: self.random_state,
+            excluded_move: None,
+            excluded_root_move: None,
+            search_moves: Vec::new(),
+            searched_root_moves: Vec::new(),
+            features: self.features,
+            reverse_futility_margin: self.reverse_futility_margin,
+            reverse_futility_depth: self.reverse_futility_depth,
+            threads: 1,
+            last_score: None,
+        }
+    }
+
+    /// Searches the given root move with iterative deepening, up to the given depth of the root.
+    /// Moves that don't score higher than alpha only get an upper bound, and no score is returned for them.
+    fn search_root_move(&mut self, board: Board, ply: Ply, depth: u64, alpha: i32, board_history: &ArrayVec<u64, 1000>) -> RootMoveResult {
+        let mut result = RootMoveResult { ply, score: None, pv: vec![ply], node_count: 0, seldepth: 0 };
+
+        let captured_piece = board.position.get_captured_piece(ply);
+        let new_board = board.make_move(ply);
+        let mut board_history = board_history.clone();
+        board_history.push(new_board.position.hash);
+
+        // the root move is extended like in the search of the whole position, a recapture is impossible at the root though
+        let extension = u64::from(self.is_enabled(SearchFeature::CheckExtension) && new_board.position.is_in_check(new_board.position.color_to_move));
+
+        for iteration_depth in 1..=depth {
+            let score = -self.negamax(new_board, iteration_depth - 1 + extension, 1, -POSITIVE_INFINITY, -alpha, iteration_depth - extension,
+                                      captured_piece.map(|piece| (ply.target(), piece)), None, None, &mut board_history);
+            result.node_count += self.search_info.node_count;
+            result.seldepth = result.seldepth.max(self.search_info.seldepth);
+
+            if self.stop {
+                break;
+            }
+
+            result.score = (score > alpha).then_some(score);
+            if result.score.is_some() {
+                result.pv = vec![ply];
+                result.pv.extend_from_slice(&self.search_info.pv_table[1][1..self.search_info.pv_length[1] as usize]);
+            }
+            self.search_info.clear_iteration();
+        }
+
+        result
+    }
+}
+
+/// Distributes the given items round robin among the given workers, each of which runs on its own thread,
+/// and applies the given function to each item with the worker the item was assigned to.
+/// Returns the results in the order of the items, together with the workers.
+pub(super) fn distribute<T: Sync, W: Send, R: Send>(items: &[T], workers: Vec<W>, function: impl Fn(&mut W, &T) -> R + Sync) -> (Vec<R>, Vec<W>) {
+    let num_workers = workers.len();
+    let function = &function;
+
+    let finished: Vec<(W, Vec<R>)> = thread::scope(|scope| {
+        let handles: Vec<_> = workers.into_iter().enumerate().map(|(index, mut worker)| scope.spawn(move || {
+            let results: Vec<R> = items.iter().skip(index).step_by(num_workers).map(|item| function(&mut worker, item)).collect();
+            (worker, results)
+        })).collect();
+
+        // a panic of a worker is passed on, as if the items were processed on the calling thread
+        handles.into_iter().map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))).collect()
+    });
+
+    let (workers, results): (Vec<W>, Vec<Vec<R>>) = finished.into_iter().unzip();
+    let mut results: Vec<_> = results.into_iter().map(|results| results.into_iter()).collect();
+    let results = (0..items.len()).filter_map(|index| results[index % num_workers].next()).collect();
+    (results, workers)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use arrayvec::ArrayVec;
+    use crate::board::Board;
+    use crate::search::{Search, SearchCommand};
+    use crate::search::root_split::distribute;
+    use crate::search::transposition_table::TranspositionTable;
+
+    #[test]
+    fn test_distribute() {
+        let items: Vec<u64> = (1..=10).collect();
+        let (results, workers) = distribute(&items, vec![0; 3], |processed: &mut u64, item| {
+            *processed += 1;
+            item * item
+        });
+        assert_eq!(items.iter().map(|item| item * item).collect::<Vec<u64>>(), results);
+        assert_eq!(vec![4, 3, 3], workers);
+    }
+
+    #[test]
+    fn test_split_tables_share_the_hash_size() {
+        let (_command_sender, command_receiver) = mpsc::channel();
+        let (message_sender, _message_receiver) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        let total_len = || TranspositionTable::new(8).len();
+
+        search.handle_command(SearchCommand::HashSize(8));
+        search.handle_command(SearchCommand::Threads(4));
+        assert_eq!(3, search.split_tables.len());
+        let table_lens = |search: &Search| search.split_tables.iter().map(|(transposition_table, _eval_cache)| transposition_table.len()).sum::<usize>() + search.transposition_table.len();
+        assert!(table_lens(&search) <= total_len());
+        assert!(table_lens(&search) > total_len() - 4);
+
+        // the split search gives the tables back to the search and keeps them for the next one
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+        assert!(search.split_search(board, 2, &ArrayVec::new()).is_some());
+        assert_eq!(3, search.split_tables.len());
+        assert!(!search.eval_cache.is_empty());
+        assert!(table_lens(&search) <= total_len());
+        assert!(search.transposition_table.stats().used > 0);
+
+        search.handle_command(SearchCommand::Threads(1));
+        assert!(search.split_tables.is_empty());
+        assert_eq!(total_len(), search.transposition_table.len());
+    }
+}
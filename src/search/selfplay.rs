@@ -0,0 +1,230 @@
+//! The selfplay module lets Ladybug play games against herself, which allows a quick sanity check of changes
+//! to the evaluation or the search without any external tools.
+//!
+//! The games start from the starting position, or from the positions of an EPD file, which are used in turn.
+//! Optionally, the games are adjudicated once their outcome is clear, and written to a PGN file.
+
+use std::fs;
+use std::time::Duration;
+use crate::board::Board;
+use crate::game::{Game, GameResult, MAX_GAME_LENGTH};
+use crate::move_gen::ply::Ply;
+use crate::search::{MAX_PLY, Search};
+use crate::search::adjudication::{Adjudication, Adjudicator};
+use crate::search::skill_level;
+
+/// The settings of a selfplay match.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SelfPlaySettings {
+    /// The number of games to play.
+    pub games: u64,
+    /// The time in milliseconds that is searched for each move.
+    pub move_time: u64,
+    /// The positions the games start from, which are used in turn. If there are none, the games start from the starting position.
+    pub openings: Vec<Board>,
+    /// The file the games are written to in PGN, if any.
+    pub pgn_path: Option<String>,
+    /// The rules for ending the games early.
+    pub adjudication: Adjudication,
+}
+
+/// The results of a selfplay match.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct SelfPlayResult {
+    /// The number of games won by White.
+    pub white_wins: u64,
+    /// The number of games won by Black.
+    pub black_wins: u64,
+    /// The number of drawn games.
+    pub draws: u64,
+}
+
+impl Search {
+    /// Plays the given number of games against itself and reports the result of each game, followed by the total result.
+    /// Games are adjudicated according to the adjudication rules, and games that reach the maximum game length are adjudicated as draws.
+    /// Returns the total result.
+    pub fn run_selfplay(&mut self, settings: &SelfPlaySettings) -> SelfPlayResult {
+        let mut result = SelfPlayResult::default();
+        let mut pgn = String::new();
+        let mut pgn_path = settings.pgn_path.clone();
+
+        for game_index in 0..settings.games {
+            // the games are abandoned if Ladybug stops them or quits in the meantime
+            if self.is_aborted() {
+                break;
+            }
+            let mut game = match settings.openings.is_empty() {
+                true => Game::new(),
+                false => Game::from_board(settings.openings[game_index as usize % settings.openings.len()]),
+            };
+
+            self.new_game();
+            let mut adjudicator = Adjudicator::new(settings.adjudication);
+            let mut adjudicated = None;
+            while adjudicated.is_none() && game.outcome().is_none() && game.board_history().len() < MAX_GAME_LENGTH && !self.is_aborted() {
+                let board = game.board();
+                let (best_move, score) = self.search_game_move(&game, settings.move_time);
+                if game.make_move(best_move).is_err() {
+                    break;
+                }
+                adjudicated = adjudicator.add_score(&board, score);
+            }
+
+            let (game_result, reason) = match (game.outcome(), adjudicated) {
+                (Some(outcome), _adjudicated) => (outcome.result(), format!("{outcome:?}")),
+                (None, Some(adjudicated)) => adjudicated,
+                (None, None) => (GameResult::Draw, String::from("Adjudicated")),
+            };
+            match game_result {
+                GameResult::WhiteWins => result.white_wins += 1,
+                GameResult::BlackWins => result.black_wins += 1,
+                GameResult::Draw => result.draws += 1,
+            }
+            self.send_output(format!("Game {} of {}: {} ({reason})", game_index + 1, settings.games, game_result.to_pgn()));
+
+            // the file is rewritten after each game, so that the finished games are kept if the match is aborted
+            if let Some(path) = &pgn_path {
+                let name = format!("Ladybug {}", env!("CARGO_PKG_VERSION"));
+                let tags = [
+                    ("Event", String::from("Ladybug selfplay")),
+                    ("Site", String::from("?")),
+                    ("Date", String::from("????.??.??")),
+                    ("Round", format!("{}", game_index + 1)),
+                    ("White", name.clone()),
+                    ("Black", name),
+                ];
+                pgn += game.to_pgn(&tags, Some(game_result)).as_str();
+                pgn += "\n";
+                if !self.save_pgn(path, pgn.as_str()) {
+                    pgn_path = None;
+                }
+            }
+        }
+
+        self.send_output(format!("Played {} games: White won {}, Black won {}, {} draws", settings.games, result.white_wins, result.black_wins, result.draws));
+
+        result
+    }
+
+    /// Searches the current position of the given game for the given amount of milliseconds, and returns the best move
+    /// together with its score from the point of view of the side to move, if the search completed an iteration.
+    /// Like in a regular search, the opening variety and the skill level apply.
+    ///
+    /// The search always completes depth 1, even if the time runs out before, so that the moves of a game
+    /// don't depend on how busy the machine is, and a mate in one is never missed.
+    pub(super) fn search_game_move(&mut self, game: &Game, time: u64) -> (Ply, Option<i32>) {
+        let board = game.board();
+        self.root_move_margin = self.get_root_move_margin(&board);
+        let depth_limit = skill_level::skill_depth(self.skill_level).unwrap_or(MAX_PLY as u64);
+        let (mut best_move, _node_count) = self.iterative_search(board, depth_limit, Some(Duration::from_millis(time)), game.board_history().clone());
+        if self.last_score.is_none() && !self.is_aborted() {
+            (best_move, _) = self.iterative_search(board, 1, None, game.board_history().clone());
+        }
+        self.root_move_margin = 0;
+        (best_move, self.last_score)
+    }
+
+    /// Writes the given games to the given PGN file.
+    /// If the file can't be written, this is reported, and false is returned.
+    pub(super) fn save_pgn(&self, path: &str, pgn: &str) -> bool {
+        let saved = fs::write(path, pgn).is_ok();
+        if !saved {
+            self.send_output(String::from("info string Could not write PGN file"));
+        }
+        saved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use crate::board::Board;
+    use crate::board::piece::Piece;
+    use crate::board::square;
+    use crate::game::Game;
+    use crate::ladybug::Message;
+    use crate::move_gen::ply::Ply;
+    use crate::search::adjudication::{Adjudication, DrawRule, ResignRule};
+    use crate::search::selfplay::{SelfPlayResult, SelfPlaySettings};
+    use crate::search::{Search, SearchCommand};
+    use crate::search::transposition_table::MATE_THRESHOLD;
+
+    #[test]
+    fn test_run_selfplay() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+
+        // White mates in one in the first opening, while the second one is drawn right away
+        let path = std::env::temp_dir().join("ladybug_test_selfplay.pgn");
+        let settings = SelfPlaySettings {
+            games: 3,
+            move_time: 50,
+            openings: vec![
+                Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 2 4").unwrap(),
+                Board::from_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap(),
+            ],
+            pgn_path: Some(path.to_str().unwrap().to_string()),
+            adjudication: Adjudication::default(),
+        };
+        assert_eq!(SelfPlayResult { white_wins: 2, black_wins: 0, draws: 1 }, search.run_selfplay(&settings));
+
+        let output: Vec<String> = message_receiver.try_iter().filter_map(|message| match message {
+            Message::SearchMessage(output) if !output.starts_with("info") => Some(output),
+            _other => None,
+        }).collect();
+        assert_eq!("Game 1 of 3: 1-0 (Checkmate(White))", output[0]);
+        assert_eq!("Game 2 of 3: 1/2-1/2 (InsufficientMaterial)", output[1]);
+        assert_eq!("Game 3 of 3: 1-0 (Checkmate(White))", output[2]);
+        assert_eq!("Played 3 games: White won 2, Black won 0, 1 draws", output[3]);
+
+        let pgn = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(3, pgn.matches("[Event \"Ladybug selfplay\"]").count());
+        assert!(pgn.contains("[Round \"3\"]\n"));
+        assert!(pgn.contains("\n4. Qxf7# 1-0\n"));
+        assert!(pgn.contains("[FEN \"4k3/8/8/8/8/8/8/4KN2 w - - 0 1\"]\n\n1/2-1/2\n"));
+    }
+
+    #[test]
+    fn test_run_selfplay_with_adjudication() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+
+        // Black is a queen down in the first opening, while the second one is level
+        let settings = SelfPlaySettings {
+            games: 2,
+            move_time: 50,
+            openings: vec![
+                Board::from_fen("4k3/8/8/8/8/8/3PP3/3QK3 b - - 0 1").unwrap(),
+                Board::from_fen("4k3/3pp3/8/8/8/8/3PP3/4K3 w - - 0 1").unwrap(),
+            ],
+            pgn_path: None,
+            adjudication: Adjudication {
+                resign: Some(ResignRule { move_count: 1, score: 500 }),
+                draw: Some(DrawRule { move_number: 1, move_count: 2, score: 300 }),
+            },
+        };
+        assert_eq!(SelfPlayResult { white_wins: 1, black_wins: 0, draws: 1 }, search.run_selfplay(&settings));
+
+        let output: Vec<String> = message_receiver.try_iter().filter_map(|message| match message {
+            Message::SearchMessage(output) if output.starts_with("Game") => Some(output),
+            _other => None,
+        }).collect();
+        assert_eq!(vec!["Game 1 of 2: 1-0 (Black resigns)", "Game 2 of 2: 1/2-1/2 (Adjudicated draw)"], output);
+    }
+
+    #[test]
+    fn test_search_game_move_completes_depth_1() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+
+        // without any time, the mate in one is still found
+        let game = Game::from_fen("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 2 4").unwrap();
+        let (best_move, score) = search.search_game_move(&game, 0);
+        assert_eq!(Ply::new(square::H5, square::F7, Piece::Queen, None, true), best_move);
+        assert!(score.is_some_and(|score| score > MATE_THRESHOLD));
+    }
+}
@@ -0,0 +1,46 @@
+//! The skill level weakens Ladybug, so that casual players have a chance to beat her.
+//!
+//! Below the maximum skill level, the search depth is limited, and the best move is picked randomly
+//! among the root moves that scored within a margin of the best one. The lower the skill level,
+//! the shallower the search and the larger the margin.
+
+/// The maximum skill level, at which Ladybug plays at full strength.
+pub const MAX_SKILL_LEVEL: u32 = 20;
+
+/// The margin in centipawns within which root moves are picked randomly grows by this number with each skill level below the maximum.
+const MARGIN_PER_SKILL_LEVEL: i32 = 15;
+
+/// Returns the maximum search depth for the given skill level, or None if the depth is not limited.
+pub fn skill_depth(skill_level: u32) -> Option<u64> {
+    match skill_level < MAX_SKILL_LEVEL {
+        true => Some(skill_level as u64 / 2 + 1),
+        false => None,
+    }
+}
+
+/// Returns the margin in centipawns within which the best move is picked randomly among the root moves for the given skill level.
+/// At the maximum skill level, the margin is 0, so that the best move is always played.
+pub fn skill_margin(skill_level: u32) -> i32 {
+    MAX_SKILL_LEVEL.saturating_sub(skill_level) as i32 * MARGIN_PER_SKILL_LEVEL
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::search::skill_level::{MAX_SKILL_LEVEL, skill_depth, skill_margin};
+
+    #[test]
+    fn test_skill_level() {
+        assert_eq!(Some(1), skill_depth(0));
+        assert_eq!(Some(10), skill_depth(19));
+        assert_eq!(None, skill_depth(MAX_SKILL_LEVEL));
+        assert_eq!(300, skill_margin(0));
+        assert_eq!(15, skill_margin(19));
+        assert_eq!(0, skill_margin(MAX_SKILL_LEVEL));
+
+        // lower skill levels never search deeper or pick worse moves less often
+        for skill_level in 1..=MAX_SKILL_LEVEL {
+            assert!(skill_depth(skill_level - 1) <= skill_depth(skill_level) || skill_depth(skill_level).is_none());
+            assert!(skill_margin(skill_level - 1) > skill_margin(skill_level));
+        }
+    }
+}
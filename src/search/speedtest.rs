@@ -0,0 +1,163 @@
+use std::hint::black_box;
+use std::time::Duration;
+use arrayvec::ArrayVec;
+use crate::board::Board;
+use crate::board::position::Position;
+use crate::evaluation;
+use crate::move_gen;
+use crate::move_gen::ply::Ply;
+use crate::search::bench::BENCH_POSITIONS;
+use crate::search::clock::Instant;
+use crate::move_gen::perft::perft_driver;
+use crate::search::Search;
+
+/// The depth of the perfts and searches of the speedtest, if no depth is specified.
+pub const SPEEDTEST_DEPTH: u64 = 4;
+
+/// The maximum number of plies from the bench positions within which the positions of the phase timings are collected.
+const MAX_PHASE_POSITIONS_DEPTH: u64 = 2;
+
+/// The number of times each phase timing goes through all of its positions, so that the timings are long enough to be measured.
+const PHASE_ROUNDS: u32 = 5;
+
+impl Search {
+    /// Measures the speed of Ladybug on the bench positions, and reports the nodes, the time and the nodes per second
+    /// of each phase, so that performance-oriented changes can be compared on the same machine.
+    ///
+    /// The move generation, making moves and the evaluation are timed separately on the positions within a few plies of the
+    /// bench positions, but at most within the given depth.
+    /// The positions are collected beforehand, so that each timer only measures its own phase.
+    /// Ladybug makes moves by copying the position, so there is no unmake phase.
+    /// Afterwards, the bench positions are perfted and searched to the given depth, which measures the phases working together.
+    pub fn speedtest(&mut self, depth: u64) {
+        // collect the positions and their legal moves
+        let mut positions = Vec::new();
+        for fen in BENCH_POSITIONS {
+            let board = Board::from_fen(fen).expect("The bench positions are valid");
+            collect_positions(board.position, depth.min(MAX_PHASE_POSITIONS_DEPTH), &mut positions);
+        }
+        let moves: Vec<Vec<Ply>> = positions.iter()
+            .map(|position| {
+                let move_list = move_gen::generate_moves(*position);
+                (0..move_list.len()).map(|i| move_list.get(i)).collect()
+            })
+            .collect();
+
+        let (nodes, time) = self.time_phase(|| {
+            for position in positions.iter() {
+                black_box(move_gen::generate_moves(*position));
+            }
+            positions.len() as u128
+        });
+        self.report_phase("movegen", nodes, time);
+
+        let (nodes, time) = self.time_phase(|| {
+            for (position, plies) in positions.iter().zip(moves.iter()) {
+                for ply in plies {
+                    black_box(position.make_move(*ply));
+                }
+            }
+            moves.iter().map(|plies| plies.len() as u128).sum()
+        });
+        self.report_phase("make", nodes, time);
+
+        let (nodes, time) = self.time_phase(|| {
+            for position in positions.iter() {
+                black_box(evaluation::evaluate(*position));
+            }
+            positions.len() as u128
+        });
+        self.report_phase("eval", nodes, time);
+
+        // perft the bench positions
+        let start_time = Instant::now();
+        let mut nodes: u128 = 0;
+        for fen in BENCH_POSITIONS {
+            let board = Board::from_fen(fen).expect("The bench positions are valid");
+            nodes += perft_driver(board.position, depth) as u128;
+        }
+        self.report_phase("perft", nodes, start_time.elapsed());
+
+        // search the bench positions, just like the bench
+        let start_time = Instant::now();
+        let mut nodes: u128 = 0;
+        for fen in BENCH_POSITIONS {
+            let board = Board::from_fen(fen).expect("The bench positions are valid");
+            self.new_game();
+            nodes += self.iterative_search(board, depth, None, ArrayVec::new()).1;
+        }
+        self.report_phase("search", nodes, start_time.elapsed());
+    }
+
+    /// Runs the given phase for the number of phase rounds, and returns the total number of nodes and the elapsed time.
+    /// The phase returns the number of nodes of a single round.
+    fn time_phase(&self, phase: impl Fn() -> u128) -> (u128, Duration) {
+        let start_time = Instant::now();
+        let mut nodes: u128 = 0;
+        for _ in 0..PHASE_ROUNDS {
+            nodes += phase();
+        }
+        (nodes, start_time.elapsed())
+    }
+
+    /// Reports the nodes, the time and the nodes per second of the given phase.
+    fn report_phase(&self, phase: &str, nodes: u128, time: Duration) {
+        let time_elapsed = time.as_millis();
+        let nps = (nodes * 1000).checked_div(time_elapsed).unwrap_or(nodes);
+        self.send_output(format!("{phase}: {nodes} nodes {time_elapsed} ms {nps} nps"));
+    }
+}
+
+/// Adds the given position and all positions within the given number of plies from it to the given list.
+fn collect_positions(position: Position, depth: u64, positions: &mut Vec<Position>) {
+    positions.push(position);
+    if depth == 0 {
+        return;
+    }
+    let move_list = move_gen::generate_moves(position);
+    for i in 0..move_list.len() {
+        collect_positions(position.make_move(move_list.get(i)), depth - 1, positions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use crate::board::Board;
+    use crate::board::position::Position;
+    use crate::ladybug::Message;
+    use crate::search::{Search, SearchCommand};
+    use crate::search::speedtest::collect_positions;
+
+    #[test]
+    fn test_collect_positions() {
+        let mut positions: Vec<Position> = Vec::new();
+        collect_positions(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position, 2, &mut positions);
+        // the start position, the 20 positions after the first move and the 400 positions after the second move
+        assert_eq!(421, positions.len());
+    }
+
+    #[test]
+    fn test_speedtest() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+
+        search.speedtest(1);
+
+        let phases: Vec<String> = message_receiver.try_iter()
+            .filter_map(|message| match message {
+                Message::SearchMessage(output) if !output.starts_with("info") => Some(output),
+                _other => None,
+            })
+            .collect();
+        assert_eq!(5, phases.len());
+        for (output, phase) in phases.iter().zip(["movegen", "make", "eval", "perft", "search"]) {
+            assert!(output.starts_with(format!("{phase}: ").as_str()));
+            assert!(output.ends_with(" nps"));
+        }
+        // a perft to depth 1 counts the legal moves of the bench positions
+        assert!(phases[3].starts_with("perft: 442 nodes "), "{}", phases[3]);
+    }
+}
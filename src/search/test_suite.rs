@@ -0,0 +1,77 @@
+use std::time::Duration;
+use crate::search::clock::Instant;
+use arrayvec::ArrayVec;
+use crate::epd::EpdEntry;
+use crate::search::{MAX_PLY, Search};
+
+impl Search {
+    /// Runs an EPD test suite, searching each position for the given amount of milliseconds.
+    /// For each position, the search result is reported, followed by the number of solved positions and the total node count.
+    /// Returns the number of solved positions.
+    pub fn run_test_suite(&mut self, entries: Vec<EpdEntry>, time: u64) -> usize {
+        // used to measure the elapsed time
+        let start_time = Instant::now();
+
+        let mut solved = 0;
+        let mut total_node_count: u128 = 0;
+
+        for (index, entry) in entries.iter().enumerate() {
+            if self.is_aborted() {
+                break;
+            }
+            // the positions are unrelated, so the results of the previous positions are of no use
+            self.new_game();
+            let (best_move, node_count) = self.iterative_search(entry.board, MAX_PLY as u64, Some(Duration::from_millis(time)), ArrayVec::new());
+            total_node_count += node_count;
+
+            let result = match entry.is_solved_by(best_move) {
+                true => {
+                    solved += 1;
+                    "solved"
+                }
+                false => "not solved",
+            };
+
+            // positions without an id are numbered
+            let id = entry.id.clone().unwrap_or(format!("{}", index + 1));
+            self.send_output(format!("{id}: {} {result}", best_move.to_san(entry.board.position)));
+        }
+
+        let time_elapsed = start_time.elapsed().as_millis();
+        let nps = (total_node_count * 1000).checked_div(time_elapsed).unwrap_or(total_node_count);
+        self.send_output(format!("Solved {solved} of {} positions, searched {total_node_count} nodes in {time_elapsed} ms ({nps} nps)", entries.len()));
+
+        solved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use crate::epd::EpdEntry;
+    use crate::ladybug::Message;
+    use crate::search::{Search, SearchCommand};
+
+    #[test]
+    fn test_run_test_suite() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+
+        // a mate in one, and a position where the mate in one has to be avoided
+        let entries = vec![
+            EpdEntry::parse("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7#; id \"mate\";").unwrap(),
+            EpdEntry::parse("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - am Qxf7#;").unwrap(),
+        ];
+        assert_eq!(1, search.run_test_suite(entries, 200));
+
+        let output: Vec<String> = message_receiver.try_iter().filter_map(|message| match message {
+            Message::SearchMessage(output) if !output.starts_with("info") => Some(output),
+            _other => None,
+        }).collect();
+        assert_eq!("mate: Qxf7# solved", output[0]);
+        assert_eq!("2: Qxf7# not solved", output[1]);
+        assert!(output[2].starts_with("Solved 1 of 2 positions"));
+    }
+}
@@ -0,0 +1,147 @@
+//! The time manager decides when a search with a clock stops starting new iterations.
+//!
+//! Ladybug allocates an optimum time and a maximum time for each move. The search never exceeds the maximum time,
+//! but between iterations, it compares the elapsed time with the optimum time, scaled by how stable the best move is:
+//! if the best move stayed the same for several iterations, another iteration will most likely not change it either,
+//! so the search stops early. If the best move keeps changing, the search is not sure about it yet, and continues longer.
+
+use crate::move_gen::ply::Ply;
+use crate::search::Search;
+
+/// The number of iterations with the same best move, after which the best move is considered stable.
+pub const STABLE_ITERATIONS: u32 = 4;
+/// The percentage of the optimum time that is used if the best move is stable.
+pub const STABLE_TIME_PERCENT: u32 = 50;
+/// The number of most recent iterations in which the changes of the best move are counted.
+pub const CHANGE_WINDOW: u32 = 4;
+/// The percentage of the optimum time that is added for each change of the best move in the recent iterations.
+pub const TIME_PERCENT_PER_CHANGE: u32 = 25;
+/// The factor by which the maximum time of a move may exceed its optimum time.
+pub const MAX_TIME_FACTOR: u64 = 2;
+
+/// Tracks how the best move of the search changed between the completed iterations.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct BestMoveStability {
+    /// The best move of the last completed iteration.
+    best_move: Option<Ply>,
+    /// The number of consecutive iterations that ended with the current best move, including the iteration that found it.
+    stable_iterations: u32,
+    /// One bit for each recent iteration, with the last iteration in the lowest bit, which is set if the best move changed in the iteration.
+    change_history: u32,
+}
+
+impl BestMoveStability {
+    /// Records the best move of a completed iteration.
+    pub fn update(&mut self, best_move: Ply) {
+        // the first iteration finds the first best move, which is no change
+        let changed = self.best_move.is_some_and(|previous| previous != best_move);
+        self.change_history = self.change_history << 1 | u32::from(changed);
+        self.stable_iterations = match self.best_move == Some(best_move) {
+            true => self.stable_iterations + 1,
+            false => 1,
+        };
+        self.best_move = Some(best_move);
+    }
+
+    /// Returns the percentage of the optimum time the search should use.
+    pub fn time_percent(&self) -> u32 {
+        if self.stable_iterations >= STABLE_ITERATIONS {
+            return STABLE_TIME_PERCENT;
+        }
+        let changes = (self.change_history & ((1 << CHANGE_WINDOW) - 1)).count_ones();
+        100 + changes * TIME_PERCENT_PER_CHANGE
+    }
+}
+
+impl Search {
+    /// Returns whether the search should not start another iteration, because it used the optimum time,
+    /// scaled by the stability of the best move. Searches without an optimum time always continue.
+    pub(super) fn is_optimum_time_reached(&self, stability: &BestMoveStability) -> bool {
+        match (self.optimum_time, self.total_time) {
+            (Some(optimum_time), Some(start_time)) if !self.pondering => {
+                start_time.elapsed() >= optimum_time * stability.time_percent() / 100
+            }
+            _other => false,
+        }
+    }
+}
+
+/// Returns the maximum time in milliseconds the search may use for a move with the given optimum time,
+/// which is at most the given fraction of the available time.
+pub fn max_time(optimum_time: u64, available_time: u64, max_time_fraction: u64) -> u64 {
+    (optimum_time * MAX_TIME_FACTOR).min(available_time / max_time_fraction).max(optimum_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::mpsc::{Receiver, Sender, SyncSender};
+    use std::time::Duration;
+    use crate::board::square;
+    use crate::board::piece::Piece;
+    use crate::ladybug::Message;
+    use crate::move_gen::ply::Ply;
+    use crate::search::{Search, SearchCommand};
+    use crate::search::clock::Instant;
+    use crate::search::time_manager::{max_time, BestMoveStability, STABLE_ITERATIONS, STABLE_TIME_PERCENT, TIME_PERCENT_PER_CHANGE};
+
+    #[test]
+    fn test_best_move_stability() {
+        let e4 = Ply::new(square::E2, square::E4, Piece::Pawn, None, false);
+        let d4 = Ply::new(square::D2, square::D4, Piece::Pawn, None, false);
+        let mut stability = BestMoveStability::default();
+
+        // the first best moves use the optimum time
+        stability.update(e4);
+        stability.update(e4);
+        assert_eq!(100, stability.time_percent());
+
+        // a best move that keeps changing extends the time
+        stability.update(d4);
+        assert_eq!(100 + TIME_PERCENT_PER_CHANGE, stability.time_percent());
+        stability.update(e4);
+        assert_eq!(100 + 2 * TIME_PERCENT_PER_CHANGE, stability.time_percent());
+
+        // a stable best move shortens it
+        stability.update(e4);
+        stability.update(e4);
+        assert_eq!(100 + 2 * TIME_PERCENT_PER_CHANGE, stability.time_percent());
+        stability.update(e4);
+        assert_eq!(STABLE_TIME_PERCENT, stability.time_percent());
+    }
+
+    #[test]
+    fn test_is_optimum_time_reached() {
+        let (_command_sender, command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
+        let (message_sender, _message_receiver): (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(1024);
+        let mut search = Search::new(command_receiver, message_sender);
+        let e4 = Ply::new(square::E2, square::E4, Piece::Pawn, None, false);
+        let mut stability = BestMoveStability::default();
+        stability.update(e4);
+
+        // without an optimum time, e.g. in searches to a fixed depth or with a fixed move time, the search always continues
+        search.total_time = Instant::now().checked_sub(Duration::from_secs(10));
+        assert!(!search.is_optimum_time_reached(&stability));
+
+        // after 60% of the optimum time, only a search with a stable best move stops
+        search.optimum_time = Some(Duration::from_secs(100));
+        search.total_time = Instant::now().checked_sub(Duration::from_secs(60));
+        assert!(!search.is_optimum_time_reached(&stability));
+        for _iteration in 1..STABLE_ITERATIONS {
+            stability.update(e4);
+        }
+        assert!(search.is_optimum_time_reached(&stability));
+
+        // the time spent pondering doesn't count
+        search.pondering = true;
+        assert!(!search.is_optimum_time_reached(&stability));
+    }
+
+    #[test]
+    fn test_max_time() {
+        assert_eq!(2000, max_time(1000, 40_000, 2));
+        // the maximum time never exceeds the fraction of the available time, but is at least the optimum time
+        assert_eq!(1500, max_time(1000, 3000, 2));
+        assert_eq!(450, max_time(450, 900, 2));
+    }
+}
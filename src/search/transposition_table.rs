@@ -0,0 +1,394 @@
+use std::mem::size_of;
+use crate::move_gen::ply::Ply;
+use crate::search::MAX_PLY;
+use crate::evaluation::POSITIVE_INFINITY;
+
+/// The default size of the transposition table in megabytes.
+pub const DEFAULT_HASH_SIZE: usize = 16;
+
+/// The minimum size of the transposition table in megabytes, as advertised by the "Hash" option.
+pub const MIN_HASH_SIZE: usize = 1;
+
+/// The maximum size of the transposition table in megabytes, as advertised by the "Hash" option.
+pub const MAX_HASH_SIZE: usize = 65536;
+
+/// Scores with an absolute value above this threshold are mate scores.
+/// Mate scores depend on the ply at which the mate was found, so they must be adjusted when stored and retrieved.
+pub const MATE_THRESHOLD: i32 = POSITIVE_INFINITY - (MAX_PLY as i32 * 5000);
+
+/// The number of entries that are sampled to calculate the `hashfull` value.
+const HASHFULL_SAMPLE_SIZE: usize = 1000;
+
+/// Encodes what kind of score is stored in a transposition table entry.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The score is a lower bound (the node failed high).
+    Lower,
+    /// The score is an upper bound (the node failed low).
+    Upper,
+}
+
+/// An entry in the transposition table.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TTEntry {
+    /// The zobrist hash of the position. A hash of 0 marks an empty entry.
+    pub hash: u64,
+    /// The depth the position was searched to.
+    pub depth: u8,
+    /// The score of the position.
+    pub score: i32,
+    /// What kind of score is stored.
+    pub bound: Bound,
+    /// The best move found in the position, or None if no move raised alpha.
+    pub best_move: Option<Ply>,
+    /// The age of the table when the entry was stored, which tells the entries of previous searches apart.
+    pub age: u8,
+}
+
+impl Default for TTEntry {
+    /// Returns an empty entry.
+    fn default() -> Self {
+        Self {
+            hash: 0,
+            depth: 0,
+            score: 0,
+            bound: Bound::Exact,
+            best_move: None,
+            age: 0,
+        }
+    }
+}
+
+/// Statistics about the use of the transposition table, which help to tune its replacement strategy.
+/// The counts of stored results are reset when the table is cleared.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct TTStats {
+    /// The number of entries the table can hold.
+    pub capacity: usize,
+    /// The number of used entries.
+    pub used: usize,
+    /// The number of used entries that were stored by the current search.
+    pub current: usize,
+    /// The average depth of the used entries.
+    pub average_depth: f64,
+    /// The number of results that were stored in an empty slot.
+    pub new: u64,
+    /// The number of results that replaced the entry of a different position.
+    pub replaced: u64,
+    /// The number of results that overwrote the entry of the same position.
+    pub overwritten: u64,
+    /// The number of results that were discarded, because the entry of the current search in their slot was searched deeper.
+    pub discarded: u64,
+}
+
+/// The [transposition table](https://www.chessprogramming.org/Transposition_Table) stores the results of previously searched positions,
+/// so that they don't have to be searched again when they are reached through a different move order.
+///
+/// The table is kept between the searches of a game, so that each search can use the results of the previous ones.
+/// Its age is incremented with each search, and entries of previous searches are replaced before those of the current one.
+pub struct TranspositionTable {
+    /// The entries of the table, indexed by the position hash.
+    entries: Vec<TTEntry>,
+    /// The age of the current search.
+    age: u8,
+    /// The counts of stored results since the table was cleared.
+    stats: TTStats,
+}
+
+impl Default for TranspositionTable {
+    /// Constructs a transposition table with the default size.
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_SIZE)
+    }
+}
+
+impl TranspositionTable {
+    /// Constructs a new transposition table with the given size in megabytes.
+    /// Panics if the memory can't be allocated, so it must only be used for small sizes (see `try_new`).
+    pub fn new(size_mb: usize) -> Self {
+        Self::try_new(size_mb).expect("The transposition table can be allocated")
+    }
+
+    /// Constructs a new transposition table with the given size in megabytes.
+    /// If the memory can't be allocated, an error is returned instead of aborting the process.
+    pub fn try_new(size_mb: usize) -> Result<Self, String> {
+        let error = || format!("could not allocate {size_mb} MB for the transposition table");
+
+        let size_bytes = size_mb.checked_mul(1024 * 1024).ok_or_else(error)?;
+        Self::try_with_len(size_bytes / size_of::<TTEntry>()).map_err(|_| error())
+    }
+
+    /// Constructs the given number of transposition tables, but at least one, which together have the given size in megabytes.
+    /// If the memory can't be allocated, an error is returned instead of aborting the process.
+    pub fn try_new_split(size_mb: usize, parts: usize) -> Result<Vec<Self>, String> {
+        let error = || format!("could not allocate {size_mb} MB for the transposition table");
+
+        let parts = parts.max(1);
+        let size_bytes = size_mb.checked_mul(1024 * 1024).ok_or_else(error)?;
+        (0..parts).map(|_| Self::try_with_len(size_bytes / size_of::<TTEntry>() / parts).map_err(|_| error())).collect()
+    }
+
+    /// Constructs a new transposition table that can hold the given number of entries, but at least one.
+    /// If the memory can't be allocated, an error is returned instead of aborting the process.
+    pub fn try_with_len(num_entries: usize) -> Result<Self, String> {
+        let num_entries = num_entries.max(1);
+
+        let mut entries = Vec::new();
+        entries.try_reserve_exact(num_entries).map_err(|_| format!("could not allocate {num_entries} entries for the transposition table"))?;
+        entries.resize(num_entries, TTEntry::default());
+        Ok(Self {
+            entries,
+            age: 0,
+            stats: TTStats::default(),
+        })
+    }
+
+    /// Returns the number of entries the table can hold.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the table can not hold any entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Moves the entries of this table into the returned table, which leaves this table empty until a table is assigned to it again.
+    pub(crate) fn take(&mut self) -> Self {
+        Self {
+            entries: std::mem::take(&mut self.entries),
+            age: self.age,
+            stats: self.stats,
+        }
+    }
+
+    /// Removes all entries from the table.
+    pub fn clear(&mut self) {
+        self.entries.fill(TTEntry::default());
+        self.age = 0;
+        self.stats = TTStats::default();
+    }
+
+    /// Ages the table at the start of a search, so that the entries of previous searches are replaced first.
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    /// Returns the entry for the given hash, if the table contains one.
+    /// The score of the returned entry is already adjusted to the given ply index.
+    pub fn probe(&self, hash: u64, ply_index: u64) -> Option<TTEntry> {
+        let entry = self.entries[self.index(hash)];
+        if entry.hash != hash || hash == 0 {
+            return None;
+        }
+        Some(TTEntry {
+            score: score_from_tt(entry.score, ply_index),
+            ..entry
+        })
+    }
+
+    /// Stores the search result for the given hash.
+    /// An entry of the current search for a different position is only replaced if it was not searched deeper.
+    pub fn store(&mut self, hash: u64, depth: u64, ply_index: u64, score: i32, bound: Bound, best_move: Option<Ply>) {
+        let index = self.index(hash);
+        let depth = depth.min(u8::MAX as u64) as u8;
+        let entry = self.entries[index];
+        if entry.hash == 0 {
+            self.stats.new += 1;
+        } else if entry.hash == hash {
+            self.stats.overwritten += 1;
+        } else if entry.age == self.age && entry.depth > depth {
+            self.stats.discarded += 1;
+            return;
+        } else {
+            self.stats.replaced += 1;
+        }
+        self.entries[index] = TTEntry {
+            hash,
+            depth,
+            score: score_to_tt(score, ply_index),
+            bound,
+            best_move,
+            age: self.age,
+        };
+    }
+
+    /// Returns how full the table is in permill with entries of the current search, estimated from the first entries of the table.
+    pub fn hashfull(&self) -> u64 {
+        let sample_size = HASHFULL_SAMPLE_SIZE.min(self.entries.len());
+        let used = self.entries[..sample_size].iter().filter(|entry| entry.hash != 0 && entry.age == self.age).count();
+        (used * 1000 / sample_size) as u64
+    }
+
+    /// Returns the statistics of the table, which requires scanning all entries.
+    pub fn stats(&self) -> TTStats {
+        let used_entries = || self.entries.iter().filter(|entry| entry.hash != 0);
+        let used = used_entries().count();
+        let depth_sum: u64 = used_entries().map(|entry| entry.depth as u64).sum();
+        TTStats {
+            capacity: self.entries.len(),
+            used,
+            current: used_entries().filter(|entry| entry.age == self.age).count(),
+            average_depth: match used {
+                0 => 0.0,
+                used => depth_sum as f64 / used as f64,
+            },
+            ..self.stats
+        }
+    }
+
+    /// Returns the index of the slot for the given hash.
+    fn index(&self, hash: u64) -> usize {
+        (hash % self.entries.len() as u64) as usize
+    }
+}
+
+/// Converts a mate score relative to the root into one relative to the current node.
+fn score_to_tt(score: i32, ply_index: u64) -> i32 {
+    let adjustment = ply_index as i32 * 5000;
+    if score > MATE_THRESHOLD {
+        score.saturating_add(adjustment)
+    } else if score < -MATE_THRESHOLD {
+        score.saturating_sub(adjustment)
+    } else {
+        score
+    }
+}
+
+/// Converts a mate score relative to the stored node into one relative to the root.
+fn score_from_tt(score: i32, ply_index: u64) -> i32 {
+    let adjustment = ply_index as i32 * 5000;
+    if score > MATE_THRESHOLD {
+        score.saturating_sub(adjustment)
+    } else if score < -MATE_THRESHOLD {
+        score.saturating_add(adjustment)
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+    use crate::board::piece::Piece;
+    use crate::board::square;
+    use crate::evaluation::NEGATIVE_INFINITY;
+    use crate::move_gen::ply::Ply;
+    use crate::search::transposition_table::{Bound, TranspositionTable, TTEntry, TTStats};
+
+    #[test]
+    fn test_store_and_probe() {
+        let mut tt = TranspositionTable::new(1);
+        assert_eq!(None, tt.probe(12345, 0));
+
+        let best_move = Some(Ply::new(square::E2, square::E4, Piece::Pawn, None, false));
+        tt.store(12345, 4, 0, 120, Bound::Lower, best_move);
+        assert_eq!(Some(TTEntry { hash: 12345, depth: 4, score: 120, bound: Bound::Lower, best_move, age: 0 }), tt.probe(12345, 0));
+
+        // a different hash mapping to the same slot must not be returned
+        assert_eq!(None, tt.probe(12345 + tt.len() as u64, 0));
+
+        tt.clear();
+        assert_eq!(None, tt.probe(12345, 0));
+    }
+
+    #[test]
+    fn test_try_new() {
+        let tt = TranspositionTable::try_new(2).unwrap();
+        assert_eq!(2 * 1024 * 1024 / size_of::<TTEntry>(), tt.len());
+
+        // sizes that can't be allocated result in an error instead of an abort
+        assert!(TranspositionTable::try_new(usize::MAX).is_err());
+        assert!(TranspositionTable::try_new(usize::MAX / (1024 * 1024)).is_err());
+    }
+
+    #[test]
+    fn test_try_new_split() {
+        let tables = TranspositionTable::try_new_split(2, 3).unwrap();
+        assert_eq!(3, tables.len());
+        assert!(tables.iter().all(|tt| tt.len() == 2 * 1024 * 1024 / size_of::<TTEntry>() / 3));
+
+        assert_eq!(1, TranspositionTable::try_new_split(2, 0).unwrap().len());
+        assert!(TranspositionTable::try_new_split(usize::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_try_with_len() {
+        assert_eq!(1000, TranspositionTable::try_with_len(1000).unwrap().len());
+        assert_eq!(1, TranspositionTable::try_with_len(0).unwrap().len());
+        assert!(TranspositionTable::try_with_len(usize::MAX).is_err());
+    }
+
+    #[test]
+    fn test_mate_scores_are_adjusted() {
+        let mut tt = TranspositionTable::new(1);
+
+        // getting mated at ply 6, stored at ply 2
+        let score = NEGATIVE_INFINITY + 6 * 5000;
+        tt.store(999, 3, 2, score, Bound::Exact, None);
+
+        // retrieved at ply 4, the mate is two plies further away from the root
+        assert_eq!(NEGATIVE_INFINITY + 8 * 5000, tt.probe(999, 4).unwrap().score);
+        assert_eq!(score, tt.probe(999, 2).unwrap().score);
+    }
+
+    #[test]
+    fn test_hashfull() {
+        let mut tt = TranspositionTable::new(1);
+        assert_eq!(0, tt.hashfull());
+
+        for hash in 1..=500 {
+            tt.store(hash, 1, 0, 0, Bound::Exact, None);
+        }
+        assert_eq!(500, tt.hashfull());
+
+        // the entries of previous searches don't count
+        tt.new_search();
+        assert_eq!(0, tt.hashfull());
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut tt = TranspositionTable::new(1);
+        let other_hash = 12345 + tt.len() as u64;
+
+        // an entry of the current search is kept unless the new result was searched at least as deep
+        tt.store(12345, 6, 0, 10, Bound::Exact, None);
+        tt.store(other_hash, 5, 0, 20, Bound::Exact, None);
+        assert_eq!(6, tt.probe(12345, 0).unwrap().depth);
+        tt.store(other_hash, 6, 0, 20, Bound::Exact, None);
+        assert_eq!(None, tt.probe(12345, 0));
+
+        // the same position is always replaced
+        tt.store(other_hash, 2, 0, 30, Bound::Upper, None);
+        assert_eq!(30, tt.probe(other_hash, 0).unwrap().score);
+
+        // entries of previous searches are kept for probing, but replaced by any new result
+        tt.store(12345, 8, 0, 10, Bound::Exact, None);
+        tt.new_search();
+        assert_eq!(Some(0), tt.probe(12345, 0).map(|entry| entry.age));
+        tt.store(other_hash, 1, 0, 40, Bound::Exact, None);
+        assert_eq!(None, tt.probe(12345, 0));
+        assert_eq!(Some(1), tt.probe(other_hash, 0).map(|entry| entry.age));
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut tt = TranspositionTable::try_with_len(100).unwrap();
+        tt.store(1, 4, 0, 0, Bound::Exact, None);
+        tt.store(2, 2, 0, 0, Bound::Exact, None);
+        tt.store(2, 3, 0, 0, Bound::Lower, None);
+        tt.store(101, 5, 0, 0, Bound::Exact, None);
+        tt.store(102, 1, 0, 0, Bound::Exact, None);
+        tt.new_search();
+        tt.store(3, 3, 0, 0, Bound::Exact, None);
+
+        let expected = TTStats { capacity: 100, used: 3, current: 1, average_depth: 11.0 / 3.0, new: 3, replaced: 1, overwritten: 1, discarded: 1 };
+        assert_eq!(expected, tt.stats());
+
+        tt.clear();
+        assert_eq!(TTStats { capacity: 100, ..TTStats::default() }, tt.stats());
+    }
+}
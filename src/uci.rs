@@ -1,3 +1,6 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
 /// Represents a UCI command.
 #[derive(PartialEq, Debug)]
 pub enum UciCommand {
@@ -9,19 +12,65 @@ pub enum UciCommand {
     GoMoveTime(String),
     GoDepth(String),
     GoPerft(String),
+    GoMate(String),
+    GoNodes(String),
+    GoSearchMoves(Vec<String>, Box<UciCommand>),
+    GoPonder(Box<UciCommand>),
+    PonderHit,
+    Stop,
+    SetOption(String, String),
+    Test(String, String),
+    PerftSuite(String),
+    PerftDiff(String, String),
+    SelfPlay(Vec<String>),
+    Match(Vec<String>),
+    Bench(Option<String>),
+    BenchCompare(String),
+    Speedtest(Option<String>),
+    Debug(bool),
+    Exclude(Option<String>),
+    ResetOptions,
+    SaveConfig(Option<String>),
+    Analyze,
     Quit,
     Help,
-    Display,
+    Display(bool),
+    Board(bool, bool),
+    HashStats,
+}
+
+/// The reasons why a string can't be parsed as a UCI command.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum UciParseError {
+    /// The input contains no command.
+    Empty,
+    /// The given command is unknown.
+    UnknownCommand(String),
+    /// The arguments of the given command are missing, superfluous or invalid.
+    InvalidArguments(String),
 }
 
+/// Prints the error as text.
+impl Display for UciParseError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            UciParseError::Empty => write!(f, "Empty command"),
+            UciParseError::UnknownCommand(command) => write!(f, "Unknown command '{command}'"),
+            UciParseError::InvalidArguments(command) => write!(f, "Invalid arguments for '{command}'"),
+        }
+    }
+}
+
+impl Error for UciParseError {}
+
 /// Tries to parse a string as a UCI command.
-pub fn parse_uci(input: String) -> Result<UciCommand, String> {
+pub fn parse_uci(input: String) -> Result<UciCommand, UciParseError> {
     // split input string into parts
     let mut uci_parts: Vec<String> = input.split_whitespace().map(|s| s.to_string()).collect();
     
     // if uci_parts is empty, return error
     if uci_parts.is_empty() {
-        return Err(String::from("info string unknown command"))
+        return Err(UciParseError::Empty)
     }
     
     // return matching uci command, otherwise error
@@ -31,61 +80,240 @@ pub fn parse_uci(input: String) -> Result<UciCommand, String> {
         "ucinewgame" => Ok(UciCommand::UciNewGame),
         "position" => {
             match uci_parts.len() > 1 {
-                false => Err(String::from("info string unknown command")),
+                false => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
                 true => Ok(UciCommand::Position(uci_parts.split_off(1)))
             }
         }
-        "go" => {
-            if uci_parts.len() < 2 {
-                Err(String::from("info string unknown command"))
-            } else {
-                match uci_parts[1].as_str() {
-                    "depth" => {
-                        if uci_parts.len() != 3 {
-                            Err(String::from("info string unknown command"))
-                        }
-                        else {
-                            Ok(UciCommand::GoDepth(uci_parts[2].clone()))
-                        }
-                    }
-                    "perft" => {
-                        if uci_parts.len() != 3 {
-                            Err(String::from("info string unknown command"))
-                        }
-                        else {
-                            Ok(UciCommand::GoPerft(uci_parts[2].clone()))
-                        }
-                    }
-                    "wtime" => Ok(UciCommand::GoClockTime(uci_parts.split_off(1))),
-                    "movetime" => {
-                        if uci_parts.len() != 3 {
-                            Err(String::from("info string unknown command"))
-                        }
-                        else {
-                            Ok(UciCommand::GoMoveTime(uci_parts[2].clone()))
-                        }
-                    }
-                    _other => Err(String::from("info string unknown command"))
-                }
+        "go" => parse_go(uci_parts),
+        "stop" => Ok(UciCommand::Stop),
+        "ponderhit" => Ok(UciCommand::PonderHit),
+        "setoption" => parse_set_option(&uci_parts),
+        "test" => {
+            if uci_parts.len() != 3 {
+                Err(UciParseError::InvalidArguments(uci_parts[0].clone()))
+            }
+            else {
+                Ok(UciCommand::Test(uci_parts[1].clone(), uci_parts[2].clone()))
+            }
+        }
+        "perftsuite" => {
+            match uci_parts.len() {
+                2 => Ok(UciCommand::PerftSuite(uci_parts[1].clone())),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+            }
+        }
+        "perftdiff" => {
+            match uci_parts.len() {
+                3 => Ok(UciCommand::PerftDiff(uci_parts[1].clone(), uci_parts[2].clone())),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+            }
+        }
+        "selfplay" => {
+            match uci_parts.len() {
+                3.. => Ok(UciCommand::SelfPlay(uci_parts.split_off(1))),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+            }
+        }
+        "match" => {
+            match uci_parts.len() {
+                5.. => Ok(UciCommand::Match(uci_parts.split_off(1))),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
             }
         }
+        "bench" => {
+            match uci_parts.len() {
+                1 => Ok(UciCommand::Bench(None)),
+                2 => Ok(UciCommand::Bench(Some(uci_parts[1].clone()))),
+                3 if uci_parts[1] == "--compare" => Ok(UciCommand::BenchCompare(uci_parts[2].clone())),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+            }
+        }
+        "speedtest" => {
+            match uci_parts.len() {
+                1 => Ok(UciCommand::Speedtest(None)),
+                2 => Ok(UciCommand::Speedtest(Some(uci_parts[1].clone()))),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+            }
+        }
+        "debug" => {
+            match uci_parts.get(1).map(|part| part.as_str()) {
+                Some("on") if uci_parts.len() == 2 => Ok(UciCommand::Debug(true)),
+                Some("off") if uci_parts.len() == 2 => Ok(UciCommand::Debug(false)),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+            }
+        }
+        "exclude" => {
+            match uci_parts.len() {
+                1 => Ok(UciCommand::Exclude(None)),
+                2 => Ok(UciCommand::Exclude(Some(uci_parts[1].clone()))),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+            }
+        }
+        "resetoptions" => Ok(UciCommand::ResetOptions),
+        "saveconfig" => {
+            match uci_parts.len() {
+                1 => Ok(UciCommand::SaveConfig(None)),
+                2 => Ok(UciCommand::SaveConfig(Some(uci_parts[1].clone()))),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+            }
+        }
+        "analyze" => Ok(UciCommand::Analyze),
         "quit" => Ok(UciCommand::Quit),
         "help" => Ok(UciCommand::Help),
-        "display" => Ok(UciCommand::Display),
-        _other => Err(String::from("info string unknown command")),
+        "display" => {
+            match uci_parts.len() {
+                1 => Ok(UciCommand::Display(true)),
+                2 if uci_parts[1] == "noclock" => Ok(UciCommand::Display(false)),
+                _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+            }
+        }
+        "hashstats" => Ok(UciCommand::HashStats),
+        "board" => parse_board(&uci_parts),
+        other => Err(UciParseError::UnknownCommand(other.to_string())),
+    }
+}
+
+/// The keywords of the "go" command, which end the move list of "searchmoves".
+const GO_KEYWORDS: [&str; 13] = ["searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite", "perft"];
+
+/// Parses the "go" command.
+/// The search limits can be combined with "searchmoves <move> ...", which restricts the search to the given root moves.
+/// Searches with a time limit can be started with "ponder", which ponders until the ponderhit.
+fn parse_go(mut uci_parts: Vec<String>) -> Result<UciCommand, UciParseError> {
+    if let Some(index) = uci_parts.iter().position(|part| part == "ponder") {
+        uci_parts.remove(index);
+        return match parse_go(uci_parts)? {
+            command @ (UciCommand::GoClockTime(_) | UciCommand::GoMoveTime(_)) => Ok(UciCommand::GoPonder(Box::new(command))),
+            UciCommand::GoSearchMoves(moves, command) if matches!(*command, UciCommand::GoClockTime(_) | UciCommand::GoMoveTime(_)) => {
+                Ok(UciCommand::GoPonder(Box::new(UciCommand::GoSearchMoves(moves, command))))
+            }
+            _other => Err(UciParseError::InvalidArguments(String::from("go"))),
+        };
+    }
+
+    let Some(index) = uci_parts.iter().position(|part| part == "searchmoves") else {
+        return parse_go_limit(uci_parts);
+    };
+
+    let end = uci_parts[index + 1..].iter().position(|part| GO_KEYWORDS.contains(&part.as_str())).map_or(uci_parts.len(), |offset| index + 1 + offset);
+    let moves: Vec<String> = uci_parts.drain(index..end).skip(1).collect();
+    if moves.is_empty() {
+        return Err(UciParseError::InvalidArguments(uci_parts[0].clone()));
+    }
+
+    // only searches can be restricted, but not perft or the mate search
+    match parse_go_limit(uci_parts)? {
+        command @ (UciCommand::GoClockTime(_) | UciCommand::GoMoveTime(_) | UciCommand::GoDepth(_) | UciCommand::GoNodes(_)) => {
+            Ok(UciCommand::GoSearchMoves(moves, Box::new(command)))
+        }
+        _other => Err(UciParseError::InvalidArguments(String::from("go"))),
     }
 }
 
+/// Parses the "go" command with a single search limit, e.g. "go depth <depth>".
+fn parse_go_limit(mut uci_parts: Vec<String>) -> Result<UciCommand, UciParseError> {
+    if uci_parts.len() < 2 {
+        Err(UciParseError::InvalidArguments(uci_parts[0].clone()))
+    } else {
+        match uci_parts[1].as_str() {
+            "depth" => {
+                if uci_parts.len() != 3 {
+                    Err(UciParseError::InvalidArguments(uci_parts[0].clone()))
+                }
+                else {
+                    Ok(UciCommand::GoDepth(uci_parts[2].clone()))
+                }
+            }
+            "perft" => {
+                if uci_parts.len() != 3 {
+                    Err(UciParseError::InvalidArguments(uci_parts[0].clone()))
+                }
+                else {
+                    Ok(UciCommand::GoPerft(uci_parts[2].clone()))
+                }
+            }
+            "mate" => {
+                if uci_parts.len() != 3 {
+                    Err(UciParseError::InvalidArguments(uci_parts[0].clone()))
+                }
+                else {
+                    Ok(UciCommand::GoMate(uci_parts[2].clone()))
+                }
+            }
+            "nodes" => {
+                if uci_parts.len() != 3 {
+                    Err(UciParseError::InvalidArguments(uci_parts[0].clone()))
+                }
+                else {
+                    Ok(UciCommand::GoNodes(uci_parts[2].clone()))
+                }
+            }
+            "wtime" => Ok(UciCommand::GoClockTime(uci_parts.split_off(1))),
+            "movetime" => {
+                if uci_parts.len() != 3 {
+                    Err(UciParseError::InvalidArguments(uci_parts[0].clone()))
+                }
+                else {
+                    Ok(UciCommand::GoMoveTime(uci_parts[2].clone()))
+                }
+            }
+            _other => Err(UciParseError::InvalidArguments(uci_parts[0].clone()))
+        }
+    }
+}
+
+/// Parses the "board [unicode] [flip]" command.
+/// The options can be given in any order.
+fn parse_board(uci_parts: &[String]) -> Result<UciCommand, UciParseError> {
+    let mut unicode = false;
+    let mut flip = false;
+    for part in uci_parts.iter().skip(1) {
+        match part.as_str() {
+            "unicode" if !unicode => unicode = true,
+            "flip" if !flip => flip = true,
+            _other => return Err(UciParseError::InvalidArguments(uci_parts[0].clone())),
+        }
+    }
+    Ok(UciCommand::Board(unicode, flip))
+}
+
+/// Parses the "setoption name <name> value <value>" command.
+/// Both the name and the value may contain spaces. For button options, the value is empty.
+fn parse_set_option(uci_parts: &[String]) -> Result<UciCommand, UciParseError> {
+    if uci_parts.len() < 3 || uci_parts[1] != "name" {
+        return Err(UciParseError::InvalidArguments(uci_parts[0].clone()));
+    }
+
+    let value_index = uci_parts.iter().position(|part| part == "value").unwrap_or(uci_parts.len());
+    if value_index <= 2 {
+        return Err(UciParseError::InvalidArguments(uci_parts[0].clone()));
+    }
+
+    let name = uci_parts[2..value_index].join(" ");
+    let value = match value_index < uci_parts.len() {
+        true => uci_parts[value_index + 1..].join(" "),
+        false => String::new(),
+    };
+
+    Ok(UciCommand::SetOption(name, value))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::uci;
-    use crate::uci::UciCommand;
+    use crate::uci::{UciCommand, UciParseError};
 
     #[test]
     fn parse_uci_with_invalid_input_returns_error() {
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("Not Uci")));
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("       ")));
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("123456789")));
+        assert_eq!(Err(UciParseError::UnknownCommand(String::from("Not"))), uci::parse_uci(String::from("Not Uci")));
+        assert_eq!(Err(UciParseError::Empty), uci::parse_uci(String::from("       ")));
+        assert_eq!(Err(UciParseError::UnknownCommand(String::from("123456789"))), uci::parse_uci(String::from("123456789")));
+    }
+
+    #[test]
+    fn uci_parse_error_is_printed_as_text() {
+        assert_eq!("Unknown command 'Not'", uci::parse_uci(String::from("Not Uci")).unwrap_err().to_string());
+        assert_eq!("Invalid arguments for 'go'", uci::parse_uci(String::from("go fast")).unwrap_err().to_string());
     }
 
     #[test]
@@ -105,7 +333,7 @@ mod tests {
 
     #[test]
     fn test_parse_uci_for_position() {
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("position")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("position"))), uci::parse_uci(String::from("position")));
         
         assert_eq!(Ok(UciCommand::Position(vec!(String::from("startpos"), String::from("moves"), String::from("h3h4"), String::from("c6g2")))),
                    uci::parse_uci(String::from("position startpos moves h3h4 c6g2")));
@@ -129,21 +357,173 @@ mod tests {
 
     #[test]
     fn test_parse_uci_for_go_depth() {
-        assert_eq!(Err("info string unknown command".to_string()), uci::parse_uci(String::from("go depth")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go depth")));
         assert_eq!(UciCommand::GoDepth("5".to_string()), uci::parse_uci(String::from("go depth 5")).unwrap());
         assert_eq!(UciCommand::GoDepth("10".to_string()), uci::parse_uci(String::from("go depth 10")).unwrap());
     }
     
     #[test]
     fn test_parse_uci_for_go_perft() {
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("go perft")));
-        assert_eq!(Err(String::from("info string unknown command")), uci::parse_uci(String::from("go perft one two")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go perft")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go perft one two")));
         
         assert_eq!(UciCommand::GoPerft(String::from("5")), uci::parse_uci(String::from("go perft 5")).unwrap());
         assert_eq!(UciCommand::GoPerft(String::from("0")), uci::parse_uci(String::from("go perft 0")).unwrap());
         assert_eq!(UciCommand::GoPerft(String::from("100")), uci::parse_uci(String::from("go perft 100")).unwrap());
     }
 
+    #[test]
+    fn test_parse_uci_for_go_mate() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go mate")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go mate 3 4")));
+
+        assert_eq!(UciCommand::GoMate(String::from("3")), uci::parse_uci(String::from("go mate 3")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_set_option() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("setoption"))), uci::parse_uci(String::from("setoption")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("setoption"))), uci::parse_uci(String::from("setoption name")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("setoption"))), uci::parse_uci(String::from("setoption name value true")));
+
+        assert_eq!(UciCommand::SetOption(String::from("OwnBook"), String::from("true")), uci::parse_uci(String::from("setoption name OwnBook value true")).unwrap());
+        assert_eq!(UciCommand::SetOption(String::from("BookFile"), String::from("my books/book.bin")), uci::parse_uci(String::from("setoption name BookFile value my books/book.bin")).unwrap());
+        assert_eq!(UciCommand::SetOption(String::from("Clear Hash"), String::new()), uci::parse_uci(String::from("setoption name Clear Hash")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_test() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("test"))), uci::parse_uci(String::from("test")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("test"))), uci::parse_uci(String::from("test wac.epd")));
+
+        assert_eq!(UciCommand::Test(String::from("wac.epd"), String::from("1000")), uci::parse_uci(String::from("test wac.epd 1000")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_perft_suite() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("perftsuite"))), uci::parse_uci(String::from("perftsuite")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("perftsuite"))), uci::parse_uci(String::from("perftsuite perft.epd 5")));
+
+        assert_eq!(UciCommand::PerftSuite(String::from("perft.epd")), uci::parse_uci(String::from("perftsuite perft.epd")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_selfplay() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("selfplay"))), uci::parse_uci(String::from("selfplay")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("selfplay"))), uci::parse_uci(String::from("selfplay 10")));
+
+        assert_eq!(UciCommand::SelfPlay(vec![String::from("10"), String::from("100")]), uci::parse_uci(String::from("selfplay 10 100")).unwrap());
+        assert_eq!(UciCommand::SelfPlay(vec![String::from("10"), String::from("100"), String::from("pgn"), String::from("games.pgn")]), uci::parse_uci(String::from("selfplay 10 100 pgn games.pgn")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_perftdiff() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("perftdiff"))), uci::parse_uci(String::from("perftdiff stockfish")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("perftdiff"))), uci::parse_uci(String::from("perftdiff stockfish 5 6")));
+
+        assert_eq!(UciCommand::PerftDiff(String::from("stockfish"), String::from("5")), uci::parse_uci(String::from("perftdiff stockfish 5")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_match() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("match"))), uci::parse_uci(String::from("match")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("match"))), uci::parse_uci(String::from("match stockfish 10 1000")));
+
+        assert_eq!(UciCommand::Match(vec![String::from("stockfish"), String::from("10"), String::from("1000"), String::from("10")]), uci::parse_uci(String::from("match stockfish 10 1000 10")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_bench() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("bench"))), uci::parse_uci(String::from("bench 5 6")));
+
+        assert_eq!(UciCommand::Bench(None), uci::parse_uci(String::from("bench")).unwrap());
+        assert_eq!(UciCommand::Bench(Some(String::from("3"))), uci::parse_uci(String::from("bench 3")).unwrap());
+        assert_eq!(UciCommand::BenchCompare(String::from("baselines.txt")), uci::parse_uci(String::from("bench --compare baselines.txt")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_speedtest() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("speedtest"))), uci::parse_uci(String::from("speedtest 4 5")));
+        assert_eq!(UciCommand::Speedtest(None), uci::parse_uci(String::from("speedtest")).unwrap());
+        assert_eq!(UciCommand::Speedtest(Some(String::from("3"))), uci::parse_uci(String::from("speedtest 3")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_debug() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("debug"))), uci::parse_uci(String::from("debug")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("debug"))), uci::parse_uci(String::from("debug maybe")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("debug"))), uci::parse_uci(String::from("debug on off")));
+
+        assert_eq!(UciCommand::Debug(true), uci::parse_uci(String::from("debug on")).unwrap());
+        assert_eq!(UciCommand::Debug(false), uci::parse_uci(String::from("debug off")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_go_nodes() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go nodes")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go nodes 1000 2000")));
+
+        assert_eq!(UciCommand::GoNodes("1000".to_string()), uci::parse_uci(String::from("go nodes 1000")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_go_search_moves() {
+        let moves = vec![String::from("e2e4"), String::from("d2d4")];
+        assert_eq!(UciCommand::GoSearchMoves(moves.clone(), Box::new(UciCommand::GoDepth(String::from("5")))),
+                   uci::parse_uci(String::from("go depth 5 searchmoves e2e4 d2d4")).unwrap());
+        assert_eq!(UciCommand::GoSearchMoves(moves.clone(), Box::new(UciCommand::GoMoveTime(String::from("100")))),
+                   uci::parse_uci(String::from("go searchmoves e2e4 d2d4 movetime 100")).unwrap());
+        assert_eq!(UciCommand::GoSearchMoves(moves, Box::new(UciCommand::GoClockTime(vec![String::from("wtime"), String::from("1000"), String::from("btime"), String::from("1000")]))),
+                   uci::parse_uci(String::from("go wtime 1000 searchmoves e2e4 d2d4 btime 1000")).unwrap());
+
+        // the moves are missing, there is no search limit, or the command is no search
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go depth 5 searchmoves")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go searchmoves e2e4")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go perft 3 searchmoves e2e4")));
+    }
+
+    #[test]
+    fn test_parse_uci_for_go_ponder() {
+        let clock_time = || UciCommand::GoClockTime(["wtime", "1000", "btime", "2000"].map(String::from).to_vec());
+        assert_eq!(UciCommand::GoPonder(Box::new(clock_time())), uci::parse_uci(String::from("go ponder wtime 1000 btime 2000")).unwrap());
+        assert_eq!(UciCommand::GoPonder(Box::new(clock_time())), uci::parse_uci(String::from("go wtime 1000 btime 2000 ponder")).unwrap());
+        assert_eq!(UciCommand::GoPonder(Box::new(UciCommand::GoMoveTime(String::from("100")))), uci::parse_uci(String::from("go ponder movetime 100")).unwrap());
+        assert_eq!(UciCommand::GoPonder(Box::new(UciCommand::GoSearchMoves(vec![String::from("e2e4")], Box::new(clock_time())))),
+                   uci::parse_uci(String::from("go ponder searchmoves e2e4 wtime 1000 btime 2000")).unwrap());
+        assert_eq!(UciCommand::PonderHit, uci::parse_uci(String::from("ponderhit")).unwrap());
+
+        // only searches with a time limit can ponder
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go ponder depth 5")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go ponder searchmoves e2e4 depth 5")));
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("go"))), uci::parse_uci(String::from("go ponder")));
+    }
+
+    #[test]
+    fn test_parse_uci_for_exclude() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("exclude"))), uci::parse_uci(String::from("exclude e2e4 d2d4")));
+
+        assert_eq!(UciCommand::Exclude(None), uci::parse_uci(String::from("exclude")).unwrap());
+        assert_eq!(UciCommand::Exclude(Some(String::from("e2e4"))), uci::parse_uci(String::from("exclude e2e4")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_resetoptions() {
+        assert_eq!(UciCommand::ResetOptions, uci::parse_uci(String::from("resetoptions")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_saveconfig() {
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("saveconfig"))), uci::parse_uci(String::from("saveconfig a.toml b.toml")));
+
+        assert_eq!(UciCommand::SaveConfig(None), uci::parse_uci(String::from("saveconfig")).unwrap());
+        assert_eq!(UciCommand::SaveConfig(Some(String::from("a.toml"))), uci::parse_uci(String::from("saveconfig a.toml")).unwrap());
+    }
+
+    #[test]
+    fn test_parse_uci_for_stop() {
+        assert_eq!(UciCommand::Stop, uci::parse_uci(String::from("stop")).unwrap());
+    }
+
     #[test]
     fn test_parse_uci_for_quit() {
         assert_eq!(UciCommand::Quit, uci::parse_uci(String::from("quit")).unwrap());
@@ -156,6 +536,16 @@ mod tests {
 
     #[test]
     fn test_parse_uci_for_display() {
-        assert_eq!(UciCommand::Display, uci::parse_uci(String::from("display")).unwrap());
+        assert_eq!(UciCommand::Display(true), uci::parse_uci(String::from("display")).unwrap());
+        assert_eq!(UciCommand::Display(false), uci::parse_uci(String::from("display noclock")).unwrap());
+        assert_eq!(Err(UciParseError::InvalidArguments(String::from("display"))), uci::parse_uci(String::from("display clock")));
+        assert_eq!(UciCommand::HashStats, uci::parse_uci(String::from("hashstats")).unwrap());
+        assert_eq!(UciCommand::Analyze, uci::parse_uci(String::from("analyze")).unwrap());
+        assert_eq!(UciCommand::Board(false, false), uci::parse_uci(String::from("board")).unwrap());
+        assert_eq!(UciCommand::Board(true, false), uci::parse_uci(String::from("board unicode")).unwrap());
+        assert_eq!(UciCommand::Board(false, true), uci::parse_uci(String::from("board flip")).unwrap());
+        assert_eq!(UciCommand::Board(true, true), uci::parse_uci(String::from("board flip unicode")).unwrap());
+        assert!(uci::parse_uci(String::from("board flip flip")).is_err());
+        assert!(uci::parse_uci(String::from("board ascii")).is_err());
     }
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+//! The wasm module is the JavaScript API of the WebAssembly build, which is built with the "wasm" feature, e.g. with wasm-pack:
+//!
+//! ```text
+//! wasm-pack build --target web -- --features wasm
+//! ```
+//!
+//! It wraps the `Engine` of the engine module, so that a web GUI can set up positions, play moves and search in the browser.
+//! Moves are passed as strings in pure algebraic coordinate notation, e.g. "e2e4" or "e7e8q".
+//! The searches run on the calling thread, so they should be run in a web worker to keep the page responsive.
+
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+use crate::engine::{Engine, SearchLimit};
+
+/// Ladybug for JavaScript.
+#[wasm_bindgen(js_name = Ladybug)]
+pub struct WasmEngine {
+    /// The engine that holds the game and the search.
+    engine: Engine,
+}
+
+impl Default for WasmEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen(js_class = Ladybug)]
+impl WasmEngine {
+    /// Constructs an engine with the starting position.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmEngine {
+        WasmEngine { engine: Engine::new() }
+    }
+
+    /// Starts a new game from the position described by the given FEN string.
+    #[wasm_bindgen(js_name = setFen)]
+    pub fn set_fen(&mut self, fen: &str) -> Result<(), JsError> {
+        self.engine.set_fen(fen).map(|_engine| ()).map_err(|error| JsError::new(error.to_string().as_str()))
+    }
+
+    /// Plays the given move.
+    #[wasm_bindgen(js_name = playMove)]
+    pub fn play_move(&mut self, move_str: &str) -> Result<(), JsError> {
+        self.engine.play_move(move_str).map(|_engine| ()).map_err(|message| JsError::new(message.as_str()))
+    }
+
+    /// Returns the FEN string of the current position.
+    pub fn fen(&self) -> String {
+        self.engine.board().to_fen()
+    }
+
+    /// Returns the legal moves in the current position.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        let move_list = self.engine.game().legal_moves();
+        (0..move_list.len()).map(|i| move_list.get(i).to_string()).collect()
+    }
+
+    /// Returns the result of the game in PGN notation, e.g. "1-0", or undefined if the game is not over yet.
+    pub fn result(&self) -> Option<String> {
+        self.engine.game().result().map(|result| result.to_pgn().to_string())
+    }
+
+    /// Searches the current position to the given depth and returns the best move, or undefined if there are no legal moves.
+    /// If a callback is given, it is called with each info line of the search, e.g. "info depth 5 ...", once the search is finished.
+    #[wasm_bindgen(js_name = searchDepth)]
+    pub fn search_depth(&mut self, depth: u32, on_info: Option<Function>) -> Option<String> {
+        self.search(SearchLimit::Depth(depth as u64), on_info)
+    }
+
+    /// Searches the current position for the given amount of milliseconds and returns the best move,
+    /// or undefined if there are no legal moves. The callback is called just like by `searchDepth`.
+    #[wasm_bindgen(js_name = searchTime)]
+    pub fn search_time(&mut self, time: u32, on_info: Option<Function>) -> Option<String> {
+        self.search(SearchLimit::Time(time as u64), on_info)
+    }
+
+    /// Searches the current position until the given number of nodes is searched and returns the best move,
+    /// or undefined if there are no legal moves. The callback is called just like by `searchDepth`.
+    #[wasm_bindgen(js_name = searchNodes)]
+    pub fn search_nodes(&mut self, nodes: u32, on_info: Option<Function>) -> Option<String> {
+        self.search(SearchLimit::Nodes(nodes as u64), on_info)
+    }
+}
+
+impl WasmEngine {
+    /// Searches the current position with the given limit, and passes the info lines to the given callback, if any.
+    fn search(&mut self, limit: SearchLimit, on_info: Option<Function>) -> Option<String> {
+        let best_move = self.engine.search(limit, |info| {
+            if let Some(on_info) = &on_info {
+                // errors thrown by the callback don't stop the search
+                let _ = on_info.call1(&JsValue::NULL, &JsValue::from_str(info));
+            }
+        });
+        best_move.map(|ply| ply.to_string())
+    }
+}
@@ -1,6 +1,4 @@
-use crate::board::color::Color;
 use crate::board::position::Position;
-use crate::board::square::{NUM_SQUARES, Square};
 
 pub mod random;
 
@@ -8,15 +6,12 @@ pub mod random;
 pub fn get_hash(position: &Position) -> u64 {
     // pieces
     let mut pieces: u64 = 0;
-    for square_index in 0..NUM_SQUARES {
-        let square = Square::new(square_index);
-        if let Some((piece, color)) = position.get_piece(square) {
-            pieces ^= random::get_random_piece(piece, color, square);
-        }
+    for (square, piece, color) in position.pieces_iter() {
+        pieces ^= random::get_random_piece(piece, color, square);
     }
 
     // castling rights
-    let castling_rights = random::get_random_castle(position.castling_rights[Color::White.to_index() as usize], position.castling_rights[Color::Black.to_index() as usize]);
+    let castling_rights = random::get_random_castle(position.castling_rights);
 
     // en passant
     let en_passant = random::get_random_en_passant(position.en_passant.map(|square| square.get_file()));
@@ -32,8 +27,6 @@ pub fn get_hash(position: &Position) -> u64 {
 mod tests {
     use crate::board::Board;
     use crate::board::position::Position;
-    use crate::lookup::LOOKUP_TABLE;
-    use crate::lookup::lookup_table::LookupTable;
     use crate::move_gen;
     use crate::zobrist::get_hash;
 
@@ -71,10 +64,6 @@ mod tests {
     #[test]
     #[ignore]
     fn test_incremental_hash_updates() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // Position 1 - Starting Position
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         zobrist_perft(position, 5);
@@ -98,20 +87,16 @@ mod tests {
 
     #[test]
     fn test_get_hash() {
-        let mut lookup = LookupTable::default();
-        lookup.initialize_tables();
-        let _ = LOOKUP_TABLE.set(lookup);
-
         // starting position
         let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap().position;
         assert_eq!(0x463b96181691fc9c, get_hash(&position));
 
         // position after e2e4
-        let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap().position;
+        //let position = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap().position;
         //assert_eq!(0x823c9b50fd114196, get_hash(position));
 
         // position after e2e4 d7d5
-        let position = Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2").unwrap().position;
+        //let position = Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2").unwrap().position;
         //assert_eq!(0x0756b94461c50fb0, get_hash(position));
 
         // position after e2e4 d7d5 e4e5
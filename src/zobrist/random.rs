@@ -31,47 +31,10 @@ pub fn get_random_piece(piece: Piece, color: Color, square: Square) -> u64 {
 }
 
 /// Returns a random number for the given combination of castling rights.
-pub fn get_random_castle(castling_rights_white: CastlingRights, castling_rights_black: CastlingRights) -> u64 {
-    let mut castle = 0;
-    
-    // white kingside
-    if castling_rights_white == CastlingRights::KingSide || castling_rights_white == CastlingRights::Both {
-        castle ^= RANDOM64[RANDOM_CASTLE_OFFSET];
-    }
-    // white queenside
-    if castling_rights_white == CastlingRights::QueenSide || castling_rights_white == CastlingRights::Both {
-        castle ^= RANDOM64[RANDOM_CASTLE_OFFSET + 1];
-    }
-    // black kingside
-    if castling_rights_black == CastlingRights::KingSide || castling_rights_black == CastlingRights::Both {
-        castle ^= RANDOM64[RANDOM_CASTLE_OFFSET + 2];
-    }
-    // black queenside
-    if castling_rights_black == CastlingRights::QueenSide || castling_rights_black == CastlingRights::Both {
-        castle ^= RANDOM64[RANDOM_CASTLE_OFFSET + 3];
-    }
-    
-    castle
-}
-
-/// Returns a random number for the given castling rights of the given color.
-pub fn get_random_castling(castling_rights: CastlingRights, color: Color) -> u64 {
-    let mut castle = 0;
-    
-    let color_offset = match color {
-        Color::White => 0,
-        Color::Black => 2,
-    };
-    
-    if castling_rights == CastlingRights::KingSide || castling_rights == CastlingRights::Both {
-        castle ^= RANDOM64[RANDOM_CASTLE_OFFSET + color_offset];
-    }
-
-    if castling_rights == CastlingRights::QueenSide || castling_rights == CastlingRights::Both {
-        castle ^= RANDOM64[RANDOM_CASTLE_OFFSET + 1 + color_offset];
-    }
-
-    castle
+/// The random numbers of the flags are combined, so the castling rights that differ between two combinations
+/// are the XOR of their random numbers.
+pub fn get_random_castle(castling_rights: CastlingRights) -> u64 {
+    castling_rights.indices().fold(0, |castle, index| castle ^ RANDOM64[RANDOM_CASTLE_OFFSET + index])
 }
 
 /// Returns a random number for the given target en passant file.
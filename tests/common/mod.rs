@@ -1,11 +1,28 @@
+// not every test crate uses every helper function
+#![allow(dead_code)]
+
 use std::sync::mpsc;
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
 use std::thread;
-use ladybug::ladybug::{Ladybug, Message};
-use ladybug::lookup::LOOKUP_TABLE;
-use ladybug::lookup::lookup_table::LookupTable;
+use std::time::Duration;
+use ladybug::ladybug::{Ladybug, Message, MESSAGE_CHANNEL_CAPACITY, OUTPUT_CHANNEL_CAPACITY};
 use ladybug::search::{Search, SearchCommand};
 
+/// The maximum time to wait for a single response, so that a missing response fails the test instead of hanging it.
+pub const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Helper function to receive the next line of output from Ladybug that is not an info line.
+/// If no such line arrives within the given time, the test fails instead of hanging.
+pub fn receive(receiver: &Receiver<String>, timeout: Duration) -> String {
+    loop {
+        match receiver.recv_timeout(timeout) {
+            Ok(output) if output.starts_with("info") => continue,
+            Ok(output) => return output,
+            Err(_) => panic!("Ladybug did not respond in time"),
+        }
+    }
+}
+
 /// Helper function to assert that the engine returns the expected output after reaching the given depth.
 /// 
 /// This function makes the tests cleaner and easier to read, since we want to discard all the "info depth..." messages and
@@ -21,7 +38,7 @@ pub fn assert_result(receiver: &Receiver<String>, depth: u8 , expected: &str) {
 }
 
 /// Helper function to send the given fen string to Ladybug and tell her to search to the given depth.
-pub fn go_position(sender: &Sender<Message>, fen: &str, depth: u8) {
+pub fn go_position(sender: &SyncSender<Message>, fen: &str, depth: u8) {
     let go_command = format!("position fen {fen}");
     let _ = sender.send(Message::ConsoleMessage(go_command));
     let _ = sender.send(Message::ConsoleMessage(format!("go depth {depth}")));
@@ -30,29 +47,26 @@ pub fn go_position(sender: &Sender<Message>, fen: &str, depth: u8) {
 /// Helper function to initialize and spawn the main and search threads, just like in the main function, but return the sender and receiver 
 /// to the test function instead of creating dedicated input and output threads. The test thread will act as both input and output thread,
 /// and is thus able to properly test Ladybug's output for various input.
-pub fn setup() -> (Sender<Message>, Receiver<String>) {
-    // initialize the lookup table
-    let mut lookup = LookupTable::default();
-    lookup.initialize_tables();
-    let _ = LOOKUP_TABLE.set(lookup);
-
+pub fn setup() -> (SyncSender<Message>, Receiver<String>) {
     // create search_command_sender and search_command_receiver so that the ladybug thread can send commands to the search thread
     let (search_command_sender, search_command_receiver): (Sender<SearchCommand>, Receiver<SearchCommand>) = mpsc::channel();
 
     // create message_sender and message_receiver so that the test and search threads can send messages to the ladybug thread
-    let (message_sender, message_receiver) : (Sender<Message>, Receiver<Message>) = mpsc::channel();
+    let (message_sender, message_receiver) : (SyncSender<Message>, Receiver<Message>) = mpsc::sync_channel(MESSAGE_CHANNEL_CAPACITY);
 
     // create output_sender and output_receiver so that the ladybug thread can send output to the test thread.
-    let (output_sender, output_receiver) : (Sender<String>, Receiver<String>) = mpsc::channel();
+    let (output_sender, output_receiver) : (SyncSender<String>, Receiver<String>) = mpsc::sync_channel(OUTPUT_CHANNEL_CAPACITY);
 
     // initialize the search
     let mut search = Search::new(search_command_receiver, message_sender.clone());
+    let stop_flag = search.stop_flag();
+    let ponder_flag = search.ponder_flag();
 
     // spawn the search thread
     let _ = thread::Builder::new().name("search".to_string()).spawn(move || search.run());
 
     // initialize Ladybug
-    let mut ladybug = Ladybug::new(search_command_sender, output_sender.clone(), message_receiver);
+    let mut ladybug = Ladybug::new(search_command_sender, output_sender.clone(), message_receiver, stop_flag, ponder_flag);
 
     // spawn the Ladybug thread
     thread::spawn(move || ladybug.run());
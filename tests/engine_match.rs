@@ -0,0 +1,64 @@
+use std::time::Duration;
+use ladybug::ladybug::Message;
+use common::RESPONSE_TIMEOUT;
+
+mod common;
+
+/// The maximum time to wait for a game of the match, which takes longer than a single response on slow machines.
+const GAME_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[test]
+fn test_match_against_ladybug() {
+    let (sender, receiver) = common::setup();
+
+    // whoever plays White mates in one, and each opening is played with both colors
+    let openings = std::env::temp_dir().join("ladybug_test_match_openings.epd");
+    std::fs::write(&openings, "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq -\n").unwrap();
+    let pgn = std::env::temp_dir().join("ladybug_test_match.pgn");
+
+    let engine = env!("CARGO_BIN_EXE_ladybug");
+    let _ = sender.send(Message::ConsoleMessage(format!("match {engine} 2 10000 100 openings {} pgn {}", openings.display(), pgn.display())));
+
+    let output: Vec<String> = (0..6).map(|_| common::receive(&receiver, GAME_TIMEOUT)).collect();
+
+    let name = format!("Ladybug {}", env!("CARGO_PKG_VERSION"));
+    assert_eq!(format!("Game 1 of 2: {name} - {name} 1-0 (Checkmate(White))"), output[0]);
+    assert_eq!(format!("Game 2 of 2: {name} - {name} 1-0 (Checkmate(White))"), output[1]);
+    assert!(output[2].starts_with("Engine"));
+    assert!(output[3].starts_with(name.as_str()));
+    assert_eq!(vec!["1", "1", "0", "1.0/2"], output[3].split_whitespace().skip(2).collect::<Vec<&str>>());
+    assert_eq!(vec!["1", "1", "0", "1.0/2"], output[4].split_whitespace().skip(2).collect::<Vec<&str>>());
+    assert_eq!("Elo difference: +0", output[5]);
+
+    let pgn = std::fs::read_to_string(&pgn).unwrap();
+    assert_eq!(2, pgn.matches("Qxf7# 1-0").count());
+}
+
+#[test]
+fn test_match_against_missing_engine() {
+    let (sender, receiver) = common::setup();
+
+    let _ = sender.send(Message::ConsoleMessage(String::from("match /this/engine/does/not/exist 2 1000 10")));
+    assert_eq!("info string Could not start engine /this/engine/does/not/exist", receiver.recv_timeout(RESPONSE_TIMEOUT).unwrap());
+
+    let _ = sender.send(Message::ConsoleMessage(String::from("match ladybug 2 0 10")));
+    assert_eq!("info string unknown command", receiver.recv_timeout(RESPONSE_TIMEOUT).unwrap());
+}
+
+#[test]
+fn test_perft_diff_against_ladybug() {
+    let (sender, receiver) = common::setup();
+
+    // Ladybug agrees with herself, so there is no divergent position
+    let engine = env!("CARGO_BIN_EXE_ladybug");
+    let _ = sender.send(Message::ConsoleMessage(String::from("position fen r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")));
+    let _ = sender.send(Message::ConsoleMessage(format!("perftdiff {engine} 2")));
+    let name = format!("Ladybug {}", env!("CARGO_PKG_VERSION"));
+    assert_eq!(format!("Perft matches {name} at depth 2: 2039 nodes"), receiver.recv_timeout(RESPONSE_TIMEOUT).unwrap());
+
+    let _ = sender.send(Message::ConsoleMessage(String::from("perftdiff /this/engine/does/not/exist 2")));
+    assert_eq!("info string Could not start engine /this/engine/does/not/exist", receiver.recv_timeout(RESPONSE_TIMEOUT).unwrap());
+
+    let _ = sender.send(Message::ConsoleMessage(format!("perftdiff {engine} 0")));
+    assert_eq!("info string unknown command", receiver.recv_timeout(RESPONSE_TIMEOUT).unwrap());
+}
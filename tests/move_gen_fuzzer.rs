@@ -0,0 +1,140 @@
+//! Plays random legal games and checks the invariants of the board representation after every move,
+//! so that move generation and move making bugs are caught before they corrupt a search.
+
+use ladybug::board::Board;
+use ladybug::board::bitboard::Bitboard;
+use ladybug::board::color::{Color, NUM_COLORS};
+use ladybug::board::piece::{Piece, NUM_PIECES};
+use ladybug::board::position::Position;
+use ladybug::game::Game;
+use ladybug::move_gen::ply::Ply;
+use ladybug::zobrist;
+
+/// The positions the random games start from: the starting position, and positions with castling, en passant and promotions.
+const START_FENS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+];
+
+/// The maximum number of moves of a random game.
+const MAX_GAME_LENGTH: usize = 300;
+
+/// A small [xorshift](https://en.wikipedia.org/wiki/Xorshift) random number generator, so that the games are reproducible.
+struct Random(u64);
+
+impl Random {
+    /// Returns the next random number.
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+/// Asserts the invariants of the given board, reporting the moves that led to it on failure.
+fn assert_invariants(board: Board, moves: &str) {
+    let position = board.position;
+
+    // exactly one king per side
+    for color in [Color::White, Color::Black] {
+        assert_eq!(1, position.get_num_pieces(Piece::King, color), "{color:?} king count after{moves}");
+    }
+
+    // the piece bitboards don't overlap, and the occupancies are their union
+    let mut occupancies = Bitboard::new(0);
+    for color_index in 0..NUM_COLORS {
+        let mut occupancy = Bitboard::new(0);
+        for piece_index in 0..NUM_PIECES {
+            let piece_bb = position.piece_bb(Color::from_index(color_index), Piece::from_index(piece_index));
+            assert_eq!(0, occupancies.value & piece_bb.value, "overlapping pieces after{moves}");
+            occupancy.value |= piece_bb.value;
+            occupancies.value |= piece_bb.value;
+        }
+        assert_eq!(occupancy, position.get_occupancy(Color::from_index(color_index)), "occupancy after{moves}");
+    }
+    assert_eq!(occupancies, position.get_occupancies(), "occupancies after{moves}");
+
+    // the mailbox agrees with the bitboards
+    for (square_index, square) in position.get_mailbox().iter().enumerate() {
+        assert_eq!(occupancies.value >> square_index & 1 == 1, square.is_some(), "mailbox after{moves}");
+    }
+
+    // the incrementally updated hash and attack bitboards match the ones calculated from scratch
+    assert_eq!(zobrist::get_hash(&position), position.hash, "hash after{moves}");
+    let mut recalculated = position;
+    recalculated.initialize_attack_bb();
+    for color in [Color::White, Color::Black] {
+        assert_eq!(recalculated.get_attack_bb(color), position.get_attack_bb(color), "{color:?} attacks after{moves}");
+    }
+
+    // the side that just moved is not in check
+    assert!(position.is_legal(), "illegal position after{moves}");
+
+    // the FEN describes the board exactly
+    let fen = board.to_fen();
+    let parsed = Board::from_fen(fen.as_str()).unwrap_or_else(|error| panic!("invalid FEN {fen} after{moves}: {error:?}"));
+    assert_eq!(board, parsed, "FEN round trip of {fen} after{moves}");
+    assert_eq!(fen, parsed.to_fen(), "FEN round trip after{moves}");
+}
+
+/// Asserts that reverting the pieces that changed by the given move restores the pieces of the position before the move.
+fn assert_unmake(before: Position, ply: Ply, moves: &str) {
+    let (after, dirty_pieces) = before.make_move_with_dirty_pieces(ply);
+    let mut restored = after;
+    // the pieces are reverted in reverse order, so that a promotion removes the promotion piece before the pawn is put back
+    for dirty_piece in dirty_pieces.iter().collect::<Vec<_>>().into_iter().rev() {
+        if let Some(square) = dirty_piece.to {
+            restored.remove_piece(dirty_piece.piece, dirty_piece.color, square);
+        }
+        if let Some(square) = dirty_piece.from {
+            restored.set_piece(dirty_piece.piece, dirty_piece.color, square);
+        }
+    }
+    assert_eq!(before.pieces, restored.pieces, "unmake after{moves}");
+}
+
+/// Plays the given number of random games, starting from each start position in turn, and checks the invariants after every move.
+fn play_random_games(games: u64, seed: u64) {
+    let mut random = Random(seed);
+    for game_index in 0..games {
+        let mut game = Game::from_fen(START_FENS[game_index as usize % START_FENS.len()]).unwrap();
+        let mut moves = String::new();
+        assert_invariants(game.board(), moves.as_str());
+
+        for _ in 0..MAX_GAME_LENGTH {
+            if game.outcome().is_some() {
+                break;
+            }
+            let move_list = game.legal_moves();
+            let ply = move_list.get((random.next() % move_list.len() as u64) as u8);
+            moves += format!(" {ply}").as_str();
+
+            let before = game.board();
+            game.make_move(ply).unwrap_or_else(|error| panic!("{error} after{moves}"));
+            assert_invariants(game.board(), moves.as_str());
+            assert_unmake(before.position, ply, moves.as_str());
+
+            // taking the move back restores the game exactly
+            let history_length = game.board_history().len();
+            assert_eq!(Some(ply), game.undo());
+            assert_eq!(before, game.board(), "undo after{moves}");
+            assert_eq!(history_length - 1, game.board_history().len());
+            let _ = game.make_move(ply);
+        }
+    }
+}
+
+#[test]
+fn test_random_games() {
+    play_random_games(40, 0x2545_F491_4F6C_DD1D);
+}
+
+// This takes about a minute in release mode, run it with --release --ignored after changing the move generation.
+#[test]
+#[ignore]
+fn test_many_random_games() {
+    play_random_games(10_000, 0x9E37_79B9_7F4A_7C15);
+}
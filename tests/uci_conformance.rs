@@ -0,0 +1,214 @@
+use std::sync::mpsc::{Receiver, SyncSender};
+use ladybug::board::Board;
+use ladybug::ladybug::Message;
+use ladybug::move_gen::ply::Ply;
+use common::{receive, RESPONSE_TIMEOUT};
+
+mod common;
+
+/// Helper function to drive Ladybug through a UCI session transcript.
+///
+/// Each step consists of a command and the responses expected for it, in order.
+/// Info lines that are not expected are skipped, since their content depends on the search.
+/// An expected response ending with '*' only has to match the beginning of the actual response.
+fn run_transcript(sender: &SyncSender<Message>, receiver: &Receiver<String>, transcript: &[(&str, &[&str])]) {
+    for (command, expected_responses) in transcript {
+        let _ = sender.send(Message::ConsoleMessage(command.to_string()));
+
+        for expected in expected_responses.iter() {
+            let output = match expected.starts_with("info") {
+                true => receiver.recv_timeout(RESPONSE_TIMEOUT).expect("Ladybug did not respond in time"),
+                false => receive(receiver, RESPONSE_TIMEOUT),
+            };
+
+            match expected.strip_suffix('*') {
+                Some(prefix) => assert!(output.starts_with(prefix), "command `{command}`: expected `{expected}`, got `{output}`"),
+                None => assert_eq!(*expected, output, "command `{command}`"),
+            }
+        }
+    }
+}
+
+/// Helper function to wait for the best move and assert that it is legal in the given position.
+fn assert_legal_best_move(receiver: &Receiver<String>, fen: &str) {
+    let output = receive(receiver, RESPONSE_TIMEOUT);
+
+    let best_move = output.strip_prefix("bestmove ").unwrap_or_else(|| panic!("expected a best move, got `{output}`"));
+    let position = Board::from_fen(fen).unwrap().position;
+    assert!(Ply::from_string(best_move, position).is_ok(), "illegal best move `{best_move}` for `{fen}`");
+}
+
+#[test]
+fn test_handshake() {
+    let (sender, receiver) = common::setup();
+
+    run_transcript(&sender, &receiver, &[
+        ("uci", &[
+            "id name Ladybug*",
+            "id author*",
+            "option name OwnBook type check default false",
+            "option name BookFile type string default <empty>",
+            "option name OpeningVariety type spin default 0 min 0 max 100",
+            "option name Variety type spin default 0 min 0 max 100",
+            "option name Skill Level type spin default 20 min 0 max 20",
+            "option name Contempt type spin default 0 min -100 max 100",
+            "option name Hash type spin default 16 min 1 max 65536",
+            "option name Clear Hash type button",
+            "option name Threads type spin default 1 min 1 max 64",
+            "option name Move Overhead type spin default 10 min 0 max 5000",
+            "option name Ponder type check default false",
+            "option name Ponder Credit type spin default 50 min 0 max 100",
+            "option name EvalParamsFile type string default <empty>",
+            "option name LogFile type string default <empty>",
+            "option name UCI_Variant type combo default chess var chess var nocastle var kingofthehill",
+            "option name UCI_ShowWDL type check default false",
+            "uciok",
+        ]),
+        ("isready", &["readyok"]),
+        ("ucinewgame", &[]),
+        ("isready", &["readyok"]),
+    ]);
+}
+
+#[test]
+fn test_set_option() {
+    let (sender, receiver) = common::setup();
+
+    run_transcript(&sender, &receiver, &[
+        ("uci", &["id name Ladybug*", "id author*", "option name OwnBook*", "option name BookFile*", "option name OpeningVariety*", "option name Variety*", "option name Skill Level*", "option name Contempt*", "option name Hash*", "option name Clear Hash*", "option name Threads*", "option name Move Overhead*", "option name Ponder*", "option name Ponder Credit*", "option name EvalParamsFile*", "option name LogFile*", "option name UCI_Variant*", "option name UCI_ShowWDL*", "uciok"]),
+        ("setoption name OwnBook value false", &[]),
+        ("setoption name ownbook value true", &[]),
+        ("setoption name OwnBook value maybe", &["info string invalid option value"]),
+        ("setoption name Does Not Exist value 42", &["info string unknown option"]),
+        ("setoption name BookFile value /this/file/does/not/exist.bin", &["info string could not load book"]),
+        ("setoption name OpeningVariety value 8", &[]),
+        ("setoption name OpeningVariety value 101", &["info string invalid option value"]),
+        ("setoption name Variety value 25", &[]),
+        ("setoption name Variety value 101", &["info string invalid option value"]),
+        ("setoption name Hash value 8", &[]),
+        ("setoption name Hash value 0", &["info string hash size clamped to 1 MB"]),
+        ("setoption name Hash value 16", &[]),
+        ("setoption name Clear Hash", &[]),
+        ("setoption name Threads value 2", &[]),
+        ("setoption name Threads value 0", &["info string invalid option value"]),
+        ("setoption name Move Overhead value 50", &[]),
+        ("setoption name Move Overhead value -50", &["info string invalid option value"]),
+        ("setoption name EvalParamsFile value /this/file/does/not/exist.toml", &["info string could not load evaluation parameters*"]),
+        ("setoption name EvalParamsFile value <empty>", &[]),
+        ("setoption name LogFile value /this/directory/does/not/exist.log", &["info string Could not open log file"]),
+        ("setoption name LogFile value <empty>", &[]),
+        ("setoption value true", &["info string unknown command"]),
+        ("isready", &["readyok"]),
+        // without a book file, Ladybug plays a move from its internal book
+        ("position startpos", &[]),
+        ("go depth 2", &["bestmove *"]),
+        // the shallow iterations are not reported, only the last one
+        ("setoption name OwnBook value false", &[]),
+        ("go depth 2", &["info depth 2*", "bestmove *"]),
+    ]);
+}
+
+#[test]
+fn test_position_and_go() {
+    let (sender, receiver) = common::setup();
+
+    run_transcript(&sender, &receiver, &[
+        ("uci", &["id name Ladybug*", "id author*", "option name OwnBook*", "option name BookFile*", "option name OpeningVariety*", "option name Variety*", "option name Skill Level*", "option name Contempt*", "option name Hash*", "option name Clear Hash*", "option name Threads*", "option name Move Overhead*", "option name Ponder*", "option name Ponder Credit*", "option name EvalParamsFile*", "option name LogFile*", "option name UCI_Variant*", "option name UCI_ShowWDL*", "uciok"]),
+        ("isready", &["readyok"]),
+        ("ucinewgame", &[]),
+        ("position startpos moves e2e4 e7e5 g1f3", &[]),
+        ("display", &["rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"]),
+        // the halfmove clock and the fullmove counter may be omitted
+        ("position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 moves e7e5", &[]),
+        ("display", &["rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"]),
+        ("position fen rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 moves b8c6", &[]),
+        ("display", &["r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 2"]),
+        ("position startpos moves e2e4 e7e5 g1f3", &[]),
+        ("go depth 3", &["info depth 3*", "bestmove *"]),
+        ("isready", &["readyok"]),
+    ]);
+
+    // every kind of go command answers with a legal move for the position
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let _ = sender.send(Message::ConsoleMessage(format!("position fen {fen}")));
+
+    let _ = sender.send(Message::ConsoleMessage(String::from("go depth 3")));
+    assert_legal_best_move(&receiver, fen);
+
+    let _ = sender.send(Message::ConsoleMessage(String::from("go movetime 200")));
+    assert_legal_best_move(&receiver, fen);
+
+    let _ = sender.send(Message::ConsoleMessage(String::from("go wtime 10000 btime 10000")));
+    assert_legal_best_move(&receiver, fen);
+
+    let _ = sender.send(Message::ConsoleMessage(String::from("go wtime 10000 btime 10000 winc 100 binc 100")));
+    assert_legal_best_move(&receiver, fen);
+}
+
+#[test]
+fn test_mate_is_reported() {
+    let (sender, receiver) = common::setup();
+
+    common::go_position(&sender, "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 2 4", 2);
+    common::assert_result(&receiver, 2, "bestmove h5f7");
+}
+
+#[test]
+fn test_illegal_input() {
+    let (sender, receiver) = common::setup();
+
+    // Ladybug must reject malformed commands and keep responding afterwards
+    run_transcript(&sender, &receiver, &[
+        ("uci", &["id name Ladybug*", "id author*", "option name OwnBook*", "option name BookFile*", "option name OpeningVariety*", "option name Variety*", "option name Skill Level*", "option name Contempt*", "option name Hash*", "option name Clear Hash*", "option name Threads*", "option name Move Overhead*", "option name Ponder*", "option name Ponder Credit*", "option name EvalParamsFile*", "option name LogFile*", "option name UCI_Variant*", "option name UCI_ShowWDL*", "uciok"]),
+        ("", &["info string unknown command"]),
+        ("this is not uci", &["info string unknown command"]),
+        ("position", &["info string unknown command"]),
+        ("position somewhere", &["info string unknown command"]),
+        ("position fen this is not a fen", &["info string invalid fen"]),
+        ("position fen 8/8/8/8/8/8/8/4K2k w -", &["info string invalid fen"]),
+        ("position startpos moves e2e5", &["info string invalid moves: Illegal move: e2e5 (move 1)"]),
+        ("display", &["8/8/8/8/8/8/8/8 w - - 0 1"]),
+        ("go", &["info string unknown command"]),
+        ("go depth", &["info string unknown command"]),
+        ("go depth deep", &["info string unknown command"]),
+        ("go movetime soon", &["info string unknown command"]),
+        ("go wtime 1000", &["info string unknown command"]),
+        ("go wtime 1000 btime", &["info string unknown command"]),
+        ("go wtime a btime b", &["info string unknown command"]),
+        ("isready", &["readyok"]),
+        ("position startpos moves e2e4", &[]),
+        ("display", &["rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"]),
+    ]);
+}
+
+#[test]
+fn test_isready_and_stop_during_search() {
+    let (sender, receiver) = common::setup();
+
+    // Ladybug keeps responding while searching, and "stop" ends the search with a legal move
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    run_transcript(&sender, &receiver, &[
+        (format!("position fen {fen}").as_str(), &[]),
+        ("go movetime 600000", &[]),
+        ("isready", &["readyok"]),
+        ("stop", &[]),
+    ]);
+    assert_legal_best_move(&receiver, fen);
+
+    // a "stop" without a running search is ignored
+    run_transcript(&sender, &receiver, &[
+        ("stop", &[]),
+        ("isready", &["readyok"]),
+    ]);
+}
+
+#[test]
+fn test_quit() {
+    let (sender, receiver) = common::setup();
+
+    run_transcript(&sender, &receiver, &[
+        ("uci", &["id name Ladybug*", "id author*", "option name OwnBook*", "option name BookFile*", "option name OpeningVariety*", "option name Variety*", "option name Skill Level*", "option name Contempt*", "option name Hash*", "option name Clear Hash*", "option name Threads*", "option name Move Overhead*", "option name Ponder*", "option name Ponder Credit*", "option name EvalParamsFile*", "option name LogFile*", "option name UCI_Variant*", "option name UCI_ShowWDL*", "uciok"]),
+        ("isready", &["readyok"]),
+        ("quit", &["quit"]),
+    ]);
+}
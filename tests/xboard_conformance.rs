@@ -0,0 +1,78 @@
+//! Ladybug does not speak the xboard protocol (CECP) natively. xboard users run her through the polyglot adapter,
+//! which translates the xboard session into UCI. These tests replay the UCI traffic polyglot generates for an
+//! xboard game, so that the adapter keeps working as the protocol handling changes.
+
+use ladybug::board::Board;
+use ladybug::ladybug::Message;
+use ladybug::move_gen::ply::Ply;
+use common::{receive, RESPONSE_TIMEOUT};
+
+mod common;
+
+#[test]
+fn test_polyglot_launch_sequence() {
+    let (sender, receiver) = common::setup();
+    let send = |command: &str| {
+        let _ = sender.send(Message::ConsoleMessage(command.to_string()));
+    };
+
+    // xboard: "xboard", "protover 2" - polyglot starts the engine and waits for uciok
+    send("uci");
+    assert!(receive(&receiver, RESPONSE_TIMEOUT).starts_with("id name Ladybug"));
+    assert!(receive(&receiver, RESPONSE_TIMEOUT).starts_with("id author"));
+    // polyglot collects the options until uciok is received
+    let mut output = receive(&receiver, RESPONSE_TIMEOUT);
+    while output.starts_with("option name") {
+        output = receive(&receiver, RESPONSE_TIMEOUT);
+    }
+    assert_eq!("uciok", output);
+    send("isready");
+    assert_eq!("readyok", receive(&receiver, RESPONSE_TIMEOUT));
+
+    // xboard: "new", "force" - polyglot resets the game
+    send("ucinewgame");
+    send("isready");
+    assert_eq!("readyok", receive(&receiver, RESPONSE_TIMEOUT));
+
+    // xboard: "usermove e2e4", "time 2000", "otim 2000" - the engine plays black
+    send("position startpos moves e2e4");
+    send("go wtime 20000 btime 20000 winc 0 binc 0");
+    let best_move = receive(&receiver, RESPONSE_TIMEOUT);
+    let best_move = best_move.strip_prefix("bestmove ").expect("expected a best move");
+    let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+    let ply = Ply::from_string(best_move, board.position).expect("illegal best move");
+
+    // xboard: "usermove d2d4" - polyglot sends the whole game again
+    let moves = format!("e2e4 {ply} d2d4");
+    send(format!("position startpos moves {moves}").as_str());
+    send("go wtime 19000 btime 19500 winc 0 binc 0");
+    assert!(receive(&receiver, RESPONSE_TIMEOUT).starts_with("bestmove "));
+
+    // xboard: "result 1-0 {White resigns}", "new" - the next game starts from scratch
+    send("ucinewgame");
+    send("isready");
+    assert_eq!("readyok", receive(&receiver, RESPONSE_TIMEOUT));
+    send("display");
+    assert_eq!("8/8/8/8/8/8/8/8 w - - 0 1", receive(&receiver, RESPONSE_TIMEOUT));
+
+    // xboard: "quit"
+    send("quit");
+    assert_eq!("quit", receive(&receiver, RESPONSE_TIMEOUT));
+}
+
+#[test]
+fn test_polyglot_force_and_go() {
+    let (sender, receiver) = common::setup();
+    let send = |command: &str| {
+        let _ = sender.send(Message::ConsoleMessage(command.to_string()));
+    };
+
+    // xboard: "force", "usermove e2e4", "usermove e7e5", "go" - the engine has to move for white
+    send("ucinewgame");
+    send("position startpos moves e2e4 e7e5");
+    send("go wtime 20000 btime 20000");
+    let best_move = receive(&receiver, RESPONSE_TIMEOUT);
+    let best_move = best_move.strip_prefix("bestmove ").expect("expected a best move");
+    let board = Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2").unwrap();
+    assert!(Ply::from_string(best_move, board.position).is_ok(), "illegal best move {best_move}");
+}